@@ -0,0 +1,6 @@
+fn main() {
+    // plugins are loaded with dlopen and resolve the host API, e.g. register_plugin_signal, from
+    // the executable, which only works if its symbols are exported
+    println!("cargo:rustc-link-arg-bins=-rdynamic");
+    println!("cargo:rustc-link-arg-tests=-rdynamic");
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use dbus::{nonblock::SyncConnection, MethodErr, Path};
+use dbus_crossroads::Crossroads;
+use re_set_lib::audio::audio_structures::Sink;
+
+use crate::{
+    audio::volume_limit::{default_volume_step, volume_to_percent},
+    bluetooth::bluetooth_manager::{get_bluetooth_adapter, set_adapter_enabled},
+    network::network_manager::{set_wifi_enabled, set_wifi_listener_enabled},
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{get_wifi_status, AudioRequest, AudioResponse, ACTIONS},
+    DaemonData,
+};
+
+/// High level verbs for hotkey daemons.\
+/// The results are emitted as signals in order to show them within an OSD.
+pub fn setup_actions_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(ACTIONS, |c| {
+        c.signal::<(String, u32, bool), _>("VolumeChanged", ("sink", "volume", "muted"));
+        c.signal::<(bool,), _>("WifiToggled", ("enabled",));
+        c.signal::<(bool,), _>("BluetoothToggled", ("enabled",));
+        c.method_with_cr_async(
+            "VolumeUp",
+            ("step",),
+            ("volume",),
+            move |mut ctx, cross, (step,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                let connection = data.connection.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::ChangeDefaultSinkVolume(
+                        step_or_default(step) as i32,
+                    ));
+                    let response = receiver.recv();
+                    ctx.reply(volume_response(&connection, response))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "VolumeDown",
+            ("step",),
+            ("volume",),
+            move |mut ctx, cross, (step,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                let connection = data.connection.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::ChangeDefaultSinkVolume(
+                        -(step_or_default(step) as i32),
+                    ));
+                    let response = receiver.recv();
+                    ctx.reply(volume_response(&connection, response))
+                }
+            },
+        );
+        c.method_with_cr_async("ToggleMute", (), ("volume",), move |mut ctx, cross, ()| {
+            let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+            let sender = data.audio_sender.clone();
+            let receiver = data.audio_receiver.clone();
+            let connection = data.connection.clone();
+            async move {
+                let _ = sender.send(AudioRequest::ToggleDefaultSinkMute);
+                let response = receiver.recv();
+                ctx.reply(volume_response(&connection, response))
+            }
+        });
+        // needs blocking
+        c.method(
+            "ToggleWifi",
+            (),
+            ("enabled",),
+            move |_, data: &mut DaemonData, ()| {
+                let enabled = !get_wifi_status();
+                set_wifi_listener_enabled(enabled, data);
+                if !set_wifi_enabled(enabled, data) {
+                    return Err(status_error(StatusCode::Failed, "Could not toggle WiFi"));
+                }
+                emit_signal(&data.connection, toggled_signal("WifiToggled", enabled));
+                Ok((enabled,))
+            },
+        );
+        c.method(
+            "ToggleBluetooth",
+            (),
+            ("enabled",),
+            move |_, data: &mut DaemonData, ()| {
+                let path = data.b_interface.current_adapter.clone();
+                if &*path == "/" {
+                    return Err(status_error(
+                        StatusCode::AdapterNotFound,
+                        "No Bluetooth adapter available",
+                    ));
+                }
+                let enabled = !get_bluetooth_adapter(&path).powered;
+                if !set_adapter_enabled(path, enabled) {
+                    return Err(status_error(
+                        StatusCode::Failed,
+                        "Could not toggle Bluetooth",
+                    ));
+                }
+                emit_signal(
+                    &data.connection,
+                    toggled_signal("BluetoothToggled", enabled),
+                );
+                Ok((enabled,))
+            },
+        );
+    });
+    token
+}
+
+/// Converts the updated default sink into the volume in percent and emits VolumeChanged.
+fn volume_response(
+    connection: &Arc<SyncConnection>,
+    response: Result<AudioResponse, crossbeam::channel::RecvError>,
+) -> Result<(u32,), MethodErr> {
+    let sink: Sink = match response {
+        Ok(AudioResponse::DefaultSink(sink)) => sink,
+        _ => {
+            return Err(status_error(
+                StatusCode::PulseUnavailable,
+                "Could not get default sink",
+            ))
+        }
+    };
+    let volume = sink.volume.iter().max().copied().unwrap_or(0);
+    let percent = volume_to_percent(volume);
+    let msg = dbus::Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &ACTIONS.into(),
+        &"VolumeChanged".into(),
+    )
+    .append3(sink.alias, percent, sink.muted);
+    emit_signal(connection, msg);
+    Ok((percent,))
+}
+
+/// A step of 0 uses the configured default step.
+fn step_or_default(step: u32) -> u32 {
+    if step == 0 {
+        default_volume_step()
+    } else {
+        step
+    }
+}
+
+fn toggled_signal(name: &'static str, enabled: bool) -> dbus::Message {
+    dbus::Message::signal(&Path::from(DBUS_PATH!()), &ACTIONS.into(), &name.into()).append1(enabled)
+}
@@ -16,6 +16,11 @@
 /// d: f64\
 /// o: `Path<'static>` this is the object path\
 /// a: `Vec<T>` an array of something
+///
+/// ## Authorization
+/// Methods marked with "Requires authorization" may only be called by the user running the daemon
+/// and the uids listed in `authorized_uids` within the `[Daemon]` section of the config.\
+/// Other callers receive the org.Xetibo.ReSet.Error.AuthorizationDenied error.
 #[allow(non_snake_case)]
 pub mod API {
     use dbus::{arg::PropMap, Path};
@@ -26,27 +31,227 @@ pub mod API {
     };
     use std::collections::HashMap;
 
+    use crate::inhibitor::InhibitorInfo;
+    use crate::media::MediaPlayer;
+    use crate::modem::modem_manager::Modem;
+    use crate::network::dns::DnsConfiguration;
+    use crate::network::network_manager::AccessPointGroup;
+    use crate::network::priority_learning::LearnedLocation;
+    use crate::plugin::signals::PluginInfo;
+    use crate::proxy::ProxySettings;
+
     /// # Base API
-    /// Simple API for connectivety checks and functionality check.
+    /// Simple API for connectivety checks and functionality check.\
+    /// Methods of every interface which fail unexpectedly return the
+    /// org.Xetibo.ReSet.Error.HandlerPanicked error, the daemon keeps serving other calls.
+    ///
+    /// ## Status Codes
+    /// User visible failures carry a stable status code, which GUIs should translate instead of
+    /// showing the message of the error, the message is only a detail.\
+    /// Errors are named org.Xetibo.ReSet.Error.<code>, e.g. org.Xetibo.ReSet.Error.WrongPassword,
+    /// except for InvalidArgument and Failed, which keep the dbus names
+    /// org.freedesktop.DBus.Error.InvalidArgs and org.freedesktop.DBus.Error.Failed.\
+    /// Signals reporting a result contain the code itself, Ok on success.\
+    /// Codes: Ok, Failed, InvalidArgument, AuthorizationDenied, RfkillBlocked, HandlerPanicked,
+    /// ServiceUnavailable, PulseUnavailable, DeviceNotFound, AdapterNotFound, WrongDeviceType,
+    /// NotConnected, ConnectionNotFound, ApNotFound, WrongPassword, SecretsUnavailable,
+    /// ConnectionFailed, PairingFailed, Timeout, Cancelled, ServerUnreachable, Unsupported and
+    /// Busy. Codes are never renamed or removed, new codes may be added.
     ///
     /// DBus interface name: org.Xetibo.ReSet.Daemon
     ///
+    /// ## Events
+    /// CapabilityStateChanged -> (String, String, String, String)\
+    /// emitted as (capability, status, code, reason) when NetworkManager, BlueZ or ModemManager drop
+    /// off the bus and once the daemon reattached to them, see GetCapabilityDetails.\
+    /// Also emitted for Audio when the audio server finished connecting after startup.\
+    /// CapabilitiesChanged -> (Vec<String>, Vec<String>)\
+    /// emitted as (added, removed) when GetCapabilities changed at runtime, e.g. after a plugin was
+    /// unloaded or failed to load.\
+    /// SystemContextChanged -> (bool, bool, bool)\
+    /// emitted as (on battery, metered, do not disturb) whenever one of them changed, see
+    /// GetSystemContext.
+    ///
     #[allow(dead_code, non_snake_case)]
     pub trait BaseAPI {
         ///
-        /// Returns all capabilities of the daemon as strings
+        /// Returns all capabilities of the daemon as strings.\
+        /// The daemon is ready once startup_quorum of the startup tasks (audio server, WiFi
+        /// devices and Bluetooth adapters) within the `[Daemon]` section of the config finished,
+        /// by default all of them. Audio is listed while the audio server is still connecting.\
+        /// Features can be disabled with wifi, bluetooth, modem, battery, audio, media, proxy,
+        /// do_not_disturb or speedtest set to false within the `[Daemon]` section, disabled
+        /// features are neither set up nor listed in the capabilities or their details.
         fn GetCapabilities() -> Vec<String>;
         ///
+        /// Returns details for every feature as (name, status, backend, reason).\
+        /// The status is either active, degraded (e.g. no wireless device) or unavailable (e.g.
+        /// NetworkManager not running). The backend contains the backing service and its version
+        /// if available, e.g. "NetworkManager 1.46.0" or "PulseAudio (on PipeWire 1.0.5) 15.0.0".\
+        /// The reason contains a setup hint for degraded and unavailable features.\
+        /// Features whose service stopped while the daemon is running stay degraded until the
+        /// daemon reattached to the restarted service.
+        fn GetCapabilityDetails() -> Vec<(String, String, String, String)>;
+        ///
+        /// Returns the state of the system as (on battery, metered, do not disturb), so clients
+        /// and plugins can reduce their work, e.g. poll less often on a metered LTE connection.\
+        /// On battery is read from UPower and metered from NetworkManager, including connections
+        /// NetworkManager guesses to be metered. Do not disturb is only tracked while a supported
+        /// notification daemon runs.\
+        /// Plugins read the context with get_system_context and register for changes with
+        /// register_system_context_callback, see plugin::context.
+        fn GetSystemContext() -> (bool, bool, bool);
+        ///
+        /// Returns whether any radio of the kind is blocked by rfkill as (soft_blocked,
+        /// hard_blocked).\
+        /// Kind is one of "wlan", "bluetooth" or "wwan", systems without such a radio report
+        /// both as false.
+        fn GetRfkillState(kind: String) -> (bool, bool);
+        ///
+        /// Soft blocks or unblocks all radios of the kind, see GetRfkillState.\
+        /// Hard blocks can only be lifted with the hardware switch, unblocking therefore returns
+        /// false while a hard block is in place.\
+        /// Fails if the user running the daemon may not write to /dev/rfkill.\
+        /// Requires authorization.
+        fn SetRfkillBlocked(kind: String, blocked: bool) -> bool;
+        ///
+        /// Returns the configured hooks as (event, executable).\
+        /// Hooks are executables run on daemon events, configured per event within the
+        /// `[Daemon.hooks]` section of the config, e.g. `wifi_connected = ["/path/to/script"]`.\
+        /// The events are wifi_connected (RESET_DEVICE, RESET_SSID, RESET_BSSID),
+        /// bluetooth_device_connected (RESET_DEVICE, RESET_ADDRESS, RESET_NAME) and
+        /// default_sink_changed (RESET_SINK), the values are passed as environment variables
+        /// together with RESET_EVENT. Hooks are killed after 10 seconds.
+        fn ListHooks() -> Vec<(String, String)>;
+        ///
+        /// Runs the hooks of the event with sample values and RESET_TEST set to 1.\
+        /// Returns (executable, exit code) for every hook, the exit code is -1 if the hook could
+        /// not be started, timed out or was killed by a signal.\
+        /// Returns dbus invalid arguments on an unknown event.\
+        /// Requires authorization.
+        fn TestHook(event: String) -> Vec<(String, i32)>;
+        ///
+        /// Returns all loaded backend plugins as (name, capabilities, signals).\
+        /// Signals are the ones registered through the plugin signal relay as
+        /// (path, interface, name, signature).
+        fn GetPluginInfo() -> Vec<PluginInfo>;
+        ///
+        /// Returns the introspection XML of a single interface of the daemon object, e.g.
+        /// org.Xetibo.ReSet.Audio, in order to generate bindings for other languages.\
+        /// Methods and signals carrying a struct are annotated with org.Xetibo.ReSet.Struct.Out0,
+        /// which contains the struct name, as structs like Sink and Source share a signature.\
+        /// The field names of each struct are annotated on the interface as
+        /// org.Xetibo.ReSet.StructFields.<Struct>, e.g. "index,name,alias,channels,volume,muted,active".\
+        /// Plugin interfaces are registered on their own objects below /org/Xetibo/ReSet/Plugins and
+        /// can be introspected there.\
+        /// Returns dbus invalid arguments on an unknown interface.
+        fn GetInterfaceXML(interface: String) -> String;
+        ///
         /// Register the client to the daemon.\
         /// This is mainly useful for clients that want to ensure the daemon is running before
         /// starting calls.\
-        /// Later on this can be expanded for more functionality.
-        fn RegisterClient(client_name: String) -> bool;
+        /// Returns a unique token, which identifies the client in the other client methods.\
+        /// Clients are tracked with the unique bus name of the calling connection, once the
+        /// connection disconnects, e.g. after a crash, the idle inhibitors, signal subscriptions
+        /// and stream peak monitors of the client are released. The registration itself is kept
+        /// for 300 seconds, allowing the restarted client to continue with ResumeClient.\
+        /// Signals negotiates per-client delivery with the keys intervals (a{su}, see
+        /// RegisterThrottledClient), delta (b, see RegisterDeltaClient) and strength_threshold
+        /// (y, see RegisterStrengthThresholdClient), an empty map keeps the broadcast.\
+        /// With any of these, the client receives every signal addressed to its unique bus name
+        /// and has to match on its own name as destination, the broadcast is not meant for it.\
+        /// Returns dbus invalid arguments on an unknown key or value.
+        fn RegisterClient(client_name: String, signals: PropMap) -> String;
+        ///
+        /// Moves the registration of the token to the calling connection and restores the signal
+        /// throttling, delta signals and strength threshold requested by the client.\
+        /// Returns false on an unknown or expired token, or if the registration is still in use by
+        /// another connection.
+        fn ResumeClient(token: String) -> bool;
+        ///
+        /// Requests throttled signals for the client of the token, like intervals of
+        /// RegisterClient.\
+        /// Intervals map a signal class (Audio, Network or Bluetooth) to the minimum amount of
+        /// milliseconds between two changed signals of the same object, e.g. SinkChanged for one
+        /// sink. Changes within the interval are coalesced and only the latest state is sent.\
+        /// Added and removed signals are never throttled.\
+        /// Changes the preferences negotiated with RegisterClient, the client has to match on its
+        /// own name as destination from then on.\
+        /// Returns dbus invalid arguments on an unknown signal class or token.
+        fn RegisterThrottledClient(token: String, intervals: HashMap<String, u32>) -> bool;
+        ///
+        /// Requests delta signals for audio objects for the client of the token.\
+        /// Once the client received the full state of an object, SinkChanged, SourceChanged,
+        /// InputStreamChanged and OutputStreamChanged are replaced by SinkChangedDelta etc. with
+        /// (index, changed fields mask, values). Bit n of the mask refers to the nth field in the
+        /// org.Xetibo.ReSet.StructFields annotation of the struct, the values contain the new
+        /// value of each set bit in ascending order, e.g. mask 0b10000 with the new volume of a
+        /// sink. Changes without any differing field are not sent.\
+        /// Can be combined with RegisterThrottledClient, deltas are relative to the last state
+        /// delivered to the client.\
+        /// Changes the preferences negotiated with RegisterClient, the client has to match on its
+        /// own name as destination from then on.\
+        /// Returns dbus invalid arguments on an unknown token.
+        fn RegisterDeltaClient(token: String) -> bool;
         ///
-        /// Deletes the entry for this client from the daemon.
-        fn UnregisterClient(client_name: String) -> bool;
+        /// Sets a strength threshold for AccessPointChanged for the client of the token,
+        /// replacing access_point_strength_threshold of the config.\
+        /// The client receives a change once the strength differs by at least the threshold in
+        /// percent from the last strength delivered to it, changes of the SSID or the stored state
+        /// are always delivered. A threshold of 0 delivers every change.\
+        /// Changes the preferences negotiated with RegisterClient, the client has to match on its
+        /// own name as destination from then on.\
+        /// Returns dbus invalid arguments on a threshold above 100 or an unknown token.
+        fn RegisterStrengthThresholdClient(token: String, threshold: u8) -> bool;
         ///
-        /// Shuts down the daemon.
+        /// Deletes the entry for the client of the token from the daemon.\
+        /// This also removes signal throttling and idle inhibitors of the client.\
+        /// Returns false on an unknown token.
+        fn UnregisterClient(token: String) -> bool;
+        ///
+        /// Returns the registered clients as (client name, unique bus name, registration time in
+        /// seconds since the unix epoch), ordered by their registration.\
+        /// Intended for debugging, the unique bus name is empty for direct connections.
+        fn ListClients() -> Vec<(String, String, u64)>;
+        ///
+        /// Returns the listener threads as (name, running, restarts, last error), the names are
+        /// audio, network and bluetooth.\
+        /// Listeners that exit with an error or panic are restarted with backoff, a worker waiting
+        /// for its restart is still reported as running.
+        fn GetWorkerMetrics() -> Vec<(String, bool, u32, String)>;
+        ///
+        /// Re-probes NetworkManager, BlueZ and the audio server, restarts active listeners and emits
+        /// the added signals for all access points, Bluetooth devices, sinks, sources and streams.\
+        /// This is done automatically after resuming from sleep, clients can use it to rebuild
+        /// stale lists.
+        fn ResyncAll();
+        ///
+        /// Shuts the backend plugin with the given name down and removes its objects below
+        /// /org/Xetibo/ReSet/Plugins, its capabilities and its signals.\
+        /// Plugins register their objects with register_plugin_object, objects of registered
+        /// signals are removed as well.\
+        /// Requires authorization.\
+        /// The capabilities of the plugin are reported as removed with CapabilitiesChanged.\
+        /// Returns false if no plugin with this name is loaded.
+        fn UnloadPlugin(name: String) -> bool;
+        ///
+        /// Returns the `[Plugin.<name>]` section of the config file, empty if the section does not
+        /// exist. Nested tables are returned as nested maps.
+        fn GetPluginConfig(name: String) -> PropMap;
+        ///
+        /// Replaces the `[Plugin.<name>]` section of the config file and passes it to the
+        /// backend_config function of the plugin, see plugin::config.\
+        /// Supported are strings, booleans, integers, doubles, arrays and nested maps with string
+        /// keys, other values return dbus invalid arguments.\
+        /// The rest of the config file is left untouched, including comments.\
+        /// Requires authorization.\
+        /// Returns false if the config file could not be written, the plugin still uses the new
+        /// section until the daemon restarts.
+        fn SetPluginConfig(name: String, config: PropMap) -> bool;
+        ///
+        /// Shuts down the daemon.\
+        /// Loaded plugins are unloaded before the daemon exits.\
+        /// Requires authorization.
         fn Shutdown();
     }
 
@@ -70,22 +275,189 @@ pub mod API {
     /// Removed events are done with paths since the actual data behind the specific object is
     /// already removed.
     ///
-    /// AccessPointChanged -> AccessPoint\
-    /// AccessPointAdded -> AccessPoint\
-    /// AccessPointRemoved -> Path<'static>\
+    /// Events are emitted for all WiFi devices, access point events contain the path of the owning
+    /// device as second argument.
+    ///
+    /// AccessPointChanged -> (AccessPoint, Path<'static>)\
+    /// only emitted once the strength changed by at least access_point_strength_threshold of the
+    /// config, 5 by default, or the SSID or stored state changed. See
+    /// RegisterStrengthThresholdClient for a threshold per client.\
+    /// AccessPointAdded -> (AccessPoint, Path<'static>)\
+    /// AccessPointRemoved -> (Path<'static>, Path<'static>)\
     /// WifiDeviceChanged -> WifiDevice\
     /// called after disabling wifi in order to provide functionality.\
     /// ResetWifiDevices -> Vec<WifiDevices>\
+    /// WifiLinkInfoChanged -> (Path<'static>, (u32, u32, u8, u8))\
+    /// emitted when the bitrate of a connected WiFi device changed, see GetWifiLinkInfo.\
+    /// AutoconnectSuspended -> Path<'static>\
+    /// emitted when autoconnect of a stored connection was suspended by its retry policy, see
+    /// SetConnectionRetryPolicy.\
+    /// RoamingProgress -> (Path<'static>, String, String)\
+    /// emitted as (device, stage, bssid) while ReassociateToStrongestBSSID runs, the stage is one
+    /// of scanning, reassociating, done, already_strongest or failed. The bssid is only set for
+    /// reassociating.\
+    /// CaptivePortalDetected -> String\
+    /// emitted when NetworkManager reports a captive portal, contains the URL of the login page,
+    /// see MarkPortalResolved.\
+    /// HotspotClientConnected -> (Path<'static>, String, String, String, u64)\
+    /// emitted as (device, mac address, ip address, hostname, connected since) when a client
+    /// associated with a hotspot started with EnableConnectionSharing, see ListHotspotClients.\
+    /// HotspotClientDisconnected -> (Path<'static>, String)\
+    /// emitted as (device, mac address) when a client left the hotspot or the hotspot stopped.\
+    /// OperationQueueChanged -> (Path<'static>, Vec<(String, String)>)\
+    /// emitted as (device, operations) when a connect or disconnect was queued for a device or
+    /// finished, see GetOperationQueue.\
+    /// WifiConnectResult -> (Path<'static>, bool, String, String)\
+    /// emitted as (access point, success, code, detail) after ConnectToKnownAccessPoint,
+    /// ConnectToNewAccessPoint and AddConnectionFromQRPayload, e.g. with the code WrongPassword.
+    /// The access point is "/" if a QR payload could not be connected.\
+    /// ConnectionStateChanged -> (Path<'static>, String, String)\
+    /// emitted as (device, state, reason) while one of these connection attempts is activated.
+    /// The state is one of unknown, activating, activated, deactivating or deactivated, the reason
+    /// is the NetworkManager reason in kebab case, e.g. no-secrets or connect-timeout.\
+    /// SecretsRequested -> (u32, Path<'static>, String, String, Vec<String>, u32)\
+    /// emitted when NetworkManager requests agent-owned secrets as (id, connection path,
+    /// connection name, setting, hints, flags), answer it with ProvideSecrets.\
+    /// SecretsRequestCanceled -> u32\
+    /// emitted when NetworkManager canceled a request, e.g. as the connection attempt was aborted.\
+    /// RadioStateChanged -> (bool, bool, bool)\
+    /// emitted when a radio is toggled by software or by an rfkill switch, see GetRadioStates.
     ///
     pub trait WirelessAPI {
         ///
-        /// Returns all access points for the current wireless network device.
+        /// Returns all access points for the current wireless network device.\
+        /// Access points are deduplicated by SSID, see SetPreferStrongestBSSID for which BSSID
+        /// represents the SSID.
         fn ListAccessPoints() -> Vec<AccessPoint>;
         ///
+        /// Returns one entry per SSID as (ssid, stored, associated_connection, bssids).\
+        /// Each BSSID is (path, hardware address, frequency in MHz, strength), sorted from strongest
+        /// to weakest. The path can be used as dbus_path of an AccessPoint in order to connect to a
+        /// specific BSSID.
+        fn ListAccessPointGroups() -> Vec<AccessPointGroup>;
+        ///
+        /// Returns the radio of every access point of the current wireless network device as
+        /// (path, frequency in MHz, max bitrate in kbit/s, band).\
+        /// The path matches the dbus_path of an AccessPoint, the band is one of 2.4GHz, 5GHz or
+        /// 6GHz and empty for unknown frequencies.
+        fn ListAccessPointRadios() -> Vec<(Path<'static>, u32, u32, String)>;
+        ///
+        /// Returns the access points seen by the daemon as (ssid, security, last_seen,
+        /// strength_history), most recently seen first.\
+        /// The cache is persisted and can be shown on startup before a scan completed. The
+        /// security is one of none, owe, wep, wpa, wpa3 or enterprise, last_seen is in seconds
+        /// since the unix epoch and the strength history contains up to 10 samples, oldest first.\
+        /// Entries are removed after not being seen for access_point_cache_ttl seconds within the
+        /// `[Daemon]` section of the config, the default is two weeks.
+        fn GetCachedAccessPoints() -> Vec<(Vec<u8>, String, u64, Vec<u8>)>;
+        ///
+        /// Sets whether ListAccessPoints represents an SSID with its strongest BSSID instead of the
+        /// first one found, the connected access point is always kept.\
+        /// Defaults to prefer_strongest_bssid within the `[Daemon]` section of the config or false.
+        fn SetPreferStrongestBSSID(enabled: bool);
+        ///
+        /// Returns whether ListAccessPoints prefers the strongest BSSID.
+        fn GetPreferStrongestBSSID() -> bool;
+        ///
         /// A check that returns the current status of Wifi.\
         /// Returns a bool as a result of the operation.
         fn GetWifiStatus() -> bool;
         ///
+        /// Returns the radio states of NetworkManager as (wireless_enabled,
+        /// wireless_hardware_enabled, wwan_enabled).\
+        /// wireless_hardware_enabled is false when WiFi is blocked by a hardware rfkill switch,
+        /// SetWifiEnabled has no effect in this case.
+        fn GetRadioStates() -> (bool, bool, bool);
+        ///
+        /// Returns the link of the WiFi device as (bitrate in kbit/s, frequency in MHz, WiFi
+        /// generation, strength in percent).\
+        /// The generation is derived from the band and the maximum bitrate of the access point, as
+        /// NetworkManager does not expose the 802.11 mode. WiFi 6 is only detected on the 6 GHz
+        /// band, 0 refers to links older than WiFi 4.\
+        /// Fails if the device is not a WiFi device or not connected.
+        fn GetWifiLinkInfo(device: Path<'static>) -> (u32, u32, u8, u8);
+        ///
+        /// Returns the address families of the active connection of the device as (family, IPv4
+        /// addresses, global IPv6 addresses).\
+        /// The family is one of dual-stack, ipv4-only, ipv6-only or none. A family only counts if
+        /// the device has an address and a default route for it, link-local and unique local IPv6
+        /// addresses are not global and therefore ignored.\
+        /// Devices without an active connection report none, fails if the device does not exist.
+        fn GetConnectionAddressFamilies(
+            device: Path<'static>,
+        ) -> (String, Vec<String>, Vec<String>);
+        ///
+        /// Scans with the WiFi device and returns the visible access points per channel as (band,
+        /// channel, access points, strongest signal in percent), sorted by band and channel.\
+        /// The band is one of 2.4GHz, 5GHz or 6GHz. The existing results are used if
+        /// NetworkManager rejects the scan, e.g. shortly after another scan.\
+        /// With sharing_channel = "auto" within the `[Daemon]` section of the config, hotspots of
+        /// EnableConnectionSharing use the least crowded channel of the band set with
+        /// sharing_band, either bg (default) or a.\
+        /// Fails if the device is not a WiFi device.
+        fn GetChannelUtilization(device: Path<'static>) -> Vec<(String, u32, u32, u8)>;
+        ///
+        /// Returns the wake-on-lan mode of the connection of an ethernet device, the active
+        /// connection is preferred over other connections of the device.\
+        /// The mode is disabled, default, ignore or a comma separated list of phy, unicast,
+        /// multicast, broadcast, arp and magic.\
+        /// default uses the global setting of NetworkManager, ignore keeps the setting of the
+        /// driver.
+        fn GetWakeOnLan(device: Path<'static>) -> String;
+        ///
+        /// Sets the wake-on-lan mode of the connection of an ethernet device, see GetWakeOnLan
+        /// for the modes.\
+        /// The mode is applied once the connection is activated again.\
+        /// Returns true on success and false on error, fails for unknown modes and devices that
+        /// are not ethernet devices.\
+        /// Requires authorization.
+        fn SetWakeOnLan(device: Path<'static>, mode: String) -> bool;
+        ///
+        /// Returns the powersave mode of the connection of a WiFi device, the active connection
+        /// is preferred over other connections of the device.\
+        /// The mode is default, ignore, disable or enable. default uses the global setting of
+        /// NetworkManager, ignore keeps the setting of the driver.
+        fn GetWifiPowersave(device: Path<'static>) -> String;
+        ///
+        /// Sets the powersave mode of the connection of a WiFi device, see GetWifiPowersave for
+        /// the modes.\
+        /// Active connections are reapplied without reconnecting, drivers which can't change the
+        /// mode at runtime use it once the connection is activated again.\
+        /// Returns true on success and false on error, fails for unknown modes and devices that
+        /// are not WiFi devices.\
+        /// Requires authorization.
+        fn SetWifiPowersave(device: Path<'static>, mode: String) -> bool;
+        ///
+        /// Returns the MAC settings of a stored WiFi connection as (cloned_mac_address,
+        /// scan_randomization).\
+        /// The cloned mac address is one of default, permanent, preserve, random or stable, or an
+        /// explicit address. Scan randomization is the mac-address-randomization of the
+        /// connection, true unless it is set to never.\
+        /// Fails for connections that are not WiFi connections.
+        fn GetWifiMacSettings(connection: Path<'static>) -> (String, bool);
+        ///
+        /// Sets the cloned mac address of a stored WiFi connection, see GetWifiMacSettings for
+        /// the modes. random uses a new address with every activation, stable keeps a generated
+        /// address per connection and default uses the global setting of NetworkManager.\
+        /// The address changes with the next activation of the connection.\
+        /// Returns true on success and false on error, fails for unknown modes.\
+        /// Requires authorization.
+        fn SetWifiMacRandomization(connection: Path<'static>, mode: String) -> bool;
+        ///
+        /// Enables or disables random addresses for a stored WiFi connection by setting its
+        /// mac-address-randomization to always or never, it applies with the next activation.\
+        /// The global configuration of NetworkManager is not changed.\
+        /// Requires authorization.\
+        /// Returns true on success and false on error, fails for connections that are not WiFi
+        /// connections.
+        fn SetWifiScanMacRandomization(connection: Path<'static>, enabled: bool) -> bool;
+        ///
+        /// Wakes another machine by sending a magic packet with its mac address to UDP port 9 of
+        /// the broadcast address, e.g. 192.168.1.255.\
+        /// An empty broadcast address uses 255.255.255.255.\
+        /// Returns false if the packet could not be sent, fails for invalid addresses.
+        fn SendMagicPacket(mac: String, broadcast: String) -> bool;
+        ///
         /// Enables or disables Wifi for the entire system.
         fn SetWifiEnabled(enabled: bool) -> bool;
         ///
@@ -101,18 +473,31 @@ pub mod API {
         ///
         /// Connects to an access point that has a known connection inside the NetworkManager.\
         /// Note, for a new access point, use the ConnectToNewAccessPoint function.\
-        /// Returns true on success and false on error.
+        /// Returns true on success and false on error, the reason is emitted with
+        /// WifiConnectResult.\
+        /// Connects and disconnects of a device run one after another, a connect while another
+        /// one is queued for the device fails with Busy.
         fn ConnectToKnownAccessPoint(access_point: AccessPoint) -> bool;
         ///
         /// Connects to a new access point with a password.\
-        /// Returns true on success and false on error.
+        /// Returns true on success and false on error, the reason is emitted with
+        /// WifiConnectResult.
         fn ConnectToNewKnownAccessPoint(access_point: AccessPoint, password: String) -> bool;
         ///
         /// Disconnects from the currently connected access point.\
         /// Calling this without a connected access point will return false.\
-        /// Returns true on success and false on error.
+        /// Returns true on success and false on error.\
+        /// Waits for a queued connect of the device, fails with Busy if another disconnect is
+        /// already queued.
         fn DisconnectFromCurrentAccessPoint() -> bool;
         ///
+        /// Returns the operations queued for the device as (operation, target), e.g. ("connect",
+        /// path of the access point). The first operation is running, the index of the others is
+        /// their position in the queue.\
+        /// Operations are connect, disconnect and scan, scans of the daemon are skipped while
+        /// clients operate on the device.
+        fn GetOperationQueue(device: Path<'static>) -> Vec<(String, String)>;
+        ///
         /// Returns the stored connections for the currently selected wireless device from NetworkManager.\
         /// Returns dbus invalid arguments on error.
         fn ListStoredConnections() -> Vec<(Path<'static>, Vec<u8>)>;
@@ -120,21 +505,179 @@ pub mod API {
         /// Returns the settings of a connection.\
         /// Can be used in combination with the Connection struct in order to provide easy serialization
         /// and deserialization from and to this hashmap.\
+        /// Secrets are never included, as every user on the session bus may call this, use
+        /// GetConnectionSecrets instead.\
         /// Returns dbus invalid arguments on error.
         fn GetConnectionSettings(path: Path<'static>) -> HashMap<String, PropMap>;
         ///
+        /// Returns the secrets of a single setting of a connection, e.g.
+        /// 802-11-wireless-security.\
+        /// Returns dbus invalid arguments on error.\
+        /// Requires authorization.
+        fn GetConnectionSecrets(path: Path<'static>, setting: String) -> HashMap<String, PropMap>;
+        ///
+        /// Answers a SecretsRequested signal with the secrets of the requested setting, e.g.
+        /// {"802-11-wireless-security": {"psk": "password"}}. Empty secrets cancel the request.\
+        /// The daemon is only registered as secret agent of NetworkManager if secret_agent is set
+        /// to true within the `[Daemon]` section of the config. Requests are only forwarded while
+        /// a client is registered with RegisterClient, unanswered requests fail after 60 seconds.
+        /// Secrets are never stored by the daemon.\
+        /// Returns false if the request does not exist anymore.\
+        /// Requires authorization.
+        fn ProvideSecrets(id: u32, secrets: HashMap<String, PropMap>) -> bool;
+        ///
         /// Sets the settings of a connection.\
         /// Can be used in combination with the Connection struct in order to provide easy serialization
         /// and deserialization from and to this hashmap.\
-        /// Returns true on success and false on error.
+        /// Returns true on success and false on error.\
+        /// Requires authorization.
         fn SetConnectionSettings(path: Path<'static>, settings: HashMap<String, PropMap>) -> bool;
         ///
+        /// Returns the connection in the keyfile format of NetworkManager, e.g. for backups or to
+        /// share a connection with another machine.\
+        /// Values which can't be expressed in a keyfile, like the data of VPN plugins, are
+        /// omitted.\
+        /// Returns dbus invalid arguments on an unknown connection.\
+        /// With include_secrets the WiFi secrets are included, which requires authorization.
+        fn ExportConnection(path: Path<'static>, include_secrets: bool) -> String;
+        ///
+        /// Creates a new connection from a keyfile created with ExportConnection or by
+        /// NetworkManager and returns its dbus path.\
+        /// The connection receives a new uuid, an existing connection is never replaced.\
+        /// Returns dbus invalid arguments if the keyfile can't be parsed and a failed error if
+        /// NetworkManager rejects the connection.\
+        /// Requires authorization.
+        fn ImportConnection(keyfile: String) -> Path<'static>;
+        ///
+        /// Replaces the DNS servers and search domains of the connection given the dbus path,
+        /// all other settings including secrets are kept.\
+        /// Servers are IPv4 or IPv6 addresses and are split into the ipv4 and ipv6 sections, the
+        /// search domains and ignore_auto_dns are applied to both. With ignore_auto_dns the servers
+        /// provided by DHCP are no longer used. Empty lists remove the overrides.\
+        /// Returns true on success and false on error.\
+        /// Returns dbus invalid arguments on an invalid address or an unknown connection.\
+        /// Requires authorization.
+        fn SetConnectionDNS(
+            path: Path<'static>,
+            servers: Vec<String>,
+            search_domains: Vec<String>,
+            ignore_auto_dns: bool,
+        ) -> bool;
+        ///
+        /// Returns the DNS state of NetworkManager as (mode, rc_manager, servers, configurations),
+        /// e.g. in order to find out why a domain is resolved by the wrong server.\
+        /// The mode is the DNS backend, one of default, dnsmasq, systemd-resolved or none, and the
+        /// rc_manager is how /etc/resolv.conf is written, e.g. symlink or file.\
+        /// Servers are all servers in the order NetworkManager uses them. Configurations are
+        /// (connection name, interface, servers, domains, priority, vpn) per connection, lower
+        /// priorities are preferred. The global configuration of NetworkManager has no connection
+        /// name and interface.\
+        /// Returns the Unsupported error if NetworkManager does not provide its DNS state.
+        fn GetDNSState() -> (String, String, Vec<String>, Vec<DnsConfiguration>);
+        ///
+        /// Sets the autoconnect priority of the stored connection given the dbus path, connections
+        /// with a higher priority are preferred when NetworkManager autoconnects.\
+        /// The priority ranges from -999 to 999, the default is 0.\
+        /// Returns true on success and false on error.\
+        /// Returns dbus invalid arguments on a priority out of range or an unknown connection.\
+        /// Requires authorization.
+        fn SetConnectionAutoconnectPriority(path: Path<'static>, priority: i32) -> bool;
+        ///
+        /// Returns the stored WiFi connections as (path, ssid, priority, autoconnect) in the
+        /// order NetworkManager tries them when autoconnecting.\
+        /// Connections are sorted by priority, equal priorities by the time they were last used.
+        /// Connections with autoconnect disabled come last.\
+        /// Reordering can be done by assigning descending priorities with
+        /// SetConnectionAutoconnectPriority.
+        fn GetAutoconnectOrder() -> Vec<(Path<'static>, Vec<u8>, i32, bool)>;
+        ///
+        /// Returns the locations learned from manual connections as (visible SSIDs, connections),
+        /// connections are (SSID, amount of manual connections).\
+        /// Learning is disabled by default and enabled with `learn_autoconnect_priority = true`
+        /// within the `[Daemon]` section of the config. Every connection made with
+        /// ConnectToKnownAccessPoint or ConnectToNewAccessPoint is counted for the location, which
+        /// is recognized by the SSIDs visible at the time. Whenever a scan shows a known location,
+        /// its most used connections get the highest autoconnect priorities, up to 50.
+        fn GetLearnedAutoconnectPriorities() -> Vec<LearnedLocation>;
+        ///
+        /// Forgets all learned locations, priorities that were already applied are kept and can
+        /// be changed with SetConnectionAutoconnectPriority.\
+        /// Requires authorization.
+        fn ClearLearnedAutoconnectPriorities() -> bool;
+        ///
         /// Deletes the stored connection given the dbus path.\
-        /// Returns true on success and false on error.
+        /// Returns true on success and false on error.\
+        /// Requires authorization.
         fn DeleteConnection(path: Path<'static>) -> bool;
         ///
+        /// Sets the retry policy of the stored connection given the dbus path.\
+        /// After max_retries failed activations, autoconnect of the connection is disabled for
+        /// backoff seconds and AutoconnectSuspended is emitted. The change is not saved to disk
+        /// by NetworkManager, a restart therefore re-enables autoconnect.\
+        /// A max_retries of 0 removes the policy, policies are persisted by the daemon.\
+        /// Returns dbus invalid arguments when the backoff is 0.\
+        /// Requires authorization.
+        fn SetConnectionRetryPolicy(path: Path<'static>, max_retries: u32, backoff: u32) -> bool;
+        ///
+        /// Blacklists a BSSID (e.g. AA:BB:CC:DD:EE:FF) for the duration in seconds, useful when a
+        /// single node of a mesh network is broken.\
+        /// WiFi devices connected or roaming to the BSSID are moved to the strongest other BSSID
+        /// of the same SSID, or disconnected if there is none.\
+        /// A duration of 0 removes the BSSID from the blacklist, the blacklist is persisted by the
+        /// daemon.\
+        /// Returns dbus invalid arguments when the BSSID is malformed.\
+        /// Requires authorization.
+        fn BlacklistAccessPoint(bssid: String, duration: u32) -> bool;
+        ///
+        /// Returns all blacklisted BSSIDs as (bssid, expiry), the expiry is provided in seconds
+        /// since the unix epoch.\
+        /// Expired BSSIDs are removed automatically.
+        fn ListBlacklistedBSSIDs() -> Vec<(String, u64)>;
+        ///
+        /// Checks credentials before connecting, following the rules NetworkManager applies to
+        /// connection settings, so GUIs can reject them without a failed activation.\
+        /// The security flags are the NM80211ApSecurityFlags of the access point (WpaFlags |
+        /// RsnFlags), WEP networks are identified by the WEP cipher flags 0x1, 0x2, 0x10 and 0x20.\
+        /// PSKs need 8 to 63 printable ASCII characters or 64 hex digits, WEP keys 5 or 13 ASCII
+        /// characters or 10 or 26 hex digits. For enterprise networks the eap map contains the
+        /// 802-1x settings, e.g. eap, identity, phase2-auth, client-cert and private-key, and is
+        /// ignored otherwise.\
+        /// Returns (valid, reason), the reason is empty for valid credentials.
+        fn ValidateWifiCredentials(
+            security_flags: u32,
+            password: String,
+            eap: PropMap,
+        ) -> (bool, String);
+        ///
+        /// Moves a connected WiFi device to the strongest BSSID of its current SSID, useful when
+        /// a device sticks to a far away access point.\
+        /// The device scans, the connection is locked to the strongest BSSID in memory and
+        /// reactivated, the lock is removed again afterwards. Blacklisted BSSIDs and BSSIDs which
+        /// are not at least 10 percent stronger than the current one are skipped.\
+        /// Returns true once the roaming started, progress is emitted as RoamingProgress.\
+        /// Fails if the device is not connected, is already reassociating or its connection is
+        /// locked to a BSSID by the user.\
+        /// Requires authorization.
+        fn ReassociateToStrongestBSSID(device: Path<'static>) -> bool;
+        ///
+        /// Returns the WiFi QR code payload (WIFI:S:<ssid>;T:<type>;P:<password>;;) of the stored
+        /// connection belonging to the access point given the dbus path.\
+        /// The password is fetched from the secrets of the connection, rendering the code is up to
+        /// the client.\
+        /// Returns dbus invalid arguments when the access point has no stored connection and the
+        /// Unsupported error for enterprise, OWE and dynamic WEP networks.\
+        /// Requires authorization.
+        fn GetAccessPointQRPayload(path: Path<'static>) -> String;
+        ///
+        /// Adds and activates a connection on the current wireless device from a scanned WiFi QR
+        /// code payload.\
+        /// Returns true on success and false on error.\
+        /// Returns dbus invalid arguments if the payload could not be parsed.
+        fn AddConnectionFromQRPayload(payload: String) -> bool;
+        ///
         /// Starts the wireless network listener which provides dbus events on access points and the
-        /// wireless device.\
+        /// wireless devices.\
+        /// All WiFi devices are watched and scanned, not only the current one.\
         /// Repeatedly starting the network listener twice will simply return an error on consecutive
         /// tries.\
         /// Returns true on success and false on error.
@@ -143,6 +686,40 @@ pub mod API {
         /// Stops the wireless network listener.\
         /// Returns true on success and false on error.
         fn StopNetworkListener() -> bool;
+        ///
+        /// Shares the internet connection on the given dbus path of a NetworkManager device.\
+        /// NetworkManager forwards the shared traffic over the default route, the primary
+        /// connection is shared and has to be active on another device.\
+        /// An ethernet device shares the connection with a wired client, a WiFi device is turned
+        /// into a WPA2 hotspot. The SSID defaults to "ReSet Hotspot" and can be configured with
+        /// sharing_ssid within the `[Daemon]` section of the config, the generated password can
+        /// be read with GetConnectionSecrets on the shared connection.\
+        /// The shared connection is created once per device and reused afterwards.\
+        /// Returns true on success and false on error.\
+        /// Returns the WrongDeviceType error if the device can't share a connection, NotConnected
+        /// if no other device provides an internet connection and Failed if no random hotspot
+        /// password can be generated.\
+        /// Requires authorization.
+        fn EnableConnectionSharing(device: Path<'static>) -> bool;
+        ///
+        /// Stops sharing on the given device and reactivates the connection used on the device
+        /// before sharing was enabled.\
+        /// Returns false if the device is not sharing a connection.\
+        /// Requires authorization.
+        fn DisableConnectionSharing(device: Path<'static>) -> bool;
+        ///
+        /// Returns the clients of the hotspot on the device as (mac address, ip address,
+        /// hostname, connected since).\
+        /// Stations are read with iw, addresses and hostnames from the DHCP leases of the dnsmasq
+        /// instance of NetworkManager. Both stay empty until the client requested a lease,
+        /// connected since is in seconds since the unix epoch.\
+        /// Fails if the device is not a WiFi device sharing a connection or iw is not installed.
+        fn ListHotspotClients(device: Path<'static>) -> Vec<(String, String, String, u64)>;
+        ///
+        /// Requests a new connectivity check after the user logged into a captive portal.\
+        /// Returns true when full connectivity is available, CaptivePortalDetected is emitted
+        /// again for the next portal afterwards.
+        fn MarkPortalResolved() -> bool;
     }
 
     /// # Bluetooth Manager API
@@ -166,16 +743,42 @@ pub mod API {
     ///
     /// BluetoothDeviceAdded -> BluetoothDevice\
     /// BluetoothDeviceRemoved -> Path<'static>\
-    /// BluetoothDeviceChanged -> BluetoothDevice
+    /// BluetoothDeviceChanged -> BluetoothDevice\
+    /// BluetoothDeviceRSSIChanged -> (Path<'static>, i16)\
+    /// emitted as (device, RSSI in dBm) while the Bluetooth listener is active, e.g. for pickers
+    /// sorting nearby devices by proximity without parsing BluetoothDeviceChanged. Changes below
+    /// bluetooth_rssi_threshold within the `[Daemon]` section of the config are not reported,
+    /// the default is 5 dBm.\
+    /// AnonymousDeviceCountChanged -> u32\
+    /// emitted with the new count of GetAnonymousDeviceCount. Anonymous devices are not reported
+    /// with BluetoothDeviceAdded, BluetoothDeviceChanged or BluetoothDeviceRemoved, once such a
+    /// device gets a name or services it is reported with BluetoothDeviceAdded.\
+    /// BluetoothScanFinished -> Path<'static>\
+    /// emitted with the adapter once a scan was stopped, either explicitly or after the duration
+    /// of StartBluetoothScanFor.\
+    /// DiscoverableExpired -> Path<'static>\
+    /// emitted with the adapter once the duration of MakeDiscoverableFor ran out.\
+    /// ObexTransferRequested -> (u32, Path<'static>, String, u64)\
+    /// emitted when a device without accept or reject policy pushes a file over OBEX as (id,
+    /// device, file name, size), answer it with AnswerObexTransfer. The device is / if bluez does
+    /// not know the sender.\
+    /// ObexTransferCanceled -> u32\
+    /// emitted when obexd canceled a request, e.g. as the sender aborted the push.
     ///
     /// ## Agent Events
-    /// NOTE Currently unused
-    ///
     /// PincodeRequested -> ()\
     /// DisplayPinCode -> ()\
     /// PassKeyRequested -> ()\
     /// DisplayPassKey -> (u32, u16)\
-    /// PinCodeRequested -> ()
+    /// PinCodeRequested -> ()\
+    /// PairingPinDisplay -> (Path<'static>, String)
+    ///
+    /// PairingPinDisplay carries the PIN or passkey that has to be typed on the keyboard being
+    /// paired, followed by enter.\
+    /// PINs are only generated for keyboards, other devices requesting a PIN are rejected.\
+    /// The user has 60 seconds to type the PIN, afterwards the pairing is cancelled and
+    /// BluetoothConnectResult reports the failure. The PIN should be hidden once
+    /// BluetoothConnectResult is received.
     ///
     pub trait BluetoothAPI {
         ///
@@ -186,6 +789,25 @@ pub mod API {
         /// Stops searching for Bluetooth devices.
         fn StopBluetoothSearch();
         ///
+        /// Searches for Bluetooth devices for the given amount of seconds, BluetoothScanFinished
+        /// is emitted once the scan stopped.\
+        /// Calling this during a running scan replaces its remaining time.\
+        /// Returns dbus invalid arguments when the amount of seconds is 0.
+        fn StartBluetoothScanFor(seconds: u32) -> bool;
+        ///
+        /// Returns whether a scan is running and its remaining seconds.\
+        /// The remaining seconds are 0 for scans that run until they are stopped, e.g. the scan
+        /// of the Bluetooth listener.
+        fn GetScanStatus() -> (bool, u32);
+        ///
+        /// Returns the state of Bluetooth as (powered, scanning, pairing_device, pairing_stage,
+        /// connected_devices), so GUIs started later can render it without replaying events.\
+        /// Powered refers to the current adapter, connected_devices counts the devices of all
+        /// adapters. The pairing stage is one of started, pin_displayed, pin_requested,
+        /// confirmation_requested or authorization_requested, the pairing device is "/" and the
+        /// stage empty while no device is pairing.
+        fn GetBluetoothState() -> (bool, bool, Path<'static>, String, u32);
+        ///
         /// Starts the listener for Bluetooth events for a specified duration.\
         /// Repeatedly starting the network listener while already active will do nothing.
         fn StartBluetoothListener();
@@ -203,19 +825,111 @@ pub mod API {
         /// The path can be found inside the BluetoothAdapter struct.
         fn SetBluetoothAdapter(path: Path<'static>) -> bool;
         ///
-        /// Sets the discoverability of a specific Bluetooth adapter.
+        /// Powers a specific Bluetooth adapter on or off.\
+        /// The state is stored and reapplied on daemon startup and when the adapter is plugged in
+        /// again, unless restore_bluetooth_adapter_state is set to false within the `[Daemon]`
+        /// section of the config.\
+        /// Enabling an adapter blocked by rfkill returns the org.Xetibo.ReSet.Error.RfkillBlocked
+        /// error, see GetBluetoothAdapterRfkill.
+        fn SetBluetoothAdapterEnabled(path: Path<'static>, enabled: bool) -> bool;
+        ///
+        /// Returns the rfkill state of the adapter as (soft_blocked, hard_blocked).\
+        /// Soft blocks can be lifted with SetRfkillBlocked, hard blocks require the hardware
+        /// switch.\
+        /// Returns dbus invalid arguments if the adapter has no rfkill switch.
+        fn GetBluetoothAdapterRfkill(path: Path<'static>) -> (bool, bool);
+        ///
+        /// Returns the privacy of the adapter as (privacy, just_works_repairing, supported).\
+        /// The privacy is one of off, on or limited, with privacy enabled the adapter uses
+        /// resolvable random addresses for LE. JustWorksRepairing is one of never, confirm or
+        /// always and taken from /etc/bluetooth/main.conf.\
+        /// Supported is false if the privacy can not be changed at runtime, either as the kernel
+        /// does not support it for this adapter or btmgmt of bluez is not installed.
+        fn GetBluetoothAdapterPrivacy(path: Path<'static>) -> (String, String, bool);
+        ///
+        /// Sets the LE privacy of the adapter to off, on or limited through btmgmt, the adapter is
+        /// briefly powered off for the change.\
+        /// Requires CAP_NET_ADMIN, otherwise the AuthorizationDenied error is returned. The mode is
+        /// reset once bluetoothd restarts, use Privacy within main.conf for a permanent change.\
+        /// Requires authorization.
+        fn SetBluetoothAdapterPrivacy(path: Path<'static>, privacy: String) -> bool;
+        ///
+        /// Enables privacy with a newly generated identity resolving key, the adapter then
+        /// advertises with random addresses unrelated to the previous ones.\
+        /// Previously paired LE devices can no longer resolve the adapter, the BR/EDR address is
+        /// not changed. Requires the same privileges and authorization as
+        /// SetBluetoothAdapterPrivacy.
+        fn RandomizeAdapterAddress(path: Path<'static>) -> bool;
+        ///
+        /// Not supported, always returns the Unsupported error.\
+        /// JustWorksRepairing is only read from /etc/bluetooth/main.conf, change it there and
+        /// restart bluetoothd. The daemon does not write the configuration of the system.\
+        /// Requires authorization.
+        fn SetJustWorksRepairing(mode: String) -> bool;
+        ///
+        /// Sets how files pushed over OBEX by the device are handled, one of accept, prompt, reject
+        /// or default.\
+        /// With default, pushes of trusted devices are accepted and all others are forwarded to
+        /// clients with ObexTransferRequested. Policies are stored by the device address.\
+        /// The daemon only registers itself as agent of obexd if obex_agent is set to true within
+        /// the `[Daemon]` section of the config.\
+        /// Requires authorization.
+        fn SetObexPolicy(device: Path<'static>, policy: String) -> bool;
+        ///
+        /// Sets the directory accepted OBEX pushes are stored in, $HOME/Downloads by default.\
+        /// The path has to be an existing directory within the home directory, hidden directories
+        /// like ~/.config are rejected with dbus invalid arguments. Existing files are never
+        /// overwritten and received files get a numbered suffix instead.\
+        /// Requires authorization.
+        fn SetObexDownloadDir(path: String) -> bool;
+        ///
+        /// Accepts or rejects a push of ObexTransferRequested, unanswered pushes are rejected after
+        /// 30 seconds.\
+        /// Returns false if the request does not exist anymore.\
+        /// Requires authorization.
+        fn AnswerObexTransfer(id: u32, accept: bool) -> bool;
+        ///
+        /// Sets the discoverability of a specific Bluetooth adapter.\
+        /// The state is restored like the power state of SetBluetoothAdapterEnabled.
         fn SetBluetoothAdapterDiscoverability(path: Path<'static>, enabled: bool) -> bool;
         ///
+        /// Makes the current Bluetooth adapter discoverable for the given amount of seconds,
+        /// DiscoverableExpired is emitted once bluez disables it again.\
+        /// Unlike SetBluetoothAdapterDiscoverability, the state is not restored on startup.
+        /// Calling SetBluetoothAdapterDiscoverability cancels the timer.\
+        /// Returns dbus invalid arguments when the amount of seconds is 0.
+        fn MakeDiscoverableFor(seconds: u32) -> bool;
+        ///
         /// Sets the pairability of a specific Bluetooth adapter.
         fn SetBluetoothAdapterPairability(path: Path<'static>, enabled: bool) -> bool;
         ///
+        /// Returns the device currently pairing and the PIN to type on it, e.g. for GUIs opened
+        /// after PairingPinDisplay was emitted.\
+        /// Returns the path "/" and an empty PIN when no keyboard is pairing.
+        fn GetPairingPin() -> (Path<'static>, String);
+        ///
         /// Returns the currently available Bluetooth devices.
         /// Only returns devices provided by bluetooth discovery.
-        /// Use StartBluetoothScan in order to enable temporary discovery.
+        /// Use StartBluetoothScan in order to enable temporary discovery.\
+        /// Anonymous LE devices are left out, see GetAnonymousDeviceCount.
         fn GetBluetoothDevices() -> Vec<BluetoothDevice>;
         ///
+        /// Returns the amount of anonymous LE devices, which are collapsed into this count instead
+        /// of being reported as devices.\
+        /// Anonymous devices use a random address and have neither a name nor services, e.g.
+        /// beacons or phones advertising with rotating addresses, and are neither paired, trusted
+        /// nor connected.\
+        /// The daemon reports them like every other device when started with
+        /// `--raw-bluetooth-devices` or with bluetooth_raw_devices = true within the `[Daemon]`
+        /// section of the config.
+        fn GetAnonymousDeviceCount() -> u32;
+        ///
         /// Connects to a Bluetooth device given the DBus path.\
         /// Note that this requires an existing pairing.\
+        /// The connection is established in the background, once it finishes the
+        /// BluetoothConnectResult(device, success, error_name, code) signal is emitted. On failure
+        /// error_name contains the reason reported by BlueZ, e.g. br-connection-page-timeout,
+        /// and code the status code, e.g. Timeout or PairingFailed. The code is Ok on success.\
         /// Returns true on success and false on error.
         fn ConnectToBluetoothDevice(path: Path<'static>) -> bool;
         ///
@@ -229,13 +943,87 @@ pub mod API {
         /// Returns true on success and false on error.
         fn DisconnectFromBluetoothDevice(path: Path<'static>) -> bool;
         ///
-        /// This will remove the pairing on the Bluetooth device.
+        /// Connects a single profile of a Bluetooth device, e.g. only the audio sink of a headset
+        /// with buttons.\
+        /// The uuid is either a full UUID or a 16 bit assigned number like 110b, see
+        /// GetBluetoothProfileUUIDs. The result is emitted as BluetoothConnectResult.\
+        /// Returns dbus invalid arguments if the device does not offer the profile.
+        fn ConnectBluetoothDeviceProfile(path: Path<'static>, uuid: String) -> bool;
+        ///
+        /// Disconnects a single profile of a Bluetooth device, see ConnectBluetoothDeviceProfile.
+        fn DisconnectBluetoothDeviceProfile(path: Path<'static>, uuid: String) -> bool;
+        ///
+        /// Returns the profiles that can be connected individually as (name, uuid), e.g.
+        /// ("audio_sink", "0000110b-0000-1000-8000-00805f9b34fb").
+        fn GetBluetoothProfileUUIDs() -> Vec<(String, String)>;
+        ///
+        /// This will remove the pairing on the Bluetooth device.\
+        /// Requires authorization.
         fn RemoveDevicePairing(path: Path<'static>) -> bool;
         ///
+        /// Removes every device that is neither paired nor connected from all adapters, e.g. the
+        /// devices left over from previous scans.\
+        /// Returns the amount of removed devices.\
+        /// Requires authorization, see RemoveDevicePairing.
+        fn RemoveAllUnpairedDevices() -> u32;
+        ///
+        /// Removes every device that was last seen before the timestamp in seconds since the unix
+        /// epoch, including paired devices. Connected devices are always kept.\
+        /// Last seen is taken from the device history, see GetBluetoothDeviceHistory, devices that
+        /// were never recorded count as not seen.\
+        /// Returns the amount of removed devices.\
+        /// Requires authorization, see RemoveDevicePairing.
+        fn RemoveDevicesNotSeenSince(timestamp: u64) -> u32;
+        ///
         /// Returns all connected Bluetooth devices.
         /// The first part of the HashMap is the DBus path of the object, the second is the object
         /// itself.
         fn GetConnectedBluetoothDevices() -> Vec<BluetoothDevice>;
+        ///
+        /// Returns the last seen and last connected time of a device given its address.\
+        /// Times are in seconds since the unix epoch with a resolution of a minute, 0 if never.\
+        /// Devices are recorded while the bluetooth listener is running and when listing devices.
+        fn GetBluetoothDeviceHistory(address: String) -> (u64, u64);
+        ///
+        /// Returns (address, last seen, last connected) of all recorded devices, most recently
+        /// connected first. Useful for "recently used devices" sections.
+        fn ListBluetoothDeviceHistory() -> Vec<(String, u64, u64)>;
+        ///
+        /// Returns the device type and the services of a device as (uuid, name).\
+        /// The type is derived from the icon, class and appearance reported by bluez and is one
+        /// of headset, headphones, speaker, keyboard, mouse, gamepad, phone, computer, watch or
+        /// unknown.\
+        /// Services are the UUIDs announced by the device, the name is empty for unknown
+        /// services. E.g. only devices offering "OBEX Object Push" can receive files.\
+        /// Returns dbus invalid arguments on an unknown device.
+        fn GetBluetoothDeviceServices(path: Path<'static>) -> (String, Vec<(String, String)>);
+        ///
+        /// Returns whether a device supports Bluetooth LE, classic (BR/EDR) or both as "le",
+        /// "classic" or "dual".\
+        /// The mode is derived from the address type, class, appearance and services reported by
+        /// bluez. Devices using a random address are always LE only.\
+        /// Returns dbus invalid arguments on an unknown device.
+        fn GetBluetoothDeviceMode(path: Path<'static>) -> String;
+        ///
+        /// Returns the currently available Bluetooth devices like GetBluetoothDevices, each with
+        /// its mode as in GetBluetoothDeviceMode.\
+        /// Without include_le_only, LE only devices that are neither paired nor connected are
+        /// skipped, these are mostly beacons that can not be paired.
+        fn ListBluetoothDevices(include_le_only: bool) -> Vec<(BluetoothDevice, String)>;
+        ///
+        /// Returns the connected Bluetooth devices, each with its mode as in
+        /// GetBluetoothDeviceMode.\
+        /// include_le_only is applied like in ListBluetoothDevices.
+        fn ListConnectedBluetoothDevices(include_le_only: bool) -> Vec<(BluetoothDevice, String)>;
+        ///
+        /// Enables or disables the automatic routing of audio to Bluetooth devices.\
+        /// When enabled, a connecting Bluetooth audio device is set as the default sink and the
+        /// previous default sink is restored once the device disconnects.\
+        /// The value is stored as `bluetooth_auto_route` in the `[Daemon]` section of the config
+        /// file and applied again on the next start.\
+        /// Returns true on success and false on error.\
+        /// Requires authorization.
+        fn SetBluetoothAutoRoute(enabled: bool) -> bool;
     }
 
     /// # Audio Manager API
@@ -284,11 +1072,49 @@ pub mod API {
     /// InputStreamRemoved -> Path<'static>\
     /// OutputStreamChanged -> OutputStream\
     /// OutputStreamAdded -> OutputStream\
-    /// OutputStreamRemoved -> Path<'static>
+    /// OutputStreamRemoved -> Path<'static>\
+    /// AudioDeviceConnected -> (String, String, u32)
+    ///
+    /// AudioDeviceConnected is a simplified event for notification daemons, it is emitted when a
+    /// new sink or source is plugged in and contains the kind ("sink" or "source"), the
+    /// device.description of pulse, e.g. "Headset", and the index.
+    ///
+    /// SinkChangedDelta -> (u32, u32, Vec<Variant>)\
+    /// SourceChangedDelta -> (u32, u32, Vec<Variant>)\
+    /// InputStreamChangedDelta -> (u32, u32, Vec<Variant>)\
+    /// OutputStreamChangedDelta -> (u32, u32, Vec<Variant>)
+    ///
+    /// The delta events are only sent to clients registered with RegisterDeltaClient, see there.
+    ///
+    /// AudioDevicesAvailable -> bool
+    ///
+    /// AudioDevicesAvailable is emitted when the first audio device appeared or the last one was
+    /// removed. Monitors and the dummy output (auto_null) do not count as devices.
+    ///
+    /// StreamPeak -> (u32, f64)\
+    /// StreamPeakMonitorStopped -> u32
+    ///
+    /// StreamPeak is emitted about 25 times per second for every input stream monitored with
+    /// StartStreamPeakMonitor and contains the index and the peak between 0.0 and 1.0.\
+    /// StreamPeakMonitorStopped is emitted when the server ended a monitor, e.g. as the input
+    /// stream was removed or moved to another sink by a different client.
+    ///
+    /// StreamCorkStateChanged -> (u32, bool, bool)
+    ///
+    /// StreamCorkStateChanged is emitted with the index, corked and suspended once an input
+    /// stream was paused or resumed. Corked streams were paused by their application, suspended
+    /// streams play to a sink suspended by the server, mixers can gray out both.
+    ///
+    /// ## Properties
+    /// HasAudioDevices -> bool\
+    /// Whether any sink or source besides monitors and the dummy output exists, changes are
+    /// emitted with PropertiesChanged as well as AudioDevicesAvailable.
     ///
     pub trait AudioAPI {
         ///
         /// Returns the default sink(speaker, headphones, etc.) from pulseaudio.\
+        /// If no sink or only the dummy output exists, a sink with the index u32::MAX and empty
+        /// fields is returned instead of an error, see HasAudioDevices.
         fn GetDefaultSink() -> Sink;
         ///
         /// Returns the default sink name(speaker, headphones, etc.) from pulseaudio.\
@@ -297,6 +1123,8 @@ pub mod API {
         fn GetDefaultSinkName() -> String;
         ///
         /// Returns the default source(microphone) from pulseaudio.\
+        /// If no source or only the monitor of the dummy output exists, a source with the index
+        /// u32::MAX and empty fields is returned instead of an error, see HasAudioDevices.
         fn GetDefaultSource() -> Source;
         ///
         /// Returns the default source name(microphone) from pulseaudio.\
@@ -314,15 +1142,30 @@ pub mod API {
         /// events.
         fn SetDefaultSource(source: String) -> Source;
         ///
-        /// Returns all current sinks.
+        /// Returns all current sinks.\
+        /// Served from the cache of the daemon, which is kept up to date with the events of
+        /// pulseaudio.
         fn ListSinks() -> Vec<Sink>;
         ///
-        /// Returns all current sources.
+        /// Returns all current sources.\
+        /// Served from the cache of the daemon, which is kept up to date with the events of
+        /// pulseaudio.
         fn ListSources() -> Vec<Source>;
         ///
+        /// Forces a new introspection of all sinks, sources and streams, which replaces the cached
+        /// objects of the list and get calls.\
+        /// Only necessary when a client suspects the cache to be stale, e.g. after resuming from
+        /// suspend.\
+        /// Returns true on success.
+        fn RefreshAudioCache() -> bool;
+        ///
         /// Returns all streams that are responsible for playing audio, e.g. applications.\
         fn ListInputStreams() -> Vec<InputStream>;
         ///
+        /// Returns the playback state of all input streams as (index, corked, suspended), see
+        /// StreamCorkStateChanged.
+        fn ListStreamCorkStates() -> Vec<(u32, bool, bool)>;
+        ///
         /// Returns all streams that are responsible for recording audio, e.g. OBS, voice chat applications.\
         fn ListOutputStreams() -> Vec<OutputStream>;
         ///
@@ -340,6 +1183,12 @@ pub mod API {
         /// The index can be found within the Sink data structure.
         fn SetSinkMute(index: u32, muted: bool);
         ///
+        /// Suspends or resumes a sink, e.g. an unused HDMI output.\
+        /// The state is reported with SinkChanged, the active field of the sink is 2 while it is
+        /// suspended. Streams playing to a suspended sink are paused, see StreamCorkStateChanged.\
+        /// Returns false if the sink does not exist or the server refused the request.
+        fn SuspendSink(index: u32, suspend: bool) -> bool;
+        ///
         /// Sets the default volume of the source on all channels to the specified value.\
         /// Currently ReSet does not offer individual channel volumes. (This will be added later)\
         /// The index can be found within the Source data structure.
@@ -350,11 +1199,20 @@ pub mod API {
         /// The index can be found within the Source data structure.
         fn SetSourceMute(index: u32, muted: bool);
         ///
-        /// Sets the default volume of the input_stream on all channels to the specified value.\
-        /// Currently ReSet does not offer individual channel volumes. (This will be added later)\
+        /// Moves the input stream to the sink.\
+        /// The sink is remembered for the application.name of the stream, new streams of the
+        /// application are moved to it as long as the sink exists. See ForgetStreamRouting.\
         /// The index can be found within the InputStream data structure.
         fn SetSinkOfInputStream(input_stream: u32, sink: u32);
         ///
+        /// Forgets the sink learned for an application by SetSinkOfInputStream, new streams of the
+        /// application play on the default sink again.\
+        /// Returns false if nothing was learned for the application.
+        fn ForgetStreamRouting(application_name: String) -> bool;
+        ///
+        /// Returns all learned routes as (application name, sink name).
+        fn ListStreamRouting() -> Vec<(String, String)>;
+        ///
         /// Sets the default volume of the input stream on all channels to the specified value.\
         /// Currently ReSet does not offer individual channel volumes. (This will be added later)\
         /// The index can be found within the InputStream data structure.
@@ -385,5 +1243,536 @@ pub mod API {
         /// the ListCards() function.\
         /// The index of the device can be found in the Device data structure.
         fn SetCardOfDevice(device_index: u32, profile_name: String);
+        ///
+        /// Returns (source index, is_monitor, monitored sink index) for every source.\
+        /// Monitor sources capture the audio played on a sink, the sink index is u32::MAX for
+        /// regular sources like microphones.
+        fn ListSourceMonitors() -> Vec<(u32, bool, u32)>;
+        ///
+        /// Routes a source to a sink with module-loopback, e.g. a microphone to speakers or a monitor
+        /// source to another sink.\
+        /// A latency of 0 uses the default latency of the module.\
+        /// Returns the index of the loaded module, which is used to remove the loopback.
+        fn CreateLoopback(source_index: u32, sink_index: u32, latency_ms: u32) -> u32;
+        ///
+        /// Removes a loopback created with CreateLoopback.\
+        /// Returns false if the module does not exist or is not a loopback.
+        fn RemoveLoopback(index: u32) -> bool;
+        ///
+        /// Renames the sink on the audio server using module-device-manager, which is loaded if
+        /// necessary. The server keeps the description when the device reappears.\
+        /// The new description is reported with SinkChanged and used by ListSinks, device aliases
+        /// set with SetDeviceAlias still take precedence.\
+        /// Returns false if the sink does not exist or the server does not support renaming.
+        fn SetSinkDescription(index: u32, description: String) -> bool;
+        ///
+        /// Renames the source, see SetSinkDescription.\
+        /// The new description is reported with SourceChanged.
+        fn SetSourceDescription(index: u32, description: String) -> bool;
+        ///
+        /// Enables or disables the noise suppression of a source, e.g. a microphone.\
+        /// Enabling loads module-echo-cancel with only the WebRTC noise suppression, which creates a
+        /// filtered source named "<source name>.noise-suppressed". Applications have to record from
+        /// the filtered source, e.g. by setting it as default source.\
+        /// The modules are unloaded when the noise suppression is disabled or the daemon stops.\
+        /// Returns false if the source does not exist, is itself a filtered source or the module
+        /// could not be loaded.
+        fn SetNoiseSuppression(source_index: u32, enabled: bool) -> bool;
+        ///
+        /// Returns (source index, filtered source index) for every source with enabled noise
+        /// suppression.
+        fn ListNoiseSuppressedSources() -> Vec<(u32, u32)>;
+        ///
+        /// Returns the sinks playing on other devices as (index, name, description, kind), the kind
+        /// is tunnel for other audio servers and raop for AirPlay receivers.\
+        /// The first call loads module-zeroconf-discover and module-raop-discover, which keep
+        /// creating sinks for devices announced on the local network until the daemon stops.
+        /// Devices found later are reported with SinkAdded like any other sink.
+        fn DiscoverNetworkSinks() -> Vec<(u32, String, String, String)>;
+        ///
+        /// Loads module-tunnel-sink for the audio server on the host, optionally followed by
+        /// :port.\
+        /// The remote server has to accept connections with module-native-protocol-tcp, the sink
+        /// is reported with SinkAdded and can be used like any local sink.\
+        /// Returns false if the host is invalid or the module could not be loaded.
+        fn ConnectNetworkSink(host: String) -> bool;
+        ///
+        /// Unloads the tunnel sink loaded with ConnectNetworkSink.\
+        /// Returns false if the host was not connected.
+        fn DisconnectNetworkSink(host: String) -> bool;
+        ///
+        /// Returns the name and version of the audio server along with the default sample spec
+        /// and channel map, e.g. ("PulseAudio (on PipeWire 1.0.5)", "15.0.0", "s16le", 48000, 2,
+        /// [("front-left", "Front Left"), ("front-right", "Front Right")]).\
+        /// The DBus signature is sssuya(ss), the channel map uses the positions of
+        /// GetSinkChannelMap.
+        fn GetAudioServerInfo() -> (String, String, String, u32, u8, Vec<(String, String)>);
+        ///
+        /// Forces the sample rate of the audio graph, one of 44100, 48000, 88200, 96000, 176400
+        /// or 192000. 0 lets the server choose the rate again.\
+        /// Only supported with PipeWire, plain PulseAudio reads the rate from daemon.conf on
+        /// start and fails with Unsupported.\
+        /// Returns false if the rate could not be set.
+        fn SetDefaultSampleRate(rate: u32) -> bool;
+        ///
+        /// Starts emitting StreamPeak for the input stream, e.g. for per application VU meters.\
+        /// The peak is measured on the monitor of the sink the input stream plays on, restricted
+        /// to the input stream. Moving the input stream with SetSinkOfInputStream keeps the
+        /// monitor running.\
+        /// The monitor is stopped once every client that started it disconnected.\
+        /// Returns false if the input stream does not exist.
+        fn StartStreamPeakMonitor(index: u32) -> bool;
+        ///
+        /// Stops emitting StreamPeak for the input stream.\
+        /// Returns false if no monitor was running for the input stream.
+        fn StopStreamPeakMonitor(index: u32) -> bool;
+        ///
+        /// Returns the channels of a sink in the order of its channel map as (position, name),
+        /// e.g. (front-left, Front Left) or (rear-left, Rear Left).\
+        /// Returns the org.Xetibo.ReSet.Error.DeviceNotFound error for unknown sinks.
+        fn GetSinkChannelMap(index: u32) -> Vec<(String, String)>;
+        ///
+        /// Returns the device.form_factor and an icon name of a sink, e.g. ("headphone",
+        /// "audio-headphones-bluetooth") or ("", "video-display") for HDMI outputs.\
+        /// The form factor is empty if the server does not know it, the icon name follows the
+        /// freedesktop icon naming specification and falls back to audio-card.\
+        /// Returns the org.Xetibo.ReSet.Error.DeviceNotFound error for unknown sinks.
+        fn GetSinkHints(index: u32) -> (String, String);
+        ///
+        /// Returns the form factor and icon name of a source like GetSinkHints, e.g. ("webcam",
+        /// "camera-web").\
+        /// Returns the org.Xetibo.ReSet.Error.DeviceNotFound error for unknown sources.
+        fn GetSourceHints(index: u32) -> (String, String);
+        ///
+        /// Plays a one second test tone on the speaker at the position of the sink, e.g.
+        /// front-left, as used by speaker setup pages. The LFE channel receives a lower tone.\
+        /// The tone is played without remixing, other speakers stay silent.\
+        /// Returns false if the sink does not exist or has no such channel, returns dbus invalid
+        /// arguments on an unknown position.
+        fn PlayChannelTest(index: u32, position: String) -> bool;
+        ///
+        /// Sets a persistent label for a sink or source, the kind is either "sink" or "source".\
+        /// The identifier is the device name, the bus path (device.bus_path, e.g. pci-0000:00:1f.3)
+        /// or the ALSA card name, which stay stable when the device name changes between reboots.\
+        /// The label is used as the alias of matching sinks and sources, an empty label removes it.\
+        /// Returns true on success and false if the alias could not be saved.
+        fn SetDeviceAlias(kind: String, identifier: String, label: String) -> bool;
+        ///
+        /// Sets the maximum volume in percent, e.g. 150 to allow overamplification.\
+        /// Once a limit is set, all volume setters clamp to it regardless of the client, the limit
+        /// is persisted and takes precedence over max_volume in the Daemon section of the config
+        /// file. Without a limit only relative changes like VolumeUp stop at 100 percent.\
+        /// Fails if the limit is not between 1 and 200 percent, returns false if it could not be
+        /// saved.
+        fn SetVolumeLimit(percent: u32) -> bool;
+        ///
+        /// Returns the maximum volume and the default step for relative changes in percent, the
+        /// maximum is 100 unless a limit is configured.\
+        /// The default step can be configured with volume_step in the Daemon section of the config
+        /// file.
+        fn GetVolumeLimit() -> (u32, u32);
+        ///
+        /// Returns the volume of every object of the kind as (index, percent per channel, dB per
+        /// channel).\
+        /// Kind is one of "sink", "source", "input_stream" or "output_stream", a volume of 0 is
+        /// reported as negative infinity dB.
+        fn ListVolumeInfo(kind: String) -> Vec<(u32, Vec<u32>, Vec<f64>)>;
+        ///
+        /// Sets all channels of the sink to the volume in percent.\
+        /// The volume is rounded to the nearest raw volume and clamped to the volume limit.\
+        /// Returns the applied raw volume, fails on negative percentages or unknown indices.
+        fn SetSinkVolumePercent(index: u32, percent: f64) -> u32;
+        ///
+        /// Sets all channels of the source to the volume in percent, see SetSinkVolumePercent.
+        fn SetSourceVolumePercent(index: u32, percent: f64) -> u32;
+        ///
+        /// Sets all channels of the input stream to the volume in percent, see
+        /// SetSinkVolumePercent.
+        fn SetInputStreamVolumePercent(index: u32, percent: f64) -> u32;
+        ///
+        /// Sets all channels of the output stream to the volume in percent, see
+        /// SetSinkVolumePercent.
+        fn SetOutputStreamVolumePercent(index: u32, percent: f64) -> u32;
+        ///
+        /// Enables or disables the automatic switch to the headset profile of Bluetooth cards.\
+        /// When enabled, Bluetooth cards using an A2DP profile are switched to their headset
+        /// profile while any application records from a microphone, the previous profile is
+        /// restored once recording stops.\
+        /// The initial value can be set with `auto_headset_profile` in the `[Daemon]` section of
+        /// the config file.\
+        /// Returns true on success and false on error.
+        fn SetAutoHeadsetProfile(enabled: bool) -> bool;
+    }
+    /// # Actions API
+    /// High level verbs intended to be bound by hotkey daemons.\
+    /// The relative changes are applied and clamped by the daemon, the results are emitted as
+    /// signals which can be shown within an OSD.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Actions
+    ///
+    /// Signals:\
+    /// VolumeChanged -> (String, u32, bool) the sink alias, volume in percent and mute state\
+    /// WifiToggled -> bool\
+    /// BluetoothToggled -> bool
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait ActionsAPI {
+        ///
+        /// Raises the volume of the default sink by step percent, up to the volume limit.\
+        /// A step of 0 uses the default step, see GetVolumeLimit.\
+        /// Returns the new volume in percent and emits VolumeChanged.
+        fn VolumeUp(step: u32) -> u32;
+        ///
+        /// Lowers the volume of the default sink by step percent, down to 0 percent.\
+        /// A step of 0 uses the default step, see GetVolumeLimit.\
+        /// Returns the new volume in percent and emits VolumeChanged.
+        fn VolumeDown(step: u32) -> u32;
+        ///
+        /// Mutes or unmutes the default sink depending on its current state.\
+        /// Returns the volume in percent and emits VolumeChanged.
+        fn ToggleMute() -> u32;
+        ///
+        /// Enables or disables WiFi depending on its current state.\
+        /// Returns the new state and emits WifiToggled.
+        fn ToggleWifi() -> bool;
+        ///
+        /// Powers the current Bluetooth adapter on or off depending on its current state.\
+        /// Returns the new state and emits BluetoothToggled, fails if no adapter is available.
+        fn ToggleBluetooth() -> bool;
+    }
+    /// # Modem API
+    /// Lists mobile broadband modems of ModemManager and activates their connections through
+    /// NetworkManager.\
+    /// Only available with the Modem capability, which requires both ModemManager and
+    /// NetworkManager.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Modem
+    ///
+    /// ## Types
+    ///
+    /// ### Modem
+    /// The Modem has the following DBus signature: osssusi\
+    /// `Path<'static>, String, String, u32, String, i32`\
+    /// path, manufacturer, model, signal quality in percent, operator name and MMModemState.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait ModemAPI {
+        ///
+        /// Returns all modems known to ModemManager.\
+        /// The operator is empty while the modem is not registered to a network.
+        fn ListModems() -> Vec<Modem>;
+        ///
+        /// Returns the stored mobile broadband (gsm) connections of NetworkManager as (path, name).
+        fn ListMobileConnections() -> Vec<(Path<'static>, String)>;
+        ///
+        /// Activates a stored mobile broadband connection on the given modem.\
+        /// Returns true on success and false on error.
+        fn ActivateMobileConnection(connection: Path<'static>, modem: Path<'static>) -> bool;
+    }
+    /// # Battery API
+    /// Lists the batteries of UPower, the system battery as well as peripherals like wireless
+    /// mice and keyboards.\
+    /// Only available with the Battery capability, which requires UPower.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Battery
+    ///
+    /// ## Types
+    ///
+    /// ### Battery
+    /// The Battery has the following DBus signature: ossdsb\
+    /// `Path<'static>, String, String, f64, String, bool`\
+    /// path, kind, model, percentage, state and power supply.\
+    /// The kind is e.g. battery, mouse, keyboard or headset, the state one of unknown, charging,
+    /// discharging, empty, fully-charged, pending-charge or pending-discharge. Power supply is true
+    /// for batteries powering the system.
+    ///
+    /// ## Events
+    /// BatteryChanged -> Battery\
+    /// emitted when the percentage or state of a battery changed and when a peripheral appeared.\
+    /// BatteryRemoved -> Path<'static>\
+    /// emitted when a peripheral disappeared, e.g. a mouse was disconnected.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait BatteryAPI {
+        ///
+        /// Returns all present batteries, batteries powering the system first. Power supplies
+        /// without a battery are omitted.
+        fn ListBatteries() -> Vec<(Path<'static>, String, String, f64, String, bool)>;
+    }
+    /// # Bluetooth Media API
+    /// Media control of connected Bluetooth audio devices through the AVRCP player of bluez
+    /// (org.bluez.MediaPlayer1), e.g. media keys of headsets.\
+    /// Only available with the Bluetooth capability. Devices are addressed with their bluez path,
+    /// players only exist while the remote control profile is connected.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.BluetoothMedia
+    ///
+    /// ## Types
+    ///
+    /// ### BluetoothMediaTrack
+    /// The BluetoothMediaTrack has the following DBus signature: sssu\
+    /// `String, String, String, u32`\
+    /// title, artist, album and duration in milliseconds, unknown fields are empty or 0.
+    ///
+    /// ## Events
+    /// TrackChanged -> (Path<'static>, BluetoothMediaTrack)\
+    /// emitted with the device when the player switched to another track.\
+    /// PlaybackStatusChanged -> (Path<'static>, String)\
+    /// emitted with the device and the new status, see GetMediaState.\
+    /// BatteryPercentageChanged -> (Path<'static>, u8)\
+    /// emitted with the device and the new battery level in percent.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait BluetoothMediaAPI {
+        ///
+        /// Starts or resumes playback on the device.\
+        /// Returns true on success and false on error, fails for unknown devices and devices
+        /// without a media player.
+        fn Play(device: Path<'static>) -> bool;
+        ///
+        /// Pauses playback on the device.\
+        /// Returns true on success and false on error, fails for unknown devices and devices
+        /// without a media player.
+        fn Pause(device: Path<'static>) -> bool;
+        ///
+        /// Skips to the next track.\
+        /// Returns true on success and false on error, fails for unknown devices and devices
+        /// without a media player.
+        fn Next(device: Path<'static>) -> bool;
+        ///
+        /// Returns to the previous track.\
+        /// Returns true on success and false on error, fails for unknown devices and devices
+        /// without a media player.
+        fn Previous(device: Path<'static>) -> bool;
+        ///
+        /// Returns the player state as (status, track, position in milliseconds).\
+        /// The status is one of playing, paused, stopped, forward-seek, reverse-seek or error.
+        fn GetMediaState(device: Path<'static>) -> (String, (String, String, String, u32), u32);
+        ///
+        /// Returns the battery level in percent of the experimental org.bluez.Battery1 interface.\
+        /// Fails if the device does not report its battery level, older versions of bluez have to
+        /// run with --experimental.
+        fn GetBatteryPercentage(device: Path<'static>) -> u8;
+    }
+    /// # Notifications API
+    /// Do not disturb of the running notification daemon, supported are mako, dunst and swaync.\
+    /// Only available with the DoNotDisturb capability, which requires one of these daemons to
+    /// run when the daemon starts.\
+    /// mako has no dedicated do not disturb, the do-not-disturb mode is toggled instead. The mode
+    /// has to be defined in the config of mako, e.g. with `invisible=1`, another mode can be chosen
+    /// with dnd_mode within the `[Daemon]` section of the config.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Notifications
+    ///
+    /// ## Events
+    /// DoNotDisturbChanged -> bool\
+    /// emitted when do not disturb was toggled, including changes outside of the daemon.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait NotificationsAPI {
+        ///
+        /// Returns whether do not disturb is enabled.
+        fn GetDoNotDisturb() -> bool;
+        ///
+        /// Enables or disables do not disturb.\
+        /// Returns true on success and false on error, fails if no supported notification daemon
+        /// is running.
+        fn SetDoNotDisturb(enabled: bool) -> bool;
+        ///
+        /// Returns the running notification daemon, one of mako, dunst or swaync.\
+        /// Empty if the notification daemon was stopped or replaced by an unsupported one.
+        fn GetNotificationDaemon() -> String;
+    }
+    /// # Idle API
+    /// Keeps the screen awake with idle inhibitors of logind, e.g. for a "keep screen awake"
+    /// toggle. Idle daemons respecting inhibitors of logind, e.g. hypridle, will not blank the
+    /// screen while an inhibitor exists.\
+    /// Inhibitors belong to the client which created them, they are released once the client calls
+    /// UnregisterClient or disconnects from the bus.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Idle
+    ///
+    /// ## Types
+    ///
+    /// ### Inhibitor
+    /// The Inhibitor has the following DBus signature: usst\
+    /// `u32, String, String, u64`\
+    /// cookie, unique bus name of the owning client, reason and creation time in seconds since the
+    /// unix epoch.
+    ///
+    /// ## Events
+    /// InhibitorsChanged -> Vec<Inhibitor>\
+    /// emitted with all inhibitors whenever one was added or released.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait IdleAPI {
+        ///
+        /// Takes an idle inhibitor with the given reason and returns its cookie.\
+        /// Fails if logind is not available, each client may hold up to 8 inhibitors, further
+        /// calls return the Busy error.\
+        /// Requires authorization.
+        fn Inhibit(reason: String) -> u32;
+        ///
+        /// Releases an inhibitor of the calling client.\
+        /// Returns false for unknown cookies and inhibitors of other clients.
+        fn Uninhibit(cookie: u32) -> bool;
+        ///
+        /// Returns the inhibitors of all clients.
+        fn ListInhibitors() -> Vec<InhibitorInfo>;
+    }
+    /// # Media API
+    /// Aggregates the MPRIS players on the session bus, applets get media controls of every
+    /// player without implementing the discovery of MPRIS themselves.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Media
+    ///
+    /// ## Types
+    ///
+    /// ### MediaPlayer
+    /// The MediaPlayer has the following DBus signature: sss(sassts)\
+    /// `String, String, String, (String, Vec<String>, String, u64, String)`\
+    /// bus name, identity, playback status and the track as (title, artists, album, length in
+    /// milliseconds, art url).\
+    /// The bus name identifies the player for PlayerAction, the identity is the name shown to
+    /// users. The playback status is one of Playing, Paused or Stopped.
+    ///
+    /// ## Events
+    /// NowPlayingChanged -> MediaPlayer\
+    /// emitted when a player appears or changes its track or playback status.\
+    /// PlayerRemoved -> String\
+    /// emitted with the bus name once a player left the bus.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait MediaAPI {
+        ///
+        /// Returns all MPRIS players, playing players first.
+        fn ListPlayers() -> Vec<MediaPlayer>;
+        ///
+        /// Runs an action on a player, one of play, pause, play_pause, stop, next or previous.\
+        /// Returns false if the player rejected the action, fails for unknown players and
+        /// actions.
+        fn PlayerAction(player: String, action: String) -> bool;
+    }
+    /// # Proxy API
+    /// Proxy of the session, GUIs use this interface as the single source of truth instead of
+    /// editing environment files themselves.\
+    /// The settings are stored within the `[Daemon.proxy]` section of the config:
+    /// ```toml
+    /// [Daemon.proxy]
+    /// mode = "manual"
+    /// host = "proxy.example.com"
+    /// port = 3128
+    /// ignore_hosts = ["localhost", "127.0.0.1"]
+    /// ```
+    /// Manual proxies are exported as http_proxy, https_proxy, ftp_proxy, all_proxy and no_proxy
+    /// to environment.d/90-reset-proxy.conf within the config directory, which systemd applies
+    /// to the next session. PAC files can not be exported as environment variables.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.Proxy
+    ///
+    /// ## Types
+    ///
+    /// ### ProxySettings
+    /// The ProxySettings has the following DBus signature: ssqsas\
+    /// `String, String, u16, String, Vec<String>`\
+    /// mode, host, port, PAC url and hosts which bypass the proxy.\
+    /// The mode is one of none, manual or auto. Host and port are used by manual, the PAC url by
+    /// auto.
+    ///
+    /// ## Events
+    /// ProxySettingsChanged -> ProxySettings\
+    /// emitted when the settings were changed.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait ProxyAPI {
+        ///
+        /// Returns the current proxy settings.
+        fn GetProxySettings() -> ProxySettings;
+        ///
+        /// Stores the proxy settings within the config and writes the environment file.\
+        /// Returns false if the settings could not be written, fails for unknown modes, manual
+        /// proxies without host or port and automatic proxies without PAC url.\
+        /// Hosts may only contain host names, IPv4 or bracketed IPv6 addresses with an optional
+        /// http, https, socks, socks4 or socks5 scheme, hosts to ignore may additionally contain
+        /// wildcards and CIDR ranges. Other values return dbus invalid arguments.\
+        /// Requires authorization.
+        fn SetProxySettings(settings: ProxySettings) -> bool;
+    }
+    /// # SpeedTest API
+    /// Measures latency, download and upload against plain HTTP(S) endpoints.\
+    /// Only available with the SpeedTest capability, which requires curl to be installed.\
+    /// No server is contacted by default, servers have to be configured within the `[Daemon]`
+    /// section of the config:
+    /// ```toml
+    /// [Daemon.speedtest_servers.home]
+    /// download = "http://192.168.1.2/100MB.bin"
+    /// upload = "http://192.168.1.2/upload"
+    /// ```
+    /// The download endpoint has to serve a large file, the upload endpoint has to accept POST
+    /// requests of arbitrary size. Each transfer stops after 10 seconds.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.SpeedTest
+    ///
+    /// ## Types
+    ///
+    /// ### SpeedTestResult
+    /// The SpeedTestResult has the following DBus signature: sddd\
+    /// `String, f64, f64, f64`\
+    /// server, latency in milliseconds, download and upload in Mbit/s.
+    ///
+    /// ## Events
+    /// SpeedTestProgress -> (String, f64)\
+    /// emitted while the test runs with the stage and its current value, the stage is one of
+    /// latency, download or upload.\
+    /// SpeedTestFinished -> (SpeedTestResult, String, String)\
+    /// emitted with the result, an error and the status code, the error is empty and the code Ok
+    /// if the test succeeded. Failures use Cancelled, ServerUnreachable, Unsupported without
+    /// curl and Failed.
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait SpeedTestAPI {
+        ///
+        /// Starts a speed test against the configured server, the first server is used if the
+        /// name is empty.\
+        /// Returns false if another test is running, fails for unknown servers and with the
+        /// Unsupported error if no server is configured.
+        fn StartSpeedTest(server: String) -> bool;
+        ///
+        /// Cancels the running test, SpeedTestFinished is emitted with an error.\
+        /// Returns false if no test is running.
+        fn CancelSpeedTest() -> bool;
+        ///
+        /// Returns the names of the configured servers.
+        fn ListSpeedTestServers() -> Vec<String>;
+    }
+    /// # System API
+    /// Restricted interface served on the system bus for greeters and other users without access
+    /// to the session bus of the daemon.\
+    /// Only available when the daemon is started with --system-bus or system_bus = true within
+    /// the `[Daemon]` section of the config, the dbus/org.Xetibo.ReSet.Daemon.conf policy has to be
+    /// installed and the user running the daemon has to be a member of the reset group to claim
+    /// the name on the system bus.
+    ///
+    /// DBus interface name: org.Xetibo.ReSet.System
+    ///
+    #[allow(dead_code, non_snake_case)]
+    pub trait SystemAPI {
+        ///
+        /// Returns the status as (wifi enabled, ssid of the connected access point, bluetooth
+        /// enabled, connected bluetooth devices).\
+        /// The ssid is empty if no WiFi device is connected.
+        fn GetStatusSummary() -> (bool, Vec<u8>, bool, u32);
+        ///
+        /// Enables or disables WiFi.\
+        /// Requires the org.xetibo.reset.toggle-radio polkit action, see
+        /// dbus/org.xetibo.reset.policy.\
+        /// Unauthorized calls return the org.Xetibo.ReSet.Error.AuthorizationDenied error.
+        fn SetWifiEnabled(enabled: bool) -> bool;
+        ///
+        /// Powers all Bluetooth adapters on or off.\
+        /// Requires the org.xetibo.reset.toggle-radio polkit action, see
+        /// dbus/org.xetibo.reset.policy.\
+        /// Unauthorized calls return the org.Xetibo.ReSet.Error.AuthorizationDenied error.
+        fn SetBluetoothEnabled(enabled: bool) -> bool;
     }
 }
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use pulse::{
+    context::introspect::{SinkInfo, SourceInfo},
+    proplist::Proplist,
+};
+use re_set_lib::audio::audio_structures::{Sink, Source};
+
+use crate::utils::{load_state_file, save_state_file};
+
+const ALIAS_FILE: &str = "device_aliases.toml";
+
+/// User defined labels for audio devices, persisted within the ReSet config directory.\
+/// ```toml
+/// [sink]
+/// "pci-0000:00:1f.3" = "Desk Speakers"
+/// ```
+static DEVICE_ALIASES: Lazy<RwLock<DeviceAliases>> =
+    Lazy::new(|| RwLock::new(DeviceAliases::load()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AliasKind {
+    Sink,
+    Source,
+}
+
+impl AliasKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sink" => Some(AliasKind::Sink),
+            "source" => Some(AliasKind::Source),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            AliasKind::Sink => "sink",
+            AliasKind::Source => "source",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DeviceAliases {
+    aliases: HashMap<AliasKind, HashMap<String, String>>,
+}
+
+impl DeviceAliases {
+    fn load() -> Self {
+        let table = load_state_file(ALIAS_FILE);
+        let mut aliases = HashMap::new();
+        for kind in [AliasKind::Sink, AliasKind::Source] {
+            let mut labels = HashMap::new();
+            if let Some(entries) = table.get(kind.as_str()).and_then(|value| value.as_table()) {
+                for (identifier, label) in entries {
+                    if let Some(label) = label.as_str() {
+                        labels.insert(identifier.clone(), label.to_string());
+                    }
+                }
+            }
+            aliases.insert(kind, labels);
+        }
+        Self { aliases }
+    }
+
+    fn save(&self) -> bool {
+        let mut table = toml::Table::new();
+        for (kind, labels) in self.aliases.iter() {
+            let mut entries = toml::Table::new();
+            for (identifier, label) in labels {
+                entries.insert(identifier.clone(), toml::Value::String(label.clone()));
+            }
+            table.insert(kind.as_str().to_string(), toml::Value::Table(entries));
+        }
+        save_state_file(ALIAS_FILE, &table)
+    }
+
+    /// Identifiers are checked from most to least specific: device name, bus path, card name.
+    fn find(&self, kind: AliasKind, name: &str, proplist: &Proplist) -> Option<String> {
+        let labels = self.aliases.get(&kind)?;
+        let mut identifiers = vec![name.to_string()];
+        identifiers.extend(proplist.get_str("device.bus_path"));
+        identifiers.extend(proplist.get_str("alsa.card_name"));
+        identifiers
+            .iter()
+            .find_map(|identifier| labels.get(identifier).cloned())
+    }
+}
+
+/// Sets the label of an audio device, an empty label removes the alias.
+pub fn set_device_alias(kind: AliasKind, identifier: String, label: String) -> bool {
+    let mut aliases = DEVICE_ALIASES.write().unwrap();
+    let labels = aliases.aliases.entry(kind).or_default();
+    if label.is_empty() {
+        labels.remove(&identifier);
+    } else {
+        labels.insert(identifier, label);
+    }
+    aliases.save()
+}
+
+pub fn sink_with_alias(info: &SinkInfo) -> Sink {
+    let mut sink = Sink::from(info);
+    if let Some(alias) =
+        DEVICE_ALIASES
+            .read()
+            .unwrap()
+            .find(AliasKind::Sink, &sink.name, &info.proplist)
+    {
+        sink.alias = alias;
+    }
+    sink
+}
+
+pub fn source_with_alias(info: &SourceInfo) -> Source {
+    let mut source = Source::from(info);
+    if let Some(alias) =
+        DEVICE_ALIASES
+            .read()
+            .unwrap()
+            .find(AliasKind::Source, &source.name, &info.proplist)
+    {
+        source.alias = alias;
+    }
+    source
+}
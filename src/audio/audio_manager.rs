@@ -1,15 +1,23 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use std::{cell::RefCell, ops::Deref, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    ops::Deref,
+    rc::Rc,
+};
 
-use crossbeam::channel::{Receiver, Sender};
-use dbus::channel::Sender as dbus_sender;
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
 use dbus::nonblock::SyncConnection;
 use dbus::{Message, Path};
+use pulse::channelmap::{Map, Position};
 use pulse::context::introspect::Introspector;
 use pulse::context::subscribe::{InterestMaskSet, Operation};
-use pulse::def::Retval;
+use pulse::def::{BufferAttr, Retval, INVALID_INDEX};
 use pulse::mainloop::api::Mainloop as mainloop_api;
+use pulse::sample::{Format, Spec};
+use pulse::stream::{self, PeekResult, SeekMode, Stream};
 use pulse::volume::{ChannelVolumes, Volume};
 use pulse::{
     callbacks::ListResult,
@@ -18,11 +26,31 @@ use pulse::{
     proplist::Proplist,
 };
 use re_set_lib::audio::audio_structures::{InputStream, OutputStream, Sink, Source};
-use re_set_lib::ERROR;
 #[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
 
-use crate::utils::AUDIO;
+use super::aliases::{sink_with_alias, source_with_alias};
+use super::availability::{is_dummy_device, set_audio_devices_available};
+use super::cache::{invalidate_audio_cache, AUDIO_CACHE};
+use super::channel_test::{channel_positions, test_tone, TEST_TONE_RATE};
+use super::device_hints::{proplist_device_hints, DeviceHints};
+use super::network_sinks::{
+    network_sink_kind, tunnel_sink_arguments, NetworkSink, DISCOVERY_MODULES, TUNNEL_SINK_MODULE,
+};
+use super::noise_suppression::{
+    filtered_source_name, noise_suppression_arguments, NOISE_SUPPRESSION_MODULE,
+};
+use super::server_info::{convert_server_info, set_pipewire_sample_rate, AudioServerInfo};
+use super::stream_routing::{learned_sink, remember_stream_routing, routing_target};
+use super::stream_state::{forget_input_stream, report_input_stream, report_sink};
+use super::volume_limit::{clamp_volume, percent_to_volume, relative_volume_limit};
+use crate::hooks::{run_hooks, HookEvent};
+use crate::signals::{emit_changed_signal, emit_signal};
+use crate::utils::{get_daemon_config_value, AUDIO};
+use crate::virtual_devices::{
+    virtual_sink_arguments, virtual_source_arguments, NULL_SINK_MODULE, NULL_SOURCE_MODULE,
+};
 use crate::{AudioRequest, AudioResponse};
 
 pub struct PulseServer {
@@ -30,16 +58,98 @@ pub struct PulseServer {
     context: Rc<RefCell<Context>>,
     sender: Sender<AudioResponse>,
     receiver: Receiver<AudioRequest>,
+    connection: Arc<SyncConnection>,
+    bluetooth_route: RefCell<BluetoothAutoRoute>,
+    headset_profile: RefCell<AutoHeadsetProfile>,
+    peak_monitors: RefCell<HashMap<u32, Rc<RefCell<Stream>>>>,
+    /// Test tones of PlayChannelTest, which disconnect themselves once drained.
+    channel_tests: RefCell<Vec<Rc<RefCell<Stream>>>>,
+    /// Modules loaded with SetNoiseSuppression as source index -> (module index, name of the
+    /// filtered source).
+    noise_suppression: RefCell<HashMap<u32, (u32, String)>>,
+    /// Discovery modules loaded by DiscoverNetworkSinks.
+    discovery_modules: RefCell<Vec<u32>>,
+    /// Modules loaded with ConnectNetworkSink as host -> module index.
+    network_sinks: RefCell<HashMap<String, u32>>,
+    /// Null sink and source loaded for --virtual-devices.
+    virtual_modules: RefCell<Vec<u32>>,
+}
+
+/// Peaks are sampled at this rate per second, enough for VU meters.
+const PEAK_RATE: u32 = 25;
+
+/// State of the automatic routing to Bluetooth sinks.\
+/// The previous default sink is stored in order to restore it once the Bluetooth device
+/// disconnects.
+#[derive(Debug, Default)]
+pub struct BluetoothAutoRoute {
+    pub enabled: bool,
+    pub pending_address: Option<String>,
+    pub routed_sink: Option<String>,
+    pub previous_default: Option<String>,
+}
+
+impl BluetoothAutoRoute {
+    /// Returns true if the sink belongs to the connected device which is still waiting for its
+    /// sink.
+    pub fn is_pending_sink(&self, sink_name: &str) -> bool {
+        self.enabled
+            && self
+                .pending_address
+                .as_ref()
+                .is_some_and(|address| bluetooth_sink_matches(sink_name, address))
+    }
+
+    /// Records the route to the sink, current_default is restored once the device disconnects.
+    pub fn route(&mut self, sink_name: &str, current_default: String) {
+        // only remember the first non-bluetooth default, switching between bluetooth devices
+        // should still restore the original sink
+        if self.routed_sink.is_none() && !current_default.is_empty() && current_default != sink_name
+        {
+            self.previous_default = Some(current_default);
+        }
+        self.routed_sink = Some(sink_name.to_string());
+        self.pending_address = None;
+    }
+
+    /// Forgets the route of the disconnected device and returns the default sink to restore.
+    pub fn disconnect(&mut self, address: &str) -> Option<String> {
+        if self.pending_address.as_deref() == Some(address) {
+            self.pending_address = None;
+        }
+        let routed = self
+            .routed_sink
+            .as_ref()
+            .is_some_and(|sink| bluetooth_sink_matches(sink, address));
+        if !routed {
+            return None;
+        }
+        self.routed_sink = None;
+        self.previous_default.take()
+    }
+}
+
+/// State of the automatic switch to the headset profile of Bluetooth cards.\
+/// The previous profile of every switched card is stored in order to restore it once recording
+/// stops.
+#[derive(Debug, Default)]
+pub struct AutoHeadsetProfile {
+    pub enabled: bool,
+    pub previous_profiles: HashMap<u32, String>,
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct PulseError(pub &'static str);
 
+/// Interval in which the state of the context is checked while no requests arrive.
+const CONTEXT_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+
 impl PulseServer {
     pub fn create(
         sender: Sender<AudioResponse>,
         receiver: Receiver<AudioRequest>,
+        request_sender: Sender<AudioRequest>,
         connection: Arc<SyncConnection>,
     ) -> Result<Self, PulseError> {
         let mut proplist = Proplist::new().unwrap();
@@ -106,6 +216,7 @@ impl PulseServer {
         mask.insert(InterestMaskSet::SOURCE);
         mask.insert(InterestMaskSet::SINK_INPUT);
         mask.insert(InterestMaskSet::SOURCE_OUTPUT);
+        mask.insert(InterestMaskSet::SERVER);
 
         context.borrow_mut().subscribe(mask, |_| {});
         let connection_ref = connection.clone();
@@ -118,17 +229,38 @@ impl PulseServer {
                 let connection_source = connection_ref.clone();
                 let connection_input_stream = connection_ref.clone();
                 let connection_output_stream = connection_ref.clone();
+                let request_sender = request_sender.clone();
                 let operation = operation.unwrap();
                 let facility = facility.unwrap();
                 match facility {
                     pulse::context::subscribe::Facility::Sink => {
+                        if operation != Operation::Changed {
+                            let _ = request_sender.send(AudioRequest::AudioDevicesChanged);
+                        }
                         if operation == Operation::Removed {
                             handle_sink_removed(&connection_ref, index);
                             return;
                         }
                         introspector.get_sink_info_by_index(index, move |result| match result {
-                            ListResult::Item(sink) => {
-                                handle_sink_events(&connection_sink, Sink::from(sink), operation);
+                            ListResult::Item(info) => {
+                                let sink = sink_with_alias(info);
+                                if operation == Operation::New && sink.name.starts_with("bluez_") {
+                                    let _ = request_sender
+                                        .send(AudioRequest::BluetoothSinkAdded(sink.name.clone()));
+                                }
+                                if operation == Operation::New {
+                                    handle_device_connected(
+                                        &connection_sink,
+                                        "sink",
+                                        device_description(
+                                            &info.proplist,
+                                            info.description.as_deref(),
+                                            &sink.name,
+                                        ),
+                                        sink.index,
+                                    );
+                                }
+                                handle_sink_events(&connection_sink, sink, operation);
                             }
                             ListResult::Error => {
                                 ERROR!("Could not get sink info", ErrorLevel::PartialBreakage);
@@ -137,15 +269,31 @@ impl PulseServer {
                         });
                     }
                     pulse::context::subscribe::Facility::Source => {
+                        if operation != Operation::Changed {
+                            let _ = request_sender.send(AudioRequest::AudioDevicesChanged);
+                        }
                         if operation == Operation::Removed {
                             handle_source_removed(&connection, index);
                             return;
                         }
                         introspector.get_source_info_by_index(index, move |result| match result {
-                            ListResult::Item(source) => {
+                            ListResult::Item(info) => {
+                                // monitors appear with every sink and are no devices on their own
+                                if operation == Operation::New && info.monitor_of_sink.is_none() {
+                                    handle_device_connected(
+                                        &connection_source,
+                                        "source",
+                                        device_description(
+                                            &info.proplist,
+                                            info.description.as_deref(),
+                                            info.name.as_deref().unwrap_or_default(),
+                                        ),
+                                        info.index,
+                                    );
+                                }
                                 handle_source_events(
                                     &connection_source,
-                                    Source::from(source),
+                                    source_with_alias(info),
                                     operation,
                                 );
                             }
@@ -162,9 +310,17 @@ impl PulseServer {
                         }
                         introspector.get_sink_input_info(index, move |result| match result {
                             ListResult::Item(input_stream) => {
+                                let input_stream = InputStream::from(input_stream);
+                                if operation == Operation::New {
+                                    let _ = request_sender.send(AudioRequest::InputStreamAdded(
+                                        input_stream.index,
+                                        input_stream.application_name.clone(),
+                                        input_stream.sink_index,
+                                    ));
+                                }
                                 handle_input_stream_events(
                                     &connection_input_stream,
-                                    InputStream::from(input_stream),
+                                    input_stream,
                                     operation,
                                 );
                             }
@@ -178,6 +334,9 @@ impl PulseServer {
                         });
                     }
                     pulse::context::subscribe::Facility::SourceOutput => {
+                        if operation != Operation::Changed {
+                            let _ = request_sender.send(AudioRequest::SourceOutputsChanged);
+                        }
                         if operation == Operation::Removed {
                             handle_output_stream_removed(&connection, index);
                             return;
@@ -199,6 +358,26 @@ impl PulseServer {
                             ListResult::End => (),
                         });
                     }
+                    pulse::context::subscribe::Facility::Server => {
+                        introspector.get_server_info(|info| {
+                            let mut cache = AUDIO_CACHE.write().unwrap();
+                            let default_sink_name =
+                                info.default_sink_name.as_ref().map(|name| name.to_string());
+                            if let Some(name) = &default_sink_name {
+                                if cache.default_sink_name.as_ref() != Some(name) {
+                                    run_hooks(
+                                        HookEvent::DefaultSinkChanged,
+                                        vec![("RESET_SINK", name.clone())],
+                                    );
+                                }
+                            }
+                            cache.default_sink_name = default_sink_name;
+                            cache.default_source_name = info
+                                .default_source_name
+                                .as_ref()
+                                .map(|name| name.to_string());
+                        });
+                    }
                     _ => (),
                 }
             })));
@@ -206,23 +385,63 @@ impl PulseServer {
 
         context.borrow_mut().set_state_callback(None);
         mainloop.borrow_mut().unlock();
+        let bluetooth_route = BluetoothAutoRoute {
+            enabled: get_daemon_config_value("bluetooth_auto_route")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+            ..Default::default()
+        };
+        let headset_profile = AutoHeadsetProfile {
+            enabled: get_daemon_config_value("auto_headset_profile")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+            ..Default::default()
+        };
         Ok(Self {
             mainloop,
             context,
             sender,
             receiver,
+            connection,
+            bluetooth_route: RefCell::new(bluetooth_route),
+            headset_profile: RefCell::new(headset_profile),
+            peak_monitors: RefCell::new(HashMap::new()),
+            channel_tests: RefCell::new(Vec::new()),
+            noise_suppression: RefCell::new(HashMap::new()),
+            discovery_modules: RefCell::new(Vec::new()),
+            network_sinks: RefCell::new(HashMap::new()),
+            virtual_modules: RefCell::new(Vec::new()),
         })
     }
 
-    pub fn listen_to_messages(&mut self) {
+    /// Handles requests until the connection to the audio server is lost.\
+    /// Returns an error once the context failed or was terminated, the worker is then restarted
+    /// with a new connection.
+    pub fn listen_to_messages(&mut self) -> Result<(), PulseError> {
+        self.update_audio_devices_available();
         loop {
-            let message = self.receiver.recv();
-            if let Ok(message) = message {
-                self.handle_message(message);
+            match self.receiver.recv_timeout(CONTEXT_CHECK_INTERVAL) {
+                Ok(message) => self.handle_message(message),
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if !self.context_alive() {
+                self.mainloop.borrow_mut().stop();
+                return Err(PulseError("Lost the connection to the audio server."));
             }
         }
     }
 
+    fn context_alive(&self) -> bool {
+        self.mainloop.borrow_mut().lock();
+        let state = self.context.borrow().get_state();
+        self.mainloop.borrow_mut().unlock();
+        !matches!(
+            state,
+            pulse::context::State::Failed | pulse::context::State::Terminated
+        )
+    }
+
     pub fn handle_message(&self, message: AudioRequest) {
         match message {
             AudioRequest::ListSinks => self.get_sinks(),
@@ -255,7 +474,10 @@ impl PulseServer {
                 self.set_sink_volume(index, channels, volume)
             }
             AudioRequest::SetSinkMute(index, muted) => self.set_sink_mute(index, muted),
+            AudioRequest::SuspendSink(index, suspend) => self.suspend_sink(index, suspend),
             AudioRequest::SetDefaultSink(sink) => self.set_default_sink(sink),
+            AudioRequest::ChangeDefaultSinkVolume(step) => self.change_default_sink_volume(step),
+            AudioRequest::ToggleDefaultSinkMute => self.toggle_default_sink_mute(),
             AudioRequest::SetSourceVolume(index, channels, volume) => {
                 self.set_source_volume(index, channels, volume)
             }
@@ -265,17 +487,80 @@ impl PulseServer {
             AudioRequest::SetCardProfileOfDevice(device_index, profile_name) => {
                 self.set_card_profile_of_device(device_index, profile_name)
             }
+            AudioRequest::SetBluetoothAutoRoute(enabled) => self.set_bluetooth_auto_route(enabled),
+            AudioRequest::BluetoothDeviceConnected(address) => {
+                self.handle_bluetooth_device_connected(address)
+            }
+            AudioRequest::BluetoothDeviceDisconnected(address) => {
+                self.handle_bluetooth_device_disconnected(address)
+            }
+            AudioRequest::BluetoothSinkAdded(sink_name) => {
+                self.handle_bluetooth_sink_added(sink_name)
+            }
+            AudioRequest::InputStreamAdded(index, application, sink) => {
+                self.apply_learned_routing(index, application, sink)
+            }
+            AudioRequest::SetAutoHeadsetProfile(enabled) => self.set_auto_headset_profile(enabled),
+            AudioRequest::SourceOutputsChanged => self.handle_source_outputs_changed(),
+            AudioRequest::AudioDevicesChanged => self.update_audio_devices_available(),
+            AudioRequest::ListSourceMonitors => self.get_source_monitors(),
+            AudioRequest::CreateLoopback(source, sink, latency) => {
+                self.create_loopback(source, sink, latency)
+            }
+            AudioRequest::RemoveLoopback(index) => self.remove_loopback(index),
+            AudioRequest::SetSinkDescription(index, description) => {
+                self.set_sink_description(index, description)
+            }
+            AudioRequest::SetSourceDescription(index, description) => {
+                self.set_source_description(index, description)
+            }
+            AudioRequest::SetNoiseSuppression(index, enabled) => {
+                self.set_noise_suppression(index, enabled)
+            }
+            AudioRequest::ListNoiseSuppressedSources => self.get_noise_suppressed_sources(),
+            AudioRequest::DiscoverNetworkSinks => self.discover_network_sinks(),
+            AudioRequest::ConnectNetworkSink(host) => self.connect_network_sink(host),
+            AudioRequest::DisconnectNetworkSink(host) => self.disconnect_network_sink(host),
+            AudioRequest::StartStreamPeakMonitor(index) => self.start_stream_peak_monitor(index),
+            AudioRequest::GetSinkChannelMap(index) => self.get_sink_channel_map(index),
+            AudioRequest::GetSinkHints(index) => self.get_sink_hints(index),
+            AudioRequest::GetSourceHints(index) => self.get_source_hints(index),
+            AudioRequest::PlayChannelTest(index, position) => {
+                self.play_channel_test(index, position)
+            }
+            AudioRequest::StopStreamPeakMonitor(index) => self.stop_stream_peak_monitor(index),
+            AudioRequest::ReleasePeakMonitors(indices) => self.release_peak_monitors(indices),
+            AudioRequest::GetServerInfo => self.get_server_info(),
+            AudioRequest::GetAudioServerInfo => self.get_audio_server_info(),
+            AudioRequest::CreateVirtualDevices => self.create_virtual_devices(),
+            AudioRequest::SetDefaultSampleRate(rate) => self.set_default_sample_rate(rate),
+            AudioRequest::RefreshCache => self.refresh_cache(),
+            AudioRequest::Resync => self.resync(),
             AudioRequest::StopListener => self.stop_listener(),
         }
     }
 
     pub fn stop_listener(&self) {
+        invalidate_audio_cache();
+        self.unload_noise_suppression();
+        self.unload_network_sinks();
+        self.unload_virtual_devices();
         self.mainloop.borrow_mut().lock();
         self.mainloop.borrow_mut().stop();
         self.mainloop.borrow_mut().quit(Retval(0));
     }
 
     pub fn get_default_sink(&self) {
+        let res = match self.fetch_default_sink() {
+            Some(sink) => self.sender.send(AudioResponse::DefaultSink(sink)),
+            None => self.sender.send(AudioResponse::Error),
+        };
+        if res.is_err() {
+            ERROR!("Could not get default sink", ErrorLevel::PartialBreakage);
+        }
+    }
+
+    fn fetch_default_sink(&self) -> Option<Sink> {
         self.mainloop.borrow_mut().lock();
         let introspector = self.context.borrow().introspect();
         let sink = Rc::new(RefCell::new(Sink::default()));
@@ -283,16 +568,15 @@ impl PulseServer {
         let ml_ref = Rc::clone(&self.mainloop);
         let sink_name = self.no_lock_get_default_sink_name(&introspector);
         if sink_name.borrow().is_empty() {
-            let _ = self.sender.send(AudioResponse::Error);
             self.mainloop.borrow_mut().unlock();
-            return;
+            return None;
         }
         let result =
             introspector.get_sink_info_by_name(
                 sink_name.take().as_str(),
                 move |result| match result {
                     ListResult::Item(item) => {
-                        sink_ref.replace(item.into());
+                        sink_ref.replace(sink_with_alias(item));
                     }
                     ListResult::Error => unsafe {
                         ERROR!("Could not get sink info", ErrorLevel::PartialBreakage);
@@ -306,11 +590,36 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
-        let res = self.sender.send(AudioResponse::DefaultSink(sink.take()));
-        if res.is_err() {
-            ERROR!("Could not get default sink", ErrorLevel::PartialBreakage);
-        }
         self.mainloop.borrow_mut().unlock();
+        Some(sink.take())
+    }
+
+    /// Changes the volume of the default sink by step percent, clamped to the relative volume
+    /// limit. Volumes already above the limit are not lowered by increasing them.\
+    /// Responds with the updated default sink.
+    pub fn change_default_sink_volume(&self, step: i32) {
+        let Some(mut sink) = self.fetch_default_sink() else {
+            let _ = self.sender.send(AudioResponse::Error);
+            return;
+        };
+        let current = sink.volume.iter().max().copied().unwrap_or(0) as i64;
+        let upper = (percent_to_volume(relative_volume_limit()) as i64).max(current);
+        let step = step as i64 * Volume::NORMAL.0 as i64 / 100;
+        let volume = clamp_volume((current + step).clamp(0, upper) as u32);
+        self.set_sink_volume(sink.index, sink.channels, volume);
+        sink.volume = vec![volume; sink.channels as usize];
+        let _ = self.sender.send(AudioResponse::DefaultSink(sink));
+    }
+
+    /// Inverts the mute state of the default sink and responds with the updated default sink.
+    pub fn toggle_default_sink_mute(&self) {
+        let Some(mut sink) = self.fetch_default_sink() else {
+            let _ = self.sender.send(AudioResponse::Error);
+            return;
+        };
+        sink.muted = !sink.muted;
+        self.set_sink_mute(sink.index, sink.muted);
+        let _ = self.sender.send(AudioResponse::DefaultSink(sink));
     }
 
     pub fn get_default_sink_name(&self) {
@@ -340,6 +649,77 @@ impl PulseServer {
         self.mainloop.borrow_mut().unlock();
     }
 
+    /// Introspects all cached objects again, e.g. after the cache missed events while the
+    /// daemon was suspended.
+    pub fn refresh_cache(&self) {
+        self.list_sinks();
+        self.list_sources();
+        self.list_input_streams();
+        self.list_output_streams();
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        self.no_lock_get_default_sink_name(&introspector);
+        self.no_lock_get_default_source_name(&introspector);
+        self.mainloop.borrow_mut().unlock();
+        let _ = self.sender.send(AudioResponse::CacheRefreshed);
+    }
+
+    /// Sends the name and version of the audio server, e.g.
+    /// "PulseAudio (on PipeWire 1.0.5) 15.0.0".
+    pub fn get_server_info(&self) {
+        let _ = self
+            .sender
+            .send(AudioResponse::ServerInfo(self.fetch_server_info()));
+    }
+
+    fn fetch_server_info(&self) -> String {
+        let (name, version, ..) = self.fetch_audio_server_info();
+        format!("{} {}", name, version)
+    }
+
+    pub fn get_audio_server_info(&self) {
+        let _ = self.sender.send(AudioResponse::AudioServerInfo(
+            self.fetch_audio_server_info(),
+        ));
+    }
+
+    /// Forces the sample rate of the audio graph, 0 lets the server choose again.
+    pub fn set_default_sample_rate(&self, rate: u32) {
+        if !self.fetch_server_info().contains("PipeWire") {
+            let _ = self.sender.send(AudioResponse::DefaultSampleRate(None));
+            return;
+        }
+        let changed = set_pipewire_sample_rate(rate);
+        if !changed {
+            ERROR!(
+                format!("Could not set the default sample rate to {}", rate),
+                ErrorLevel::Recoverable
+            );
+        }
+        let _ = self
+            .sender
+            .send(AudioResponse::DefaultSampleRate(Some(changed)));
+    }
+
+    fn fetch_audio_server_info(&self) -> AudioServerInfo {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let server_info = Rc::new(RefCell::new(AudioServerInfo::default()));
+        let server_info_ref = server_info.clone();
+        let result = introspector.get_server_info(move |result| {
+            *server_info_ref.borrow_mut() = convert_server_info(result);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        server_info.take()
+    }
+
     pub fn no_lock_get_default_sink_name(
         &self,
         introspector: &Introspector,
@@ -359,6 +739,9 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
+        if !sink_name.borrow().is_empty() {
+            AUDIO_CACHE.write().unwrap().default_sink_name = Some(sink_name.borrow().clone());
+        }
         sink_name
     }
 
@@ -408,6 +791,9 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
+        if !source_name.borrow().is_empty() {
+            AUDIO_CACHE.write().unwrap().default_source_name = Some(source_name.borrow().clone());
+        }
         source_name
     }
 
@@ -427,7 +813,7 @@ impl PulseServer {
             introspector.get_source_info_by_name(source_name.take().as_str(), move |result| {
                 match result {
                     ListResult::Item(item) => {
-                        source_ref.replace(item.into());
+                        source_ref.replace(source_with_alias(item));
                     }
                     ListResult::Error => unsafe {
                         ERROR!("Could not get default source", ErrorLevel::PartialBreakage);
@@ -451,6 +837,11 @@ impl PulseServer {
     }
 
     pub fn get_sinks(&self) {
+        let sinks = self.list_sinks();
+        let _ = self.sender.send(AudioResponse::Sinks(sinks));
+    }
+
+    fn list_sinks(&self) -> Vec<Sink> {
         self.mainloop.borrow_mut().lock();
         let introspector = self.context.borrow().introspect();
         let sinks = Rc::new(RefCell::new(Vec::new()));
@@ -458,7 +849,7 @@ impl PulseServer {
         let ml_ref = Rc::clone(&self.mainloop);
         let result = introspector.get_sink_info_list(move |result| match result {
             ListResult::Item(item) => {
-                sinks_ref.borrow_mut().push(item.into());
+                sinks_ref.borrow_mut().push(sink_with_alias(item));
             }
             ListResult::Error => unsafe {
                 ERROR!("Could not get sinks", ErrorLevel::PartialBreakage);
@@ -471,11 +862,33 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
-        let _ = self.sender.send(AudioResponse::Sinks(sinks.take()));
+        // still locked, events of the subscribe callback can only be applied afterwards
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .sinks
+            .replace(&sinks.borrow(), |sink| sink.index);
         self.mainloop.borrow_mut().unlock();
+        sinks.take()
+    }
+
+    /// Checks whether any sink or source besides the dummy output and monitors exists.
+    pub fn update_audio_devices_available(&self) {
+        let sinks = self.list_sinks();
+        let sources = self.list_sources();
+        let available = sinks.iter().any(|sink| !is_dummy_device(&sink.name))
+            || sources
+                .iter()
+                .any(|source| !source.name.ends_with(".monitor") && !is_dummy_device(&source.name));
+        set_audio_devices_available(&self.connection, available);
     }
 
     pub fn get_sources(&self) {
+        let sources = self.list_sources();
+        let _ = self.sender.send(AudioResponse::Sources(sources));
+    }
+
+    fn list_sources(&self) -> Vec<Source> {
         self.mainloop.borrow_mut().lock();
         let introspector = self.context.borrow().introspect();
         let sources: Rc<RefCell<Vec<Source>>> = Rc::new(RefCell::new(Vec::new()));
@@ -483,7 +896,7 @@ impl PulseServer {
         let ml_ref = Rc::clone(&self.mainloop);
         let result = introspector.get_source_info_list(move |result| match result {
             ListResult::Item(item) => {
-                sources_ref.borrow_mut().push(item.into());
+                sources_ref.borrow_mut().push(source_with_alias(item));
             }
             ListResult::Error => unsafe {
                 ERROR!("Could not get sources", ErrorLevel::PartialBreakage);
@@ -496,11 +909,18 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
-        let _ = self.sender.send(AudioResponse::Sources(sources.take()));
+        // still locked, events of the subscribe callback can only be applied afterwards
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .sources
+            .replace(&sources.borrow(), |source| source.index);
         self.mainloop.borrow_mut().unlock();
+        sources.take()
     }
 
     pub fn set_sink_volume(&self, index: u32, channels: u16, volume: u32) {
+        let volume = clamp_volume(volume);
         self.mainloop.borrow_mut().lock();
         let mut introspector = self.context.borrow_mut().introspect();
         let mut channel_volume = ChannelVolumes::default();
@@ -534,7 +954,32 @@ impl PulseServer {
         self.mainloop.borrow_mut().unlock();
     }
 
+    /// Suspends or resumes a sink, the new state is reported with SinkChanged.
+    pub fn suspend_sink(&self, index: u32, suspend: bool) {
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let success = Rc::new(Cell::new(false));
+        let success_ref = success.clone();
+        let result = introspector.suspend_sink_by_index(
+            index,
+            suspend,
+            Some(Box::new(move |res| unsafe {
+                success_ref.set(res);
+                (*ml_ref.as_ptr()).signal(false);
+            })),
+        );
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        let _ = self
+            .sender
+            .send(AudioResponse::SinkSuspended(success.get()));
+    }
+
     pub fn set_source_volume(&self, index: u32, channels: u16, volume: u32) {
+        let volume = clamp_volume(volume);
         self.mainloop.borrow_mut().lock();
         let mut introspector = self.context.borrow_mut().introspect();
         let mut channel_volume = ChannelVolumes::default();
@@ -587,7 +1032,7 @@ impl PulseServer {
         let introspector = context.introspect();
         let result = introspector.get_sink_info_by_name(&sink_name, move |result| match result {
             ListResult::Item(item) => {
-                sink_ref.replace(item.into());
+                sink_ref.replace(sink_with_alias(item));
             }
             ListResult::Error => unsafe {
                 ERROR!("Could not set default sink", ErrorLevel::PartialBreakage);
@@ -621,7 +1066,7 @@ impl PulseServer {
         let result =
             introspector.get_source_info_by_name(&source_name, move |result| match result {
                 ListResult::Item(item) => {
-                    source_ref.replace(item.into());
+                    source_ref.replace(source_with_alias(item));
                 }
                 ListResult::Error => unsafe {
                     ERROR!("Could not set default source", ErrorLevel::PartialBreakage);
@@ -641,6 +1086,11 @@ impl PulseServer {
     }
 
     pub fn get_input_streams(&self) {
+        let input_streams = self.list_input_streams();
+        let _ = self.sender.send(AudioResponse::InputStreams(input_streams));
+    }
+
+    fn list_input_streams(&self) -> Vec<InputStream> {
         self.mainloop.borrow_mut().lock();
         let introspector = self.context.borrow().introspect();
         let input_streams = Rc::new(RefCell::new(Vec::new()));
@@ -661,30 +1111,88 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
-        let _ = self
-            .sender
-            .send(AudioResponse::InputStreams(input_streams.take()));
+        // still locked, events of the subscribe callback can only be applied afterwards
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .input_streams
+            .replace(&input_streams.borrow(), |input_stream| input_stream.index);
         self.mainloop.borrow_mut().unlock();
+        input_streams.take()
     }
 
+    /// Moves the stream and remembers the sink for the application of the stream.
     pub fn set_sink_of_input_stream(&self, input_stream: u32, sink: u32) {
+        if !self.move_input_stream(input_stream, sink) {
+            return;
+        }
+        let application = self
+            .list_input_streams()
+            .into_iter()
+            .find(|stream| stream.index == input_stream)
+            .map(|stream| stream.application_name);
+        let sink_name = self
+            .list_sinks()
+            .into_iter()
+            .find(|candidate| candidate.index == sink)
+            .map(|candidate| candidate.name);
+        if let (Some(application), Some(sink_name)) = (application, sink_name) {
+            remember_stream_routing(&application, &sink_name);
+        }
+    }
+
+    /// Moves a new stream to the sink its application was last routed to.
+    fn apply_learned_routing(&self, index: u32, application: String, current_sink: u32) {
+        let learned = learned_sink(&application);
+        if learned.is_none() {
+            return;
+        }
+        let learned = learned.unwrap();
+        let sinks: Vec<(u32, String)> = self
+            .list_sinks()
+            .into_iter()
+            .map(|sink| (sink.index, sink.name))
+            .collect();
+        if let Some(sink) = routing_target(&learned, current_sink, &sinks) {
+            LOG!(format!(
+                "Routing {} to learned sink {}",
+                application, learned
+            ));
+            self.move_input_stream(index, sink);
+        }
+    }
+
+    fn move_input_stream(&self, input_stream: u32, sink: u32) -> bool {
+        // moving kills the peak monitor, it has to follow the stream to its new sink
+        let monitor = self.peak_monitors.borrow_mut().remove(&input_stream);
         self.mainloop.borrow_mut().lock();
+        if let Some(monitor) = &monitor {
+            stop_peak_stream(monitor);
+        }
         let mut introspector = self.context.borrow_mut().introspect();
         let ml_ref = Rc::clone(&self.mainloop);
+        let success = Rc::new(Cell::new(false));
+        let success_ref = success.clone();
         let result = introspector.move_sink_input_by_index(
             input_stream,
             sink,
-            Some(Box::new(move |error| unsafe {
-                (*ml_ref.as_ptr()).signal(!error);
+            Some(Box::new(move |res| unsafe {
+                success_ref.set(res);
+                (*ml_ref.as_ptr()).signal(!res);
             })),
         );
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
         self.mainloop.borrow_mut().unlock();
+        if monitor.is_some() && self.create_peak_monitor(input_stream).is_none() {
+            handle_peak_monitor_stopped(&self.connection, input_stream);
+        }
+        success.get()
     }
 
     pub fn set_volume_of_input_stream(&self, index: u32, channels: u16, volume: u32) {
+        let volume = clamp_volume(volume);
         self.mainloop.borrow_mut().lock();
         let ml_ref = Rc::clone(&self.mainloop);
         let mut introspector = self.context.borrow_mut().introspect();
@@ -722,6 +1230,13 @@ impl PulseServer {
     }
 
     pub fn get_output_streams(&self) {
+        let output_streams = self.list_output_streams();
+        let _ = self
+            .sender
+            .send(AudioResponse::OutputStreams(output_streams));
+    }
+
+    fn list_output_streams(&self) -> Vec<OutputStream> {
         self.mainloop.borrow_mut().lock();
         let introspector = self.context.borrow().introspect();
         let output_streams = Rc::new(RefCell::new(Vec::new()));
@@ -742,10 +1257,16 @@ impl PulseServer {
         while result.get_state() != pulse::operation::State::Done {
             self.mainloop.borrow_mut().wait();
         }
-        let _ = self
-            .sender
-            .send(AudioResponse::OutputStreams(output_streams.take()));
+        // still locked, events of the subscribe callback can only be applied afterwards
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .output_streams
+            .replace(&output_streams.borrow(), |output_stream| {
+                output_stream.index
+            });
         self.mainloop.borrow_mut().unlock();
+        output_streams.take()
     }
 
     pub fn set_source_of_output_stream(&self, output_stream: u32, source: u32) {
@@ -766,6 +1287,7 @@ impl PulseServer {
     }
 
     pub fn set_volume_of_output_stream(&self, index: u32, channels: u16, volume: u32) {
+        let volume = clamp_volume(volume);
         self.mainloop.borrow_mut().lock();
         let mut introspector = self.context.borrow_mut().introspect();
         let mut channel_volume = ChannelVolumes::default();
@@ -843,139 +1365,1364 @@ impl PulseServer {
         }
         self.mainloop.borrow_mut().unlock();
     }
-}
 
-fn handle_sink_events(conn: &Arc<SyncConnection>, sink: Sink, operation: Operation) {
-    match operation {
-        Operation::New => {
-            let msg = Message::signal(
-                &Path::from(DBUS_PATH!()),
-                &AUDIO.into(),
-                &"SinkAdded".into(),
-            )
-            .append1(sink);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
+    /// Responds with (source index, is_monitor, index of the monitored sink) for every source.\
+    /// The sink index is INVALID_INDEX (u32::MAX) for regular sources.
+    pub fn get_source_monitors(&self) {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let monitors = Rc::new(RefCell::new(Vec::new()));
+        let monitors_ref = monitors.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_source_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                monitors_ref.borrow_mut().push((
+                    item.index,
+                    item.monitor_of_sink.is_some(),
+                    item.monitor_of_sink.unwrap_or(INVALID_INDEX),
+                ));
             }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get sources", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
         }
-        Operation::Changed => {
-            let msg = Message::signal(
-                &Path::from(DBUS_PATH!()),
-                &AUDIO.into(),
-                &"SinkChanged".into(),
-            )
-            .append1(sink);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
+        self.mainloop.borrow_mut().unlock();
+        let _ = self
+            .sender
+            .send(AudioResponse::SourceMonitors(monitors.take()));
+    }
+
+    /// Loads module-loopback from the source to the sink and responds with the module index.\
+    /// A latency of 0 uses the default latency of the module.
+    pub fn create_loopback(&self, source_index: u32, sink_index: u32, latency_ms: u32) {
+        let source = self
+            .list_sources()
+            .into_iter()
+            .find(|source| source.index == source_index);
+        let sink = self
+            .list_sinks()
+            .into_iter()
+            .find(|sink| sink.index == sink_index);
+        if source.is_none() || sink.is_none() {
+            let _ = self.sender.send(AudioResponse::Error);
+            return;
+        }
+        let mut argument = format!(
+            "source={} sink={} source_dont_move=true sink_dont_move=true",
+            source.unwrap().name,
+            sink.unwrap().name
+        );
+        if latency_ms > 0 {
+            argument.push_str(&format!(" latency_msec={}", latency_ms));
+        }
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let module = Rc::new(RefCell::new(INVALID_INDEX));
+        let module_ref = module.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.load_module("module-loopback", &argument, move |index| {
+            module_ref.replace(index);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(false);
             }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
         }
-        Operation::Removed => (),
+        self.mainloop.borrow_mut().unlock();
+        let module = module.take();
+        if module == INVALID_INDEX {
+            ERROR!(
+                format!("Could not load loopback with arguments {}", argument),
+                ErrorLevel::Recoverable
+            );
+            let _ = self.sender.send(AudioResponse::Error);
+            return;
+        }
+        let _ = self.sender.send(AudioResponse::Loopback(module));
     }
-}
 
-fn handle_sink_removed(conn: &Arc<SyncConnection>, index: u32) {
-    let msg = Message::signal(
-        &Path::from(DBUS_PATH!()),
-        &AUDIO.into(),
-        &"SinkRemoved".into(),
-    )
-    .append1(index);
-    let res = conn.send(msg);
-    if res.is_err() {
-        ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
+    /// Starts a peak detecting record stream on the monitor of the sink the input stream plays
+    /// on, restricted to the input stream.\
+    /// Responds with false if the input stream does not exist.
+    pub fn start_stream_peak_monitor(&self, index: u32) {
+        self.remove_stopped_peak_monitors();
+        if self.peak_monitors.borrow().contains_key(&index) {
+            let _ = self.sender.send(AudioResponse::PeakMonitor(true));
+            return;
+        }
+        let started = self.create_peak_monitor(index).is_some();
+        let _ = self.sender.send(AudioResponse::PeakMonitor(started));
     }
-}
 
-fn handle_source_events(conn: &Arc<SyncConnection>, source: Source, operation: Operation) {
-    match operation {
-        Operation::New => {
-            let msg = Message::signal(
-                &Path::from(DBUS_PATH!()),
-                &AUDIO.into(),
-                &"SourceAdded".into(),
-            )
-            .append1(source);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
-        }
-        Operation::Changed => {
-            let msg = Message::signal(
-                &Path::from(DBUS_PATH!()),
-                &AUDIO.into(),
-                &"SourceChanged".into(),
-            )
-            .append1(source);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
+    /// Responds with false if no peak monitor was running for the input stream.
+    pub fn stop_stream_peak_monitor(&self, index: u32) {
+        self.remove_stopped_peak_monitors();
+        let stopped = self.remove_peak_monitor(index);
+        let _ = self.sender.send(AudioResponse::PeakMonitor(stopped));
+    }
+
+    /// Stops the peak monitors of a client that disconnected, nothing is sent back.
+    pub fn release_peak_monitors(&self, indices: Vec<u32>) {
+        self.remove_stopped_peak_monitors();
+        for index in indices {
+            self.remove_peak_monitor(index);
         }
-        Operation::Removed => (),
     }
-}
 
-fn handle_source_removed(conn: &Arc<SyncConnection>, index: u32) {
-    let msg = Message::signal(
-        &Path::from(DBUS_PATH!()),
-        &AUDIO.into(),
-        &"SourceRemoved".into(),
-    )
-    .append1(index);
-    let res = conn.send(msg);
-    if res.is_err() {
-        ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
+    fn remove_peak_monitor(&self, index: u32) -> bool {
+        let stream = self.peak_monitors.borrow_mut().remove(&index);
+        if let Some(stream) = &stream {
+            self.mainloop.borrow_mut().lock();
+            stop_peak_stream(stream);
+            self.mainloop.borrow_mut().unlock();
+        }
+        stream.is_some()
     }
-}
 
-fn handle_input_stream_events(
-    conn: &Arc<SyncConnection>,
-    input_stream: InputStream,
-    operation: Operation,
-) {
-    match operation {
-        Operation::New => {
-            let msg = Message::signal(
-                &Path::from(DBUS_PATH!()),
+    fn create_peak_monitor(&self, index: u32) -> Option<()> {
+        let sink = self
+            .list_input_streams()
+            .into_iter()
+            .find(|input_stream| input_stream.index == index)?
+            .sink_index;
+        let monitor = self.get_monitor_of_sink(sink)?;
+        let spec = Spec {
+            format: Format::FLOAT32NE,
+            channels: 1,
+            rate: PEAK_RATE,
+        };
+        // only a single sample is required per read
+        let attributes = BufferAttr {
+            maxlength: u32::MAX,
+            tlength: u32::MAX,
+            prebuf: u32::MAX,
+            minreq: u32::MAX,
+            fragsize: std::mem::size_of::<f32>() as u32,
+        };
+        self.mainloop.borrow_mut().lock();
+        let stream = Stream::new(
+            &mut self.context.borrow_mut(),
+            "ReSet peak monitor",
+            &spec,
+            None,
+        );
+        let Some(mut stream) = stream else {
+            self.mainloop.borrow_mut().unlock();
+            ERROR!("Could not create peak monitor", ErrorLevel::Recoverable);
+            return None;
+        };
+        let res = stream.set_monitor_stream(index).and_then(|_| {
+            stream.connect_record(
+                Some(&monitor.to_string()),
+                Some(&attributes),
+                stream::FlagSet::DONT_MOVE
+                    | stream::FlagSet::PEAK_DETECT
+                    | stream::FlagSet::ADJUST_LATENCY,
+            )
+        });
+        if let Err(_error) = res {
+            self.mainloop.borrow_mut().unlock();
+            ERROR!(
+                format!("Could not connect peak monitor: {}", _error),
+                ErrorLevel::Recoverable
+            );
+            return None;
+        }
+        let stream = Rc::new(RefCell::new(stream));
+        let stream_ref = stream.clone();
+        let connection = self.connection.clone();
+        stream
+            .borrow_mut()
+            .set_read_callback(Some(Box::new(move |_| {
+                let stream = unsafe { &mut *stream_ref.as_ptr() };
+                let peak = match stream.peek() {
+                    Ok(PeekResult::Data(data)) => data
+                        .chunks_exact(std::mem::size_of::<f32>())
+                        .last()
+                        .map(|sample| f32::from_ne_bytes(sample.try_into().unwrap())),
+                    Ok(PeekResult::Hole(_)) => None,
+                    _ => return,
+                };
+                let _ = stream.discard();
+                if let Some(peak) = peak {
+                    handle_stream_peak(&connection, index, peak);
+                }
+            })));
+        let stream_ref = stream.clone();
+        let connection = self.connection.clone();
+        stream
+            .borrow_mut()
+            .set_state_callback(Some(Box::new(move || {
+                let state = unsafe { (*stream_ref.as_ptr()).get_state() };
+                if state == stream::State::Failed || state == stream::State::Terminated {
+                    handle_peak_monitor_stopped(&connection, index);
+                }
+            })));
+        self.peak_monitors.borrow_mut().insert(index, stream);
+        self.mainloop.borrow_mut().unlock();
+        Some(())
+    }
+
+    /// Removes peak monitors which were killed by the server, e.g. as their input stream was
+    /// removed.
+    fn remove_stopped_peak_monitors(&self) {
+        self.mainloop.borrow_mut().lock();
+        self.peak_monitors.borrow_mut().retain(|_, stream| {
+            let state = stream.borrow().get_state();
+            let running = state != stream::State::Failed && state != stream::State::Terminated;
+            if !running {
+                stop_peak_stream(stream);
+            }
+            running
+        });
+        self.mainloop.borrow_mut().unlock();
+    }
+
+    /// Responds with the channel positions of the sink, unknown sinks are an error.
+    pub fn get_sink_channel_map(&self, index: u32) {
+        let response = match self.fetch_sink_channel_map(index) {
+            Some(map) => AudioResponse::ChannelMap(channel_positions(&map)),
+            None => AudioResponse::Error,
+        };
+        let _ = self.sender.send(response);
+    }
+
+    /// Responds with the icon and form factor hints of the sink, unknown sinks are an error.
+    pub fn get_sink_hints(&self, index: u32) {
+        let response = match self.fetch_sink_hints(index) {
+            Some(hints) => AudioResponse::DeviceHints(hints),
+            None => AudioResponse::Error,
+        };
+        let _ = self.sender.send(response);
+    }
+
+    /// Responds with the icon and form factor hints of the source, unknown sources are an error.
+    pub fn get_source_hints(&self, index: u32) {
+        let response = match self.fetch_source_hints(index) {
+            Some(hints) => AudioResponse::DeviceHints(hints),
+            None => AudioResponse::Error,
+        };
+        let _ = self.sender.send(response);
+    }
+
+    /// Plays a test tone on the speaker at the position, responds with false if the sink does not
+    /// exist or has no such position.
+    pub fn play_channel_test(&self, index: u32, position: Position) {
+        self.remove_finished_channel_tests();
+        let started = self.create_channel_test(index, position).is_some();
+        let _ = self.sender.send(AudioResponse::ChannelTest(started));
+    }
+
+    fn create_channel_test(&self, index: u32, position: Position) -> Option<()> {
+        let map = self.fetch_sink_channel_map(index)?;
+        let channel = map.get().iter().position(|current| *current == position)?;
+        let channels = map.get().len();
+        let spec = Spec {
+            format: Format::FLOAT32NE,
+            channels: channels as u8,
+            rate: TEST_TONE_RATE,
+        };
+        let tone = test_tone(channels, channel, position == Position::Lfe, TEST_TONE_RATE);
+        // the whole tone is buffered before playback starts
+        let attributes = BufferAttr {
+            maxlength: u32::MAX,
+            tlength: tone.len() as u32,
+            prebuf: u32::MAX,
+            minreq: u32::MAX,
+            fragsize: u32::MAX,
+        };
+        self.mainloop.borrow_mut().lock();
+        let stream = Stream::new(
+            &mut self.context.borrow_mut(),
+            "ReSet channel test",
+            &spec,
+            Some(&map),
+        );
+        let Some(mut stream) = stream else {
+            self.mainloop.borrow_mut().unlock();
+            ERROR!("Could not create channel test", ErrorLevel::Recoverable);
+            return None;
+        };
+        // the channel map of the sink is used, remixing would spread the tone to other speakers
+        let res = stream.connect_playback(
+            Some(&index.to_string()),
+            Some(&attributes),
+            stream::FlagSet::DONT_MOVE | stream::FlagSet::NO_REMIX_CHANNELS,
+            None,
+            None,
+        );
+        if let Err(_error) = res {
+            self.mainloop.borrow_mut().unlock();
+            ERROR!(
+                format!("Could not connect channel test: {}", _error),
+                ErrorLevel::Recoverable
+            );
+            return None;
+        }
+        let stream = Rc::new(RefCell::new(stream));
+        let stream_ref = stream.clone();
+        let mut tone = Some(tone);
+        stream
+            .borrow_mut()
+            .set_state_callback(Some(Box::new(move || {
+                let stream = unsafe { &mut *stream_ref.as_ptr() };
+                if stream.get_state() != stream::State::Ready {
+                    return;
+                }
+                let Some(tone) = tone.take() else {
+                    return;
+                };
+                if stream.write_copy(&tone, 0, SeekMode::Relative).is_err() {
+                    stream.set_state_callback(None);
+                    let _ = stream.disconnect();
+                    return;
+                }
+                let stream_ref = stream_ref.clone();
+                stream.drain(Some(Box::new(move |_| {
+                    let stream = unsafe { &mut *stream_ref.as_ptr() };
+                    stream.set_state_callback(None);
+                    let _ = stream.disconnect();
+                })));
+            })));
+        self.mainloop.borrow_mut().unlock();
+        self.channel_tests.borrow_mut().push(stream);
+        LOG!(format!(
+            "Playing channel test on {} of sink {}",
+            Position::to_string(position).unwrap_or_default(),
+            index
+        ));
+        Some(())
+    }
+
+    fn remove_finished_channel_tests(&self) {
+        self.mainloop.borrow_mut().lock();
+        self.channel_tests.borrow_mut().retain(|stream| {
+            let state = stream.borrow().get_state();
+            let running = state != stream::State::Failed && state != stream::State::Terminated;
+            if !running {
+                // the callback holds a reference to the stream
+                stream.borrow_mut().set_state_callback(None);
+            }
+            running
+        });
+        self.mainloop.borrow_mut().unlock();
+    }
+
+    fn fetch_sink_channel_map(&self, sink: u32) -> Option<Map> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let map = Rc::new(RefCell::new(None));
+        let map_ref = map.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_sink_info_by_index(sink, move |result| match result {
+            ListResult::Item(item) => {
+                map_ref.replace(Some(item.channel_map));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        map.take()
+    }
+
+    fn fetch_sink_hints(&self, sink: u32) -> Option<DeviceHints> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let hints = Rc::new(RefCell::new(None));
+        let hints_ref = hints.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_sink_info_by_index(sink, move |result| match result {
+            ListResult::Item(item) => {
+                hints_ref.replace(Some(proplist_device_hints(&item.proplist)));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        hints.take()
+    }
+
+    fn fetch_source_hints(&self, source: u32) -> Option<DeviceHints> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let hints = Rc::new(RefCell::new(None));
+        let hints_ref = hints.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_source_info_by_index(source, move |result| match result {
+            ListResult::Item(item) => {
+                hints_ref.replace(Some(proplist_device_hints(&item.proplist)));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        hints.take()
+    }
+
+    fn get_monitor_of_sink(&self, sink: u32) -> Option<u32> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let monitor = Rc::new(RefCell::new(None));
+        let monitor_ref = monitor.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_sink_info_by_index(sink, move |result| match result {
+            ListResult::Item(item) => {
+                monitor_ref.replace(Some(item.monitor_source));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        monitor.take()
+    }
+
+    /// Unloads a loopback module, other modules are left untouched.
+    pub fn remove_loopback(&self, index: u32) {
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let is_loopback = Rc::new(RefCell::new(false));
+        let is_loopback_ref = is_loopback.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_module_info(index, move |result| match result {
+            ListResult::Item(item) => {
+                is_loopback_ref.replace(item.name.as_deref() == Some("module-loopback"));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        if !is_loopback.take() {
+            self.mainloop.borrow_mut().unlock();
+            let _ = self.sender.send(AudioResponse::LoopbackRemoved(false));
+            return;
+        }
+        let removed = Rc::new(RefCell::new(false));
+        let removed_ref = removed.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.unload_module(index, move |success| {
+            removed_ref.replace(success);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(!success);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        let _ = self
+            .sender
+            .send(AudioResponse::LoopbackRemoved(removed.take()));
+    }
+
+    /// Loads or unloads the noise suppression of a source, responds with false if the source
+    /// does not exist or the module could not be loaded.
+    pub fn set_noise_suppression(&self, index: u32, enabled: bool) {
+        self.remove_unloaded_noise_suppression();
+        if !enabled {
+            let entry = self.noise_suppression.borrow_mut().remove(&index);
+            let removed = entry.is_none_or(|(module, _)| self.unload_module(module));
+            let _ = self.sender.send(AudioResponse::NoiseSuppression(removed));
+            return;
+        }
+        if self.noise_suppression.borrow().contains_key(&index) {
+            let _ = self.sender.send(AudioResponse::NoiseSuppression(true));
+            return;
+        }
+        let source = self
+            .list_sources()
+            .into_iter()
+            .find(|source| source.index == index);
+        let filtered_sources: Vec<String> = self
+            .noise_suppression
+            .borrow()
+            .values()
+            .map(|(_, name)| name.clone())
+            .collect();
+        // suppressing the filtered source again only adds latency
+        let Some(source) = source.filter(|source| !filtered_sources.contains(&source.name)) else {
+            let _ = self.sender.send(AudioResponse::NoiseSuppression(false));
+            return;
+        };
+        let pipewire = self.fetch_server_info().contains("PipeWire");
+        let argument = noise_suppression_arguments(&source.name, &source.alias, pipewire);
+        let module = self.load_module(NOISE_SUPPRESSION_MODULE, &argument);
+        if module == INVALID_INDEX {
+            ERROR!(
+                format!(
+                    "Could not load noise suppression with arguments {}",
+                    argument
+                ),
+                ErrorLevel::Recoverable
+            );
+            let _ = self.sender.send(AudioResponse::NoiseSuppression(false));
+            return;
+        }
+        self.noise_suppression
+            .borrow_mut()
+            .insert(index, (module, filtered_source_name(&source.name)));
+        let _ = self.sender.send(AudioResponse::NoiseSuppression(true));
+    }
+
+    pub fn get_noise_suppressed_sources(&self) {
+        self.remove_unloaded_noise_suppression();
+        let sources = self.list_sources();
+        let suppressed = self
+            .noise_suppression
+            .borrow()
+            .iter()
+            .filter_map(|(index, (_, name))| {
+                let filtered = sources.iter().find(|source| &source.name == name)?;
+                Some((*index, filtered.index))
+            })
+            .collect();
+        let _ = self
+            .sender
+            .send(AudioResponse::NoiseSuppressedSources(suppressed));
+    }
+
+    /// Forgets modules which were unloaded by the audio server, e.g. after the source was
+    /// removed.
+    fn remove_unloaded_noise_suppression(&self) {
+        let loaded: Vec<u32> = self
+            .noise_suppression
+            .borrow()
+            .values()
+            .map(|(module, _)| *module)
+            .filter(|module| self.module_name(*module).as_deref() == Some(NOISE_SUPPRESSION_MODULE))
+            .collect();
+        self.noise_suppression
+            .borrow_mut()
+            .retain(|_, (module, _)| loaded.contains(module));
+    }
+
+    /// Unloads all noise suppression modules, as they would otherwise outlive the daemon.
+    fn unload_noise_suppression(&self) {
+        self.remove_unloaded_noise_suppression();
+        let modules: Vec<(u32, (u32, String))> =
+            self.noise_suppression.borrow_mut().drain().collect();
+        for (_, (module, _)) in modules {
+            self.unload_module(module);
+        }
+    }
+
+    /// Loads the discovery modules once and responds with the network sinks found so far, sinks
+    /// discovered later are reported with SinkAdded.
+    pub fn discover_network_sinks(&self) {
+        let loaded = self
+            .discovery_modules
+            .borrow()
+            .iter()
+            .any(|module| self.module_name(*module).is_some());
+        if !loaded {
+            let modules: Vec<u32> = DISCOVERY_MODULES
+                .iter()
+                .map(|name| (name, self.load_module(name, "")))
+                .filter_map(|(_name, module)| {
+                    if module == INVALID_INDEX {
+                        ERROR!(
+                            format!("Could not load discovery module {}", _name),
+                            ErrorLevel::Recoverable
+                        );
+                        return None;
+                    }
+                    Some(module)
+                })
+                .collect();
+            self.discovery_modules.replace(modules);
+        }
+        let _ = self
+            .sender
+            .send(AudioResponse::NetworkSinks(self.list_network_sinks()));
+    }
+
+    fn list_network_sinks(&self) -> Vec<NetworkSink> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let sinks = Rc::new(RefCell::new(Vec::new()));
+        let sinks_ref = sinks.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_sink_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                let name = item.name.as_ref().map(|name| name.to_string());
+                let name = name.unwrap_or_default();
+                let driver = item.driver.as_ref().map(|driver| driver.to_string());
+                if let Some(kind) = network_sink_kind(&name, &driver.unwrap_or_default()) {
+                    let description = item.description.as_ref().map(|d| d.to_string());
+                    sinks_ref.borrow_mut().push((
+                        item.index,
+                        name,
+                        description.unwrap_or_default(),
+                        kind.to_string(),
+                    ));
+                }
+            }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get sinks", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        sinks.take()
+    }
+
+    /// Loads a tunnel sink to the audio server on the host, responds with false if the host is
+    /// invalid or the module could not be loaded.
+    pub fn connect_network_sink(&self, host: String) {
+        let connected = self.network_sinks.borrow().get(&host).copied();
+        if connected.is_some_and(|module| self.module_name(module).is_some()) {
+            let _ = self.sender.send(AudioResponse::NetworkSink(true));
+            return;
+        }
+        let Some(argument) = tunnel_sink_arguments(&host) else {
+            let _ = self.sender.send(AudioResponse::NetworkSink(false));
+            return;
+        };
+        let module = self.load_module(TUNNEL_SINK_MODULE, &argument);
+        if module == INVALID_INDEX {
+            ERROR!(
+                format!("Could not connect to network sink {}", host),
+                ErrorLevel::Recoverable
+            );
+            let _ = self.sender.send(AudioResponse::NetworkSink(false));
+            return;
+        }
+        self.network_sinks.borrow_mut().insert(host, module);
+        let _ = self.sender.send(AudioResponse::NetworkSink(true));
+    }
+
+    /// Unloads the tunnel sink of the host, responds with false if it was not connected.
+    pub fn disconnect_network_sink(&self, host: String) {
+        let module = self.network_sinks.borrow_mut().remove(&host);
+        let removed = module.is_some_and(|module| self.unload_module(module));
+        let _ = self.sender.send(AudioResponse::NetworkSink(removed));
+    }
+
+    /// Unloads the discovery and tunnel modules, as they would otherwise outlive the daemon.
+    fn unload_network_sinks(&self) {
+        let mut modules: Vec<u32> = self.discovery_modules.borrow_mut().drain(..).collect();
+        modules.extend(
+            self.network_sinks
+                .borrow_mut()
+                .drain()
+                .map(|(_, module)| module),
+        );
+        for module in modules {
+            if self.module_name(module).is_some() {
+                self.unload_module(module);
+            }
+        }
+    }
+
+    /// Loads a null sink and a null source for --virtual-devices, both are reported with SinkAdded
+    /// and SourceAdded like any other device.
+    pub fn create_virtual_devices(&self) {
+        let devices = [
+            (NULL_SINK_MODULE, virtual_sink_arguments()),
+            (NULL_SOURCE_MODULE, virtual_source_arguments()),
+        ];
+        for (name, argument) in devices {
+            let module = self.load_module(name, &argument);
+            if module == INVALID_INDEX {
+                ERROR!(
+                    format!("Could not load {} for virtual devices", name),
+                    ErrorLevel::Recoverable
+                );
+                continue;
+            }
+            self.virtual_modules.borrow_mut().push(module);
+        }
+    }
+
+    fn unload_virtual_devices(&self) {
+        let modules: Vec<u32> = self.virtual_modules.borrow_mut().drain(..).collect();
+        for module in modules {
+            if self.module_name(module).is_some() {
+                self.unload_module(module);
+            }
+        }
+    }
+
+    fn load_module(&self, name: &str, argument: &str) -> u32 {
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let module = Rc::new(RefCell::new(INVALID_INDEX));
+        let module_ref = module.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.load_module(name, argument, move |index| {
+            module_ref.replace(index);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        module.take()
+    }
+
+    fn unload_module(&self, index: u32) -> bool {
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let removed = Rc::new(RefCell::new(false));
+        let removed_ref = removed.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.unload_module(index, move |success| {
+            removed_ref.replace(success);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(!success);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        removed.take()
+    }
+
+    /// Returns None if the module is not loaded.
+    fn module_name(&self, index: u32) -> Option<String> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let name = Rc::new(RefCell::new(None));
+        let name_ref = name.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_module_info(index, move |result| match result {
+            ListResult::Item(item) => {
+                name_ref.replace(item.name.as_ref().map(|name| name.to_string()));
+            }
+            ListResult::Error => unsafe {
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        name.take()
+    }
+
+    /// Renames a sink on the audio server, the new description is reported with SinkChanged.
+    pub fn set_sink_description(&self, index: u32, description: String) {
+        let sink = self
+            .list_sinks()
+            .into_iter()
+            .find(|sink| sink.index == index);
+        let set = sink.is_some_and(|sink| {
+            self.set_device_description(&format!("sink:{}", sink.name), &description)
+        });
+        let _ = self.sender.send(AudioResponse::DescriptionSet(set));
+    }
+
+    /// Renames a source on the audio server, the new description is reported with SourceChanged.
+    pub fn set_source_description(&self, index: u32, description: String) {
+        let source = self
+            .list_sources()
+            .into_iter()
+            .find(|source| source.index == index);
+        let set = source.is_some_and(|source| {
+            self.set_device_description(&format!("source:{}", source.name), &description)
+        });
+        let _ = self.sender.send(AudioResponse::DescriptionSet(set));
+    }
+
+    /// Sets the description with module-device-manager, which also restores it once the device
+    /// reappears.\
+    /// The module is loaded if the server does not provide it yet.
+    fn set_device_description(&self, device: &str, description: &str) -> bool {
+        if !self.ensure_device_manager() {
+            ERROR!(
+                "module-device-manager is not available, can not rename devices",
+                ErrorLevel::Recoverable
+            );
+            return false;
+        }
+        self.mainloop.borrow_mut().lock();
+        let mut device_manager = self.context.borrow().device_manager();
+        let set = Rc::new(RefCell::new(false));
+        let set_ref = set.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = device_manager.set_device_description(device, description, move |success| {
+            set_ref.replace(success);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(!success);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        let set = set.take();
+        if set {
+            LOG!(format!("Renamed {} to {}", device, description));
+        }
+        set
+    }
+
+    fn ensure_device_manager(&self) -> bool {
+        if self.device_manager_version() != INVALID_INDEX {
+            return true;
+        }
+        self.mainloop.borrow_mut().lock();
+        let mut introspector = self.context.borrow_mut().introspect();
+        let module = Rc::new(RefCell::new(INVALID_INDEX));
+        let module_ref = module.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.load_module("module-device-manager", "", move |index| {
+            module_ref.replace(index);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        module.take() != INVALID_INDEX && self.device_manager_version() != INVALID_INDEX
+    }
+
+    /// Returns INVALID_INDEX if module-device-manager is not loaded.
+    fn device_manager_version(&self) -> u32 {
+        self.mainloop.borrow_mut().lock();
+        let mut device_manager = self.context.borrow().device_manager();
+        let version = Rc::new(RefCell::new(INVALID_INDEX));
+        let version_ref = version.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = device_manager.test(move |result| {
+            version_ref.replace(result);
+            unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            }
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        version.take()
+    }
+
+    /// Emits the added signals for all current sinks, sources and streams.\
+    /// Used after events like resuming from sleep, where clients might have missed changes.
+    pub fn resync(&self) {
+        for sink in self.list_sinks() {
+            handle_sink_events(&self.connection, sink, Operation::New);
+        }
+        for source in self.list_sources() {
+            handle_source_events(&self.connection, source, Operation::New);
+        }
+        for input_stream in self.list_input_streams() {
+            handle_input_stream_events(&self.connection, input_stream, Operation::New);
+        }
+        for output_stream in self.list_output_streams() {
+            handle_output_stream_events(&self.connection, output_stream, Operation::New);
+        }
+    }
+
+    pub fn set_bluetooth_auto_route(&self, enabled: bool) {
+        let mut route = self.bluetooth_route.borrow_mut();
+        *route = BluetoothAutoRoute {
+            enabled,
+            ..Default::default()
+        };
+    }
+
+    pub fn handle_bluetooth_device_connected(&self, address: String) {
+        if !self.bluetooth_route.borrow().enabled {
+            return;
+        }
+        // the sink might not exist yet, in this case the route is applied once pulse reports
+        // the new sink
+        if let Some(sink_name) = self.find_bluetooth_sink(&address) {
+            self.route_to_bluetooth_sink(sink_name);
+        } else {
+            self.bluetooth_route.borrow_mut().pending_address = Some(address);
+        }
+    }
+
+    pub fn handle_bluetooth_sink_added(&self, sink_name: String) {
+        let matches = self.bluetooth_route.borrow().is_pending_sink(&sink_name);
+        if matches {
+            self.route_to_bluetooth_sink(sink_name);
+        }
+    }
+
+    pub fn handle_bluetooth_device_disconnected(&self, address: String) {
+        let previous_default = self.bluetooth_route.borrow_mut().disconnect(&address);
+        if let Some(previous_default) = previous_default {
+            LOG!(format!(
+                "Bluetooth device {} disconnected, restoring default sink {}",
+                address, previous_default
+            ));
+            self.apply_default_sink(&previous_default);
+        }
+    }
+
+    fn route_to_bluetooth_sink(&self, sink_name: String) {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let current_default = self.no_lock_get_default_sink_name(&introspector).take();
+        self.mainloop.borrow_mut().unlock();
+        self.bluetooth_route
+            .borrow_mut()
+            .route(&sink_name, current_default);
+        LOG!(format!("Routing audio to bluetooth sink {}", sink_name));
+        self.apply_default_sink(&sink_name);
+    }
+
+    fn find_bluetooth_sink(&self, address: &str) -> Option<String> {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let sink_name = Rc::new(RefCell::new(None));
+        let sink_name_ref = sink_name.clone();
+        let address = address.to_string();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_sink_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                if let Some(name) = item.name.as_ref() {
+                    if bluetooth_sink_matches(name, &address) {
+                        sink_name_ref.replace(Some(name.to_string()));
+                    }
+                }
+            }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get sinks", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        sink_name.take()
+    }
+
+    pub fn set_auto_headset_profile(&self, enabled: bool) {
+        self.headset_profile.borrow_mut().enabled = enabled;
+        if enabled {
+            self.handle_source_outputs_changed();
+        } else {
+            self.restore_card_profiles();
+        }
+    }
+
+    /// Switches Bluetooth cards to their headset profile while any application records from a
+    /// microphone, A2DP profiles provide no source.
+    pub fn handle_source_outputs_changed(&self) {
+        if !self.headset_profile.borrow().enabled {
+            return;
+        }
+        let recording = self.is_recording();
+        let switched = !self.headset_profile.borrow().previous_profiles.is_empty();
+        if recording && !switched {
+            self.switch_to_headset_profiles();
+        } else if !recording && switched {
+            self.restore_card_profiles();
+        }
+    }
+
+    /// Whether any source output records from a source that is not a monitor.
+    fn is_recording(&self) -> bool {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        let monitors = Rc::new(RefCell::new(Vec::new()));
+        let monitors_ref = monitors.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_source_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                if item.monitor_of_sink.is_some() {
+                    monitors_ref.borrow_mut().push(item.index);
+                }
+            }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get sources", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        let recording = Rc::new(RefCell::new(false));
+        let recording_ref = recording.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_source_output_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                if !monitors.borrow().contains(&item.source) {
+                    recording_ref.replace(true);
+                }
+            }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get output streams", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        recording.take()
+    }
+
+    fn switch_to_headset_profiles(&self) {
+        self.mainloop.borrow_mut().lock();
+        let introspector = self.context.borrow().introspect();
+        // (card index, active profile, headset profile)
+        let switches = Rc::new(RefCell::new(Vec::new()));
+        let switches_ref = switches.clone();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result = introspector.get_card_info_list(move |result| match result {
+            ListResult::Item(item) => {
+                if !item
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| name.starts_with("bluez_"))
+                {
+                    return;
+                }
+                let active_profile = item
+                    .active_profile
+                    .as_ref()
+                    .and_then(|profile| profile.name.as_ref())
+                    .map(|name| name.to_string())
+                    .unwrap_or_default();
+                if !active_profile.starts_with("a2dp") {
+                    return;
+                }
+                let headset_profile = item
+                    .profiles
+                    .iter()
+                    .filter(|profile| profile.available)
+                    .filter_map(|profile| profile.name.as_ref())
+                    .find(|name| is_headset_profile(name));
+                if let Some(headset_profile) = headset_profile {
+                    switches_ref.borrow_mut().push((
+                        item.index,
+                        active_profile,
+                        headset_profile.to_string(),
+                    ));
+                }
+            }
+            ListResult::Error => unsafe {
+                ERROR!("Could not get profile cards", ErrorLevel::PartialBreakage);
+                (*ml_ref.as_ptr()).signal(true);
+            },
+            ListResult::End => unsafe {
+                (*ml_ref.as_ptr()).signal(false);
+            },
+        });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+        for (index, active_profile, headset_profile) in switches.take() {
+            LOG!(format!(
+                "Recording started, switching card {} to {}",
+                index, headset_profile
+            ));
+            self.set_card_profile_of_device(index, headset_profile);
+            self.headset_profile
+                .borrow_mut()
+                .previous_profiles
+                .insert(index, active_profile);
+        }
+    }
+
+    fn restore_card_profiles(&self) {
+        let previous_profiles: Vec<(u32, String)> = self
+            .headset_profile
+            .borrow_mut()
+            .previous_profiles
+            .drain()
+            .collect();
+        // cards of disconnected devices are gone, pulse ignores the request in this case
+        for (index, profile) in previous_profiles {
+            LOG!(format!(
+                "Recording stopped, restoring profile {} of card {}",
+                profile, index
+            ));
+            self.set_card_profile_of_device(index, profile);
+        }
+    }
+
+    /// Sets the default sink without sending a response to the DBus side.
+    fn apply_default_sink(&self, sink_name: &str) {
+        self.mainloop.borrow_mut().lock();
+        let ml_ref = Rc::clone(&self.mainloop);
+        let result =
+            self.context
+                .borrow_mut()
+                .set_default_sink(sink_name, move |error: bool| unsafe {
+                    (*ml_ref.as_ptr()).signal(!error);
+                });
+        while result.get_state() != pulse::operation::State::Done {
+            self.mainloop.borrow_mut().wait();
+        }
+        self.mainloop.borrow_mut().unlock();
+    }
+}
+
+/// Bluetooth sinks contain the device address with underscores instead of colons,
+/// e.g. bluez_output.AA_BB_CC_DD_EE_FF.1 for the device AA:BB:CC:DD:EE:FF
+fn bluetooth_sink_matches(sink_name: &str, address: &str) -> bool {
+    !address.is_empty() && sink_name.contains(&address.replace(':', "_"))
+}
+
+/// Profile names differ between PulseAudio (headset_head_unit) and PipeWire
+/// (headset-head-unit, handsfree_head_unit).
+fn is_headset_profile(name: &str) -> bool {
+    name.starts_with("headset") || name.starts_with("handsfree")
+}
+
+/// Prefers the device.description entry, as the description of the sink or source might be
+/// changed by profiles, e.g. "Headset (HSP/HFP)".
+fn device_description(proplist: &Proplist, description: Option<&str>, name: &str) -> String {
+    proplist
+        .get_str("device.description")
+        .or_else(|| description.map(|description| description.to_string()))
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Removes the callbacks of a peak monitor before disconnecting it, the callbacks hold a
+/// reference to the stream itself.\
+/// Requires the mainloop to be locked.
+fn stop_peak_stream(stream: &Rc<RefCell<Stream>>) {
+    let mut stream = stream.borrow_mut();
+    stream.set_read_callback(None);
+    stream.set_state_callback(None);
+    if stream.get_state() == stream::State::Ready {
+        let _ = stream.disconnect();
+    }
+}
+
+fn handle_stream_peak(conn: &Arc<SyncConnection>, index: u32, peak: f32) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"StreamPeak".into(),
+    )
+    .append2(index, peak.clamp(0.0, 1.0) as f64);
+    emit_changed_signal(conn, msg, index);
+}
+
+fn handle_peak_monitor_stopped(conn: &Arc<SyncConnection>, index: u32) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"StreamPeakMonitorStopped".into(),
+    )
+    .append1(index);
+    emit_signal(conn, msg);
+}
+
+/// Simplified event for notification daemons, emitted for new sinks and sources.
+fn handle_device_connected(
+    conn: &Arc<SyncConnection>,
+    kind: &str,
+    description: String,
+    index: u32,
+) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"AudioDeviceConnected".into(),
+    )
+    .append3(kind, description, index);
+    emit_signal(conn, msg);
+}
+
+fn handle_sink_events(conn: &Arc<SyncConnection>, sink: Sink, operation: Operation) {
+    if operation != Operation::Removed {
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .sinks
+            .update(sink.index, sink.clone());
+        report_sink(conn, &sink);
+    }
+    match operation {
+        Operation::New => {
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &AUDIO.into(),
+                &"SinkAdded".into(),
+            )
+            .append1(sink);
+            emit_signal(conn, msg);
+        }
+        Operation::Changed => {
+            let index = sink.index;
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &AUDIO.into(),
+                &"SinkChanged".into(),
+            )
+            .append1(sink);
+            emit_changed_signal(conn, msg, index);
+        }
+        Operation::Removed => (),
+    }
+}
+
+fn handle_sink_removed(conn: &Arc<SyncConnection>, index: u32) {
+    AUDIO_CACHE.write().unwrap().sinks.remove(index);
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"SinkRemoved".into(),
+    )
+    .append1(index);
+    emit_signal(conn, msg);
+}
+
+fn handle_source_events(conn: &Arc<SyncConnection>, source: Source, operation: Operation) {
+    if operation != Operation::Removed {
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .sources
+            .update(source.index, source.clone());
+    }
+    match operation {
+        Operation::New => {
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &AUDIO.into(),
+                &"SourceAdded".into(),
+            )
+            .append1(source);
+            emit_signal(conn, msg);
+        }
+        Operation::Changed => {
+            let index = source.index;
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &AUDIO.into(),
+                &"SourceChanged".into(),
+            )
+            .append1(source);
+            emit_changed_signal(conn, msg, index);
+        }
+        Operation::Removed => (),
+    }
+}
+
+fn handle_source_removed(conn: &Arc<SyncConnection>, index: u32) {
+    AUDIO_CACHE.write().unwrap().sources.remove(index);
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"SourceRemoved".into(),
+    )
+    .append1(index);
+    emit_signal(conn, msg);
+}
+
+fn handle_input_stream_events(
+    conn: &Arc<SyncConnection>,
+    input_stream: InputStream,
+    operation: Operation,
+) {
+    if operation != Operation::Removed {
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .input_streams
+            .update(input_stream.index, input_stream.clone());
+        report_input_stream(conn, &input_stream);
+    }
+    match operation {
+        Operation::New => {
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
                 &AUDIO.into(),
                 &"InputStreamAdded".into(),
             )
             .append1(input_stream);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
+            emit_signal(conn, msg);
         }
         Operation::Changed => {
+            let index = input_stream.index;
             let msg = Message::signal(
                 &Path::from(DBUS_PATH!()),
                 &AUDIO.into(),
                 &"InputStreamChanged".into(),
             )
             .append1(input_stream);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
+            emit_changed_signal(conn, msg, index);
         }
         Operation::Removed => (),
     }
 }
 
 fn handle_input_stream_removed(conn: &Arc<SyncConnection>, index: u32) {
+    AUDIO_CACHE.write().unwrap().input_streams.remove(index);
+    forget_input_stream(index);
     let msg = Message::signal(
         &Path::from(DBUS_PATH!()),
         &AUDIO.into(),
         &"InputStreamRemoved".into(),
     )
     .append1(index);
-    let res = conn.send(msg);
-    if res.is_err() {
-        ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-    }
+    emit_signal(conn, msg);
 }
 
 fn handle_output_stream_events(
@@ -983,6 +2730,13 @@ fn handle_output_stream_events(
     output_stream: OutputStream,
     operation: Operation,
 ) {
+    if operation != Operation::Removed {
+        AUDIO_CACHE
+            .write()
+            .unwrap()
+            .output_streams
+            .update(output_stream.index, output_stream.clone());
+    }
     match operation {
         Operation::New => {
             let msg = Message::signal(
@@ -991,36 +2745,29 @@ fn handle_output_stream_events(
                 &"OutputStreamAdded".into(),
             )
             .append1(output_stream);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
+            emit_signal(conn, msg);
         }
         Operation::Changed => {
+            let index = output_stream.index;
             let msg = Message::signal(
                 &Path::from(DBUS_PATH!()),
                 &AUDIO.into(),
                 &"OutputStreamChanged".into(),
             )
             .append1(output_stream);
-            let res = conn.send(msg);
-            if res.is_err() {
-                ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-            }
+            emit_changed_signal(conn, msg, index);
         }
         Operation::Removed => (),
     }
 }
 
 fn handle_output_stream_removed(conn: &Arc<SyncConnection>, index: u32) {
+    AUDIO_CACHE.write().unwrap().output_streams.remove(index);
     let msg = Message::signal(
         &Path::from(DBUS_PATH!()),
         &AUDIO.into(),
         &"OutputStreamRemoved".into(),
     )
     .append1(index);
-    let res = conn.send(msg);
-    if res.is_err() {
-        ERROR!("Could not get send message", ErrorLevel::PartialBreakage);
-    }
+    emit_signal(conn, msg);
 }
@@ -1,25 +1,82 @@
 use dbus_crossroads::Crossroads;
 use re_set_lib::audio::audio_structures::{Card, InputStream, OutputStream, Sink, Source};
 
+use super::aliases::{set_device_alias, AliasKind};
+use super::availability::{has_audio_devices, is_dummy_device, no_sink, no_source};
+use super::cache::{invalidate_audio_cache, AUDIO_CACHE};
+use super::channel_test::{parse_channel_position, ChannelPosition};
+use super::delta::AudioDelta;
+use super::device_hints::DeviceHints;
+use super::network_sinks::NetworkSink;
+use super::noise_suppression::NoiseSuppressedSource;
+use super::server_info::is_supported_sample_rate;
+use super::stream_routing::{forget_stream_routing, list_stream_routing};
+use super::stream_state::{list_stream_cork_states, StreamCorkState};
+use super::volume_info::{list_volume_info, set_volume_percent, VolumeInfo, VolumeKind};
+use super::volume_limit::{
+    default_volume_step, relative_volume_limit, set_volume_limit, MAX_VOLUME_LIMIT,
+};
 use crate::{
+    introspection::{annotate_struct_fields, STRUCT_OUT0},
+    status::{status_error, StatusCode},
     utils::{AudioRequest, AudioResponse, AUDIO},
     DaemonData,
 };
 
 pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
     let token = cross.register(AUDIO, |c| {
-        c.signal::<(Sink,), _>("SinkChanged", ("sink",));
-        c.signal::<(Sink,), _>("SinkAdded", ("sink",));
+        annotate_struct_fields(
+            c,
+            &[
+                "Sink",
+                "Source",
+                "InputStream",
+                "OutputStream",
+                "Card",
+                "CardProfile",
+            ],
+        );
+        c.signal::<(Sink,), _>("SinkChanged", ("sink",))
+            .annotate(STRUCT_OUT0, "Sink");
+        c.signal::<(Sink,), _>("SinkAdded", ("sink",))
+            .annotate(STRUCT_OUT0, "Sink");
         c.signal::<(u32,), _>("SinkRemoved", ("sink",));
-        c.signal::<(Source,), _>("SourceChanged", ("source",));
-        c.signal::<(Source,), _>("SourceAdded", ("source",));
+        c.signal::<(Source,), _>("SourceChanged", ("source",))
+            .annotate(STRUCT_OUT0, "Source");
+        c.signal::<(Source,), _>("SourceAdded", ("source",))
+            .annotate(STRUCT_OUT0, "Source");
         c.signal::<(u32,), _>("SourceRemoved", ("source",));
-        c.signal::<(InputStream,), _>("InputStreamChanged", ("input_stream",));
-        c.signal::<(InputStream,), _>("InputStreamAdded", ("input_stream",));
+        c.signal::<(InputStream,), _>("InputStreamChanged", ("input_stream",))
+            .annotate(STRUCT_OUT0, "InputStream");
+        c.signal::<(InputStream,), _>("InputStreamAdded", ("input_stream",))
+            .annotate(STRUCT_OUT0, "InputStream");
         c.signal::<(u32,), _>("InputStreamRemoved", ("input_stream",));
-        c.signal::<(OutputStream,), _>("OutputStreamChanged", ("output_stream",));
-        c.signal::<(OutputStream,), _>("OutputStreamAdded", ("output_stream",));
+        c.signal::<StreamCorkState, _>(
+            "StreamCorkStateChanged",
+            ("input_stream", "corked", "suspended"),
+        );
+        c.signal::<(bool,), _>("AudioDevicesAvailable", ("available",));
+        c.property::<bool, _>("HasAudioDevices")
+            .get(|_, _| Ok(has_audio_devices()));
+        c.signal::<(u32, f64), _>("StreamPeak", ("input_stream", "peak"));
+        c.signal::<(u32,), _>("StreamPeakMonitorStopped", ("input_stream",));
+        c.signal::<(OutputStream,), _>("OutputStreamChanged", ("output_stream",))
+            .annotate(STRUCT_OUT0, "OutputStream");
+        c.signal::<(OutputStream,), _>("OutputStreamAdded", ("output_stream",))
+            .annotate(STRUCT_OUT0, "OutputStream");
         c.signal::<(u32,), _>("OutputStreamRemoved", ("output_stream",));
+        for member in [
+            "SinkChangedDelta",
+            "SourceChangedDelta",
+            "InputStreamChangedDelta",
+            "OutputStreamChangedDelta",
+        ] {
+            c.signal::<AudioDelta, _>(member, ("index", "mask", "values"));
+        }
+        c.signal::<(String, String, u32), _>(
+            "AudioDeviceConnected",
+            ("kind", "description", "index"),
+        );
         c.method_with_cr_async(
             "GetDefaultSink",
             (),
@@ -28,26 +85,31 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().default_sink();
                 async move {
-                    let _ = sender.send(AudioRequest::GetDefaultSink);
-                    let response = receiver.recv();
-                    let sink: Option<Sink> = if let Ok(response) = response {
-                        match response {
-                            AudioResponse::DefaultSink(s) => Some(s),
-                            _ => None,
-                        }
+                    let sink = if let Some(sink) = cached {
+                        sink
                     } else {
-                        None
-                    };
-                    let response: Result<(Sink,), dbus::MethodErr> = if let Some(sink) = sink {
-                        Ok((sink,))
-                    } else {
-                        Err(dbus::MethodErr::failed("Could not get default sink"))
+                        let _ = sender.send(AudioRequest::GetDefaultSink);
+                        match receiver.recv() {
+                            Ok(AudioResponse::DefaultSink(sink)) => sink,
+                            Ok(_) => no_sink(),
+                            Err(_) => {
+                                return ctx.reply(Err(status_error(
+                                    StatusCode::PulseUnavailable,
+                                    "Could not get default sink",
+                                )))
+                            }
+                        }
                     };
-                    ctx.reply(response)
+                    if is_dummy_device(&sink.name) {
+                        return ctx.reply(Ok((no_sink(),)));
+                    }
+                    ctx.reply(Ok((sink,)))
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "Sink");
         c.method_with_cr_async(
             "GetDefaultSinkName",
             (),
@@ -56,7 +118,11 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().default_sink_name.clone();
                 async move {
+                    if let Some(sink_name) = cached {
+                        return ctx.reply(Ok((sink_name,)));
+                    }
                     let _ = sender.send(AudioRequest::GetDefaultSinkName);
                     let response = receiver.recv();
                     let sink_name = if let Ok(response) = response {
@@ -79,27 +145,31 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().default_source();
                 async move {
-                    let _ = sender.send(AudioRequest::GetDefaultSource);
-                    let response = receiver.recv();
-                    let source: Option<Source> = if let Ok(response) = response {
-                        match response {
-                            AudioResponse::DefaultSource(s) => Some(s),
-                            _ => None,
-                        }
+                    let source = if let Some(source) = cached {
+                        source
                     } else {
-                        None
-                    };
-                    let response: Result<(Source,), dbus::MethodErr> = if let Some(source) = source
-                    {
-                        Ok((source,))
-                    } else {
-                        Err(dbus::MethodErr::failed("Could not get default source"))
+                        let _ = sender.send(AudioRequest::GetDefaultSource);
+                        match receiver.recv() {
+                            Ok(AudioResponse::DefaultSource(source)) => source,
+                            Ok(_) => no_source(),
+                            Err(_) => {
+                                return ctx.reply(Err(status_error(
+                                    StatusCode::PulseUnavailable,
+                                    "Could not get default source",
+                                )))
+                            }
+                        }
                     };
-                    ctx.reply(response)
+                    if is_dummy_device(&source.name) {
+                        return ctx.reply(Ok((no_source(),)));
+                    }
+                    ctx.reply(Ok((source,)))
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "Source");
         c.method_with_cr_async(
             "GetDefaultSourceName",
             (),
@@ -108,7 +178,11 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().default_source_name.clone();
                 async move {
+                    if let Some(source_name) = cached {
+                        return ctx.reply(Ok((source_name,)));
+                    }
                     let _ = sender.send(AudioRequest::GetDefaultSourceName);
                     let response = receiver.recv();
                     let source_name = if let Ok(response) = response {
@@ -127,7 +201,11 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
             let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
             let sender = data.audio_sender.clone();
             let receiver = data.audio_receiver.clone();
+            let cached = AUDIO_CACHE.read().unwrap().sinks.list();
             async move {
+                if let Some(sinks) = cached {
+                    return ctx.reply(Ok((sinks,)));
+                }
                 let _ = sender.send(AudioRequest::ListSinks);
                 let response = receiver.recv();
                 let sinks: Vec<Sink> = if let Ok(response) = response {
@@ -140,12 +218,17 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 };
                 ctx.reply(Ok((sinks,)))
             }
-        });
+        })
+        .annotate(STRUCT_OUT0, "Sink");
         c.method_with_cr_async("ListSources", (), ("sinks",), move |mut ctx, cross, ()| {
             let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
             let sender = data.audio_sender.clone();
             let receiver = data.audio_receiver.clone();
+            let cached = AUDIO_CACHE.read().unwrap().sources.list();
             async move {
+                if let Some(sources) = cached {
+                    return ctx.reply(Ok((sources,)));
+                }
                 let _ = sender.send(AudioRequest::ListSources);
                 let response = receiver.recv();
                 let sources: Vec<Source> = if let Ok(response) = response {
@@ -158,7 +241,24 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 };
                 ctx.reply(Ok((sources,)))
             }
-        });
+        })
+        .annotate(STRUCT_OUT0, "Source");
+        c.method_with_cr_async(
+            "RefreshAudioCache",
+            (),
+            ("result",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::RefreshCache);
+                    let response = receiver.recv();
+                    let refreshed = matches!(response, Ok(AudioResponse::CacheRefreshed));
+                    ctx.reply(Ok((refreshed,)))
+                }
+            },
+        );
         c.method_with_cr_async(
             "SetSinkVolume",
             ("index", "channels", "volume"),
@@ -185,6 +285,22 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 }
             },
         );
+        c.method_with_cr_async(
+            "SuspendSink",
+            ("index", "suspend"),
+            ("result",),
+            move |mut ctx, cross, (index, suspend): (u32, bool)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::SuspendSink(index, suspend));
+                    let response = receiver.recv();
+                    let suspended = matches!(response, Ok(AudioResponse::SinkSuspended(true)));
+                    ctx.reply(Ok((suspended,)))
+                }
+            },
+        );
         c.method_with_cr_async(
             "SetSourceVolume",
             ("index", "channels", "volume"),
@@ -222,15 +338,19 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 async move {
                     let _ = sender.send(AudioRequest::SetDefaultSink(sink));
                     let response = receiver.recv();
-                    let result = if let Ok(AudioResponse::DefaultSink(response)) = response {
-                        Ok((response,))
-                    } else {
-                        Err(dbus::MethodErr::failed("Could not get default sink"))
+                    let result = match response {
+                        Ok(AudioResponse::DefaultSink(response)) => Ok((response,)),
+                        Ok(_) => Err(status_error(StatusCode::DeviceNotFound, "Unknown sink")),
+                        Err(_) => Err(status_error(
+                            StatusCode::PulseUnavailable,
+                            "Could not get default sink",
+                        )),
                     };
                     ctx.reply(result)
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "Sink");
         c.method_with_cr_async(
             "SetDefaultSource",
             ("source",),
@@ -242,15 +362,19 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 async move {
                     let _ = sender.send(AudioRequest::SetDefaultSource(source));
                     let response = receiver.recv();
-                    let result = if let Ok(AudioResponse::DefaultSource(response)) = response {
-                        Ok((response,))
-                    } else {
-                        Err(dbus::MethodErr::failed("Could not get default source"))
+                    let result = match response {
+                        Ok(AudioResponse::DefaultSource(response)) => Ok((response,)),
+                        Ok(_) => Err(status_error(StatusCode::DeviceNotFound, "Unknown source")),
+                        Err(_) => Err(status_error(
+                            StatusCode::PulseUnavailable,
+                            "Could not get default source",
+                        )),
                     };
                     ctx.reply(result)
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "Source");
         c.method_with_cr_async(
             "ListInputStreams",
             (),
@@ -259,7 +383,11 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().input_streams.list();
                 async move {
+                    if let Some(input_streams) = cached {
+                        return ctx.reply(Ok((input_streams,)));
+                    }
                     let _ = sender.send(AudioRequest::ListInputStreams);
                     let response = receiver.recv();
                     let input_streams: Vec<InputStream> = if let Ok(response) = response {
@@ -273,6 +401,37 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                     ctx.reply(Ok((input_streams,)))
                 }
             },
+        )
+        .annotate(STRUCT_OUT0, "InputStream");
+        c.method_with_cr_async(
+            "ListStreamCorkStates",
+            (),
+            ("states",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                let cache = AUDIO_CACHE.read().unwrap();
+                let requests: Vec<AudioRequest> = [
+                    (cache.sinks.list().is_none(), AudioRequest::ListSinks),
+                    (
+                        cache.input_streams.list().is_none(),
+                        AudioRequest::ListInputStreams,
+                    ),
+                ]
+                .into_iter()
+                .filter_map(|(missing, request)| missing.then_some(request))
+                .collect();
+                drop(cache);
+                async move {
+                    // listing fills the cache the states are derived from
+                    for request in requests {
+                        let _ = sender.send(request);
+                        let _ = receiver.recv();
+                    }
+                    ctx.reply(Ok((list_stream_cork_states(),)))
+                }
+            },
         );
         c.method_with_cr_async(
             "SetSinkOfInputStream",
@@ -322,7 +481,11 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let sender = data.audio_sender.clone();
                 let receiver = data.audio_receiver.clone();
+                let cached = AUDIO_CACHE.read().unwrap().output_streams.list();
                 async move {
+                    if let Some(output_streams) = cached {
+                        return ctx.reply(Ok((output_streams,)));
+                    }
                     let _ = sender.send(AudioRequest::ListOutputStreams);
                     let response = receiver.recv();
                     let output_streams: Vec<OutputStream> = if let Ok(response) = response {
@@ -336,7 +499,8 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                     ctx.reply(Ok((output_streams,)))
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "OutputStream");
         c.method_with_cr_async(
             "SetSourceOfOutputStream",
             ("input_stream", "source"),
@@ -395,7 +559,390 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 };
                 ctx.reply(Ok((cards,)))
             }
-        });
+        })
+        .annotate(STRUCT_OUT0, "Card");
+        c.method_with_cr_async(
+            "ListSourceMonitors",
+            (),
+            ("monitors",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::ListSourceMonitors);
+                    let response = receiver.recv();
+                    let monitors: Vec<(u32, bool, u32)> = if let Ok(response) = response {
+                        match response {
+                            AudioResponse::SourceMonitors(s) => s,
+                            _ => Vec::new(),
+                        }
+                    } else {
+                        Vec::new()
+                    };
+                    ctx.reply(Ok((monitors,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "CreateLoopback",
+            ("source_index", "sink_index", "latency_ms"),
+            ("index",),
+            move |mut ctx, cross, (source_index, sink_index, latency_ms): (u32, u32, u32)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::CreateLoopback(
+                        source_index,
+                        sink_index,
+                        latency_ms,
+                    ));
+                    let response = receiver.recv();
+                    let response: Result<(u32,), dbus::MethodErr> = match response {
+                        Ok(AudioResponse::Loopback(index)) => Ok((index,)),
+                        _ => Err(status_error(
+                            StatusCode::Failed,
+                            "Could not create loopback",
+                        )),
+                    };
+                    ctx.reply(response)
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "RemoveLoopback",
+            ("index",),
+            ("result",),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::RemoveLoopback(index));
+                    let response = receiver.recv();
+                    let removed = matches!(response, Ok(AudioResponse::LoopbackRemoved(true)));
+                    ctx.reply(Ok((removed,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetSinkDescription",
+            ("index", "description"),
+            ("result",),
+            move |mut ctx, cross, (index, description): (u32, String)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    if description.trim().is_empty() {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "The description can not be empty",
+                        )));
+                    }
+                    let _ = sender.send(AudioRequest::SetSinkDescription(index, description));
+                    let response = receiver.recv();
+                    let set = matches!(response, Ok(AudioResponse::DescriptionSet(true)));
+                    ctx.reply(Ok((set,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetSourceDescription",
+            ("index", "description"),
+            ("result",),
+            move |mut ctx, cross, (index, description): (u32, String)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    if description.trim().is_empty() {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "The description can not be empty",
+                        )));
+                    }
+                    let _ = sender.send(AudioRequest::SetSourceDescription(index, description));
+                    let response = receiver.recv();
+                    let set = matches!(response, Ok(AudioResponse::DescriptionSet(true)));
+                    ctx.reply(Ok((set,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetNoiseSuppression",
+            ("source_index", "enabled"),
+            ("result",),
+            move |mut ctx, cross, (source_index, enabled): (u32, bool)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::SetNoiseSuppression(source_index, enabled));
+                    let response = receiver.recv();
+                    let set = matches!(response, Ok(AudioResponse::NoiseSuppression(true)));
+                    ctx.reply(Ok((set,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ListNoiseSuppressedSources",
+            (),
+            ("sources",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::ListNoiseSuppressedSources);
+                    let sources: Vec<NoiseSuppressedSource> = match receiver.recv() {
+                        Ok(AudioResponse::NoiseSuppressedSources(sources)) => sources,
+                        _ => Vec::new(),
+                    };
+                    ctx.reply(Ok((sources,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "DiscoverNetworkSinks",
+            (),
+            ("sinks",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::DiscoverNetworkSinks);
+                    let sinks: Vec<NetworkSink> = match receiver.recv() {
+                        Ok(AudioResponse::NetworkSinks(sinks)) => sinks,
+                        _ => Vec::new(),
+                    };
+                    ctx.reply(Ok((sinks,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ConnectNetworkSink",
+            ("host",),
+            ("result",),
+            move |mut ctx, cross, (host,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::ConnectNetworkSink(host));
+                    let response = receiver.recv();
+                    let connected = matches!(response, Ok(AudioResponse::NetworkSink(true)));
+                    ctx.reply(Ok((connected,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "DisconnectNetworkSink",
+            ("host",),
+            ("result",),
+            move |mut ctx, cross, (host,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::DisconnectNetworkSink(host));
+                    let response = receiver.recv();
+                    let removed = matches!(response, Ok(AudioResponse::NetworkSink(true)));
+                    ctx.reply(Ok((removed,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetAudioServerInfo",
+            (),
+            (
+                "name",
+                "version",
+                "sample_format",
+                "sample_rate",
+                "channels",
+                "channel_map",
+            ),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::GetAudioServerInfo);
+                    let response = match receiver.recv() {
+                        Ok(AudioResponse::AudioServerInfo(info)) => Ok(info),
+                        _ => Err(status_error(
+                            StatusCode::PulseUnavailable,
+                            "Could not get audio server info",
+                        )),
+                    };
+                    ctx.reply(response)
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetDefaultSampleRate",
+            ("rate",),
+            ("result",),
+            move |mut ctx, cross, (rate,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    if !is_supported_sample_rate(rate) {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "Unsupported sample rate",
+                        )));
+                    }
+                    let _ = sender.send(AudioRequest::SetDefaultSampleRate(rate));
+                    let response = match receiver.recv() {
+                        Ok(AudioResponse::DefaultSampleRate(Some(changed))) => Ok((changed,)),
+                        Ok(AudioResponse::DefaultSampleRate(None)) => Err(status_error(
+                            StatusCode::Unsupported,
+                            "The audio server can not change the sample rate at runtime",
+                        )),
+                        _ => Err(status_error(
+                            StatusCode::PulseUnavailable,
+                            "Could not set the sample rate",
+                        )),
+                    };
+                    ctx.reply(response)
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "StartStreamPeakMonitor",
+            ("index",),
+            ("result",),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                let clients = data.clients.clone();
+                let client = ctx.message().sender().map(|client| client.to_string());
+                async move {
+                    let _ = sender.send(AudioRequest::StartStreamPeakMonitor(index));
+                    let response = receiver.recv();
+                    let started = matches!(response, Ok(AudioResponse::PeakMonitor(true)));
+                    // the monitor is stopped once the client disconnects
+                    if let (true, Some(client)) = (started, client) {
+                        clients.add_peak_monitor(index, &client);
+                    }
+                    ctx.reply(Ok((started,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "StopStreamPeakMonitor",
+            ("index",),
+            ("result",),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                data.clients.remove_peak_monitor(index);
+                async move {
+                    let _ = sender.send(AudioRequest::StopStreamPeakMonitor(index));
+                    let response = receiver.recv();
+                    let stopped = matches!(response, Ok(AudioResponse::PeakMonitor(true)));
+                    ctx.reply(Ok((stopped,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetSinkChannelMap",
+            ("index",),
+            ("channels",),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::GetSinkChannelMap(index));
+                    let channels: Vec<ChannelPosition> = match receiver.recv() {
+                        Ok(AudioResponse::ChannelMap(channels)) => channels,
+                        _ => {
+                            return ctx.reply(Err(status_error(
+                                StatusCode::DeviceNotFound,
+                                "Unknown sink",
+                            )))
+                        }
+                    };
+                    ctx.reply(Ok((channels,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetSinkHints",
+            ("index",),
+            ("form_factor", "icon_name"),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::GetSinkHints(index));
+                    let hints: DeviceHints = match receiver.recv() {
+                        Ok(AudioResponse::DeviceHints(hints)) => hints,
+                        _ => {
+                            return ctx.reply(Err(status_error(
+                                StatusCode::DeviceNotFound,
+                                "Unknown sink",
+                            )))
+                        }
+                    };
+                    ctx.reply(Ok(hints))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetSourceHints",
+            ("index",),
+            ("form_factor", "icon_name"),
+            move |mut ctx, cross, (index,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let _ = sender.send(AudioRequest::GetSourceHints(index));
+                    let hints: DeviceHints = match receiver.recv() {
+                        Ok(AudioResponse::DeviceHints(hints)) => hints,
+                        _ => {
+                            return ctx.reply(Err(status_error(
+                                StatusCode::DeviceNotFound,
+                                "Unknown source",
+                            )))
+                        }
+                    };
+                    ctx.reply(Ok(hints))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "PlayChannelTest",
+            ("index", "position"),
+            ("result",),
+            move |mut ctx, cross, (index, position): (u32, String)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let Some(position) = parse_channel_position(&position) else {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "Unknown channel position",
+                        )));
+                    };
+                    let _ = sender.send(AudioRequest::PlayChannelTest(index, position));
+                    let response = receiver.recv();
+                    let started = matches!(response, Ok(AudioResponse::ChannelTest(true)));
+                    ctx.reply(Ok((started,)))
+                }
+            },
+        );
         c.method_with_cr_async(
             "SetCardProfileOfDevice",
             ("device_index", "profile_name"),
@@ -412,6 +959,157 @@ pub fn setup_audio_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToke
                 }
             },
         );
+        c.method(
+            "SetDeviceAlias",
+            ("kind", "identifier", "label"),
+            ("result",),
+            move |_, _, (kind, identifier, label): (String, String, String)| {
+                let kind = AliasKind::from_name(&kind);
+                if kind.is_none() {
+                    return Err(status_error(
+                        StatusCode::InvalidArgument,
+                        "Unknown device kind, expected sink or source.",
+                    ));
+                }
+                let result = set_device_alias(kind.unwrap(), identifier, label);
+                // the cached objects still carry the previous alias
+                invalidate_audio_cache();
+                Ok((result,))
+            },
+        );
+        c.method(
+            "SetVolumeLimit",
+            ("percent",),
+            ("result",),
+            move |_, _, (percent,): (u32,)| {
+                if percent == 0 || percent > MAX_VOLUME_LIMIT {
+                    return Err(status_error(
+                        StatusCode::InvalidArgument,
+                        &format!(
+                            "The volume limit has to be between 1 and {} percent.",
+                            MAX_VOLUME_LIMIT
+                        ),
+                    ));
+                }
+                Ok((set_volume_limit(percent),))
+            },
+        );
+        c.method(
+            "ForgetStreamRouting",
+            ("application_name",),
+            ("result",),
+            move |_, _, (application,): (String,)| Ok((forget_stream_routing(&application),)),
+        );
+        c.method("ListStreamRouting", (), ("routes",), move |_, _, ()| {
+            Ok((list_stream_routing(),))
+        });
+        c.method(
+            "GetVolumeLimit",
+            (),
+            ("percent", "step"),
+            move |_, _, ()| Ok((relative_volume_limit(), default_volume_step())),
+        );
+        c.method_with_cr_async(
+            "ListVolumeInfo",
+            ("kind",),
+            ("volumes",),
+            move |mut ctx, cross, (kind,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let Some(kind) = VolumeKind::from_name(&kind) else {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "The kind has to be sink, source, input_stream or output_stream.",
+                        )));
+                    };
+                    let volumes: Vec<VolumeInfo> = list_volume_info(kind, &sender, &receiver);
+                    ctx.reply(Ok((volumes,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetSinkVolumePercent",
+            ("index", "percent"),
+            ("volume",),
+            move |mut ctx, cross, (index, percent): (u32, f64)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let result =
+                        set_volume_percent(VolumeKind::Sink, index, percent, &sender, &receiver);
+                    ctx.reply(result.map(|volume| (volume,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetSourceVolumePercent",
+            ("index", "percent"),
+            ("volume",),
+            move |mut ctx, cross, (index, percent): (u32, f64)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let result =
+                        set_volume_percent(VolumeKind::Source, index, percent, &sender, &receiver);
+                    ctx.reply(result.map(|volume| (volume,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetInputStreamVolumePercent",
+            ("index", "percent"),
+            ("volume",),
+            move |mut ctx, cross, (index, percent): (u32, f64)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let result = set_volume_percent(
+                        VolumeKind::InputStream,
+                        index,
+                        percent,
+                        &sender,
+                        &receiver,
+                    );
+                    ctx.reply(result.map(|volume| (volume,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetOutputStreamVolumePercent",
+            ("index", "percent"),
+            ("volume",),
+            move |mut ctx, cross, (index, percent): (u32, f64)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let sender = data.audio_sender.clone();
+                let receiver = data.audio_receiver.clone();
+                async move {
+                    let result = set_volume_percent(
+                        VolumeKind::OutputStream,
+                        index,
+                        percent,
+                        &sender,
+                        &receiver,
+                    );
+                    ctx.reply(result.map(|volume| (volume,)))
+                }
+            },
+        );
+        c.method(
+            "SetAutoHeadsetProfile",
+            ("enabled",),
+            ("result",),
+            move |_, d: &mut DaemonData, (enabled,): (bool,)| {
+                let res = d
+                    .audio_sender
+                    .send(AudioRequest::SetAutoHeadsetProfile(enabled));
+                Ok((res.is_ok(),))
+            },
+        );
     });
     token
 }
@@ -0,0 +1,76 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged,
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use pulse::def::INVALID_INDEX;
+use re_set_lib::audio::audio_structures::{Sink, Source};
+
+use crate::{signals::emit_signal, utils::AUDIO};
+
+/// Index of the sink or source returned by the default sink and source methods when no audio
+/// device exists.
+pub const NO_DEVICE: u32 = INVALID_INDEX;
+/// Null sink loaded by PulseAudio and PipeWire when no device exists, shown as "Dummy Output".
+const DUMMY_SINK: &str = "auto_null";
+
+static AUDIO_DEVICES_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Whether any sink or source other than the dummy output and monitors exists.
+pub fn has_audio_devices() -> bool {
+    AUDIO_DEVICES_AVAILABLE.load(Ordering::SeqCst)
+}
+
+/// The dummy output and its monitor are no devices.
+pub fn is_dummy_device(name: &str) -> bool {
+    name.strip_suffix(".monitor").unwrap_or(name) == DUMMY_SINK
+}
+
+/// Sink returned instead of an error when there is no default sink, only the index is set.
+pub fn no_sink() -> Sink {
+    Sink {
+        index: NO_DEVICE,
+        ..Default::default()
+    }
+}
+
+/// Source returned instead of an error when there is no default source, only the index is set.
+pub fn no_source() -> Source {
+    Source {
+        index: NO_DEVICE,
+        ..Default::default()
+    }
+}
+
+/// Stores whether audio devices exist, AudioDevicesAvailable and PropertiesChanged for
+/// HasAudioDevices are emitted when this changed.
+pub fn set_audio_devices_available(conn: &Arc<SyncConnection>, available: bool) {
+    if AUDIO_DEVICES_AVAILABLE.swap(available, Ordering::SeqCst) == available {
+        return;
+    }
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"AudioDevicesAvailable".into(),
+    )
+    .append1(available);
+    emit_signal(conn, msg);
+    let mut changed_properties = PropMap::new();
+    changed_properties.insert(
+        String::from("HasAudioDevices"),
+        Variant(Box::new(available) as Box<dyn RefArg>),
+    );
+    let changed = PropertiesPropertiesChanged {
+        interface_name: AUDIO.to_string(),
+        changed_properties,
+        invalidated_properties: Vec::new(),
+    };
+    emit_signal(conn, changed.to_emit_message(&Path::from(DBUS_PATH!())));
+}
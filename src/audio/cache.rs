@@ -0,0 +1,82 @@
+use std::{collections::BTreeMap, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use re_set_lib::audio::audio_structures::{InputStream, OutputStream, Sink, Source};
+
+/// Copy of the audio objects kept up to date by the subscribe callback of the pulse context.\
+/// List calls are answered from the cache without a roundtrip through the mainloop, each kind is
+/// filled by the first introspection of that kind.
+pub static AUDIO_CACHE: Lazy<RwLock<AudioCache>> = Lazy::new(|| RwLock::new(AudioCache::default()));
+
+#[derive(Debug, Default)]
+pub struct AudioCache {
+    pub sinks: CachedObjects<Sink>,
+    pub sources: CachedObjects<Source>,
+    pub input_streams: CachedObjects<InputStream>,
+    pub output_streams: CachedObjects<OutputStream>,
+    pub default_sink_name: Option<String>,
+    pub default_source_name: Option<String>,
+}
+
+impl AudioCache {
+    pub fn default_sink(&self) -> Option<Sink> {
+        let name = self.default_sink_name.as_ref()?;
+        self.sinks.find(|sink| &sink.name == name)
+    }
+
+    pub fn default_source(&self) -> Option<Source> {
+        let name = self.default_source_name.as_ref()?;
+        self.sources.find(|source| &source.name == name)
+    }
+}
+
+/// Objects keyed by their index, None until the kind was introspected once.
+#[derive(Debug)]
+pub struct CachedObjects<T>(Option<BTreeMap<u32, T>>);
+
+impl<T> Default for CachedObjects<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T: Clone> CachedObjects<T> {
+    pub fn list(&self) -> Option<Vec<T>> {
+        Some(self.0.as_ref()?.values().cloned().collect())
+    }
+
+    fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<T> {
+        self.0
+            .as_ref()?
+            .values()
+            .find(|object| predicate(object))
+            .cloned()
+    }
+
+    pub fn replace(&mut self, objects: &[T], index: fn(&T) -> u32) {
+        self.0 = Some(
+            objects
+                .iter()
+                .map(|object| (index(object), object.clone()))
+                .collect(),
+        );
+    }
+
+    /// Events before the first introspection are ignored, the introspection covers them.
+    pub fn update(&mut self, index: u32, object: T) {
+        if let Some(objects) = self.0.as_mut() {
+            objects.insert(index, object);
+        }
+    }
+
+    pub fn remove(&mut self, index: u32) {
+        if let Some(objects) = self.0.as_mut() {
+            objects.remove(&index);
+        }
+    }
+}
+
+/// Drops all cached objects, the next list call introspects the server again.
+pub fn invalidate_audio_cache() {
+    *AUDIO_CACHE.write().unwrap() = AudioCache::default();
+}
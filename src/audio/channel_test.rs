@@ -0,0 +1,62 @@
+use std::f32::consts::PI;
+
+use pulse::channelmap::{Map, Position};
+
+pub const TEST_TONE_RATE: u32 = 48000;
+const TEST_TONE_DURATION_MS: u32 = 1000;
+const TEST_TONE_FREQUENCY: f32 = 440.0;
+/// Subwoofers usually cut everything above ~120 Hz.
+const LFE_TONE_FREQUENCY: f32 = 60.0;
+const TEST_TONE_AMPLITUDE: f32 = 0.3;
+/// Fade in and out, hard edges of the sine are audible as clicks.
+const FADE_MS: u32 = 20;
+
+/// Channel of a sink as (position, readable name), e.g. (front-left, Front Left).\
+/// Channels are listed in the order of the channel map of the sink.
+pub type ChannelPosition = (String, String);
+
+pub fn channel_positions(map: &Map) -> Vec<ChannelPosition> {
+    map.get()
+        .iter()
+        .map(|position| {
+            let name = Position::to_string(*position)
+                .map(|name| name.to_string())
+                .unwrap_or_default();
+            let pretty_name = Position::to_pretty_string(*position).unwrap_or_else(|| name.clone());
+            (name, pretty_name)
+        })
+        .collect()
+}
+
+/// Returns the position with the given name, e.g. front-left or lfe.
+pub fn parse_channel_position(name: &str) -> Option<Position> {
+    if name.is_empty() || name.contains('\0') {
+        return None;
+    }
+    Some(Position::from_string(name)).filter(|position| *position != Position::Invalid)
+}
+
+/// Interleaved FLOAT32NE samples of a sine on one of the channels, the other channels are
+/// silent.\
+/// The LFE channel receives a lower tone, as subwoofers would not play the regular one.
+pub fn test_tone(channels: usize, channel: usize, lfe: bool, rate: u32) -> Vec<u8> {
+    let frames = (rate * TEST_TONE_DURATION_MS / 1000) as usize;
+    let fade = (rate * FADE_MS / 1000).max(1) as f32;
+    let frequency = if lfe {
+        LFE_TONE_FREQUENCY
+    } else {
+        TEST_TONE_FREQUENCY
+    };
+    let mut data = Vec::with_capacity(frames * channels * std::mem::size_of::<f32>());
+    for frame in 0..frames {
+        let envelope = (frame.min(frames - 1 - frame) as f32 / fade).min(1.0);
+        let sample = (2.0 * PI * frequency * frame as f32 / rate as f32).sin()
+            * TEST_TONE_AMPLITUDE
+            * envelope;
+        for current in 0..channels {
+            let value = if current == channel { sample } else { 0.0 };
+            data.extend_from_slice(&value.to_ne_bytes());
+        }
+    }
+    data
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use dbus::{
+    arg::{RefArg, Variant},
+    Message, Path,
+};
+use re_set_lib::audio::audio_structures::{InputStream, OutputStream, Sink, Source};
+
+use crate::utils::AUDIO;
+
+/// Changed fields of an audio object as (index, changed fields mask, values).\
+/// Bit n of the mask refers to the nth field of the struct, in the order of the StructFields
+/// annotation, the values contain the new value of every set bit in ascending order.
+pub type AudioDelta = (u32, u32, Vec<Variant<Box<dyn RefArg>>>);
+
+/// Last state of an audio object delivered to a delta client.
+#[derive(Debug, Clone)]
+pub enum AudioObject {
+    Sink(Sink),
+    Source(Source),
+    InputStream(InputStream),
+    OutputStream(OutputStream),
+}
+
+/// How a changed signal has to be delivered to a delta client.
+pub enum Delivery {
+    /// The client does not know the object yet or the signal has no delta variant.
+    Full,
+    Delta(Message),
+    /// Nothing the client can observe changed.
+    Unchanged,
+}
+
+#[derive(Default)]
+struct DeltaBuilder {
+    field: u32,
+    mask: u32,
+    values: Vec<Variant<Box<dyn RefArg>>>,
+}
+
+impl DeltaBuilder {
+    fn field<T: RefArg + PartialEq + Clone + 'static>(mut self, previous: &T, current: &T) -> Self {
+        if previous != current {
+            self.mask |= 1 << self.field;
+            self.values.push(Variant(Box::new(current.clone())));
+        }
+        self.field += 1;
+        self
+    }
+}
+
+impl AudioObject {
+    fn from_signal(msg: &Message) -> Option<Self> {
+        match &*msg.member()? {
+            "SinkChanged" => msg.read1().ok().map(AudioObject::Sink),
+            "SourceChanged" => msg.read1().ok().map(AudioObject::Source),
+            "InputStreamChanged" => msg.read1().ok().map(AudioObject::InputStream),
+            "OutputStreamChanged" => msg.read1().ok().map(AudioObject::OutputStream),
+            _ => None,
+        }
+    }
+
+    fn delta_member(&self) -> &'static str {
+        match self {
+            AudioObject::Sink(_) => "SinkChangedDelta",
+            AudioObject::Source(_) => "SourceChangedDelta",
+            AudioObject::InputStream(_) => "InputStreamChangedDelta",
+            AudioObject::OutputStream(_) => "OutputStreamChangedDelta",
+        }
+    }
+
+    fn delta(&self, previous: &Self) -> Option<AudioDelta> {
+        let (index, builder) = match (previous, self) {
+            (AudioObject::Sink(previous), AudioObject::Sink(current)) => (
+                current.index,
+                DeltaBuilder::default()
+                    .field(&previous.index, &current.index)
+                    .field(&previous.name, &current.name)
+                    .field(&previous.alias, &current.alias)
+                    .field(&previous.channels, &current.channels)
+                    .field(&previous.volume, &current.volume)
+                    .field(&previous.muted, &current.muted)
+                    .field(&previous.active, &current.active),
+            ),
+            (AudioObject::Source(previous), AudioObject::Source(current)) => (
+                current.index,
+                DeltaBuilder::default()
+                    .field(&previous.index, &current.index)
+                    .field(&previous.name, &current.name)
+                    .field(&previous.alias, &current.alias)
+                    .field(&previous.channels, &current.channels)
+                    .field(&previous.volume, &current.volume)
+                    .field(&previous.muted, &current.muted)
+                    .field(&previous.active, &current.active),
+            ),
+            (AudioObject::InputStream(previous), AudioObject::InputStream(current)) => (
+                current.index,
+                DeltaBuilder::default()
+                    .field(&previous.index, &current.index)
+                    .field(&previous.name, &current.name)
+                    .field(&previous.application_name, &current.application_name)
+                    .field(&previous.sink_index, &current.sink_index)
+                    .field(&previous.channels, &current.channels)
+                    .field(&previous.volume, &current.volume)
+                    .field(&previous.muted, &current.muted)
+                    .field(&previous.corked, &current.corked),
+            ),
+            (AudioObject::OutputStream(previous), AudioObject::OutputStream(current)) => (
+                current.index,
+                DeltaBuilder::default()
+                    .field(&previous.index, &current.index)
+                    .field(&previous.name, &current.name)
+                    .field(&previous.application_name, &current.application_name)
+                    .field(&previous.source_index, &current.source_index)
+                    .field(&previous.channels, &current.channels)
+                    .field(&previous.volume, &current.volume)
+                    .field(&previous.muted, &current.muted)
+                    .field(&previous.corked, &current.corked),
+            ),
+            _ => return None,
+        };
+        Some((index, builder.mask, builder.values))
+    }
+}
+
+/// Decides how a changed signal is delivered to a delta client and records the delivered state.\
+/// The key identifies the object like the key of throttled signals.
+pub fn delta_delivery(
+    msg: &Message,
+    delivered: &mut HashMap<String, AudioObject>,
+    key: &str,
+) -> Delivery {
+    let Some(object) = AudioObject::from_signal(msg) else {
+        return Delivery::Full;
+    };
+    let member = object.delta_member();
+    let previous = delivered.insert(key.to_string(), object.clone());
+    let Some((index, mask, values)) = previous.and_then(|previous| object.delta(&previous)) else {
+        return Delivery::Full;
+    };
+    if mask == 0 {
+        return Delivery::Unchanged;
+    }
+    let msg = Message::signal(&Path::from(DBUS_PATH!()), &AUDIO.into(), &member.into())
+        .append3(index, mask, values);
+    Delivery::Delta(msg)
+}
+
+/// Drops the delivered state of removed audio objects, a new object with the same index is sent
+/// in full first.
+pub fn forget_removed(msg: &Message, delivered: &mut HashMap<String, AudioObject>) {
+    if msg.interface().is_none_or(|interface| &*interface != AUDIO) {
+        return;
+    }
+    let Some(member) = msg.member() else {
+        return;
+    };
+    let Some(kind) = member.strip_suffix("Removed") else {
+        return;
+    };
+    if let Ok(index) = msg.read1::<u32>() {
+        delivered.remove(&format!("{}Changed{}", kind, index));
+    }
+}
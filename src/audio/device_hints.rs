@@ -0,0 +1,52 @@
+use pulse::proplist::Proplist;
+
+/// Hints for rendering a sink or source as (form factor, icon name), e.g. ("headphone",
+/// "audio-headphones-bluetooth").\
+/// The form factor is the device.form_factor of the server and empty if unknown, the icon name
+/// is always set and follows the freedesktop icon naming specification.
+pub type DeviceHints = (String, String);
+
+/// Icon used if neither the server nor the form factor provide a better one.
+pub const GENERIC_DEVICE_ICON: &str = "audio-card";
+const HDMI_DEVICE_ICON: &str = "video-display";
+
+pub fn proplist_device_hints(proplist: &Proplist) -> DeviceHints {
+    device_hints(
+        proplist.get_str("device.form_factor").as_deref(),
+        proplist.get_str("device.icon_name").as_deref(),
+        proplist.get_str("device.profile.name").as_deref(),
+    )
+}
+
+/// Prefers the icon name of the server, HDMI and S/PDIF outputs only get the generic sound card
+/// icon from the server, as they have no form factor.
+pub fn device_hints(
+    form_factor: Option<&str>,
+    icon_name: Option<&str>,
+    profile: Option<&str>,
+) -> DeviceHints {
+    let form_factor = form_factor.unwrap_or_default().to_string();
+    let digital = profile.is_some_and(|profile| {
+        profile.contains("hdmi") || profile.contains("iec958") || profile.contains("displayport")
+    });
+    let icon_name = match icon_name.filter(|icon| !icon.is_empty()) {
+        Some(icon) if !(digital && icon.starts_with(GENERIC_DEVICE_ICON)) => icon.to_string(),
+        _ if digital => HDMI_DEVICE_ICON.to_string(),
+        _ => form_factor_icon(&form_factor).to_string(),
+    };
+    (form_factor, icon_name)
+}
+
+fn form_factor_icon(form_factor: &str) -> &'static str {
+    match form_factor {
+        "headphone" => "audio-headphones",
+        "headset" | "hands-free" => "audio-headset",
+        "handset" => "phone",
+        "speaker" | "hifi" | "car" | "portable" => "audio-speakers",
+        "microphone" => "audio-input-microphone",
+        "webcam" => "camera-web",
+        "tv" => HDMI_DEVICE_ICON,
+        "computer" => "computer",
+        _ => GENERIC_DEVICE_ICON,
+    }
+}
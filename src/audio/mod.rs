@@ -1,2 +1,15 @@
+pub mod aliases;
 pub mod audio_manager;
 pub mod audio_manager_dbus;
+pub mod availability;
+pub mod cache;
+pub mod channel_test;
+pub mod delta;
+pub mod device_hints;
+pub mod network_sinks;
+pub mod noise_suppression;
+pub mod server_info;
+pub mod stream_routing;
+pub mod stream_state;
+pub mod volume_info;
+pub mod volume_limit;
@@ -0,0 +1,35 @@
+/// Modules which create sinks for the audio servers and AirPlay receivers announced on the local
+/// network, pipewire-pulse implements both with modules of PipeWire.
+pub const DISCOVERY_MODULES: &[&str] = &["module-zeroconf-discover", "module-raop-discover"];
+
+/// Module loaded by ConnectNetworkSink, which forwards a sink to another audio server.
+pub const TUNNEL_SINK_MODULE: &str = "module-tunnel-sink";
+
+/// Sink which plays on another device as (index, name, description, kind).\
+/// The kind is tunnel for other audio servers and raop for AirPlay receivers.
+pub type NetworkSink = (u32, String, String, String);
+
+/// Returns the kind of a sink created by the discovery or tunnel modules, None for local sinks.\
+/// PulseAudio reports the module as driver, PipeWire only uses the prefixes of the sink names.
+pub fn network_sink_kind(name: &str, driver: &str) -> Option<&'static str> {
+    if driver.contains("raop") || name.starts_with("raop") {
+        Some("raop")
+    } else if driver.contains("tunnel") || name.starts_with("tunnel") {
+        Some("tunnel")
+    } else {
+        None
+    }
+}
+
+/// Arguments of module-tunnel-sink for a host with an optional port, None if the host contains
+/// characters that would end the module arguments.
+pub fn tunnel_sink_arguments(host: &str) -> Option<String> {
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '[' | ']'));
+    if !valid {
+        return None;
+    }
+    Some(format!("server=tcp:{}", host))
+}
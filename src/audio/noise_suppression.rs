@@ -0,0 +1,32 @@
+/// Module loaded for noise suppression, pipewire-pulse implements it with the echo-cancel module
+/// of PipeWire, the same module is therefore used for both audio servers.
+pub const NOISE_SUPPRESSION_MODULE: &str = "module-echo-cancel";
+
+/// Noise suppression of a source as (source index, filtered source index).\
+/// Applications have to record from the filtered source in order to receive the suppressed
+/// audio.
+pub type NoiseSuppressedSource = (u32, u32);
+
+/// Name of the source created by the noise suppression of the source.
+pub fn filtered_source_name(source_name: &str) -> String {
+    format!("{}.noise-suppressed", source_name)
+}
+
+/// Arguments of module-echo-cancel which only enable the noise suppression of the WebRTC
+/// processing, the key names of the processing arguments differ between PulseAudio and PipeWire.
+pub fn noise_suppression_arguments(source_name: &str, description: &str, pipewire: bool) -> String {
+    let aec_args = if pipewire {
+        "webrtc.noise_suppression=true webrtc.gain_control=false webrtc.high_pass_filter=true"
+    } else {
+        "noise_suppression=1 analog_gain_control=0 digital_gain_control=0 high_pass_filter=1"
+    };
+    // quotes would end the quoted module arguments
+    let description = description.replace(['"', '\''], "");
+    format!(
+        "source_master={source} source_name={filtered} sink_name={filtered}.sink \
+         source_properties='device.description=\"{description} (Noise Suppression)\"' \
+         aec_method=webrtc aec_args=\"{aec_args}\"",
+        source = source_name,
+        filtered = filtered_source_name(source_name),
+    )
+}
@@ -0,0 +1,57 @@
+use std::process::{Command, Stdio};
+
+use pulse::{context::introspect::ServerInfo, sample::Format};
+
+use super::channel_test::{channel_positions, ChannelPosition};
+
+/// Rates offered by settings applications, 0 returns to the rate chosen by the server.
+pub const SUPPORTED_SAMPLE_RATES: [u32; 6] = [44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Audio server as (name, version, sample format, sample rate, channels, channel map).\
+/// The sample spec and channel map are the defaults used for new sinks and sources, e.g.
+/// s16le, 48000 and 2 with front-left and front-right.
+pub type AudioServerInfo = (String, String, String, u32, u8, Vec<ChannelPosition>);
+
+pub fn convert_server_info(info: &ServerInfo) -> AudioServerInfo {
+    let spec = info.sample_spec;
+    (
+        info.server_name.clone().unwrap_or_default().to_string(),
+        info.server_version.clone().unwrap_or_default().to_string(),
+        sample_format_name(spec.format),
+        spec.rate,
+        spec.channels,
+        channel_positions(&info.channel_map),
+    )
+}
+
+pub fn sample_format_name(format: Format) -> String {
+    format
+        .to_string()
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| String::from("invalid"))
+}
+
+pub fn is_supported_sample_rate(rate: u32) -> bool {
+    rate == 0 || SUPPORTED_SAMPLE_RATES.contains(&rate)
+}
+
+/// Arguments of pw-metadata forcing the graph rate of PipeWire, 0 removes the forced rate.
+pub fn sample_rate_arguments(rate: u32) -> Vec<String> {
+    ["-n", "settings", "0", "clock.force-rate", &rate.to_string()]
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect()
+}
+
+/// Plain PulseAudio only reads default-sample-rate from daemon.conf on start, the rate can only be
+/// changed at runtime with PipeWire.
+pub fn set_pipewire_sample_rate(rate: u32) -> bool {
+    Command::new("pw-metadata")
+        .args(sample_rate_arguments(rate))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
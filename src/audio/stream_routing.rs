@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::utils::{load_state_file, save_state_file};
+
+const ROUTING_FILE: &str = "stream_routing.toml";
+
+/// Sink names keyed by the application.name of the streams that were last moved to them.
+/// ```toml
+/// Firefox = "alsa_output.usb-headset.analog-stereo"
+/// ```
+static ROUTING: Lazy<Mutex<toml::Table>> = Lazy::new(|| Mutex::new(load_state_file(ROUTING_FILE)));
+
+/// Remembers the sink a stream of the application was moved to, streams without application name
+/// can not be recognized again and are ignored.
+pub fn remember_stream_routing(application: &str, sink_name: &str) {
+    if application.is_empty() || sink_name.is_empty() {
+        return;
+    }
+    let mut routing = ROUTING.lock().unwrap();
+    if routing.get(application).and_then(|sink| sink.as_str()) == Some(sink_name) {
+        return;
+    }
+    routing.insert(
+        application.to_string(),
+        toml::Value::String(sink_name.to_string()),
+    );
+    save_state_file(ROUTING_FILE, &routing);
+}
+
+/// Returns the sink the application was last routed to.
+pub fn learned_sink(application: &str) -> Option<String> {
+    ROUTING
+        .lock()
+        .unwrap()
+        .get(application)?
+        .as_str()
+        .map(String::from)
+}
+
+/// Returns false if nothing was learned for the application.
+pub fn forget_stream_routing(application: &str) -> bool {
+    let mut routing = ROUTING.lock().unwrap();
+    if routing.remove(application).is_none() {
+        return false;
+    }
+    save_state_file(ROUTING_FILE, &routing)
+}
+
+/// Returns all learned routes as (application, sink name).
+pub fn list_stream_routing() -> Vec<(String, String)> {
+    ROUTING
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|(application, sink)| Some((application.clone(), sink.as_str()?.to_string())))
+        .collect()
+}
+
+/// Returns the index of the learned sink if a new stream has to be moved to it, streams already
+/// playing on it and sinks that are currently not available are left alone.
+pub fn routing_target(learned: &str, current_sink: u32, sinks: &[(u32, String)]) -> Option<u32> {
+    sinks
+        .iter()
+        .find(|(_, name)| name == learned)
+        .map(|(index, _)| *index)
+        .filter(|index| *index != current_sink)
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use dbus::{nonblock::SyncConnection, Message, Path};
+use once_cell::sync::Lazy;
+use re_set_lib::audio::audio_structures::{InputStream, Sink};
+
+use super::cache::AUDIO_CACHE;
+use crate::{signals::emit_signal, utils::AUDIO};
+
+/// pa_sink_state_t of suspended sinks, which is exposed as active of the sink.
+const SINK_SUSPENDED: i32 = 2;
+
+/// Playback state of an input stream as (index, corked, suspended).\
+/// Corked streams were paused by their application, suspended streams play to a sink which was
+/// suspended by the audio server, e.g. after being idle.
+pub type StreamCorkState = (u32, bool, bool);
+
+/// Last state reported with StreamCorkStateChanged, streams which never changed are playing.
+static REPORTED: Lazy<Mutex<HashMap<u32, (bool, bool)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn cork_state(input_stream: &InputStream, sink: Option<&Sink>) -> StreamCorkState {
+    let suspended = sink.is_some_and(|sink| sink.active == SINK_SUSPENDED);
+    (input_stream.index, input_stream.corked, suspended)
+}
+
+/// Returns the states of all input streams, empty if the input streams were not listed yet.
+pub fn list_stream_cork_states() -> Vec<StreamCorkState> {
+    let cache = AUDIO_CACHE.read().unwrap();
+    let sinks = cache.sinks.list().unwrap_or_default();
+    cache
+        .input_streams
+        .list()
+        .unwrap_or_default()
+        .iter()
+        .map(|input_stream| {
+            let sink = sinks
+                .iter()
+                .find(|sink| sink.index == input_stream.sink_index);
+            cork_state(input_stream, sink)
+        })
+        .collect()
+}
+
+/// Reports the state of a changed input stream, the sink is taken from the cache.
+pub fn report_input_stream(conn: &Arc<SyncConnection>, input_stream: &InputStream) {
+    let sink = AUDIO_CACHE
+        .read()
+        .unwrap()
+        .sinks
+        .list()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|sink| sink.index == input_stream.sink_index);
+    report(conn, cork_state(input_stream, sink.as_ref()));
+}
+
+/// Reports the states of the input streams playing to a changed sink, as suspending the sink
+/// pauses all of them.
+pub fn report_sink(conn: &Arc<SyncConnection>, sink: &Sink) {
+    let input_streams = AUDIO_CACHE
+        .read()
+        .unwrap()
+        .input_streams
+        .list()
+        .unwrap_or_default();
+    for input_stream in input_streams
+        .iter()
+        .filter(|input_stream| input_stream.sink_index == sink.index)
+    {
+        report(conn, cork_state(input_stream, Some(sink)));
+    }
+}
+
+pub fn forget_input_stream(index: u32) {
+    REPORTED.lock().unwrap().remove(&index);
+}
+
+fn report(conn: &Arc<SyncConnection>, (index, corked, suspended): StreamCorkState) {
+    let previous = REPORTED.lock().unwrap().insert(index, (corked, suspended));
+    if previous.unwrap_or((false, false)) == (corked, suspended) {
+        return;
+    }
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &AUDIO.into(),
+        &"StreamCorkStateChanged".into(),
+    )
+    .append3(index, corked, suspended);
+    emit_signal(conn, msg);
+}
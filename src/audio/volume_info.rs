@@ -0,0 +1,175 @@
+use crossbeam::channel::{Receiver, Sender};
+use dbus::MethodErr;
+use pulse::volume::{Volume, VolumeDB};
+
+use super::cache::AUDIO_CACHE;
+use super::volume_limit::{clamp_volume, volume_to_percent};
+use crate::status::{status_error, StatusCode};
+use crate::utils::{AudioRequest, AudioResponse};
+
+/// Volume of an audio object as (index, percent per channel, dB per channel).\
+/// A volume of 0 is reported as negative infinity dB.
+pub type VolumeInfo = (u32, Vec<u32>, Vec<f64>);
+
+/// Audio objects with a volume, see ListVolumeInfo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeKind {
+    Sink,
+    Source,
+    InputStream,
+    OutputStream,
+}
+
+/// Volume of an audio object as (index, channels, raw volume per channel).
+type RawVolume = (u32, u16, Vec<u32>);
+
+impl VolumeKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sink" => Some(VolumeKind::Sink),
+            "source" => Some(VolumeKind::Source),
+            "input_stream" => Some(VolumeKind::InputStream),
+            "output_stream" => Some(VolumeKind::OutputStream),
+            _ => None,
+        }
+    }
+
+    pub fn set_volume_request(self, index: u32, channels: u16, volume: u32) -> AudioRequest {
+        match self {
+            VolumeKind::Sink => AudioRequest::SetSinkVolume(index, channels, volume),
+            VolumeKind::Source => AudioRequest::SetSourceVolume(index, channels, volume),
+            VolumeKind::InputStream => AudioRequest::SetInputStreamVolume(index, channels, volume),
+            VolumeKind::OutputStream => {
+                AudioRequest::SetOutputStreamVolume(index, channels, volume)
+            }
+        }
+    }
+
+    fn list_request(self) -> AudioRequest {
+        match self {
+            VolumeKind::Sink => AudioRequest::ListSinks,
+            VolumeKind::Source => AudioRequest::ListSources,
+            VolumeKind::InputStream => AudioRequest::ListInputStreams,
+            VolumeKind::OutputStream => AudioRequest::ListOutputStreams,
+        }
+    }
+
+    fn cached(self) -> Option<Vec<RawVolume>> {
+        let cache = AUDIO_CACHE.read().unwrap();
+        match self {
+            VolumeKind::Sink => cache.sinks.list().map(|sinks| {
+                sinks
+                    .into_iter()
+                    .map(|sink| (sink.index, sink.channels, sink.volume))
+                    .collect()
+            }),
+            VolumeKind::Source => cache.sources.list().map(|sources| {
+                sources
+                    .into_iter()
+                    .map(|source| (source.index, source.channels, source.volume))
+                    .collect()
+            }),
+            VolumeKind::InputStream => cache.input_streams.list().map(|streams| {
+                streams
+                    .into_iter()
+                    .map(|stream| (stream.index, stream.channels, stream.volume))
+                    .collect()
+            }),
+            VolumeKind::OutputStream => cache.output_streams.list().map(|streams| {
+                streams
+                    .into_iter()
+                    .map(|stream| (stream.index, stream.channels, stream.volume))
+                    .collect()
+            }),
+        }
+    }
+
+    /// Returns the volumes from the cache, the objects are introspected if the cache was not
+    /// filled yet.
+    fn volumes(
+        self,
+        sender: &Sender<AudioRequest>,
+        receiver: &Receiver<AudioResponse>,
+    ) -> Vec<RawVolume> {
+        if let Some(volumes) = self.cached() {
+            return volumes;
+        }
+        let _ = sender.send(self.list_request());
+        match receiver.recv() {
+            Ok(AudioResponse::Sinks(sinks)) => sinks
+                .into_iter()
+                .map(|sink| (sink.index, sink.channels, sink.volume))
+                .collect(),
+            Ok(AudioResponse::Sources(sources)) => sources
+                .into_iter()
+                .map(|source| (source.index, source.channels, source.volume))
+                .collect(),
+            Ok(AudioResponse::InputStreams(streams)) => streams
+                .into_iter()
+                .map(|stream| (stream.index, stream.channels, stream.volume))
+                .collect(),
+            Ok(AudioResponse::OutputStreams(streams)) => streams
+                .into_iter()
+                .map(|stream| (stream.index, stream.channels, stream.volume))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Returns the volume of every object of the kind in percent and dB.
+pub fn list_volume_info(
+    kind: VolumeKind,
+    sender: &Sender<AudioRequest>,
+    receiver: &Receiver<AudioResponse>,
+) -> Vec<VolumeInfo> {
+    kind.volumes(sender, receiver)
+        .into_iter()
+        .map(|(index, _, volume)| {
+            let percent = volume
+                .iter()
+                .map(|volume| volume_to_percent(*volume))
+                .collect();
+            let db = volume.iter().map(|volume| volume_to_db(*volume)).collect();
+            (index, percent, db)
+        })
+        .collect()
+}
+
+/// Sets all channels of an object to the volume in percent, rounded to the nearest raw volume
+/// and clamped to the volume limit.\
+/// Returns the raw volume that was applied.
+pub fn set_volume_percent(
+    kind: VolumeKind,
+    index: u32,
+    percent: f64,
+    sender: &Sender<AudioRequest>,
+    receiver: &Receiver<AudioResponse>,
+) -> Result<u32, MethodErr> {
+    if !percent.is_finite() || percent < 0.0 {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Volume has to be a positive percentage",
+        ));
+    }
+    let channels = kind
+        .volumes(sender, receiver)
+        .into_iter()
+        .find(|(object_index, _, _)| *object_index == index)
+        .map(|(_, channels, _)| channels);
+    let Some(channels) = channels else {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown audio object",
+        ));
+    };
+    let volume = (percent * Volume::NORMAL.0 as f64 / 100.0).round();
+    let volume = clamp_volume(volume.min(Volume::MAX.0 as f64) as u32);
+    let _ = sender.send(kind.set_volume_request(index, channels, volume));
+    Ok(volume)
+}
+
+/// Converts a raw volume to dB with the cubic mapping of pulse.
+pub fn volume_to_db(volume: u32) -> f64 {
+    VolumeDB::from(Volume(volume)).0
+}
@@ -0,0 +1,92 @@
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use pulse::volume::Volume;
+
+use crate::utils::{get_daemon_config_value, load_state_file, save_state_file};
+
+const POLICY_FILE: &str = "volume_policy.toml";
+/// Relative changes stop at this volume unless a limit is configured, absolute setters are not
+/// limited at all in order to keep overamplification working.
+const DEFAULT_RELATIVE_LIMIT: u32 = 100;
+const DEFAULT_VOLUME_STEP: u32 = 5;
+/// Highest limit that can be configured in percent, anything above distorts on most devices.
+pub const MAX_VOLUME_LIMIT: u32 = 200;
+
+/// Volume policy of the user, a configured limit is enforced on every volume setter regardless
+/// of the client.\
+/// The limit set with SetVolumeLimit takes precedence over the config file.
+/// ```toml
+/// [Daemon]
+/// max_volume = 150
+/// volume_step = 5
+/// ```
+static VOLUME_POLICY: Lazy<RwLock<VolumePolicy>> = Lazy::new(|| RwLock::new(VolumePolicy::load()));
+
+#[derive(Debug)]
+struct VolumePolicy {
+    limit: Option<u32>,
+    step: u32,
+}
+
+impl VolumePolicy {
+    fn load() -> Self {
+        let state = load_state_file(POLICY_FILE);
+        let limit = state
+            .get("max_volume")
+            .and_then(|value| value.as_integer())
+            .or_else(|| get_daemon_config_value("max_volume").and_then(|value| value.as_integer()))
+            .map(|limit| limit.clamp(1, MAX_VOLUME_LIMIT as i64) as u32);
+        let step = get_daemon_config_value("volume_step")
+            .and_then(|value| value.as_integer())
+            .map(|step| step.clamp(1, MAX_VOLUME_LIMIT as i64) as u32)
+            .unwrap_or(DEFAULT_VOLUME_STEP);
+        Self { limit, step }
+    }
+}
+
+/// Returns the configured maximum volume in percent, None if the volume is not limited.
+pub fn volume_limit() -> Option<u32> {
+    VOLUME_POLICY.read().unwrap().limit
+}
+
+/// Returns the maximum volume in percent reached by relative changes like VolumeUp.
+pub fn relative_volume_limit() -> u32 {
+    volume_limit().unwrap_or(DEFAULT_RELATIVE_LIMIT)
+}
+
+/// Returns the step in percent used when a client does not provide one.
+pub fn default_volume_step() -> u32 {
+    VOLUME_POLICY.read().unwrap().step
+}
+
+/// Sets and persists the maximum volume in percent, the limit has to be within 1 and
+/// MAX_VOLUME_LIMIT.
+pub fn set_volume_limit(percent: u32) -> bool {
+    if percent == 0 || percent > MAX_VOLUME_LIMIT {
+        return false;
+    }
+    VOLUME_POLICY.write().unwrap().limit = Some(percent);
+    let mut table = load_state_file(POLICY_FILE);
+    table.insert(
+        String::from("max_volume"),
+        toml::Value::Integer(percent as i64),
+    );
+    save_state_file(POLICY_FILE, &table)
+}
+
+/// Clamps a raw pulse volume to the configured limit, volumes are left untouched without one.
+pub fn clamp_volume(volume: u32) -> u32 {
+    match volume_limit() {
+        Some(limit) => volume.min(percent_to_volume(limit)),
+        None => volume,
+    }
+}
+
+pub fn percent_to_volume(percent: u32) -> u32 {
+    (percent as u64 * Volume::NORMAL.0 as u64 / 100) as u32
+}
+
+pub fn volume_to_percent(volume: u32) -> u32 {
+    ((volume as u64 * 100 + Volume::NORMAL.0 as u64 / 2) / Volume::NORMAL.0 as u64) as u32
+}
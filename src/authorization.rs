@@ -0,0 +1,171 @@
+use std::{
+    fs,
+    future::Future,
+    os::unix::fs::MetadataExt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use dbus::{
+    blocking::Connection,
+    nonblock::{Proxy, SyncConnection},
+    Message, MethodErr,
+};
+use re_set_lib::LOG;
+
+use crate::status::{status_error, StatusCode};
+use crate::utils::{get_daemon_config_value, session_connection};
+
+/// Connection of the daemon, used to look up the callers.
+static DAEMON_CONNECTION: Mutex<Option<Arc<SyncConnection>>> = Mutex::new(None);
+
+/// Separate connection of authorize_blocking, opened on the first call and reused afterwards.
+static BLOCKING_CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+pub(crate) fn set_authorization_connection(conn: Arc<SyncConnection>) {
+    *DAEMON_CONNECTION.lock().unwrap() = Some(conn);
+}
+
+/// Checks whether the sender of a message is allowed to call a destructive method.\
+/// Allowed are the user running the daemon and every uid listed in `authorized_uids` within the
+/// `[Daemon]` section of the config file.\
+/// The uid of the sender is queried from the bus asynchronously, hence the result has to be
+/// awaited within the async part of the method.\
+/// Read-only methods do not need to call this.
+pub fn authorize(message: &Message) -> impl Future<Output = Result<(), MethodErr>> + Send {
+    let method = message
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+    let sender = message.sender().map(|sender| sender.to_string());
+    let conn = DAEMON_CONNECTION.lock().unwrap().clone();
+    async move {
+        let Some(sender) = sender else {
+            return Err(denied(&method, "the caller could not be identified"));
+        };
+        let uid = match (sender_uid_override(&sender), conn) {
+            (Some(uid), _) => Some(uid),
+            (None, Some(conn)) => get_sender_uid(conn, &sender).await,
+            (None, None) => None,
+        };
+        check_uid(&method, &sender, uid)
+    }
+}
+
+/// Blocking variant of [`authorize`] for methods which need the Crossroads after authorizing
+/// and can therefore not await.\
+/// The uid is queried with a separate connection, as the daemon connection only handles the reply
+/// once the current method returns. The connection is kept for later calls.
+pub fn authorize_blocking(message: &Message) -> Result<(), MethodErr> {
+    let method = message
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+    let Some(sender) = message.sender().map(|sender| sender.to_string()) else {
+        return Err(denied(&method, "the caller could not be identified"));
+    };
+    let uid = sender_uid_override(&sender).or_else(|| get_sender_uid_blocking(&sender));
+    check_uid(&method, &sender, uid)
+}
+
+/// Uids reported for senders instead of asking the bus, tests use this to call as a foreign user.
+#[cfg(test)]
+static SENDER_UIDS: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+#[cfg(test)]
+pub(crate) fn set_sender_uid(sender: &str, uid: u32) {
+    SENDER_UIDS.lock().unwrap().push((sender.to_string(), uid));
+}
+
+#[cfg(test)]
+fn sender_uid_override(sender: &str) -> Option<u32> {
+    SENDER_UIDS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(name, _)| name == sender)
+        .map(|(_, uid)| *uid)
+}
+
+#[cfg(not(test))]
+fn sender_uid_override(_sender: &str) -> Option<u32> {
+    None
+}
+
+fn check_uid(method: &str, sender: &str, uid: Option<u32>) -> Result<(), MethodErr> {
+    if uid.is_none() {
+        return Err(denied(
+            method,
+            &format!("the uid of {} could not be determined", sender),
+        ));
+    }
+    let uid = uid.unwrap();
+    if is_authorized_uid(uid) {
+        return Ok(());
+    }
+    LOG!(format!("Denied call to {} from uid {}", method, uid));
+    Err(denied(
+        method,
+        &format!(
+            "uid {} is not authorized, add it to authorized_uids in the [Daemon] section of the ReSet config",
+            uid
+        ),
+    ))
+}
+
+fn denied(method: &str, reason: &str) -> MethodErr {
+    status_error(
+        StatusCode::AuthorizationDenied,
+        &format!("Calling {} requires authorization: {}.", method, reason),
+    )
+}
+
+fn is_authorized_uid(uid: u32) -> bool {
+    if get_daemon_uid() == Some(uid) {
+        return true;
+    }
+    let allowed = get_daemon_config_value("authorized_uids").and_then(|value| value.as_array());
+    if let Some(allowed) = allowed {
+        return allowed
+            .iter()
+            .any(|entry| entry.as_integer() == Some(uid as i64));
+    }
+    false
+}
+
+fn get_daemon_uid() -> Option<u32> {
+    fs::metadata("/proc/self")
+        .ok()
+        .map(|metadata| metadata.uid())
+}
+
+async fn get_sender_uid(conn: Arc<SyncConnection>, sender: &str) -> Option<u32> {
+    let proxy = Proxy::new(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(1000),
+        conn,
+    );
+    let res: Result<(u32,), dbus::Error> = proxy
+        .method_call("org.freedesktop.DBus", "GetConnectionUnixUser", (sender,))
+        .await;
+    res.ok().map(|res| res.0)
+}
+
+fn get_sender_uid_blocking(sender: &str) -> Option<u32> {
+    let mut conn = BLOCKING_CONNECTION.lock().unwrap();
+    if !conn
+        .as_ref()
+        .is_some_and(|conn| conn.channel().is_connected())
+    {
+        *conn = Some(session_connection().ok()?);
+    }
+    let proxy = conn.as_ref().unwrap().with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(1000),
+    );
+    let res: Result<(u32,), dbus::Error> =
+        proxy.method_call("org.freedesktop.DBus", "GetConnectionUnixUser", (sender,));
+    res.ok().map(|res| res.0)
+}
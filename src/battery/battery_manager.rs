@@ -0,0 +1,214 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::{MatchRule, SignalArgs},
+    nonblock::SyncConnection,
+    Message, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use crate::{
+    signals::{emit_changed_signal, emit_signal},
+    utils::BATTERY,
+};
+
+pub const UPOWER_NAME: &str = "org.freedesktop.UPower";
+pub const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_DEVICE_INTERFACE: &str = "org.freedesktop.UPower.Device";
+const UPOWER_DEVICES_PATH: &str = "/org/freedesktop/UPower/devices";
+
+// UpDeviceKind
+const KIND_LINE_POWER: u32 = 1;
+
+/// Battery as (path, kind, model, percentage, state, power supply).\
+/// The kind is e.g. battery for the system battery, mouse or keyboard. The state is one of
+/// unknown, charging, discharging, empty, fully-charged, pending-charge or pending-discharge.
+/// Power supply is true for batteries powering the system.
+pub type Battery = (Path<'static>, String, String, f64, String, bool);
+
+/// Name of an UpDeviceKind.
+pub fn battery_kind(kind: u32) -> &'static str {
+    match kind {
+        1 => "line-power",
+        2 => "battery",
+        3 => "ups",
+        4 => "monitor",
+        5 => "mouse",
+        6 => "keyboard",
+        7 => "pda",
+        8 => "phone",
+        9 => "media-player",
+        10 => "tablet",
+        11 => "computer",
+        12 => "gaming-input",
+        13 => "pen",
+        14 => "touchpad",
+        15 => "modem",
+        16 => "network",
+        17 => "headset",
+        18 => "speakers",
+        19 => "headphones",
+        20 => "video",
+        21 => "other-audio",
+        22 => "remote-control",
+        23 => "printer",
+        24 => "scanner",
+        25 => "camera",
+        26 => "wearable",
+        27 => "toy",
+        28 => "bluetooth-generic",
+        _ => "unknown",
+    }
+}
+
+/// Name of an UpDeviceState.
+pub fn battery_state(state: u32) -> &'static str {
+    match state {
+        1 => "charging",
+        2 => "discharging",
+        3 => "empty",
+        4 => "fully-charged",
+        5 => "pending-charge",
+        6 => "pending-discharge",
+        _ => "unknown",
+    }
+}
+
+/// Converts the properties of an UPower device, None for power supplies without a battery and
+/// batteries that are not present.
+pub fn convert_battery(path: Path<'static>, properties: &PropMap) -> Option<Battery> {
+    let kind = prop_cast::<u32>(properties, "Type").copied().unwrap_or(0);
+    let present = prop_cast::<bool>(properties, "IsPresent")
+        .copied()
+        .unwrap_or(false);
+    if kind == KIND_LINE_POWER || !present {
+        return None;
+    }
+    Some((
+        path,
+        battery_kind(kind).to_string(),
+        prop_cast::<String>(properties, "Model")
+            .cloned()
+            .unwrap_or_default(),
+        prop_cast::<f64>(properties, "Percentage")
+            .copied()
+            .unwrap_or(0.0),
+        battery_state(prop_cast::<u32>(properties, "State").copied().unwrap_or(0)).to_string(),
+        prop_cast::<bool>(properties, "PowerSupply")
+            .copied()
+            .unwrap_or(false),
+    ))
+}
+
+fn get_battery(path: Path<'static>) -> Option<Battery> {
+    let res = dbus_method!(
+        UPOWER_NAME,
+        path.clone(),
+        "GetAll",
+        "org.freedesktop.DBus.Properties",
+        (UPOWER_DEVICE_INTERFACE,),
+        1000,
+        (PropMap,),
+    );
+    convert_battery(path, &res.ok()?.0)
+}
+
+/// Returns the system batteries and the batteries of peripherals, e.g. wireless mice, system
+/// batteries first.
+pub fn get_batteries() -> Vec<Battery> {
+    let res = dbus_method!(
+        UPOWER_NAME,
+        Path::from(UPOWER_PATH),
+        "EnumerateDevices",
+        UPOWER_NAME,
+        (),
+        1000,
+        (Vec<Path<'static>>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not get batteries from UPower: {}", _error),
+            ErrorLevel::PartialBreakage
+        );
+        return Vec::new();
+    }
+    let mut batteries: Vec<Battery> = res.unwrap().0.into_iter().filter_map(get_battery).collect();
+    batteries.sort_by(|a, b| b.5.cmp(&a.5).then(a.0.cmp(&b.0)));
+    batteries
+}
+
+/// Emits BatteryChanged when UPower reports a change of a battery or a new peripheral and
+/// BatteryRemoved once a peripheral disappeared.
+pub fn start_battery_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let mut device_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&UPOWER_NAME.into()),
+            Some(&Path::from(UPOWER_DEVICES_PATH)),
+        )
+        .static_clone();
+        device_changed.path_is_namespace = true;
+        let changed_ref = connection.clone();
+        let changed = conn.add_match(
+            device_changed,
+            move |_: PropertiesPropertiesChanged, _, msg| {
+                if let Some(path) = msg.path() {
+                    emit_battery_changed(&changed_ref, path.into_static());
+                }
+                true
+            },
+        );
+        let added_ref = connection.clone();
+        let added = conn.add_match(
+            MatchRule::new_signal(UPOWER_NAME, "DeviceAdded"),
+            move |(device,): (Path<'static>,), _, _| {
+                emit_battery_changed(&added_ref, device);
+                true
+            },
+        );
+        let removed = conn.add_match(
+            MatchRule::new_signal(UPOWER_NAME, "DeviceRemoved"),
+            move |(device,): (Path<'static>,), _, _| {
+                let msg = Message::signal(
+                    &Path::from(DBUS_PATH!()),
+                    &BATTERY.into(),
+                    &"BatteryRemoved".into(),
+                )
+                .append1(device);
+                emit_signal(&connection, msg);
+                true
+            },
+        );
+        if let Err(_error) = changed.and(added).and(removed) {
+            ERROR!(
+                format!("Failed to match signal on UPower {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on UPower.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn emit_battery_changed(conn: &Arc<SyncConnection>, path: Path<'static>) {
+    let Some(battery) = get_battery(path.clone()) else {
+        return;
+    };
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BATTERY.into(),
+        &"BatteryChanged".into(),
+    )
+    .append1(battery);
+    // percentages of peripherals change often, throttled clients only need the latest state
+    emit_changed_signal(conn, msg, path);
+}
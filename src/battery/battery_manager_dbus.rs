@@ -0,0 +1,20 @@
+use dbus::Path;
+use dbus_crossroads::Crossroads;
+
+use crate::{utils::BATTERY, DaemonData};
+
+use super::battery_manager::{get_batteries, Battery};
+
+pub fn setup_battery_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(BATTERY, |c| {
+        c.signal::<(Battery,), _>("BatteryChanged", ("battery",));
+        c.signal::<(Path<'static>,), _>("BatteryRemoved", ("path",));
+        c.method_with_cr_async(
+            "ListBatteries",
+            (),
+            ("batteries",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((get_batteries(),))) },
+        );
+    });
+    token
+}
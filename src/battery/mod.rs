@@ -0,0 +1,2 @@
+pub mod battery_manager;
+pub mod battery_manager_dbus;
@@ -0,0 +1,120 @@
+use std::{thread, time::Duration};
+
+use dbus::{blocking::Connection, message::SignalArgs, Path};
+use re_set_lib::bluetooth::bluetooth_signals::BluetoothDeviceAdded;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::utils::{get_daemon_config_value, load_state_file, save_state_file};
+
+const ADAPTER_STATE_FILE: &str = "bluetooth_adapters.toml";
+
+/// Power and discoverable states chosen by the user, keyed by the adapter address.
+/// ```toml
+/// ["00:1A:7D:DA:71:13"]
+/// Powered = true
+/// Discoverable = false
+/// ```
+/// Restoring can be disabled with restore_bluetooth_adapter_state = false within the daemon
+/// section of the config.
+fn restore_enabled() -> bool {
+    get_daemon_config_value("restore_bluetooth_adapter_state")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true)
+}
+
+/// The address stays the same across reboots, while hci indices depend on probing order.
+fn adapter_key(path: &Path<'static>) -> String {
+    get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "Address",
+        String,
+    )
+    .unwrap_or_else(|_| path.to_string())
+}
+
+/// Stores a user chosen adapter property, either Powered or Discoverable.
+pub fn save_adapter_state(path: &Path<'static>, property: &str, enabled: bool) {
+    if !restore_enabled() {
+        return;
+    }
+    let mut table = load_state_file(ADAPTER_STATE_FILE);
+    let entry = table
+        .entry(adapter_key(path))
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if let Some(entry) = entry.as_table_mut() {
+        entry.insert(property.to_string(), toml::Value::Boolean(enabled));
+    }
+    save_state_file(ADAPTER_STATE_FILE, &table);
+}
+
+/// Reapplies the stored states of an adapter, unknown adapters are left untouched.
+pub fn restore_adapter_state(path: &Path<'static>) {
+    if !restore_enabled() {
+        return;
+    }
+    let table = load_state_file(ADAPTER_STATE_FILE);
+    let entry = table
+        .get(&adapter_key(path))
+        .and_then(|entry| entry.as_table());
+    if entry.is_none() {
+        return;
+    }
+    for property in ["Powered", "Discoverable"] {
+        let enabled = entry
+            .unwrap()
+            .get(property)
+            .and_then(|value| value.as_bool());
+        if enabled.is_none() {
+            continue;
+        }
+        let res = set_dbus_property!(
+            BLUEZ_INTERFACE!(),
+            path.clone(),
+            BLUEZ_ADAPTER_INTERFACE!(),
+            property,
+            enabled.unwrap(),
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!(
+                    "Could not restore {} of bluetooth adapter {}: {}",
+                    property, path, _error
+                ),
+                ErrorLevel::Recoverable
+            );
+        }
+    }
+    LOG!(format!("Restored state of bluetooth adapter {}", path));
+}
+
+/// Restores the state of adapters plugged in while the daemon is running.
+pub fn start_adapter_hotplug_listener() {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let adapter_added =
+            BluetoothDeviceAdded::match_rule(Some(&BLUEZ_INTERFACE!().into()), None).static_clone();
+        let res = conn.add_match(adapter_added, move |ir: BluetoothDeviceAdded, _, _| {
+            if ir.interfaces.contains_key(BLUEZ_ADAPTER_INTERFACE!()) {
+                restore_adapter_state(&ir.object);
+            }
+            true
+        });
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on bluez {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on bluez.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
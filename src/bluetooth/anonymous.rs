@@ -0,0 +1,146 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use once_cell::sync::Lazy;
+
+use crate::{
+    signals::emit_changed_signal,
+    utils::{convert_bluetooth_map_bool, get_daemon_config_value},
+};
+
+/// Set with `--raw-bluetooth-devices`, reports every device found by bluez.
+static RAW_DEVICES: AtomicBool = AtomicBool::new(false);
+
+static ANONYMOUS_DEVICES: Lazy<Mutex<AnonymousDevices>> =
+    Lazy::new(|| Mutex::new(AnonymousDevices::default()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceVisibility {
+    /// Anonymous device, only counted.
+    Hidden,
+    /// Previously anonymous device which is identifiable now, clients have not seen it yet.
+    Revealed,
+    Visible,
+}
+
+/// Anonymous LE devices, e.g. beacons and phones advertising with rotating addresses, which
+/// would otherwise flood clients with hundreds of unnamed entries while scanning.
+#[derive(Default)]
+pub struct AnonymousDevices {
+    devices: HashSet<Path<'static>>,
+}
+
+impl AnonymousDevices {
+    pub fn update(&mut self, path: &Path<'static>, anonymous: bool) -> DeviceVisibility {
+        if anonymous {
+            self.devices.insert(path.clone());
+            DeviceVisibility::Hidden
+        } else if self.devices.remove(path) {
+            DeviceVisibility::Revealed
+        } else {
+            DeviceVisibility::Visible
+        }
+    }
+
+    /// Returns true if the device was hidden.
+    pub fn remove(&mut self, path: &Path<'static>) -> bool {
+        self.devices.remove(path)
+    }
+
+    pub fn count(&self) -> u32 {
+        self.devices.len() as u32
+    }
+}
+
+pub fn enable_raw_devices() {
+    RAW_DEVICES.store(true, Ordering::SeqCst);
+}
+
+/// Raw mode can also be enabled with bluetooth_raw_devices = true within the `[Daemon]` section
+/// of the config.
+pub fn raw_devices_enabled() -> bool {
+    RAW_DEVICES.load(Ordering::SeqCst)
+        || get_daemon_config_value("bluetooth_raw_devices")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+}
+
+/// Devices with a random address without name and services, which are not known to the user.
+pub fn is_anonymous_device(map: &PropMap) -> bool {
+    let random = prop_cast::<String>(map, "AddressType")
+        .is_some_and(|address_type| address_type == "random");
+    let named = prop_cast::<String>(map, "Name").is_some_and(|name| !name.is_empty());
+    let services = prop_cast::<Vec<String>>(map, "UUIDs").is_some_and(|uuids| !uuids.is_empty());
+    let known = ["Paired", "Bonded", "Trusted", "Connected"]
+        .iter()
+        .any(|property| convert_bluetooth_map_bool(map.get(*property)));
+    random && !named && !services && !known
+}
+
+/// Tracks the device and returns (visibility, new count if the count changed).
+fn track_device(path: &Path<'static>, map: &PropMap) -> (DeviceVisibility, Option<u32>) {
+    let mut devices = ANONYMOUS_DEVICES.lock().unwrap();
+    let previous = devices.count();
+    let visibility = devices.update(path, is_anonymous_device(map));
+    let count = devices.count();
+    let visibility = if raw_devices_enabled() {
+        DeviceVisibility::Visible
+    } else {
+        visibility
+    };
+    (visibility, (count != previous).then_some(count))
+}
+
+/// Used for listings, which are not followed by a count change.
+pub fn is_hidden_device(path: &Path<'static>, map: &PropMap) -> bool {
+    track_device(path, map).0 == DeviceVisibility::Hidden
+}
+
+/// Tracks the added or changed device and emits AnonymousDeviceCountChanged if necessary.
+pub fn handle_device_visibility(
+    conn: &Arc<SyncConnection>,
+    path: &Path<'static>,
+    map: &PropMap,
+) -> DeviceVisibility {
+    let (visibility, count) = track_device(path, map);
+    if let Some(count) = count {
+        emit_anonymous_device_count(conn, count);
+    }
+    visibility
+}
+
+/// Returns true if the removal should not be reported, as clients never saw the device.
+pub fn handle_device_removed(conn: &Arc<SyncConnection>, path: &Path<'static>) -> bool {
+    let (hidden, count) = {
+        let mut devices = ANONYMOUS_DEVICES.lock().unwrap();
+        (devices.remove(path), devices.count())
+    };
+    if hidden {
+        emit_anonymous_device_count(conn, count);
+    }
+    hidden && !raw_devices_enabled()
+}
+
+pub fn anonymous_device_count() -> u32 {
+    ANONYMOUS_DEVICES.lock().unwrap().count()
+}
+
+fn emit_anonymous_device_count(conn: &Arc<SyncConnection>, count: u32) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &"AnonymousDeviceCountChanged".into(),
+    )
+    .append1(count);
+    emit_changed_signal(conn, msg, "AnonymousDeviceCount");
+}
@@ -1,139 +1,235 @@
-use dbus::{Message, Path};
-use dbus_crossroads::Crossroads;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 
-use crate::DaemonData;
+use dbus::{
+    blocking::SyncConnection,
+    channel::{MatchingReceiver, Sender},
+    message::MatchRule,
+    nonblock, Message, MethodErr, Path,
+};
+use once_cell::sync::OnceCell;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
 
-#[allow(dead_code)]
-pub fn setup_bluetooth_agent(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
-    let token = cross.register("org.bluez.Agent1", |c| {
-        c.method(
-            "RequestPinCode",
-            ("device",),
-            ("result",),
-            move |ctx, d: &mut DaemonData, (_device,): (Path<'static>,)| {
-                println!("pincode requested!");
-                if d.bluetooth_agent.in_progress {
-                    return Ok(("No pairing in progress.",));
-                }
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"PincodeRequested".into(),
-                );
-                ctx.push_msg(msg);
-                Ok(("",))
-                // TODO handle receive with a dynamic dbus function? does that even exist?
-            },
-        );
-        c.method(
-            "DisplayPinCode",
-            ("device", "pincode"),
-            (),
-            move |ctx, _d: &mut DaemonData, (_device, pincode): (Path<'static>, String)| {
-                println!("display pincode");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"DisplayPinCode".into(),
-                )
-                .append1(pincode);
-                ctx.push_msg(msg);
-                Ok(())
-            },
-        );
-        c.method(
-            "RequestPasskey",
-            ("device",),
-            ("passkey",),
-            move |ctx, _d: &mut DaemonData, (_device,): (Path<'static>,)| {
-                println!("request passkey");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"RequestPassKey".into(),
-                );
-                ctx.push_msg(msg);
-                #[allow(clippy::unnecessary_cast)]
-                Ok((0 as u32,))
-                // leave me alone clippy, I am dealing with C code
-            },
-        );
-        c.method(
-            "DisplayPasskey",
-            ("device", "passkey", "entered"),
-            (),
-            move |ctx,
-                  _d: &mut DaemonData,
-                  (_device, passkey, entered): (Path<'static>, u32, u16)| {
-                println!("display passkey");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"DisplayPassKey".into(),
-                )
-                .append2(passkey, entered);
-                ctx.push_msg(msg);
-                Ok(())
-            },
+use super::pairing::{
+    clear_pairing_pin, finish_pairing, format_passkey, pin_display_signal, request_pin_code,
+    set_pairing_stage, PairingStage,
+};
+use crate::{signals::emit_signal, utils::is_sent_by_owner};
+
+const BLUEZ_AGENT_PATH: &str = "/org/Xetibo/ReSet/BluetoothAgent";
+const BLUEZ_AGENT1_INTERFACE: &str = "org.bluez.Agent1";
+
+/// Connection exporting the agent, bluez only calls agents on the connection which registered
+/// them.
+static AGENT: OnceCell<Arc<SyncConnection>> = OnceCell::new();
+
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Exports the pairing agent on a separate system bus connection, the agent is registered with
+/// bluez once the first pairing is started, see register_bluetooth_agent.\
+/// Requests of bluez are forwarded to clients with the signals of the Bluetooth interface.
+pub fn start_bluetooth_agent(connection: Arc<nonblock::SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let agent = Arc::new(dbus_sync_connection!()?);
+        let agent_ref = agent.clone();
+        agent.start_receive(
+            MatchRule::new_method_call()
+                .with_path(BLUEZ_AGENT_PATH)
+                .with_interface(BLUEZ_AGENT1_INTERFACE),
+            Box::new(move |msg, _| {
+                handle_agent_call(&connection, &agent_ref, msg);
+                true
+            }),
         );
-        c.method(
-            "RequestConfirmation",
-            ("device", "passkey"),
-            (),
-            move |ctx, _d: &mut DaemonData, (_device, passkey): (Path<'static>, u32)| {
-                println!("request confirmation");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"RequestConfirmation".into(),
-                )
-                .append1(passkey);
-                ctx.push_msg(msg);
-                Ok(())
-            },
+        let _ = AGENT.set(agent.clone());
+        loop {
+            agent.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+/// Registers the agent with the AgentManager of bluez.\
+/// Returns false if the agent is not running yet or bluez rejected it.
+pub fn register_bluetooth_agent() -> bool {
+    let res = agent_manager_call(
+        "RegisterAgent",
+        (Path::from(BLUEZ_AGENT_PATH), "DisplayYesNo"),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to register bluetooth agent: {}", _error),
+            ErrorLevel::PartialBreakage
         );
-        c.method(
-            "RequestAuthorization",
-            ("device",),
-            (),
-            move |ctx, _d: &mut DaemonData, (_device,): (Path<'static>,)| {
-                println!("request authorization");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"RequestAuthorization".into(),
-                );
-                ctx.push_msg(msg);
-                Ok(())
-            },
+        return false;
+    }
+    LOG!("Registered bluetooth agent");
+    REGISTERED.store(true, Ordering::SeqCst);
+    true
+}
+
+/// Returns false if the agent was not registered.
+pub fn unregister_bluetooth_agent() -> bool {
+    if !REGISTERED.load(Ordering::SeqCst) {
+        return false;
+    }
+    let res = agent_manager_call("UnregisterAgent", (Path::from(BLUEZ_AGENT_PATH),));
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to unregister bluetooth agent {}", _error),
+            ErrorLevel::PartialBreakage
         );
-        c.method(
-            "AuthorizeService",
-            ("device", "uuid"),
-            (),
-            move |ctx, _d: &mut DaemonData, (_device, uuid): (Path<'static>, String)| {
-                println!("authorize service");
-                let msg = Message::signal(
-                    &Path::from(DBUS_PATH!()),
-                    &BLUETOOTH_INTERFACE!().into(),
-                    &"AuthorizeService".into(),
-                )
-                .append1(uuid);
-                ctx.push_msg(msg);
-                Ok(())
-            },
+        return false;
+    }
+    REGISTERED.store(false, Ordering::SeqCst);
+    true
+}
+
+pub fn bluetooth_agent_registered() -> bool {
+    REGISTERED.load(Ordering::SeqCst)
+}
+
+fn agent_manager_call<A: dbus::arg::AppendAll>(method: &str, args: A) -> Result<(), dbus::Error> {
+    let Some(agent) = AGENT.get() else {
+        return Err(dbus::Error::new_custom(
+            "AgentUnavailable",
+            "The bluetooth agent is not running.",
+        ));
+    };
+    agent
+        .with_proxy(
+            BLUEZ_INTERFACE!(),
+            Path::from(BLUEZ_PATH!()),
+            Duration::from_millis(1000),
+        )
+        .method_call(BLUEZ_AGENT_INTERFACE!(), method, args)
+}
+
+fn handle_agent_call(
+    conn: &Arc<nonblock::SyncConnection>,
+    agent: &Arc<SyncConnection>,
+    msg: Message,
+) {
+    let member = msg
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+    // every other peer could otherwise confirm pairings or read the PIN in the name of bluez
+    if !is_sent_by_owner(agent, &msg, BLUEZ_INTERFACE!()) {
+        LOG!(format!(
+            "Rejected bluetooth agent call {} from {:?}",
+            member,
+            msg.sender()
+        ));
+        let _ = agent.send(
+            MethodErr::from((
+                "org.freedesktop.DBus.Error.AccessDenied",
+                "Only bluez may call the bluetooth agent",
+            ))
+            .to_message(&msg),
         );
-        c.method("Cancel", (), (), move |_, d: &mut DaemonData, ()| {
-            println!("called cancel");
-            d.bluetooth_agent.in_progress = false;
-            Ok(())
-        });
-        c.method("Release", (), (), move |_, d: &mut DaemonData, ()| {
-            println!("called release");
-            d.bluetooth_agent.in_progress = false;
-            Ok(())
-        });
-    });
+        return;
+    }
+    let reply = match agent_reply(conn, &member, &msg) {
+        Ok(reply) => reply,
+        Err(error) => error.to_message(&msg),
+    };
+    let _ = agent.send(reply);
+}
+
+fn agent_reply(
+    conn: &Arc<nonblock::SyncConnection>,
+    member: &str,
+    msg: &Message,
+) -> Result<Message, MethodErr> {
+    let reply = match member {
+        "RequestPinCode" => {
+            let device: Path<'static> = msg.read1()?;
+            let pin = request_pin_code(&device);
+            if pin.is_err() {
+                set_pairing_stage(device.clone(), PairingStage::PinRequested);
+                emit_signal(conn, bluetooth_signal("PincodeRequested"));
+            }
+            let pin = pin?;
+            emit_signal(conn, pin_display_signal(device, pin.clone()));
+            msg.method_return().append1(pin)
+        }
+        "DisplayPinCode" => {
+            let (device, pincode): (Path<'static>, String) = msg.read2()?;
+            emit_signal(
+                conn,
+                bluetooth_signal("DisplayPinCode").append1(pincode.clone()),
+            );
+            emit_signal(conn, pin_display_signal(device, pincode));
+            msg.method_return()
+        }
+        "RequestPasskey" => {
+            let device: Path<'static> = msg.read1()?;
+            set_pairing_stage(device, PairingStage::PinRequested);
+            emit_signal(conn, bluetooth_signal("RequestPassKey"));
+            msg.method_return().append1(0_u32)
+        }
+        "DisplayPasskey" => {
+            let (device, passkey, entered): (Path<'static>, u32, u16) = msg.read3()?;
+            emit_signal(
+                conn,
+                bluetooth_signal("DisplayPassKey").append2(passkey, entered),
+            );
+            // called again for every typed digit, the passkey itself stays the same
+            if entered == 0 {
+                emit_signal(conn, pin_display_signal(device, format_passkey(passkey)));
+            }
+            msg.method_return()
+        }
+        "RequestConfirmation" => {
+            let (device, passkey): (Path<'static>, u32) = msg.read2()?;
+            set_pairing_stage(device, PairingStage::ConfirmationRequested);
+            emit_signal(
+                conn,
+                bluetooth_signal("RequestConfirmation").append1(passkey),
+            );
+            msg.method_return()
+        }
+        "RequestAuthorization" => {
+            let device: Path<'static> = msg.read1()?;
+            set_pairing_stage(device, PairingStage::AuthorizationRequested);
+            emit_signal(conn, bluetooth_signal("RequestAuthorization"));
+            msg.method_return()
+        }
+        "AuthorizeService" => {
+            let (_device, uuid): (Path<'static>, String) = msg.read2()?;
+            emit_signal(conn, bluetooth_signal("AuthorizeService").append1(uuid));
+            msg.method_return()
+        }
+        "Cancel" => {
+            LOG!("Bluetooth pairing was cancelled");
+            clear_pairing_pin(None);
+            finish_pairing(None);
+            msg.method_return()
+        }
+        "Release" => {
+            LOG!("Bluetooth agent was released");
+            clear_pairing_pin(None);
+            finish_pairing(None);
+            REGISTERED.store(false, Ordering::SeqCst);
+            msg.method_return()
+        }
+        _ => return Err(MethodErr::no_method(member)),
+    };
+    Ok(reply)
+}
 
-    token
+fn bluetooth_signal(name: &'static str) -> Message {
+    Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &name.into(),
+    )
 }
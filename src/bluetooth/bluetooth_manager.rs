@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicBool, AtomicI8, Ordering},
+        atomic::{AtomicBool, Ordering},
         Arc,
     },
     thread,
@@ -11,23 +11,48 @@ use std::{
 use dbus::{
     arg::{self, prop_cast, PropMap},
     blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
-    channel::Sender,
     message::SignalArgs,
     nonblock::SyncConnection,
     Message, Path,
 };
-use dbus_tokio::connection;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
 use re_set_lib::{
     bluetooth::{
         bluetooth_signals::{BluetoothDeviceAdded, BluetoothDeviceRemoved},
         bluetooth_structures::{BluetoothAdapter, BluetoothDevice},
     },
-    {ERROR, LOG},
+    ERROR,
 };
-#[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
 
-use crate::utils::{convert_bluetooth_map_bool, MaskedPropMap};
+use super::adapter_state::save_adapter_state;
+use super::anonymous::{
+    handle_device_removed, handle_device_visibility, is_hidden_device, DeviceVisibility,
+};
+use super::bluetooth_agent_dbus::{
+    bluetooth_agent_registered, register_bluetooth_agent, unregister_bluetooth_agent,
+};
+use super::device_history::record_device;
+use super::discoverable::cancel_discoverable_timer;
+use super::pairing::{
+    clear_pairing_pin, finish_pairing, get_pairing_state, set_pairing_stage, PairingStage,
+    PAIRING_TIMEOUT,
+};
+use super::rssi::{forget_rssi, handle_rssi_change};
+use super::scan::{get_scan_status, start_scan, stop_scan, SharedScanState};
+use crate::signals::{emit_changed_signal, emit_signal};
+use crate::status::StatusCode;
+use crate::supervisor::{supervise_worker, WORKER_BLUETOOTH};
+use crate::utils::{
+    convert_bluetooth_map_bool, session_connection_sync, AudioRequest, MaskedPropMap,
+};
+
+/// UUID of the A2DP audio sink service, devices offering it can be used as audio output.
+pub const AUDIO_SINK_UUID: &str = "0000110b-0000-1000-8000-00805f9b34fb";
+
+/// State of Bluetooth as (powered, scanning, pairing device, pairing stage, connected devices).\
+/// The pairing device is "/" and the stage empty while no device is pairing.
+pub type BluetoothState = (bool, bool, Path<'static>, String, u32);
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -36,7 +61,6 @@ pub struct BluetoothInterface {
     pub current_adapter: Path<'static>,
     devices: HashMap<Path<'static>, BluetoothDevice>,
     enabled: bool,
-    registered: bool,
     in_discovery: Arc<AtomicBool>,
     connection: Arc<SyncConnection>,
 }
@@ -57,7 +81,7 @@ impl Default for BluetoothAgent {
     }
 }
 
-fn get_objects() -> HashMap<Path<'static>, HashMap<String, PropMap>> {
+pub fn get_objects() -> HashMap<Path<'static>, HashMap<String, PropMap>> {
     let res = dbus_method!(
         BLUEZ_INTERFACE!(),
         "/",
@@ -193,6 +217,28 @@ pub fn get_connections() -> Vec<re_set_lib::bluetooth::bluetooth_structures::Blu
     devices
 }
 
+/// Collects the state GUIs would otherwise have to rebuild from the events since startup.
+pub fn get_bluetooth_state(adapter: &Path<'static>, scan: &SharedScanState) -> BluetoothState {
+    let powered = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        adapter.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "Powered",
+        bool,
+    )
+    .unwrap_or(false);
+    let (scanning, _) = get_scan_status(scan);
+    let (pairing_device, pairing_stage) = get_pairing_state()
+        .map(|(device, stage)| (device, stage.name().to_string()))
+        .unwrap_or_else(|| (Path::from("/"), String::new()));
+    let connected = get_objects()
+        .values()
+        .filter_map(|interfaces| interfaces.get(BLUEZ_DEVICE_INTERFACE!()))
+        .filter(|device| prop_cast::<bool>(device, "Connected") == Some(&true))
+        .count() as u32;
+    (powered, scanning, pairing_device, pairing_stage, connected)
+}
+
 #[allow(dead_code)]
 // pairing is currently not used
 // TODO handle pairing according to bluetooth rules
@@ -203,9 +249,8 @@ impl BluetoothInterface {
             current_adapter: Path::from("/"),
             devices: HashMap::new(),
             enabled: false,
-            registered: false,
             in_discovery: Arc::new(AtomicBool::new(false)),
-            connection: connection::new_session_sync().unwrap().1,
+            connection: session_connection_sync().unwrap().1,
         }
     }
 
@@ -228,7 +273,6 @@ impl BluetoothInterface {
             current_adapter,
             devices: HashMap::new(),
             enabled: false,
-            registered: false,
             in_discovery: Arc::new(AtomicBool::new(false)),
             connection: conn,
         })
@@ -237,200 +281,177 @@ impl BluetoothInterface {
     pub fn start_bluetooth_listener(
         &self,
         active_listener: Arc<AtomicBool>,
-        scan_request: Arc<AtomicI8>,
-        scan_active: Arc<AtomicBool>,
+        scan: SharedScanState,
         stop_requested: Arc<AtomicBool>,
     ) -> bool {
         let path = self.current_adapter.clone();
-        let added_ref = self.connection.clone();
-        let removed_ref = self.connection.clone();
-        let changed_ref = self.connection.clone();
+        let connection = self.connection.clone();
 
         if active_listener.load(Ordering::SeqCst) {
             return false;
         }
-        thread::spawn(move || {
-            let conn = dbus_connection!();
-            let bluetooth_device_added =
-                BluetoothDeviceAdded::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
-                    .static_clone();
-            let bluetooth_device_removed =
-                BluetoothDeviceRemoved::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
-                    .static_clone();
-            let mut bluetooth_device_changed = PropertiesPropertiesChanged::match_rule(
-                Some(&BLUEZ_INTERFACE!().into()),
-                Some(&path.clone()),
-            )
-            .static_clone();
-            bluetooth_device_changed.path_is_namespace = true;
-            let res = conn.add_match(
-                bluetooth_device_added,
-                move |ir: BluetoothDeviceAdded, _, _| {
-                    let device = convert_device(&ir.object, &ir.interfaces);
-                    if let Some(device) = device {
-                        let msg = Message::signal(
-                            &Path::from(DBUS_PATH!()),
-                            &BLUETOOTH_INTERFACE!().into(),
-                            &"BluetoothDeviceAdded".into(),
-                        )
-                        .append1(device);
-                        let res = added_ref.send(msg);
-                        if let Err(_error) = res {
-                            ERROR!(
-                                format!("Could not send signal: {:?}", _error),
-                                ErrorLevel::PartialBreakage
-                            );
-                        }
-                    }
-                    true
-                },
-            );
-            if let Err(_error) = res {
-                ERROR!(
-                    format!("Failed to match signal on bluez {:?}", _error),
-                    ErrorLevel::Critical
-                );
-                return Err(dbus::Error::new_custom(
-                    "SignalMatchFailed",
-                    "Failed to match signal on bluez.",
-                ));
-            }
-            let res = conn.add_match(
-                bluetooth_device_removed,
-                move |ir: BluetoothDeviceRemoved, _, _| {
-                    let msg = Message::signal(
-                        &Path::from(DBUS_PATH!()),
-                        &BLUETOOTH_INTERFACE!().into(),
-                        &"BluetoothDeviceRemoved".into(),
-                    )
-                    .append1(ir.object);
-                    let res = removed_ref.send(msg);
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Could not send signal {:?}", _error),
-                            ErrorLevel::PartialBreakage
-                        );
-                    }
-                    true
-                },
-            );
-            if let Err(_error) = res {
-                ERROR!(
-                    format!("Failed to match signal on bluez {:?}", _error),
-                    ErrorLevel::Critical
-                );
-                return Err(dbus::Error::new_custom(
-                    "SignalMatchFailed",
-                    "Failed to match signal on bluez.",
-                ));
+        let mut restarted = false;
+        supervise_worker(WORKER_BLUETOOTH, move || {
+            // the stop might have been requested while waiting for the restart
+            if restarted && stop_requested.swap(false, Ordering::SeqCst) {
+                active_listener.store(false, Ordering::SeqCst);
+                return Ok(());
             }
-            let res = conn.add_match(
-                bluetooth_device_changed,
-                move |ir: PropertiesPropertiesChanged, _, msg| {
-                    if ir.interface_name != BLUEZ_DEVICE_INTERFACE!() {
-                        // Here we only want to match on bluetooth device signals, the rest can be
-                        // ignored.
-                        return true;
-                    }
-                    if let Some(path) = msg.path() {
-                        let string = path.to_string();
-                        let path = Path::from(string);
-                        let map = get_bluetooth_device_properties(&path);
-                        let device_opt = bluetooth_device_from_map(&path, &map);
-
-                        if let Some(device) = device_opt {
+            restarted = true;
+            let scan_ref = connection.clone();
+            let added_ref = connection.clone();
+            let removed_ref = connection.clone();
+            let changed_ref = connection.clone();
+            let listen = || -> Result<(), dbus::Error> {
+                let conn = dbus_connection!();
+                let bluetooth_device_added =
+                    BluetoothDeviceAdded::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
+                        .static_clone();
+                let bluetooth_device_removed =
+                    BluetoothDeviceRemoved::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
+                        .static_clone();
+                let mut bluetooth_device_changed = PropertiesPropertiesChanged::match_rule(
+                    Some(&BLUEZ_INTERFACE!().into()),
+                    Some(&path.clone()),
+                )
+                .static_clone();
+                bluetooth_device_changed.path_is_namespace = true;
+                let res = conn.add_match(
+                    bluetooth_device_added,
+                    move |ir: BluetoothDeviceAdded, _, _| {
+                        if let Some(map) = ir.interfaces.get(BLUEZ_DEVICE_INTERFACE!()) {
+                            let visibility = handle_device_visibility(&added_ref, &ir.object, map);
+                            if visibility == DeviceVisibility::Hidden {
+                                return true;
+                            }
+                        }
+                        let device = convert_device(&ir.object, &ir.interfaces);
+                        if let Some(device) = device {
+                            record_device(&device);
                             let msg = Message::signal(
                                 &Path::from(DBUS_PATH!()),
                                 &BLUETOOTH_INTERFACE!().into(),
-                                &"BluetoothDeviceChanged".into(),
+                                &"BluetoothDeviceAdded".into(),
                             )
                             .append1(device);
-                            let res = changed_ref.clone().send(msg);
-                            if let Err(_error) = res {
-                                ERROR!(
-                                    format!("Could not send signal: {:?}", _error),
-                                    ErrorLevel::PartialBreakage
-                                );
+                            emit_signal(&added_ref, msg);
+                        }
+                        true
+                    },
+                );
+                if let Err(_error) = res {
+                    ERROR!(
+                        format!("Failed to match signal on bluez {:?}", _error),
+                        ErrorLevel::Critical
+                    );
+                    return Err(dbus::Error::new_custom(
+                        "SignalMatchFailed",
+                        "Failed to match signal on bluez.",
+                    ));
+                }
+                let res = conn.add_match(
+                    bluetooth_device_removed,
+                    move |ir: BluetoothDeviceRemoved, _, _| {
+                        forget_rssi(&ir.object);
+                        if handle_device_removed(&removed_ref, &ir.object) {
+                            return true;
+                        }
+                        let msg = Message::signal(
+                            &Path::from(DBUS_PATH!()),
+                            &BLUETOOTH_INTERFACE!().into(),
+                            &"BluetoothDeviceRemoved".into(),
+                        )
+                        .append1(ir.object.clone());
+                        emit_signal(&removed_ref, msg);
+                        true
+                    },
+                );
+                if let Err(_error) = res {
+                    ERROR!(
+                        format!("Failed to match signal on bluez {:?}", _error),
+                        ErrorLevel::Critical
+                    );
+                    return Err(dbus::Error::new_custom(
+                        "SignalMatchFailed",
+                        "Failed to match signal on bluez.",
+                    ));
+                }
+                let res = conn.add_match(
+                    bluetooth_device_changed,
+                    move |ir: PropertiesPropertiesChanged, _, msg| {
+                        if ir.interface_name != BLUEZ_DEVICE_INTERFACE!() {
+                            // Here we only want to match on bluetooth device signals, the rest can be
+                            // ignored.
+                            return true;
+                        }
+                        if let Some(path) = msg.path() {
+                            let string = path.to_string();
+                            let path = Path::from(string);
+                            let map = get_bluetooth_device_properties(&path);
+                            let visibility = handle_device_visibility(&changed_ref, &path, &map);
+                            if visibility == DeviceVisibility::Hidden {
+                                return true;
+                            }
+                            handle_rssi_change(&changed_ref, &path, &ir.changed_properties);
+                            let device_opt = bluetooth_device_from_map(&path, &map);
+
+                            if let Some(device) = device_opt {
+                                record_device(&device);
+                                // devices which are no longer anonymous are new to clients
+                                let (signal, key) = if visibility == DeviceVisibility::Revealed {
+                                    ("BluetoothDeviceAdded", None)
+                                } else {
+                                    ("BluetoothDeviceChanged", Some(&path))
+                                };
+                                let msg = Message::signal(
+                                    &Path::from(DBUS_PATH!()),
+                                    &BLUETOOTH_INTERFACE!().into(),
+                                    &signal.into(),
+                                )
+                                .append1(device);
+                                match key {
+                                    Some(key) => emit_changed_signal(&changed_ref, msg, key),
+                                    None => emit_signal(&changed_ref, msg),
+                                }
+                                true
+                            } else {
+                                false
                             }
-                            true
                         } else {
                             false
                         }
-                    } else {
-                        false
-                    }
-                },
-            );
-            if let Err(_error) = res {
-                ERROR!(
-                    format!("Failed to match signal on bluez: {:?}", _error),
-                    ErrorLevel::Critical
+                    },
                 );
-                return Err(dbus::Error::new_custom(
-                    "SignalMatchFailed",
-                    "Failed to match signal on bluez.",
-                ));
-            }
-            let other = Connection::new_system().unwrap();
-            let proxy = other.with_proxy(
-                BLUEZ_INTERFACE!(),
-                path.clone(),
-                Duration::from_millis(1000),
-            );
-            let res: Result<(), dbus::Error> =
-                proxy.method_call(BLUEZ_ADAPTER_INTERFACE!(), "StartDiscovery", ());
-            active_listener.store(true, Ordering::SeqCst);
-            scan_active.store(true, Ordering::SeqCst);
-            loop {
-                let _ = conn.process(Duration::from_millis(1000))?;
-                if stop_requested.load(Ordering::SeqCst) {
-                    scan_request.store(0, Ordering::SeqCst);
-                    active_listener.store(false, Ordering::SeqCst);
-                    stop_requested.store(false, Ordering::SeqCst);
-                    let res: Result<(), dbus::Error> =
-                        proxy.method_call(BLUEZ_ADAPTER_INTERFACE!(), "StopDiscovery", ());
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Failed to stop bluetooth discovery: {:?}", _error),
-                            ErrorLevel::Critical
-                        );
-                    } else {
-                        scan_active.store(false, Ordering::SeqCst);
-                    }
-                    break;
+                if let Err(_error) = res {
+                    ERROR!(
+                        format!("Failed to match signal on bluez: {:?}", _error),
+                        ErrorLevel::Critical
+                    );
+                    return Err(dbus::Error::new_custom(
+                        "SignalMatchFailed",
+                        "Failed to match signal on bluez.",
+                    ));
                 }
-                if scan_request.load(Ordering::SeqCst) == 1 {
-                    scan_request.store(0, Ordering::SeqCst);
-                    let res: Result<(), dbus::Error> =
-                        proxy.method_call(BLUEZ_ADAPTER_INTERFACE!(), "StartDiscovery", ());
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Failed to start bluetooth discovery: {:?}", _error),
-                            ErrorLevel::Critical
-                        );
-                    } else {
-                        scan_active.store(true, Ordering::SeqCst);
-                    }
-                } else if scan_request.load(Ordering::SeqCst) == 2 {
-                    scan_request.store(0, Ordering::SeqCst);
-                    let res: Result<(), dbus::Error> =
-                        proxy.method_call(BLUEZ_ADAPTER_INTERFACE!(), "StopDiscovery", ());
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Failed to stop bluetooth discovery: {:?}", _error),
-                            ErrorLevel::Critical
-                        );
-                    } else {
-                        scan_active.store(false, Ordering::SeqCst);
+                start_scan(&scan_ref, &path, &scan, None);
+                active_listener.store(true, Ordering::SeqCst);
+                loop {
+                    let _ = conn.process(Duration::from_millis(1000))?;
+                    if stop_requested.load(Ordering::SeqCst) {
+                        active_listener.store(false, Ordering::SeqCst);
+                        stop_requested.store(false, Ordering::SeqCst);
+                        stop_scan(&scan_ref, &path, &scan);
+                        break;
                     }
                 }
-            }
-            res
+                Ok(())
+            };
+            listen().map_err(|error| error.to_string())
         });
         true
     }
 
     pub fn connect_to(&self, device: Path<'static>) {
+        let connection = self.connection.clone();
         thread::spawn(move || {
             let res = dbus_method!(
                 BLUEZ_INTERFACE!(),
@@ -441,7 +462,7 @@ impl BluetoothInterface {
                 10000,
                 (),
             );
-            if let Err(_error) = res {
+            if let Err(_error) = &res {
                 ERROR!(
                     format!(
                         "Failed to connect to bluetooth device: {} with error: {}",
@@ -450,24 +471,59 @@ impl BluetoothInterface {
                     ErrorLevel::Critical
                 );
             }
+            send_connect_result(&connection, device, res);
+        });
+    }
+
+    /// Connects or disconnects a single profile, the result is emitted as BluetoothConnectResult.
+    pub fn set_profile_connected(&self, device: Path<'static>, uuid: String, connected: bool) {
+        let connection = self.connection.clone();
+        let method = if connected {
+            "ConnectProfile"
+        } else {
+            "DisconnectProfile"
+        };
+        thread::spawn(move || {
+            let res = dbus_method!(
+                BLUEZ_INTERFACE!(),
+                device.clone(),
+                method,
+                BLUEZ_DEVICE_INTERFACE!(),
+                (uuid.clone(),),
+                10000,
+                (),
+            );
+            if let Err(_error) = &res {
+                ERROR!(
+                    format!(
+                        "Failed to call {} with {} on bluetooth device: {} with error: {}",
+                        method, uuid, device, _error
+                    ),
+                    ErrorLevel::Recoverable
+                );
+            }
+            send_connect_result(&connection, device, res);
         });
     }
 
     pub fn pair_with(&mut self, device: Path<'static>) {
-        if !self.registered {
-            self.register_agent();
+        if !bluetooth_agent_registered() {
+            register_bluetooth_agent();
         }
+        let connection = self.connection.clone();
+        set_pairing_stage(device.clone(), PairingStage::Started);
         thread::spawn(move || {
+            // keyboards wait for the user to type the PIN
             let res = dbus_method!(
                 BLUEZ_INTERFACE!(),
                 device.clone(),
                 "Pair",
                 BLUEZ_DEVICE_INTERFACE!(),
                 (),
-                10000,
+                PAIRING_TIMEOUT.as_millis() as u64,
                 (),
             );
-            if let Err(_error) = res {
+            if let Err(_error) = &res {
                 ERROR!(
                     format!(
                         "Failed to pair with bluetooth device: {} with error {}",
@@ -476,6 +532,24 @@ impl BluetoothInterface {
                     ErrorLevel::Critical
                 );
             }
+            if res
+                .as_ref()
+                .is_err_and(|error| error.name() == Some("org.freedesktop.DBus.Error.NoReply"))
+            {
+                // bluez would otherwise keep waiting for the PIN
+                let _ = dbus_method!(
+                    BLUEZ_INTERFACE!(),
+                    device.clone(),
+                    "CancelPairing",
+                    BLUEZ_DEVICE_INTERFACE!(),
+                    (),
+                    1000,
+                    (),
+                );
+            }
+            clear_pairing_pin(Some(&device));
+            finish_pairing(Some(&device));
+            send_connect_result(&connection, device, res);
         });
     }
 
@@ -492,111 +566,161 @@ impl BluetoothInterface {
     }
 
     pub fn register_agent(&mut self) -> bool {
-        if self.registered {
-            return false;
-        }
-        let res = dbus_method!(
-            BLUEZ_INTERFACE!(),
-            Path::from(BLUEZ_PATH!()),
-            "RegisterAgent",
-            BLUEZ_AGENT_INTERFACE!(),
-            (Path::from(DBUS_PATH!()), "DisplayYesNo"),
-            1000,
-            (),
-        );
-        if let Err(_error) = res {
-            ERROR!(
-                format!("Failed to register bluetooth agent: {}", _error),
-                ErrorLevel::PartialBreakage
-            );
+        if bluetooth_agent_registered() {
             return false;
         }
-        self.registered = true;
-        true
+        register_bluetooth_agent()
     }
 
     pub fn unregister_agent(&mut self) -> bool {
-        if !self.registered {
-            return false;
-        }
-        let res = dbus_method!(
-            BLUEZ_INTERFACE!(),
-            Path::from(BLUEZ_PATH!()),
-            "UnregisterAgent",
-            BLUEZ_AGENT_INTERFACE!(),
-            (Path::from(DBUS_PATH!()),),
-            1000,
-            (Path<'static>,),
-        );
-        if let Err(_error) = res {
-            ERROR!(
-                format!("Failed to unregister bluetooth agent {}", _error),
-                ErrorLevel::PartialBreakage
-            );
-            return false;
-        }
-        self.registered = false;
-        true
+        unregister_bluetooth_agent()
     }
 
-    pub fn start_bluetooth_discovery(&self, scan_active: Arc<AtomicBool>) {
-        if scan_active.load(Ordering::SeqCst) {
-            LOG!("Failed to start bluetooth, already active");
-            return;
-        }
-        let res = dbus_method!(
-            BLUEZ_INTERFACE!(),
-            self.current_adapter.clone(),
-            "StartDiscovery",
-            BLUEZ_ADAPTER_INTERFACE!(),
-            (),
-            1000,
-            (),
-        );
-        if let Err(_error) = res {
-            ERROR!(
-                format!("Failed to start bluetooth discovery: {}", _error),
-                ErrorLevel::PartialBreakage
-            );
-        } else {
-            scan_active.store(true, Ordering::SeqCst);
+    /// See start_scan, the scan runs on the current adapter.
+    pub fn start_bluetooth_discovery(
+        &self,
+        scan: &SharedScanState,
+        duration: Option<Duration>,
+    ) -> bool {
+        start_scan(&self.connection, &self.current_adapter, scan, duration)
+    }
+
+    pub fn stop_bluetooth_discovery(&self, scan: &SharedScanState) {
+        stop_scan(&self.connection, &self.current_adapter, scan);
+    }
+}
+
+pub fn remove_device_pairing(
+    adapter: Path<'static>,
+    path: Path<'static>,
+) -> Result<(), dbus::Error> {
+    dbus_method!(
+        BLUEZ_INTERFACE!(),
+        adapter,
+        "RemoveDevice",
+        BLUEZ_ADAPTER_INTERFACE!(),
+        (path,),
+        1000,
+        (),
+    )
+}
+
+/// Reports the outcome of a connection or pairing attempt.\
+/// BlueZ puts the actual reason like br-connection-page-timeout into the error message, the
+/// generic error name is only used when no message is available.
+fn send_connect_result(
+    connection: &Arc<SyncConnection>,
+    device: Path<'static>,
+    result: Result<(), dbus::Error>,
+) {
+    let (success, error_name, code) = match result {
+        Ok(()) => (true, String::new(), StatusCode::Ok),
+        Err(error) => {
+            let reason = error
+                .message()
+                .filter(|message| !message.is_empty())
+                .or(error.name())
+                .unwrap_or("unknown");
+            let code = connect_error_code(error.name().unwrap_or_default());
+            (false, reason.to_string(), code)
         }
+    };
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &"BluetoothConnectResult".into(),
+    )
+    .append3(device, success, error_name)
+    .append1(code.name());
+    emit_signal(connection, msg);
+}
+
+/// Maps the error names of BlueZ to status codes, e.g. org.bluez.Error.AuthenticationFailed.
+pub fn connect_error_code(error_name: &str) -> StatusCode {
+    let error = error_name.rsplit('.').next().unwrap_or_default();
+    match error {
+        "NoReply" | "AuthenticationTimeout" => StatusCode::Timeout,
+        "DoesNotExist" => StatusCode::DeviceNotFound,
+        "NotReady" => StatusCode::AdapterNotFound,
+        "Busy" | "InProgress" | "AlreadyConnected" => StatusCode::Busy,
+        "NotSupported" => StatusCode::Unsupported,
+        error if error.starts_with("Authentication") => StatusCode::PairingFailed,
+        _ => StatusCode::ConnectionFailed,
     }
+}
 
-    pub fn stop_bluetooth_discovery(&self, scan_active: Arc<AtomicBool>) {
-        let res = dbus_method!(
-            BLUEZ_INTERFACE!(),
-            self.current_adapter.clone(),
-            "StopDiscovery",
-            BLUEZ_ADAPTER_INTERFACE!(),
-            (),
-            1000,
-            (),
+/// Watches connection changes of Bluetooth audio devices and forwards them to the audio server,
+/// which handles the automatic routing to the new sink.\
+/// This runs independently of the Bluetooth listener, as routing should also work without an
+/// open client.\
+/// The listener stops and releases its sender once stop_requested is set or the audio server
+/// stopped.
+pub fn start_audio_routing_listener(
+    audio_sender: Arc<crossbeam::channel::Sender<AudioRequest>>,
+    stop_requested: Arc<AtomicBool>,
+) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let stop_ref = stop_requested.clone();
+        let mut device_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&BLUEZ_INTERFACE!().into()),
+            Some(&Path::from(BLUEZ_PATH!())),
+        )
+        .static_clone();
+        device_changed.path_is_namespace = true;
+        let res = conn.add_match(
+            device_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                if ir.interface_name != BLUEZ_DEVICE_INTERFACE!() {
+                    return true;
+                }
+                let connected: Option<&bool> = prop_cast(&ir.changed_properties, "Connected");
+                if connected.is_none() {
+                    return true;
+                }
+                let connected = *connected.unwrap();
+                if let Some(path) = msg.path() {
+                    let path = Path::from(path.to_string());
+                    let map = get_bluetooth_device_properties(&path);
+                    let uuids: Option<&Vec<String>> = prop_cast(&map, "UUIDs");
+                    let is_audio_device =
+                        uuids.is_some_and(|uuids| uuids.iter().any(|uuid| uuid == AUDIO_SINK_UUID));
+                    let address: Option<&String> = prop_cast(&map, "Address");
+                    if !is_audio_device || address.is_none() {
+                        return true;
+                    }
+                    let address = address.unwrap().clone();
+                    let request = if connected {
+                        AudioRequest::BluetoothDeviceConnected(address)
+                    } else {
+                        AudioRequest::BluetoothDeviceDisconnected(address)
+                    };
+                    if audio_sender.send(request).is_err() {
+                        stop_ref.store(true, Ordering::SeqCst);
+                        return false;
+                    }
+                }
+                true
+            },
         );
         if let Err(_error) = res {
             ERROR!(
-                format!("Could not stop bluetooth discovery {}", _error),
+                format!("Failed to match signal on bluez: {:?}", _error),
                 ErrorLevel::PartialBreakage
             );
-        } else {
-            scan_active.store(false, Ordering::SeqCst);
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on bluez.",
+            ));
         }
-    }
-
-    pub fn remove_device_pairing(&self, path: Path<'static>) -> Result<(), dbus::Error> {
-        dbus_method!(
-            BLUEZ_INTERFACE!(),
-            self.current_adapter.clone(),
-            "RemoveDevice",
-            BLUEZ_ADAPTER_INTERFACE!(),
-            (path,),
-            1000,
-            (),
-        )
-    }
+        while !stop_requested.load(Ordering::SeqCst) {
+            let _ = conn.process(Duration::from_millis(1000))?;
+        }
+        Ok(())
+    });
 }
 
-fn get_bluetooth_device_properties(path: &Path<'static>) -> PropMap {
+pub fn get_bluetooth_device_properties(path: &Path<'static>) -> PropMap {
     let res = dbus_method!(
         BLUEZ_INTERFACE!(),
         path,
@@ -637,10 +761,13 @@ pub fn set_adapter_enabled(path: Path<'static>, enabled: bool) -> bool {
         );
         return false;
     }
+    save_adapter_state(&path, "Powered", enabled);
     true
 }
 
 pub fn set_adapter_discoverable(path: Path<'static>, enabled: bool) -> bool {
+    // the user chose the state, the timeout of MakeDiscoverableFor no longer applies
+    cancel_discoverable_timer(&path);
     let res = set_dbus_property!(
         BLUEZ_INTERFACE!(),
         path.clone(),
@@ -658,6 +785,7 @@ pub fn set_adapter_discoverable(path: Path<'static>, enabled: bool) -> bool {
         );
         return false;
     }
+    save_adapter_state(&path, "Discoverable", enabled);
     true
 }
 
@@ -701,10 +829,13 @@ pub fn get_all_bluetooth_devices() -> Vec<BluetoothDevice> {
     let objects = get_objects();
     for (path, map) in objects {
         if path.contains(BLUEZ_CONTAINS_PATH!()) && map.contains_key(BLUEZ_DEVICE_INTERFACE!()) {
-            devices.push(
-                bluetooth_device_from_map(&path, map.get(BLUEZ_DEVICE_INTERFACE!()).unwrap())
-                    .unwrap(),
-            );
+            let map = map.get(BLUEZ_DEVICE_INTERFACE!()).unwrap();
+            if is_hidden_device(&path, map) {
+                continue;
+            }
+            let device = bluetooth_device_from_map(&path, map).unwrap();
+            record_device(&device);
+            devices.push(device);
         }
     }
     devices
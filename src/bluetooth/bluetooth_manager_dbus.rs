@@ -1,43 +1,123 @@
-use std::sync::atomic::Ordering;
+use std::{sync::atomic::Ordering, time::Duration};
 
 use dbus::Path;
 use dbus_crossroads::Crossroads;
 use re_set_lib::bluetooth::bluetooth_structures::BluetoothDevice;
-use re_set_lib::ERROR;
 #[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
 
-use crate::DaemonData;
+use crate::{
+    authorization::authorize,
+    introspection::{annotate_struct_fields, STRUCT_OUT0},
+    rfkill::{blocked_error, get_adapter_rfkill_state},
+    utils::{set_daemon_config_value, AudioRequest},
+    DaemonData,
+};
 
+use super::anonymous::anonymous_device_count;
 use super::bluetooth_manager::{
-    get_all_bluetooth_adapters, get_all_bluetooth_devices, get_bluetooth_adapter, get_connections,
-    set_adapter_discoverable, set_adapter_enabled, set_adapter_pairable,
+    get_all_bluetooth_adapters, get_all_bluetooth_devices, get_bluetooth_adapter,
+    get_bluetooth_state, get_connections, remove_device_pairing, set_adapter_discoverable,
+    set_adapter_enabled, set_adapter_pairable,
+};
+use super::cleanup::{remove_all_unpaired_devices, remove_devices_not_seen_since};
+use super::device_history::{get_device_history, list_device_history};
+use super::device_services::{
+    find_device_profile, get_device_mode, get_device_services, list_devices_with_mode, profiles,
+};
+use super::discoverable::make_discoverable_for;
+use super::obex::{answer_transfer, set_device_policy, set_download_dir, ObexTransferRequested};
+use super::pairing::get_pairing_pin;
+use super::privacy::{
+    get_adapter_privacy, randomize_adapter_address, run_privacy_change, set_adapter_privacy,
+    set_just_works_repairing,
 };
+use super::scan::get_scan_status;
+use crate::status::{status_error, StatusCode};
 
 pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
     let token = cross.register(BLUETOOTH_INTERFACE!(), |c| {
-        c.signal::<(BluetoothDevice,), _>("BluetoothDeviceAdded", ("device",));
+        annotate_struct_fields(c, &["BluetoothDevice", "BluetoothAdapter"]);
+        c.signal::<(BluetoothDevice,), _>("BluetoothDeviceAdded", ("device",))
+            .annotate(STRUCT_OUT0, "BluetoothDevice");
         c.signal::<(Path<'static>,), _>("BluetoothDeviceRemoved", ("path",));
-        c.signal::<(BluetoothDevice,), _>("BluetoothDeviceChanged", ("device",));
+        c.signal::<(Path<'static>, bool, String, String), _>(
+            "BluetoothConnectResult",
+            ("device", "success", "error_name", "code"),
+        );
+        c.signal::<(BluetoothDevice,), _>("BluetoothDeviceChanged", ("device",))
+            .annotate(STRUCT_OUT0, "BluetoothDevice");
+        c.signal::<(Path<'static>, i16), _>("BluetoothDeviceRSSIChanged", ("device", "rssi"));
+        c.signal::<(u32,), _>("AnonymousDeviceCountChanged", ("count",));
         c.signal::<(), _>("PincodeRequested", ());
         c.signal::<(String,), _>("DisplayPinCode", ("code",));
         c.signal::<(), _>("PassKeyRequested", ());
         c.signal::<(u32, u16), _>("DisplayPassKey", ("passkey", "entered"));
         c.signal::<(), _>("PinCodeRequested", ());
+        c.signal::<(Path<'static>,), _>("BluetoothScanFinished", ("adapter",));
+        c.signal::<(Path<'static>,), _>("DiscoverableExpired", ("adapter",));
+        c.signal::<(Path<'static>, String), _>("PairingPinDisplay", ("device", "pin"));
+        c.signal::<ObexTransferRequested, _>(
+            "ObexTransferRequested",
+            ("id", "device", "name", "size"),
+        );
+        c.signal::<(u32,), _>("ObexTransferCanceled", ("id",));
         c.method_with_cr_async("StartBluetoothScan", (), (), move |mut ctx, cross, ()| {
             let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
-            data.bluetooth_scan_request.store(1, Ordering::SeqCst);
             data.b_interface
-                .start_bluetooth_discovery(data.bluetooth_scan_active.clone());
+                .start_bluetooth_discovery(&data.bluetooth_scan, None);
             async move { ctx.reply(Ok(())) }
         });
+        c.method_with_cr_async(
+            "StartBluetoothScanFor",
+            ("seconds",),
+            ("result",),
+            move |mut ctx, cross, (seconds,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let result = if seconds == 0 {
+                    Err(status_error(
+                        StatusCode::InvalidArgument,
+                        "Scan has to run for at least one second",
+                    ))
+                } else {
+                    Ok((data.b_interface.start_bluetooth_discovery(
+                        &data.bluetooth_scan,
+                        Some(Duration::from_secs(seconds as u64)),
+                    ),))
+                };
+                async move { ctx.reply(result) }
+            },
+        );
         c.method_with_cr_async("StopBluetoothScan", (), (), move |mut ctx, cross, ()| {
             let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
-            data.bluetooth_scan_request.store(2, Ordering::SeqCst);
             data.b_interface
-                .stop_bluetooth_discovery(data.bluetooth_scan_active.clone());
+                .stop_bluetooth_discovery(&data.bluetooth_scan);
             async move { ctx.reply(Ok(())) }
         });
+        c.method(
+            "GetScanStatus",
+            (),
+            ("scanning", "remaining"),
+            move |_, d: &mut DaemonData, ()| Ok(get_scan_status(&d.bluetooth_scan)),
+        );
+        c.method_with_cr_async(
+            "GetBluetoothState",
+            (),
+            (
+                "powered",
+                "scanning",
+                "pairing_device",
+                "pairing_stage",
+                "connected_devices",
+            ),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let adapter = data.b_interface.current_adapter.clone();
+                let scan = data.bluetooth_scan.clone();
+                async move { ctx.reply(Ok(get_bluetooth_state(&adapter, &scan))) }
+            },
+        );
         c.method_with_cr_async(
             "StartBluetoothListener",
             (),
@@ -46,8 +126,7 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 data.b_interface.start_bluetooth_listener(
                     data.bluetooth_listener_active.clone(),
-                    data.bluetooth_scan_request.clone(),
-                    data.bluetooth_scan_active.clone(),
+                    data.bluetooth_scan.clone(),
                     data.bluetooth_stop_requested.clone(),
                 );
                 async move { ctx.reply(Ok(())) }
@@ -80,7 +159,8 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
             (),
             ("adapters",),
             move |_, _, ()| Ok((get_all_bluetooth_adapters(),)),
-        );
+        )
+        .annotate(STRUCT_OUT0, "BluetoothAdapter");
         c.method(
             "GetCurrentBluetoothAdapter",
             (),
@@ -88,7 +168,8 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
             move |_, d: &mut DaemonData, ()| {
                 Ok((get_bluetooth_adapter(&d.b_interface.current_adapter),))
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "BluetoothAdapter");
         c.method(
             "SetBluetoothAdapter",
             ("path",),
@@ -108,9 +189,122 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
             ("path", "enabled"),
             ("result",),
             move |_, _, (path, enabled): (Path<'static>, bool)| {
+                if enabled {
+                    let state = get_adapter_rfkill_state(&path);
+                    if let Some(state @ (soft, hard)) = state {
+                        if soft || hard {
+                            // bluez only reports a generic failure for blocked adapters
+                            return Err(blocked_error(&path, state));
+                        }
+                    }
+                }
                 Ok((set_adapter_enabled(path, enabled),))
             },
         );
+        c.method(
+            "GetBluetoothAdapterRfkill",
+            ("path",),
+            ("soft_blocked", "hard_blocked"),
+            move |_, _, (path,): (Path<'static>,)| {
+                get_adapter_rfkill_state(&path).ok_or_else(|| {
+                    status_error(StatusCode::Unsupported, "The adapter has no rfkill switch")
+                })
+            },
+        );
+        c.method(
+            "GetBluetoothAdapterPrivacy",
+            ("path",),
+            ("privacy", "just_works_repairing", "supported"),
+            move |_, _, (path,): (Path<'static>,)| get_adapter_privacy(&path),
+        );
+        c.method_with_cr_async(
+            "SetBluetoothAdapterPrivacy",
+            ("path", "privacy"),
+            ("result",),
+            move |mut ctx, _, (path, privacy): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res =
+                        run_privacy_change(move || set_adapter_privacy(&path, &privacy, None));
+                    ctx.reply(res.await.map(|_| (true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "RandomizeAdapterAddress",
+            ("path",),
+            ("result",),
+            move |mut ctx, _, (path,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = run_privacy_change(move || randomize_adapter_address(&path));
+                    ctx.reply(res.await.map(|_| (true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetJustWorksRepairing",
+            ("mode",),
+            ("result",),
+            move |mut ctx, _, (_mode,): (String,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_just_works_repairing().map(|_| (true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetObexPolicy",
+            ("device", "policy"),
+            ("result",),
+            move |mut ctx, _, (device, policy): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = set_device_policy(&device, &policy);
+                    ctx.reply(res.map(|_| (true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetObexDownloadDir",
+            ("path",),
+            ("result",),
+            move |mut ctx, _, (path,): (String,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_download_dir(&path).map(|_| (true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "AnswerObexTransfer",
+            ("id", "accept"),
+            ("result",),
+            move |mut ctx, _, (id, accept): (u32, bool)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((answer_transfer(id, accept),)))
+                }
+            },
+        );
         c.method(
             "SetBluetoothAdapterDiscoverability",
             ("path", "enabled"),
@@ -119,6 +313,20 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
                 Ok((set_adapter_discoverable(path, enabled),))
             },
         );
+        c.method(
+            "MakeDiscoverableFor",
+            ("seconds",),
+            ("result",),
+            move |_, data: &mut DaemonData, (seconds,): (u32,)| {
+                Ok((make_discoverable_for(
+                    data.b_interface.current_adapter.clone(),
+                    seconds,
+                )?,))
+            },
+        );
+        c.method("GetPairingPin", (), ("device", "pin"), move |_, _, ()| {
+            Ok(get_pairing_pin().unwrap_or((Path::from("/"), String::new())))
+        });
         c.method(
             "SetBluetoothAdapterPairability",
             ("path", "enabled"),
@@ -129,7 +337,14 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
         );
         c.method("GetBluetoothDevices", (), ("devices",), move |_, _, ()| {
             Ok((get_all_bluetooth_devices(),))
-        });
+        })
+        .annotate(STRUCT_OUT0, "BluetoothDevice");
+        c.method(
+            "GetAnonymousDeviceCount",
+            (),
+            ("count",),
+            move |_, _, ()| Ok((anonymous_device_count(),)),
+        );
         c.method(
             "ConnectToBluetoothDevice",
             ("device",),
@@ -139,6 +354,38 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
                 Ok((true,))
             },
         );
+        c.method(
+            "GetBluetoothProfileUUIDs",
+            (),
+            ("profiles",),
+            move |_, _, ()| {
+                let profiles: Vec<(String, String)> = profiles::PROFILES
+                    .iter()
+                    .map(|(name, uuid)| (name.to_string(), uuid.to_string()))
+                    .collect();
+                Ok((profiles,))
+            },
+        );
+        c.method(
+            "ConnectBluetoothDeviceProfile",
+            ("device", "uuid"),
+            ("result",),
+            move |_, d: &mut DaemonData, (device, uuid): (Path<'static>, String)| {
+                let uuid = find_device_profile(&device, &uuid)?;
+                d.b_interface.set_profile_connected(device, uuid, true);
+                Ok((true,))
+            },
+        );
+        c.method(
+            "DisconnectBluetoothDeviceProfile",
+            ("device", "uuid"),
+            ("result",),
+            move |_, d: &mut DaemonData, (device, uuid): (Path<'static>, String)| {
+                let uuid = find_device_profile(&device, &uuid)?;
+                d.b_interface.set_profile_connected(device, uuid, false);
+                Ok((true,))
+            },
+        );
         // TODO pairing does not work this way
         // figure out how pairing works
         // c.method(
@@ -171,20 +418,117 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
                 Ok((true,))
             },
         );
-        c.method(
+        c.method_with_cr_async(
             "RemoveDevicePairing",
             ("path",),
             ("result",),
-            move |_, d: &mut DaemonData, (path,): (Path<'static>,)| {
-                let res = d.b_interface.remove_device_pairing(path.clone());
-                if res.is_err() {
-                    ERROR!(
-                        format!("Could not remove device pairing: {}", path),
-                        ErrorLevel::PartialBreakage
+            move |mut ctx, cross, (path,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let adapter = data.b_interface.current_adapter.clone();
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = remove_device_pairing(adapter, path.clone());
+                    if res.is_err() {
+                        ERROR!(
+                            format!("Could not remove device pairing: {}", path),
+                            ErrorLevel::PartialBreakage
+                        );
+                        return ctx.reply(Ok((false,)));
+                    }
+                    ctx.reply(Ok((true,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "RemoveAllUnpairedDevices",
+            (),
+            ("removed",),
+            move |mut ctx, _, ()| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((remove_all_unpaired_devices(),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "RemoveDevicesNotSeenSince",
+            ("timestamp",),
+            ("removed",),
+            move |mut ctx, _, (timestamp,): (u64,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((remove_devices_not_seen_since(timestamp),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetBluetoothAutoRoute",
+            ("enabled",),
+            ("result",),
+            move |mut ctx, cross, (enabled,): (bool,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let audio_sender = data.audio_sender.clone();
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = audio_sender.send(AudioRequest::SetBluetoothAutoRoute(enabled));
+                    let persisted = set_daemon_config_value(
+                        "bluetooth_auto_route",
+                        toml::Value::Boolean(enabled),
                     );
-                    return Ok((false,));
+                    ctx.reply(Ok((res.is_ok() && persisted,)))
                 }
-                Ok((true,))
+            },
+        );
+        c.method(
+            "GetBluetoothDeviceHistory",
+            ("address",),
+            ("last_seen", "last_connected"),
+            move |_, _, (address,): (String,)| Ok(get_device_history(&address)),
+        );
+        c.method(
+            "ListBluetoothDeviceHistory",
+            (),
+            ("devices",),
+            move |_, _, ()| Ok((list_device_history(),)),
+        );
+        c.method(
+            "GetBluetoothDeviceServices",
+            ("path",),
+            ("device_type", "services"),
+            move |_, _, (path,): (Path<'static>,)| get_device_services(&path),
+        );
+        c.method(
+            "GetBluetoothDeviceMode",
+            ("path",),
+            ("mode",),
+            move |_, _, (path,): (Path<'static>,)| Ok((get_device_mode(&path)?,)),
+        );
+        c.method(
+            "ListBluetoothDevices",
+            ("include_le_only",),
+            ("devices",),
+            move |_, _, (include_le_only,): (bool,)| {
+                Ok((list_devices_with_mode(include_le_only, false),))
+            },
+        );
+        c.method(
+            "ListConnectedBluetoothDevices",
+            ("include_le_only",),
+            ("devices",),
+            move |_, _, (include_le_only,): (bool,)| {
+                Ok((list_devices_with_mode(include_le_only, true),))
             },
         );
         c.method(
@@ -192,7 +536,8 @@ pub fn setup_bluetooth_manager(cross: &mut Crossroads) -> dbus_crossroads::Iface
             (),
             ("devices",),
             move |_, _, ()| Ok((get_connections(),)),
-        );
+        )
+        .annotate(STRUCT_OUT0, "BluetoothDevice");
     });
     token
 }
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use dbus::{arg::prop_cast, blocking::Connection, Path};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::{bluetooth_manager::get_objects, device_history::get_device_history};
+
+/// Device known to bluez as (path, adapter, address, paired, connected).
+pub type CleanupCandidate = (Path<'static>, Path<'static>, String, bool, bool);
+
+/// Devices that are neither paired nor connected, usually left over from scans.
+pub fn select_unpaired(devices: &[CleanupCandidate]) -> Vec<&CleanupCandidate> {
+    devices
+        .iter()
+        .filter(|(_, _, _, paired, connected)| !paired && !connected)
+        .collect()
+}
+
+/// Devices that were last seen before the timestamp, connected devices are always kept.
+pub fn select_not_seen_since(
+    devices: &[CleanupCandidate],
+    timestamp: u64,
+    last_seen: impl Fn(&str) -> u64,
+) -> Vec<&CleanupCandidate> {
+    devices
+        .iter()
+        .filter(|(_, _, address, _, connected)| !connected && last_seen(address) < timestamp)
+        .collect()
+}
+
+fn get_candidates() -> Vec<CleanupCandidate> {
+    get_objects()
+        .into_iter()
+        .filter_map(|(path, interfaces)| {
+            let device = interfaces.get(BLUEZ_DEVICE_INTERFACE!())?;
+            let adapter: &Path<'static> = prop_cast(device, "Adapter")?;
+            let address: &String = prop_cast(device, "Address")?;
+            Some((
+                path,
+                adapter.clone(),
+                address.clone(),
+                prop_cast::<bool>(device, "Paired")
+                    .copied()
+                    .unwrap_or(false),
+                prop_cast::<bool>(device, "Connected")
+                    .copied()
+                    .unwrap_or(false),
+            ))
+        })
+        .collect()
+}
+
+/// Removes the devices from their adapters and returns how many were removed.
+fn remove_devices(devices: Vec<&CleanupCandidate>) -> u32 {
+    let mut removed = 0;
+    for (path, adapter, _, _, _) in devices {
+        let res = dbus_method!(
+            BLUEZ_INTERFACE!(),
+            adapter.clone(),
+            "RemoveDevice",
+            BLUEZ_ADAPTER_INTERFACE!(),
+            (path.clone(),),
+            1000,
+            (),
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Could not remove device {}: {}", path, _error),
+                ErrorLevel::Recoverable
+            );
+            continue;
+        }
+        removed += 1;
+    }
+    removed
+}
+
+/// Removes every device that is neither paired nor connected from all adapters.
+pub fn remove_all_unpaired_devices() -> u32 {
+    let devices = get_candidates();
+    let removed = remove_devices(select_unpaired(&devices));
+    LOG!(format!("Removed {} unpaired Bluetooth devices", removed));
+    removed
+}
+
+/// Removes every device not seen since the timestamp in seconds since the unix epoch, including
+/// paired devices. Devices the daemon has never seen count as not seen.
+pub fn remove_devices_not_seen_since(timestamp: u64) -> u32 {
+    let devices = get_candidates();
+    let removed = remove_devices(select_not_seen_since(&devices, timestamp, |address| {
+        get_device_history(address).0
+    }));
+    LOG!(format!(
+        "Removed {} Bluetooth devices not seen since {}",
+        removed, timestamp
+    ));
+    removed
+}
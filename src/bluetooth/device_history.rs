@@ -0,0 +1,99 @@
+use std::{
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use re_set_lib::bluetooth::bluetooth_structures::BluetoothDevice;
+
+use crate::utils::{load_state_file, save_state_file};
+
+const HISTORY_FILE: &str = "bluetooth_history.toml";
+/// Timestamps are only written when they moved by at least this many seconds, RSSI updates would
+/// otherwise write the file multiple times per second during a scan.
+const RESOLUTION: u64 = 60;
+
+/// Last seen and last connected times in seconds since the unix epoch, keyed by the device address.
+/// ```toml
+/// ["00:1A:7D:DA:71:13"]
+/// last_seen = 1718000000
+/// last_connected = 1717990000
+/// ```
+static HISTORY: Lazy<Mutex<toml::Table>> = Lazy::new(|| Mutex::new(load_state_file(HISTORY_FILE)));
+
+/// History entry as (address, last seen, last connected), 0 if never.
+pub type DeviceHistory = (String, u64, u64);
+
+/// Records a device reported by bluez, devices cached by bluez without RSSI are not in range.
+pub fn record_device(device: &BluetoothDevice) {
+    if device.address.is_empty() || (device.rssi == -1 && !device.connected) {
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let mut history = HISTORY.lock().unwrap();
+    let entry = history
+        .entry(device.address.clone())
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let entry = entry.as_table_mut();
+    if entry.is_none() {
+        return;
+    }
+    let entry = entry.unwrap();
+    let mut changed = update_timestamp(entry, "last_seen", now);
+    if device.connected {
+        changed |= update_timestamp(entry, "last_connected", now);
+    }
+    if changed {
+        save_state_file(HISTORY_FILE, &history);
+    }
+}
+
+fn update_timestamp(entry: &mut toml::Table, key: &str, now: u64) -> bool {
+    let previous = timestamp(entry, key);
+    if now < previous + RESOLUTION {
+        return false;
+    }
+    entry.insert(key.to_string(), toml::Value::Integer(now as i64));
+    true
+}
+
+fn timestamp(entry: &toml::Table, key: &str) -> u64 {
+    entry
+        .get(key)
+        .and_then(|value| value.as_integer())
+        .unwrap_or(0) as u64
+}
+
+pub fn get_device_history(address: &str) -> (u64, u64) {
+    let history = HISTORY.lock().unwrap();
+    let entry = history.get(address).and_then(|entry| entry.as_table());
+    if entry.is_none() {
+        return (0, 0);
+    }
+    let entry = entry.unwrap();
+    (
+        timestamp(entry, "last_seen"),
+        timestamp(entry, "last_connected"),
+    )
+}
+
+/// Returns all known devices, most recently connected first.
+pub fn list_device_history() -> Vec<DeviceHistory> {
+    let history = HISTORY.lock().unwrap();
+    let mut devices: Vec<DeviceHistory> = history
+        .iter()
+        .filter_map(|(address, entry)| {
+            let entry = entry.as_table()?;
+            Some((
+                address.clone(),
+                timestamp(entry, "last_seen"),
+                timestamp(entry, "last_connected"),
+            ))
+        })
+        .collect();
+    devices.sort_by_key(|device| std::cmp::Reverse((device.2, device.1)));
+    devices
+}
@@ -0,0 +1,290 @@
+use std::ops::Range;
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    MethodErr, Path,
+};
+use re_set_lib::bluetooth::bluetooth_structures::BluetoothDevice;
+
+use super::bluetooth_manager::{
+    bluetooth_device_from_map, get_bluetooth_device_properties, get_objects,
+};
+use crate::status::{status_error, StatusCode};
+
+/// Service as (uuid, name), the name is empty for unknown services.
+pub type BluetoothService = (String, String);
+/// Device with its mode, see GetBluetoothDeviceMode.
+pub type BluetoothDeviceWithMode = (BluetoothDevice, String);
+
+/// Suffix of all UUIDs derived from a 16 bit assigned number.
+const BASE_UUID_SUFFIX: &str = "-0000-1000-8000-00805f9b34fb";
+/// MIDI over Bluetooth LE, defined by the MIDI association instead of an assigned number.
+const MIDI_SERVICE_UUID: &str = "03b80e5a-ede8-4b33-a751-6ce34ec4c700";
+
+/// Assigned numbers of the service classes and GATT services relevant for clients.
+const SERVICE_NAMES: &[(u16, &str)] = &[
+    (0x1101, "Serial Port"),
+    (0x1105, "OBEX Object Push"),
+    (0x1106, "OBEX File Transfer"),
+    (0x1108, "Headset"),
+    (0x110a, "Audio Source"),
+    (0x110b, "Audio Sink"),
+    (0x110c, "A/V Remote Control Target"),
+    (0x110e, "A/V Remote Control"),
+    (0x1112, "Headset Audio Gateway"),
+    (0x1115, "PAN User"),
+    (0x1116, "Network Access Point"),
+    (0x111e, "Handsfree"),
+    (0x111f, "Handsfree Audio Gateway"),
+    (0x1124, "Human Interface Device"),
+    (0x112f, "Phonebook Access Server"),
+    (0x1132, "Message Access Server"),
+    (0x1200, "PnP Information"),
+    (0x1800, "Generic Access"),
+    (0x1801, "Generic Attribute"),
+    (0x180a, "Device Information"),
+    (0x180f, "Battery Service"),
+    (0x1812, "Human Interface Device over GATT"),
+];
+
+/// UUIDs of the profiles that can be connected individually with ConnectBluetoothDeviceProfile.
+pub mod profiles {
+    pub const SERIAL_PORT: &str = "00001101-0000-1000-8000-00805f9b34fb";
+    pub const HEADSET: &str = "00001108-0000-1000-8000-00805f9b34fb";
+    pub const AUDIO_SOURCE: &str = "0000110a-0000-1000-8000-00805f9b34fb";
+    pub const AUDIO_SINK: &str = "0000110b-0000-1000-8000-00805f9b34fb";
+    pub const AV_REMOTE_CONTROL_TARGET: &str = "0000110c-0000-1000-8000-00805f9b34fb";
+    pub const AV_REMOTE_CONTROL: &str = "0000110e-0000-1000-8000-00805f9b34fb";
+    pub const PAN_USER: &str = "00001115-0000-1000-8000-00805f9b34fb";
+    pub const NETWORK_ACCESS_POINT: &str = "00001116-0000-1000-8000-00805f9b34fb";
+    pub const HANDSFREE: &str = "0000111e-0000-1000-8000-00805f9b34fb";
+    pub const HANDSFREE_AUDIO_GATEWAY: &str = "0000111f-0000-1000-8000-00805f9b34fb";
+    pub const HUMAN_INTERFACE_DEVICE: &str = "00001124-0000-1000-8000-00805f9b34fb";
+    pub const HUMAN_INTERFACE_DEVICE_OVER_GATT: &str = "00001812-0000-1000-8000-00805f9b34fb";
+
+    /// Profile as (name, uuid), see GetBluetoothProfileUUIDs.
+    pub const PROFILES: &[(&str, &str)] = &[
+        ("serial_port", SERIAL_PORT),
+        ("headset", HEADSET),
+        ("audio_source", AUDIO_SOURCE),
+        ("audio_sink", AUDIO_SINK),
+        ("av_remote_control_target", AV_REMOTE_CONTROL_TARGET),
+        ("av_remote_control", AV_REMOTE_CONTROL),
+        ("pan_user", PAN_USER),
+        ("network_access_point", NETWORK_ACCESS_POINT),
+        ("handsfree", HANDSFREE),
+        ("handsfree_audio_gateway", HANDSFREE_AUDIO_GATEWAY),
+        ("human_interface_device", HUMAN_INTERFACE_DEVICE),
+        (
+            "human_interface_device_over_gatt",
+            HUMAN_INTERFACE_DEVICE_OVER_GATT,
+        ),
+    ];
+}
+
+const MODE_LE: &str = "le";
+const MODE_CLASSIC: &str = "classic";
+const MODE_DUAL: &str = "dual";
+// assigned numbers of the service classes of BR/EDR and the services of GATT
+const CLASSIC_SERVICE_CLASSES: Range<u16> = 0x1100..0x1400;
+const GATT_SERVICES: Range<u16> = 0x1800..0x1900;
+
+// major device classes of the class of device
+const MAJOR_CLASS_COMPUTER: u32 = 0x01;
+const MAJOR_CLASS_PHONE: u32 = 0x02;
+const MAJOR_CLASS_AUDIO: u32 = 0x04;
+const MAJOR_CLASS_PERIPHERAL: u32 = 0x05;
+const MAJOR_CLASS_WEARABLE: u32 = 0x07;
+// appearance category of Bluetooth LE watches
+const APPEARANCE_CATEGORY_WATCH: u16 = 0x03;
+
+/// Returns the derived device type and all services of a device, see GetBluetoothDeviceServices.
+pub fn get_device_services(
+    path: &Path<'static>,
+) -> Result<(String, Vec<BluetoothService>), MethodErr> {
+    let map = get_bluetooth_device_properties(path);
+    if map.is_empty() {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown bluetooth device",
+        ));
+    }
+    let services = prop_cast::<Vec<String>>(&map, "UUIDs")
+        .map(|uuids| {
+            uuids
+                .iter()
+                .map(|uuid| (uuid.clone(), service_name(uuid).to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok((device_type(&map).to_string(), services))
+}
+
+fn assigned_number(uuid: &str) -> Option<u16> {
+    uuid.to_lowercase()
+        .strip_suffix(BASE_UUID_SUFFIX)
+        .and_then(|prefix| prefix.strip_prefix("0000"))
+        .and_then(|number| u16::from_str_radix(number, 16).ok())
+}
+
+/// Expands 16 bit assigned numbers like 110b to the full UUID, full UUIDs are lowercased.
+pub fn normalize_uuid(uuid: &str) -> Option<String> {
+    let uuid = uuid.trim_start_matches("0x").to_lowercase();
+    if uuid.len() == 4 && u16::from_str_radix(&uuid, 16).is_ok() {
+        return Some(format!("0000{}{}", uuid, BASE_UUID_SUFFIX));
+    }
+    let groups: Vec<&str> = uuid.split('-').collect();
+    let valid = groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_hexdigit()));
+    valid.then_some(uuid)
+}
+
+/// Returns the normalized UUID if the device offers the profile.
+pub fn find_device_profile(path: &Path<'static>, uuid: &str) -> Result<String, MethodErr> {
+    let Some(uuid) = normalize_uuid(uuid) else {
+        return Err(status_error(StatusCode::InvalidArgument, "Invalid UUID"));
+    };
+    let map = get_bluetooth_device_properties(path);
+    if map.is_empty() {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown bluetooth device",
+        ));
+    }
+    let offered = prop_cast::<Vec<String>>(&map, "UUIDs").is_some_and(|uuids| {
+        uuids
+            .iter()
+            .any(|offered| offered.eq_ignore_ascii_case(&uuid))
+    });
+    if !offered {
+        return Err(status_error(
+            StatusCode::Unsupported,
+            "The device does not offer this profile",
+        ));
+    }
+    Ok(uuid)
+}
+
+fn service_name(uuid: &str) -> &'static str {
+    if uuid.eq_ignore_ascii_case(MIDI_SERVICE_UUID) {
+        return "MIDI";
+    }
+    assigned_number(uuid)
+        .and_then(|number| {
+            SERVICE_NAMES
+                .iter()
+                .find(|(assigned, _)| *assigned == number)
+        })
+        .map(|(_, name)| *name)
+        .unwrap_or("")
+}
+
+/// Returns the mode of a device, see GetBluetoothDeviceMode.
+pub fn get_device_mode(path: &Path<'static>) -> Result<String, MethodErr> {
+    let map = get_bluetooth_device_properties(path);
+    if map.is_empty() {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown bluetooth device",
+        ));
+    }
+    Ok(device_mode(&map).to_string())
+}
+
+pub fn is_keyboard(path: &Path<'static>) -> bool {
+    device_type(&get_bluetooth_device_properties(path)) == "keyboard"
+}
+
+/// Returns all devices known to bluez with their mode.\
+/// Without include_le_only, LE-only devices that are neither paired nor connected are skipped,
+/// these are mostly beacons that can not be paired.
+pub fn list_devices_with_mode(
+    include_le_only: bool,
+    connected_only: bool,
+) -> Vec<BluetoothDeviceWithMode> {
+    let mut devices = Vec::new();
+    for (path, interfaces) in get_objects() {
+        let Some(map) = interfaces.get(BLUEZ_DEVICE_INTERFACE!()) else {
+            continue;
+        };
+        let Some(device) = bluetooth_device_from_map(&path, map) else {
+            continue;
+        };
+        if connected_only && !device.connected {
+            continue;
+        }
+        let mode = device_mode(map);
+        if !include_le_only && mode == MODE_LE && !device.paired && !device.connected {
+            continue;
+        }
+        devices.push((device, mode.to_string()));
+    }
+    devices
+}
+
+/// Derives whether a device supports Bluetooth LE, classic (BR/EDR) or both.\
+/// Random addresses only exist on LE, otherwise the class of device and the classic service
+/// classes indicate BR/EDR while the LE appearance and GATT services indicate LE.
+fn device_mode(map: &PropMap) -> &'static str {
+    if prop_cast::<String>(map, "AddressType").is_some_and(|address_type| address_type == "random")
+    {
+        return MODE_LE;
+    }
+    let uuids = prop_cast::<Vec<String>>(map, "UUIDs")
+        .cloned()
+        .unwrap_or_default();
+    let classic = map.contains_key("Class")
+        || uuids
+            .iter()
+            .filter_map(|uuid| assigned_number(uuid))
+            .any(|number| CLASSIC_SERVICE_CLASSES.contains(&number));
+    let le = map.contains_key("Appearance")
+        || uuids.iter().any(|uuid| {
+            uuid.eq_ignore_ascii_case(MIDI_SERVICE_UUID)
+                || assigned_number(uuid).is_some_and(|number| GATT_SERVICES.contains(&number))
+        });
+    match (classic, le) {
+        (true, true) => MODE_DUAL,
+        (false, true) => MODE_LE,
+        _ => MODE_CLASSIC,
+    }
+}
+
+/// Derives the device type from the icon chosen by bluez, with the class of device and the LE
+/// appearance as fallback.\
+/// One of headset, headphones, speaker, keyboard, mouse, gamepad, phone, computer, watch or
+/// unknown.
+fn device_type(map: &PropMap) -> &'static str {
+    let icon = prop_cast::<String>(map, "Icon")
+        .map(|icon| icon.as_str())
+        .unwrap_or_default();
+    match icon {
+        "audio-headset" => return "headset",
+        "audio-headphones" => return "headphones",
+        "audio-card" => return "speaker",
+        "input-keyboard" => return "keyboard",
+        "input-mouse" | "input-tablet" => return "mouse",
+        "input-gaming" => return "gamepad",
+        "phone" => return "phone",
+        "computer" => return "computer",
+        _ => (),
+    }
+    if prop_cast::<u16>(map, "Appearance")
+        .is_some_and(|appearance| appearance >> 6 == APPEARANCE_CATEGORY_WATCH)
+    {
+        return "watch";
+    }
+    let class = prop_cast::<u32>(map, "Class").copied().unwrap_or(0);
+    let minor_class = (class >> 2) & 0x3f;
+    match (class >> 8) & 0x1f {
+        MAJOR_CLASS_COMPUTER => "computer",
+        MAJOR_CLASS_PHONE => "phone",
+        MAJOR_CLASS_AUDIO => "headset",
+        MAJOR_CLASS_PERIPHERAL if minor_class & 0x10 != 0 => "keyboard",
+        MAJOR_CLASS_PERIPHERAL if minor_class & 0x20 != 0 => "mouse",
+        MAJOR_CLASS_WEARABLE if minor_class == 0x01 => "watch",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::signals::emit_signal;
+use crate::status::{status_error, StatusCode};
+
+/// Adapters made discoverable by MakeDiscoverableFor with the DiscoverableTimeout they had
+/// before, the timeout would otherwise also apply when enabling discoverability permanently.
+static DISCOVERABLE_TIMERS: Lazy<Mutex<HashMap<Path<'static>, u32>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Makes the adapter discoverable until bluez disables it after the given seconds.\
+/// The state is not restored on startup, unlike SetBluetoothAdapterDiscoverability.
+pub fn make_discoverable_for(path: Path<'static>, seconds: u32) -> Result<bool, MethodErr> {
+    // bluez treats a timeout of 0 as discoverable forever
+    if seconds == 0 {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Discoverability has to last for at least one second",
+        ));
+    }
+    let previous_timeout = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "DiscoverableTimeout",
+        u32,
+    );
+    let Ok(previous_timeout) = previous_timeout else {
+        return Err(status_error(
+            StatusCode::AdapterNotFound,
+            "Unknown bluetooth adapter",
+        ));
+    };
+    let mut timers = DISCOVERABLE_TIMERS.lock().unwrap();
+    let previous_timeout = *timers.get(&path).unwrap_or(&previous_timeout);
+    let res = set_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "DiscoverableTimeout",
+        seconds,
+    )
+    .and_then(|_| {
+        set_dbus_property!(
+            BLUEZ_INTERFACE!(),
+            path.clone(),
+            BLUEZ_ADAPTER_INTERFACE!(),
+            "Discoverable",
+            true,
+        )
+    });
+    if let Err(_error) = res {
+        ERROR!(
+            format!(
+                "Failed to make bluetooth adapter {} discoverable: {}",
+                path, _error
+            ),
+            ErrorLevel::Recoverable
+        );
+        return Ok(false);
+    }
+    LOG!(format!("{} is discoverable for {} seconds", path, seconds));
+    timers.insert(path, previous_timeout);
+    Ok(true)
+}
+
+/// Stops tracking the timer of an adapter and restores its previous DiscoverableTimeout.\
+/// Returns false if no timer was running.
+pub fn cancel_discoverable_timer(path: &Path<'static>) -> bool {
+    let previous_timeout = DISCOVERABLE_TIMERS.lock().unwrap().remove(path);
+    let Some(previous_timeout) = previous_timeout else {
+        return false;
+    };
+    let res = set_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "DiscoverableTimeout",
+        previous_timeout,
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!(
+                "Failed to restore the discoverable timeout of {}: {}",
+                path, _error
+            ),
+            ErrorLevel::Recoverable
+        );
+    }
+    true
+}
+
+/// Emits DiscoverableExpired once bluez disables discoverability of an adapter made
+/// discoverable with MakeDiscoverableFor.
+pub fn start_discoverable_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let adapter_changed =
+            PropertiesPropertiesChanged::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
+                .static_clone();
+        let res = conn.add_match(
+            adapter_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                if ir.interface_name != BLUEZ_ADAPTER_INTERFACE!() {
+                    return true;
+                }
+                let expired = prop_cast::<bool>(&ir.changed_properties, "Discoverable")
+                    .is_some_and(|discoverable| !discoverable);
+                let Some(path) = msg.path().map(|path| Path::from(path.to_string())) else {
+                    return true;
+                };
+                if expired && cancel_discoverable_timer(&path) {
+                    let msg = Message::signal(
+                        &Path::from(DBUS_PATH!()),
+                        &BLUETOOTH_INTERFACE!().into(),
+                        &"DiscoverableExpired".into(),
+                    )
+                    .append1(path);
+                    emit_signal(&connection, msg);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on bluez: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on bluez.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, sync::Arc, thread, time::Duration};
+
+use dbus::{
+    arg::{prop_cast, PropMap, RefArg},
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use super::bluetooth_manager::get_objects;
+use crate::signals::emit_signal;
+use crate::status::{status_error, StatusCode};
+
+/// Track as (title, artist, album, duration in milliseconds), unknown fields are empty or 0.
+pub type BluetoothMediaTrack = (String, String, String, u32);
+/// Player state as (status, track, position in milliseconds).\
+/// The status is reported by the device, one of playing, paused, stopped, forward-seek,
+/// reverse-seek or error.
+pub type BluetoothMediaState = (String, BluetoothMediaTrack, u32);
+
+/// Commands of org.bluez.MediaPlayer1 offered by the BluetoothMedia interface.
+pub const MEDIA_COMMANDS: [&str; 4] = ["Play", "Pause", "Next", "Previous"];
+
+/// Returns the AVRCP player of a connected device.\
+/// bluez only exports a player while the remote control profile is connected, the Device
+/// property of the player links it to the device.
+fn find_player(device: &Path<'static>) -> Result<Path<'static>, MethodErr> {
+    let objects = get_objects();
+    let known = objects
+        .get(device)
+        .is_some_and(|interfaces| interfaces.contains_key(BLUEZ_DEVICE_INTERFACE!()));
+    if !known {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown bluetooth device",
+        ));
+    }
+    objects
+        .into_iter()
+        .find(|(_, interfaces)| {
+            interfaces
+                .get(BLUEZ_MEDIA_PLAYER_INTERFACE!())
+                .and_then(|player| prop_cast::<Path<'static>>(player, "Device"))
+                .is_some_and(|player_device| player_device == device)
+        })
+        .map(|(path, _)| path)
+        .ok_or_else(|| {
+            status_error(
+                StatusCode::Unsupported,
+                "The device offers no media control, is it connected with AVRCP?",
+            )
+        })
+}
+
+/// Sends one of the MEDIA_COMMANDS to the player of a device.
+pub fn send_media_command(device: &Path<'static>, command: &str) -> Result<bool, MethodErr> {
+    if !MEDIA_COMMANDS.contains(&command) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown media command",
+        ));
+    }
+    let player = find_player(device)?;
+    let res = dbus_method!(
+        BLUEZ_INTERFACE!(),
+        player,
+        command,
+        BLUEZ_MEDIA_PLAYER_INTERFACE!(),
+        (),
+        1000,
+        (),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to send {} to {}: {}", command, device, _error),
+            ErrorLevel::Recoverable
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Returns the status, track and position of the player of a device.
+pub fn get_media_state(device: &Path<'static>) -> Result<BluetoothMediaState, MethodErr> {
+    let player = find_player(device)?;
+    let res = dbus_method!(
+        BLUEZ_INTERFACE!(),
+        player,
+        "GetAll",
+        "org.freedesktop.DBus.Properties",
+        (BLUEZ_MEDIA_PLAYER_INTERFACE!(),),
+        1000,
+        (PropMap,),
+    );
+    let Ok((map,)) = res else {
+        return Err(status_error(
+            StatusCode::Failed,
+            "Could not read the media player",
+        ));
+    };
+    let status = prop_cast::<String>(&map, "Status")
+        .cloned()
+        .unwrap_or_default();
+    let track = map
+        .get("Track")
+        .map(|track| track_from_arg(&track.0))
+        .unwrap_or_default();
+    let position = prop_cast::<u32>(&map, "Position").copied().unwrap_or(0);
+    Ok((status, track, position))
+}
+
+/// Returns the battery level in percent reported by the experimental org.bluez.Battery1
+/// interface, bluez has to run with --experimental on older versions.
+pub fn get_battery_percentage(device: &Path<'static>) -> Result<u8, MethodErr> {
+    let objects = get_objects();
+    let Some(interfaces) = objects.get(device) else {
+        return Err(status_error(
+            StatusCode::DeviceNotFound,
+            "Unknown bluetooth device",
+        ));
+    };
+    interfaces
+        .get(BLUEZ_BATTERY_INTERFACE!())
+        .and_then(|battery| prop_cast::<u8>(battery, "Percentage"))
+        .copied()
+        .ok_or_else(|| {
+            status_error(
+                StatusCode::Unsupported,
+                "The device does not report its battery level",
+            )
+        })
+}
+
+/// Converts the Track property of org.bluez.MediaPlayer1.\
+/// The dictionary is iterated as key value pairs, as variants received from bluez are not
+/// deserialized into a PropMap.
+pub fn track_from_arg(track: &dyn RefArg) -> BluetoothMediaTrack {
+    let mut result = BluetoothMediaTrack::default();
+    let Some(mut entries) = track.as_iter() else {
+        return result;
+    };
+    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+        let text = || value.as_str().unwrap_or_default().to_string();
+        match key.as_str().unwrap_or_default() {
+            "Title" => result.0 = text(),
+            "Artist" => result.1 = text(),
+            "Album" => result.2 = text(),
+            "Duration" => result.3 = value.as_u64().unwrap_or(0) as u32,
+            _ => (),
+        }
+    }
+    result
+}
+
+/// Forwards track, status and battery changes of bluez as BluetoothMedia signals.\
+/// Runs for the lifetime of the daemon, players appear and vanish with the remote control
+/// profile of the devices.
+pub fn start_media_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let mut media_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&BLUEZ_INTERFACE!().into()),
+            Some(&Path::from(BLUEZ_PATH!())),
+        )
+        .static_clone();
+        media_changed.path_is_namespace = true;
+        // players are owned by the devices, only the device path is relevant for clients
+        let mut player_devices: HashMap<Path<'static>, Path<'static>> = HashMap::new();
+        let res = conn.add_match(
+            media_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                let Some(path) = msg.path().map(|path| Path::from(path.to_string())) else {
+                    return true;
+                };
+                if ir.interface_name == BLUEZ_BATTERY_INTERFACE!() {
+                    if let Some(percentage) = prop_cast::<u8>(&ir.changed_properties, "Percentage")
+                    {
+                        let msg = Message::signal(
+                            &Path::from(DBUS_PATH!()),
+                            &BLUETOOTH_MEDIA_INTERFACE!().into(),
+                            &"BatteryPercentageChanged".into(),
+                        )
+                        .append2(path, *percentage);
+                        emit_signal(&connection, msg);
+                    }
+                    return true;
+                }
+                if ir.interface_name != BLUEZ_MEDIA_PLAYER_INTERFACE!() {
+                    return true;
+                }
+                let device = match player_devices.get(&path) {
+                    Some(device) => device.clone(),
+                    None => {
+                        let device = get_dbus_property!(
+                            BLUEZ_INTERFACE!(),
+                            path.clone(),
+                            BLUEZ_MEDIA_PLAYER_INTERFACE!(),
+                            "Device",
+                            Path<'static>,
+                        );
+                        let Ok(device) = device else {
+                            return true;
+                        };
+                        player_devices.insert(path.clone(), device.clone());
+                        device
+                    }
+                };
+                if let Some(track) = ir.changed_properties.get("Track") {
+                    let msg = Message::signal(
+                        &Path::from(DBUS_PATH!()),
+                        &BLUETOOTH_MEDIA_INTERFACE!().into(),
+                        &"TrackChanged".into(),
+                    )
+                    .append2(device.clone(), track_from_arg(&track.0));
+                    emit_signal(&connection, msg);
+                }
+                if let Some(status) = prop_cast::<String>(&ir.changed_properties, "Status") {
+                    let msg = Message::signal(
+                        &Path::from(DBUS_PATH!()),
+                        &BLUETOOTH_MEDIA_INTERFACE!().into(),
+                        &"PlaybackStatusChanged".into(),
+                    )
+                    .append2(device, status.clone());
+                    emit_signal(&connection, msg);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on bluez: {:?}", _error),
+                ErrorLevel::PartialBreakage
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on bluez.",
+            ));
+        }
+        loop {
+            let _ = conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
@@ -0,0 +1,42 @@
+use dbus::Path;
+use dbus_crossroads::Crossroads;
+
+use crate::DaemonData;
+
+use super::media::{
+    get_battery_percentage, get_media_state, send_media_command, BluetoothMediaTrack,
+    MEDIA_COMMANDS,
+};
+
+pub fn setup_bluetooth_media(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(BLUETOOTH_MEDIA_INTERFACE!(), |c| {
+        c.signal::<(Path<'static>, BluetoothMediaTrack), _>("TrackChanged", ("device", "track"));
+        c.signal::<(Path<'static>, String), _>("PlaybackStatusChanged", ("device", "status"));
+        c.signal::<(Path<'static>, u8), _>("BatteryPercentageChanged", ("device", "percentage"));
+        for command in MEDIA_COMMANDS {
+            c.method_with_cr_async(
+                command,
+                ("device",),
+                ("result",),
+                move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                    ctx.reply(send_media_command(&device, command).map(|result| (result,)))
+                },
+            );
+        }
+        c.method_with_cr_async(
+            "GetMediaState",
+            ("device",),
+            ("state",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_media_state(&device).map(|state| (state,)))
+            },
+        );
+        c.method(
+            "GetBatteryPercentage",
+            ("device",),
+            ("percentage",),
+            move |_, _, (device,): (Path<'static>,)| Ok((get_battery_percentage(&device)?,)),
+        );
+    });
+    token
+}
@@ -1,3 +1,16 @@
+pub mod adapter_state;
+pub mod anonymous;
 pub mod bluetooth_agent_dbus;
 pub mod bluetooth_manager;
 pub mod bluetooth_manager_dbus;
+pub mod cleanup;
+pub mod device_history;
+pub mod device_services;
+pub mod discoverable;
+pub mod media;
+pub mod media_dbus;
+pub mod obex;
+pub mod pairing;
+pub mod privacy;
+pub mod rssi;
+pub mod scan;
@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Component, Path as FsPath, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection, SyncConnection},
+    channel::{MatchingReceiver, Sender},
+    message::MatchRule,
+    nonblock, Message, MethodErr, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::bluetooth_manager::get_objects;
+use crate::{
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{
+        get_daemon_config_value, load_state_file, save_state_file, session_channel,
+        session_connection,
+    },
+};
+
+const OBEX_SERVICE: &str = "org.bluez.obex";
+const OBEX_PATH: &str = "/org/bluez/obex";
+const OBEX_AGENT_MANAGER_INTERFACE: &str = "org.bluez.obex.AgentManager1";
+const OBEX_AGENT_INTERFACE: &str = "org.bluez.obex.Agent1";
+const OBEX_TRANSFER_INTERFACE: &str = "org.bluez.obex.Transfer1";
+const OBEX_SESSION_INTERFACE: &str = "org.bluez.obex.Session1";
+const OBEX_AGENT_PATH: &str = "/org/Xetibo/ReSet/ObexAgent";
+
+const REJECTED: &str = "org.bluez.obex.Error.Rejected";
+const CANCELED: &str = "org.bluez.obex.Error.Canceled";
+
+const OBEX_FILE: &str = "obex.toml";
+
+/// obexd cancels the push on its own shortly after, unanswered requests are rejected in any case.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Policies of SetObexPolicy, default accepts pushes of trusted devices and asks for all others.
+pub const OBEX_POLICIES: &[&str] = &["default", "accept", "prompt", "reject"];
+
+/// Arguments of the ObexTransferRequested signal as (id, device, file name, size in bytes).
+pub type ObexTransferRequested = (u32, Path<'static>, String, u64);
+
+static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Download directory and the policies keyed by the device address.
+/// ```toml
+/// download_dir = "/home/user/Downloads"
+///
+/// [policies]
+/// "00:1A:7D:DA:71:13" = "accept"
+/// ```
+static SETTINGS: Lazy<Mutex<toml::Table>> = Lazy::new(|| Mutex::new(load_state_file(OBEX_FILE)));
+
+/// Pushes forwarded to clients, keyed by the id of the ObexTransferRequested signal.
+static PENDING_TRANSFERS: Lazy<Mutex<HashMap<u32, TransferRequest>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct TransferRequest {
+    message: Message,
+    name: String,
+    agent: Arc<SyncConnection>,
+}
+
+impl TransferRequest {
+    fn reply_error(&self, name: &'static str, message: &str) {
+        let _ = self
+            .agent
+            .send(MethodErr::from((name, message)).to_message(&self.message));
+    }
+
+    fn accept(&self) -> bool {
+        let path = download_path(&download_dir(), &self.name, |path| path.exists());
+        let reply = self
+            .message
+            .method_return()
+            .append1(path.to_string_lossy().to_string());
+        self.agent.send(reply).is_ok()
+    }
+}
+
+/// Resolves the policy of a device, devices without a policy are accepted if they are trusted.
+pub fn effective_policy(policy: Option<&str>, trusted: bool) -> &'static str {
+    match policy {
+        Some("accept") => "accept",
+        Some("reject") => "reject",
+        Some("prompt") => "prompt",
+        _ if trusted => "accept",
+        _ => "prompt",
+    }
+}
+
+/// Returns the path a pushed file is stored at, existing files are never overwritten and get a
+/// suffix instead, e.g. "photo (1).jpg".\
+/// Directories within the name sent by the remote device are ignored.
+pub fn download_path(dir: &FsPath, name: &str, exists: impl Fn(&FsPath) -> bool) -> PathBuf {
+    let name = name
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+        .unwrap_or("file");
+    let path = dir.join(name);
+    if !exists(&path) {
+        return path;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    let mut index = 1;
+    loop {
+        let path = dir.join(format!("{} ({}){}", stem, index, extension));
+        if !exists(&path) {
+            return path;
+        }
+        index += 1;
+    }
+}
+
+/// Returns the configured download directory, $HOME/Downloads by default.
+pub fn download_dir() -> PathBuf {
+    let settings = SETTINGS.lock().unwrap();
+    if let Some(dir) = settings.get("download_dir").and_then(|dir| dir.as_str()) {
+        return PathBuf::from(dir);
+    }
+    PathBuf::from(env::var("HOME").unwrap_or_default()).join("Downloads")
+}
+
+/// Remote devices choose the names of the files, hence they are only stored in directories below
+/// the home directory which are not hidden, e.g. never in ~/.config/autostart or the home itself.\
+/// Both paths have to be canonical.
+pub fn is_allowed_download_dir(dir: &FsPath, home: &FsPath) -> bool {
+    let Ok(relative) = dir.strip_prefix(home) else {
+        return false;
+    };
+    let mut components = relative.components().peekable();
+    components.peek().is_some()
+        && components.all(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+            _ => false,
+        })
+}
+
+/// Sets the directory received files are stored in, the directory has to exist already.
+pub fn set_download_dir(dir: &str) -> Result<(), MethodErr> {
+    let invalid = || {
+        status_error(
+            StatusCode::InvalidArgument,
+            "The download directory has to be an existing, non hidden directory within the home directory",
+        )
+    };
+    let path = FsPath::new(dir);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(invalid());
+    }
+    // symlinks could otherwise point into hidden directories
+    let path = fs::canonicalize(path).map_err(|_| invalid())?;
+    let home = fs::canonicalize(env::var("HOME").unwrap_or_default()).map_err(|_| invalid())?;
+    if !is_allowed_download_dir(&path, &home) {
+        return Err(invalid());
+    }
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.insert(
+        String::from("download_dir"),
+        toml::Value::String(path.to_string_lossy().to_string()),
+    );
+    save_state_file(OBEX_FILE, &settings);
+    Ok(())
+}
+
+fn device_policy(address: &str) -> Option<String> {
+    let settings = SETTINGS.lock().unwrap();
+    settings
+        .get("policies")?
+        .get(address)?
+        .as_str()
+        .map(String::from)
+}
+
+/// Sets the policy for pushes of a device, default removes the policy of the device.
+pub fn set_device_policy(device: &Path<'static>, policy: &str) -> Result<(), MethodErr> {
+    if !OBEX_POLICIES.contains(&policy) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The policy has to be default, accept, prompt or reject",
+        ));
+    }
+    let address = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        device.clone(),
+        BLUEZ_DEVICE_INTERFACE!(),
+        "Address",
+        String,
+    )
+    .map_err(|_| status_error(StatusCode::DeviceNotFound, "The device does not exist"))?;
+    let mut settings = SETTINGS.lock().unwrap();
+    let policies = settings
+        .entry("policies")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if let Some(policies) = policies.as_table_mut() {
+        if policy == "default" {
+            policies.remove(&address);
+        } else {
+            policies.insert(address, toml::Value::String(policy.to_string()));
+        }
+    }
+    save_state_file(OBEX_FILE, &settings);
+    Ok(())
+}
+
+/// Returns the bluez device with the address along with its trusted state.
+fn find_device(address: &str) -> Option<(Path<'static>, bool)> {
+    get_objects().into_iter().find_map(|(path, interfaces)| {
+        let device = interfaces.get(BLUEZ_DEVICE_INTERFACE!())?;
+        let device_address: &String = prop_cast(device, "Address")?;
+        if device_address != address {
+            return None;
+        }
+        let trusted = prop_cast::<bool>(device, "Trusted").copied();
+        Some((path, trusted.unwrap_or(false)))
+    })
+}
+
+/// Registers the daemon as agent of obexd on the session bus, pushes are accepted or rejected
+/// according to the policy of the sending device, unknown devices are forwarded to clients with
+/// ObexTransferRequested and answered with AnswerObexTransfer.\
+/// Enabled with `obex_agent = true` within the `[Daemon]` section of the config.
+pub fn start_obex_agent(connection: Arc<nonblock::SyncConnection>) {
+    let enabled = get_daemon_config_value("obex_agent")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        // obexd runs within the session of the user, regardless of the bus the daemon uses
+        let agent = Arc::new(session_channel().map(SyncConnection::from)?);
+        let agent_ref = agent.clone();
+        agent.start_receive(
+            MatchRule::new_method_call()
+                .with_path(OBEX_AGENT_PATH)
+                .with_interface(OBEX_AGENT_INTERFACE),
+            Box::new(move |msg, _| {
+                handle_agent_call(&connection, &agent_ref, msg);
+                true
+            }),
+        );
+        let res: Result<(), dbus::Error> = agent
+            .with_proxy(OBEX_SERVICE, OBEX_PATH, Duration::from_millis(1000))
+            .method_call(
+                OBEX_AGENT_MANAGER_INTERFACE,
+                "RegisterAgent",
+                (Path::from(OBEX_AGENT_PATH),),
+            );
+        if let Err(error) = res {
+            ERROR!(
+                format!("Failed to register obex agent: {}", error),
+                ErrorLevel::Recoverable
+            );
+            return Err(error);
+        }
+        LOG!("Registered obex agent");
+        loop {
+            agent.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn handle_agent_call(
+    conn: &Arc<nonblock::SyncConnection>,
+    agent: &Arc<SyncConnection>,
+    msg: Message,
+) {
+    let member = msg
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+    let reply = match member.as_str() {
+        "AuthorizePush" => {
+            authorize_push(conn, agent, msg);
+            return;
+        }
+        "Cancel" => {
+            cancel_transfers(conn);
+            msg.method_return()
+        }
+        "Release" => msg.method_return(),
+        _ => MethodErr::no_method(&member).to_message(&msg),
+    };
+    let _ = agent.send(reply);
+}
+
+fn transfer_property<T: for<'b> dbus::arg::Get<'b> + 'static>(
+    path: &Path<'static>,
+    interface: &str,
+    name: &str,
+) -> Option<T> {
+    // a separate connection, the agent connection is busy dispatching this call
+    let conn = session_connection().ok()?;
+    conn.with_proxy(OBEX_SERVICE, path.clone(), Duration::from_millis(1000))
+        .get(interface, name)
+        .ok()
+}
+
+fn authorize_push(conn: &Arc<nonblock::SyncConnection>, agent: &Arc<SyncConnection>, msg: Message) {
+    let transfer: Result<Path<'static>, _> = msg.read1();
+    if let Err(error) = transfer {
+        let _ = agent.send(MethodErr::from(error).to_message(&msg));
+        return;
+    }
+    let transfer = transfer.unwrap();
+    let name: String =
+        transfer_property(&transfer, OBEX_TRANSFER_INTERFACE, "Name").unwrap_or_default();
+    let size: u64 = transfer_property(&transfer, OBEX_TRANSFER_INTERFACE, "Size").unwrap_or(0);
+    let address: String =
+        transfer_property::<Path<'static>>(&transfer, OBEX_TRANSFER_INTERFACE, "Session")
+            .and_then(|session| transfer_property(&session, OBEX_SESSION_INTERFACE, "Destination"))
+            .unwrap_or_default();
+    let device = find_device(&address);
+    let trusted = device.as_ref().is_some_and(|(_, trusted)| *trusted);
+    let device = device
+        .map(|(path, _)| path)
+        .unwrap_or_else(|| Path::from("/"));
+    let policy = device_policy(&address);
+    let request = TransferRequest {
+        message: msg,
+        name: name.clone(),
+        agent: agent.clone(),
+    };
+    match effective_policy(policy.as_deref(), trusted) {
+        "accept" => {
+            LOG!(format!("Accepted push of {} from {}", name, address));
+            request.accept();
+        }
+        "reject" => {
+            LOG!(format!("Rejected push of {} from {}", name, address));
+            request.reply_error(REJECTED, "Pushes of this device are rejected");
+        }
+        _ => {
+            let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+            PENDING_TRANSFERS.lock().unwrap().insert(id, request);
+            let signal = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &BLUETOOTH_INTERFACE!().into(),
+                &"ObexTransferRequested".into(),
+            )
+            .append3(id, device, name)
+            .append1(size);
+            emit_signal(conn, signal);
+            thread::spawn(move || {
+                thread::sleep(REQUEST_TIMEOUT);
+                if let Some(request) = PENDING_TRANSFERS.lock().unwrap().remove(&id) {
+                    request.reply_error(REJECTED, "No client accepted the transfer");
+                }
+            });
+        }
+    }
+}
+
+/// Answers a request of ObexTransferRequested.\
+/// Returns false if the request does not exist, e.g. as it already timed out.
+pub fn answer_transfer(id: u32, accept: bool) -> bool {
+    let request = PENDING_TRANSFERS.lock().unwrap().remove(&id);
+    if request.is_none() {
+        return false;
+    }
+    let request = request.unwrap();
+    if !accept {
+        request.reply_error(REJECTED, "The transfer was rejected by the user");
+        return true;
+    }
+    request.accept()
+}
+
+fn cancel_transfers(conn: &Arc<nonblock::SyncConnection>) {
+    let canceled: Vec<(u32, TransferRequest)> = PENDING_TRANSFERS.lock().unwrap().drain().collect();
+    for (id, request) in canceled {
+        request.reply_error(CANCELED, "The transfer was canceled by obexd");
+        let signal = Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &BLUETOOTH_INTERFACE!().into(),
+            &"ObexTransferCanceled".into(),
+        )
+        .append1(id);
+        emit_signal(conn, signal);
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::Duration,
+};
+
+use dbus::{Message, MethodErr, Path};
+use once_cell::sync::Lazy;
+use re_set_lib::LOG;
+
+use super::device_services::is_keyboard;
+
+/// Time the user has to type the PIN on a keyboard before the pairing is cancelled.
+pub const PAIRING_TIMEOUT: Duration = Duration::from_secs(60);
+const AGENT_REJECTED: &str = "org.bluez.Error.Rejected";
+
+/// PIN shown with PairingPinDisplay for the device currently pairing.
+static PAIRING_PIN: Lazy<Mutex<Option<(Path<'static>, String)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Device currently pairing along with the last step of the pairing.
+static PAIRING_STATE: Lazy<Mutex<Option<(Path<'static>, PairingStage)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Steps of a pairing, either started with PairWithBluetoothDevice or by the remote device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairingStage {
+    Started,
+    PinDisplayed,
+    PinRequested,
+    ConfirmationRequested,
+    AuthorizationRequested,
+}
+
+impl PairingStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PairingStage::Started => "started",
+            PairingStage::PinDisplayed => "pin_displayed",
+            PairingStage::PinRequested => "pin_requested",
+            PairingStage::ConfirmationRequested => "confirmation_requested",
+            PairingStage::AuthorizationRequested => "authorization_requested",
+        }
+    }
+}
+
+/// Creates a random 6 digit PIN for legacy keyboards, which require the PIN to be typed on the
+/// keyboard followed by enter.
+pub fn generate_pin() -> String {
+    // the keys of RandomState are seeded randomly, which suffices for the short pairing window
+    let number = RandomState::new().build_hasher().finish();
+    format!("{:06}", number % 1_000_000)
+}
+
+/// Passkeys of secure simple pairing are always shown with 6 digits.
+pub fn format_passkey(passkey: u32) -> String {
+    format!("{:06}", passkey)
+}
+
+/// Answers RequestPinCode of bluez, only keyboards are supported as the PIN of other devices
+/// can't be entered through the daemon.
+pub fn request_pin_code(device: &Path<'static>) -> Result<String, MethodErr> {
+    if !is_keyboard(device) {
+        return Err(MethodErr::from((
+            AGENT_REJECTED,
+            "PIN codes are only supported for keyboards",
+        )));
+    }
+    LOG!(format!("Generated pairing PIN for keyboard {}", device));
+    Ok(generate_pin())
+}
+
+/// Remembers the PIN and creates the PairingPinDisplay signal.
+pub fn pin_display_signal(device: Path<'static>, pin: String) -> Message {
+    PAIRING_PIN
+        .lock()
+        .unwrap()
+        .replace((device.clone(), pin.clone()));
+    set_pairing_stage(device.clone(), PairingStage::PinDisplayed);
+    Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &"PairingPinDisplay".into(),
+    )
+    .append2(device, pin)
+}
+
+/// Returns the PIN the user has to type on the keyboard currently pairing.
+pub fn get_pairing_pin() -> Option<(Path<'static>, String)> {
+    PAIRING_PIN.lock().unwrap().clone()
+}
+
+/// Moves the pairing of the device to the stage, requests of bluez for another device replace
+/// the pairing as bluez only pairs one device at a time.
+pub fn set_pairing_stage(device: Path<'static>, stage: PairingStage) {
+    PAIRING_STATE.lock().unwrap().replace((device, stage));
+}
+
+/// Returns the device currently pairing and the stage of the pairing.
+pub fn get_pairing_state() -> Option<(Path<'static>, PairingStage)> {
+    PAIRING_STATE.lock().unwrap().clone()
+}
+
+/// Ends the pairing once it finished, was cancelled or timed out.\
+/// Without a device the pairing is ended regardless of the device.
+pub fn finish_pairing(device: Option<&Path<'static>>) {
+    let mut state = PAIRING_STATE.lock().unwrap();
+    if device.is_none() || state.as_ref().map(|(path, _)| path) == device {
+        state.take();
+    }
+}
+
+/// Forgets the PIN once the pairing finished, was cancelled or timed out.\
+/// Without a device the PIN is cleared regardless of the device it belongs to.
+pub fn clear_pairing_pin(device: Option<&Path<'static>>) {
+    let mut pin = PAIRING_PIN.lock().unwrap();
+    if device.is_none() || pin.as_ref().map(|(path, _)| path) == device {
+        pin.take();
+    }
+}
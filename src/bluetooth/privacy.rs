@@ -0,0 +1,239 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use dbus::{blocking::Connection, MethodErr, Path};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::status::{status_error, StatusCode};
+
+const MAIN_CONF: &str = "/etc/bluetooth/main.conf";
+const MAIN_CONF_GROUP: &str = "General";
+const IRK_LENGTH: usize = 16;
+
+/// LE privacy modes of the kernel, limited privacy uses the identity address while discoverable.
+pub const PRIVACY_MODES: &[&str] = &["off", "on", "limited"];
+
+/// Privacy of an adapter as (privacy, just works repairing, supported).\
+/// Supported is false if the privacy can not be changed at runtime, either as the kernel does not
+/// support it for the adapter or btmgmt of bluez is not installed.
+pub type AdapterPrivacy = (String, String, bool);
+
+/// Index of the adapter within the kernel, e.g. 0 for /org/bluez/hci0.
+pub fn adapter_index(path: &Path<'static>) -> Option<u16> {
+    path.rsplit('/').next()?.strip_prefix("hci")?.parse().ok()
+}
+
+/// Returns the settings of a line of btmgmt info, e.g. "current settings".
+pub fn mgmt_settings(info: &str, kind: &str) -> Vec<String> {
+    info.lines()
+        .find_map(|line| line.trim().strip_prefix(kind)?.strip_prefix(':'))
+        .map(|settings| settings.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Maps the status reported by btmgmt for a failed command, e.g. "failed with status 0x14
+/// (Permission Denied)".
+pub fn mgmt_error_code(output: &str) -> StatusCode {
+    if output.contains("Permission Denied") {
+        StatusCode::AuthorizationDenied
+    } else if output.contains("Not Supported") {
+        StatusCode::Unsupported
+    } else if output.contains("Busy") || output.contains("Rejected") {
+        StatusCode::Busy
+    } else if output.contains("Invalid Index") {
+        StatusCode::AdapterNotFound
+    } else {
+        StatusCode::Failed
+    }
+}
+
+fn btmgmt(index: u16, args: &[&str]) -> Result<String, MethodErr> {
+    let output = Command::new("btmgmt")
+        .arg("--index")
+        .arg(index.to_string())
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    if output.is_err() {
+        return Err(status_error(
+            StatusCode::Unsupported,
+            "btmgmt of bluez is not installed",
+        ));
+    }
+    let output = output.unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    // btmgmt exits successfully for most failed commands
+    if !output.status.success() || stdout.contains("failed") {
+        ERROR!(
+            format!("btmgmt {} failed: {}", args.join(" "), stdout.trim()),
+            ErrorLevel::Recoverable
+        );
+        return Err(status_error(mgmt_error_code(&stdout), stdout.trim()));
+    }
+    Ok(stdout)
+}
+
+/// Returns the value of a key within the General group of a main.conf.
+pub fn main_conf_value(contents: &str, key: &str) -> Option<String> {
+    let mut group = "";
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            group = name;
+            continue;
+        }
+        if group != MAIN_CONF_GROUP || line.starts_with('#') {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once('=') {
+            if name.trim() == key {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Maps the Privacy of main.conf to the modes of the kernel, e.g. limited-network to limited.
+fn configured_privacy(contents: &str) -> String {
+    let privacy = main_conf_value(contents, "Privacy").unwrap_or_default();
+    match privacy.as_str() {
+        "network" | "on" | "device" => String::from("on"),
+        "limited-network" | "limited-device" => String::from("limited"),
+        _ => String::from("off"),
+    }
+}
+
+/// Returns the current privacy of the adapter, falls back to the configuration of bluez if the
+/// kernel can not be queried.
+pub fn get_adapter_privacy(path: &Path<'static>) -> Result<AdapterPrivacy, MethodErr> {
+    let index = adapter_index(path)
+        .ok_or_else(|| status_error(StatusCode::AdapterNotFound, "Not a Bluetooth adapter"))?;
+    let contents = fs::read_to_string(MAIN_CONF).unwrap_or_default();
+    let just_works_repairing =
+        main_conf_value(&contents, "JustWorksRepairing").unwrap_or_else(|| String::from("never"));
+    let info = btmgmt(index, &["info"]);
+    if info.is_err() {
+        return Ok((configured_privacy(&contents), just_works_repairing, false));
+    }
+    let info = info.unwrap();
+    let supported = mgmt_settings(&info, "supported settings").contains(&String::from("privacy"));
+    let privacy = if mgmt_settings(&info, "current settings").contains(&String::from("privacy")) {
+        // the kernel does not report whether the privacy is limited
+        if configured_privacy(&contents) == "limited" {
+            String::from("limited")
+        } else {
+            String::from("on")
+        }
+    } else {
+        String::from("off")
+    };
+    Ok((privacy, just_works_repairing, supported))
+}
+
+/// Sets the LE privacy of the adapter, the adapter is powered off while the mode is changed as the
+/// kernel rejects it otherwise.\
+/// Requires CAP_NET_ADMIN, the change is lost once bluetoothd restarts.
+pub fn set_adapter_privacy(
+    path: &Path<'static>,
+    privacy: &str,
+    irk: Option<&str>,
+) -> Result<(), MethodErr> {
+    if !PRIVACY_MODES.contains(&privacy) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The privacy has to be off, on or limited",
+        ));
+    }
+    let index = adapter_index(path)
+        .ok_or_else(|| status_error(StatusCode::AdapterNotFound, "Not a Bluetooth adapter"))?;
+    let powered = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "Powered",
+        bool,
+    )
+    .unwrap_or(false);
+    if powered {
+        set_powered(path, false);
+    }
+    let mut args = vec!["privacy", privacy];
+    args.extend(irk);
+    let res = btmgmt(index, &args);
+    if powered {
+        set_powered(path, true);
+    }
+    res?;
+    LOG!(format!("Set privacy of {} to {}", path, privacy));
+    Ok(())
+}
+
+/// Enables privacy with a new identity resolving key, which makes the adapter advertise with new
+/// random addresses that previously paired devices can not resolve anymore.\
+/// The BR/EDR address of the adapter is not changed.
+pub fn randomize_adapter_address(path: &Path<'static>) -> Result<(), MethodErr> {
+    let mut irk = [0u8; IRK_LENGTH];
+    let res = File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut irk));
+    if res.is_err() {
+        return Err(status_error(
+            StatusCode::Failed,
+            "Could not generate an identity resolving key",
+        ));
+    }
+    let irk: String = irk.iter().map(|byte| format!("{:02x}", byte)).collect();
+    set_adapter_privacy(path, "on", Some(&irk))
+}
+
+/// Runs a change of the privacy on the blocking pool, btmgmt and powering the adapter take a
+/// while.
+pub async fn run_privacy_change<F>(change: F) -> Result<(), MethodErr>
+where
+    F: FnOnce() -> Result<(), MethodErr> + Send + 'static,
+{
+    tokio::task::spawn_blocking(change)
+        .await
+        .unwrap_or_else(|_| {
+            Err(status_error(
+                StatusCode::HandlerPanicked,
+                "Changing the privacy of the adapter failed unexpectedly",
+            ))
+        })
+}
+
+/// JustWorksRepairing is only read from the main.conf of bluez, the daemon does not write the
+/// configuration of the system.\
+/// Always returns the Unsupported status with a hint to change the file instead.
+pub fn set_just_works_repairing() -> Result<(), MethodErr> {
+    Err(status_error(
+        StatusCode::Unsupported,
+        "JustWorksRepairing can only be changed within /etc/bluetooth/main.conf, bluetoothd applies it after a restart",
+    ))
+}
+
+/// Powers the adapter without storing the state, unlike SetBluetoothAdapterEnabled.
+fn set_powered(path: &Path<'static>, powered: bool) {
+    let res = set_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        path.clone(),
+        BLUEZ_ADAPTER_INTERFACE!(),
+        "Powered",
+        powered,
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to power adapter {}: {}", path, _error),
+            ErrorLevel::Recoverable
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use once_cell::sync::Lazy;
+
+use crate::{signals::emit_changed_signal, utils::get_daemon_config_value};
+
+/// Changes smaller than this many dBm are not reported, RSSI jitters by a few dBm even for
+/// devices that do not move.
+const DEFAULT_RSSI_THRESHOLD: i16 = 5;
+
+/// Last reported RSSI of each device.
+static REPORTED_RSSI: Lazy<Mutex<HashMap<Path<'static>, i16>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Threshold in dBm set with `bluetooth_rssi_threshold` within the `[Daemon]` section of the
+/// config, 0 reports every change.
+pub fn rssi_threshold() -> i16 {
+    get_daemon_config_value("bluetooth_rssi_threshold")
+        .and_then(|value| value.as_integer())
+        .and_then(|threshold| i16::try_from(threshold).ok())
+        .filter(|threshold| *threshold >= 0)
+        .unwrap_or(DEFAULT_RSSI_THRESHOLD)
+}
+
+/// Returns true if the RSSI moved far enough from the last reported one.
+pub fn rssi_changed(reported: Option<i16>, rssi: i16, threshold: i16) -> bool {
+    match reported {
+        Some(reported) => (rssi - reported).abs() >= threshold.max(1),
+        None => true,
+    }
+}
+
+/// Emits BluetoothDeviceRSSIChanged if the properties contain an RSSI beyond the threshold.
+pub fn handle_rssi_change(
+    conn: &Arc<SyncConnection>,
+    path: &Path<'static>,
+    changed_properties: &PropMap,
+) {
+    let Some(rssi) = prop_cast::<i16>(changed_properties, "RSSI").copied() else {
+        return;
+    };
+    {
+        let mut reported = REPORTED_RSSI.lock().unwrap();
+        if !rssi_changed(reported.get(path).copied(), rssi, rssi_threshold()) {
+            return;
+        }
+        reported.insert(path.clone(), rssi);
+    }
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &"BluetoothDeviceRSSIChanged".into(),
+    )
+    .append2(path.clone(), rssi);
+    emit_changed_signal(conn, msg, format!("{}/RSSI", path));
+}
+
+pub fn forget_rssi(path: &Path<'static>) {
+    REPORTED_RSSI.lock().unwrap().remove(path);
+}
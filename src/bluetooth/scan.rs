@@ -0,0 +1,137 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{blocking::Connection, nonblock::SyncConnection, Message, Path};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::signals::emit_signal;
+
+/// Scan status as (scanning, remaining seconds), the remaining seconds are 0 for scans without
+/// a scheduled stop.
+pub type ScanStatus = (bool, u32);
+
+/// Discovery state of the daemon, shared by the scan methods and the bluetooth listener.
+pub type SharedScanState = Arc<Mutex<ScanState>>;
+
+/// Identifies the scheduled stop of a timed scan, restarting a scan invalidates older stops.
+static NEXT_SCAN_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanState {
+    Idle,
+    /// Discovery runs until it is stopped.
+    Scanning,
+    /// Discovery is stopped once the deadline passed.
+    Timed {
+        deadline: Instant,
+        id: u64,
+    },
+}
+
+/// Starts discovery on the adapter, a running scan only has its duration replaced.\
+/// Without a duration the scan runs until it is stopped, otherwise BluetoothScanFinished is
+/// emitted once the duration passed.
+pub fn start_scan(
+    conn: &Arc<SyncConnection>,
+    adapter: &Path<'static>,
+    scan: &SharedScanState,
+    duration: Option<Duration>,
+) -> bool {
+    let mut state = scan.lock().unwrap();
+    if *state == ScanState::Idle {
+        let res = dbus_method!(
+            BLUEZ_INTERFACE!(),
+            adapter.clone(),
+            "StartDiscovery",
+            BLUEZ_ADAPTER_INTERFACE!(),
+            (),
+            1000,
+            (),
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to start bluetooth discovery: {}", _error),
+                ErrorLevel::PartialBreakage
+            );
+            return false;
+        }
+    }
+    let Some(duration) = duration else {
+        *state = ScanState::Scanning;
+        return true;
+    };
+    let id = NEXT_SCAN_ID.fetch_add(1, Ordering::SeqCst);
+    *state = ScanState::Timed {
+        deadline: Instant::now() + duration,
+        id,
+    };
+    drop(state);
+    let conn = conn.clone();
+    let adapter = adapter.clone();
+    let scan = scan.clone();
+    thread::spawn(move || {
+        thread::sleep(duration);
+        let expired = matches!(
+            *scan.lock().unwrap(),
+            ScanState::Timed { id: current, .. } if current == id
+        );
+        if expired {
+            LOG!(format!("Bluetooth scan on {} finished", adapter));
+            stop_scan(&conn, &adapter, &scan);
+        }
+    });
+    true
+}
+
+/// Stops discovery on the adapter and emits BluetoothScanFinished if a scan was running.
+pub fn stop_scan(conn: &Arc<SyncConnection>, adapter: &Path<'static>, scan: &SharedScanState) {
+    let mut state = scan.lock().unwrap();
+    if *state == ScanState::Idle {
+        return;
+    }
+    let res = dbus_method!(
+        BLUEZ_INTERFACE!(),
+        adapter.clone(),
+        "StopDiscovery",
+        BLUEZ_ADAPTER_INTERFACE!(),
+        (),
+        1000,
+        (),
+    );
+    if let Err(_error) = res {
+        // bluez only fails if discovery was already stopped, e.g. by powering off the adapter
+        ERROR!(
+            format!("Failed to stop bluetooth discovery: {}", _error),
+            ErrorLevel::Recoverable
+        );
+    }
+    *state = ScanState::Idle;
+    drop(state);
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BLUETOOTH_INTERFACE!().into(),
+        &"BluetoothScanFinished".into(),
+    )
+    .append1(adapter.clone());
+    emit_signal(conn, msg);
+}
+
+pub fn get_scan_status(scan: &SharedScanState) -> ScanStatus {
+    match *scan.lock().unwrap() {
+        ScanState::Idle => (false, 0),
+        ScanState::Scanning => (true, 0),
+        ScanState::Timed { deadline, .. } => {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            // a scan with less than a second left still reports 1 until it finished
+            (true, remaining.as_secs_f64().ceil() as u32)
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    sync::{atomic::Ordering, Arc, RwLock},
+    time::Duration,
+};
+
+use crossbeam::channel::{Receiver, Sender};
+use dbus::{blocking::Connection, nonblock::SyncConnection, Message, Path};
+
+use crate::{
+    battery::battery_manager::{get_batteries, UPOWER_NAME, UPOWER_PATH},
+    modem::modem_manager::get_modems,
+    network::{network_manager::Device, speedtest::speedtest_available},
+    notifications::detect_backend,
+    signals::emit_signal,
+    startup::feature_enabled,
+    supervisor::apply_supervised_state,
+    utils::{AudioRequest, AudioResponse, BASE},
+    DaemonData,
+};
+
+pub const STATUS_ACTIVE: &str = "active";
+pub const STATUS_DEGRADED: &str = "degraded";
+pub const STATUS_UNAVAILABLE: &str = "unavailable";
+
+/// Feature as (name, status, backend, reason).\
+/// The status is one of active, degraded or unavailable, the reason is empty for active features.
+pub type CapabilityDetails = (String, String, String, String);
+
+/// Emits CapabilitiesChanged unless nothing changed, e.g. after a plugin was unloaded.
+pub fn emit_capabilities_changed(
+    conn: &Arc<SyncConnection>,
+    added: Vec<String>,
+    removed: Vec<String>,
+) {
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BASE.into(),
+        &"CapabilitiesChanged".into(),
+    )
+    .append2(added, removed);
+    emit_signal(conn, msg);
+}
+
+/// Everything needed to probe the features outside of the crossroads handler.
+pub struct CapabilityProbe {
+    device: Arc<RwLock<Device>>,
+    bluetooth_adapter: Path<'static>,
+    audio_active: bool,
+    audio_sender: Arc<Sender<AudioRequest>>,
+    audio_receiver: Arc<Receiver<AudioResponse>>,
+    plugins: Vec<(String, Vec<&'static str>)>,
+}
+
+impl CapabilityProbe {
+    pub fn new(data: &DaemonData, plugins: Vec<(String, Vec<&'static str>)>) -> Self {
+        Self {
+            device: data.current_n_device.clone(),
+            bluetooth_adapter: data.b_interface.current_adapter.clone(),
+            audio_active: data.audio_listener_active.load(Ordering::SeqCst),
+            audio_sender: data.audio_sender.clone(),
+            audio_receiver: data.audio_receiver.clone(),
+            plugins,
+        }
+    }
+
+    pub fn probe(&self) -> Vec<CapabilityDetails> {
+        let mut details = vec![
+            self.probe_wifi(),
+            self.probe_bluetooth(),
+            self.probe_audio(),
+            self.probe_modem(),
+            probe_battery(),
+            // players come and go with the applications, the session bus is always available
+            detail("Media", STATUS_ACTIVE, "MPRIS", ""),
+            detail("Proxy", STATUS_ACTIVE, "environment.d", ""),
+            self.probe_do_not_disturb(),
+            probe_speedtest(),
+        ];
+        for (name, capabilities) in self.plugins.iter() {
+            for capability in capabilities {
+                details.push(detail(
+                    capability,
+                    STATUS_ACTIVE,
+                    &format!("Plugin {}", name),
+                    "",
+                ));
+            }
+        }
+        details.retain(|detail| feature_enabled(&detail.0));
+        apply_supervised_state(&mut details);
+        details
+    }
+
+    fn probe_wifi(&self) -> CapabilityDetails {
+        let version = get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            Path::from(NM_PATH!()),
+            NM_INTERFACE!(),
+            "Version",
+            String,
+        );
+        let running = version.is_ok()
+            || dbus_method!(
+                NM_INTERFACE_BASE!(),
+                Path::from(NM_PATH!()),
+                "Introspect",
+                "org.freedesktop.DBus.Introspectable",
+                (),
+                100,
+                (),
+            )
+            .is_ok();
+        if !running {
+            return detail(
+                "WiFi",
+                STATUS_UNAVAILABLE,
+                "NetworkManager",
+                "NetworkManager is not running, install and enable it to manage WiFi.",
+            );
+        }
+        let backend = match version {
+            Ok(version) => format!("NetworkManager {}", version),
+            Err(_) => String::from("NetworkManager"),
+        };
+        if self.device.read().unwrap().dbus_path.is_empty() {
+            return detail(
+                "WiFi",
+                STATUS_DEGRADED,
+                &backend,
+                "No wireless device was found.",
+            );
+        }
+        detail("WiFi", STATUS_ACTIVE, &backend, "")
+    }
+
+    fn probe_bluetooth(&self) -> CapabilityDetails {
+        let res = dbus_method!(
+            BLUEZ_INTERFACE!(),
+            "/",
+            "Introspect",
+            "org.freedesktop.DBus.Introspectable",
+            (),
+            100,
+            (),
+        );
+        // BlueZ does not expose its version over dbus
+        if res.is_err() {
+            return detail(
+                "Bluetooth",
+                STATUS_UNAVAILABLE,
+                "BlueZ",
+                "BlueZ is not running, install and enable the bluetooth service.",
+            );
+        }
+        if &*self.bluetooth_adapter == "/" {
+            return detail(
+                "Bluetooth",
+                STATUS_DEGRADED,
+                "BlueZ",
+                "No Bluetooth adapter was found, it might be blocked by rfkill.",
+            );
+        }
+        detail("Bluetooth", STATUS_ACTIVE, "BlueZ", "")
+    }
+
+    fn probe_audio(&self) -> CapabilityDetails {
+        if !self.audio_active {
+            return detail(
+                "Audio",
+                STATUS_UNAVAILABLE,
+                "PulseAudio",
+                "Could not connect to a PulseAudio compatible server, e.g. pipewire-pulse.",
+            );
+        }
+        let _ = self.audio_sender.send(AudioRequest::GetServerInfo);
+        let response = self.audio_receiver.recv();
+        match response {
+            Ok(AudioResponse::ServerInfo(server)) => detail("Audio", STATUS_ACTIVE, &server, ""),
+            _ => detail(
+                "Audio",
+                STATUS_DEGRADED,
+                "PulseAudio",
+                "The audio server did not respond.",
+            ),
+        }
+    }
+
+    fn probe_modem(&self) -> CapabilityDetails {
+        let version = get_dbus_property!(
+            MM_INTERFACE_BASE!(),
+            Path::from(MM_PATH!()),
+            MM_INTERFACE_BASE!(),
+            "Version",
+            String,
+        );
+        let running = version.is_ok()
+            || dbus_method!(
+                MM_INTERFACE_BASE!(),
+                Path::from(MM_PATH!()),
+                "Introspect",
+                "org.freedesktop.DBus.Introspectable",
+                (),
+                100,
+                (),
+            )
+            .is_ok();
+        if !running {
+            return detail(
+                "Modem",
+                STATUS_UNAVAILABLE,
+                "ModemManager",
+                "ModemManager is not running, install and enable it to manage mobile broadband.",
+            );
+        }
+        let backend = match version {
+            Ok(version) => format!("ModemManager {}", version),
+            Err(_) => String::from("ModemManager"),
+        };
+        if get_modems().is_empty() {
+            return detail("Modem", STATUS_DEGRADED, &backend, "No modem was found.");
+        }
+        detail("Modem", STATUS_ACTIVE, &backend, "")
+    }
+
+    fn probe_do_not_disturb(&self) -> CapabilityDetails {
+        match detect_backend() {
+            Some(backend) => detail("DoNotDisturb", STATUS_ACTIVE, backend.name(), ""),
+            None => detail(
+                "DoNotDisturb",
+                STATUS_UNAVAILABLE,
+                "",
+                "No supported notification daemon, i.e. mako, dunst or swaync, is running.",
+            ),
+        }
+    }
+}
+
+fn probe_battery() -> CapabilityDetails {
+    let version = get_dbus_property!(
+        UPOWER_NAME,
+        Path::from(UPOWER_PATH),
+        UPOWER_NAME,
+        "DaemonVersion",
+        String,
+    );
+    let Ok(version) = version else {
+        return detail(
+            "Battery",
+            STATUS_UNAVAILABLE,
+            "UPower",
+            "UPower is not running, install and enable it to show batteries.",
+        );
+    };
+    let backend = format!("UPower {}", version);
+    if get_batteries().is_empty() {
+        return detail(
+            "Battery",
+            STATUS_DEGRADED,
+            &backend,
+            "No battery was found.",
+        );
+    }
+    detail("Battery", STATUS_ACTIVE, &backend, "")
+}
+
+fn probe_speedtest() -> CapabilityDetails {
+    if speedtest_available() {
+        return detail("SpeedTest", STATUS_ACTIVE, "curl", "");
+    }
+    detail(
+        "SpeedTest",
+        STATUS_UNAVAILABLE,
+        "",
+        "curl is required for the speed test.",
+    )
+}
+
+fn detail(name: &str, status: &str, backend: &str, reason: &str) -> CapabilityDetails {
+    (
+        name.to_string(),
+        status.to_string(),
+        backend.to_string(),
+        reason.to_string(),
+    )
+}
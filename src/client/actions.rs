@@ -0,0 +1,42 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::utils::ACTIONS;
+
+/// org.Xetibo.ReSet.Actions
+impl ReSetClient {
+    pub async fn volume_up(&self, step: u32) -> Result<u32, Error> {
+        self.call(ACTIONS, "VolumeUp", (step,)).await.map(first)
+    }
+
+    pub async fn volume_down(&self, step: u32) -> Result<u32, Error> {
+        self.call(ACTIONS, "VolumeDown", (step,)).await.map(first)
+    }
+
+    pub async fn toggle_mute(&self) -> Result<u32, Error> {
+        self.call(ACTIONS, "ToggleMute", ()).await.map(first)
+    }
+
+    pub async fn toggle_wifi(&self) -> Result<bool, Error> {
+        self.call(ACTIONS, "ToggleWifi", ()).await.map(first)
+    }
+
+    pub async fn toggle_bluetooth(&self) -> Result<bool, Error> {
+        self.call(ACTIONS, "ToggleBluetooth", ()).await.map(first)
+    }
+
+    /// Emits (sink alias, volume in percent, muted).
+    pub async fn subscribe_volume_changed(
+        &self,
+    ) -> Result<SignalStream<(String, u32, bool)>, Error> {
+        self.subscribe(ACTIONS, "VolumeChanged", |args| args).await
+    }
+
+    pub async fn subscribe_wifi_toggled(&self) -> Result<SignalStream<bool>, Error> {
+        self.subscribe(ACTIONS, "WifiToggled", first).await
+    }
+
+    pub async fn subscribe_bluetooth_toggled(&self) -> Result<SignalStream<bool>, Error> {
+        self.subscribe(ACTIONS, "BluetoothToggled", first).await
+    }
+}
@@ -0,0 +1,465 @@
+use dbus::Error;
+use re_set_lib::audio::audio_structures::{Card, InputStream, OutputStream, Sink, Source};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{
+    audio::{
+        network_sinks::NetworkSink, noise_suppression::NoiseSuppressedSource,
+        server_info::AudioServerInfo, stream_state::StreamCorkState,
+    },
+    utils::AUDIO,
+};
+
+/// org.Xetibo.ReSet.Audio
+impl ReSetClient {
+    /// Reads the HasAudioDevices property.
+    pub async fn has_audio_devices(&self) -> Result<bool, Error> {
+        self.property(AUDIO, "HasAudioDevices").await
+    }
+
+    pub async fn get_default_sink(&self) -> Result<Sink, Error> {
+        self.call(AUDIO, "GetDefaultSink", ()).await.map(first)
+    }
+
+    pub async fn get_default_sink_name(&self) -> Result<String, Error> {
+        self.call(AUDIO, "GetDefaultSinkName", ()).await.map(first)
+    }
+
+    pub async fn get_default_source(&self) -> Result<Source, Error> {
+        self.call(AUDIO, "GetDefaultSource", ()).await.map(first)
+    }
+
+    pub async fn get_default_source_name(&self) -> Result<String, Error> {
+        self.call(AUDIO, "GetDefaultSourceName", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn set_default_sink(&self, sink: &str) -> Result<Sink, Error> {
+        self.call(AUDIO, "SetDefaultSink", (sink,)).await.map(first)
+    }
+
+    pub async fn set_default_source(&self, source: &str) -> Result<Source, Error> {
+        self.call(AUDIO, "SetDefaultSource", (source,))
+            .await
+            .map(first)
+    }
+
+    pub async fn list_sinks(&self) -> Result<Vec<Sink>, Error> {
+        self.call(AUDIO, "ListSinks", ()).await.map(first)
+    }
+
+    pub async fn list_sources(&self) -> Result<Vec<Source>, Error> {
+        self.call(AUDIO, "ListSources", ()).await.map(first)
+    }
+
+    /// Replaces the cached objects of the daemon with a new introspection, the list and get calls
+    /// are served from this cache.
+    pub async fn refresh_audio_cache(&self) -> Result<bool, Error> {
+        self.call(AUDIO, "RefreshAudioCache", ()).await.map(first)
+    }
+
+    pub async fn list_input_streams(&self) -> Result<Vec<InputStream>, Error> {
+        self.call(AUDIO, "ListInputStreams", ()).await.map(first)
+    }
+
+    /// Returns (index, corked, suspended) of every input stream.
+    pub async fn list_stream_cork_states(&self) -> Result<Vec<StreamCorkState>, Error> {
+        self.call(AUDIO, "ListStreamCorkStates", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn list_output_streams(&self) -> Result<Vec<OutputStream>, Error> {
+        self.call(AUDIO, "ListOutputStreams", ()).await.map(first)
+    }
+
+    pub async fn list_cards(&self) -> Result<Vec<Card>, Error> {
+        self.call(AUDIO, "ListCards", ()).await.map(first)
+    }
+
+    pub async fn set_sink_volume(
+        &self,
+        index: u32,
+        channels: u16,
+        volume: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetSinkVolume", (index, channels, volume))
+            .await
+    }
+
+    pub async fn set_sink_mute(&self, index: u32, muted: bool) -> Result<(), Error> {
+        self.call(AUDIO, "SetSinkMute", (index, muted)).await
+    }
+
+    /// Returns false if the sink does not exist or the server refused the request.
+    pub async fn suspend_sink(&self, index: u32, suspend: bool) -> Result<bool, Error> {
+        self.call(AUDIO, "SuspendSink", (index, suspend))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_source_volume(
+        &self,
+        index: u32,
+        channels: u16,
+        volume: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetSourceVolume", (index, channels, volume))
+            .await
+    }
+
+    pub async fn set_source_mute(&self, index: u32, muted: bool) -> Result<(), Error> {
+        self.call(AUDIO, "SetSourceMute", (index, muted)).await
+    }
+
+    pub async fn set_sink_of_input_stream(
+        &self,
+        input_stream: u32,
+        sink: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetSinkOfInputStream", (input_stream, sink))
+            .await
+    }
+
+    pub async fn forget_stream_routing(&self, application_name: &str) -> Result<bool, Error> {
+        self.call(AUDIO, "ForgetStreamRouting", (application_name,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (application name, sink name) of all learned routes.
+    pub async fn list_stream_routing(&self) -> Result<Vec<(String, String)>, Error> {
+        self.call(AUDIO, "ListStreamRouting", ()).await.map(first)
+    }
+
+    pub async fn set_input_stream_volume(
+        &self,
+        index: u32,
+        channels: u16,
+        volume: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetInputStreamVolume", (index, channels, volume))
+            .await
+    }
+
+    pub async fn set_input_stream_mute(&self, index: u32, muted: bool) -> Result<(), Error> {
+        self.call(AUDIO, "SetInputStreamMute", (index, muted)).await
+    }
+
+    pub async fn set_source_of_output_stream(
+        &self,
+        output_stream: u32,
+        source: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetSourceOfOutputStream", (output_stream, source))
+            .await
+    }
+
+    pub async fn set_output_stream_volume(
+        &self,
+        index: u32,
+        channels: u16,
+        volume: u32,
+    ) -> Result<(), Error> {
+        self.call(AUDIO, "SetOutputStreamVolume", (index, channels, volume))
+            .await
+    }
+
+    pub async fn set_output_stream_mute(&self, index: u32, muted: bool) -> Result<(), Error> {
+        self.call(AUDIO, "SetOutputStreamMute", (index, muted))
+            .await
+    }
+
+    pub async fn set_card_profile_of_device(
+        &self,
+        device_index: u32,
+        profile_name: &str,
+    ) -> Result<(), Error> {
+        self.call(
+            AUDIO,
+            "SetCardProfileOfDevice",
+            (device_index, profile_name),
+        )
+        .await
+    }
+
+    /// Returns (index, is a monitor, index of the monitored sink).
+    pub async fn list_source_monitors(&self) -> Result<Vec<(u32, bool, u32)>, Error> {
+        self.call(AUDIO, "ListSourceMonitors", ()).await.map(first)
+    }
+
+    pub async fn create_loopback(
+        &self,
+        source_index: u32,
+        sink_index: u32,
+        latency_ms: u32,
+    ) -> Result<u32, Error> {
+        self.call(
+            AUDIO,
+            "CreateLoopback",
+            (source_index, sink_index, latency_ms),
+        )
+        .await
+        .map(first)
+    }
+
+    pub async fn remove_loopback(&self, index: u32) -> Result<bool, Error> {
+        self.call(AUDIO, "RemoveLoopback", (index,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_sink_description(&self, index: u32, description: &str) -> Result<bool, Error> {
+        self.call(AUDIO, "SetSinkDescription", (index, description))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_source_description(
+        &self,
+        index: u32,
+        description: &str,
+    ) -> Result<bool, Error> {
+        self.call(AUDIO, "SetSourceDescription", (index, description))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_noise_suppression(
+        &self,
+        source_index: u32,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.call(AUDIO, "SetNoiseSuppression", (source_index, enabled))
+            .await
+            .map(first)
+    }
+
+    /// Returns (source index, filtered source index).
+    pub async fn list_noise_suppressed_sources(&self) -> Result<Vec<NoiseSuppressedSource>, Error> {
+        self.call(AUDIO, "ListNoiseSuppressedSources", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (index, name, description, kind) of the sinks playing on other devices.
+    pub async fn discover_network_sinks(&self) -> Result<Vec<NetworkSink>, Error> {
+        self.call(AUDIO, "DiscoverNetworkSinks", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn connect_network_sink(&self, host: &str) -> Result<bool, Error> {
+        self.call(AUDIO, "ConnectNetworkSink", (host,))
+            .await
+            .map(first)
+    }
+
+    pub async fn disconnect_network_sink(&self, host: &str) -> Result<bool, Error> {
+        self.call(AUDIO, "DisconnectNetworkSink", (host,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (name, version, sample format, sample rate, channels, channel map).
+    pub async fn get_audio_server_info(&self) -> Result<AudioServerInfo, Error> {
+        self.call(AUDIO, "GetAudioServerInfo", ()).await
+    }
+
+    /// 0 lets the audio server choose the rate again, fails with Unsupported without PipeWire.
+    pub async fn set_default_sample_rate(&self, rate: u32) -> Result<bool, Error> {
+        self.call(AUDIO, "SetDefaultSampleRate", (rate,))
+            .await
+            .map(first)
+    }
+
+    pub async fn start_stream_peak_monitor(&self, index: u32) -> Result<bool, Error> {
+        self.call(AUDIO, "StartStreamPeakMonitor", (index,))
+            .await
+            .map(first)
+    }
+
+    pub async fn stop_stream_peak_monitor(&self, index: u32) -> Result<bool, Error> {
+        self.call(AUDIO, "StopStreamPeakMonitor", (index,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (position, name) of every channel of the sink.
+    pub async fn get_sink_channel_map(&self, index: u32) -> Result<Vec<(String, String)>, Error> {
+        self.call(AUDIO, "GetSinkChannelMap", (index,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (form factor, icon name) of the sink.
+    pub async fn get_sink_hints(&self, index: u32) -> Result<(String, String), Error> {
+        self.call(AUDIO, "GetSinkHints", (index,)).await
+    }
+
+    /// Returns (form factor, icon name) of the source.
+    pub async fn get_source_hints(&self, index: u32) -> Result<(String, String), Error> {
+        self.call(AUDIO, "GetSourceHints", (index,)).await
+    }
+
+    pub async fn play_channel_test(&self, index: u32, position: &str) -> Result<bool, Error> {
+        self.call(AUDIO, "PlayChannelTest", (index, position))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_device_alias(
+        &self,
+        kind: &str,
+        identifier: &str,
+        label: &str,
+    ) -> Result<bool, Error> {
+        self.call(AUDIO, "SetDeviceAlias", (kind, identifier, label))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_volume_limit(&self, percent: u32) -> Result<bool, Error> {
+        self.call(AUDIO, "SetVolumeLimit", (percent,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (limit in percent, default step in percent).
+    pub async fn get_volume_limit(&self) -> Result<(u32, u32), Error> {
+        self.call(AUDIO, "GetVolumeLimit", ()).await
+    }
+
+    /// Returns (index, percent per channel, dB per channel) for every object of the kind.
+    pub async fn list_volume_info(
+        &self,
+        kind: &str,
+    ) -> Result<Vec<(u32, Vec<u32>, Vec<f64>)>, Error> {
+        self.call(AUDIO, "ListVolumeInfo", (kind,)).await.map(first)
+    }
+
+    pub async fn set_sink_volume_percent(&self, index: u32, percent: f64) -> Result<u32, Error> {
+        self.call(AUDIO, "SetSinkVolumePercent", (index, percent))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_source_volume_percent(&self, index: u32, percent: f64) -> Result<u32, Error> {
+        self.call(AUDIO, "SetSourceVolumePercent", (index, percent))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_input_stream_volume_percent(
+        &self,
+        index: u32,
+        percent: f64,
+    ) -> Result<u32, Error> {
+        self.call(AUDIO, "SetInputStreamVolumePercent", (index, percent))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_output_stream_volume_percent(
+        &self,
+        index: u32,
+        percent: f64,
+    ) -> Result<u32, Error> {
+        self.call(AUDIO, "SetOutputStreamVolumePercent", (index, percent))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_auto_headset_profile(&self, enabled: bool) -> Result<bool, Error> {
+        self.call(AUDIO, "SetAutoHeadsetProfile", (enabled,))
+            .await
+            .map(first)
+    }
+
+    pub async fn subscribe_sink_changed(&self) -> Result<SignalStream<Sink>, Error> {
+        self.subscribe(AUDIO, "SinkChanged", first).await
+    }
+
+    pub async fn subscribe_sink_added(&self) -> Result<SignalStream<Sink>, Error> {
+        self.subscribe(AUDIO, "SinkAdded", first).await
+    }
+
+    /// Emits the index of the removed sink.
+    pub async fn subscribe_sink_removed(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(AUDIO, "SinkRemoved", first).await
+    }
+
+    pub async fn subscribe_source_changed(&self) -> Result<SignalStream<Source>, Error> {
+        self.subscribe(AUDIO, "SourceChanged", first).await
+    }
+
+    pub async fn subscribe_source_added(&self) -> Result<SignalStream<Source>, Error> {
+        self.subscribe(AUDIO, "SourceAdded", first).await
+    }
+
+    /// Emits the index of the removed source.
+    pub async fn subscribe_source_removed(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(AUDIO, "SourceRemoved", first).await
+    }
+
+    pub async fn subscribe_input_stream_changed(&self) -> Result<SignalStream<InputStream>, Error> {
+        self.subscribe(AUDIO, "InputStreamChanged", first).await
+    }
+
+    pub async fn subscribe_input_stream_added(&self) -> Result<SignalStream<InputStream>, Error> {
+        self.subscribe(AUDIO, "InputStreamAdded", first).await
+    }
+
+    /// Emits the index of the removed input stream.
+    pub async fn subscribe_input_stream_removed(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(AUDIO, "InputStreamRemoved", first).await
+    }
+
+    /// Emits (index, corked, suspended).
+    pub async fn subscribe_stream_cork_state_changed(
+        &self,
+    ) -> Result<SignalStream<StreamCorkState>, Error> {
+        self.subscribe(AUDIO, "StreamCorkStateChanged", |args| args)
+            .await
+    }
+
+    /// Emits whether any audio device besides the dummy output exists.
+    pub async fn subscribe_audio_devices_available(&self) -> Result<SignalStream<bool>, Error> {
+        self.subscribe(AUDIO, "AudioDevicesAvailable", first).await
+    }
+
+    /// Emits (input stream index, peak between 0.0 and 1.0).
+    pub async fn subscribe_stream_peak(&self) -> Result<SignalStream<(u32, f64)>, Error> {
+        self.subscribe(AUDIO, "StreamPeak", |args| args).await
+    }
+
+    /// Emits the index of the input stream whose peak monitor was ended by the server.
+    pub async fn subscribe_stream_peak_monitor_stopped(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(AUDIO, "StreamPeakMonitorStopped", first)
+            .await
+    }
+
+    pub async fn subscribe_output_stream_changed(
+        &self,
+    ) -> Result<SignalStream<OutputStream>, Error> {
+        self.subscribe(AUDIO, "OutputStreamChanged", first).await
+    }
+
+    pub async fn subscribe_output_stream_added(&self) -> Result<SignalStream<OutputStream>, Error> {
+        self.subscribe(AUDIO, "OutputStreamAdded", first).await
+    }
+
+    /// Emits the index of the removed output stream.
+    pub async fn subscribe_output_stream_removed(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(AUDIO, "OutputStreamRemoved", first).await
+    }
+
+    /// Emits (kind, description, index).
+    pub async fn subscribe_audio_device_connected(
+        &self,
+    ) -> Result<SignalStream<(String, String, u32)>, Error> {
+        self.subscribe(AUDIO, "AudioDeviceConnected", |args| args)
+            .await
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::HashMap, sync::atomic::Ordering};
+
+use dbus::{arg::PropMap, Error};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{plugin::signals::PluginInfo, utils::BASE};
+
+/// org.Xetibo.ReSet.Daemon
+impl ReSetClient {
+    pub async fn get_capabilities(&self) -> Result<Vec<String>, Error> {
+        self.call(BASE, "GetCapabilities", ()).await.map(first)
+    }
+
+    pub async fn api_version(&self) -> Result<String, Error> {
+        self.call(BASE, "APIVersion", ()).await.map(first)
+    }
+
+    pub async fn get_capability_details(
+        &self,
+    ) -> Result<Vec<(String, String, String, String)>, Error> {
+        self.call(BASE, "GetCapabilityDetails", ()).await.map(first)
+    }
+
+    /// Returns (on battery, metered, do not disturb).
+    pub async fn get_system_context(&self) -> Result<(bool, bool, bool), Error> {
+        self.call(BASE, "GetSystemContext", ()).await
+    }
+
+    /// Returns (soft blocked, hard blocked) of the wlan, bluetooth or wwan radios.
+    pub async fn get_rfkill_state(&self, kind: &str) -> Result<(bool, bool), Error> {
+        self.call(BASE, "GetRfkillState", (kind,)).await
+    }
+
+    pub async fn set_rfkill_blocked(&self, kind: &str, blocked: bool) -> Result<bool, Error> {
+        self.call(BASE, "SetRfkillBlocked", (kind, blocked))
+            .await
+            .map(first)
+    }
+
+    /// Returns (event, executable) for every configured hook.
+    pub async fn list_hooks(&self) -> Result<Vec<(String, String)>, Error> {
+        self.call(BASE, "ListHooks", ()).await.map(first)
+    }
+
+    /// Returns (executable, exit code) for every hook of the event.
+    pub async fn test_hook(&self, event: &str) -> Result<Vec<(String, i32)>, Error> {
+        self.call(BASE, "TestHook", (event,)).await.map(first)
+    }
+
+    pub async fn get_interface_xml(&self, interface: &str) -> Result<String, Error> {
+        self.call(BASE, "GetInterfaceXML", (interface,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_plugin_info(&self) -> Result<Vec<PluginInfo>, Error> {
+        self.call(BASE, "GetPluginInfo", ()).await.map(first)
+    }
+
+    /// Returns the token identifying the client in the other client methods.\
+    /// Signals negotiates per-client delivery, see RegisterClient for the keys. Once negotiated,
+    /// the signal streams of this client only receive the signals addressed to it.
+    pub async fn register_client(
+        &self,
+        client_name: &str,
+        signals: PropMap,
+    ) -> Result<String, Error> {
+        let per_client = !signals.is_empty();
+        let token = self
+            .call(BASE, "RegisterClient", (client_name, signals))
+            .await
+            .map(first)?;
+        if per_client {
+            self.per_client.store(true, Ordering::SeqCst);
+        }
+        Ok(token)
+    }
+
+    /// Moves the registration of the token to this connection, e.g. after a crash, and restores
+    /// the requested signal throttling.
+    pub async fn resume_client(&self, token: &str) -> Result<bool, Error> {
+        self.call(BASE, "ResumeClient", (token,)).await.map(first)
+    }
+
+    pub async fn register_throttled_client(
+        &self,
+        token: &str,
+        intervals: HashMap<String, u32>,
+    ) -> Result<bool, Error> {
+        self.call(BASE, "RegisterThrottledClient", (token, intervals))
+            .await
+            .map(first)
+    }
+
+    pub async fn register_delta_client(&self, token: &str) -> Result<bool, Error> {
+        self.call(BASE, "RegisterDeltaClient", (token,))
+            .await
+            .map(first)
+    }
+
+    pub async fn register_strength_threshold_client(
+        &self,
+        token: &str,
+        threshold: u8,
+    ) -> Result<bool, Error> {
+        self.call(BASE, "RegisterStrengthThresholdClient", (token, threshold))
+            .await
+            .map(first)
+    }
+
+    pub async fn unregister_client(&self, token: &str) -> Result<bool, Error> {
+        self.call(BASE, "UnregisterClient", (token,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (client name, unique bus name, registration time) of every registered client.
+    pub async fn list_clients(&self) -> Result<Vec<(String, String, u64)>, Error> {
+        self.call(BASE, "ListClients", ()).await.map(first)
+    }
+
+    /// Returns (name, running, restarts, last error) of every listener thread.
+    pub async fn get_worker_metrics(&self) -> Result<Vec<(String, bool, u32, String)>, Error> {
+        self.call(BASE, "GetWorkerMetrics", ()).await.map(first)
+    }
+
+    pub async fn resync_all(&self) -> Result<(), Error> {
+        self.call(BASE, "ResyncAll", ()).await
+    }
+
+    pub async fn unload_plugin(&self, name: &str) -> Result<bool, Error> {
+        self.call(BASE, "UnloadPlugin", (name,)).await.map(first)
+    }
+
+    pub async fn get_plugin_config(&self, name: &str) -> Result<PropMap, Error> {
+        self.call(BASE, "GetPluginConfig", (name,)).await.map(first)
+    }
+
+    pub async fn set_plugin_config(&self, name: &str, config: PropMap) -> Result<bool, Error> {
+        self.call(BASE, "SetPluginConfig", (name, config))
+            .await
+            .map(first)
+    }
+
+    pub async fn shutdown(&self) -> Result<(), Error> {
+        self.call(BASE, "Shutdown", ()).await
+    }
+
+    /// Emits (capability, status, code, reason) when a backing service stops or was reattached.
+    pub async fn subscribe_capability_state_changed(
+        &self,
+    ) -> Result<SignalStream<(String, String, String, String)>, Error> {
+        self.subscribe(BASE, "CapabilityStateChanged", |state| state)
+            .await
+    }
+
+    /// Emits (added, removed) capabilities, e.g. when a plugin was unloaded.
+    pub async fn subscribe_capabilities_changed(
+        &self,
+    ) -> Result<SignalStream<(Vec<String>, Vec<String>)>, Error> {
+        self.subscribe(BASE, "CapabilitiesChanged", |changes| changes)
+            .await
+    }
+
+    /// Emits (on battery, metered, do not disturb) whenever one of them changed.
+    pub async fn subscribe_system_context_changed(
+        &self,
+    ) -> Result<SignalStream<(bool, bool, bool)>, Error> {
+        self.subscribe(BASE, "SystemContextChanged", |context| context)
+            .await
+    }
+}
@@ -0,0 +1,22 @@
+use dbus::{Error, Path};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{battery::battery_manager::Battery, utils::BATTERY};
+
+/// org.Xetibo.ReSet.Battery
+impl ReSetClient {
+    /// Returns (path, kind, model, percentage, state, power supply), system batteries first.
+    pub async fn list_batteries(&self) -> Result<Vec<Battery>, Error> {
+        self.call(BATTERY, "ListBatteries", ()).await.map(first)
+    }
+
+    pub async fn subscribe_battery_changed(&self) -> Result<SignalStream<Battery>, Error> {
+        self.subscribe(BATTERY, "BatteryChanged", |(battery,)| battery)
+            .await
+    }
+
+    pub async fn subscribe_battery_removed(&self) -> Result<SignalStream<Path<'static>>, Error> {
+        self.subscribe(BATTERY, "BatteryRemoved", |(path,)| path)
+            .await
+    }
+}
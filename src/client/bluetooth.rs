@@ -0,0 +1,386 @@
+use dbus::{Error, Path};
+use re_set_lib::bluetooth::bluetooth_structures::{BluetoothAdapter, BluetoothDevice};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::bluetooth::{
+    bluetooth_manager::BluetoothState,
+    device_history::DeviceHistory,
+    device_services::{BluetoothDeviceWithMode, BluetoothService},
+    obex::ObexTransferRequested,
+    scan::ScanStatus,
+};
+
+const BLUETOOTH: &str = BLUETOOTH_INTERFACE!();
+
+/// org.Xetibo.ReSet.Bluetooth
+impl ReSetClient {
+    pub async fn start_bluetooth_scan(&self) -> Result<(), Error> {
+        self.call(BLUETOOTH, "StartBluetoothScan", ()).await
+    }
+
+    pub async fn stop_bluetooth_scan(&self) -> Result<(), Error> {
+        self.call(BLUETOOTH, "StopBluetoothScan", ()).await
+    }
+
+    pub async fn start_bluetooth_scan_for(&self, seconds: u32) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "StartBluetoothScanFor", (seconds,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_scan_status(&self) -> Result<ScanStatus, Error> {
+        self.call(BLUETOOTH, "GetScanStatus", ()).await
+    }
+
+    /// Returns (powered, scanning, pairing device, pairing stage, connected devices).
+    pub async fn get_bluetooth_state(&self) -> Result<BluetoothState, Error> {
+        self.call(BLUETOOTH, "GetBluetoothState", ()).await
+    }
+
+    pub async fn start_bluetooth_listener(&self) -> Result<(), Error> {
+        self.call(BLUETOOTH, "StartBluetoothListener", ()).await
+    }
+
+    pub async fn stop_bluetooth_listener(&self) -> Result<(), Error> {
+        self.call(BLUETOOTH, "StopBluetoothListener", ()).await
+    }
+
+    pub async fn get_bluetooth_adapters(&self) -> Result<Vec<BluetoothAdapter>, Error> {
+        self.call(BLUETOOTH, "GetBluetoothAdapters", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_current_bluetooth_adapter(&self) -> Result<BluetoothAdapter, Error> {
+        self.call(BLUETOOTH, "GetCurrentBluetoothAdapter", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn set_bluetooth_adapter(&self, path: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetBluetoothAdapter", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_bluetooth_adapter_enabled(
+        &self,
+        path: Path<'static>,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetBluetoothAdapterEnabled", (path, enabled))
+            .await
+            .map(first)
+    }
+
+    /// Returns (soft blocked, hard blocked).
+    pub async fn get_bluetooth_adapter_rfkill(
+        &self,
+        path: Path<'static>,
+    ) -> Result<(bool, bool), Error> {
+        self.call(BLUETOOTH, "GetBluetoothAdapterRfkill", (path,))
+            .await
+    }
+
+    /// Returns (privacy, just works repairing, supported).
+    pub async fn get_bluetooth_adapter_privacy(
+        &self,
+        path: Path<'static>,
+    ) -> Result<(String, String, bool), Error> {
+        self.call(BLUETOOTH, "GetBluetoothAdapterPrivacy", (path,))
+            .await
+    }
+
+    pub async fn set_bluetooth_adapter_privacy(
+        &self,
+        path: Path<'static>,
+        privacy: &str,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetBluetoothAdapterPrivacy", (path, privacy))
+            .await
+            .map(first)
+    }
+
+    pub async fn randomize_adapter_address(&self, path: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "RandomizeAdapterAddress", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_just_works_repairing(&self, mode: &str) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetJustWorksRepairing", (mode,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_obex_policy(
+        &self,
+        device: Path<'static>,
+        policy: &str,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetObexPolicy", (device, policy))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_obex_download_dir(&self, path: &str) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetObexDownloadDir", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn answer_obex_transfer(&self, id: u32, accept: bool) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "AnswerObexTransfer", (id, accept))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_bluetooth_adapter_discoverability(
+        &self,
+        path: Path<'static>,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.call(
+            BLUETOOTH,
+            "SetBluetoothAdapterDiscoverability",
+            (path, enabled),
+        )
+        .await
+        .map(first)
+    }
+
+    pub async fn make_discoverable_for(&self, seconds: u32) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "MakeDiscoverableFor", (seconds,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_bluetooth_adapter_pairability(
+        &self,
+        path: Path<'static>,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetBluetoothAdapterPairability", (path, enabled))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_pairing_pin(&self) -> Result<(Path<'static>, String), Error> {
+        self.call(BLUETOOTH, "GetPairingPin", ()).await
+    }
+
+    pub async fn get_bluetooth_devices(&self) -> Result<Vec<BluetoothDevice>, Error> {
+        self.call(BLUETOOTH, "GetBluetoothDevices", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns the amount of anonymous LE devices left out of GetBluetoothDevices.
+    pub async fn get_anonymous_device_count(&self) -> Result<u32, Error> {
+        self.call(BLUETOOTH, "GetAnonymousDeviceCount", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_connected_bluetooth_devices(&self) -> Result<Vec<BluetoothDevice>, Error> {
+        self.call(BLUETOOTH, "GetConnectedBluetoothDevices", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (last seen, last connected) in seconds since the unix epoch.
+    pub async fn get_bluetooth_device_history(&self, address: &str) -> Result<(u64, u64), Error> {
+        self.call(BLUETOOTH, "GetBluetoothDeviceHistory", (address,))
+            .await
+    }
+
+    pub async fn list_bluetooth_device_history(&self) -> Result<Vec<DeviceHistory>, Error> {
+        self.call(BLUETOOTH, "ListBluetoothDeviceHistory", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns the device type and the services of a device as (uuid, name).
+    pub async fn get_bluetooth_device_services(
+        &self,
+        path: Path<'static>,
+    ) -> Result<(String, Vec<BluetoothService>), Error> {
+        self.call(BLUETOOTH, "GetBluetoothDeviceServices", (path,))
+            .await
+    }
+
+    pub async fn get_bluetooth_device_mode(&self, path: Path<'static>) -> Result<String, Error> {
+        self.call(BLUETOOTH, "GetBluetoothDeviceMode", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn list_bluetooth_devices(
+        &self,
+        include_le_only: bool,
+    ) -> Result<Vec<BluetoothDeviceWithMode>, Error> {
+        self.call(BLUETOOTH, "ListBluetoothDevices", (include_le_only,))
+            .await
+            .map(first)
+    }
+
+    pub async fn list_connected_bluetooth_devices(
+        &self,
+        include_le_only: bool,
+    ) -> Result<Vec<BluetoothDeviceWithMode>, Error> {
+        self.call(
+            BLUETOOTH,
+            "ListConnectedBluetoothDevices",
+            (include_le_only,),
+        )
+        .await
+        .map(first)
+    }
+
+    pub async fn connect_to_bluetooth_device(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "ConnectToBluetoothDevice", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn disconnect_from_bluetooth_device(
+        &self,
+        device: Path<'static>,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "DisconnectFromBluetoothDevice", (device,))
+            .await
+            .map(first)
+    }
+
+    /// The uuid is one of [`super::profiles`] or another UUID offered by the device.
+    pub async fn connect_bluetooth_device_profile(
+        &self,
+        device: Path<'static>,
+        uuid: &str,
+    ) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "ConnectBluetoothDeviceProfile", (device, uuid))
+            .await
+            .map(first)
+    }
+
+    pub async fn disconnect_bluetooth_device_profile(
+        &self,
+        device: Path<'static>,
+        uuid: &str,
+    ) -> Result<bool, Error> {
+        self.call(
+            BLUETOOTH,
+            "DisconnectBluetoothDeviceProfile",
+            (device, uuid),
+        )
+        .await
+        .map(first)
+    }
+
+    /// Returns (name, uuid) for every profile in [`super::profiles`].
+    pub async fn get_bluetooth_profile_uuids(&self) -> Result<Vec<(String, String)>, Error> {
+        self.call(BLUETOOTH, "GetBluetoothProfileUUIDs", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn remove_device_pairing(&self, path: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "RemoveDevicePairing", (path,))
+            .await
+            .map(first)
+    }
+
+    /// Returns the amount of removed devices.
+    pub async fn remove_all_unpaired_devices(&self) -> Result<u32, Error> {
+        self.call(BLUETOOTH, "RemoveAllUnpairedDevices", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns the amount of removed devices.
+    pub async fn remove_devices_not_seen_since(&self, timestamp: u64) -> Result<u32, Error> {
+        self.call(BLUETOOTH, "RemoveDevicesNotSeenSince", (timestamp,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_bluetooth_auto_route(&self, enabled: bool) -> Result<bool, Error> {
+        self.call(BLUETOOTH, "SetBluetoothAutoRoute", (enabled,))
+            .await
+            .map(first)
+    }
+
+    pub async fn subscribe_bluetooth_device_added(
+        &self,
+    ) -> Result<SignalStream<BluetoothDevice>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothDeviceAdded", first)
+            .await
+    }
+
+    pub async fn subscribe_bluetooth_device_removed(
+        &self,
+    ) -> Result<SignalStream<Path<'static>>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothDeviceRemoved", first)
+            .await
+    }
+
+    pub async fn subscribe_bluetooth_device_changed(
+        &self,
+    ) -> Result<SignalStream<BluetoothDevice>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothDeviceChanged", first)
+            .await
+    }
+
+    /// Emits (device, RSSI in dBm) once the RSSI moved by the configured threshold.
+    pub async fn subscribe_bluetooth_device_rssi_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, i16)>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothDeviceRSSIChanged", |rssi| rssi)
+            .await
+    }
+
+    pub async fn subscribe_anonymous_device_count_changed(
+        &self,
+    ) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(BLUETOOTH, "AnonymousDeviceCountChanged", first)
+            .await
+    }
+
+    /// Emits (device, success, error name, status code).
+    pub async fn subscribe_bluetooth_connect_result(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, bool, String, String)>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothConnectResult", |args| args)
+            .await
+    }
+
+    /// Emits the adapter of the finished scan.
+    pub async fn subscribe_bluetooth_scan_finished(
+        &self,
+    ) -> Result<SignalStream<Path<'static>>, Error> {
+        self.subscribe(BLUETOOTH, "BluetoothScanFinished", first)
+            .await
+    }
+
+    /// Emits the adapter which is no longer discoverable.
+    pub async fn subscribe_discoverable_expired(
+        &self,
+    ) -> Result<SignalStream<Path<'static>>, Error> {
+        self.subscribe(BLUETOOTH, "DiscoverableExpired", first)
+            .await
+    }
+
+    /// Emits (id, device, file name, size).
+    pub async fn subscribe_obex_transfer_requested(
+        &self,
+    ) -> Result<SignalStream<ObexTransferRequested>, Error> {
+        self.subscribe(BLUETOOTH, "ObexTransferRequested", |request| request)
+            .await
+    }
+
+    /// Emits the id of the canceled request.
+    pub async fn subscribe_obex_transfer_canceled(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(BLUETOOTH, "ObexTransferCanceled", first)
+            .await
+    }
+}
@@ -0,0 +1,72 @@
+use dbus::{Error, Path};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::bluetooth::media::{BluetoothMediaState, BluetoothMediaTrack};
+
+const BLUETOOTH_MEDIA: &str = BLUETOOTH_MEDIA_INTERFACE!();
+
+/// org.Xetibo.ReSet.BluetoothMedia
+impl ReSetClient {
+    pub async fn play(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH_MEDIA, "Play", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn pause(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH_MEDIA, "Pause", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn next(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH_MEDIA, "Next", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn previous(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(BLUETOOTH_MEDIA, "Previous", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_media_state(
+        &self,
+        device: Path<'static>,
+    ) -> Result<BluetoothMediaState, Error> {
+        self.call(BLUETOOTH_MEDIA, "GetMediaState", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_battery_percentage(&self, device: Path<'static>) -> Result<u8, Error> {
+        self.call(BLUETOOTH_MEDIA, "GetBatteryPercentage", (device,))
+            .await
+            .map(first)
+    }
+
+    /// Emits (device, track).
+    pub async fn subscribe_track_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, BluetoothMediaTrack)>, Error> {
+        self.subscribe(BLUETOOTH_MEDIA, "TrackChanged", |args| args)
+            .await
+    }
+
+    /// Emits (device, status).
+    pub async fn subscribe_playback_status_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, String)>, Error> {
+        self.subscribe(BLUETOOTH_MEDIA, "PlaybackStatusChanged", |args| args)
+            .await
+    }
+
+    /// Emits (device, percentage).
+    pub async fn subscribe_battery_percentage_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, u8)>, Error> {
+        self.subscribe(BLUETOOTH_MEDIA, "BatteryPercentageChanged", |args| args)
+            .await
+    }
+}
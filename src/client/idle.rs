@@ -0,0 +1,27 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{inhibitor::InhibitorInfo, utils::IDLE};
+
+/// org.Xetibo.ReSet.Idle
+impl ReSetClient {
+    /// The inhibitor belongs to the connection of this client, it is released once the connection
+    /// is closed.
+    pub async fn inhibit(&self, reason: &str) -> Result<u32, Error> {
+        self.call(IDLE, "Inhibit", (reason,)).await.map(first)
+    }
+
+    pub async fn uninhibit(&self, cookie: u32) -> Result<bool, Error> {
+        self.call(IDLE, "Uninhibit", (cookie,)).await.map(first)
+    }
+
+    pub async fn list_inhibitors(&self) -> Result<Vec<InhibitorInfo>, Error> {
+        self.call(IDLE, "ListInhibitors", ()).await.map(first)
+    }
+
+    pub async fn subscribe_inhibitors_changed(
+        &self,
+    ) -> Result<SignalStream<Vec<InhibitorInfo>>, Error> {
+        self.subscribe(IDLE, "InhibitorsChanged", first).await
+    }
+}
@@ -0,0 +1,26 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{media::MediaPlayer, utils::MEDIA};
+
+/// org.Xetibo.ReSet.Media
+impl ReSetClient {
+    pub async fn list_players(&self) -> Result<Vec<MediaPlayer>, Error> {
+        self.call(MEDIA, "ListPlayers", ()).await.map(first)
+    }
+
+    pub async fn player_action(&self, player: &str, action: &str) -> Result<bool, Error> {
+        self.call(MEDIA, "PlayerAction", (player, action))
+            .await
+            .map(first)
+    }
+
+    pub async fn subscribe_now_playing_changed(&self) -> Result<SignalStream<MediaPlayer>, Error> {
+        self.subscribe(MEDIA, "NowPlayingChanged", first).await
+    }
+
+    /// Emits the bus name of the removed player.
+    pub async fn subscribe_player_removed(&self) -> Result<SignalStream<String>, Error> {
+        self.subscribe(MEDIA, "PlayerRemoved", first).await
+    }
+}
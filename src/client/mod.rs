@@ -0,0 +1,184 @@
+//! # Typed client for the ReSet-Daemon
+//! Async wrappers around the DBus API of the daemon, available with the `client` feature.\
+//! Methods are named after the DBus methods in snake case and return the same types, see
+//! [`crate::api::API`] for the documentation of each method.
+//! ```no_run
+//! use reset_daemon::client::ReSetClient;
+//!
+//! #[tokio::main]
+//! pub async fn main() {
+//!     let client = ReSetClient::new().await.unwrap();
+//!     let sinks = client.list_sinks().await.unwrap();
+//!     let mut changed = client.subscribe_sink_changed().await.unwrap();
+//!     while let Some(sink) = changed.next().await {
+//!         // update your applet
+//!     }
+//! }
+//! ```
+mod actions;
+mod audio;
+mod base;
+mod battery;
+mod bluetooth;
+mod bluetooth_media;
+mod idle;
+mod media;
+mod modem;
+mod network;
+mod notifications;
+mod proxy;
+mod speedtest;
+
+/// UUIDs of Bluetooth profiles, see [`ReSetClient::connect_bluetooth_device_profile`].
+pub use crate::bluetooth::device_services::profiles;
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use dbus::{
+    arg::{AppendAll, Get, ReadAll},
+    message::MatchRule,
+    nonblock::{stdintf::org_freedesktop_dbus::Properties, MsgMatch, Proxy, SyncConnection},
+    Error,
+};
+use futures_core::Stream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use crate::utils::{session_connection_sync, BASE};
+
+/// Connection to the daemon on the session bus.
+#[derive(Clone)]
+pub struct ReSetClient {
+    connection: Arc<SyncConnection>,
+    timeout: Duration,
+    /// Set once per-client delivery was negotiated with RegisterClient, the broadcast copies of
+    /// signals are then ignored.
+    per_client: Arc<AtomicBool>,
+}
+
+impl ReSetClient {
+    /// Connects to the session bus, the connection is driven by a tokio task.
+    pub async fn new() -> Result<Self, Error> {
+        let (resource, connection) = session_connection_sync()?;
+        tokio::spawn(async {
+            let err = resource.await;
+            panic!("Lost connection to D-Bus: {}", err);
+        });
+        Ok(Self::with_connection(connection))
+    }
+
+    /// Uses an existing connection, e.g. one shared with the rest of the application.
+    pub fn with_connection(connection: Arc<SyncConnection>) -> Self {
+        Self {
+            connection,
+            timeout: Duration::from_millis(5000),
+            per_client: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Timeout of method calls, defaults to 5 seconds.\
+    /// Scans and connection attempts might take longer than the default.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    async fn call<A: AppendAll, R: ReadAll + 'static>(
+        &self,
+        interface: &str,
+        method: &str,
+        args: A,
+    ) -> Result<R, Error> {
+        let proxy = Proxy::new(BASE, DBUS_PATH!(), self.timeout, self.connection.clone());
+        proxy.method_call(interface, method, args).await
+    }
+
+    async fn property<R>(&self, interface: &str, name: &str) -> Result<R, Error>
+    where
+        R: for<'b> Get<'b> + 'static,
+    {
+        let proxy = Proxy::new(BASE, DBUS_PATH!(), self.timeout, self.connection.clone());
+        proxy.get(interface, name).await
+    }
+
+    async fn subscribe<R, T, F>(
+        &self,
+        interface: &str,
+        member: &str,
+        map: F,
+    ) -> Result<SignalStream<T>, Error>
+    where
+        R: ReadAll + Send + 'static,
+        T: Send + 'static,
+        F: Fn(R) -> T + Send + 'static,
+    {
+        let rule = MatchRule::new_signal(interface.to_string(), member.to_string())
+            .with_sender(BASE)
+            .with_path(DBUS_PATH!());
+        let (sender, receiver) = unbounded_channel();
+        let per_client = self.per_client.clone();
+        let signal = self
+            .connection
+            .add_match(rule)
+            .await?
+            .cb(move |msg, args: R| {
+                if per_client.load(Ordering::SeqCst) && msg.destination().is_none() {
+                    return true;
+                }
+                sender.send(map(args)).is_ok()
+            });
+        Ok(SignalStream {
+            receiver,
+            connection: self.connection.clone(),
+            signal: Some(signal),
+        })
+    }
+}
+
+/// Stream of signal arguments, the match is removed from the connection when dropped.\
+/// Use [`SignalStream::next`] or any stream combinator.
+pub struct SignalStream<T> {
+    receiver: UnboundedReceiver<T>,
+    connection: Arc<SyncConnection>,
+    signal: Option<MsgMatch>,
+}
+
+impl<T> SignalStream<T> {
+    /// Waits for the next signal.
+    pub async fn next(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+}
+
+impl<T> Stream for SignalStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for SignalStream<T> {
+    fn drop(&mut self) {
+        let signal = self.signal.take();
+        let runtime = tokio::runtime::Handle::try_current();
+        if signal.is_none() || runtime.is_err() {
+            return;
+        }
+        let connection = self.connection.clone();
+        runtime.unwrap().spawn(async move {
+            let _ = connection.remove_match(signal.unwrap().token()).await;
+        });
+    }
+}
+
+/// Unwraps single value replies and signals.
+fn first<T>((value,): (T,)) -> T {
+    value
+}
@@ -0,0 +1,30 @@
+use dbus::{Error, Path};
+
+use super::{first, ReSetClient};
+use crate::{
+    modem::modem_manager::{MobileConnection, Modem},
+    utils::MODEM,
+};
+
+/// org.Xetibo.ReSet.Modem
+impl ReSetClient {
+    pub async fn list_modems(&self) -> Result<Vec<Modem>, Error> {
+        self.call(MODEM, "ListModems", ()).await.map(first)
+    }
+
+    pub async fn list_mobile_connections(&self) -> Result<Vec<MobileConnection>, Error> {
+        self.call(MODEM, "ListMobileConnections", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn activate_mobile_connection(
+        &self,
+        connection: Path<'static>,
+        modem: Path<'static>,
+    ) -> Result<bool, Error> {
+        self.call(MODEM, "ActivateMobileConnection", (connection, modem))
+            .await
+            .map(first)
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::HashMap;
+
+use dbus::{arg::PropMap, Error, Path};
+use re_set_lib::network::network_structures::{AccessPoint, WifiDevice};
+
+use super::{first, ReSetClient, SignalStream};
+use crate::network::{
+    access_point_cache::CachedAccessPoint,
+    address_family::AddressFamilies,
+    channel_advisor::ChannelUtilization,
+    dns::DnsState,
+    hotspot_clients::HotspotClient,
+    link_info::WifiLinkInfo,
+    network_manager::{AccessPointGroup, AccessPointRadio, AutoconnectEntry},
+    operation_queue::QueuedOperation,
+    priority_learning::LearnedLocation,
+    secret_agent::SecretsRequested,
+};
+
+const NETWORK: &str = NETWORK_INTERFACE!();
+
+/// org.Xetibo.ReSet.Network
+impl ReSetClient {
+    pub async fn list_access_points(&self) -> Result<Vec<AccessPoint>, Error> {
+        self.call(NETWORK, "ListAccessPoints", ()).await.map(first)
+    }
+
+    pub async fn list_access_point_groups(&self) -> Result<Vec<AccessPointGroup>, Error> {
+        self.call(NETWORK, "ListAccessPointGroups", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (path, frequency, max bitrate, band) for every access point.
+    pub async fn list_access_point_radios(&self) -> Result<Vec<AccessPointRadio>, Error> {
+        self.call(NETWORK, "ListAccessPointRadios", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (ssid, security, last seen, strength history), most recently seen first.
+    pub async fn get_cached_access_points(&self) -> Result<Vec<CachedAccessPoint>, Error> {
+        self.call(NETWORK, "GetCachedAccessPoints", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn set_prefer_strongest_bssid(&self, enabled: bool) -> Result<(), Error> {
+        self.call(NETWORK, "SetPreferStrongestBSSID", (enabled,))
+            .await
+    }
+
+    pub async fn get_prefer_strongest_bssid(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "GetPreferStrongestBSSID", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_wifi_status(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "GetWifiStatus", ()).await.map(first)
+    }
+
+    /// Returns (wireless enabled, wireless hardware enabled, wwan enabled).
+    pub async fn get_radio_states(&self) -> Result<(bool, bool, bool), Error> {
+        self.call(NETWORK, "GetRadioStates", ()).await
+    }
+
+    /// Returns (bitrate in kbit/s, frequency in MHz, WiFi generation, strength).
+    pub async fn get_wifi_link_info(&self, device: Path<'static>) -> Result<WifiLinkInfo, Error> {
+        self.call(NETWORK, "GetWifiLinkInfo", (device,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (family, IPv4 addresses, global IPv6 addresses).
+    pub async fn get_connection_address_families(
+        &self,
+        device: Path<'static>,
+    ) -> Result<AddressFamilies, Error> {
+        self.call(NETWORK, "GetConnectionAddressFamilies", (device,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (band, channel, access points, strongest signal) per channel.
+    pub async fn get_channel_utilization(
+        &self,
+        device: Path<'static>,
+    ) -> Result<Vec<ChannelUtilization>, Error> {
+        self.call(NETWORK, "GetChannelUtilization", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_wake_on_lan(&self, device: Path<'static>) -> Result<String, Error> {
+        self.call(NETWORK, "GetWakeOnLan", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_wake_on_lan(&self, device: Path<'static>, mode: &str) -> Result<bool, Error> {
+        self.call(NETWORK, "SetWakeOnLan", (device, mode))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_wifi_powersave(&self, device: Path<'static>) -> Result<String, Error> {
+        self.call(NETWORK, "GetWifiPowersave", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_wifi_powersave(
+        &self,
+        device: Path<'static>,
+        mode: &str,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "SetWifiPowersave", (device, mode))
+            .await
+            .map(first)
+    }
+
+    /// Returns (cloned mac address, scan randomization).
+    pub async fn get_wifi_mac_settings(
+        &self,
+        connection: Path<'static>,
+    ) -> Result<(String, bool), Error> {
+        self.call(NETWORK, "GetWifiMacSettings", (connection,))
+            .await
+    }
+
+    pub async fn set_wifi_mac_randomization(
+        &self,
+        connection: Path<'static>,
+        mode: &str,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "SetWifiMacRandomization", (connection, mode))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_wifi_scan_mac_randomization(
+        &self,
+        connection: Path<'static>,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.call(
+            NETWORK,
+            "SetWifiScanMacRandomization",
+            (connection, enabled),
+        )
+        .await
+        .map(first)
+    }
+
+    /// An empty broadcast address sends the packet to 255.255.255.255.
+    pub async fn send_magic_packet(&self, mac: &str, broadcast: &str) -> Result<bool, Error> {
+        self.call(NETWORK, "SendMagicPacket", (mac, broadcast))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_wifi_enabled(&self, enabled: bool) -> Result<bool, Error> {
+        self.call(NETWORK, "SetWifiEnabled", (enabled,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_current_wifi_device(&self) -> Result<WifiDevice, Error> {
+        self.call(NETWORK, "GetCurrentWifiDevice", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_all_wifi_devices(&self) -> Result<Vec<WifiDevice>, Error> {
+        self.call(NETWORK, "GetAllWifiDevices", ()).await.map(first)
+    }
+
+    pub async fn set_wifi_device(&self, path: Path<'static>) -> Result<bool, Error> {
+        self.call(NETWORK, "SetWifiDevice", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn connect_to_known_access_point(
+        &self,
+        access_point: AccessPoint,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "ConnectToKnownAccessPoint", (access_point,))
+            .await
+            .map(first)
+    }
+
+    pub async fn connect_to_new_access_point(
+        &self,
+        access_point: AccessPoint,
+        password: &str,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "ConnectToNewAccessPoint", (access_point, password))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_access_point_qr_payload(&self, path: Path<'static>) -> Result<String, Error> {
+        self.call(NETWORK, "GetAccessPointQRPayload", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn add_connection_from_qr_payload(&self, payload: &str) -> Result<bool, Error> {
+        self.call(NETWORK, "AddConnectionFromQRPayload", (payload,))
+            .await
+            .map(first)
+    }
+
+    pub async fn disconnect_from_current_access_point(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "DisconnectFromCurrentAccessPoint", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (operation, target) of the queued operations, the first one is running.
+    pub async fn get_operation_queue(
+        &self,
+        device: Path<'static>,
+    ) -> Result<Vec<QueuedOperation>, Error> {
+        self.call(NETWORK, "GetOperationQueue", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn list_stored_connections(&self) -> Result<Vec<(Path<'static>, Vec<u8>)>, Error> {
+        self.call(NETWORK, "ListStoredConnections", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_connection_settings(
+        &self,
+        path: Path<'static>,
+    ) -> Result<HashMap<String, PropMap>, Error> {
+        self.call(NETWORK, "GetConnectionSettings", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_connection_settings(
+        &self,
+        path: Path<'static>,
+        settings: HashMap<String, PropMap>,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "SetConnectionSettings", (path, settings))
+            .await
+            .map(first)
+    }
+
+    pub async fn export_connection(
+        &self,
+        path: Path<'static>,
+        include_secrets: bool,
+    ) -> Result<String, Error> {
+        self.call(NETWORK, "ExportConnection", (path, include_secrets))
+            .await
+            .map(first)
+    }
+
+    pub async fn import_connection(&self, keyfile: &str) -> Result<Path<'static>, Error> {
+        self.call(NETWORK, "ImportConnection", (keyfile,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_connection_secrets(
+        &self,
+        path: Path<'static>,
+        setting: String,
+    ) -> Result<HashMap<String, PropMap>, Error> {
+        self.call(NETWORK, "GetConnectionSecrets", (path, setting))
+            .await
+            .map(first)
+    }
+
+    pub async fn provide_secrets(
+        &self,
+        id: u32,
+        secrets: HashMap<String, PropMap>,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "ProvideSecrets", (id, secrets))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_connection_dns(
+        &self,
+        path: Path<'static>,
+        servers: Vec<String>,
+        search_domains: Vec<String>,
+        ignore_auto_dns: bool,
+    ) -> Result<bool, Error> {
+        self.call(
+            NETWORK,
+            "SetConnectionDNS",
+            (path, servers, search_domains, ignore_auto_dns),
+        )
+        .await
+        .map(first)
+    }
+
+    /// Returns (mode, resolv.conf manager, servers, configurations).
+    pub async fn get_dns_state(&self) -> Result<DnsState, Error> {
+        self.call(NETWORK, "GetDNSState", ()).await
+    }
+
+    pub async fn set_connection_autoconnect_priority(
+        &self,
+        path: Path<'static>,
+        priority: i32,
+    ) -> Result<bool, Error> {
+        self.call(
+            NETWORK,
+            "SetConnectionAutoconnectPriority",
+            (path, priority),
+        )
+        .await
+        .map(first)
+    }
+
+    pub async fn get_autoconnect_order(&self) -> Result<Vec<AutoconnectEntry>, Error> {
+        self.call(NETWORK, "GetAutoconnectOrder", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn get_learned_autoconnect_priorities(&self) -> Result<Vec<LearnedLocation>, Error> {
+        self.call(NETWORK, "GetLearnedAutoconnectPriorities", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn clear_learned_autoconnect_priorities(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "ClearLearnedAutoconnectPriorities", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn delete_connection(&self, path: Path<'static>) -> Result<bool, Error> {
+        self.call(NETWORK, "DeleteConnection", (path,))
+            .await
+            .map(first)
+    }
+
+    pub async fn set_connection_retry_policy(
+        &self,
+        path: Path<'static>,
+        max_retries: u32,
+        backoff: u32,
+    ) -> Result<bool, Error> {
+        self.call(
+            NETWORK,
+            "SetConnectionRetryPolicy",
+            (path, max_retries, backoff),
+        )
+        .await
+        .map(first)
+    }
+
+    pub async fn blacklist_access_point(
+        &self,
+        bssid: String,
+        duration: u32,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "BlacklistAccessPoint", (bssid, duration))
+            .await
+            .map(first)
+    }
+
+    pub async fn list_blacklisted_bssids(&self) -> Result<Vec<(String, u64)>, Error> {
+        self.call(NETWORK, "ListBlacklistedBSSIDs", ())
+            .await
+            .map(first)
+    }
+
+    /// Returns (valid, reason).
+    pub async fn validate_wifi_credentials(
+        &self,
+        security_flags: u32,
+        password: String,
+        eap: PropMap,
+    ) -> Result<(bool, String), Error> {
+        self.call(
+            NETWORK,
+            "ValidateWifiCredentials",
+            (security_flags, password, eap),
+        )
+        .await
+    }
+
+    pub async fn reassociate_to_strongest_bssid(
+        &self,
+        device: Path<'static>,
+    ) -> Result<bool, Error> {
+        self.call(NETWORK, "ReassociateToStrongestBSSID", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn start_network_listener(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "StartNetworkListener", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn stop_network_listener(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "StopNetworkListener", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn enable_connection_sharing(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(NETWORK, "EnableConnectionSharing", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn disable_connection_sharing(&self, device: Path<'static>) -> Result<bool, Error> {
+        self.call(NETWORK, "DisableConnectionSharing", (device,))
+            .await
+            .map(first)
+    }
+
+    /// Returns (mac address, ip address, hostname, connected since) of the hotspot clients.
+    pub async fn list_hotspot_clients(
+        &self,
+        device: Path<'static>,
+    ) -> Result<Vec<HotspotClient>, Error> {
+        self.call(NETWORK, "ListHotspotClients", (device,))
+            .await
+            .map(first)
+    }
+
+    pub async fn mark_portal_resolved(&self) -> Result<bool, Error> {
+        self.call(NETWORK, "MarkPortalResolved", ())
+            .await
+            .map(first)
+    }
+
+    /// Emits (access point, device).
+    pub async fn subscribe_access_point_changed(
+        &self,
+    ) -> Result<SignalStream<(AccessPoint, Path<'static>)>, Error> {
+        self.subscribe(NETWORK, "AccessPointChanged", |args| args)
+            .await
+    }
+
+    /// Emits (access point, device).
+    pub async fn subscribe_access_point_added(
+        &self,
+    ) -> Result<SignalStream<(AccessPoint, Path<'static>)>, Error> {
+        self.subscribe(NETWORK, "AccessPointAdded", |args| args)
+            .await
+    }
+
+    /// Emits (access point path, device).
+    pub async fn subscribe_access_point_removed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, Path<'static>)>, Error> {
+        self.subscribe(NETWORK, "AccessPointRemoved", |args| args)
+            .await
+    }
+
+    pub async fn subscribe_wifi_device_changed(&self) -> Result<SignalStream<WifiDevice>, Error> {
+        self.subscribe(NETWORK, "WifiDeviceChanged", first).await
+    }
+
+    pub async fn subscribe_autoconnect_suspended(
+        &self,
+    ) -> Result<SignalStream<Path<'static>>, Error> {
+        self.subscribe(NETWORK, "AutoconnectSuspended", first).await
+    }
+
+    /// Emits (device, link info).
+    pub async fn subscribe_wifi_link_info_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, WifiLinkInfo)>, Error> {
+        self.subscribe(NETWORK, "WifiLinkInfoChanged", |args| args)
+            .await
+    }
+
+    /// Emits (device, stage, bssid).
+    pub async fn subscribe_roaming_progress(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, String, String)>, Error> {
+        self.subscribe(NETWORK, "RoamingProgress", |args| args)
+            .await
+    }
+
+    /// Emits the URL of the login page.
+    pub async fn subscribe_captive_portal_detected(&self) -> Result<SignalStream<String>, Error> {
+        self.subscribe(NETWORK, "CaptivePortalDetected", first)
+            .await
+    }
+
+    /// Emits (device, mac address, ip address, hostname, connected since).
+    pub async fn subscribe_hotspot_client_connected(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, String, String, String, u64)>, Error> {
+        self.subscribe(NETWORK, "HotspotClientConnected", |client| client)
+            .await
+    }
+
+    /// Emits (device, mac address).
+    pub async fn subscribe_hotspot_client_disconnected(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, String)>, Error> {
+        self.subscribe(NETWORK, "HotspotClientDisconnected", |client| client)
+            .await
+    }
+
+    pub async fn subscribe_operation_queue_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, Vec<QueuedOperation>)>, Error> {
+        self.subscribe(NETWORK, "OperationQueueChanged", |queue| queue)
+            .await
+    }
+
+    /// Emits (access point, success, status code, detail) after connecting to an access point.
+    pub async fn subscribe_wifi_connect_result(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, bool, String, String)>, Error> {
+        self.subscribe(NETWORK, "WifiConnectResult", |args| args)
+            .await
+    }
+
+    /// Emits (device, state, reason) while a connection is activated.
+    pub async fn subscribe_connection_state_changed(
+        &self,
+    ) -> Result<SignalStream<(Path<'static>, String, String)>, Error> {
+        self.subscribe(NETWORK, "ConnectionStateChanged", |args| args)
+            .await
+    }
+
+    /// Emits (wireless enabled, wireless hardware enabled, wwan enabled).
+    pub async fn subscribe_radio_state_changed(
+        &self,
+    ) -> Result<SignalStream<(bool, bool, bool)>, Error> {
+        self.subscribe(NETWORK, "RadioStateChanged", |states| states)
+            .await
+    }
+
+    /// Emits (id, connection path, connection name, setting, hints, flags).
+    pub async fn subscribe_secrets_requested(
+        &self,
+    ) -> Result<SignalStream<SecretsRequested>, Error> {
+        self.subscribe(NETWORK, "SecretsRequested", |request| request)
+            .await
+    }
+
+    /// Emits the id of the canceled request.
+    pub async fn subscribe_secrets_request_canceled(&self) -> Result<SignalStream<u32>, Error> {
+        self.subscribe(NETWORK, "SecretsRequestCanceled", first)
+            .await
+    }
+}
@@ -0,0 +1,30 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::utils::NOTIFICATIONS;
+
+/// org.Xetibo.ReSet.Notifications
+impl ReSetClient {
+    pub async fn get_do_not_disturb(&self) -> Result<bool, Error> {
+        self.call(NOTIFICATIONS, "GetDoNotDisturb", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn set_do_not_disturb(&self, enabled: bool) -> Result<bool, Error> {
+        self.call(NOTIFICATIONS, "SetDoNotDisturb", (enabled,))
+            .await
+            .map(first)
+    }
+
+    pub async fn get_notification_daemon(&self) -> Result<String, Error> {
+        self.call(NOTIFICATIONS, "GetNotificationDaemon", ())
+            .await
+            .map(first)
+    }
+
+    pub async fn subscribe_do_not_disturb_changed(&self) -> Result<SignalStream<bool>, Error> {
+        self.subscribe(NOTIFICATIONS, "DoNotDisturbChanged", first)
+            .await
+    }
+}
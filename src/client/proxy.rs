@@ -0,0 +1,23 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{proxy::ProxySettings, utils::PROXY};
+
+/// org.Xetibo.ReSet.Proxy
+impl ReSetClient {
+    pub async fn get_proxy_settings(&self) -> Result<ProxySettings, Error> {
+        self.call(PROXY, "GetProxySettings", ()).await.map(first)
+    }
+
+    pub async fn set_proxy_settings(&self, settings: ProxySettings) -> Result<bool, Error> {
+        self.call(PROXY, "SetProxySettings", (settings,))
+            .await
+            .map(first)
+    }
+
+    pub async fn subscribe_proxy_settings_changed(
+        &self,
+    ) -> Result<SignalStream<ProxySettings>, Error> {
+        self.subscribe(PROXY, "ProxySettingsChanged", first).await
+    }
+}
@@ -0,0 +1,40 @@
+use dbus::Error;
+
+use super::{first, ReSetClient, SignalStream};
+use crate::{network::speedtest::SpeedTestResult, utils::SPEEDTEST};
+
+/// org.Xetibo.ReSet.SpeedTest
+impl ReSetClient {
+    /// An empty server uses the first configured server.
+    pub async fn start_speed_test(&self, server: &str) -> Result<bool, Error> {
+        self.call(SPEEDTEST, "StartSpeedTest", (server,))
+            .await
+            .map(first)
+    }
+
+    pub async fn cancel_speed_test(&self) -> Result<bool, Error> {
+        self.call(SPEEDTEST, "CancelSpeedTest", ()).await.map(first)
+    }
+
+    pub async fn list_speed_test_servers(&self) -> Result<Vec<String>, Error> {
+        self.call(SPEEDTEST, "ListSpeedTestServers", ())
+            .await
+            .map(first)
+    }
+
+    /// Emits (stage, value).
+    pub async fn subscribe_speed_test_progress(
+        &self,
+    ) -> Result<SignalStream<(String, f64)>, Error> {
+        self.subscribe(SPEEDTEST, "SpeedTestProgress", |args| args)
+            .await
+    }
+
+    /// Emits (result, error, code).
+    pub async fn subscribe_speed_test_finished(
+        &self,
+    ) -> Result<SignalStream<(SpeedTestResult, String, String)>, Error> {
+        self.subscribe(SPEEDTEST, "SpeedTestFinished", |args| args)
+            .await
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap, HashSet},
+    fs::File,
+    hash::{BuildHasher, Hasher},
+    io::Read,
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam::channel::Sender;
+use dbus::{
+    arg::{PropMap, RefArg},
+    message::MatchRule,
+    nonblock::SyncConnection,
+    MethodErr,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    inhibitor::release_client_inhibitors,
+    signals::{
+        register_delta_client, register_strength_threshold_client, register_throttled_client,
+        unregister_throttled_client, SignalClass,
+    },
+    status::{status_error, StatusCode},
+    utils::{session_connection, AudioRequest},
+};
+
+/// Registrations of disconnected clients can be resumed with ResumeClient for this many seconds.
+const RESUME_TIMEOUT: u64 = 300;
+const TOKEN_BYTES: usize = 16;
+
+/// Registered client as (client name, unique bus name, registration time in seconds since the
+/// unix epoch).\
+/// The unique bus name is empty for clients using a direct connection.
+pub type ClientInfo = (String, String, u64);
+
+/// Signal preferences of a client, negotiated with RegisterClient and restored for the new
+/// connection by ResumeClient.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientPreferences {
+    /// Intervals of RegisterThrottledClient.
+    pub intervals: Option<HashMap<SignalClass, Duration>>,
+    /// Set by RegisterDeltaClient.
+    pub delta: bool,
+    /// Threshold of RegisterStrengthThresholdClient.
+    pub strength_threshold: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct Client {
+    name: String,
+    /// Unique bus name of the current connection, empty while the client is disconnected.
+    sender: String,
+    registered: u64,
+    /// Time the connection of the client closed.
+    disconnected: Option<u64>,
+    preferences: ClientPreferences,
+}
+
+/// Clients registered with RegisterClient and the resources they hold, shared between the
+/// method handlers and the listener that releases disconnected clients.\
+/// Clients are identified by the token issued on registration, which stays valid across
+/// reconnects until the client unregisters.
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    clients: RwLock<HashMap<String, Client>>,
+    /// Unique bus names of the clients that started a peak monitor, keyed by the input stream.
+    peak_monitors: RwLock<HashMap<u32, HashSet<String>>>,
+}
+
+impl ClientRegistry {
+    /// Returns the token of the new registration.\
+    /// Registering an existing name again on the same connection replaces the previous
+    /// registration.
+    pub fn register(&self, client_name: String, sender: &str) -> String {
+        let registered = now();
+        let token = generate_token();
+        let mut clients = self.clients.write().unwrap();
+        remove_expired(&mut clients, registered);
+        clients.retain(|_, client| client.name != client_name || client.sender != sender);
+        clients.insert(
+            token.clone(),
+            Client {
+                name: client_name,
+                sender: sender.to_string(),
+                registered,
+                disconnected: None,
+                preferences: ClientPreferences::default(),
+            },
+        );
+        token
+    }
+
+    /// Removes the registration and returns the unique bus name it was connected with.
+    pub fn unregister(&self, token: &str) -> Option<String> {
+        self.clients
+            .write()
+            .unwrap()
+            .remove(token)
+            .map(|client| client.sender)
+    }
+
+    /// Moves the registration to a new connection, e.g. after the client crashed, and returns its
+    /// preferences.\
+    /// Fails for unknown or expired tokens and registrations still used by another connection.
+    pub fn resume(&self, token: &str, sender: &str) -> Option<ClientPreferences> {
+        let mut clients = self.clients.write().unwrap();
+        remove_expired(&mut clients, now());
+        let client = clients.get_mut(token)?;
+        if client.disconnected.is_none() && client.sender != sender {
+            return None;
+        }
+        client.sender = sender.to_string();
+        client.disconnected = None;
+        Some(client.preferences.clone())
+    }
+
+    /// Changes the preferences of a registration, only the connection of the client may do so.
+    pub fn update_preferences(
+        &self,
+        token: &str,
+        sender: &str,
+        update: impl FnOnce(&mut ClientPreferences),
+    ) -> bool {
+        let mut clients = self.clients.write().unwrap();
+        let client = clients
+            .get_mut(token)
+            .filter(|client| client.disconnected.is_none() && client.sender == sender);
+        if let Some(client) = client {
+            update(&mut client.preferences);
+            return true;
+        }
+        false
+    }
+
+    /// Returns the connected clients ordered by their registration.
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self
+            .clients
+            .read()
+            .unwrap()
+            .values()
+            .filter(|client| client.disconnected.is_none())
+            .map(|client| {
+                (
+                    client.name.clone(),
+                    client.sender.clone(),
+                    client.registered,
+                )
+            })
+            .collect();
+        clients.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+        clients
+    }
+
+    pub fn add_peak_monitor(&self, index: u32, sender: &str) {
+        self.peak_monitors
+            .write()
+            .unwrap()
+            .entry(index)
+            .or_default()
+            .insert(sender.to_string());
+    }
+
+    /// Called once the monitor was stopped, which stops it for every client.
+    pub fn remove_peak_monitor(&self, index: u32) {
+        self.peak_monitors.write().unwrap().remove(&index);
+    }
+
+    /// Disconnects the clients of a connection, returns their names and the peak monitors that
+    /// no other client uses.\
+    /// The registrations are kept for RESUME_TIMEOUT seconds to be resumed with ResumeClient.
+    pub fn remove_connection(&self, sender: &str) -> (Vec<String>, Vec<u32>) {
+        let mut names = Vec::new();
+        let disconnected = now();
+        for client in self.clients.write().unwrap().values_mut() {
+            if client.disconnected.is_some() || client.sender != sender {
+                continue;
+            }
+            names.push(client.name.clone());
+            client.sender.clear();
+            client.disconnected = Some(disconnected);
+        }
+        let mut unused = Vec::new();
+        self.peak_monitors.write().unwrap().retain(|index, owners| {
+            if !owners.remove(sender) || !owners.is_empty() {
+                return true;
+            }
+            unused.push(*index);
+            false
+        });
+        names.sort();
+        unused.sort();
+        (names, unused)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn remove_expired(clients: &mut HashMap<String, Client>, now: u64) {
+    clients.retain(|_, client| {
+        client
+            .disconnected
+            .is_none_or(|disconnected| now < disconnected + RESUME_TIMEOUT)
+    });
+}
+
+/// Tokens are handed to the client only, knowing one allows resuming its registration.
+fn generate_token() -> String {
+    let mut bytes = [0_u8; TOKEN_BYTES];
+    let res = File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes));
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not read random bytes for a client token: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        // the keys of RandomState are seeded randomly as well
+        for chunk in bytes.chunks_mut(8) {
+            let random = RandomState::new().build_hasher().finish().to_ne_bytes();
+            chunk.copy_from_slice(&random[..chunk.len()]);
+        }
+    }
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn unknown_client_token() -> MethodErr {
+    status_error(
+        StatusCode::InvalidArgument,
+        "Unknown client token, register the client with RegisterClient first.",
+    )
+}
+
+/// Parses the signal preferences given to RegisterClient, see there for the keys.
+pub fn signal_preferences(signals: &PropMap) -> Result<ClientPreferences, MethodErr> {
+    let mut preferences = ClientPreferences::default();
+    for (key, value) in signals {
+        match key.as_str() {
+            "intervals" => preferences.intervals = Some(signal_intervals(&*value.0)?),
+            "delta" => preferences.delta = value.0.as_u64().is_some_and(|delta| delta != 0),
+            "strength_threshold" => {
+                let threshold = value.0.as_u64().filter(|threshold| *threshold <= 100);
+                let Some(threshold) = threshold else {
+                    return Err(status_error(
+                        StatusCode::InvalidArgument,
+                        "The strength threshold has to be between 0 and 100.",
+                    ));
+                };
+                preferences.strength_threshold = Some(threshold as u8);
+            }
+            _ => {
+                return Err(status_error(
+                    StatusCode::InvalidArgument,
+                    &format!(
+                        "Unknown signal preference {}, expected intervals, delta or strength_threshold.",
+                        key
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(preferences)
+}
+
+/// Parses a map of signal class to milliseconds, e.g. {"Audio": 1000}.
+fn signal_intervals(intervals: &dyn RefArg) -> Result<HashMap<SignalClass, Duration>, MethodErr> {
+    let invalid = || {
+        status_error(
+            StatusCode::InvalidArgument,
+            "Intervals map a signal class of Audio, Network or Bluetooth to milliseconds.",
+        )
+    };
+    let mut class_intervals = HashMap::new();
+    let mut iter = intervals.as_iter().ok_or_else(invalid)?;
+    while let (Some(class), Some(interval)) = (iter.next(), iter.next()) {
+        let class = class.as_str().and_then(SignalClass::from_name);
+        let (Some(class), Some(interval)) = (class, interval.as_u64()) else {
+            return Err(invalid());
+        };
+        class_intervals.insert(class, Duration::from_millis(interval));
+    }
+    Ok(class_intervals)
+}
+
+/// Requests the signals of the preferences again, e.g. for the new connection of a resumed
+/// client.
+pub fn apply_preferences(
+    connection: &Arc<SyncConnection>,
+    sender: &str,
+    preferences: ClientPreferences,
+) {
+    if let Some(intervals) = preferences.intervals {
+        register_throttled_client(connection.clone(), sender.to_string(), intervals);
+    }
+    if preferences.delta {
+        register_delta_client(connection.clone(), sender.to_string());
+    }
+    if let Some(threshold) = preferences.strength_threshold {
+        register_strength_threshold_client(connection.clone(), sender.to_string(), threshold);
+    }
+}
+
+/// Releases everything a connection held, its registrations, idle inhibitors, signal
+/// subscriptions and the peak monitors only it used.
+pub fn release_connection(
+    connection: &Arc<SyncConnection>,
+    clients: &ClientRegistry,
+    audio_sender: &Sender<AudioRequest>,
+    sender: &str,
+) {
+    let (names, peak_monitors) = clients.remove_connection(sender);
+    unregister_throttled_client(sender);
+    release_client_inhibitors(connection, sender);
+    if !peak_monitors.is_empty() {
+        let _ = audio_sender.send(AudioRequest::ReleasePeakMonitors(peak_monitors));
+    }
+    if !names.is_empty() {
+        LOG!(format!(
+            "Unregistered clients {} of {}",
+            names.join(", "),
+            sender
+        ));
+    }
+}
+
+/// Releases clients that disconnected from the session bus without unregistering, e.g. after a
+/// crash.
+pub fn start_client_listener(
+    connection: Arc<SyncConnection>,
+    clients: Arc<ClientRegistry>,
+    audio_sender: Arc<Sender<AudioRequest>>,
+) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = session_connection()?;
+        let rule = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged");
+        let res = conn.add_match(
+            rule,
+            move |(name, _, new_owner): (String, String, String), _, _| {
+                // clients are tracked with their unique name, which is never reused
+                if name.starts_with(':') && new_owner.is_empty() {
+                    release_connection(&connection, &clients, &audio_sender, &name);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on the session bus: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on the session bus.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
@@ -0,0 +1,142 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    blocking::{
+        stdintf::org_freedesktop_dbus::{Properties, PropertiesPropertiesChanged},
+        Connection,
+    },
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    battery::battery_manager::{UPOWER_NAME, UPOWER_PATH},
+    plugin::context::notify_system_context,
+    signals::emit_signal,
+    utils::BASE,
+};
+
+// NMMetered
+const METERED_YES: u32 = 1;
+const METERED_GUESS_YES: u32 = 3;
+
+/// State of the system as (on battery, metered, do not disturb).\
+/// Plugins and clients use it to reduce their work, e.g. polling less often on a metered
+/// connection.
+pub type SystemContext = (bool, bool, bool);
+
+static CONTEXT: Lazy<Mutex<SystemContext>> = Lazy::new(|| Mutex::new((false, false, false)));
+
+/// Whether the Metered property of NetworkManager counts as metered, guesses included as
+/// NetworkManager guesses for mobile broadband and tethered connections.
+pub fn is_metered(metered: u32) -> bool {
+    metered == METERED_YES || metered == METERED_GUESS_YES
+}
+
+pub fn current_system_context() -> SystemContext {
+    *CONTEXT.lock().unwrap()
+}
+
+/// Applies a change to the context and emits SystemContextChanged if anything changed.
+fn update_context(conn: &Arc<SyncConnection>, update: impl FnOnce(&mut SystemContext)) {
+    let mut context = CONTEXT.lock().unwrap();
+    let previous = *context;
+    update(&mut context);
+    let current = *context;
+    drop(context);
+    if previous == current {
+        return;
+    }
+    LOG!(format!("System context changed to {:?}", current));
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BASE.into(),
+        &"SystemContextChanged".into(),
+    )
+    .append3(current.0, current.1, current.2);
+    emit_signal(conn, msg);
+    notify_system_context(current);
+}
+
+/// Called by the do not disturb listener, do not disturb stays false without a supported
+/// notification daemon.
+pub fn set_do_not_disturb_context(conn: &Arc<SyncConnection>, enabled: bool) {
+    update_context(conn, |context| context.2 = enabled);
+}
+
+fn read_on_battery(conn: &Connection) -> bool {
+    conn.with_proxy(UPOWER_NAME, UPOWER_PATH, Duration::from_millis(1000))
+        .get(UPOWER_NAME, "OnBattery")
+        .unwrap_or(false)
+}
+
+fn read_metered() -> bool {
+    get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        NM_INTERFACE!(),
+        "Metered",
+        u32,
+    )
+    .is_ok_and(is_metered)
+}
+
+/// Follows OnBattery of UPower and Metered of NetworkManager.\
+/// Both are read again on every change, systems without UPower are never on battery.
+pub fn start_system_context_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = Connection::new_system()?;
+        let on_battery = read_on_battery(&conn);
+        let metered = read_metered();
+        update_context(&connection, |context| {
+            context.0 = on_battery;
+            context.1 = metered;
+        });
+        let rules = [
+            PropertiesPropertiesChanged::match_rule(
+                Some(&UPOWER_NAME.into()),
+                Some(&Path::from(UPOWER_PATH)),
+            )
+            .static_clone(),
+            PropertiesPropertiesChanged::match_rule(
+                Some(&NM_INTERFACE_BASE!().into()),
+                Some(&Path::from(NM_PATH!())),
+            )
+            .static_clone(),
+        ];
+        for rule in rules {
+            let changed_ref = connection.clone();
+            let res = conn.add_match(rule, move |_: PropertiesPropertiesChanged, conn, _| {
+                let on_battery = read_on_battery(conn);
+                let metered = read_metered();
+                update_context(&changed_ref, |context| {
+                    context.0 = on_battery;
+                    context.1 = metered;
+                });
+                true
+            });
+            if let Err(_error) = res {
+                ERROR!(
+                    format!("Failed to match signal on the system bus: {:?}", _error),
+                    ErrorLevel::Recoverable
+                );
+                return Err(dbus::Error::new_custom(
+                    "SignalMatchFailed",
+                    "Failed to match signal on the system bus.",
+                ));
+            }
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
@@ -0,0 +1,311 @@
+use std::{
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::network::network_manager::{get_access_point_properties, get_bssid_properties};
+use crate::utils::get_daemon_config_value;
+
+/// Hooks which do not exit within this time are killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// NM_DEVICE_STATE_ACTIVATED
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+
+/// Hook as (event, executable).
+pub type Hook = (String, String);
+/// Result of a hook as (executable, exit code), the exit code is -1 if the hook could not be
+/// started, timed out or was killed by a signal.
+pub type HookResult = (String, i32);
+type HookEnvironment = Vec<(&'static str, String)>;
+
+/// Events that can run hooks, configured as executables per event.
+/// ```toml
+/// [Daemon.hooks]
+/// wifi_connected = ["/home/user/.local/bin/mount-nas"]
+/// bluetooth_device_connected = ["/home/user/.local/bin/headset-connected"]
+/// default_sink_changed = []
+/// ```
+/// Executables are run without a shell and receive the event as environment variables, every
+/// event sets RESET_EVENT to its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// RESET_DEVICE, RESET_SSID and RESET_BSSID
+    WifiConnected,
+    /// RESET_DEVICE, RESET_ADDRESS and RESET_NAME
+    BluetoothDeviceConnected,
+    /// RESET_SINK
+    DefaultSinkChanged,
+}
+
+const HOOK_EVENTS: [HookEvent; 3] = [
+    HookEvent::WifiConnected,
+    HookEvent::BluetoothDeviceConnected,
+    HookEvent::DefaultSinkChanged,
+];
+
+impl HookEvent {
+    pub fn from_name(name: &str) -> Option<Self> {
+        HOOK_EVENTS.into_iter().find(|event| event.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::WifiConnected => "wifi_connected",
+            HookEvent::BluetoothDeviceConnected => "bluetooth_device_connected",
+            HookEvent::DefaultSinkChanged => "default_sink_changed",
+        }
+    }
+
+    /// Environment used by TestHook, RESET_TEST is set in addition.
+    fn sample_environment(self) -> HookEnvironment {
+        match self {
+            HookEvent::WifiConnected => vec![
+                (
+                    "RESET_DEVICE",
+                    String::from("/org/freedesktop/NetworkManager/Devices/1"),
+                ),
+                ("RESET_SSID", String::from("ReSet")),
+                ("RESET_BSSID", String::from("00:00:00:00:00:00")),
+            ],
+            HookEvent::BluetoothDeviceConnected => vec![
+                (
+                    "RESET_DEVICE",
+                    String::from("/org/bluez/hci0/dev_00_00_00_00_00_00"),
+                ),
+                ("RESET_ADDRESS", String::from("00:00:00:00:00:00")),
+                ("RESET_NAME", String::from("ReSet")),
+            ],
+            HookEvent::DefaultSinkChanged => vec![("RESET_SINK", String::from("reset_sink"))],
+        }
+    }
+}
+
+fn configured_hooks(event: HookEvent) -> Vec<String> {
+    let Some(hooks) = get_daemon_config_value("hooks") else {
+        return Vec::new();
+    };
+    match hooks.get(event.name()) {
+        Some(toml::Value::String(hook)) => vec![hook.clone()],
+        Some(toml::Value::Array(hooks)) => hooks
+            .iter()
+            .filter_map(|hook| hook.as_str())
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns every configured hook.
+pub fn list_hooks() -> Vec<Hook> {
+    HOOK_EVENTS
+        .into_iter()
+        .flat_map(|event| {
+            configured_hooks(event)
+                .into_iter()
+                .map(move |hook| (event.name().to_string(), hook))
+        })
+        .collect()
+}
+
+/// Runs the hooks of the event in the background.
+pub fn run_hooks(event: HookEvent, environment: HookEnvironment) {
+    let hooks = configured_hooks(event);
+    if hooks.is_empty() {
+        return;
+    }
+    thread::spawn(move || {
+        for hook in hooks {
+            run_hook(&hook, event, &environment);
+        }
+    });
+}
+
+/// Runs the hooks of the event with sample values and returns their exit codes.
+pub fn test_hook(event: HookEvent) -> Vec<HookResult> {
+    let mut environment = event.sample_environment();
+    environment.push(("RESET_TEST", String::from("1")));
+    configured_hooks(event)
+        .into_iter()
+        .map(|hook| {
+            let code = run_hook(&hook, event, &environment);
+            (hook, code)
+        })
+        .collect()
+}
+
+fn run_hook(hook: &str, event: HookEvent, environment: &HookEnvironment) -> i32 {
+    let child = Command::new(hook)
+        .env("RESET_EVENT", event.name())
+        .envs(environment.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_error) => {
+            ERROR!(
+                format!("Failed to run hook {}: {}", hook, _error),
+                ErrorLevel::Recoverable
+            );
+            return -1;
+        }
+    };
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                LOG!(format!("Hook {} exited with {}", hook, status));
+                return status.code().unwrap_or(-1);
+            }
+            Ok(None) if start.elapsed() < HOOK_TIMEOUT => thread::sleep(HOOK_POLL_INTERVAL),
+            _ => {
+                ERROR!(
+                    format!("Hook {} did not exit in time and was killed", hook),
+                    ErrorLevel::Recoverable
+                );
+                let _ = child.kill();
+                let _ = child.wait();
+                return -1;
+            }
+        }
+    }
+}
+
+/// Runs the WiFi and Bluetooth hooks, nothing is matched if no hook is configured.
+pub fn start_hook_listener(wifi: bool, bluetooth: bool) {
+    let wifi = wifi && !configured_hooks(HookEvent::WifiConnected).is_empty();
+    let bluetooth = bluetooth && !configured_hooks(HookEvent::BluetoothDeviceConnected).is_empty();
+    if !wifi && !bluetooth {
+        return;
+    }
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        if wifi {
+            let mut device_changed = PropertiesPropertiesChanged::match_rule(
+                Some(&NM_INTERFACE_BASE!().into()),
+                Some(&Path::from(NM_DEVICES_PATH!())),
+            )
+            .static_clone();
+            device_changed.path_is_namespace = true;
+            let res = conn.add_match(
+                device_changed,
+                move |ir: PropertiesPropertiesChanged, _, msg| {
+                    let state: Option<&u32> = prop_cast(&ir.changed_properties, "State");
+                    if ir.interface_name == NM_DEVICE_INTERFACE!()
+                        && state == Some(&DEVICE_STATE_ACTIVATED)
+                    {
+                        if let Some(path) = msg.path() {
+                            wifi_connected(path.into_static());
+                        }
+                    }
+                    true
+                },
+            );
+            if let Err(_error) = res {
+                ERROR!(
+                    format!("Failed to match signal on NetworkManager {:?}", _error),
+                    ErrorLevel::Recoverable
+                );
+                return Err(dbus::Error::new_custom(
+                    "SignalMatchFailed",
+                    "Failed to match signal on NetworkManager.",
+                ));
+            }
+        }
+        if bluetooth {
+            let device_changed =
+                PropertiesPropertiesChanged::match_rule(Some(&BLUEZ_INTERFACE!().into()), None)
+                    .static_clone();
+            let res = conn.add_match(
+                device_changed,
+                move |ir: PropertiesPropertiesChanged, _, msg| {
+                    let connected: Option<&bool> = prop_cast(&ir.changed_properties, "Connected");
+                    if ir.interface_name == BLUEZ_DEVICE_INTERFACE!() && connected == Some(&true) {
+                        if let Some(path) = msg.path() {
+                            bluetooth_device_connected(path.into_static());
+                        }
+                    }
+                    true
+                },
+            );
+            if let Err(_error) = res {
+                ERROR!(
+                    format!("Failed to match signal on bluez {:?}", _error),
+                    ErrorLevel::Recoverable
+                );
+                return Err(dbus::Error::new_custom(
+                    "SignalMatchFailed",
+                    "Failed to match signal on bluez.",
+                ));
+            }
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn wifi_connected(device: Path<'static>) {
+    // wired and other devices have no access point
+    let active_access_point = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        "ActiveAccessPoint",
+        Path<'static>,
+    );
+    let Ok(active_access_point) = active_access_point else {
+        return;
+    };
+    if &*active_access_point == "/" {
+        return;
+    }
+    let ssid = get_access_point_properties(active_access_point.clone()).ssid;
+    let (_, bssid, _, _) = get_bssid_properties(active_access_point);
+    run_hooks(
+        HookEvent::WifiConnected,
+        vec![
+            ("RESET_DEVICE", device.to_string()),
+            ("RESET_SSID", String::from_utf8_lossy(&ssid).to_string()),
+            ("RESET_BSSID", bssid),
+        ],
+    );
+}
+
+fn bluetooth_device_connected(device: Path<'static>) {
+    let address = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        device.clone(),
+        BLUEZ_DEVICE_INTERFACE!(),
+        "Address",
+        String,
+    );
+    let name = get_dbus_property!(
+        BLUEZ_INTERFACE!(),
+        device.clone(),
+        BLUEZ_DEVICE_INTERFACE!(),
+        "Alias",
+        String,
+    );
+    run_hooks(
+        HookEvent::BluetoothDeviceConnected,
+        vec![
+            ("RESET_DEVICE", device.to_string()),
+            ("RESET_ADDRESS", address.unwrap_or_default()),
+            ("RESET_NAME", name.unwrap_or_default()),
+        ],
+    );
+}
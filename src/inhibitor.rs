@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dbus::{
+    arg::OwnedFd, blocking::Connection, nonblock::SyncConnection, Message, MethodErr, Path,
+};
+use dbus_crossroads::Crossroads;
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    authorization::authorize,
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::IDLE,
+    DaemonData,
+};
+
+/// Inhibitor as (cookie, client, reason, creation time in seconds since the unix epoch).\
+/// The client is the unique bus name of the client which created the inhibitor.
+pub type InhibitorInfo = (u32, String, String, u64);
+
+struct Inhibitor {
+    client: String,
+    reason: String,
+    since: u64,
+    // logind releases the inhibitor once this is closed
+    _fd: OwnedFd,
+}
+
+static INHIBITORS: Lazy<Mutex<HashMap<u32, Inhibitor>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+/// Inhibitors a single client may hold at once.
+const MAX_INHIBITORS_PER_CLIENT: usize = 8;
+
+/// Keeps the screen awake with idle inhibitors of logind, e.g. for a "keep screen awake" toggle.\
+/// Inhibitors belong to the client which created them and are released once the client
+/// unregisters or disconnects from the bus.
+pub fn setup_idle_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(IDLE, |c| {
+        c.signal::<(Vec<InhibitorInfo>,), _>("InhibitorsChanged", ("inhibitors",));
+        c.method_with_cr_async(
+            "Inhibit",
+            ("reason",),
+            ("cookie",),
+            move |mut ctx, cross, (reason,): (String,)| {
+                let authorized = authorize(ctx.message());
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                let sender = ctx.message().sender().map(|sender| sender.to_string());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let Some(sender) = sender else {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::Failed,
+                            "The client could not be identified",
+                        )));
+                    };
+                    let res = inhibit(sender, reason);
+                    if res.is_ok() {
+                        emit_inhibitors_changed(&connection);
+                    }
+                    ctx.reply(res.map(|cookie| (cookie,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "Uninhibit",
+            ("cookie",),
+            ("result",),
+            move |mut ctx, cross, (cookie,): (u32,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                let sender = ctx.message().sender().map(|sender| sender.to_string());
+                async move {
+                    let released = sender.is_some_and(|sender| uninhibit(&sender, cookie));
+                    if released {
+                        emit_inhibitors_changed(&connection);
+                    }
+                    ctx.reply(Ok((released,)))
+                }
+            },
+        );
+        c.method("ListInhibitors", (), ("inhibitors",), move |_, _, ()| {
+            Ok((list_inhibitors(),))
+        });
+    });
+    token
+}
+
+/// Takes an idle inhibitor of logind for the client and returns its cookie.
+fn inhibit(client: String, reason: String) -> Result<u32, MethodErr> {
+    check_inhibitor_limit(&INHIBITORS.lock().unwrap(), &client)?;
+    let res = dbus_method!(
+        LOGIND_INTERFACE_BASE!(),
+        Path::from(LOGIND_PATH!()),
+        "Inhibit",
+        LOGIND_MANAGER_INTERFACE!(),
+        ("idle", "ReSet", reason.as_str(), "block"),
+        1000,
+        (OwnedFd,),
+    );
+    let fd = match res {
+        Ok((fd,)) => fd,
+        Err(_error) => {
+            ERROR!(
+                format!("Could not take an idle inhibitor of logind: {}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(status_error(
+                StatusCode::Failed,
+                "Could not take an idle inhibitor",
+            ));
+        }
+    };
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let mut inhibitors = INHIBITORS.lock().unwrap();
+    // another call of the same client might have finished in the meantime
+    check_inhibitor_limit(&inhibitors, &client)?;
+    let cookie = NEXT_COOKIE.fetch_add(1, Ordering::SeqCst);
+    inhibitors.insert(
+        cookie,
+        Inhibitor {
+            client,
+            reason,
+            since,
+            _fd: fd,
+        },
+    );
+    Ok(cookie)
+}
+
+fn check_inhibitor_limit(
+    inhibitors: &HashMap<u32, Inhibitor>,
+    client: &str,
+) -> Result<(), MethodErr> {
+    let held = inhibitors
+        .values()
+        .filter(|inhibitor| inhibitor.client == client)
+        .count();
+    if held >= MAX_INHIBITORS_PER_CLIENT {
+        return Err(status_error(
+            StatusCode::Busy,
+            "The client holds too many inhibitors",
+        ));
+    }
+    Ok(())
+}
+
+/// Releases an inhibitor, clients can only release their own inhibitors.
+fn uninhibit(client: &str, cookie: u32) -> bool {
+    let mut inhibitors = INHIBITORS.lock().unwrap();
+    let owned = inhibitors
+        .get(&cookie)
+        .is_some_and(|inhibitor| inhibitor.client == client);
+    if owned {
+        inhibitors.remove(&cookie);
+    }
+    owned
+}
+
+pub fn list_inhibitors() -> Vec<InhibitorInfo> {
+    let mut inhibitors: Vec<InhibitorInfo> = INHIBITORS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(cookie, inhibitor)| {
+            (
+                *cookie,
+                inhibitor.client.clone(),
+                inhibitor.reason.clone(),
+                inhibitor.since,
+            )
+        })
+        .collect();
+    inhibitors.sort_by_key(|inhibitor| inhibitor.0);
+    inhibitors
+}
+
+/// Releases all inhibitors of a client, InhibitorsChanged is only emitted if the client held any.
+pub fn release_client_inhibitors(connection: &Arc<SyncConnection>, client: &str) {
+    let released = {
+        let mut inhibitors = INHIBITORS.lock().unwrap();
+        let count = inhibitors.len();
+        inhibitors.retain(|_, inhibitor| inhibitor.client != client);
+        count - inhibitors.len()
+    };
+    if released > 0 {
+        LOG!(format!(
+            "Released {} idle inhibitors of {}",
+            released, client
+        ));
+        emit_inhibitors_changed(connection);
+    }
+}
+
+fn emit_inhibitors_changed(connection: &Arc<SyncConnection>) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &IDLE.into(),
+        &"InhibitorsChanged".into(),
+    )
+    .append1(list_inhibitors());
+    emit_signal(connection, msg);
+}
@@ -0,0 +1,81 @@
+use std::{sync::Arc, time::Duration};
+
+use dbus::{nonblock::SyncConnection, MethodErr};
+use dbus_crossroads::IfaceBuilder;
+
+use crate::status::{status_error, StatusCode};
+use crate::utils::BASE;
+
+/// Name of the struct returned by a method or emitted by a signal.\
+/// Structs with equal signatures like Sink and Source can only be told apart with this annotation.
+pub const STRUCT_OUT0: &str = "org.Xetibo.ReSet.Struct.Out0";
+/// Prefix of the interface annotations containing the field names of each struct, e.g.
+/// org.Xetibo.ReSet.StructFields.Sink.
+pub const STRUCT_FIELDS: &str = "org.Xetibo.ReSet.StructFields";
+
+/// Field names in signature order of the structs provided by re_set-lib.
+const FIELD_NAMES: &[(&str, &str)] = &[
+    ("Sink", "index,name,alias,channels,volume,muted,active"),
+    ("Source", "index,name,alias,channels,volume,muted,active"),
+    (
+        "InputStream",
+        "index,name,application_name,sink_index,channels,volume,muted,corked",
+    ),
+    (
+        "OutputStream",
+        "index,name,application_name,source_index,channels,volume,muted,corked",
+    ),
+    ("Card", "index,name,profiles,active_profile"),
+    ("CardProfile", "name,description,available"),
+    (
+        "AccessPoint",
+        "ssid,strength,associated_connection,dbus_path,stored",
+    ),
+    ("WifiDevice", "path,name,active_access_point"),
+    (
+        "BluetoothDevice",
+        "path,rssi,alias,name,adapter,trusted,bonded,paired,blocked,connected,icon,address",
+    ),
+    (
+        "BluetoothAdapter",
+        "path,alias,powered,discoverable,pairable",
+    ),
+];
+
+/// Adds the field names of the given structs to the interface.
+pub fn annotate_struct_fields<T: Send + 'static>(c: &mut IfaceBuilder<T>, structs: &[&str]) {
+    for (name, fields) in FIELD_NAMES {
+        if structs.contains(name) {
+            c.annotate(format!("{}.{}", STRUCT_FIELDS, name), *fields);
+        }
+    }
+}
+
+/// Returns the introspection data of a single interface of the daemon object.
+pub async fn get_interface_xml(
+    conn: Arc<SyncConnection>,
+    interface: String,
+) -> Result<String, MethodErr> {
+    let proxy = dbus::nonblock::Proxy::new(BASE, DBUS_PATH!(), Duration::from_millis(1000), conn);
+    let res: Result<(String,), dbus::Error> = proxy
+        .method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+        .await;
+    if let Err(error) = res {
+        return Err(status_error(
+            StatusCode::Failed,
+            &format!("Could not introspect daemon: {}", error),
+        ));
+    }
+    extract_interface(&res.unwrap().0, &interface).ok_or_else(|| {
+        status_error(
+            StatusCode::InvalidArgument,
+            &format!("Unknown interface {}", interface),
+        )
+    })
+}
+
+fn extract_interface(xml: &str, interface: &str) -> Option<String> {
+    let start = xml.find(&format!("<interface name=\"{}\">", interface))?;
+    let length = xml[start..].find("</interface>")? + "</interface>".len();
+    Some(xml[start..start + length].to_string())
+}
@@ -1,37 +1,122 @@
 #[macro_use]
 mod macros;
+mod actions;
 pub mod api;
 mod audio;
+mod authorization;
+mod battery;
 mod bluetooth;
+mod capabilities;
+#[cfg(feature = "client")]
+pub mod client;
+mod clients;
+mod context;
+mod hooks;
+mod inhibitor;
+mod introspection;
+mod logging;
+mod media;
 pub mod mock;
+mod modem;
 mod network;
+mod notifications;
+mod panic_guard;
 pub mod plugin;
+mod proxy;
+mod resync;
+mod rfkill;
+mod signals;
+mod startup;
+pub mod status;
+pub mod storage;
+mod supervisor;
+mod system_bus;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
 pub mod utils;
+mod virtual_devices;
 
 use re_set_lib::utils::config::CONFIG_STRING;
 use re_set_lib::utils::flags::FLAGS;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, RwLock};
 use std::thread;
-use std::{fs, future, process::exit, time::Duration};
+use std::{future, process::exit, time::Duration};
 
-use dbus::blocking::Connection;
-use dbus::{channel::MatchingReceiver, message::MatchRule, Path};
+use dbus::{arg::PropMap, channel::MatchingReceiver, message::MatchRule};
 use dbus_crossroads::Crossroads;
-use dbus_tokio::connection;
-use re_set_lib::utils::plugin_setup::{CrossWrapper, BACKEND_PLUGINS, PLUGIN_DIR};
 #[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::utils::plugin_setup::{CrossWrapper, BACKEND_PLUGINS, PLUGIN_DIR};
 use re_set_lib::{ERROR, LOG};
-use utils::{AudioRequest, AudioResponse, BASE};
+use utils::{session_connection_sync, AudioRequest, AudioResponse, BASE};
 
 use crate::{
+    actions::setup_actions_manager,
     audio::audio_manager_dbus::setup_audio_manager,
-    bluetooth::bluetooth_manager_dbus::setup_bluetooth_manager,
-    network::network_manager_dbus::setup_wireless_manager, utils::DaemonData,
+    authorization::{authorize, authorize_blocking, set_authorization_connection},
+    battery::{
+        battery_manager::start_battery_listener, battery_manager_dbus::setup_battery_manager,
+    },
+    bluetooth::{
+        adapter_state::{restore_adapter_state, start_adapter_hotplug_listener},
+        anonymous::enable_raw_devices,
+        bluetooth_agent_dbus::start_bluetooth_agent,
+        bluetooth_manager::start_audio_routing_listener,
+        bluetooth_manager_dbus::setup_bluetooth_manager,
+        discoverable::start_discoverable_listener,
+        media::start_media_listener,
+        media_dbus::setup_bluetooth_media,
+        obex::start_obex_agent,
+    },
+    capabilities::{emit_capabilities_changed, CapabilityProbe},
+    clients::{
+        apply_preferences, signal_preferences, start_client_listener, unknown_client_token,
+        ClientPreferences,
+    },
+    context::{current_system_context, start_system_context_listener},
+    hooks::{list_hooks, start_hook_listener, test_hook, HookEvent},
+    inhibitor::{release_client_inhibitors, setup_idle_manager},
+    introspection::get_interface_xml,
+    logging::{create_log_file, disable_log_file, set_log_file},
+    media::{setup_media_manager, start_media_player_listener},
+    modem::modem_manager_dbus::setup_modem_manager,
+    network::{
+        bssid_blacklist::start_bssid_blacklist_listener,
+        captive_portal::start_captive_portal_listener,
+        network_manager_dbus::setup_wireless_manager,
+        priority_learning::start_priority_learning_listener, radio::start_radio_state_listener,
+        retry_policy::start_retry_policy_listener, secret_agent::start_secret_agent,
+        speedtest::setup_speedtest_manager,
+    },
+    notifications::{setup_notification_manager, start_do_not_disturb_listener},
+    panic_guard::{handle_message_guarded, spawn_handler},
+    plugin::{
+        config::{get_plugin_config_map, set_plugin_config},
+        lifecycle::{
+            call_plugin, capability_changes, export_host_api, is_plugin_loaded,
+            loaded_capabilities, plugin_of_object, remove_plugin_objects, set_plugin_loaded,
+            unload_all_plugins, unload_plugin,
+        },
+        signals::{get_plugin_signals, set_relay_connection, PluginInfo},
+    },
+    proxy::setup_proxy_manager,
+    resync::{resync_all, start_sleep_listener, ResyncTargets},
+    rfkill::{get_rfkill_state, set_rfkill_blocked, RfkillKind},
+    signals::{
+        register_delta_client, register_strength_threshold_client, register_throttled_client,
+        unregister_throttled_client, SignalClass,
+    },
+    startup::{feature_enabled, probe_features, startup_quorum, StartupTasks, STARTUP_AUDIO},
+    status::{status_error, StatusCode},
+    supervisor::{start_service_supervisor, worker_metrics},
+    system_bus::{serve_system_bus, system_bus_requested},
+    utils::DaemonData,
+    virtual_devices::{enable_virtual_devices, virtual_devices_enabled},
 };
 
 /// Version of the current package.
@@ -50,6 +135,10 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// }
 /// ```
 ///
+/// Plugins resolve the host API, e.g. register_plugin_signal, from the executable embedding the
+/// daemon. Link it with `-rdynamic`, e.g. `cargo:rustc-link-arg-bins=-rdynamic` within its build
+/// script, otherwise plugins fail to load.
+///
 /// The daemon will run to infinity, so it might be a good idea to put it into a different thread.
 /// ```no_run
 /// use std::sync::Arc;
@@ -61,6 +150,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// // your other code here...
 /// ```
 pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
+    let mut system_bus_flag = false;
     for flag in FLAGS.0.iter() {
         // more configuration possible in the future
         match flag {
@@ -76,6 +166,25 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
                     *PLUGIN_DIR = PathBuf::from(path);
                 }
             }
+            re_set_lib::utils::flags::Flag::Other((name, value)) if name == "--log-file" => {
+                if let Ok(path) = value.to_value_cloned::<String>() {
+                    set_log_file(path);
+                }
+            }
+            re_set_lib::utils::flags::Flag::Other((name, _)) if name == "--no-log-file" => {
+                disable_log_file();
+            }
+            re_set_lib::utils::flags::Flag::Other((name, _)) if name == "--system-bus" => {
+                system_bus_flag = true;
+            }
+            re_set_lib::utils::flags::Flag::Other((name, _)) if name == "--virtual-devices" => {
+                enable_virtual_devices();
+            }
+            re_set_lib::utils::flags::Flag::Other((name, _))
+                if name == "--raw-bluetooth-devices" =>
+            {
+                enable_raw_devices();
+            }
             re_set_lib::utils::flags::Flag::Other(_flag) => {
                 LOG!(format!(
                     "Custom flag {} with value {:#?}",
@@ -87,9 +196,8 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
         }
     }
     create_log_file();
-
     LOG!("Running in debug mode");
-    let res = connection::new_session_sync();
+    let res = session_connection_sync();
     if res.is_err() {
         return;
     }
@@ -101,34 +209,23 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
     });
 
     conn.request_name(BASE, false, true, false).await.unwrap();
+    set_authorization_connection(conn.clone());
     let mut cross = Crossroads::new();
-    cross.set_async_support(Some((
-        conn.clone(),
-        Box::new(|x| {
-            tokio::spawn(x);
-        }),
-    )));
+    cross.set_async_support(Some((conn.clone(), Box::new(spawn_handler(conn.clone())))));
 
-    let res = dbus_method!(
-        NM_INTERFACE_BASE!(),
-        Path::from(NM_PATH!()),
-        "Introspect",
-        "org.freedesktop.DBus.Introspectable",
-        (),
-        100,
-        (),
-    );
-    let wifi_enabled = res.is_ok();
-    let res = dbus_method!(
-        BLUEZ_INTERFACE!(),
-        "/",
-        "Introspect",
-        "org.freedesktop.DBus.Introspectable",
-        (),
-        100,
-        (),
+    let startup = StartupTasks::new(startup_quorum());
+    let data_startup = startup.clone();
+    let data_conn = conn.clone();
+    let (probe, data) = tokio::join!(
+        probe_features(),
+        tokio::task::spawn_blocking(move || DaemonData::create(_handle, data_conn, data_startup)),
     );
-    let bluetooth_enabled = res.is_ok();
+    let wifi_enabled = probe.wifi;
+    let bluetooth_enabled = probe.bluetooth;
+    let modem_enabled = probe.modem;
+    let battery_enabled = probe.battery;
+    let audio_enabled = feature_enabled("Audio");
+    let media_enabled = feature_enabled("Media");
 
     let mut features = Vec::new();
     let mut feature_strings = Vec::new();
@@ -141,22 +238,69 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
 
     if bluetooth_enabled {
         features.push(setup_bluetooth_manager(&mut cross));
-        // the agent is currently not implemented
-        // features.push(setup_bluetooth_agent(&mut cross));
+        features.push(setup_bluetooth_media(&mut cross));
         feature_strings.push("Bluetooth");
         LOG!("Bluetooth feature started");
     }
 
-    features.push(setup_audio_manager(&mut cross));
-    feature_strings.push("Audio");
+    if modem_enabled {
+        features.push(setup_modem_manager(&mut cross));
+        feature_strings.push("Modem");
+        LOG!("Modem feature started");
+    }
+
+    if battery_enabled {
+        features.push(setup_battery_manager(&mut cross));
+        feature_strings.push("Battery");
+        LOG!("Battery feature started");
+    }
 
+    if audio_enabled {
+        features.push(setup_audio_manager(&mut cross));
+        feature_strings.push("Audio");
+    }
+    features.push(setup_actions_manager(&mut cross));
+    features.push(setup_idle_manager(&mut cross));
+    if media_enabled {
+        features.push(setup_media_manager(&mut cross));
+        feature_strings.push("Media");
+    }
+    if feature_enabled("Proxy") {
+        features.push(setup_proxy_manager(&mut cross));
+        feature_strings.push("Proxy");
+    }
+
+    let do_not_disturb_enabled = probe.do_not_disturb.is_some();
+    if do_not_disturb_enabled {
+        features.push(setup_notification_manager(&mut cross));
+        feature_strings.push("DoNotDisturb");
+        LOG!("DoNotDisturb feature started");
+    }
+
+    if probe.speedtest {
+        features.push(setup_speedtest_manager(&mut cross));
+        feature_strings.push("SpeedTest");
+        LOG!("SpeedTest feature started");
+    }
+
+    let mut plugins = Vec::new();
     unsafe {
         for plugin in BACKEND_PLUGINS.iter() {
             feature_strings.extend(plugin.capabilities.iter());
+            plugins.push(((plugin.name)(), plugin.capabilities.clone()));
         }
     }
 
-    let data = DaemonData::create(_handle, conn.clone());
+    let data = match data {
+        Ok(data) => data,
+        Err(_error) => {
+            ERROR!(
+                format!("Daemon data could not be created: {}", _error),
+                ErrorLevel::Critical
+            );
+            return;
+        }
+    };
     if data.is_err() {
         ERROR!(
             format!("{}", data.as_ref().err().unwrap().message),
@@ -166,50 +310,110 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
     }
     let data = data.unwrap();
 
-    if data
-        .audio_listener_active
-        .load(std::sync::atomic::Ordering::SeqCst)
-        == false
-    {
-        let mut index = -1;
-        for (i, feature) in feature_strings.iter().enumerate() {
-            if *feature == "Audio" {
-                index = i as i32;
-            }
+    if virtual_devices_enabled() {
+        let _ = data.audio_sender.send(AudioRequest::CreateVirtualDevices);
+    }
+    if bluetooth_enabled {
+        start_audio_routing_listener(
+            data.audio_sender.clone(),
+            data.audio_routing_stop_requested.clone(),
+        );
+        start_media_listener(data.connection.clone());
+        start_discoverable_listener(data.connection.clone());
+        start_obex_agent(data.connection.clone());
+        // answers the PIN requests of keyboards paired through the daemon
+        start_bluetooth_agent(data.connection.clone());
+        for adapter in data.b_interface.adapters.iter() {
+            restore_adapter_state(adapter);
         }
-        feature_strings.remove(index as usize);
+        start_adapter_hotplug_listener();
+    }
+    if wifi_enabled {
+        start_retry_policy_listener(data.connection.clone());
+        start_bssid_blacklist_listener();
+        start_priority_learning_listener();
+        start_captive_portal_listener(data.connection.clone());
+        start_radio_state_listener(data.connection.clone());
+        start_secret_agent(data.connection.clone(), data.clients.clone());
+    }
+    if media_enabled {
+        start_media_player_listener(data.connection.clone());
+    }
+    start_client_listener(
+        data.connection.clone(),
+        data.clients.clone(),
+        data.audio_sender.clone(),
+    );
+    if do_not_disturb_enabled {
+        start_do_not_disturb_listener(data.connection.clone());
     }
+    start_system_context_listener(data.connection.clone());
+    if battery_enabled {
+        start_battery_listener(data.connection.clone());
+    }
+    start_hook_listener(wifi_enabled, bluetooth_enabled);
+    if system_bus_requested(system_bus_flag) {
+        tokio::spawn(serve_system_bus(wifi_enabled, bluetooth_enabled));
+    }
+    start_sleep_listener();
+    start_service_supervisor(data.connection.clone(), feature_strings.clone());
 
-    features.push(setup_base(&mut cross, feature_strings));
-    unsafe {
+    // an audio server which is still connecting keeps the feature
+    if startup.failed(STARTUP_AUDIO) {
+        feature_strings.retain(|feature| *feature != "Audio");
+    }
+
+    let capabilities = feature_strings.clone();
+    let capability_plugins = plugins.clone();
+    features.push(setup_base(&mut cross, feature_strings, plugins));
+    set_relay_connection(conn.clone());
+    export_host_api();
+    let failed_plugins = unsafe {
         thread::scope(|scope| {
             let wrapper = Arc::new(RwLock::new(CrossWrapper::new(&mut cross)));
-            for plugin in BACKEND_PLUGINS.iter() {
-                let wrapper_loop = wrapper.clone();
-                scope.spawn(move || {
-                    // allocate plugin specific things
-                    (plugin.startup)();
-                    // register and insert plugin interfaces
-                    (plugin.data)(wrapper_loop);
-                    let _name = (plugin.name)();
-                    LOG!(format!("Loaded plugin: {}", _name));
-                });
-            }
-        });
+            let handles: Vec<_> = BACKEND_PLUGINS
+                .iter()
+                .map(|plugin| {
+                    let wrapper_loop = wrapper.clone();
+                    let name = (plugin.name)();
+                    let plugin_name = name.clone();
+                    let handle = scope.spawn(move || {
+                        call_plugin(&plugin_name, || {
+                            // allocate plugin specific things
+                            (plugin.startup)();
+                            // register and insert plugin interfaces
+                            (plugin.data)(wrapper_loop);
+                        });
+                        LOG!(format!("Loaded plugin: {}", plugin_name));
+                        set_plugin_loaded(plugin_name, *plugin.shutdown);
+                    });
+                    (name, handle)
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|(name, handle)| handle.join().is_err().then_some(name))
+                .collect::<Vec<String>>()
+        })
+    };
+    // interfaces inserted before the failure would otherwise still be served
+    for name in failed_plugins.iter() {
+        ERROR!(
+            format!("Failed to load plugin: {}", name),
+            ErrorLevel::PartialBreakage
+        );
+        remove_plugin_objects(&mut cross, name);
+    }
+    if !failed_plugins.is_empty() {
+        let (added, removed) = capability_changes(
+            &capabilities,
+            &loaded_capabilities(&capabilities, &capability_plugins),
+        );
+        emit_capabilities_changed(&conn, added, removed);
     }
 
     cross.insert(DBUS_PATH!(), &features, data);
 
-    // register bluetooth agent before start
-    // will be uncommented when agent is fully functional
-    // {
-    //     let data: &mut DaemonData = cross.data_mut(&Path::from(DBUS_PATH)).unwrap();
-    //     if data.b_interface.current_adapter != Path::from("/") {
-    //         // register bluetooth agent before listening to calls
-    //         data.b_interface.register_agent();
-    //     }
-    // }
-    //
     if let Some(ready) = ready {
         ready.store(true, std::sync::atomic::Ordering::SeqCst);
     }
@@ -217,7 +421,14 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
     conn.start_receive(
         MatchRule::new_method_call(),
         Box::new(move |msg, conn| {
-            cross.handle_message(msg, conn).unwrap();
+            // host API calls within the handlers of plugin objects are attributed to the plugin
+            let plugin = msg.path().and_then(|path| plugin_of_object(&path));
+            match plugin {
+                Some(plugin) => {
+                    call_plugin(&plugin, || handle_message_guarded(&mut cross, msg, conn))
+                }
+                None => handle_message_guarded(&mut cross, msg, conn),
+            }
             true
         }),
     );
@@ -226,50 +437,330 @@ pub async fn run_daemon(ready: Option<Arc<AtomicBool>>) {
     unreachable!()
 }
 
-fn create_log_file() {
-    fs::File::create("/tmp/reset_daemon_log").expect("Could not create log file.");
-}
-
 fn setup_base(
     cross: &mut Crossroads,
     features: Vec<&'static str>,
+    plugins: Vec<(String, Vec<&'static str>)>,
 ) -> dbus_crossroads::IfaceToken<DaemonData> {
     cross.register(BASE, |c| {
+        c.signal::<(String, String, String, String), _>(
+            "CapabilityStateChanged",
+            ("capability", "status", "code", "reason"),
+        );
+        c.signal::<(Vec<String>, Vec<String>), _>("CapabilitiesChanged", ("added", "removed"));
+        c.signal::<(bool, bool, bool), _>(
+            "SystemContextChanged",
+            ("on_battery", "metered", "do_not_disturb"),
+        );
+        let capability_plugins = plugins.clone();
+        let unload_features = features.clone();
+        let unload_plugins = plugins.clone();
         c.method("GetCapabilities", (), ("capabilities",), move |_, _, ()| {
-            Ok((features.clone(),))
+            Ok((loaded_capabilities(&features, &capability_plugins),))
         });
+        c.method(
+            "GetSystemContext",
+            (),
+            ("on_battery", "metered", "do_not_disturb"),
+            move |_, _, ()| Ok(current_system_context()),
+        );
         c.method("APIVersion", (), ("api-version",), move |_, _, ()| {
             // let the client handle the mismatch -> e.g. they decide if they want to keep using
             // the current daemon or not.
             Ok((VERSION,))
         });
+        let detail_plugins = plugins.clone();
+        c.method_with_cr_async(
+            "GetCapabilityDetails",
+            (),
+            ("capabilities",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let probe = CapabilityProbe::new(data, detail_plugins.clone());
+                async move { ctx.reply(Ok((probe.probe(),))) }
+            },
+        );
+        c.method(
+            "GetRfkillState",
+            ("kind",),
+            ("soft_blocked", "hard_blocked"),
+            move |_, _, (kind,): (String,)| {
+                let Some(kind) = RfkillKind::from_name(&kind) else {
+                    return Err(status_error(StatusCode::InvalidArgument,
+                        "The kind has to be wlan, bluetooth or wwan.",
+                    ));
+                };
+                Ok(get_rfkill_state(kind))
+            },
+        );
+        c.method_with_cr_async(
+            "SetRfkillBlocked",
+            ("kind", "blocked"),
+            ("result",),
+            move |mut ctx, _, (kind, blocked): (String, bool)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let Some(kind) = RfkillKind::from_name(&kind) else {
+                        return ctx.reply(Err(status_error(StatusCode::InvalidArgument,
+                            "The kind has to be wlan, bluetooth or wwan.",
+                        )));
+                    };
+                    let result = set_rfkill_blocked(kind, blocked);
+                    ctx.reply(result.map(|result| (result,)))
+                }
+            },
+        );
+        c.method("ListHooks", (), ("hooks",), move |_, _, ()| Ok((list_hooks(),)));
+        c.method_with_cr_async(
+            "TestHook",
+            ("event",),
+            ("results",),
+            move |mut ctx, _, (event,): (String,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let Some(event) = HookEvent::from_name(&event) else {
+                        return ctx.reply(Err(status_error(StatusCode::InvalidArgument,
+                            "The event has to be wifi_connected, bluetooth_device_connected or default_sink_changed.",
+                        )));
+                    };
+                    // the hooks run as commands which are waited for
+                    let results = tokio::task::spawn_blocking(move || test_hook(event)).await;
+                    ctx.reply(results.map(|results| (results,)).map_err(|_| {
+                        status_error(StatusCode::HandlerPanicked, "Running the hooks failed")
+                    }))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetInterfaceXML",
+            ("interface",),
+            ("xml",),
+            move |mut ctx, cross, (interface,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                async move {
+                    let xml = get_interface_xml(connection, interface).await;
+                    ctx.reply(xml.map(|xml| (xml,)))
+                }
+            },
+        );
+        c.method("GetPluginInfo", (), ("plugins",), move |_, _, ()| {
+            let plugins: Vec<PluginInfo> = plugins
+                .iter()
+                .filter(|(name, _)| is_plugin_loaded(name))
+                .map(|(name, capabilities)| {
+                    (
+                        name.clone(),
+                        capabilities
+                            .iter()
+                            .map(|capability| capability.to_string())
+                            .collect(),
+                        get_plugin_signals(name),
+                    )
+                })
+                .collect();
+            Ok((plugins,))
+        });
         c.method(
             "RegisterClient",
-            ("client_name",),
+            ("client_name", "signals"),
+            ("token",),
+            move |ctx, data: &mut DaemonData, (client_name, signals): (String, PropMap)| {
+                let preferences = signal_preferences(&signals)?;
+                let sender = ctx
+                    .message()
+                    .sender()
+                    .map(|sender| sender.to_string())
+                    .unwrap_or_default();
+                let token = data.clients.register(client_name, &sender);
+                // clients of direct connections have no name to address signals to
+                if preferences != ClientPreferences::default() && !sender.is_empty() {
+                    data.clients.update_preferences(&token, &sender, |negotiated| {
+                        *negotiated = preferences.clone();
+                    });
+                    apply_preferences(&data.connection, &sender, preferences);
+                }
+                Ok((token,))
+            },
+        );
+        c.method(
+            "ResumeClient",
+            ("token",),
+            ("result",),
+            move |ctx, data: &mut DaemonData, (token,): (String,)| {
+                let Some(sender) = ctx.message().sender() else {
+                    return Ok((false,));
+                };
+                let Some(preferences) = data.clients.resume(&token, &sender) else {
+                    return Ok((false,));
+                };
+                apply_preferences(&data.connection, &sender, preferences);
+                Ok((true,))
+            },
+        );
+        c.method(
+            "RegisterThrottledClient",
+            ("token", "intervals"),
+            ("result",),
+            move |ctx,
+                  data: &mut DaemonData,
+                  (token, intervals): (String, HashMap<String, u32>)| {
+                let sender = ctx.message().sender();
+                if sender.is_none() {
+                    return Ok((false,));
+                }
+                let mut class_intervals = HashMap::new();
+                for (class, interval) in intervals {
+                    let signal_class = SignalClass::from_name(&class);
+                    if signal_class.is_none() {
+                        return Err(status_error(StatusCode::InvalidArgument, &format!(
+                            "Unknown signal class {}, expected Audio, Network or Bluetooth.",
+                            class
+                        )));
+                    }
+                    class_intervals.insert(
+                        signal_class.unwrap(),
+                        Duration::from_millis(interval as u64),
+                    );
+                }
+                let sender = sender.unwrap().to_string();
+                let known = data.clients.update_preferences(&token, &sender, |preferences| {
+                    preferences.intervals = Some(class_intervals.clone());
+                });
+                if !known {
+                    return Err(unknown_client_token());
+                }
+                register_throttled_client(data.connection.clone(), sender, class_intervals);
+                Ok((true,))
+            },
+        );
+        c.method(
+            "RegisterDeltaClient",
+            ("token",),
             ("result",),
-            move |_, data: &mut DaemonData, (client_name,): (String,)| {
-                data.clients.insert(client_name, data.clients.len());
+            move |ctx, data: &mut DaemonData, (token,): (String,)| {
+                let sender = ctx.message().sender();
+                if sender.is_none() {
+                    return Ok((false,));
+                }
+                let sender = sender.unwrap().to_string();
+                let known = data.clients.update_preferences(&token, &sender, |preferences| {
+                    preferences.delta = true;
+                });
+                if !known {
+                    return Err(unknown_client_token());
+                }
+                register_delta_client(data.connection.clone(), sender);
+                Ok((true,))
+            },
+        );
+        c.method(
+            "RegisterStrengthThresholdClient",
+            ("token", "threshold"),
+            ("result",),
+            move |ctx, data: &mut DaemonData, (token, threshold): (String, u8)| {
+                if threshold > 100 {
+                    return Err(status_error(
+                        StatusCode::InvalidArgument,
+                        "The strength threshold has to be between 0 and 100.",
+                    ));
+                }
+                let sender = ctx.message().sender();
+                if sender.is_none() {
+                    return Ok((false,));
+                }
+                let sender = sender.unwrap().to_string();
+                let known = data.clients.update_preferences(&token, &sender, |preferences| {
+                    preferences.strength_threshold = Some(threshold);
+                });
+                if !known {
+                    return Err(unknown_client_token());
+                }
+                register_strength_threshold_client(data.connection.clone(), sender, threshold);
                 Ok((true,))
             },
         );
         c.method(
             "UnregisterClient",
-            ("client_name",),
+            ("token",),
             ("result",),
-            move |_, data: &mut DaemonData, (client_name,): (String,)| {
-                data.clients.remove(&client_name);
+            move |_, data: &mut DaemonData, (token,): (String,)| {
+                let Some(sender) = data.clients.unregister(&token) else {
+                    return Ok((false,));
+                };
+                // disconnected clients already released their subscriptions and inhibitors
+                if !sender.is_empty() {
+                    unregister_throttled_client(&sender);
+                    release_client_inhibitors(&data.connection, &sender);
+                }
                 Ok((true,))
             },
         );
-        c.method("Shutdown", (), (), move |_, data: &mut DaemonData, ()| {
+        c.method("ListClients", (), ("clients",), move |_, data: &mut DaemonData, ()| {
+            Ok((data.clients.list(),))
+        });
+        c.method("GetWorkerMetrics", (), ("workers",), move |_, _, ()| {
+            Ok((worker_metrics(),))
+        });
+        c.method_with_cr_async("ResyncAll", (), (), move |mut ctx, cross, ()| {
+            let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+            let targets = ResyncTargets::new(data);
+            async move {
+                let result = tokio::task::spawn_blocking(move || resync_all(targets)).await;
+                ctx.reply(result.map_err(|_| {
+                    status_error(StatusCode::HandlerPanicked, "Resyncing the services failed")
+                }))
+            }
+        });
+        c.method_with_cr(
+            "UnloadPlugin",
+            ("name",),
+            ("result",),
+            move |ctx, cross, (name,): (String,)| {
+                authorize_blocking(ctx.message())?;
+                let before = loaded_capabilities(&unload_features, &unload_plugins);
+                if !unload_plugin(cross, &name) {
+                    return Ok((false,));
+                }
+                let after = loaded_capabilities(&unload_features, &unload_plugins);
+                let (added, removed) = capability_changes(&before, &after);
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                emit_capabilities_changed(&data.connection, added, removed);
+                Ok((true,))
+            },
+        );
+        c.method("GetPluginConfig", ("name",), ("config",), move |_, _, (name,): (String,)| {
+            Ok((get_plugin_config_map(&name),))
+        });
+        c.method_with_cr_async(
+            "SetPluginConfig",
+            ("name", "config"),
+            ("result",),
+            move |mut ctx, _, (name, config): (String, PropMap)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_plugin_config(&name, &config).map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr("Shutdown", (), (), move |ctx, cross, ()| {
+            authorize_blocking(ctx.message())?;
+            let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
             data.b_interface.unregister_agent();
             data.handle.abort();
+            data.audio_routing_stop_requested
+                .store(true, std::sync::atomic::Ordering::SeqCst);
             let _ = data.audio_sender.send(AudioRequest::StopListener);
-            unsafe {
-                for plugin in BACKEND_PLUGINS.iter() {
-                    (plugin.shutdown)();
-                }
-            }
+            unload_all_plugins(cross);
             exit(0);
             #[allow(unreachable_code)]
             Ok(())
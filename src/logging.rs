@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::utils::get_daemon_config_value;
+
+const DEFAULT_LOG_FILE: &str = "/tmp/reset_daemon_log";
+const DEFAULT_MAX_SIZE_KB: u64 = 1024;
+const DEFAULT_RETAINED_FILES: u64 = 3;
+
+/// Location and rotation of the log file.
+/// ```toml
+/// [Daemon]
+/// log_file = "/home/user/.local/state/reset/daemon_log"
+/// log_max_size_kb = 1024
+/// log_retained_files = 3
+/// ```
+/// log_file = false disables the file entirely, the messages are then written to stderr, which
+/// ends up in journald when started as a service.\
+/// The flags --log-file <path> and --no-log-file take precedence over the config.\
+/// Release builds never create a log file and only use stderr.
+static LOG_SETTINGS: Lazy<Mutex<LogSettings>> = Lazy::new(|| Mutex::new(LogSettings::load()));
+
+#[derive(Debug)]
+struct LogSettings {
+    path: Option<PathBuf>,
+    max_size: u64,
+    retained_files: u64,
+}
+
+impl LogSettings {
+    fn load() -> Self {
+        let path = match get_daemon_config_value("log_file") {
+            Some(toml::Value::Boolean(false)) => None,
+            Some(toml::Value::String(path)) => Some(PathBuf::from(path)),
+            _ => Some(PathBuf::from(DEFAULT_LOG_FILE)),
+        };
+        let max_size = get_daemon_config_value("log_max_size_kb")
+            .and_then(|value| value.as_integer())
+            .map(|size| size.max(1) as u64)
+            .unwrap_or(DEFAULT_MAX_SIZE_KB)
+            * 1024;
+        let retained_files = get_daemon_config_value("log_retained_files")
+            .and_then(|value| value.as_integer())
+            .map(|files| files.max(0) as u64)
+            .unwrap_or(DEFAULT_RETAINED_FILES);
+        Self {
+            path,
+            max_size,
+            retained_files,
+        }
+    }
+}
+
+/// Overrides the configured log file, used for the --log-file flag.
+pub fn set_log_file(path: &str) {
+    LOG_SETTINGS.lock().unwrap().path = Some(PathBuf::from(path));
+}
+
+/// Disables file logging, used for the --no-log-file flag.
+pub fn disable_log_file() {
+    LOG_SETTINGS.lock().unwrap().path = None;
+}
+
+/// Rotates the log of the previous run instead of discarding it.\
+/// Falls back to stderr if the log file can't be created, e.g. for an invalid log_file.
+pub fn create_log_file() {
+    let mut settings = LOG_SETTINGS.lock().unwrap();
+    if cfg!(not(debug_assertions)) {
+        settings.path = None;
+    }
+    let Some(path) = settings.path.clone() else {
+        return;
+    };
+    let res = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > 0) {
+                rotate(&path, settings.retained_files);
+            }
+            fs::File::create(&path)
+        });
+    if let Err(error) = res {
+        settings.path = None;
+        drop(settings);
+        write_log(&format!(
+            "Could not create log file {}: {}, logging to stderr instead",
+            path.display(),
+            error
+        ));
+    }
+}
+
+/// Appends a message to the log file, the file is rotated once it exceeds the maximum size.\
+/// Messages are written to stderr without a log file or if the file is not writable.\
+/// Used by the ERROR! and LOG! macros via write_log_to_file!.
+pub fn write_log(message: &str) {
+    let settings = LOG_SETTINGS.lock().unwrap();
+    let Some(path) = settings.path.as_ref() else {
+        write_stderr(message);
+        return;
+    };
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= settings.max_size) {
+        rotate(path, settings.retained_files);
+    }
+    let written = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", message));
+    if written.is_err() {
+        write_stderr(message);
+    }
+}
+
+fn write_stderr(message: &str) {
+    let _ = writeln!(std::io::stderr(), "{}", message);
+}
+
+/// Shifts log -> log.1 -> log.2 and drops everything beyond the retained files.
+fn rotate(path: &Path, retained_files: u64) {
+    if retained_files == 0 {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    let rotated = |index: u64| PathBuf::from(format!("{}.{}", path.display(), index));
+    let _ = fs::remove_file(rotated(retained_files));
+    for index in (1..retained_files).rev() {
+        let _ = fs::rename(rotated(index), rotated(index + 1));
+    }
+    let _ = fs::rename(path, rotated(1));
+}
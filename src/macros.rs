@@ -1,3 +1,40 @@
+/// Replaces the macro of re_set-lib used within ERROR! and LOG!, which always writes to
+/// /tmp/reset_daemon_log.\
+/// Macros are resolved at the call site, hence this is picked up by every module of the daemon.
+#[cfg(debug_assertions)]
+macro_rules! write_log_to_file {
+    ($message:expr) => {{
+        $crate::logging::write_log(&$message);
+    }};
+}
+
+/// Routes to the mock implementations within unit tests and while a testing::TestBus is running,
+/// the system services are used otherwise.
+#[cfg(test)]
+macro_rules! MOCK_ROUTE {
+    ($real:expr, $mock:expr) => {
+        $mock
+    };
+}
+
+#[cfg(all(not(test), feature = "testing"))]
+macro_rules! MOCK_ROUTE {
+    ($real:expr, $mock:expr) => {
+        if $crate::testing::mock_routing() {
+            $mock
+        } else {
+            $real
+        }
+    };
+}
+
+#[cfg(not(any(test, feature = "testing")))]
+macro_rules! MOCK_ROUTE {
+    ($real:expr, $mock:expr) => {
+        $real
+    };
+}
+
 macro_rules! DBUS_PATH {
     () => {
         "/org/Xetibo/ReSet/Daemon"
@@ -22,6 +59,12 @@ macro_rules! BLUETOOTH_INTERFACE {
     };
 }
 
+macro_rules! BLUETOOTH_MEDIA_INTERFACE {
+    () => {
+        "org.Xetibo.ReSet.BluetoothMedia"
+    };
+}
+
 #[cfg(test)]
 macro_rules! BASE_INTERFACE {
     () => {
@@ -35,45 +78,27 @@ macro_rules! BASE_TEST_INTERFACE {
     };
 }
 
-#[cfg(not(test))]
 macro_rules! NM_INTERFACE_BASE {
     () => {
-        "org.freedesktop.NetworkManager"
+        MOCK_ROUTE!("org.freedesktop.NetworkManager", "org.Xetibo.ReSet.Test")
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_INTERFACE_BASE {
-    () => {
-        "org.Xetibo.ReSet.Test"
-    };
-}
-
-#[cfg(not(test))]
-macro_rules! NM_INTERFACE {
-    () => {
-        "org.freedesktop.NetworkManager"
-    };
-}
-
-#[cfg(test)]
 macro_rules! NM_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager"
-    };
-}
-
-#[cfg(not(test))]
-macro_rules! NM_SETTINGS_INTERFACE {
-    () => {
-        "org.freedesktop.NetworkManager.Settings"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager",
+            "org.Xetibo.ReSet.Test.NetworkManager"
+        )
     };
 }
 
-#[cfg(test)]
 macro_rules! NM_SETTINGS_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.Settings"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.Settings",
+            "org.Xetibo.ReSet.Test.NetworkManager.Settings"
+        )
     };
 }
 
@@ -84,250 +109,270 @@ macro_rules! NM_INTERFACE_TEST {
     };
 }
 
-#[cfg(not(test))]
 macro_rules! NM_DEVICE_INTERFACE {
     () => {
-        "org.freedesktop.NetworkManager.Device"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.Device",
+            "org.Xetibo.ReSet.Test.NetworkManager.Device"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_DEVICE_INTERFACE {
+macro_rules! NM_DEVICE_WIRELESS_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.Device"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.Device.Wireless",
+            "org.Xetibo.ReSet.Test.NetworkManager.Device"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_DEVICE_WIRELESS_INTERFACE {
+macro_rules! NM_ACCESS_POINT_INTERFACE {
     () => {
-        "org.freedesktop.NetworkManager.Device.Wireless"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.AccessPoint",
+            "org.Xetibo.ReSet.Test.NetworkManager.AccessPoint"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_DEVICE_WIRELESS_INTERFACE {
+macro_rules! NM_CONNECTION_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.Device"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "org.Xetibo.ReSet.Test.NetworkManager.Connection"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_ACCESS_POINT_INTERFACE {
+macro_rules! NM_ACTIVE_CONNECTION_INTERFACE {
     () => {
-        "org.freedesktop.NetworkManager.AccessPoint"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.Connection.Active",
+            "org.Xetibo.ReSet.Test.NetworkManager.ActiveConnection"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_ACCESS_POINT_INTERFACE {
+macro_rules! NM_AGENT_MANAGER_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.AccessPoint"
+        MOCK_ROUTE!(
+            "org.freedesktop.NetworkManager.AgentManager",
+            "org.Xetibo.ReSet.Test.NetworkManager.AgentManager"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_CONNECTION_INTERFACE {
+macro_rules! NM_SECRET_AGENT_INTERFACE {
     () => {
-        "org.freedesktop.NetworkManager.Settings.Connection"
+        "org.freedesktop.NetworkManager.SecretAgent"
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_CONNECTION_INTERFACE {
+macro_rules! BLUEZ_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.Connection"
+        MOCK_ROUTE!("org.bluez", "org.Xetibo.ReSet.Test")
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_ACTIVE_CONNECTION_INTERFACE {
+macro_rules! BLUEZ_ADAPTER_INTERFACE {
     () => {
-        "org.freedesktop.NetworkManager.Connection.Active"
+        MOCK_ROUTE!("org.bluez.Adapter1", "org.Xetibo.ReSet.Test.Bluez.Adapter")
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_ACTIVE_CONNECTION_INTERFACE {
+macro_rules! BLUEZ_DEVICE_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.NetworkManager.ActiveConnection"
+        MOCK_ROUTE!("org.bluez.Device1", "org.Xetibo.ReSet.Test.Bluez.Device")
     };
 }
 
-#[cfg(not(test))]
-macro_rules! BLUEZ_INTERFACE {
+macro_rules! BLUEZ_AGENT_INTERFACE {
     () => {
-        "org.bluez"
+        MOCK_ROUTE!(
+            "org.bluez.AgentManager1",
+            "org.Xetibo.ReSet.Test.Bluez.AgentManager"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! BLUEZ_INTERFACE {
+macro_rules! BLUEZ_MEDIA_PLAYER_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test"
+        MOCK_ROUTE!(
+            "org.bluez.MediaPlayer1",
+            "org.Xetibo.ReSet.Test.Bluez.MediaPlayer"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! BLUEZ_ADAPTER_INTERFACE {
+macro_rules! BLUEZ_BATTERY_INTERFACE {
     () => {
-        "org.Xetibo.ReSet.Test.Bluez.Adapter"
+        MOCK_ROUTE!("org.bluez.Battery1", "org.Xetibo.ReSet.Test.Bluez.Battery")
     };
 }
 
-#[cfg(not(test))]
-macro_rules! BLUEZ_ADAPTER_INTERFACE {
+macro_rules! LOGIND_INTERFACE_BASE {
     () => {
-        "org.bluez.Adapter1"
+        MOCK_ROUTE!("org.freedesktop.login1", "org.Xetibo.ReSet.Test")
     };
 }
 
-#[cfg(test)]
-macro_rules! BLUEZ_DEVICE_INTERFACE {
+macro_rules! LOGIND_PATH {
     () => {
-        "org.Xetibo.ReSet.Test.Bluez.Device"
+        MOCK_ROUTE!("/org/freedesktop/login1", "/org/Xetibo/ReSet/Test/Login")
     };
 }
 
-#[cfg(not(test))]
-macro_rules! BLUEZ_DEVICE_INTERFACE {
+macro_rules! LOGIND_MANAGER_INTERFACE {
     () => {
-        "org.bluez.Device1"
+        MOCK_ROUTE!(
+            "org.freedesktop.login1.Manager",
+            "org.Xetibo.ReSet.Test.Login.Manager"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! BLUEZ_AGENT_INTERFACE {
+macro_rules! MM_INTERFACE_BASE {
     () => {
-        "org.Xetibo.ReSet.Test.Bluez.AgentManager"
+        MOCK_ROUTE!("org.freedesktop.ModemManager1", "org.Xetibo.ReSet.Test")
     };
 }
 
-#[cfg(not(test))]
-macro_rules! BLUEZ_AGENT_INTERFACE {
+macro_rules! MM_MODEM_INTERFACE {
     () => {
-        "org.bluez.AgentManager1"
+        MOCK_ROUTE!(
+            "org.freedesktop.ModemManager1.Modem",
+            "org.Xetibo.ReSet.Test.ModemManager.Modem"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_PATH {
+macro_rules! MM_MODEM_3GPP_INTERFACE {
     () => {
-        "/org/freedesktop/NetworkManager"
+        MOCK_ROUTE!(
+            "org.freedesktop.ModemManager1.Modem.Modem3gpp",
+            "org.Xetibo.ReSet.Test.ModemManager.Modem3gpp"
+        )
     };
 }
 
-#[cfg(test)]
 macro_rules! NM_PATH {
     () => {
-        "/org/Xetibo/ReSet/Test"
+        MOCK_ROUTE!("/org/freedesktop/NetworkManager", "/org/Xetibo/ReSet/Test")
     };
 }
 
-#[cfg(not(test))]
 macro_rules! NM_SETTINGS_PATH {
     () => {
-        "/org/freedesktop/NetworkManager/Settings"
+        MOCK_ROUTE!(
+            "/org/freedesktop/NetworkManager/Settings",
+            "/org/Xetibo/ReSet/Test"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_SETTINGS_PATH {
+macro_rules! NM_DEVICES_PATH {
     () => {
-        "/org/Xetibo/ReSet/Test"
+        MOCK_ROUTE!(
+            "/org/freedesktop/NetworkManager/Devices",
+            "/org/Xetibo/ReSet/Test/Devices"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_DEVICES_PATH {
+macro_rules! NM_ACCESS_POINT_PATH {
     () => {
-        "/org/freedesktop/NetworkManager/Devices"
+        MOCK_ROUTE!(
+            "/org/freedesktop/NetworkManager/AccessPoint",
+            "/org/Xetibo/ReSet/Test/Devices"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_DEVICES_PATH {
+macro_rules! NM_ACTIVE_CONNECTION_PATH {
     () => {
-        "/org/Xetibo/ReSet/Test/Devices"
+        MOCK_ROUTE!(
+            "/org/freedesktop/NetworkManager/ActiveConnection/",
+            "/org/Xetibo/ReSet/Test"
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_ACCESS_POINT_PATH {
+macro_rules! NM_AGENT_MANAGER_PATH {
     () => {
-        "/org/freedesktop/NetworkManager/AccessPoint"
+        MOCK_ROUTE!(
+            "/org/freedesktop/NetworkManager/AgentManager",
+            "/org/Xetibo/ReSet/Test"
+        )
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_ACCESS_POINT_PATH {
+macro_rules! NM_DNS_MANAGER_INTERFACE {
     () => {
-        "/org/Xetibo/ReSet/Test/Devices"
+        "org.freedesktop.NetworkManager.DnsManager"
     };
 }
 
-#[cfg(not(test))]
-macro_rules! NM_ACTIVE_CONNECTION_PATH {
+macro_rules! NM_DNS_MANAGER_PATH {
     () => {
-        "/org/freedesktop/NetworkManager/ActiveConnection/"
+        "/org/freedesktop/NetworkManager/DnsManager"
     };
 }
 
-#[cfg(test)]
-macro_rules! NM_ACTIVE_CONNECTION_PATH {
+// NetworkManager calls agents on this fixed path
+macro_rules! NM_SECRET_AGENT_PATH {
     () => {
-        "/org/Xetibo/ReSet/Test"
+        "/org/freedesktop/NetworkManager/SecretAgent"
     };
 }
 
-#[cfg(not(test))]
-macro_rules! BLUEZ_PATH {
+macro_rules! MM_PATH {
     () => {
-        "/org/bluez"
+        MOCK_ROUTE!("/org/freedesktop/ModemManager1", "/")
+    };
+}
+
+// the object manager of the mock implementation is registered on the root
+macro_rules! MM_MODEM_PATH {
+    () => {
+        MOCK_ROUTE!(
+            "/org/freedesktop/ModemManager1/Modem/0",
+            "/org/Xetibo/ReSet/Test/ModemManager1/Modem/0"
+        )
     };
 }
 
-#[cfg(test)]
 macro_rules! BLUEZ_PATH {
     () => {
-        "/org/Xetibo/ReSet/Test/Bluez"
+        MOCK_ROUTE!("/org/bluez", "/org/Xetibo/ReSet/Test/Bluez")
     };
 }
 
-#[cfg(not(test))]
 macro_rules! BLUEZ_CONTAINS_PATH {
     () => {
-        "bluez"
+        MOCK_ROUTE!("bluez", "Xetibo")
     };
 }
 
-#[cfg(test)]
-macro_rules! BLUEZ_CONTAINS_PATH {
+#[allow(unused_macros)]
+macro_rules! dbus_connection {
     () => {
-        "Xetibo"
+        MOCK_ROUTE!(
+            Connection::new_system().unwrap(),
+            Connection::from($crate::utils::session_channel().unwrap())
+        )
     };
 }
 
-#[cfg(not(test))]
-macro_rules! dbus_method {
-    (
-    $name:expr,
-    $object:expr,
-    $function:expr,
-    $proxy_name:expr,
-    $params:expr,
-    $time:expr,
-    $output:ty,
-) => {{
-        let conn = Connection::new_system().unwrap();
-        let proxy = conn.with_proxy($name, $object, Duration::from_millis($time));
-        let result: Result<$output, dbus::Error> =
-            proxy.method_call($proxy_name, $function, $params);
-        result
-    }};
+macro_rules! dbus_sync_connection {
+    () => {
+        MOCK_ROUTE!(
+            dbus::blocking::SyncConnection::new_system(),
+            $crate::utils::session_channel().map(dbus::blocking::SyncConnection::from)
+        )
+    };
 }
 
-#[cfg(test)]
 macro_rules! dbus_method {
     (
     $name:expr,
@@ -338,7 +383,7 @@ macro_rules! dbus_method {
     $time:expr,
     $output:ty,
 ) => {{
-        let conn = Connection::new_session().unwrap();
+        let conn = dbus_connection!();
         let proxy = conn.with_proxy($name, $object, Duration::from_millis($time));
         let result: Result<$output, dbus::Error> =
             proxy.method_call($proxy_name, $function, $params);
@@ -346,25 +391,6 @@ macro_rules! dbus_method {
     }};
 }
 
-#[cfg(not(test))]
-macro_rules! get_dbus_property {
-    (
-    $name:expr,
-    $object:expr,
-    $interface:expr,
-    $property:expr,
-    $output:ty,
-) => {{
-        let conn = Connection::new_system().unwrap();
-        let proxy = conn.with_proxy($name, $object, Duration::from_millis(1000));
-        use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
-
-        let result: Result<$output, dbus::Error> = proxy.get($interface, $property);
-        result
-    }};
-}
-
-#[cfg(test)]
 macro_rules! get_dbus_property {
     (
     $name:expr,
@@ -373,7 +399,7 @@ macro_rules! get_dbus_property {
     $property:expr,
     $output:ty,
 ) => {{
-        let conn = Connection::new_session().unwrap();
+        let conn = dbus_connection!();
         let proxy = conn.with_proxy($name, $object, Duration::from_millis(1000));
         use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
 
@@ -382,7 +408,6 @@ macro_rules! get_dbus_property {
     }};
 }
 
-#[cfg(not(test))]
 macro_rules! set_dbus_property {
     (
     $name:expr,
@@ -391,7 +416,7 @@ macro_rules! set_dbus_property {
     $property:expr,
     $value:expr,
 ) => {{
-        let conn = Connection::new_system().unwrap();
+        let conn = dbus_connection!();
         let proxy = conn.with_proxy($name, $object, Duration::from_millis(1000));
         use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
 
@@ -399,35 +424,3 @@ macro_rules! set_dbus_property {
         result
     }};
 }
-
-#[cfg(test)]
-macro_rules! set_dbus_property {
-    (
-    $name:expr,
-    $object:expr,
-    $interface:expr,
-    $property:expr,
-    $value:expr,
-) => {{
-        let conn = Connection::new_session().unwrap();
-        let proxy = conn.with_proxy($name, $object, Duration::from_millis(1000));
-        use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
-
-        let result: Result<(), dbus::Error> = proxy.set($interface, $property, $value);
-        result
-    }};
-}
-
-#[allow(unused_macros)]
-macro_rules! dbus_connection {
-    () => {
-        Connection::new_system().unwrap()
-    };
-}
-
-#[cfg(test)]
-macro_rules! dbus_connection {
-    () => {
-        Connection::new_session().unwrap()
-    };
-}
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::{PropMap, RefArg},
+    blocking::{
+        stdintf::org_freedesktop_dbus::{Properties, PropertiesPropertiesChanged},
+        Connection,
+    },
+    message::{MatchRule, SignalArgs},
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+use dbus_crossroads::Crossroads;
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use crate::{
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{self, MEDIA},
+    DaemonData,
+};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Actions of PlayerAction as (action, MPRIS method).
+pub const PLAYER_ACTIONS: &[(&str, &str)] = &[
+    ("play", "Play"),
+    ("pause", "Pause"),
+    ("play_pause", "PlayPause"),
+    ("stop", "Stop"),
+    ("next", "Next"),
+    ("previous", "Previous"),
+];
+
+/// Track as (title, artists, album, length in milliseconds, art url).\
+/// Unknown fields are empty or 0.
+pub type MediaTrack = (String, Vec<String>, String, u64, String);
+/// Player as (bus name, identity, playback status, track).\
+/// The bus name identifies the player for PlayerAction, the identity is the name shown to users.
+/// The playback status is one of Playing, Paused or Stopped.
+pub type MediaPlayer = (String, String, String, MediaTrack);
+
+/// Well-known names of the players keyed by their unique name, PropertiesChanged of MPRIS is
+/// sent from the unique name.
+static PLAYER_NAMES: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Aggregates the MPRIS players on the session bus.\
+/// Applets get media controls of every player without implementing the discovery of MPRIS.
+pub fn setup_media_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(MEDIA, |c| {
+        c.signal::<(MediaPlayer,), _>("NowPlayingChanged", ("player",));
+        c.signal::<(String,), _>("PlayerRemoved", ("player",));
+        c.method_with_cr_async(
+            "ListPlayers",
+            (),
+            ("players",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((list_players(),))) },
+        );
+        c.method_with_cr_async(
+            "PlayerAction",
+            ("player", "action"),
+            ("result",),
+            move |mut ctx, _, (player, action): (String, String)| async move {
+                ctx.reply(player_action(&player, &action).map(|result| (result,)))
+            },
+        );
+    });
+    token
+}
+
+fn session_connection() -> Result<Connection, MethodErr> {
+    utils::session_connection().map_err(|_| {
+        status_error(
+            StatusCode::ServiceUnavailable,
+            "Could not connect to the session bus",
+        )
+    })
+}
+
+/// Returns all MPRIS players, playing players first.
+pub fn list_players() -> Vec<MediaPlayer> {
+    let Ok(conn) = session_connection() else {
+        return Vec::new();
+    };
+    let mut players: Vec<MediaPlayer> = player_names(&conn)
+        .into_iter()
+        .filter_map(|name| get_player(&conn, &name))
+        .collect();
+    players.sort_by(|a, b| (a.2 != "Playing", &a.0).cmp(&(b.2 != "Playing", &b.0)));
+    players
+}
+
+/// Runs one of the PLAYER_ACTIONS on a player.\
+/// Returns false if the player rejected the action, e.g. Next without a playlist.
+pub fn player_action(player: &str, action: &str) -> Result<bool, MethodErr> {
+    let Some((_, method)) = PLAYER_ACTIONS.iter().find(|(name, _)| *name == action) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown player action",
+        ));
+    };
+    let conn = session_connection()?;
+    if !player.starts_with(MPRIS_PREFIX) || !player_names(&conn).iter().any(|name| name == player) {
+        return Err(status_error(StatusCode::DeviceNotFound, "Unknown player"));
+    }
+    let proxy = conn.with_proxy(player, MPRIS_PATH, Duration::from_millis(1000));
+    let res: Result<(), dbus::Error> = proxy.method_call(MPRIS_PLAYER_INTERFACE, *method, ());
+    if let Err(_error) = res {
+        ERROR!(
+            format!("{} rejected {}: {}", player, action, _error),
+            ErrorLevel::Recoverable
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn player_names(conn: &Connection) -> Vec<String> {
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(1000),
+    );
+    let res: Result<(Vec<String>,), dbus::Error> =
+        proxy.method_call("org.freedesktop.DBus", "ListNames", ());
+    res.map(|(names,)| {
+        names
+            .into_iter()
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn get_player(conn: &Connection, name: &str) -> Option<MediaPlayer> {
+    let proxy = conn.with_proxy(name, MPRIS_PATH, Duration::from_millis(1000));
+    let properties: PropMap = proxy.get_all(MPRIS_PLAYER_INTERFACE).ok()?;
+    // the identity is optional for players, the bus name is used instead
+    let identity: String = proxy
+        .get(MPRIS_INTERFACE, "Identity")
+        .unwrap_or_else(|_| name.trim_start_matches(MPRIS_PREFIX).to_string());
+    let status = properties
+        .get("PlaybackStatus")
+        .and_then(|status| status.0.as_str())
+        .unwrap_or("Stopped")
+        .to_string();
+    let track = properties
+        .get("Metadata")
+        .map(|metadata| track_from_metadata(&metadata.0))
+        .unwrap_or_default();
+    Some((name.to_string(), identity, status, track))
+}
+
+/// Converts the Metadata property of MPRIS.\
+/// The dictionary is iterated as key value pairs, as variants received from the bus are not
+/// deserialized into a PropMap.
+pub fn track_from_metadata(metadata: &dyn RefArg) -> MediaTrack {
+    let mut track = MediaTrack::default();
+    let Some(mut entries) = metadata.as_iter() else {
+        return track;
+    };
+    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+        let text = || value.as_str().unwrap_or_default().to_string();
+        match key.as_str().unwrap_or_default() {
+            "xesam:title" => track.0 = text(),
+            "xesam:artist" => track.1 = strings(value),
+            "xesam:album" => track.2 = text(),
+            // microseconds, some players send an unsigned length
+            "mpris:length" => {
+                let length = value
+                    .as_i64()
+                    .or_else(|| value.as_u64().map(|length| length as i64))
+                    .unwrap_or(0);
+                track.3 = length.max(0) as u64 / 1000;
+            }
+            "mpris:artUrl" => track.4 = text(),
+            _ => (),
+        }
+    }
+    track
+}
+
+/// Collects the strings of a string or a (variant of an) array of strings.
+fn strings(value: &dyn RefArg) -> Vec<String> {
+    if let Some(value) = value.as_str() {
+        return vec![value.to_string()];
+    }
+    value
+        .as_iter()
+        .map(|values| values.flat_map(strings).collect())
+        .unwrap_or_default()
+}
+
+/// Emits NowPlayingChanged when players appear or change their track or playback status, and
+/// PlayerRemoved once they leave the bus.
+pub fn start_media_player_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = utils::session_connection()?;
+        for name in player_names(&conn) {
+            if let Some(owner) = get_name_owner(&conn, &name) {
+                PLAYER_NAMES.lock().unwrap().insert(owner, name);
+            }
+        }
+        let mut owner_changed = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged");
+        owner_changed.sender = Some("org.freedesktop.DBus".into());
+        let changed_ref = connection.clone();
+        let res = conn.add_match(
+            owner_changed,
+            move |(name, old_owner, new_owner): (String, String, String), _, _| {
+                if !name.starts_with(MPRIS_PREFIX) {
+                    return true;
+                }
+                let mut names = PLAYER_NAMES.lock().unwrap();
+                names.remove(&old_owner);
+                if new_owner.is_empty() {
+                    drop(names);
+                    let msg = Message::signal(
+                        &Path::from(DBUS_PATH!()),
+                        &MEDIA.into(),
+                        &"PlayerRemoved".into(),
+                    )
+                    .append1(name);
+                    emit_signal(&connection, msg);
+                    return true;
+                }
+                names.insert(new_owner, name.clone());
+                drop(names);
+                emit_now_playing(&connection, &name);
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on the session bus: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on the session bus.",
+            ));
+        }
+        let player_changed =
+            PropertiesPropertiesChanged::match_rule(None, Some(&Path::from(MPRIS_PATH)))
+                .static_clone();
+        let res = conn.add_match(
+            player_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                if ir.interface_name != MPRIS_PLAYER_INTERFACE {
+                    return true;
+                }
+                // position updates are left to the clients, only tracks and status are relevant
+                let relevant = ir.changed_properties.contains_key("Metadata")
+                    || ir.changed_properties.contains_key("PlaybackStatus");
+                if !relevant {
+                    return true;
+                }
+                let Some(sender) = msg.sender() else {
+                    return true;
+                };
+                let name = PLAYER_NAMES.lock().unwrap().get(&*sender).cloned();
+                if let Some(name) = name {
+                    emit_now_playing(&changed_ref, &name);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on the session bus: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on the session bus.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn get_name_owner(conn: &Connection, name: &str) -> Option<String> {
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(1000),
+    );
+    let res: Result<(String,), dbus::Error> =
+        proxy.method_call("org.freedesktop.DBus", "GetNameOwner", (name,));
+    res.ok().map(|(owner,)| owner)
+}
+
+fn emit_now_playing(connection: &Arc<SyncConnection>, name: &str) {
+    let Ok(conn) = utils::session_connection() else {
+        return;
+    };
+    let Some(player) = get_player(&conn, name) else {
+        return;
+    };
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &MEDIA.into(),
+        &"NowPlayingChanged".into(),
+    )
+    .append1(player);
+    emit_signal(connection, msg);
+}
@@ -3,6 +3,9 @@ use std::{thread, time::Duration};
 use dbus::Path;
 use dbus_crossroads::Crossroads;
 
+/// Devices found by each discovery.
+const MOCK_DEVICES: [&str; 2] = ["Device1", "Device2"];
+
 pub fn mock_bluetooth_adapter_interface(
     cross: &mut Crossroads,
 ) -> dbus_crossroads::IfaceToken<MockBluetoothAdapterData> {
@@ -13,6 +16,12 @@ pub fn mock_bluetooth_adapter_interface(
                 Ok(Some(new_value))
             })
             .get(|_, data: &mut MockBluetoothAdapterData| Ok(data.discoverable));
+        c.property("DiscoverableTimeout")
+            .set(|_, data: &mut MockBluetoothAdapterData, new_value| {
+                data.discoverable_timeout = new_value;
+                Ok(Some(new_value))
+            })
+            .get(|_, data: &mut MockBluetoothAdapterData| Ok(data.discoverable_timeout));
         c.property("Pairable")
             .set(|_, data: &mut MockBluetoothAdapterData, new_value| {
                 data.pairable = new_value;
@@ -31,18 +40,22 @@ pub fn mock_bluetooth_adapter_interface(
             let data: &mut MockBluetoothAdapterData = cross.data_mut(ctx.path()).unwrap();
             let adapter_path = data.adapter_path.clone();
             let interface = data.device_interface;
-            create_mock_bluetooth_device(
-                cross,
-                interface,
-                &Path::from(BLUEZ_PATH!().to_string() + "/hci0/Device1"),
-                adapter_path,
-            );
+            for device in MOCK_DEVICES {
+                create_mock_bluetooth_device(
+                    cross,
+                    interface,
+                    &Path::from(BLUEZ_PATH!().to_string() + "/hci0/" + device),
+                    adapter_path.clone(),
+                );
+            }
             async move { ctx.reply(Ok(())) }
         });
         c.method_with_cr_async("StopDiscovery", (), (), move |mut ctx, cross, ()| {
-            cross.remove::<dbus_crossroads::IfaceToken<MockBluetoothAdapterData>>(&Path::from(
-                BLUEZ_PATH!().to_string() + "/hci0/Device1",
-            ));
+            for device in MOCK_DEVICES {
+                cross.remove::<dbus_crossroads::IfaceToken<MockBluetoothAdapterData>>(&Path::from(
+                    BLUEZ_PATH!().to_string() + "/hci0/" + device,
+                ));
+            }
             async move { ctx.reply(Ok(())) }
         });
     })
@@ -64,6 +77,17 @@ pub fn mock_bluetooth_device_interface(
             .get(|_, _: &mut MockBluetoothDeviceData| Ok(false));
         c.property("Adapter")
             .get(|_, data: &mut MockBluetoothDeviceData| Ok(data.adapter.clone()));
+        c.property("Icon")
+            .get(|_, _: &mut MockBluetoothDeviceData| Ok(String::from("audio-headset")));
+        c.property("AddressType")
+            .get(|_, _: &mut MockBluetoothDeviceData| Ok(String::from("public")));
+        c.property("UUIDs")
+            .get(|_, _: &mut MockBluetoothDeviceData| {
+                Ok(vec![
+                    String::from("00001108-0000-1000-8000-00805f9b34fb"),
+                    String::from("0000110b-0000-1000-8000-00805f9b34fb"),
+                ])
+            });
         c.method_with_cr_async("Connect", (), (), move |mut ctx, cross, ()| {
             let data: &mut MockBluetoothDeviceData = cross.data_mut(ctx.path()).unwrap();
             data.connected = true;
@@ -168,6 +192,7 @@ pub struct MockBluetoothAdapterData {
     pub adapter_path: Path<'static>,
     pub device_interface: dbus_crossroads::IfaceToken<MockBluetoothDeviceData>,
     pub discoverable: bool,
+    pub discoverable_timeout: u32,
     pub pairable: bool,
     pub powered: bool,
     pub alias: String,
@@ -182,6 +207,7 @@ impl MockBluetoothAdapterData {
             adapter_path,
             device_interface,
             discoverable: false,
+            discoverable_timeout: 180,
             pairable: false,
             powered: false,
             alias: String::from("test_adapter"),
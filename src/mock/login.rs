@@ -0,0 +1,25 @@
+use std::{fs::File, os::fd::IntoRawFd};
+
+use dbus::arg::OwnedFd;
+use dbus_crossroads::Crossroads;
+
+pub fn mock_login_manager_interface(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<()> {
+    cross.register(LOGIND_MANAGER_INTERFACE!(), |c| {
+        c.method(
+            "Inhibit",
+            ("what", "who", "why", "mode"),
+            ("fd",),
+            move |_, _, (_, _, _, _): (String, String, String, String)| {
+                // any file descriptor works, the inhibitor is released once it is closed
+                let file = File::open("/dev/null").unwrap();
+                let fd = unsafe { OwnedFd::new(file.into_raw_fd()) };
+                Ok((fd,))
+            },
+        );
+    })
+}
+
+pub fn create_mock_login(cross: &mut Crossroads) {
+    let login_manager_interface = mock_login_manager_interface(cross);
+    cross.insert(LOGIND_PATH!(), &[login_manager_interface], ());
+}
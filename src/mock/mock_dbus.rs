@@ -1,16 +1,23 @@
-use std::{collections::HashMap, future, sync::atomic::AtomicBool};
+use std::{
+    collections::HashMap,
+    future,
+    sync::{atomic::AtomicBool, Mutex},
+};
 
+use crate::utils::session_connection_sync;
 use dbus::{channel::MatchingReceiver, message::MatchRule};
 use dbus_crossroads::Crossroads;
-use dbus_tokio::connection;
 use re_set_lib::utils::variant::Variant;
 
-use crate::mock::{bluetooth::MockBluetooth, network::mock_network_manager};
+use crate::mock::{
+    bluetooth::MockBluetooth, login::create_mock_login, modem::create_mock_modem,
+    network::mock_network_manager,
+};
 
 use crate::mock::{bluetooth::MockBluetoothData, network::MockNetworkManager};
 
 pub async fn start_mock_implementation_server(ready: &AtomicBool) {
-    let res = connection::new_session_sync();
+    let res = session_connection_sync();
     if res.is_err() {
         return;
     }
@@ -36,6 +43,8 @@ pub async fn start_mock_implementation_server(ready: &AtomicBool) {
     let mut mock_implementations = Vec::new();
     let mock_network_manager = mock_network_manager(&mut cross, conn.clone());
     let mock_bluetooth = MockBluetooth::new(&mut cross);
+    create_mock_modem(&mut cross);
+    create_mock_login(&mut cross);
     mock_implementations.push(mock_network_manager.network_manager_base);
     mock_implementations.push(mock_network_manager.network_manager_settings);
     // mock_implementations.push(mock_network_manager.network_manager_active_connection);
@@ -51,7 +60,7 @@ pub async fn start_mock_implementation_server(ready: &AtomicBool) {
         MockTestData {
             network_data: mock_network_manager,
             bluetooth_data: mock_bluetooth.data,
-            plugin_data: HashMap::new(),
+            plugin_data: Mutex::new(HashMap::new()),
         },
     );
     // needed for bluetooth
@@ -74,8 +83,5 @@ pub async fn start_mock_implementation_server(ready: &AtomicBool) {
 pub struct MockTestData {
     pub network_data: MockNetworkManager,
     pub bluetooth_data: MockBluetoothData,
-    pub plugin_data: HashMap<String, Variant>,
+    pub plugin_data: Mutex<HashMap<String, Variant>>,
 }
-
-unsafe impl Send for MockTestData {}
-unsafe impl Sync for MockTestData {}
@@ -1,4 +1,6 @@
 mod bluetooth;
+mod login;
 pub mod mock_dbus;
+mod modem;
 mod network;
-mod sound;
+pub mod sound;
@@ -0,0 +1,54 @@
+use dbus_crossroads::Crossroads;
+
+pub fn mock_modem_interface(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<MockModemData> {
+    cross.register(MM_MODEM_INTERFACE!(), |c| {
+        c.property("Manufacturer")
+            .get(|_, data: &mut MockModemData| Ok(data.manufacturer.clone()));
+        c.property("Model")
+            .get(|_, data: &mut MockModemData| Ok(data.model.clone()));
+        c.property("SignalQuality")
+            .get(|_, data: &mut MockModemData| Ok((data.signal_quality, true)));
+        c.property("State")
+            .get(|_, data: &mut MockModemData| Ok(data.state));
+    })
+}
+
+pub fn mock_modem_3gpp_interface(
+    cross: &mut Crossroads,
+) -> dbus_crossroads::IfaceToken<MockModemData> {
+    cross.register(MM_MODEM_3GPP_INTERFACE!(), |c| {
+        c.property("OperatorName")
+            .get(|_, data: &mut MockModemData| Ok(data.operator.clone()));
+    })
+}
+
+pub fn create_mock_modem(cross: &mut Crossroads) {
+    let modem_interface = mock_modem_interface(cross);
+    let modem_3gpp_interface = mock_modem_3gpp_interface(cross);
+    cross.insert(
+        MM_MODEM_PATH!(),
+        &[modem_interface, modem_3gpp_interface],
+        MockModemData::new(),
+    );
+}
+
+pub struct MockModemData {
+    manufacturer: String,
+    model: String,
+    signal_quality: u32,
+    operator: String,
+    // registered
+    state: i32,
+}
+
+impl MockModemData {
+    pub fn new() -> Self {
+        Self {
+            manufacturer: String::from("Xetibo"),
+            model: String::from("test_modem"),
+            signal_quality: 75,
+            operator: String::from("test_operator"),
+            state: 8,
+        }
+    }
+}
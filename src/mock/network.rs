@@ -1,10 +1,13 @@
 use std::{collections::HashMap, sync::Arc};
 
-use dbus::{arg::PropMap, channel::Sender, nonblock::SyncConnection, Message, Path};
+use dbus::{
+    arg::{PropMap, Variant},
+    channel::Sender,
+    nonblock::SyncConnection,
+    Message, Path,
+};
 use dbus_crossroads::Crossroads;
 use re_set_lib::network::connection::{PropMapConvert, WifiSecuritySettings, WifiSettings};
-#[cfg(debug_assertions)]
-use re_set_lib::write_log_to_file;
 use re_set_lib::LOG;
 
 use crate::mock::mock_dbus::MockTestData;
@@ -45,6 +48,8 @@ pub fn mock_network_manager_base(
     cross.register(NM_INTERFACE!(), |c| {
         c.property("WirelessEnabled")
             .get(|_, data: &mut MockTestData| Ok(data.network_data.network_manager_data.enabled));
+        c.property("WirelessHardwareEnabled").get(|_, _| Ok(true));
+        c.property("WwanEnabled").get(|_, _| Ok(false));
         c.property("ActiveConnections")
             .get(|_, cross: &mut MockTestData| {
                 Ok(cross
@@ -72,7 +77,7 @@ pub fn mock_network_manager_base(
             ("path", "active_connection"),
             move |mut ctx,
                   cross,
-                  (connection, _device, specific_object): (
+                  (connection, device, specific_object): (
                 HashMap<String, PropMap>,
                 Path<'static>,
                 Path<'static>,
@@ -152,6 +157,9 @@ pub fn mock_network_manager_base(
                         settings.unwrap(),
                         secrets.unwrap(),
                     );
+                    if let Some(device) = cross.data_mut::<MockDeviceData>(&device) {
+                        device.active_connection = active_connection.clone();
+                    }
                 }
 
                 async move { ctx.reply(Ok((connection_path, active_connection))) }
@@ -259,22 +267,44 @@ pub fn mock_network_manager_connection(
             ("settings",),
             move |mut ctx, cross, ()| {
                 let data: &mut MockConnectionData = cross.data_mut(ctx.path()).unwrap();
-                let settings = data.settings.clone();
-                async move { ctx.reply(Ok((settings.to_propmap(),))) }
+                let mut connection = PropMap::new();
+                connection.insert(
+                    "type".into(),
+                    Variant(Box::new(String::from("802-11-wireless"))),
+                );
+                let settings = HashMap::from([
+                    (String::from("connection"), connection),
+                    (String::from("802-11-wireless"), data.settings.to_propmap()),
+                ]);
+                async move { ctx.reply(Ok((settings,))) }
             },
         );
-        c.method_with_cr_async("GetSecrets", (), ("secrets",), move |mut ctx, cross, ()| {
-            let data: &mut MockConnectionData = cross.data_mut(ctx.path()).unwrap();
-            let connections = data.secrets.clone();
-            async move { ctx.reply(Ok((connections.to_propmap(),))) }
-        });
+        c.method_with_cr_async(
+            "GetSecrets",
+            ("setting_name",),
+            ("secrets",),
+            move |mut ctx, cross, (_setting_name,): (String,)| {
+                let data: &mut MockConnectionData = cross.data_mut(ctx.path()).unwrap();
+                let secrets = HashMap::from([(
+                    String::from("802-11-wireless-security"),
+                    data.secrets.to_propmap(),
+                )]);
+                async move { ctx.reply(Ok((secrets,))) }
+            },
+        );
+        // like NetworkManager, the settings are replaced as a whole and nothing is returned
         c.method_with_cr_async(
             "Update",
-            ("settings",),
+            ("properties",),
             (),
-            move |mut ctx, cross, (settings,): (PropMap,)| {
+            move |mut ctx, cross, (settings,): (HashMap<String, PropMap>,)| {
                 let data: &mut MockConnectionData = cross.data_mut(ctx.path()).unwrap();
-                data.settings = WifiSettings::from_propmap(&settings);
+                let empty = PropMap::new();
+                data.settings =
+                    WifiSettings::from_propmap(settings.get("802-11-wireless").unwrap_or(&empty));
+                if let Some(secrets) = settings.get("802-11-wireless-security") {
+                    data.secrets = WifiSecuritySettings::from_propmap(secrets);
+                }
                 async move { ctx.reply(Ok(())) }
             },
         );
@@ -294,6 +324,16 @@ pub fn mock_network_manager_device(
         c.signal::<(Path<'static>,), _>("AccessPointRemoved", ("access_point",));
         c.property("DeviceType")
             .get(|_, data: &mut MockDeviceData| Ok(data.device_type));
+        c.property("ActiveConnection")
+            .get(|_, data: &mut MockDeviceData| Ok(data.active_connection.clone()));
+        c.method_with_cr_async(
+            "Reapply",
+            ("connection", "version_id", "flags"),
+            (),
+            move |mut ctx, _, (_, _, _): (HashMap<String, PropMap>, u64, u32)| async move {
+                ctx.reply(Ok(()))
+            },
+        );
         c.property("Interface")
             .get(|_, _: &mut MockDeviceData| Ok("Mock".to_string()));
         c.method_with_cr_async(
@@ -425,6 +465,7 @@ impl MockAccessPointData {
 pub struct MockDeviceData {
     device_type: u32,
     active_access_point: Path<'static>,
+    active_connection: Path<'static>,
     access_points: Vec<Path<'static>>,
     access_point_interface: dbus_crossroads::IfaceToken<MockAccessPointData>,
 }
@@ -437,6 +478,7 @@ impl MockDeviceData {
         Self {
             device_type: 2,
             active_access_point: Path::from("/"),
+            active_connection: Path::from("/"),
             access_points: vec![Path::from(
                 "/org/Xetibo/ReSet/Test/AccessPoint/".to_string() + &id.to_string(),
             )],
@@ -1,15 +1,208 @@
-// use super::mock_dbus::MockNetworkData;
-// use dbus_crossroads::Crossroads;
-//
-// const MOCK_SOUND: &'static str = "MOCKsound";
-//
-// pub fn mock_sound_interface(
-//     cross: &mut Crossroads,
-// ) -> dbus_crossroads::IfaceToken<MockNetworkData> {
-//     let token = cross.register(MOCK_SOUND, |c| {
-//         println!("pingpang sound");
-//     });
-//     token
-// }
-// does this even make sense ?
-// sound has no dbus, so we just require pulse ?
+use crossbeam::channel::{Receiver, Sender};
+use pulse::volume::Volume;
+use re_set_lib::audio::audio_structures::{Sink, Source};
+
+use crate::audio::audio_manager::BluetoothAutoRoute;
+use crate::utils::{AudioRequest, AudioResponse};
+
+/// Audio has no dbus service which could be mocked, the mock answers the requests of the audio
+/// interface in place of the audio server.\
+/// Provides a single stereo sink and source, requests without a response in the mock return
+/// the error response, requests which are not answered by the audio server are ignored.\
+/// Connecting Bluetooth devices add a sink named like the ones of bluez, which is removed again
+/// on disconnect, the automatic routing is applied like with the audio server.
+pub struct MockAudio {
+    sink: Sink,
+    source: Source,
+    bluetooth_sinks: Vec<Sink>,
+    default_sink: String,
+    bluetooth_route: BluetoothAutoRoute,
+}
+
+impl Default for MockAudio {
+    fn default() -> Self {
+        Self {
+            sink: Sink {
+                index: 0,
+                name: String::from("reset_mock_sink"),
+                alias: String::from("Mock Sink"),
+                channels: 2,
+                volume: vec![Volume::NORMAL.0; 2],
+                muted: false,
+                active: 1,
+            },
+            source: Source {
+                index: 0,
+                name: String::from("reset_mock_source"),
+                alias: String::from("Mock Source"),
+                channels: 2,
+                volume: vec![Volume::NORMAL.0; 2],
+                muted: false,
+                active: 1,
+            },
+            bluetooth_sinks: Vec::new(),
+            default_sink: String::from("reset_mock_sink"),
+            bluetooth_route: BluetoothAutoRoute::default(),
+        }
+    }
+}
+
+impl MockAudio {
+    /// Answers requests until the listener is stopped or the daemon drops the channels.
+    pub fn listen_to_messages(
+        &mut self,
+        sender: Sender<AudioResponse>,
+        receiver: Receiver<AudioRequest>,
+    ) {
+        while let Ok(request) = receiver.recv() {
+            if let AudioRequest::StopListener = request {
+                break;
+            }
+            if let Some(response) = self.handle(request) {
+                if sender.send(response).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn sinks(&self) -> Vec<Sink> {
+        let mut sinks = vec![self.sink.clone()];
+        sinks.extend(self.bluetooth_sinks.iter().cloned());
+        sinks
+    }
+
+    fn find_sink(&self, name: &str) -> Option<Sink> {
+        self.sinks().into_iter().find(|sink| sink.name == name)
+    }
+
+    fn default_sink(&self) -> Sink {
+        self.find_sink(&self.default_sink)
+            .unwrap_or_else(|| self.sink.clone())
+    }
+
+    fn connect_bluetooth_device(&mut self, address: String) {
+        let name = format!("bluez_output.{}.1", address.replace(':', "_"));
+        if self.find_sink(&name).is_none() {
+            self.bluetooth_sinks.push(Sink {
+                index: self.bluetooth_sinks.len() as u32 + 1,
+                name: name.clone(),
+                alias: address.clone(),
+                ..self.sink.clone()
+            });
+        }
+        if self.bluetooth_route.enabled {
+            let current_default = self.default_sink.clone();
+            self.bluetooth_route.route(&name, current_default);
+            self.default_sink = name;
+        }
+    }
+
+    fn disconnect_bluetooth_device(&mut self, address: String) {
+        let name = format!("bluez_output.{}.1", address.replace(':', "_"));
+        self.bluetooth_sinks.retain(|sink| sink.name != name);
+        if let Some(previous_default) = self.bluetooth_route.disconnect(&address) {
+            self.default_sink = previous_default;
+        }
+    }
+
+    fn handle(&mut self, request: AudioRequest) -> Option<AudioResponse> {
+        let response = match request {
+            AudioRequest::ListSinks => AudioResponse::Sinks(self.sinks()),
+            AudioRequest::GetDefaultSink => AudioResponse::DefaultSink(self.default_sink()),
+            AudioRequest::GetDefaultSinkName => {
+                AudioResponse::DefaultSinkName(self.default_sink.clone())
+            }
+            AudioRequest::SetDefaultSink(name) if self.find_sink(&name).is_some() => {
+                self.default_sink = name;
+                AudioResponse::DefaultSink(self.default_sink())
+            }
+            AudioRequest::ListSources => AudioResponse::Sources(vec![self.source.clone()]),
+            AudioRequest::GetDefaultSource => AudioResponse::DefaultSource(self.source.clone()),
+            AudioRequest::GetDefaultSourceName => {
+                AudioResponse::DefaultSourceName(self.source.name.clone())
+            }
+            AudioRequest::SetDefaultSource(name) if name == self.source.name => {
+                AudioResponse::DefaultSource(self.source.clone())
+            }
+            AudioRequest::SetSinkVolume(index, channels, volume) => {
+                if index == self.sink.index {
+                    self.sink.volume = vec![volume; channels as usize];
+                }
+                return None;
+            }
+            AudioRequest::SetSinkMute(index, muted) => {
+                if index == self.sink.index {
+                    self.sink.muted = muted;
+                }
+                return None;
+            }
+            AudioRequest::SetSourceVolume(index, channels, volume) => {
+                if index == self.source.index {
+                    self.source.volume = vec![volume; channels as usize];
+                }
+                return None;
+            }
+            AudioRequest::SetSourceMute(index, muted) => {
+                if index == self.source.index {
+                    self.source.muted = muted;
+                }
+                return None;
+            }
+            AudioRequest::ChangeDefaultSinkVolume(step) => {
+                let step = step as i64 * Volume::NORMAL.0 as i64 / 100;
+                for volume in self.sink.volume.iter_mut() {
+                    *volume = (*volume as i64 + step).clamp(0, Volume::NORMAL.0 as i64) as u32;
+                }
+                AudioResponse::DefaultSink(self.sink.clone())
+            }
+            AudioRequest::ToggleDefaultSinkMute => {
+                self.sink.muted = !self.sink.muted;
+                AudioResponse::DefaultSink(self.sink.clone())
+            }
+            AudioRequest::SetBluetoothAutoRoute(enabled) => {
+                self.bluetooth_route = BluetoothAutoRoute {
+                    enabled,
+                    ..Default::default()
+                };
+                return None;
+            }
+            AudioRequest::BluetoothDeviceConnected(address) => {
+                self.connect_bluetooth_device(address);
+                return None;
+            }
+            AudioRequest::BluetoothDeviceDisconnected(address) => {
+                self.disconnect_bluetooth_device(address);
+                return None;
+            }
+            AudioRequest::ListInputStreams => AudioResponse::InputStreams(Vec::new()),
+            AudioRequest::ListOutputStreams => AudioResponse::OutputStreams(Vec::new()),
+            AudioRequest::ListCards => AudioResponse::Cards(Vec::new()),
+            AudioRequest::ListSourceMonitors => AudioResponse::SourceMonitors(Vec::new()),
+            AudioRequest::ListNoiseSuppressedSources => {
+                AudioResponse::NoiseSuppressedSources(Vec::new())
+            }
+            AudioRequest::DiscoverNetworkSinks => AudioResponse::NetworkSinks(Vec::new()),
+            AudioRequest::GetServerInfo => AudioResponse::ServerInfo(String::from("ReSet Mock")),
+            AudioRequest::RefreshCache => AudioResponse::CacheRefreshed,
+            AudioRequest::SetInputStreamMute(..)
+            | AudioRequest::SetInputStreamVolume(..)
+            | AudioRequest::SetSinkOfInputStream(..)
+            | AudioRequest::SetOutputStreamMute(..)
+            | AudioRequest::SetOutputStreamVolume(..)
+            | AudioRequest::SetSourceOfOutputStream(..)
+            | AudioRequest::SetCardProfileOfDevice(..)
+            | AudioRequest::BluetoothSinkAdded(..)
+            | AudioRequest::InputStreamAdded(..)
+            | AudioRequest::SetAutoHeadsetProfile(..)
+            | AudioRequest::SourceOutputsChanged
+            | AudioRequest::AudioDevicesChanged
+            | AudioRequest::ReleasePeakMonitors(..)
+            | AudioRequest::CreateVirtualDevices
+            | AudioRequest::Resync
+            | AudioRequest::StopListener => return None,
+            _ => AudioResponse::Error,
+        };
+        Some(response)
+    }
+}
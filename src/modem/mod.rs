@@ -0,0 +1,2 @@
+pub mod modem_manager;
+pub mod modem_manager_dbus;
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, time::Duration};
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    blocking::Connection,
+    Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+// NMDeviceType
+const DEVICE_TYPE_MODEM: u32 = 8;
+
+/// Modem as (path, manufacturer, model, signal quality in percent, operator, state).\
+/// The state is the MMModemState of ModemManager, e.g. 8 for registered and 11 for connected.
+pub type Modem = (Path<'static>, String, String, u32, String, i32);
+
+/// Stored mobile broadband connection as (path, name).
+pub type MobileConnection = (Path<'static>, String);
+
+pub fn get_modems() -> Vec<Modem> {
+    let res = dbus_method!(
+        MM_INTERFACE_BASE!(),
+        Path::from(MM_PATH!()),
+        "GetManagedObjects",
+        "org.freedesktop.DBus.ObjectManager",
+        (),
+        1000,
+        (HashMap<Path<'static>, HashMap<String, PropMap>>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not get modems from ModemManager: {}", _error),
+            ErrorLevel::PartialBreakage
+        );
+        return Vec::new();
+    }
+    let mut modems: Vec<Modem> = res
+        .unwrap()
+        .0
+        .iter()
+        .filter_map(|(path, interfaces)| convert_modem(path, interfaces))
+        .collect();
+    modems.sort_by(|a, b| a.0.cmp(&b.0));
+    modems
+}
+
+fn convert_modem(path: &Path<'static>, interfaces: &HashMap<String, PropMap>) -> Option<Modem> {
+    let modem = interfaces.get(MM_MODEM_INTERFACE!())?;
+    let manufacturer: Option<&String> = prop_cast(modem, "Manufacturer");
+    let model: Option<&String> = prop_cast(modem, "Model");
+    // (quality, recent)
+    let signal_quality: Option<&(u32, bool)> = prop_cast(modem, "SignalQuality");
+    let state: Option<&i32> = prop_cast(modem, "State");
+    // only available once the modem is enabled
+    let operator = interfaces
+        .get(MM_MODEM_3GPP_INTERFACE!())
+        .and_then(|modem_3gpp| prop_cast::<String>(modem_3gpp, "OperatorName"))
+        .cloned()
+        .unwrap_or_default();
+    Some((
+        path.clone(),
+        manufacturer.cloned().unwrap_or_default(),
+        model.cloned().unwrap_or_default(),
+        signal_quality.map(|quality| quality.0).unwrap_or(0),
+        operator,
+        state.copied().unwrap_or(0),
+    ))
+}
+
+/// Returns all stored connections of NetworkManager with the gsm type.
+pub fn get_mobile_connections() -> Vec<MobileConnection> {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_SETTINGS_PATH!()),
+        "ListConnections",
+        NM_SETTINGS_INTERFACE!(),
+        (),
+        1000,
+        (Vec<Path<'static>>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not get stored connections: {}", _error),
+            ErrorLevel::PartialBreakage
+        );
+        return Vec::new();
+    }
+    let mut connections = Vec::new();
+    for path in res.unwrap().0 {
+        let res = dbus_method!(
+            NM_INTERFACE_BASE!(),
+            path.clone(),
+            "GetSettings",
+            NM_CONNECTION_INTERFACE!(),
+            (),
+            1000,
+            (HashMap<String, PropMap>,),
+        );
+        if res.is_err() {
+            continue;
+        }
+        let settings = res.unwrap().0;
+        let connection = settings.get("connection");
+        if connection.is_none() {
+            continue;
+        }
+        let connection = connection.unwrap();
+        if prop_cast::<String>(connection, "type").map(String::as_str) != Some("gsm") {
+            continue;
+        }
+        let id = prop_cast::<String>(connection, "id")
+            .cloned()
+            .unwrap_or_default();
+        connections.push((path, id));
+    }
+    connections
+}
+
+/// Activates a stored mobile broadband connection on the NetworkManager device of the given
+/// modem.\
+/// Returns true on success and false on error.
+pub fn activate_mobile_connection(connection: Path<'static>, modem: Path<'static>) -> bool {
+    let device = get_modem_device(&modem);
+    if device.is_none() {
+        ERROR!(
+            format!("No NetworkManager device found for modem {}", modem),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "ActivateConnection",
+        NM_INTERFACE!(),
+        (connection.clone(), device.unwrap(), Path::from("/")),
+        5000,
+        (Path<'static>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to activate mobile connection: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    LOG!(format!(
+        "Activated mobile connection {} on modem {}",
+        connection, modem
+    ));
+    true
+}
+
+/// NetworkManager exposes the ModemManager path of a modem as Udi of its device.
+fn get_modem_device(modem: &Path<'static>) -> Option<Path<'static>> {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "GetAllDevices",
+        NM_INTERFACE!(),
+        (),
+        1000,
+        (Vec<Path<'static>>,),
+    );
+    if res.is_err() {
+        return None;
+    }
+    res.unwrap().0.into_iter().find(|device| {
+        let device_type = get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            device.clone(),
+            NM_DEVICE_INTERFACE!(),
+            "DeviceType",
+            u32,
+        );
+        let udi = get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            device.clone(),
+            NM_DEVICE_INTERFACE!(),
+            "Udi",
+            String,
+        );
+        matches!(device_type, Ok(DEVICE_TYPE_MODEM))
+            && udi.map(|udi| udi == **modem).unwrap_or(false)
+    })
+}
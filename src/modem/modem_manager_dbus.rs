@@ -0,0 +1,32 @@
+use dbus::Path;
+use dbus_crossroads::Crossroads;
+
+use crate::{utils::MODEM, DaemonData};
+
+use super::modem_manager::{activate_mobile_connection, get_mobile_connections, get_modems};
+
+pub fn setup_modem_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(MODEM, |c| {
+        c.method_with_cr_async(
+            "ListModems",
+            (),
+            ("modems",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((get_modems(),))) },
+        );
+        c.method_with_cr_async(
+            "ListMobileConnections",
+            (),
+            ("connections",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((get_mobile_connections(),))) },
+        );
+        c.method_with_cr_async(
+            "ActivateMobileConnection",
+            ("connection", "modem"),
+            ("result",),
+            move |mut ctx, _, (connection, modem): (Path<'static>, Path<'static>)| async move {
+                ctx.reply(Ok((activate_mobile_connection(connection, modem),)))
+            },
+        );
+    });
+    token
+}
@@ -0,0 +1,213 @@
+use std::{
+    cmp::Reverse,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dbus::{
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+    Path,
+};
+use once_cell::sync::Lazy;
+use re_set_lib::network::network_structures::AccessPoint;
+
+use crate::utils::{get_daemon_config_value, load_state_file, save_state_file};
+
+const CACHE_FILE: &str = "access_point_cache.toml";
+/// Entries are only written when they are older than this many seconds, strength changes would
+/// otherwise write the file multiple times per scan.
+const RESOLUTION: u64 = 60;
+/// Number of strength samples kept per SSID.
+const STRENGTH_HISTORY: usize = 10;
+/// Two weeks in seconds.
+const DEFAULT_TTL: u64 = 1_209_600;
+
+// NM80211ApFlags and NM80211ApSecurityFlags of NetworkManager
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+pub const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+pub const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+pub const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+
+/// Cached access point as (ssid, security, last seen in seconds since the unix epoch, strength
+/// history).\
+/// The security is one of none, owe, wep, wpa, wpa3 or enterprise, the strength history
+/// contains up to 10 samples, oldest first.
+pub type CachedAccessPoint = (Vec<u8>, String, u64, Vec<u8>);
+
+/// Access points seen by the daemon, keyed by the hex encoded SSID as SSIDs are not necessarily
+/// valid UTF-8.
+/// ```toml
+/// ["5265536574"]
+/// ssid = [82, 101, 83, 101, 116]
+/// security = "wpa"
+/// last_seen = 1718000000
+/// strength = [70, 72, 65]
+/// ```
+/// Entries not seen for access_point_cache_ttl seconds within the daemon section of the config
+/// are removed, the default is two weeks.
+static CACHE: Lazy<Mutex<toml::Table>> = Lazy::new(|| {
+    let mut cache = load_state_file(CACHE_FILE);
+    remove_expired(&mut cache);
+    Mutex::new(cache)
+});
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn ttl() -> u64 {
+    get_daemon_config_value("access_point_cache_ttl")
+        .and_then(|value| value.as_integer())
+        .map(|ttl| ttl.max(0) as u64)
+        .unwrap_or(DEFAULT_TTL)
+}
+
+fn last_seen(entry: &toml::Table) -> u64 {
+    entry
+        .get("last_seen")
+        .and_then(|value| value.as_integer())
+        .unwrap_or(0) as u64
+}
+
+fn remove_expired(cache: &mut toml::Table) -> bool {
+    let deadline = now().saturating_sub(ttl());
+    let count = cache.len();
+    cache.retain(|_, entry| {
+        entry
+            .as_table()
+            .is_some_and(|entry| last_seen(entry) > deadline)
+    });
+    count != cache.len()
+}
+
+fn cache_key(ssid: &[u8]) -> String {
+    ssid.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn bytes(entry: &toml::Table, key: &str) -> Vec<u8> {
+    entry
+        .get(key)
+        .and_then(|value| value.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_integer())
+                .map(|value| value as u8)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn bytes_value(bytes: &[u8]) -> toml::Value {
+    toml::Value::Array(
+        bytes
+            .iter()
+            .map(|byte| toml::Value::Integer(*byte as i64))
+            .collect(),
+    )
+}
+
+/// Records an access point reported by NetworkManager, hidden networks are skipped.
+pub fn record_access_point(access_point: &AccessPoint) {
+    if access_point.ssid.is_empty() {
+        return;
+    }
+    let now = now();
+    let key = cache_key(&access_point.ssid);
+    let security = {
+        let cache = CACHE.lock().unwrap();
+        let entry = cache.get(&key).and_then(|entry| entry.as_table());
+        if entry.is_some_and(|entry| now < last_seen(entry) + RESOLUTION) {
+            return;
+        }
+        entry
+            .and_then(|entry| entry.get("security"))
+            .and_then(|value| value.as_str())
+            .map(String::from)
+    };
+    // the security of an SSID rarely changes, it is only fetched for new entries
+    let security = security.unwrap_or_else(|| get_security(&access_point.dbus_path));
+    let mut cache = CACHE.lock().unwrap();
+    let mut strength = cache
+        .get(&key)
+        .and_then(|entry| entry.as_table())
+        .map(|entry| bytes(entry, "strength"))
+        .unwrap_or_default();
+    strength.push(access_point.strength);
+    if strength.len() > STRENGTH_HISTORY {
+        strength.drain(..strength.len() - STRENGTH_HISTORY);
+    }
+    let mut entry = toml::Table::new();
+    entry.insert(String::from("ssid"), bytes_value(&access_point.ssid));
+    entry.insert(String::from("security"), toml::Value::String(security));
+    entry.insert(String::from("last_seen"), toml::Value::Integer(now as i64));
+    entry.insert(String::from("strength"), bytes_value(&strength));
+    cache.insert(key, toml::Value::Table(entry));
+    remove_expired(&mut cache);
+    save_state_file(CACHE_FILE, &cache);
+}
+
+/// Returns all cached access points, most recently seen first.
+pub fn get_cached_access_points() -> Vec<CachedAccessPoint> {
+    let mut cache = CACHE.lock().unwrap();
+    if remove_expired(&mut cache) {
+        save_state_file(CACHE_FILE, &cache);
+    }
+    let mut access_points: Vec<CachedAccessPoint> = cache
+        .values()
+        .filter_map(|entry| {
+            let entry = entry.as_table()?;
+            Some((
+                bytes(entry, "ssid"),
+                entry
+                    .get("security")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("none")
+                    .to_string(),
+                last_seen(entry),
+                bytes(entry, "strength"),
+            ))
+        })
+        .collect();
+    access_points.sort_by_key(|access_point| Reverse(access_point.2));
+    access_points
+}
+
+fn get_security(path: &Path<'static>) -> String {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        path.clone(),
+        Duration::from_millis(1000),
+    );
+    let flags: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Flags")
+        .unwrap_or(0);
+    let wpa_flags: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "WpaFlags")
+        .unwrap_or(0);
+    let rsn_flags: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "RsnFlags")
+        .unwrap_or(0);
+    security_name(flags, wpa_flags, rsn_flags).to_string()
+}
+
+pub fn security_name(flags: u32, wpa_flags: u32, rsn_flags: u32) -> &'static str {
+    let key_management = wpa_flags | rsn_flags;
+    if key_management & AP_SEC_KEY_MGMT_802_1X != 0 {
+        "enterprise"
+    } else if key_management & AP_SEC_KEY_MGMT_SAE != 0 {
+        "wpa3"
+    } else if key_management & AP_SEC_KEY_MGMT_OWE != 0 {
+        "owe"
+    } else if key_management != 0 {
+        "wpa"
+    } else if flags & AP_FLAGS_PRIVACY != 0 {
+        "wep"
+    } else {
+        "none"
+    }
+}
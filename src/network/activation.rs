@@ -0,0 +1,168 @@
+use std::{
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+use dbus::{blocking::Connection, message::MatchRule, nonblock::SyncConnection, Message, Path};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::signals::emit_signal;
+use crate::status::{StatusCode, StatusError};
+
+/// Values of NMActiveConnectionState.
+pub const ACTIVE_CONNECTION_STATES: &[&str] = &[
+    "unknown",
+    "activating",
+    "activated",
+    "deactivating",
+    "deactivated",
+];
+
+/// Values of NMActiveConnectionStateReason.
+pub const ACTIVE_CONNECTION_REASONS: &[&str] = &[
+    "unknown",
+    "none",
+    "user-disconnected",
+    "device-disconnected",
+    "service-stopped",
+    "ip-config-invalid",
+    "connect-timeout",
+    "service-start-timeout",
+    "service-start-failed",
+    "no-secrets",
+    "login-failed",
+    "connection-removed",
+    "dependency-failed",
+    "device-realize-failed",
+    "device-removed",
+];
+
+const STATE_ACTIVATING: u32 = 1;
+const STATE_ACTIVATED: u32 = 2;
+const STATE_DEACTIVATED: u32 = 4;
+const REASON_UNKNOWN: u32 = 0;
+const REASON_NONE: u32 = 1;
+
+/// NetworkManager gives up on its own long before, this only guards against a missing signal.
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(90);
+
+pub fn active_connection_state_name(state: u32) -> &'static str {
+    ACTIVE_CONNECTION_STATES
+        .get(state as usize)
+        .copied()
+        .unwrap_or("unknown")
+}
+
+pub fn active_connection_reason_name(reason: u32) -> &'static str {
+    ACTIVE_CONNECTION_REASONS
+        .get(reason as usize)
+        .copied()
+        .unwrap_or("unknown")
+}
+
+/// Status code reported for an activation that failed with the given reason.
+pub fn activation_failure_code(reason: u32) -> StatusCode {
+    match active_connection_reason_name(reason) {
+        "no-secrets" | "login-failed" => StatusCode::WrongPassword,
+        "connect-timeout" | "service-start-timeout" => StatusCode::Timeout,
+        "device-removed" | "device-realize-failed" => StatusCode::DeviceNotFound,
+        _ => StatusCode::ConnectionFailed,
+    }
+}
+
+pub fn emit_connection_state_changed(
+    conn: &Arc<SyncConnection>,
+    device: Path<'static>,
+    state: u32,
+    reason: u32,
+) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"ConnectionStateChanged".into(),
+    )
+    .append3(
+        device,
+        active_connection_state_name(state),
+        active_connection_reason_name(reason),
+    );
+    emit_signal(conn, msg);
+}
+
+/// Waits until the active connection is either activated or failed and emits every state it
+/// passes through as ConnectionStateChanged.
+pub fn wait_for_activation(
+    conn: &Arc<SyncConnection>,
+    device: &Path<'static>,
+    active_connection: &Path<'static>,
+) -> Result<(), StatusError> {
+    let dbus_conn = dbus_connection!();
+    let (sender, receiver) = mpsc::channel();
+    let rule = MatchRule::new_signal(NM_ACTIVE_CONNECTION_INTERFACE!(), "StateChanged")
+        .with_path(active_connection.clone());
+    let res = dbus_conn.add_match(rule, move |(state, reason): (u32, u32), _, _| {
+        let _ = sender.send((state, reason));
+        true
+    });
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to match activation signal: {:?}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Err(StatusError::new(
+            StatusCode::ConnectionFailed,
+            "Failed to receive the connection state",
+        ));
+    }
+    // the state might have changed before the match was added
+    let initial = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        active_connection.clone(),
+        NM_ACTIVE_CONNECTION_INTERFACE!(),
+        "State",
+        u32,
+    );
+    let (mut state, mut reason) = match initial {
+        Ok(state) => (state, REASON_NONE),
+        // NetworkManager removes active connections that failed right away
+        Err(_) => (STATE_DEACTIVATED, REASON_UNKNOWN),
+    };
+    emit_connection_state_changed(conn, device.clone(), state, reason);
+    let deadline = Instant::now() + ACTIVATION_TIMEOUT;
+    while state <= STATE_ACTIVATING {
+        if Instant::now() > deadline {
+            return Err(StatusError::new(
+                StatusCode::Timeout,
+                "The connection was not activated in time",
+            ));
+        }
+        if let Err(_error) = dbus_conn.process(Duration::from_millis(100)) {
+            ERROR!(
+                format!("Failed to receive the connection state: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(StatusError::new(
+                StatusCode::ConnectionFailed,
+                "Failed to receive the connection state",
+            ));
+        }
+        while let Ok((new_state, new_reason)) = receiver.try_recv() {
+            (state, reason) = (new_state, new_reason);
+            emit_connection_state_changed(conn, device.clone(), state, reason);
+        }
+    }
+    if state == STATE_ACTIVATED {
+        return Ok(());
+    }
+    let reason_name = active_connection_reason_name(reason);
+    LOG!(format!(
+        "Activation of {} failed: {}.",
+        active_connection, reason_name
+    ));
+    Err(StatusError::new(
+        activation_failure_code(reason),
+        format!("The connection could not be activated: {}", reason_name),
+    ))
+}
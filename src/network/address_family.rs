@@ -0,0 +1,115 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+    MethodErr, Path,
+};
+
+use crate::status::{status_error, StatusCode};
+
+const NM_IP4_CONFIG_INTERFACE: &str = "org.freedesktop.NetworkManager.IP4Config";
+const NM_IP6_CONFIG_INTERFACE: &str = "org.freedesktop.NetworkManager.IP6Config";
+
+pub const FAMILY_DUAL_STACK: &str = "dual-stack";
+pub const FAMILY_IPV4_ONLY: &str = "ipv4-only";
+pub const FAMILY_IPV6_ONLY: &str = "ipv6-only";
+pub const FAMILY_NONE: &str = "none";
+
+/// Address families of the active connection of a device as (family, IPv4 addresses, global
+/// IPv6 addresses).\
+/// The family is one of dual-stack, ipv4-only, ipv6-only or none, a family only counts if the
+/// device has an address and a default route for it.
+pub type AddressFamilies = (String, Vec<String>, Vec<String>);
+
+/// Returns the address families of the active connection of the device, the family is none for
+/// devices without an active connection.
+pub fn get_connection_address_families(
+    device: Path<'static>,
+) -> Result<AddressFamilies, MethodErr> {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(NM_INTERFACE_BASE!(), device, Duration::from_millis(1000));
+    let ip4_config: Path<'static> = proxy
+        .get(NM_DEVICE_INTERFACE!(), "Ip4Config")
+        .map_err(|_| status_error(StatusCode::DeviceNotFound, "The device does not exist"))?;
+    let ip6_config: Path<'static> = proxy
+        .get(NM_DEVICE_INTERFACE!(), "Ip6Config")
+        .unwrap_or_else(|_| Path::from("/"));
+
+    let (ipv4_addresses, ipv4_route) = ip_config(&conn, ip4_config, NM_IP4_CONFIG_INTERFACE);
+    let ipv4_addresses: Vec<String> = ipv4_addresses
+        .into_iter()
+        .filter(|address| is_usable_ipv4(address))
+        .collect();
+    let (ipv6_addresses, ipv6_route) = ip_config(&conn, ip6_config, NM_IP6_CONFIG_INTERFACE);
+    let ipv6_addresses: Vec<String> = ipv6_addresses
+        .into_iter()
+        .filter(|address| is_global_ipv6(address))
+        .collect();
+
+    let family = address_family(
+        ipv4_route && !ipv4_addresses.is_empty(),
+        ipv6_route && !ipv6_addresses.is_empty(),
+    );
+    Ok((family.to_string(), ipv4_addresses, ipv6_addresses))
+}
+
+pub fn address_family(ipv4: bool, ipv6: bool) -> &'static str {
+    match (ipv4, ipv6) {
+        (true, true) => FAMILY_DUAL_STACK,
+        (true, false) => FAMILY_IPV4_ONLY,
+        (false, true) => FAMILY_IPV6_ONLY,
+        (false, false) => FAMILY_NONE,
+    }
+}
+
+/// IPv4 addresses are usable unless they are link-local or loopback addresses, private
+/// addresses still reach the internet through NAT.
+pub fn is_usable_ipv4(address: &str) -> bool {
+    address
+        .parse::<Ipv4Addr>()
+        .is_ok_and(|address| !address.is_link_local() && !address.is_loopback())
+}
+
+/// Returns true for global unicast addresses, link-local and unique local addresses can't reach
+/// the internet.
+pub fn is_global_ipv6(address: &str) -> bool {
+    let Ok(address) = address.parse::<Ipv6Addr>() else {
+        return false;
+    };
+    let first_segment = address.segments()[0];
+    !address.is_loopback()
+        && !address.is_unspecified()
+        && !address.is_multicast()
+        // fe80::/10
+        && first_segment & 0xffc0 != 0xfe80
+        // fc00::/7
+        && first_segment & 0xfe00 != 0xfc00
+}
+
+/// Returns the addresses of the IP config and whether it contains a default route, configs of
+/// inactive devices are "/".
+fn ip_config(conn: &Connection, path: Path<'static>, interface: &str) -> (Vec<String>, bool) {
+    if &*path == "/" {
+        return (Vec::new(), false);
+    }
+    let proxy = conn.with_proxy(NM_INTERFACE_BASE!(), path, Duration::from_millis(1000));
+    let address_data: Vec<PropMap> = proxy.get(interface, "AddressData").unwrap_or_default();
+    let addresses = address_data
+        .iter()
+        .filter_map(|address| prop_cast::<String>(address, "address").cloned())
+        .collect();
+    let gateway: String = proxy.get(interface, "Gateway").unwrap_or_default();
+    let route_data: Vec<PropMap> = proxy.get(interface, "RouteData").unwrap_or_default();
+    let default_route = !gateway.is_empty() || route_data.iter().any(is_default_route);
+    (addresses, default_route)
+}
+
+fn is_default_route(route: &PropMap) -> bool {
+    let prefix = prop_cast::<u32>(route, "prefix").copied();
+    let dest = prop_cast::<String>(route, "dest").map(String::as_str);
+    prefix == Some(0) && matches!(dest, Some("0.0.0.0") | Some("::"))
+}
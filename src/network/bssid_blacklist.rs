@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    MethodErr, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::network_manager::{
+    disconnect_from_access_point, get_access_point_properties, get_bssid_properties,
+    get_wifi_devices,
+};
+use crate::status::{status_error, StatusCode};
+use crate::utils::{load_state_file, save_state_file};
+
+const BLACKLIST_FILE: &str = "bssid_blacklist.toml";
+
+/// Blacklisted BSSID as (bssid, expiry in seconds since the unix epoch).
+pub type BlacklistedBssid = (String, u64);
+
+/// Expiry of every blacklisted BSSID in seconds since the unix epoch, keyed by the BSSID.
+/// ```toml
+/// "AA:BB:CC:DD:EE:FF" = 1718000000
+/// ```
+static BLACKLIST: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(load_blacklist()));
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn load_blacklist() -> HashMap<String, u64> {
+    let now = now();
+    load_state_file(BLACKLIST_FILE)
+        .into_iter()
+        .filter_map(|(bssid, expiry)| Some((bssid, expiry.as_integer()? as u64)))
+        .filter(|(_, expiry)| *expiry > now)
+        .collect()
+}
+
+fn save_blacklist(blacklist: &HashMap<String, u64>) -> bool {
+    let table: toml::Table = blacklist
+        .iter()
+        .map(|(bssid, expiry)| (bssid.clone(), toml::Value::Integer(*expiry as i64)))
+        .collect();
+    save_state_file(BLACKLIST_FILE, &table)
+}
+
+/// Removes expired entries, expired BSSIDs are usable again without further action.
+fn remove_expired(blacklist: &mut HashMap<String, u64>) {
+    let now = now();
+    blacklist.retain(|_, expiry| *expiry > now);
+}
+
+/// NetworkManager reports BSSIDs as uppercase hex pairs separated by colons.
+fn normalize_bssid(bssid: &str) -> Option<String> {
+    let octets: Vec<&str> = bssid.split(':').collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|octet| octet.len() == 2 && u8::from_str_radix(octet, 16).is_ok());
+    if !valid {
+        return None;
+    }
+    Some(bssid.to_uppercase())
+}
+
+/// Blacklists a BSSID for the duration in seconds, a duration of 0 removes the BSSID.\
+/// WiFi devices connected to the BSSID are moved to another BSSID of the same SSID, or
+/// disconnected if there is none.
+pub fn blacklist_access_point(bssid: String, duration: u64) -> Result<bool, MethodErr> {
+    let bssid = normalize_bssid(&bssid);
+    if bssid.is_none() {
+        return Err(status_error(StatusCode::InvalidArgument, "Invalid BSSID"));
+    }
+    let bssid = bssid.unwrap();
+    let mut blacklist = BLACKLIST.lock().unwrap();
+    remove_expired(&mut blacklist);
+    if duration == 0 {
+        blacklist.remove(&bssid);
+    } else {
+        blacklist.insert(bssid.clone(), now().saturating_add(duration));
+    }
+    let result = save_blacklist(&blacklist);
+    drop(blacklist);
+    if duration > 0 {
+        thread::spawn(|| {
+            for device in get_wifi_devices() {
+                let path = device.read().unwrap().dbus_path.clone();
+                enforce_blacklist(path);
+            }
+        });
+    }
+    Ok(result)
+}
+
+/// Returns all BSSIDs which did not expire yet.
+pub fn list_blacklisted_bssids() -> Vec<BlacklistedBssid> {
+    let mut blacklist = BLACKLIST.lock().unwrap();
+    remove_expired(&mut blacklist);
+    let mut bssids: Vec<BlacklistedBssid> = blacklist
+        .iter()
+        .map(|(bssid, expiry)| (bssid.clone(), *expiry))
+        .collect();
+    bssids.sort();
+    bssids
+}
+
+pub fn is_blacklisted(bssid: &str) -> bool {
+    BLACKLIST
+        .lock()
+        .unwrap()
+        .get(&bssid.to_uppercase())
+        .is_some_and(|expiry| *expiry > now())
+}
+
+/// Moves WiFi devices away from blacklisted BSSIDs whenever NetworkManager connects or roams to
+/// one of them.
+pub fn start_bssid_blacklist_listener() {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let mut device_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&NM_INTERFACE_BASE!().into()),
+            Some(&Path::from(NM_DEVICES_PATH!())),
+        )
+        .static_clone();
+        device_changed.path_is_namespace = true;
+        let res = conn.add_match(
+            device_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                let active_access_point: Option<&Path<'static>> =
+                    prop_cast(&ir.changed_properties, "ActiveAccessPoint");
+                if active_access_point.is_some() {
+                    if let Some(path) = msg.path() {
+                        enforce_blacklist(path.into_static());
+                    }
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on NetworkManager {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on NetworkManager.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn enforce_blacklist(device: Path<'static>) {
+    let active_access_point = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        "ActiveAccessPoint",
+        Path<'static>,
+    );
+    let Ok(active_access_point) = active_access_point else {
+        return;
+    };
+    if &*active_access_point == "/" {
+        return;
+    }
+    let (_, bssid, _, _) = get_bssid_properties(active_access_point.clone());
+    if !is_blacklisted(&bssid) {
+        return;
+    }
+    let active_connection = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        NM_DEVICE_INTERFACE!(),
+        "ActiveConnection",
+        Path<'static>,
+    );
+    let Ok(active_connection) = active_connection else {
+        return;
+    };
+    let ssid = get_access_point_properties(active_access_point.clone()).ssid;
+    let alternative = find_alternative_bssid(&device, &active_access_point, &ssid);
+    if let Some(alternative) = alternative {
+        let connection = get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            active_connection.clone(),
+            NM_ACTIVE_CONNECTION_INTERFACE!(),
+            "Connection",
+            Path<'static>,
+        );
+        if let Ok(connection) = connection {
+            let res = dbus_method!(
+                NM_INTERFACE_BASE!(),
+                Path::from(NM_PATH!()),
+                "ActivateConnection",
+                NM_INTERFACE!(),
+                (connection, device.clone(), alternative.clone()),
+                1000,
+                (Path<'static>,),
+            );
+            if res.is_ok() {
+                LOG!(format!(
+                    "Moved {} from blacklisted BSSID {} to {}",
+                    device, bssid, alternative
+                ));
+                return;
+            }
+        }
+    }
+    // deactivating blocks autoconnect, NetworkManager would otherwise pick the same BSSID again
+    if disconnect_from_access_point(active_connection).is_ok() {
+        LOG!(format!(
+            "Disconnected {} from blacklisted BSSID {}",
+            device, bssid
+        ));
+    }
+}
+
+/// Returns the strongest access point with the same SSID that is not blacklisted.
+fn find_alternative_bssid(
+    device: &Path<'static>,
+    active_access_point: &Path<'static>,
+    ssid: &[u8],
+) -> Option<Path<'static>> {
+    let access_points = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        "AccessPoints",
+        Vec<Path<'static>>,
+    )
+    .ok()?;
+    access_points
+        .into_iter()
+        .filter(|path| path != active_access_point)
+        .filter(|path| get_access_point_properties(path.clone()).ssid == ssid)
+        .map(get_bssid_properties)
+        .filter(|(_, bssid, _, _)| !is_blacklisted(bssid))
+        .max_by_key(|(_, _, _, strength)| *strength)
+        .map(|(path, _, _, _)| path)
+}
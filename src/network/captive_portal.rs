@@ -0,0 +1,171 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::signals::emit_signal;
+
+// NMConnectivityState
+const CONNECTIVITY_PORTAL: u32 = 2;
+const CONNECTIVITY_FULL: u32 = 4;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// URL of the currently detected portal, None when there is no portal or it was resolved.
+static PORTAL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Emits CaptivePortalDetected once NetworkManager reports a portal.\
+/// The signal is emitted once per portal, until it is resolved or the connectivity changes.
+pub fn start_captive_portal_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let connectivity_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&NM_INTERFACE_BASE!().into()),
+            Some(&Path::from(NM_PATH!())),
+        )
+        .static_clone();
+        let res = conn.add_match(
+            connectivity_changed,
+            move |ir: PropertiesPropertiesChanged, _, _| {
+                let connectivity: Option<&u32> = prop_cast(&ir.changed_properties, "Connectivity");
+                if let Some(connectivity) = connectivity {
+                    handle_connectivity(&connection, *connectivity);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on NetworkManager {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on NetworkManager.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn handle_connectivity(conn: &Arc<SyncConnection>, connectivity: u32) {
+    if connectivity != CONNECTIVITY_PORTAL {
+        PORTAL.lock().unwrap().take();
+        return;
+    }
+    if PORTAL.lock().unwrap().is_some() {
+        return;
+    }
+    let url = probe_portal_url();
+    LOG!(format!("Captive portal detected at {}", url));
+    PORTAL.lock().unwrap().replace(url.clone());
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"CaptivePortalDetected".into(),
+    )
+    .append1(url);
+    emit_signal(conn, msg);
+}
+
+/// Requests a new connectivity check from NetworkManager after the user logged into the portal.\
+/// Returns true when full connectivity is available.
+pub fn mark_portal_resolved() -> bool {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "CheckConnectivity",
+        NM_INTERFACE!(),
+        (),
+        5000,
+        (u32,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to check connectivity: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    let resolved = res.unwrap().0 == CONNECTIVITY_FULL;
+    if resolved {
+        PORTAL.lock().unwrap().take();
+    }
+    resolved
+}
+
+/// Portals intercept the connectivity check of NetworkManager, usually with a redirect to the
+/// login page. Falls back to the check URL itself, which the portal intercepts as well.
+fn probe_portal_url() -> String {
+    let check_url = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        NM_INTERFACE!(),
+        "ConnectivityCheckUri",
+        String,
+    )
+    .unwrap_or_default();
+    if check_url.is_empty() {
+        return String::from("http://nmcheck.gnome.org/check_network_status.txt");
+    }
+    redirect_target(&check_url).unwrap_or(check_url)
+}
+
+/// Plain HTTP request without following redirects, portals can only intercept unencrypted
+/// traffic anyway.
+fn redirect_target(url: &str) -> Option<String> {
+    let url = url.strip_prefix("http://")?;
+    let (host, path) = match url.split_once('/') {
+        Some((host, path)) => (host, format!("/{}", path)),
+        None => (url, String::from("/")),
+    };
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    let address = address.to_socket_addrs().ok()?.next()?;
+    let mut stream = TcpStream::connect_timeout(&address, PROBE_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(PROBE_TIMEOUT)).ok()?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut response = Vec::new();
+    let _ = stream.take(16 * 1024).read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    let mut lines = response.lines();
+    let status = lines.next()?.split_whitespace().nth(1)?;
+    if !status.starts_with('3') {
+        return None;
+    }
+    lines.take_while(|line| !line.is_empty()).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.eq_ignore_ascii_case("location") {
+            return None;
+        }
+        let location = value.trim();
+        if location.starts_with('/') {
+            return Some(format!("http://{}{}", host, location));
+        }
+        Some(location.to_string())
+    })
+}
@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use dbus::{blocking::Connection, MethodErr, Path};
+
+use super::{network_manager::get_bssid_properties, roaming::scan};
+use crate::status::{status_error, StatusCode};
+
+/// Channels of 2.4 GHz which do not overlap with each other.
+const CHANNELS_2GHZ: &[u32] = &[1, 6, 11];
+/// Channels of 5 GHz which do not require DFS.
+const CHANNELS_5GHZ: &[u32] = &[36, 40, 44, 48, 149, 153, 157, 161];
+/// Channels of 2.4 GHz are 5 MHz apart while a transmission is 20 MHz wide, closer channels
+/// interfere with each other.
+const OVERLAPPING_CHANNELS: u32 = 4;
+
+/// Channel as (band, channel, visible access points, strongest signal in percent).\
+/// The band is one of 2.4GHz, 5GHz or 6GHz.
+pub type ChannelUtilization = (String, u32, u32, u8);
+
+/// Band of a WiFi frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiBand {
+    TwoPointFour,
+    Five,
+    Six,
+}
+
+impl WifiBand {
+    /// Returns the band of a frequency in MHz.
+    pub fn from_frequency(frequency: u32) -> Option<Self> {
+        match frequency {
+            2412..=2484 => Some(WifiBand::TwoPointFour),
+            5160..=5885 => Some(WifiBand::Five),
+            5955..=7115 => Some(WifiBand::Six),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WifiBand::TwoPointFour => "2.4GHz",
+            WifiBand::Five => "5GHz",
+            WifiBand::Six => "6GHz",
+        }
+    }
+}
+
+/// Returns the name of the band of a frequency in MHz, empty for unknown frequencies.
+pub fn band_name(frequency: u32) -> &'static str {
+    WifiBand::from_frequency(frequency).map_or("", |band| band.name())
+}
+
+/// Converts a frequency in MHz to its (band, channel).
+pub fn frequency_to_channel(frequency: u32) -> Option<(&'static str, u32)> {
+    let band = WifiBand::from_frequency(frequency)?;
+    let channel = match (band, frequency) {
+        (_, 2484) => 14,
+        (WifiBand::TwoPointFour, 2473..=2483) => return None,
+        (WifiBand::TwoPointFour, _) => (frequency - 2407) / 5,
+        (WifiBand::Five, _) => (frequency - 5000) / 5,
+        (WifiBand::Six, _) => (frequency - 5950) / 5,
+    };
+    Some((band.name(), channel))
+}
+
+/// Scans with the WiFi device and returns the visible access points per channel, sorted by band
+/// and channel.\
+/// The existing results are used if NetworkManager rejects the scan.
+pub fn get_channel_utilization(
+    device: Path<'static>,
+) -> Result<Vec<ChannelUtilization>, MethodErr> {
+    let access_points = || {
+        get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            device.clone(),
+            NM_DEVICE_WIRELESS_INTERFACE!(),
+            "AccessPoints",
+            Vec<Path<'static>>,
+        )
+    };
+    if access_points().is_err() {
+        return Err(status_error(
+            StatusCode::WrongDeviceType,
+            "The device is not a WiFi device",
+        ));
+    }
+    scan(&device);
+    let bssids: Vec<(u32, u8)> = access_points()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|access_point| {
+            let (_, _, frequency, strength) = get_bssid_properties(access_point);
+            (frequency, strength)
+        })
+        .collect();
+    Ok(channel_utilization(&bssids))
+}
+
+/// Aggregates BSSIDs as (frequency in MHz, strength) per channel.
+pub fn channel_utilization(bssids: &[(u32, u8)]) -> Vec<ChannelUtilization> {
+    let mut channels: BTreeMap<(&str, u32), (u32, u8)> = BTreeMap::new();
+    for (frequency, strength) in bssids {
+        let Some(channel) = frequency_to_channel(*frequency) else {
+            continue;
+        };
+        let entry = channels.entry(channel).or_default();
+        entry.0 += 1;
+        entry.1 = entry.1.max(*strength);
+    }
+    channels
+        .into_iter()
+        .map(|((band, channel), (count, strength))| (band.to_string(), channel, count, strength))
+        .collect()
+}
+
+/// Returns the channel of the band with the fewest interfering access points, the lower channel
+/// wins ties.\
+/// Only channels usable without overlap or DFS are considered, the band is either bg for 2.4 GHz
+/// or a for 5 GHz as in the band setting of NetworkManager.
+pub fn least_crowded_channel(utilization: &[ChannelUtilization], band: &str) -> Option<u32> {
+    // 5 GHz channels are 20 MHz apart and do not overlap
+    let (band, candidates, overlap) = match band {
+        "bg" => (
+            WifiBand::TwoPointFour.name(),
+            CHANNELS_2GHZ,
+            OVERLAPPING_CHANNELS,
+        ),
+        "a" => (WifiBand::Five.name(), CHANNELS_5GHZ, 0),
+        _ => return None,
+    };
+    candidates.iter().copied().min_by_key(|candidate| {
+        let interfering: u32 = utilization
+            .iter()
+            .filter(|(channel_band, channel, _, _)| {
+                channel_band == band && channel.abs_diff(*candidate) <= overlap
+            })
+            .map(|(_, _, count, _)| count)
+            .sum();
+        (interfering, *candidate)
+    })
+}
@@ -0,0 +1,381 @@
+use std::{collections::HashMap, fs::File, io::Read, sync::Mutex, time::Duration};
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::Connection,
+    MethodErr, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::channel_advisor::{get_channel_utilization, least_crowded_channel};
+use super::network_manager::{
+    disconnect_from_access_point, get_connection_settings_with_secrets, set_connection_settings,
+};
+use crate::status::{status_error, StatusCode};
+use crate::utils::{get_daemon_config_value, load_state_file, save_state_file};
+
+const SHARING_FILE: &str = "connection_sharing.toml";
+
+// NMDeviceType
+pub const DEVICE_TYPE_ETHERNET: u32 = 1;
+pub const DEVICE_TYPE_WIFI: u32 = 2;
+
+const PASSWORD_LENGTH: usize = 12;
+const PASSWORD_CHARACTERS: &[u8] = b"abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Shared connections created by the daemon, keyed by the path of the sharing device.
+/// ```toml
+/// ["/org/freedesktop/NetworkManager/Devices/3"]
+/// connection = "/org/freedesktop/NetworkManager/Settings/12"
+/// previous = "/org/freedesktop/NetworkManager/Settings/4"
+/// ```
+static SHARING: Lazy<Mutex<toml::Table>> = Lazy::new(|| Mutex::new(load_state_file(SHARING_FILE)));
+
+/// Shares the internet connection on the given device, a WiFi device is turned into a hotspot.\
+/// NetworkManager forwards shared traffic over the default route, the primary connection is
+/// therefore shared and has to be active on another device.\
+/// The shared connection is created once and reused, the connection previously active on the
+/// device is restored by disable_connection_sharing.
+pub fn enable_connection_sharing(device: Path<'static>) -> Result<bool, MethodErr> {
+    let device_type = get_device_property::<u32>(&device, "DeviceType");
+    if device_type != Some(DEVICE_TYPE_ETHERNET) && device_type != Some(DEVICE_TYPE_WIFI) {
+        return Err(status_error(
+            StatusCode::WrongDeviceType,
+            "Only ethernet and WiFi devices can share a connection",
+        ));
+    }
+    let primary_devices = primary_connection_devices();
+    if primary_devices.is_empty() || primary_devices.contains(&device) {
+        return Err(status_error(
+            StatusCode::NotConnected,
+            "No other device provides an internet connection to share",
+        ));
+    }
+    let mut sharing = SHARING.lock().unwrap();
+    let mut entry = sharing
+        .get(&device.to_string())
+        .and_then(|entry| entry.as_table())
+        .cloned()
+        .unwrap_or_default();
+    // the active connection might already be the shared one when enabling twice
+    if let Some(previous) = active_connection(&device).and_then(|active| settings_of(&active)) {
+        if entry.get("connection").and_then(|value| value.as_str()) != Some(&*previous) {
+            entry.insert(String::from("previous"), previous.to_string().into());
+        }
+    }
+    let connection = entry
+        .get("connection")
+        .and_then(|value| value.as_str())
+        .and_then(|connection| Path::new(connection.to_string()).ok());
+    if let (Some(connection), Some(DEVICE_TYPE_WIFI)) = (&connection, device_type) {
+        update_hotspot_channel(connection, &device);
+    }
+    let activated = match connection {
+        Some(connection) if activate(&connection, &device) => Some(connection),
+        // the stored connection was removed by the user, create it again
+        _ => add_and_activate(&device, device_type.unwrap())?,
+    };
+    if activated.is_none() {
+        return Ok(false);
+    }
+    LOG!(format!("Sharing connection on {}", device));
+    entry.insert(
+        String::from("connection"),
+        activated.unwrap().to_string().into(),
+    );
+    sharing.insert(device.to_string(), toml::Value::Table(entry));
+    Ok(save_state_file(SHARING_FILE, &sharing))
+}
+
+/// Stops sharing on the given device and reactivates the connection used before.\
+/// Returns false if the device is not sharing a connection.
+pub fn disable_connection_sharing(device: Path<'static>) -> bool {
+    let mut sharing = SHARING.lock().unwrap();
+    let entry = sharing
+        .get_mut(&device.to_string())
+        .and_then(|entry| entry.as_table_mut());
+    if entry.is_none() {
+        return false;
+    }
+    let entry = entry.unwrap();
+    let connection = entry.get("connection").and_then(|value| value.as_str());
+    let active = active_connection(&device);
+    let shared_active = active
+        .as_ref()
+        .and_then(settings_of)
+        .is_some_and(|settings| Some(&*settings) == connection);
+    if !shared_active {
+        return false;
+    }
+    if disconnect_from_access_point(active.unwrap()).is_err() {
+        return false;
+    }
+    LOG!(format!("Stopped sharing connection on {}", device));
+    let previous = entry
+        .remove("previous")
+        .and_then(|value| value.as_str().map(|previous| previous.to_string()))
+        .and_then(|previous| Path::new(previous).ok());
+    if let Some(previous) = previous {
+        activate(&previous, &device);
+    }
+    save_state_file(SHARING_FILE, &sharing)
+}
+
+/// Whether the device currently shares a connection created by enable_connection_sharing.
+pub fn is_sharing(device: &Path<'static>) -> bool {
+    let sharing = SHARING.lock().unwrap();
+    let connection = sharing
+        .get(&device.to_string())
+        .and_then(|entry| entry.get("connection"))
+        .and_then(|value| value.as_str());
+    connection.is_some()
+        && active_connection(device)
+            .as_ref()
+            .and_then(settings_of)
+            .is_some_and(|settings| Some(&*settings) == connection)
+}
+
+fn add_and_activate(
+    device: &Path<'static>,
+    device_type: u32,
+) -> Result<Option<Path<'static>>, MethodErr> {
+    let interface = get_device_property::<String>(device, "Interface").unwrap_or_default();
+    let mut properties: HashMap<String, PropMap> = HashMap::new();
+    let mut connection = PropMap::new();
+    connection.insert(
+        String::from("id"),
+        Variant(Box::new(format!("ReSet Sharing {}", interface)) as Box<dyn RefArg>),
+    );
+    connection.insert(
+        String::from("interface-name"),
+        Variant(Box::new(interface) as Box<dyn RefArg>),
+    );
+    connection.insert(
+        String::from("autoconnect"),
+        Variant(Box::new(false) as Box<dyn RefArg>),
+    );
+    if device_type == DEVICE_TYPE_WIFI {
+        connection.insert(
+            String::from("type"),
+            Variant(Box::new(String::from("802-11-wireless")) as Box<dyn RefArg>),
+        );
+        properties.extend(hotspot_settings()?);
+        apply_hotspot_channel(&mut properties, device);
+    } else {
+        connection.insert(
+            String::from("type"),
+            Variant(Box::new(String::from("802-3-ethernet")) as Box<dyn RefArg>),
+        );
+        properties.insert(String::from("802-3-ethernet"), PropMap::new());
+    }
+    properties.insert(String::from("connection"), connection);
+    let mut ipv4 = PropMap::new();
+    ipv4.insert(
+        String::from("method"),
+        Variant(Box::new(String::from("shared")) as Box<dyn RefArg>),
+    );
+    properties.insert(String::from("ipv4"), ipv4);
+    let mut ipv6 = PropMap::new();
+    ipv6.insert(
+        String::from("method"),
+        Variant(Box::new(String::from("ignore")) as Box<dyn RefArg>),
+    );
+    properties.insert(String::from("ipv6"), ipv6);
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "AddAndActivateConnection",
+        NM_INTERFACE!(),
+        (properties, device.clone(), Path::from("/")),
+        5000,
+        (Path<'static>, Path<'static>),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to add shared connection: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Ok(None);
+    }
+    Ok(Some(res.unwrap().0))
+}
+
+/// WPA2 access point, the password can be read with GetConnectionSecrets.\
+/// The SSID can be configured with sharing_ssid within the `[Daemon]` section of the config.
+fn hotspot_settings() -> Result<HashMap<String, PropMap>, MethodErr> {
+    let ssid = get_daemon_config_value("sharing_ssid")
+        .and_then(|value| value.as_str())
+        .unwrap_or("ReSet Hotspot");
+    let mut settings = HashMap::new();
+    let mut wireless = PropMap::new();
+    wireless.insert(
+        String::from("ssid"),
+        Variant(Box::new(ssid.as_bytes().to_vec()) as Box<dyn RefArg>),
+    );
+    wireless.insert(
+        String::from("mode"),
+        Variant(Box::new(String::from("ap")) as Box<dyn RefArg>),
+    );
+    settings.insert(String::from("802-11-wireless"), wireless);
+    let mut security = PropMap::new();
+    security.insert(
+        String::from("key-mgmt"),
+        Variant(Box::new(String::from("wpa-psk")) as Box<dyn RefArg>),
+    );
+    security.insert(
+        String::from("psk"),
+        Variant(Box::new(generate_password()?) as Box<dyn RefArg>),
+    );
+    settings.insert(String::from("802-11-wireless-security"), security);
+    Ok(settings)
+}
+
+/// Sets band and channel of the hotspot with sharing_band and sharing_channel within the
+/// `[Daemon]` section of the config, NetworkManager picks the channel if none is configured.
+/// ```toml
+/// [Daemon]
+/// sharing_band = "bg" # or "a" for 5 GHz
+/// sharing_channel = "auto" # or a fixed channel, e.g. 6
+/// ```
+/// auto scans with the device and picks the least crowded channel.\
+/// Returns whether the settings were changed.
+fn apply_hotspot_channel(settings: &mut HashMap<String, PropMap>, device: &Path<'static>) -> bool {
+    let band = get_daemon_config_value("sharing_band")
+        .and_then(|value| value.as_str())
+        .unwrap_or("bg");
+    let channel = match get_daemon_config_value("sharing_channel") {
+        Some(toml::Value::String(channel)) if channel == "auto" => {
+            get_channel_utilization(device.clone())
+                .ok()
+                .and_then(|utilization| least_crowded_channel(&utilization, band))
+        }
+        Some(toml::Value::Integer(channel)) => u32::try_from(*channel).ok(),
+        _ => None,
+    };
+    let Some(channel) = channel else {
+        return false;
+    };
+    LOG!(format!(
+        "Using channel {} for the hotspot on {}",
+        channel, device
+    ));
+    let wireless = settings.entry(String::from("802-11-wireless")).or_default();
+    wireless.insert(
+        String::from("band"),
+        Variant(Box::new(band.to_string()) as Box<dyn RefArg>),
+    );
+    wireless.insert(
+        String::from("channel"),
+        Variant(Box::new(channel) as Box<dyn RefArg>),
+    );
+    true
+}
+
+/// Picks the channel again for a stored hotspot, the least crowded channel changes over time.
+fn update_hotspot_channel(connection: &Path<'static>, device: &Path<'static>) {
+    let Ok(mut settings) = get_connection_settings_with_secrets(connection.clone()) else {
+        return;
+    };
+    if apply_hotspot_channel(&mut settings, device) {
+        set_connection_settings(connection.clone(), settings);
+    }
+}
+
+/// Fails instead of falling back to a predictable password if no random bytes are available.
+fn generate_password() -> Result<String, MethodErr> {
+    let mut bytes = [0_u8; PASSWORD_LENGTH];
+    let res = File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes));
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not read random bytes: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Err(status_error(
+            StatusCode::Failed,
+            "Could not generate a hotspot password",
+        ));
+    }
+    Ok(bytes
+        .iter()
+        .map(|byte| PASSWORD_CHARACTERS[*byte as usize % PASSWORD_CHARACTERS.len()] as char)
+        .collect())
+}
+
+/// Devices of the primary connection of NetworkManager, empty without internet connection.
+fn primary_connection_devices() -> Vec<Path<'static>> {
+    let primary = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        NM_INTERFACE!(),
+        "PrimaryConnection",
+        Path<'static>,
+    );
+    let Ok(primary) = primary else {
+        return Vec::new();
+    };
+    if &*primary == "/" {
+        return Vec::new();
+    }
+    get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        primary,
+        NM_ACTIVE_CONNECTION_INTERFACE!(),
+        "Devices",
+        Vec<Path<'static>>,
+    )
+    .unwrap_or_default()
+}
+
+fn activate(connection: &Path<'static>, device: &Path<'static>) -> bool {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "ActivateConnection",
+        NM_INTERFACE!(),
+        (connection.clone(), device.clone(), Path::from("/")),
+        5000,
+        (Path<'static>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to activate connection {}: {}", connection, _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    true
+}
+
+pub fn active_connection(device: &Path<'static>) -> Option<Path<'static>> {
+    get_device_property::<Path<'static>>(device, "ActiveConnection")
+        .filter(|connection| &**connection != "/")
+}
+
+/// Path of the stored connection behind an active connection.
+pub fn settings_of(active_connection: &Path<'static>) -> Option<Path<'static>> {
+    get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        active_connection.clone(),
+        NM_ACTIVE_CONNECTION_INTERFACE!(),
+        "Connection",
+        Path<'static>,
+    )
+    .ok()
+}
+
+pub fn get_device_property<T: for<'b> dbus::arg::Get<'b> + 'static>(
+    device: &Path<'static>,
+    property: &str,
+) -> Option<T> {
+    get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        NM_DEVICE_INTERFACE!(),
+        property,
+        T,
+    )
+    .ok()
+}
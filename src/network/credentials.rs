@@ -0,0 +1,140 @@
+use dbus::arg::{prop_cast, PropMap, RefArg};
+
+use super::access_point_cache::{AP_SEC_KEY_MGMT_802_1X, AP_SEC_KEY_MGMT_OWE, AP_SEC_KEY_MGMT_SAE};
+
+// NM80211ApSecurityFlags of NetworkManager
+const AP_SEC_PAIR_WEP40: u32 = 0x1;
+const AP_SEC_PAIR_WEP104: u32 = 0x2;
+const AP_SEC_GROUP_WEP40: u32 = 0x10;
+const AP_SEC_GROUP_WEP104: u32 = 0x20;
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_EAP_SUITE_B_192: u32 = 0x2000;
+
+const WEP_FLAGS: u32 =
+    AP_SEC_PAIR_WEP40 | AP_SEC_PAIR_WEP104 | AP_SEC_GROUP_WEP40 | AP_SEC_GROUP_WEP104;
+const EAP_FLAGS: u32 = AP_SEC_KEY_MGMT_802_1X | AP_SEC_KEY_MGMT_EAP_SUITE_B_192;
+
+/// EAP methods which authenticate with a password.
+const PASSWORD_METHODS: [&str; 6] = ["peap", "ttls", "fast", "leap", "md5", "pwd"];
+/// EAP methods which tunnel a second authentication.
+const TUNNELED_METHODS: [&str; 3] = ["peap", "ttls", "fast"];
+
+/// Checks credentials the same way NetworkManager verifies the settings of a connection.\
+/// The security flags are the NM80211ApSecurityFlags of the access point, i.e. WpaFlags | RsnFlags,
+/// WEP networks are identified by the WEP cipher flags. The EAP map contains the 802-1x settings
+/// of enterprise networks and is ignored otherwise.\
+/// Returns the reason why the credentials are rejected.
+pub fn validate_wifi_credentials(
+    security_flags: u32,
+    password: &str,
+    eap: &PropMap,
+) -> Result<(), String> {
+    if security_flags & EAP_FLAGS != 0 {
+        validate_eap(password, eap)
+    } else if security_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+        // WPA3 transition networks also accept PSKs, SAE itself has no length limit
+        if password.is_empty() {
+            return Err(String::from("The password must not be empty"));
+        }
+        if security_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+            return validate_psk(password);
+        }
+        Ok(())
+    } else if security_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+        validate_psk(password)
+    } else if security_flags & AP_SEC_KEY_MGMT_OWE != 0 || security_flags & WEP_FLAGS == 0 {
+        if !password.is_empty() {
+            return Err(String::from("Open networks do not use a password"));
+        }
+        Ok(())
+    } else {
+        validate_wep_key(password)
+    }
+}
+
+/// 8 to 63 printable ASCII characters or a raw key of 64 hex digits.
+fn validate_psk(password: &str) -> Result<(), String> {
+    if password.len() == 64 {
+        if password.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(());
+        }
+        return Err(String::from(
+            "A password of 64 characters has to be a hexadecimal key",
+        ));
+    }
+    if !(8..=63).contains(&password.len()) {
+        return Err(String::from(
+            "The password has to be between 8 and 63 characters long",
+        ));
+    }
+    if !password.chars().all(|c| (' '..='~').contains(&c)) {
+        return Err(String::from(
+            "The password may only contain printable ASCII characters",
+        ));
+    }
+    Ok(())
+}
+
+/// 5 or 13 ASCII characters, or 10 or 26 hex digits.
+fn validate_wep_key(password: &str) -> Result<(), String> {
+    let valid = match password.len() {
+        5 | 13 => password.is_ascii(),
+        10 | 26 => password.chars().all(|c| c.is_ascii_hexdigit()),
+        _ => false,
+    };
+    if !valid {
+        return Err(String::from(
+            "WEP keys have to be 5 or 13 ASCII characters, or 10 or 26 hexadecimal digits",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_eap(password: &str, eap: &PropMap) -> Result<(), String> {
+    let methods: Vec<String> = prop_cast::<Vec<String>>(eap, "eap")
+        .cloned()
+        .unwrap_or_default();
+    if methods.is_empty() {
+        return Err(String::from("No EAP method is set"));
+    }
+    let non_empty = |key: &str| {
+        eap.get(key).is_some_and(|value| {
+            value
+                .0
+                .as_str()
+                .map(|value| !value.is_empty())
+                .or_else(|| value.0.as_iter().map(|mut bytes| bytes.next().is_some()))
+                .unwrap_or(false)
+        })
+    };
+    if !non_empty("identity") {
+        return Err(String::from("The identity is missing"));
+    }
+    for method in methods.iter() {
+        let method = method.as_str();
+        if method == "tls" {
+            if !non_empty("client-cert") {
+                return Err(String::from("TLS requires a client certificate"));
+            }
+            if !non_empty("private-key") {
+                return Err(String::from("TLS requires a private key"));
+            }
+        } else if PASSWORD_METHODS.contains(&method) {
+            if TUNNELED_METHODS.contains(&method)
+                && !non_empty("phase2-auth")
+                && !non_empty("phase2-autheap")
+            {
+                return Err(format!(
+                    "{} requires an inner authentication method",
+                    method
+                ));
+            }
+            if password.is_empty() {
+                return Err(format!("{} requires a password", method));
+            }
+        } else {
+            return Err(format!("Unknown EAP method {}", method));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use dbus::{
+    arg::{prop_cast, PropMap},
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+    MethodErr, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use super::connection_sharing::active_connection;
+use crate::status::{status_error, StatusCode};
+
+/// DNS configuration of a single connection as (connection name, interface, servers, domains,
+/// priority, vpn).\
+/// The connection name and interface are empty for the global configuration of NetworkManager.
+pub type DnsConfiguration = (String, String, Vec<String>, Vec<String>, i32, bool);
+
+/// DNS state as (mode, resolv.conf manager, servers, configurations).\
+/// The mode is the DNS backend of NetworkManager, e.g. default, dnsmasq or systemd-resolved.
+pub type DnsState = (String, String, Vec<String>, Vec<DnsConfiguration>);
+
+/// Converts an entry of the Configuration property of the DnsManager, the connection name is
+/// filled in separately.
+pub fn dns_configuration_from_map(map: &PropMap) -> DnsConfiguration {
+    let strings = |key: &str| {
+        prop_cast::<Vec<String>>(map, key)
+            .cloned()
+            .unwrap_or_default()
+    };
+    (
+        String::new(),
+        prop_cast::<String>(map, "interface")
+            .cloned()
+            .unwrap_or_default(),
+        strings("nameservers"),
+        strings("domains"),
+        prop_cast::<i32>(map, "priority").copied().unwrap_or(0),
+        prop_cast::<bool>(map, "vpn").copied().unwrap_or(false),
+    )
+}
+
+/// Returns the servers in the order NetworkManager uses them, lower priorities come first and the
+/// global configuration precedes the connections with the same priority.
+pub fn merge_dns_servers(configurations: &[DnsConfiguration]) -> Vec<String> {
+    let mut sorted: Vec<&DnsConfiguration> = configurations.iter().collect();
+    sorted.sort_by_key(|(_, interface, _, _, priority, _)| (*priority, !interface.is_empty()));
+    let mut servers: Vec<String> = Vec::new();
+    for (_, _, configuration_servers, _, _, _) in sorted {
+        for server in configuration_servers {
+            if !servers.contains(server) {
+                servers.push(server.clone());
+            }
+        }
+    }
+    servers
+}
+
+/// Name of the connection active on an interface.
+fn connection_name(conn: &Connection, interface: &str) -> String {
+    if interface.is_empty() {
+        return String::new();
+    }
+    let device: Result<(Path<'static>,), dbus::Error> = conn
+        .with_proxy(
+            NM_INTERFACE_BASE!(),
+            Path::from(NM_PATH!()),
+            Duration::from_millis(1000),
+        )
+        .method_call(NM_INTERFACE!(), "GetDeviceByIpIface", (interface,));
+    device
+        .ok()
+        .and_then(|(device,)| active_connection(&device))
+        .and_then(|active_connection| {
+            conn.with_proxy(
+                NM_INTERFACE_BASE!(),
+                active_connection,
+                Duration::from_millis(1000),
+            )
+            .get(NM_ACTIVE_CONNECTION_INTERFACE!(), "Id")
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Compiles the DNS state from the DnsManager of NetworkManager.
+pub fn get_dns_state() -> Result<DnsState, MethodErr> {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        NM_DNS_MANAGER_PATH!(),
+        Duration::from_millis(1000),
+    );
+    let mode: Result<String, dbus::Error> = proxy.get(NM_DNS_MANAGER_INTERFACE!(), "Mode");
+    if let Err(_error) = mode {
+        ERROR!(
+            format!("Failed to get DNS state: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Err(status_error(
+            StatusCode::Unsupported,
+            "The DNS state of NetworkManager is not available",
+        ));
+    }
+    let rc_manager: String = proxy
+        .get(NM_DNS_MANAGER_INTERFACE!(), "RcManager")
+        .unwrap_or_default();
+    let configuration: Vec<PropMap> = proxy
+        .get(NM_DNS_MANAGER_INTERFACE!(), "Configuration")
+        .unwrap_or_default();
+    let configurations: Vec<DnsConfiguration> = configuration
+        .iter()
+        .map(|map| {
+            let mut configuration = dns_configuration_from_map(map);
+            configuration.0 = connection_name(&conn, &configuration.1);
+            configuration
+        })
+        .collect();
+    Ok((
+        mode.unwrap(),
+        rc_manager,
+        merge_dns_servers(&configurations),
+        configurations,
+    ))
+}
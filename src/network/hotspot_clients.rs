@@ -0,0 +1,200 @@
+use std::{
+    collections::HashSet,
+    fs,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use dbus::{nonblock::SyncConnection, Message, MethodErr, Path};
+use once_cell::sync::Lazy;
+use re_set_lib::LOG;
+
+use super::connection_sharing::{get_device_property, is_sharing, DEVICE_TYPE_WIFI};
+use crate::signals::emit_signal;
+use crate::status::{status_error, StatusCode};
+
+/// Lease files of the dnsmasq instances NetworkManager starts for shared connections.
+const LEASE_DIRECTORY: &str = "/var/lib/NetworkManager";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Client of a hotspot as (MAC address, IP address, hostname, connected since).\
+/// Connected since is in seconds since the unix epoch, IP address and hostname are empty until
+/// the client requested a DHCP lease.
+pub type HotspotClient = (String, String, String, u64);
+
+/// Devices with a running client watcher.
+static WATCHED_DEVICES: Lazy<Mutex<HashSet<Path<'static>>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Parses `iw dev <interface> station dump` into (MAC address, connected seconds).
+pub fn parse_station_dump(output: &str) -> Vec<(String, u64)> {
+    let mut stations: Vec<(String, u64)> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(station) = line.strip_prefix("Station ") {
+            let mac = station.split_whitespace().next().unwrap_or_default();
+            stations.push((mac.to_lowercase(), 0));
+        } else if let Some(connected) = line.strip_prefix("connected time:") {
+            let seconds = connected
+                .split_whitespace()
+                .next()
+                .and_then(|seconds| seconds.parse().ok());
+            if let (Some(station), Some(seconds)) = (stations.last_mut(), seconds) {
+                station.1 = seconds;
+            }
+        }
+    }
+    stations
+}
+
+/// Parses a dnsmasq lease file into (MAC address, IP address, hostname).\
+/// Each line is `<expiry> <mac> <ip> <hostname> <client id>`, unknown hostnames are `*`.
+pub fn parse_leases(contents: &str) -> Vec<(String, String, String)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            let mac = fields.next()?.to_lowercase();
+            let ip = fields.next()?.to_string();
+            let hostname = fields.next().filter(|hostname| *hostname != "*");
+            Some((mac, ip, hostname.unwrap_or_default().to_string()))
+        })
+        .collect()
+}
+
+/// Combines the associated stations with their leases, the current time is in seconds since the
+/// unix epoch.
+pub fn hotspot_clients(
+    stations: &[(String, u64)],
+    leases: &[(String, String, String)],
+    now: u64,
+) -> Vec<HotspotClient> {
+    stations
+        .iter()
+        .map(|(mac, connected)| {
+            let lease = leases.iter().find(|(lease_mac, _, _)| lease_mac == mac);
+            let (ip, hostname) = lease
+                .map(|(_, ip, hostname)| (ip.clone(), hostname.clone()))
+                .unwrap_or_default();
+            (mac.clone(), ip, hostname, now.saturating_sub(*connected))
+        })
+        .collect()
+}
+
+/// Returns the clients that (connected, disconnected) between two lists, matched by MAC address.
+pub fn hotspot_client_changes(
+    previous: &[HotspotClient],
+    current: &[HotspotClient],
+) -> (Vec<HotspotClient>, Vec<String>) {
+    let connected = current
+        .iter()
+        .filter(|client| !previous.iter().any(|known| known.0 == client.0))
+        .cloned()
+        .collect();
+    let disconnected = previous
+        .iter()
+        .filter(|known| !current.iter().any(|client| client.0 == known.0))
+        .map(|known| known.0.clone())
+        .collect();
+    (connected, disconnected)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_clients(interface: &str) -> Result<Vec<HotspotClient>, MethodErr> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "station", "dump"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    if output.is_err() {
+        return Err(status_error(StatusCode::Unsupported, "iw is not installed"));
+    }
+    let stations = parse_station_dump(&String::from_utf8_lossy(&output.unwrap().stdout));
+    let leases = fs::read_to_string(format!("{}/dnsmasq-{}.leases", LEASE_DIRECTORY, interface))
+        .map(|contents| parse_leases(&contents))
+        .unwrap_or_default();
+    Ok(hotspot_clients(&stations, &leases, now()))
+}
+
+fn hotspot_interface(device: &Path<'static>) -> Result<String, MethodErr> {
+    if get_device_property::<u32>(device, "DeviceType") != Some(DEVICE_TYPE_WIFI) {
+        return Err(status_error(
+            StatusCode::WrongDeviceType,
+            "The device is not a WiFi device",
+        ));
+    }
+    if !is_sharing(device) {
+        return Err(status_error(
+            StatusCode::NotConnected,
+            "The device is not sharing a connection",
+        ));
+    }
+    Ok(get_device_property::<String>(device, "Interface").unwrap_or_default())
+}
+
+/// Returns the clients associated with the hotspot of the device.\
+/// Fails if the device is not a WiFi device sharing a connection.
+pub fn list_hotspot_clients(device: Path<'static>) -> Result<Vec<HotspotClient>, MethodErr> {
+    let interface = hotspot_interface(&device)?;
+    read_clients(&interface)
+}
+
+/// Emits HotspotClientConnected and HotspotClientDisconnected until the device stops sharing.\
+/// Only one watcher runs per device.
+pub fn watch_hotspot_clients(conn: Arc<SyncConnection>, device: Path<'static>) {
+    if !WATCHED_DEVICES.lock().unwrap().insert(device.clone()) {
+        return;
+    }
+    thread::spawn(move || {
+        let mut clients: Vec<HotspotClient> = Vec::new();
+        while let Ok(interface) = hotspot_interface(&device) {
+            let current = read_clients(&interface).unwrap_or_default();
+            let (connected, disconnected) = hotspot_client_changes(&clients, &current);
+            for (mac, ip, hostname, since) in connected {
+                LOG!(format!("Hotspot client {} connected on {}", mac, interface));
+                let msg = Message::signal(
+                    &Path::from(DBUS_PATH!()),
+                    &NETWORK_INTERFACE!().into(),
+                    &"HotspotClientConnected".into(),
+                )
+                .append3(device.clone(), mac, ip)
+                .append2(hostname, since);
+                emit_signal(&conn, msg);
+            }
+            for mac in disconnected {
+                LOG!(format!(
+                    "Hotspot client {} disconnected on {}",
+                    mac, interface
+                ));
+                let msg = Message::signal(
+                    &Path::from(DBUS_PATH!()),
+                    &NETWORK_INTERFACE!().into(),
+                    &"HotspotClientDisconnected".into(),
+                )
+                .append2(device.clone(), mac);
+                emit_signal(&conn, msg);
+            }
+            clients = current;
+            thread::sleep(POLL_INTERVAL);
+        }
+        // clients of a stopped hotspot are gone as well
+        for (mac, _, _, _) in clients {
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &NETWORK_INTERFACE!().into(),
+                &"HotspotClientDisconnected".into(),
+            )
+            .append2(device.clone(), mac);
+            emit_signal(&conn, msg);
+        }
+        WATCHED_DEVICES.lock().unwrap().remove(&device);
+    });
+}
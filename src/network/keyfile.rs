@@ -0,0 +1,580 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use dbus::{
+    arg::{cast_mut, PropMap, RefArg, Variant},
+    blocking::Connection,
+    MethodErr, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::{
+    network_manager::{get_connection_settings, get_connection_settings_with_secrets},
+    roaming::bssid_to_bytes,
+};
+use crate::status::{status_error, StatusCode};
+use crate::utils::MaskedPropMap;
+
+/// Settings which use a shorter group name in keyfiles, as (setting, group).
+const KEYFILE_GROUPS: &[(&str, &str)] = &[
+    ("802-3-ethernet", "ethernet"),
+    ("802-11-wireless", "wifi"),
+    ("802-11-wireless-security", "wifi-security"),
+];
+
+/// Type of a key, keys which are not listed here are strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyType {
+    String,
+    Bool,
+    Int32,
+    Uint32,
+    Int64,
+    Uint64,
+    Ssid,
+    Mac,
+    StringList,
+    Ipv4List,
+    Ipv6List,
+    Addresses,
+    Routes,
+}
+
+impl KeyType {
+    fn signature(&self) -> &'static str {
+        match self {
+            KeyType::String => "s",
+            KeyType::Bool => "b",
+            KeyType::Int32 => "i",
+            KeyType::Uint32 => "u",
+            KeyType::Int64 => "x",
+            KeyType::Uint64 => "t",
+            KeyType::Ssid | KeyType::Mac => "ay",
+            KeyType::StringList => "as",
+            KeyType::Ipv4List => "au",
+            KeyType::Ipv6List => "aay",
+            KeyType::Addresses | KeyType::Routes => "aa{sv}",
+        }
+    }
+}
+
+const KEY_TYPES: &[(&str, KeyType)] = &[
+    ("autoconnect", KeyType::Bool),
+    ("read-only", KeyType::Bool),
+    ("hidden", KeyType::Bool),
+    ("may-fail", KeyType::Bool),
+    ("ignore-auto-dns", KeyType::Bool),
+    ("ignore-auto-routes", KeyType::Bool),
+    ("never-default", KeyType::Bool),
+    ("dhcp-send-hostname", KeyType::Bool),
+    ("auto-negotiate", KeyType::Bool),
+    ("system-ca-certs", KeyType::Bool),
+    ("autoconnect-priority", KeyType::Int32),
+    ("autoconnect-retries", KeyType::Int32),
+    ("auth-retries", KeyType::Int32),
+    ("auth-timeout", KeyType::Int32),
+    ("dns-priority", KeyType::Int32),
+    ("dhcp-timeout", KeyType::Int32),
+    ("required-timeout", KeyType::Int32),
+    ("multi-connect", KeyType::Int32),
+    ("lldp", KeyType::Int32),
+    ("mdns", KeyType::Int32),
+    ("llmnr", KeyType::Int32),
+    ("metered", KeyType::Int32),
+    ("wait-device-timeout", KeyType::Int32),
+    ("ip6-privacy", KeyType::Int32),
+    ("addr-gen-mode", KeyType::Int32),
+    ("ap-isolation", KeyType::Int32),
+    ("pmf", KeyType::Int32),
+    ("mtu", KeyType::Uint32),
+    ("channel", KeyType::Uint32),
+    ("rate", KeyType::Uint32),
+    ("tx-power", KeyType::Uint32),
+    ("powersave", KeyType::Uint32),
+    ("mac-address-randomization", KeyType::Uint32),
+    ("wake-on-lan", KeyType::Uint32),
+    ("speed", KeyType::Uint32),
+    ("route-table", KeyType::Uint32),
+    ("wep-key-type", KeyType::Uint32),
+    ("wep-tx-keyidx", KeyType::Uint32),
+    ("wps-method", KeyType::Uint32),
+    ("psk-flags", KeyType::Uint32),
+    ("wep-key-flags", KeyType::Uint32),
+    ("leap-password-flags", KeyType::Uint32),
+    ("password-flags", KeyType::Uint32),
+    ("private-key-password-flags", KeyType::Uint32),
+    ("route-metric", KeyType::Int64),
+    ("timestamp", KeyType::Uint64),
+    ("mac-address", KeyType::Mac),
+    ("cloned-mac-address", KeyType::Mac),
+    ("bssid", KeyType::Mac),
+    ("dns-search", KeyType::StringList),
+    ("dns-options", KeyType::StringList),
+    ("secondaries", KeyType::StringList),
+    ("permissions", KeyType::StringList),
+    ("proto", KeyType::StringList),
+    ("pairwise", KeyType::StringList),
+    ("group", KeyType::StringList),
+    ("eap", KeyType::StringList),
+    ("seen-bssids", KeyType::StringList),
+    ("mac-address-blacklist", KeyType::StringList),
+    ("mac-address-denylist", KeyType::StringList),
+];
+
+fn key_type(setting: &str, key: &str) -> KeyType {
+    match (setting, key) {
+        ("ipv4", "dns") => KeyType::Ipv4List,
+        ("ipv6", "dns") => KeyType::Ipv6List,
+        (_, "address-data") => KeyType::Addresses,
+        (_, "route-data") => KeyType::Routes,
+        (_, "ssid") => KeyType::Ssid,
+        _ => KEY_TYPES
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, key_type)| *key_type)
+            .unwrap_or(KeyType::String),
+    }
+}
+
+fn group_of(setting: &str) -> &str {
+    KEYFILE_GROUPS
+        .iter()
+        .find(|(name, _)| *name == setting)
+        .map(|(_, group)| *group)
+        .unwrap_or(setting)
+}
+
+fn setting_of(group: &str) -> &str {
+    KEYFILE_GROUPS
+        .iter()
+        .find(|(_, name)| *name == group)
+        .map(|(setting, _)| *setting)
+        .unwrap_or(group)
+}
+
+/// Returns the connection in the keyfile format of NetworkManager.\
+/// Secrets are only included if the caller was authorized to read them.
+pub fn export_connection(path: Path<'static>, include_secrets: bool) -> Result<String, MethodErr> {
+    let settings = if include_secrets {
+        get_connection_settings_with_secrets(path)
+    } else {
+        get_connection_settings(path)
+    };
+    Ok(settings_to_keyfile(&settings?))
+}
+
+/// Adds the keyfile as new connection and returns its path.\
+/// The connection receives a new uuid, importing a keyfile twice therefore creates two
+/// connections.
+pub fn import_connection(keyfile: &str) -> Result<Path<'static>, MethodErr> {
+    let mut settings = keyfile_to_settings(keyfile)
+        .map_err(|error| status_error(StatusCode::InvalidArgument, &error))?;
+    let uuid = fs::read_to_string("/proc/sys/kernel/random/uuid").map_err(|_| {
+        status_error(
+            StatusCode::Failed,
+            "Could not generate a uuid for the connection",
+        )
+    })?;
+    settings.get_mut("connection").unwrap().insert(
+        String::from("uuid"),
+        Variant(Box::new(uuid.trim().to_string()) as Box<dyn RefArg>),
+    );
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_SETTINGS_PATH!()),
+        "AddConnection",
+        NM_SETTINGS_INTERFACE!(),
+        (settings,),
+        1000,
+        (Path<'static>,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to import connection: {:?}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Err(status_error(
+            StatusCode::Failed,
+            "NetworkManager rejected the imported connection",
+        ));
+    }
+    let path = res.unwrap().0;
+    LOG!(format!("Imported connection {}", path));
+    Ok(path)
+}
+
+/// Converts connection settings to a keyfile.\
+/// Values which can not be expressed in a keyfile, e.g. the data of VPN plugins, are omitted.
+pub fn settings_to_keyfile(settings: &MaskedPropMap) -> String {
+    let mut settings: Vec<(&String, &PropMap)> = settings.iter().collect();
+    // the connection group comes first like in files written by NetworkManager
+    settings.sort_by_key(|(setting, _)| (*setting != "connection", *setting));
+    let mut keyfile = String::new();
+    for (setting, values) in settings {
+        keyfile.push_str(&format!("[{}]\n", group_of(setting)));
+        let values: BTreeMap<&String, &Variant<Box<dyn RefArg>>> = values.iter().collect();
+        for (key, value) in values {
+            let key_type = key_type(setting, key);
+            if &*value.0.signature() != key_type.signature() {
+                continue;
+            }
+            for (key, value) in value_to_keyfile(setting, key, key_type, &*value.0) {
+                keyfile.push_str(&format!("{}={}\n", key, value));
+            }
+        }
+        keyfile.push('\n');
+    }
+    keyfile
+}
+
+fn value_to_keyfile(
+    setting: &str,
+    key: &str,
+    key_type: KeyType,
+    value: &dyn RefArg,
+) -> Vec<(String, String)> {
+    let numbers = || -> Vec<u64> {
+        value
+            .as_iter()
+            .map(|values| values.filter_map(|value| value.as_u64()).collect())
+            .unwrap_or_default()
+    };
+    let value = match key_type {
+        KeyType::String if (setting, key) == ("connection", "type") => {
+            group_of(value.as_str().unwrap_or_default()).to_string()
+        }
+        KeyType::String => escape(value.as_str().unwrap_or_default(), false),
+        KeyType::Bool => (value.as_i64().unwrap_or(0) != 0).to_string(),
+        KeyType::Int32 | KeyType::Int64 => value.as_i64().unwrap_or(0).to_string(),
+        KeyType::Uint32 | KeyType::Uint64 => value.as_u64().unwrap_or(0).to_string(),
+        KeyType::Ssid => {
+            let bytes: Vec<u8> = numbers().into_iter().map(|byte| byte as u8).collect();
+            match String::from_utf8(bytes.clone()) {
+                Ok(ssid) if !ssid.chars().any(char::is_control) => escape(&ssid, false),
+                _ => join_list(bytes.iter().map(u8::to_string)),
+            }
+        }
+        KeyType::Mac => numbers()
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<String>>()
+            .join(":"),
+        KeyType::StringList => join_list(
+            value
+                .as_iter()
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str().map(|value| escape(value, true))),
+        ),
+        KeyType::Ipv4List => join_list(
+            numbers()
+                .into_iter()
+                // NetworkManager uses network byte order
+                .map(|address| Ipv4Addr::from((address as u32).to_ne_bytes()).to_string()),
+        ),
+        KeyType::Ipv6List => {
+            join_list(value.as_iter().into_iter().flatten().filter_map(|address| {
+                let bytes: Vec<u8> = address
+                    .as_iter()?
+                    .filter_map(|byte| byte.as_u64())
+                    .map(|byte| byte as u8)
+                    .collect();
+                let bytes: [u8; 16] = bytes.try_into().ok()?;
+                Some(Ipv6Addr::from(bytes).to_string())
+            }))
+        }
+        KeyType::Addresses | KeyType::Routes => {
+            return entries_to_keyfile(setting, key_type, value);
+        }
+    };
+    vec![(key.to_string(), value)]
+}
+
+/// Converts address-data and route-data to the numbered address and route keys.
+fn entries_to_keyfile(
+    setting: &str,
+    key_type: KeyType,
+    value: &dyn RefArg,
+) -> Vec<(String, String)> {
+    let unspecified = if setting == "ipv6" { "::" } else { "0.0.0.0" };
+    let mut keys = Vec::new();
+    for (index, entry) in value.as_iter().into_iter().flatten().enumerate() {
+        let entry = dict_entries(entry);
+        let text = |key: &str| entry.get(key).and_then(|value| value.as_str());
+        let prefix = entry.get("prefix").and_then(|value| value.as_u64());
+        let address = if key_type == KeyType::Addresses {
+            text("address")
+        } else {
+            text("dest")
+        };
+        let (Some(address), Some(prefix)) = (address, prefix) else {
+            continue;
+        };
+        let mut value = format!("{}/{}", address, prefix);
+        if key_type == KeyType::Routes {
+            let metric = entry.get("metric").and_then(|value| value.as_u64());
+            let next_hop = text("next-hop");
+            if next_hop.is_some() || metric.is_some() {
+                value.push_str(&format!(",{}", next_hop.unwrap_or(unspecified)));
+            }
+            if let Some(metric) = metric {
+                value.push_str(&format!(",{}", metric));
+            }
+        }
+        let name = if key_type == KeyType::Addresses {
+            "address"
+        } else {
+            "route"
+        };
+        keys.push((format!("{}{}", name, index + 1), value));
+    }
+    keys
+}
+
+fn dict_entries(dict: &dyn RefArg) -> HashMap<String, &dyn RefArg> {
+    let mut entries = HashMap::new();
+    let Some(mut iter) = dict.as_iter() else {
+        return entries;
+    };
+    while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+        if let Some(key) = key.as_str() {
+            entries.insert(key.to_string(), value);
+        }
+    }
+    entries
+}
+
+/// Parses a keyfile into connection settings, unknown keys are read as strings.
+pub fn keyfile_to_settings(keyfile: &str) -> Result<MaskedPropMap, String> {
+    let mut settings = MaskedPropMap::new();
+    let mut setting: Option<String> = None;
+    for line in keyfile.lines().map(str::trim_start) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(group) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let name = setting_of(group.trim()).to_string();
+            settings.entry(name.clone()).or_default();
+            setting = Some(name);
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("Invalid line: {}", line));
+        };
+        let Some(setting) = &setting else {
+            return Err(format!("{} is not part of a group", key.trim()));
+        };
+        let values = settings.get_mut(setting).unwrap();
+        value_from_keyfile(setting, key.trim(), value.trim_start(), values)
+            .ok_or_else(|| format!("Invalid value for {}.{}", setting, key.trim()))?;
+    }
+    let connection = settings
+        .get_mut("connection")
+        .ok_or_else(|| String::from("The keyfile has no connection group"))?;
+    let connection_type = connection
+        .get("type")
+        .and_then(|value| value.0.as_str())
+        .map(|value| setting_of(value).to_string())
+        .ok_or_else(|| String::from("The keyfile has no connection type"))?;
+    connection.insert(
+        String::from("type"),
+        Variant(Box::new(connection_type) as Box<dyn RefArg>),
+    );
+    Ok(settings)
+}
+
+fn value_from_keyfile(setting: &str, key: &str, value: &str, values: &mut PropMap) -> Option<()> {
+    let numbered = |name: &str| {
+        key.strip_prefix(name)
+            .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+    };
+    if numbered("address") || numbered("route") {
+        return entry_from_keyfile(setting, numbered("address"), value, values);
+    }
+    let key_type = key_type(setting, key);
+    let value: Box<dyn RefArg> = match key_type {
+        KeyType::String => Box::new(unescape(value)),
+        KeyType::Bool => Box::new(value.parse::<bool>().ok()?),
+        KeyType::Int32 => Box::new(value.parse::<i32>().ok()?),
+        KeyType::Uint32 => Box::new(value.parse::<u32>().ok()?),
+        KeyType::Int64 => Box::new(value.parse::<i64>().ok()?),
+        KeyType::Uint64 => Box::new(value.parse::<u64>().ok()?),
+        KeyType::Ssid => {
+            let bytes: Option<Vec<u8>> = split_list(value)
+                .iter()
+                .map(|byte| byte.parse::<u8>().ok())
+                .collect();
+            match bytes {
+                Some(bytes) if value.contains(';') => Box::new(bytes),
+                _ => Box::new(unescape(value).into_bytes()),
+            }
+        }
+        KeyType::Mac => Box::new(bssid_to_bytes(value)?),
+        KeyType::StringList => Box::new(split_list(value)),
+        KeyType::Ipv4List => Box::new(
+            split_list(value)
+                .iter()
+                .map(|address| {
+                    let address = address.parse::<Ipv4Addr>().ok()?;
+                    Some(u32::from_ne_bytes(address.octets()))
+                })
+                .collect::<Option<Vec<u32>>>()?,
+        ),
+        KeyType::Ipv6List => Box::new(
+            split_list(value)
+                .iter()
+                .map(|address| Some(address.parse::<Ipv6Addr>().ok()?.octets().to_vec()))
+                .collect::<Option<Vec<Vec<u8>>>>()?,
+        ),
+        // only written as numbered keys
+        KeyType::Addresses | KeyType::Routes => return None,
+    };
+    values.insert(key.to_string(), Variant(value));
+    Some(())
+}
+
+/// Appends an address, e.g. 192.168.1.5/24,192.168.1.1, to address-data or a route, e.g.
+/// 10.0.0.0/8,192.168.1.1,100, to route-data.\
+/// The gateway of an address is used as gateway of the connection if none is set.
+fn entry_from_keyfile(
+    setting: &str,
+    address: bool,
+    value: &str,
+    values: &mut PropMap,
+) -> Option<()> {
+    let max_prefix = if setting == "ipv6" { 128 } else { 32 };
+    let mut fields = value.split(',').map(str::trim);
+    let destination = fields.next()?;
+    let (destination, prefix) = match destination.split_once('/') {
+        Some((destination, prefix)) => (destination, prefix.parse::<u32>().ok()?),
+        None => (destination, max_prefix),
+    };
+    if prefix > max_prefix {
+        return None;
+    }
+    let mut entry = PropMap::new();
+    let destination_key = if address { "address" } else { "dest" };
+    entry.insert(
+        destination_key.to_string(),
+        Variant(Box::new(destination.to_string()) as Box<dyn RefArg>),
+    );
+    entry.insert(
+        String::from("prefix"),
+        Variant(Box::new(prefix) as Box<dyn RefArg>),
+    );
+    let gateway = fields
+        .next()
+        .filter(|gateway| !gateway.is_empty() && *gateway != "0.0.0.0" && *gateway != "::");
+    if address {
+        if let Some(gateway) = gateway {
+            values
+                .entry(String::from("gateway"))
+                .or_insert_with(|| Variant(Box::new(gateway.to_string()) as Box<dyn RefArg>));
+        }
+    } else {
+        if let Some(gateway) = gateway {
+            entry.insert(
+                String::from("next-hop"),
+                Variant(Box::new(gateway.to_string()) as Box<dyn RefArg>),
+            );
+        }
+        if let Some(metric) = fields.next() {
+            entry.insert(
+                String::from("metric"),
+                Variant(Box::new(metric.parse::<u32>().ok()?) as Box<dyn RefArg>),
+            );
+        }
+    }
+    let key = if address {
+        "address-data"
+    } else {
+        "route-data"
+    };
+    let entries = values
+        .get_mut(key)
+        .and_then(|entries| cast_mut::<Vec<PropMap>>(&mut *entries.0));
+    match entries {
+        Some(entries) => entries.push(entry),
+        None => {
+            values.insert(key.to_string(), Variant(Box::new(vec![entry])));
+        }
+    }
+    Some(())
+}
+
+/// Escapes a value like GKeyFile, semicolons are only escaped within lists.
+fn escape(value: &str, list: bool) -> String {
+    let mut escaped = String::new();
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            ' ' if index == 0 => escaped.push_str("\\s"),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ';' if list => escaped.push_str("\\;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(c @ ('\\' | ';')) => unescaped.push(c),
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn join_list(values: impl Iterator<Item = String>) -> String {
+    values.map(|value| format!("{};", value)).collect()
+}
+
+/// Splits a list at unescaped semicolons, the trailing semicolon is optional.
+fn split_list(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            ';' => values.push(unescape(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        values.push(unescape(&current));
+    }
+    values
+}
@@ -0,0 +1,102 @@
+use std::{sync::Arc, time::Duration};
+
+use dbus::{
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+
+use crate::signals::emit_signal;
+use crate::status::{status_error, StatusCode};
+
+/// Link of a WiFi device as (bitrate in kbit/s, frequency in MHz, WiFi generation, strength in
+/// percent).\
+/// The generation is 0 for links older than WiFi 4.
+pub type WifiLinkInfo = (u32, u32, u8, u8);
+
+/// Highest bitrate of WiFi 4 with 4 spatial streams, faster 5 GHz links have to be WiFi 5 or
+/// newer.
+const MAX_WIFI_4_BITRATE: u32 = 600_000;
+/// Highest bitrate of 802.11a/g.
+const MAX_LEGACY_BITRATE: u32 = 54_000;
+/// Start of the 6 GHz band, which can only be used with WiFi 6E or newer.
+const MIN_6GHZ_FREQUENCY: u32 = 5925;
+const MIN_5GHZ_FREQUENCY: u32 = 5000;
+
+/// Returns the link of the WiFi device, fails if the device is not connected.
+pub fn get_wifi_link_info(device: Path<'static>) -> Result<WifiLinkInfo, MethodErr> {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(NM_INTERFACE_BASE!(), device, Duration::from_millis(1000));
+    let bitrate: u32 = proxy
+        .get(NM_DEVICE_WIRELESS_INTERFACE!(), "Bitrate")
+        .map_err(|_| {
+            status_error(
+                StatusCode::WrongDeviceType,
+                "The device is not a WiFi device",
+            )
+        })?;
+    let active_access_point: Path<'static> = proxy
+        .get(NM_DEVICE_WIRELESS_INTERFACE!(), "ActiveAccessPoint")
+        .map_err(|_| {
+            status_error(
+                StatusCode::WrongDeviceType,
+                "The device is not a WiFi device",
+            )
+        })?;
+    if &*active_access_point == "/" {
+        return Err(status_error(
+            StatusCode::NotConnected,
+            "The device is not connected",
+        ));
+    }
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        active_access_point,
+        Duration::from_millis(1000),
+    );
+    let frequency: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Frequency")
+        .unwrap_or(0);
+    let max_bitrate: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "MaxBitrate")
+        .unwrap_or(0);
+    let strength: u8 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Strength")
+        .unwrap_or(0);
+    Ok((
+        bitrate,
+        frequency,
+        wifi_generation(frequency, max_bitrate),
+        strength,
+    ))
+}
+
+/// Derives the WiFi generation from the band and the maximum bitrate of the access point, as
+/// NetworkManager does not expose the negotiated 802.11 mode.\
+/// WiFi 6 can therefore only be told apart from WiFi 5 on the 6 GHz band.
+pub fn wifi_generation(frequency: u32, max_bitrate: u32) -> u8 {
+    if frequency >= MIN_6GHZ_FREQUENCY {
+        6
+    } else if frequency >= MIN_5GHZ_FREQUENCY && max_bitrate > MAX_WIFI_4_BITRATE {
+        5
+    } else if max_bitrate > MAX_LEGACY_BITRATE {
+        4
+    } else {
+        0
+    }
+}
+
+/// Emits WifiLinkInfoChanged for the device, disconnected devices are skipped as they already
+/// emit WifiDeviceChanged.
+pub fn emit_link_info_changed(conn: &Arc<SyncConnection>, device: Path<'static>) {
+    let Ok(info) = get_wifi_link_info(device.clone()) else {
+        return;
+    };
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"WifiLinkInfoChanged".into(),
+    )
+    .append2(device, info);
+    emit_signal(conn, msg);
+}
@@ -0,0 +1,145 @@
+use dbus::{
+    arg::{RefArg, Variant},
+    MethodErr, Path,
+};
+use re_set_lib::LOG;
+
+use super::network_manager::{
+    get_connection_settings, get_connection_settings_with_secrets, set_connection_settings,
+};
+use crate::status::{status_error, StatusCode};
+
+const WIRELESS_SETTING: &str = "802-11-wireless";
+const ASSIGNED_MAC_SETTING: &str = "assigned-mac-address";
+/// Deprecated byte array variant of assigned-mac-address.
+const CLONED_MAC_SETTING: &str = "cloned-mac-address";
+const RANDOMIZATION_SETTING: &str = "mac-address-randomization";
+/// NM_SETTING_MAC_RANDOMIZATION_NEVER and NM_SETTING_MAC_RANDOMIZATION_ALWAYS.
+const RANDOMIZATION_NEVER: u32 = 1;
+const RANDOMIZATION_ALWAYS: u32 = 2;
+
+/// Modes of cloned-mac-address, default leaves the choice to the global configuration of
+/// NetworkManager.\
+/// random changes the address with every activation, stable keeps a generated address per
+/// connection and permanent uses the address of the hardware.
+pub const MAC_MODES: &[&str] = &["default", "permanent", "preserve", "random", "stable"];
+
+/// MAC settings of a WiFi connection as (cloned mac address, scan randomization).\
+/// The cloned mac address is either one of MAC_MODES or an explicit address, scan randomization
+/// is the mac-address-randomization of the connection.
+pub type WifiMacSettings = (String, bool);
+
+pub fn is_mac_address(address: &str) -> bool {
+    let parts: Vec<&str> = address.split(':').collect();
+    parts.len() == 6
+        && parts
+            .iter()
+            .all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Returns whether mac-address-randomization allows random addresses, the default of
+/// NetworkManager randomizes.
+pub fn is_randomization_enabled(value: Option<u32>) -> bool {
+    value != Some(RANDOMIZATION_NEVER)
+}
+
+/// Returns the cloned mac address of a stored WiFi connection, default if it is not set.
+pub fn get_cloned_mac_address(connection: Path<'static>) -> Result<String, MethodErr> {
+    let settings = get_connection_settings(connection)?;
+    let Some(wireless) = settings.get(WIRELESS_SETTING) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The connection is not a WiFi connection",
+        ));
+    };
+    if let Some(address) = wireless
+        .get(ASSIGNED_MAC_SETTING)
+        .and_then(|value| value.0.as_str())
+    {
+        return Ok(address.to_string());
+    }
+    let bytes = wireless.get(CLONED_MAC_SETTING).and_then(|value| {
+        value
+            .0
+            .as_iter()?
+            .map(|byte| byte.as_u64().map(|byte| format!("{:02X}", byte)))
+            .collect::<Option<Vec<String>>>()
+    });
+    Ok(bytes
+        .filter(|bytes| bytes.len() == 6)
+        .map(|bytes| bytes.join(":"))
+        .unwrap_or_else(|| String::from("default")))
+}
+
+/// Sets the cloned mac address of a stored WiFi connection to one of MAC_MODES or an explicit
+/// address, it applies with the next activation of the connection.
+pub fn set_cloned_mac_address(connection: Path<'static>, mode: &str) -> Result<bool, MethodErr> {
+    if !MAC_MODES.contains(&mode) && !is_mac_address(mode) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown MAC address mode",
+        ));
+    }
+    let mut settings = get_connection_settings_with_secrets(connection.clone())?;
+    let Some(wireless) = settings.get_mut(WIRELESS_SETTING) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The connection is not a WiFi connection",
+        ));
+    };
+    // only one of both variants is kept, the deprecated one would conflict
+    wireless.remove(CLONED_MAC_SETTING);
+    if mode == "default" {
+        wireless.remove(ASSIGNED_MAC_SETTING);
+    } else {
+        wireless.insert(
+            String::from(ASSIGNED_MAC_SETTING),
+            Variant(Box::new(mode.to_string()) as Box<dyn RefArg>),
+        );
+    }
+    Ok(set_connection_settings(connection, settings))
+}
+
+/// Returns the cloned mac address and the randomization of a stored WiFi connection.
+pub fn get_wifi_mac_settings(connection: Path<'static>) -> Result<WifiMacSettings, MethodErr> {
+    let address = get_cloned_mac_address(connection.clone())?;
+    let settings = get_connection_settings(connection)?;
+    let randomization = settings
+        .get(WIRELESS_SETTING)
+        .and_then(|wireless| wireless.get(RANDOMIZATION_SETTING))
+        .and_then(|value| value.0.as_u64())
+        .map(|value| value as u32);
+    Ok((address, is_randomization_enabled(randomization)))
+}
+
+/// Sets mac-address-randomization of a stored WiFi connection to always or never, it applies with
+/// the next activation of the connection.
+pub fn set_scan_mac_randomization(
+    connection: Path<'static>,
+    enabled: bool,
+) -> Result<bool, MethodErr> {
+    let mut settings = get_connection_settings_with_secrets(connection.clone())?;
+    let Some(wireless) = settings.get_mut(WIRELESS_SETTING) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The connection is not a WiFi connection",
+        ));
+    };
+    let randomization = if enabled {
+        RANDOMIZATION_ALWAYS
+    } else {
+        RANDOMIZATION_NEVER
+    };
+    wireless.insert(
+        String::from(RANDOMIZATION_SETTING),
+        Variant(Box::new(randomization) as Box<dyn RefArg>),
+    );
+    let res = set_connection_settings(connection.clone(), settings);
+    if res {
+        LOG!(format!(
+            "Set MAC randomization of {} to {}",
+            connection, enabled
+        ));
+    }
+    Ok(res)
+}
@@ -1,2 +1,26 @@
+pub mod access_point_cache;
+pub mod activation;
+pub mod address_family;
+pub mod bssid_blacklist;
+pub mod captive_portal;
+pub mod channel_advisor;
+pub mod connection_sharing;
+pub mod credentials;
+pub mod dns;
+pub mod hotspot_clients;
+pub mod keyfile;
+pub mod link_info;
+pub mod mac_randomization;
 pub mod network_manager;
 pub mod network_manager_dbus;
+pub mod operation_queue;
+pub mod powersave;
+pub mod priority_learning;
+pub mod radio;
+pub mod retry_policy;
+pub mod roaming;
+pub mod secret_agent;
+pub mod speedtest;
+pub mod strength_threshold;
+pub mod wake_on_lan;
+pub mod wifi_qr;
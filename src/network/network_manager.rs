@@ -1,8 +1,10 @@
 use std::{
+    cmp::Reverse,
     collections::HashMap,
+    net::IpAddr,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, RwLock,
+        Arc, Mutex, RwLock,
     },
     thread,
     time::{Duration, SystemTime},
@@ -11,22 +13,54 @@ use std::{
 use dbus::{
     arg::{self, prop_cast, PropMap, RefArg, Variant},
     blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
-    channel::Sender,
     message::SignalArgs,
     nonblock::SyncConnection,
     Message, MethodErr, Path,
 };
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
 use re_set_lib::{
     network::{
         network_signals::{AccessPointAdded, AccessPointRemoved},
-        network_structures::{AccessPoint, ConnectionError, DeviceType, WifiDevice},
+        network_structures::{AccessPoint, DeviceType, WifiDevice},
     },
     {ERROR, LOG},
 };
-#[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
 
-use crate::utils::{DaemonData, MaskedPropMap};
+use super::access_point_cache::record_access_point;
+use super::activation::wait_for_activation;
+use super::channel_advisor::band_name;
+use super::link_info::emit_link_info_changed;
+use super::operation_queue::{try_operation, DeviceOperation};
+use super::strength_threshold::{access_point_state, strength_threshold, StrengthFilter};
+use super::wifi_qr::{WifiQrPayload, WifiQrSecurity};
+use crate::signals::{emit_access_point_changed, emit_signal};
+use crate::status::{status_error, StatusCode, StatusError};
+use crate::supervisor::{supervise_worker, WORKER_NETWORK};
+use crate::utils::{get_daemon_config_value, DaemonData, MaskedPropMap};
+
+/// BSSID of an access point as (path, hardware address, frequency in MHz, strength).
+pub type BssidInfo = (Path<'static>, String, u32, u8);
+/// All BSSIDs of an SSID as (ssid, stored, associated connection, bssids).
+pub type AccessPointGroup = (Vec<u8>, bool, Path<'static>, Vec<BssidInfo>);
+/// Radio of an access point as (path, frequency in MHz, max bitrate in kbit/s, band).
+pub type AccessPointRadio = (Path<'static>, u32, u32, String);
+/// Stored WiFi connection as (path, ssid, autoconnect priority, autoconnect).
+pub type AutoconnectEntry = (Path<'static>, Vec<u8>, i32, bool);
+
+/// Range of connection.autoconnect-priority accepted by NetworkManager.
+const MIN_AUTOCONNECT_PRIORITY: i32 = -999;
+const MAX_AUTOCONNECT_PRIORITY: i32 = 999;
+
+/// Defaults to prefer_strongest_bssid within the daemon section of the config, false otherwise.
+static PREFER_STRONGEST_BSSID: Lazy<AtomicBool> = Lazy::new(|| {
+    AtomicBool::new(
+        get_daemon_config_value("prefer_strongest_bssid")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false),
+    )
+});
 
 #[derive(Debug)]
 pub struct Device {
@@ -64,25 +98,42 @@ impl Device {
     }
 }
 
+/// Listens to the events of all WiFi devices, the current device is additionally kept up to date.\
+/// Access point signals contain the path of the owning device as second argument.
 pub fn start_listener(
     connection: Arc<SyncConnection>,
     device: Arc<RwLock<Device>>,
-    path: Path<'static>,
     active_listener: Arc<AtomicBool>,
     stop_requested: Arc<AtomicBool>,
 ) -> Result<(), dbus::Error> {
     let access_point_added_ref = connection.clone();
     let access_point_removed_ref = connection.clone();
     let active_access_point_changed_ref = connection.clone();
+    let link_info_changed_ref = connection.clone();
     let device_ref = device.clone();
     let manager_ref = device.clone();
+    let wifi_devices = get_wifi_devices();
+    let access_point_owners = Arc::new(RwLock::new(get_access_point_owners(&wifi_devices)));
+    let owners_changed_ref = access_point_owners.clone();
+    let owners_added_ref = access_point_owners.clone();
+    let owners_removed_ref = access_point_owners.clone();
+    // state of the access points as last broadcast, clients may request their own threshold
+    let strength_filter = Arc::new(Mutex::new(StrengthFilter::new(strength_threshold())));
+    let filter_added_ref = strength_filter.clone();
+    let filter_removed_ref = strength_filter.clone();
     let conn = Connection::new_system().unwrap();
-    let access_point_added =
-        AccessPointAdded::match_rule(Some(&NETWORK_INTERFACE!().into()), Some(&path))
-            .static_clone();
-    let access_point_removed =
-        AccessPointRemoved::match_rule(Some(&NETWORK_INTERFACE!().into()), Some(&path))
-            .static_clone();
+    let mut access_point_added = AccessPointAdded::match_rule(
+        Some(&NETWORK_INTERFACE!().into()),
+        Some(&Path::from(NM_DEVICES_PATH!())),
+    )
+    .static_clone();
+    access_point_added.path_is_namespace = true;
+    let mut access_point_removed = AccessPointRemoved::match_rule(
+        Some(&NETWORK_INTERFACE!().into()),
+        Some(&Path::from(NM_DEVICES_PATH!())),
+    )
+    .static_clone();
+    access_point_removed.path_is_namespace = true;
     let mut access_point_changed = PropertiesPropertiesChanged::match_rule(
         Some(&NETWORK_INTERFACE!().into()),
         Some(&Path::from(NM_ACCESS_POINT_PATH!())),
@@ -110,20 +161,24 @@ pub fn start_listener(
             }
             let path = msg.path().unwrap().to_string();
             if path.contains(NM_ACCESS_POINT_PATH!()) {
-                let access_point = get_access_point_properties(Path::from(path));
+                let path = Path::from(path);
+                let owner = owners_changed_ref
+                    .read()
+                    .unwrap()
+                    .get(&path)
+                    .cloned()
+                    .unwrap_or_else(|| Path::from("/"));
+                let access_point = get_access_point_properties(path.clone());
+                record_access_point(&access_point);
+                let state = access_point_state(&access_point);
+                let broadcast = strength_filter.lock().unwrap().accept(&path, state.clone());
                 let msg = Message::signal(
                     &Path::from(DBUS_PATH!()),
                     &NETWORK_INTERFACE!().into(),
                     &"AccessPointChanged".into(),
                 )
-                .append1(access_point);
-                let res = connection.send(msg);
-                if let Err(_error) = res {
-                    ERROR!(
-                        format!("Could not send signal: {:?}", _error),
-                        ErrorLevel::PartialBreakage
-                    );
-                }
+                .append2(access_point, owner);
+                emit_access_point_changed(&connection, msg, path, state, broadcast);
             }
             true
         },
@@ -140,12 +195,25 @@ pub fn start_listener(
     }
     let res = conn.add_match(
         wifi_device_event,
-        move |ir: PropertiesPropertiesChanged, _, _| {
+        move |ir: PropertiesPropertiesChanged, _, msg| {
+            let bitrate: Option<&u32> = prop_cast(&ir.changed_properties, "Bitrate");
+            if bitrate.is_some() {
+                emit_link_info_changed(&link_info_changed_ref, msg.path().unwrap().into_static());
+            }
             let active_access_point: Option<&Path<'static>> =
                 prop_cast(&ir.changed_properties, "ActiveAccessPoint");
             if let Some(active_access_point) = active_access_point {
                 let active_access_point = active_access_point.clone();
-                if active_access_point != Path::from("/") {
+                let path = msg.path().unwrap().into_static();
+                if path != device_ref.read().unwrap().dbus_path {
+                    let msg = Message::signal(
+                        &Path::from(DBUS_PATH!()),
+                        &NETWORK_INTERFACE!().into(),
+                        &"WifiDeviceChanged".into(),
+                    )
+                    .append1(get_wifi_device(path, active_access_point));
+                    emit_signal(&active_access_point_changed_ref, msg);
+                } else if active_access_point != Path::from("/") {
                     let parsed_access_point = get_access_point_properties(active_access_point);
                     let mut device = device_ref.write().unwrap();
                     device.access_point = Some(parsed_access_point.clone());
@@ -159,13 +227,7 @@ pub fn start_listener(
                         name: device.name.clone(),
                         active_access_point: parsed_access_point.ssid,
                     });
-                    let res = active_access_point_changed_ref.send(msg);
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Could not send signal: {:?}", _error),
-                            ErrorLevel::PartialBreakage
-                        );
-                    }
+                    emit_signal(&active_access_point_changed_ref, msg);
                 } else {
                     let device = device_ref.write().unwrap();
                     let msg = Message::signal(
@@ -178,13 +240,7 @@ pub fn start_listener(
                         name: device.name.clone(),
                         active_access_point: Vec::new(),
                     });
-                    let res = active_access_point_changed_ref.send(msg);
-                    if let Err(_error) = res {
-                        ERROR!(
-                            format!("Could not send signal: {:?}", _error),
-                            ErrorLevel::PartialBreakage
-                        );
-                    }
+                    emit_signal(&active_access_point_changed_ref, msg);
                 }
             }
             true
@@ -231,20 +287,25 @@ pub fn start_listener(
             "Failed to match signal on NetworkManager.",
         ));
     }
-    let res = conn.add_match(access_point_added, move |ir: AccessPointAdded, _, _| {
+    let res = conn.add_match(access_point_added, move |ir: AccessPointAdded, _, msg| {
+        let owner = msg.path().unwrap().into_static();
+        owners_added_ref
+            .write()
+            .unwrap()
+            .insert(ir.access_point.clone(), owner.clone());
+        let access_point = get_access_point_properties(ir.access_point.clone());
+        record_access_point(&access_point);
+        filter_added_ref
+            .lock()
+            .unwrap()
+            .record(ir.access_point, access_point_state(&access_point));
         let msg = Message::signal(
             &Path::from(DBUS_PATH!()),
             &NETWORK_INTERFACE!().into(),
             &"AccessPointAdded".into(),
         )
-        .append1(get_access_point_properties(ir.access_point));
-        let res = access_point_added_ref.send(msg);
-        if let Err(_error) = res {
-            ERROR!(
-                format!("Could not send signal: {:?}", _error),
-                ErrorLevel::PartialBreakage
-            );
-        }
+        .append2(access_point, owner);
+        emit_signal(&access_point_added_ref, msg);
         true
     });
     if let Err(_error) = res {
@@ -253,22 +314,22 @@ pub fn start_listener(
             &format!("Failed to match signal on NetworkManager: {}", _error),
         ));
     }
-    let res = conn.add_match(access_point_removed, move |ir: AccessPointRemoved, _, _| {
-        let msg = Message::signal(
-            &Path::from(DBUS_PATH!()),
-            &NETWORK_INTERFACE!().into(),
-            &"AccessPointRemoved".into(),
-        )
-        .append1(ir.access_point);
-        let res = access_point_removed_ref.send(msg);
-        if let Err(_error) = res {
-            ERROR!(
-                format!("Could not send signal: {:?}", _error),
-                ErrorLevel::PartialBreakage
-            );
-        }
-        true
-    });
+    let res = conn.add_match(
+        access_point_removed,
+        move |ir: AccessPointRemoved, _, msg| {
+            let owner = msg.path().unwrap().into_static();
+            owners_removed_ref.write().unwrap().remove(&ir.access_point);
+            filter_removed_ref.lock().unwrap().forget(&ir.access_point);
+            let msg = Message::signal(
+                &Path::from(DBUS_PATH!()),
+                &NETWORK_INTERFACE!().into(),
+                &"AccessPointRemoved".into(),
+            )
+            .append2(ir.access_point, owner);
+            emit_signal(&access_point_removed_ref, msg);
+            true
+        },
+    );
     if let Err(_error) = res {
         ERROR!(
             format!("Signal Match on NetworkManager failed: {:?}", _error),
@@ -291,11 +352,77 @@ pub fn start_listener(
         // if time.elapsed().unwrap_or(Duration::from_millis(0)) < Duration::from_secs(10) {
         if time.elapsed().unwrap() < Duration::from_secs(10) {
             time = SystemTime::now();
-            device.read().unwrap().request_scan();
+            for wifi_device in wifi_devices.iter() {
+                let wifi_device = wifi_device.read().unwrap();
+                // scans would disturb the association of a connect
+                if let Some(_scan) = try_operation(&wifi_device.dbus_path, DeviceOperation::Scan) {
+                    wifi_device.request_scan();
+                }
+            }
         }
     }
 }
 
+/// Runs the listener as supervised worker, which is restarted after errors and panics.
+pub fn supervise_listener(
+    connection: Arc<SyncConnection>,
+    device: Arc<RwLock<Device>>,
+    active_listener: Arc<AtomicBool>,
+    stop_requested: Arc<AtomicBool>,
+) {
+    let mut restarted = false;
+    supervise_worker(WORKER_NETWORK, move || {
+        // the stop might have been requested while waiting for the restart
+        if restarted && stop_requested.swap(false, Ordering::SeqCst) {
+            active_listener.store(false, Ordering::SeqCst);
+            return Ok(());
+        }
+        restarted = true;
+        start_listener(
+            connection.clone(),
+            device.clone(),
+            active_listener.clone(),
+            stop_requested.clone(),
+        )
+        .map_err(|error| error.to_string())
+    });
+}
+
+/// Maps every access point to the path of the WiFi device that found it.
+fn get_access_point_owners(
+    devices: &[Arc<RwLock<Device>>],
+) -> HashMap<Path<'static>, Path<'static>> {
+    let mut owners = HashMap::new();
+    for device in devices {
+        let device = device.read().unwrap();
+        for access_point in device.get_access_point_paths() {
+            owners.insert(access_point, device.dbus_path.clone());
+        }
+    }
+    owners
+}
+
+fn get_wifi_device(path: Path<'static>, active_access_point: Path<'static>) -> WifiDevice {
+    let name = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        path.clone(),
+        NM_DEVICE_INTERFACE!(),
+        "Interface",
+        String,
+    )
+    .unwrap_or_default();
+    let active_access_point = if &*active_access_point == "/" {
+        Vec::new()
+    } else {
+        get_access_point_properties(active_access_point).ssid
+    };
+    WifiDevice {
+        path,
+        name,
+        active_access_point,
+    }
+}
+
 pub fn stop_listener(stop_requested: Arc<AtomicBool>) {
     stop_requested.store(true, Ordering::SeqCst);
 }
@@ -365,6 +492,7 @@ pub fn get_device_type(path: String) -> DeviceType {
     DeviceType::from_u32(res)
 }
 
+/// Returns the settings of a connection without secrets.
 pub fn get_connection_settings(path: Path<'static>) -> Result<MaskedPropMap, dbus::MethodErr> {
     let res = dbus_method!(
         NM_INTERFACE_BASE!(),
@@ -380,11 +508,20 @@ pub fn get_connection_settings(path: Path<'static>) -> Result<MaskedPropMap, dbu
             format!("Failed to receive settings from connection: {:?}", _error),
             ErrorLevel::PartialBreakage
         );
-        return Err(MethodErr::invalid_arg(
+        return Err(status_error(
+            StatusCode::ConnectionNotFound,
             "Could not get settings from connection",
         ));
     }
-    let mut map = res.unwrap().0;
+    Ok(res.unwrap().0)
+}
+
+/// Returns the settings of a connection including the WiFi secrets.\
+/// Required when updating a connection, as Update replaces the secrets as well.
+pub fn get_connection_settings_with_secrets(
+    path: Path<'static>,
+) -> Result<MaskedPropMap, dbus::MethodErr> {
+    let mut map = get_connection_settings(path.clone())?;
     let res = dbus_method!(
         NM_INTERFACE_BASE!(),
         path.clone(),
@@ -417,7 +554,7 @@ pub fn set_connection_settings(path: Path<'static>, settings: HashMap<String, Pr
         NM_CONNECTION_INTERFACE!(),
         (settings,),
         1000,
-        (HashMap<String, PropMap>,),
+        (),
     );
     if let Err(_error) = res {
         ERROR!(
@@ -429,12 +566,117 @@ pub fn set_connection_settings(path: Path<'static>, settings: HashMap<String, Pr
     true
 }
 
+/// Replaces the DNS servers and search domains of a connection, all other settings are kept.\
+/// IPv4 and IPv6 servers are split into their respective sections, search domains and
+/// ignore-auto-dns are applied to both.
+pub fn set_connection_dns(
+    path: Path<'static>,
+    servers: Vec<String>,
+    search_domains: Vec<String>,
+    ignore_auto_dns: bool,
+) -> Result<bool, MethodErr> {
+    let mut ipv4_servers = Vec::new();
+    let mut ipv6_servers = Vec::new();
+    for server in servers.iter() {
+        match server.parse::<IpAddr>() {
+            // NetworkManager expects the address in network byte order
+            Ok(IpAddr::V4(address)) => ipv4_servers.push(u32::from_ne_bytes(address.octets())),
+            Ok(IpAddr::V6(address)) => ipv6_servers.push(address.octets().to_vec()),
+            Err(_) => {
+                return Err(status_error(
+                    StatusCode::InvalidArgument,
+                    &format!("{} is not a valid IP address", server),
+                ))
+            }
+        }
+    }
+    let mut settings = get_connection_settings_with_secrets(path.clone())?;
+    if !settings.contains_key("ipv6") && !ipv6_servers.is_empty() {
+        let mut ipv6 = PropMap::new();
+        ipv6.insert(
+            String::from("method"),
+            Variant(Box::new(String::from("auto")) as Box<dyn RefArg>),
+        );
+        settings.insert(String::from("ipv6"), ipv6);
+    }
+    let ipv4 = settings.entry(String::from("ipv4")).or_default();
+    ipv4.insert(
+        String::from("dns"),
+        Variant(Box::new(ipv4_servers) as Box<dyn RefArg>),
+    );
+    set_dns_options(ipv4, &search_domains, ignore_auto_dns);
+    if let Some(ipv6) = settings.get_mut("ipv6") {
+        ipv6.insert(
+            String::from("dns"),
+            Variant(Box::new(ipv6_servers) as Box<dyn RefArg>),
+        );
+        set_dns_options(ipv6, &search_domains, ignore_auto_dns);
+    }
+    Ok(set_connection_settings(path, settings))
+}
+
+fn set_dns_options(section: &mut PropMap, search_domains: &[String], ignore_auto_dns: bool) {
+    section.insert(
+        String::from("dns-search"),
+        Variant(Box::new(search_domains.to_vec()) as Box<dyn RefArg>),
+    );
+    section.insert(
+        String::from("ignore-auto-dns"),
+        Variant(Box::new(ignore_auto_dns) as Box<dyn RefArg>),
+    );
+}
+
+/// Creates the WiFi QR code payload of the stored connection belonging to an access point.
+pub fn get_access_point_qr_payload(path: Path<'static>) -> Result<String, MethodErr> {
+    let access_point = get_access_point_properties(path);
+    if !access_point.stored {
+        return Err(status_error(
+            StatusCode::ConnectionNotFound,
+            "Access point does not have a stored connection",
+        ));
+    }
+    let settings = get_connection_settings_with_secrets(access_point.associated_connection)?;
+    let mut payload = WifiQrPayload {
+        ssid: String::from_utf8_lossy(&access_point.ssid).to_string(),
+        ..Default::default()
+    };
+    if let Some(wireless) = settings.get("802-11-wireless") {
+        payload.hidden = *prop_cast::<bool>(wireless, "hidden").unwrap_or(&false);
+    }
+    if let Some(security) = settings.get("802-11-wireless-security") {
+        let key_management: Option<&String> = prop_cast(security, "key-mgmt");
+        let Some(security_type) =
+            WifiQrSecurity::from_key_management(key_management.map_or("", |key| key.as_str()))
+        else {
+            return Err(status_error(
+                StatusCode::Unsupported,
+                "Unsupported security type for a QR code",
+            ));
+        };
+        payload.security = security_type;
+        let password: Option<&String> = match payload.security {
+            WifiQrSecurity::Wep => prop_cast(security, "wep-key0"),
+            _ => prop_cast(security, "psk"),
+        };
+        if let Some(password) = password {
+            payload.password.clone_from(password);
+        }
+    }
+    if payload.security != WifiQrSecurity::None && payload.password.is_empty() {
+        return Err(status_error(
+            StatusCode::SecretsUnavailable,
+            "Could not retrieve the secrets of this connection",
+        ));
+    }
+    Ok(payload.to_payload())
+}
+
 #[allow(dead_code)]
 pub fn set_password(path: Path<'static>, password: String) {
     // yes this will be encrypted later
     // TODO: encrypt
     let password = Box::new(password) as Box<dyn RefArg>;
-    let res = get_connection_settings(path.clone());
+    let res = get_connection_settings_with_secrets(path.clone());
     if let Err(_error) = res {
         ERROR!(
             format!("Failed to get settings for connection: {:?}", _error),
@@ -454,7 +696,7 @@ pub fn set_password(path: Path<'static>, password: String) {
         NM_CONNECTION_INTERFACE!(),
         (settings,),
         1000,
-        (HashMap<String, PropMap>,),
+        (),
     );
     res.unwrap();
 }
@@ -516,6 +758,57 @@ pub fn get_access_point_properties(path: Path<'static>) -> AccessPoint {
     }
 }
 
+pub fn get_bssid_properties(path: Path<'static>) -> BssidInfo {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        path.to_string(),
+        Duration::from_millis(1000),
+    );
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    let hw_address: String = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "HwAddress")
+        .unwrap_or_default();
+    let frequency: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Frequency")
+        .unwrap_or(0);
+    let strength: u8 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Strength")
+        .unwrap_or(0);
+    (path, hw_address, frequency, strength)
+}
+
+pub fn get_access_point_radio(path: Path<'static>) -> AccessPointRadio {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        path.to_string(),
+        Duration::from_millis(1000),
+    );
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    let frequency: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "Frequency")
+        .unwrap_or(0);
+    let max_bitrate: u32 = proxy
+        .get(NM_ACCESS_POINT_INTERFACE!(), "MaxBitrate")
+        .unwrap_or(0);
+    (
+        path,
+        frequency,
+        max_bitrate,
+        band_name(frequency).to_string(),
+    )
+}
+
+/// Whether ListAccessPoints represents an SSID with its strongest BSSID.
+pub fn prefer_strongest_bssid() -> bool {
+    PREFER_STRONGEST_BSSID.load(Ordering::SeqCst)
+}
+
+pub fn set_prefer_strongest_bssid(enabled: bool) {
+    PREFER_STRONGEST_BSSID.store(enabled, Ordering::SeqCst);
+}
+
 pub fn get_active_connections() -> Vec<Path<'static>> {
     get_dbus_property!(
         NM_INTERFACE_BASE!(),
@@ -557,6 +850,21 @@ pub fn get_associations_of_active_connection(
     (devices, access_point)
 }
 
+/// Starts or stops the network listener according to the WiFi state.
+pub fn set_wifi_listener_enabled(enabled: bool, data: &DaemonData) {
+    let active_listener = data.network_listener_active.clone();
+    let stop_requested = data.network_stop_requested.clone();
+    if enabled {
+        if !active_listener.load(Ordering::SeqCst) {
+            let device = data.current_n_device.clone();
+            let connection = data.connection.clone();
+            supervise_listener(connection, device, active_listener, stop_requested);
+        }
+    } else {
+        stop_listener(stop_requested);
+    }
+}
+
 pub fn set_wifi_enabled(enabled: bool, data: &mut DaemonData) -> bool {
     let res = set_dbus_property!(
         NM_INTERFACE_BASE!(),
@@ -578,12 +886,13 @@ pub fn set_wifi_enabled(enabled: bool, data: &mut DaemonData) -> bool {
             return false;
         }
         data.current_n_device = devices.last().unwrap().clone();
+        data.current_n_device_path = data.current_n_device.read().unwrap().dbus_path.clone();
         data.n_devices = devices;
     }
     true
 }
 
-pub fn get_stored_connections() -> Vec<(Path<'static>, Vec<u8>)> {
+fn list_connections() -> Vec<Path<'static>> {
     let res = dbus_method!(
         NM_INTERFACE_BASE!(),
         Path::from(NM_SETTINGS_PATH!()),
@@ -600,7 +909,11 @@ pub fn get_stored_connections() -> Vec<(Path<'static>, Vec<u8>)> {
         );
         return Vec::new();
     }
-    let (result,) = res.ok().unwrap();
+    res.unwrap().0
+}
+
+pub fn get_stored_connections() -> Vec<(Path<'static>, Vec<u8>)> {
+    let result = list_connections();
     let mut wifi_connections = Vec::new();
     for connection in result {
         let result = get_connection_settings(connection.clone());
@@ -623,7 +936,64 @@ pub fn get_stored_connections() -> Vec<(Path<'static>, Vec<u8>)> {
     wifi_connections
 }
 
-pub fn disconnect_from_access_point(connection: Path<'static>) -> Result<(), ConnectionError> {
+/// Sets connection.autoconnect-priority of a stored connection, connections with a higher
+/// priority are preferred when autoconnecting.
+pub fn set_connection_autoconnect_priority(
+    path: Path<'static>,
+    priority: i32,
+) -> Result<bool, MethodErr> {
+    if !(MIN_AUTOCONNECT_PRIORITY..=MAX_AUTOCONNECT_PRIORITY).contains(&priority) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            &format!(
+                "Priority has to be between {} and {}",
+                MIN_AUTOCONNECT_PRIORITY, MAX_AUTOCONNECT_PRIORITY
+            ),
+        ));
+    }
+    let mut settings = get_connection_settings_with_secrets(path.clone())?;
+    settings
+        .entry(String::from("connection"))
+        .or_default()
+        .insert(
+            String::from("autoconnect-priority"),
+            Variant(Box::new(priority) as Box<dyn RefArg>),
+        );
+    Ok(set_connection_settings(path, settings))
+}
+
+/// Returns the stored WiFi connections in the order NetworkManager tries them when
+/// autoconnecting.\
+/// Connections are sorted by priority, then by the time they were last used. Connections with
+/// autoconnect disabled are never tried and therefore come last.
+pub fn get_autoconnect_order() -> Vec<AutoconnectEntry> {
+    let mut entries: Vec<(AutoconnectEntry, u64)> = list_connections()
+        .into_iter()
+        .filter_map(|path| {
+            let settings = get_connection_settings(path.clone()).ok()?;
+            let ssid = arg::prop_cast::<Vec<u8>>(settings.get("802-11-wireless")?, "ssid")
+                .cloned()
+                .unwrap_or_default();
+            let connection = settings.get("connection")?;
+            let priority = arg::prop_cast::<i32>(connection, "autoconnect-priority")
+                .copied()
+                .unwrap_or(0);
+            let autoconnect = arg::prop_cast::<bool>(connection, "autoconnect")
+                .copied()
+                .unwrap_or(true);
+            let timestamp = arg::prop_cast::<u64>(connection, "timestamp")
+                .copied()
+                .unwrap_or(0);
+            Some(((path, ssid, priority, autoconnect), timestamp))
+        })
+        .collect();
+    entries.sort_by_key(|((_, _, priority, autoconnect), timestamp)| {
+        (!autoconnect, Reverse(*priority), Reverse(*timestamp))
+    });
+    entries.into_iter().map(|(entry, _)| entry).collect()
+}
+
+pub fn disconnect_from_access_point(connection: Path<'static>) -> Result<(), StatusError> {
     let res = dbus_method!(
         NM_INTERFACE_BASE!(),
         Path::from(NM_PATH!()),
@@ -638,13 +1008,34 @@ pub fn disconnect_from_access_point(connection: Path<'static>) -> Result<(), Con
             format!("Failed to disconnect from connection: {}", _error),
             ErrorLevel::Recoverable
         );
-        return Err(ConnectionError {
-            method: "disconnect from",
-        });
+        return Err(StatusError::new(
+            StatusCode::Failed,
+            "Failed to disconnect from the access point",
+        ));
     }
     Ok(())
 }
 
+/// Emits the result of a connection attempt as WifiConnectResult, the code is Ok on success.
+pub fn emit_wifi_connect_result(
+    conn: &Arc<SyncConnection>,
+    access_point: Path<'static>,
+    result: &Result<(), StatusError>,
+) {
+    let (success, code, detail) = match result {
+        Ok(()) => (true, StatusCode::Ok, String::new()),
+        Err(error) => (false, error.code, error.detail.clone()),
+    };
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"WifiConnectResult".into(),
+    )
+    .append3(access_point, success, code.name())
+    .append1(detail);
+    emit_signal(conn, msg);
+}
+
 impl Device {
     pub fn initialize(&mut self) {
         let connections = get_active_connections();
@@ -676,7 +1067,7 @@ impl Device {
         }
     }
 
-    pub fn get_access_points(&self) -> Vec<AccessPoint> {
+    fn get_access_point_paths(&self) -> Vec<Path<'static>> {
         let res = dbus_method!(
             NM_INTERFACE_BASE!(),
             self.dbus_path.clone(),
@@ -696,47 +1087,108 @@ impl Device {
             );
             return Vec::new();
         }
-        let (res,) = res.unwrap();
+        res.unwrap().0
+    }
+
+    /// Returns one access point per SSID.\
+    /// With PreferStrongestBSSID the strongest BSSID represents the SSID, otherwise the first one
+    /// found. The connected access point is always kept.
+    pub fn get_access_points(&self) -> Vec<AccessPoint> {
+        let res = self.get_access_point_paths();
+        let prefer_strongest = prefer_strongest_bssid();
         let access_points = Arc::new(RwLock::new(Vec::new()));
         let known_points = Arc::new(RwLock::new(HashMap::new()));
+        let mut connected_ssid = None;
         if self.access_point.is_some() {
             let connected_access_point = self.access_point.clone().unwrap();
+            connected_ssid = Some(connected_access_point.ssid.clone());
             known_points
                 .write()
                 .unwrap()
                 .insert(connected_access_point.ssid.clone(), 0);
             access_points.write().unwrap().push(connected_access_point);
         }
+        let connected_ssid = Arc::new(connected_ssid);
 
         let mut threads = Vec::new();
         for label in res {
             let known_points_ref = known_points.clone();
             let access_points_ref = access_points.clone();
+            let connected_ssid_ref = connected_ssid.clone();
             threads.push(thread::spawn(move || {
                 let access_point = get_access_point_properties(label);
-                if known_points_ref
-                    .read()
-                    .unwrap()
-                    .contains_key(&access_point.ssid)
-                {
+                let mut known_points = known_points_ref.write().unwrap();
+                let mut access_points = access_points_ref.write().unwrap();
+                if let Some(index) = known_points.get(&access_point.ssid) {
+                    let connected =
+                        connected_ssid_ref.as_ref().as_ref() == Some(&access_point.ssid);
+                    let replace = prefer_strongest
+                        && !connected
+                        && access_point.strength > access_points[*index].strength;
+                    if replace {
+                        access_points[*index] = access_point;
+                    }
                     return;
                 }
-                known_points_ref
-                    .write()
-                    .unwrap()
-                    .insert(access_point.ssid.clone(), 0);
-                access_points_ref.write().unwrap().push(access_point);
+                known_points.insert(access_point.ssid.clone(), access_points.len());
+                access_points.push(access_point);
             }));
         }
         for thread in threads {
             thread.join().expect("Could not spawn thread");
         }
+        for access_point in access_points.read().unwrap().iter() {
+            record_access_point(access_point);
+        }
         Arc::try_unwrap(access_points)
             .unwrap()
             .into_inner()
             .unwrap()
     }
 
+    /// Returns one entry per SSID containing every BSSID, sorted from strongest to weakest.
+    pub fn get_access_point_groups(&self) -> Vec<AccessPointGroup> {
+        let threads: Vec<_> = self
+            .get_access_point_paths()
+            .into_iter()
+            .map(|path| {
+                thread::spawn(move || {
+                    let bssid = get_bssid_properties(path.clone());
+                    (get_access_point_properties(path), bssid)
+                })
+            })
+            .collect();
+        let mut groups: Vec<AccessPointGroup> = Vec::new();
+        for thread in threads {
+            let (access_point, bssid) = thread.join().expect("Could not spawn thread");
+            match groups.iter_mut().find(|group| group.0 == access_point.ssid) {
+                Some(group) => group.3.push(bssid),
+                None => groups.push((
+                    access_point.ssid,
+                    access_point.stored,
+                    access_point.associated_connection,
+                    vec![bssid],
+                )),
+            }
+        }
+        for group in groups.iter_mut() {
+            group.3.sort_by_key(|bssid| Reverse(bssid.3));
+        }
+        groups
+    }
+
+    pub fn get_access_point_radios(&self) -> Vec<AccessPointRadio> {
+        let threads: Vec<_> = self
+            .get_access_point_paths()
+            .into_iter()
+            .map(|path| thread::spawn(move || get_access_point_radio(path)))
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().expect("Could not spawn thread"))
+            .collect()
+    }
+
     #[allow(dead_code)]
     pub fn set_active_access_point(&mut self) {
         if self.dbus_path.is_empty() {
@@ -756,16 +1208,18 @@ impl Device {
 
     pub fn connect_to_access_point(
         &mut self,
+        conn: &Arc<SyncConnection>,
         access_point: AccessPoint,
-    ) -> Result<(), ConnectionError> {
+    ) -> Result<(), StatusError> {
         if self.dbus_path.is_empty() {
             ERROR!(
                 "Tried to connect to access point with invalid device: {:?}",
                 ErrorLevel::PartialBreakage
             );
-            return Err(ConnectionError {
-                method: "WifiDevice is not valid",
-            });
+            return Err(StatusError::new(
+                StatusCode::DeviceNotFound,
+                "The WiFi device is not valid",
+            ));
         }
         let res = dbus_method!(
             NM_INTERFACE_BASE!(),
@@ -785,41 +1239,13 @@ impl Device {
                 format!("Failed to activate connection: {:?}", _error),
                 ErrorLevel::Recoverable
             );
-            return Err(ConnectionError {
-                method: "connect to",
-            });
-        }
-        let result = res.unwrap();
-        let mut res_number = 1;
-        while res_number == 1 {
-            let path = result.0.clone();
-            let checked_result = get_dbus_property!(
-                NM_INTERFACE_BASE!(),
-                path.clone(),
-                NM_ACTIVE_CONNECTION_INTERFACE!(),
-                "State",
-                u32,
-            );
-            if let Err(_error) = checked_result {
-                ERROR!(
-                    format!("Failed to get status of WiFi: {:?}", _error),
-                    ErrorLevel::PartialBreakage
-                );
-                return Err(ConnectionError {
-                    method: "Failed to receive WiFi status",
-                });
-            }
-            res_number = checked_result.unwrap();
-        }
-        if res_number != 2 {
-            LOG!(format!(
-                "Wrong password entered for connection: {}.",
-                result.0
+            return Err(StatusError::new(
+                StatusCode::ConnectionFailed,
+                "Failed to connect to the access point",
             ));
-            return Err(ConnectionError {
-                method: "Password was wrong",
-            });
         }
+        let result = res.unwrap();
+        wait_for_activation(conn, &self.dbus_path, &result.0)?;
         let connection = get_associations_of_active_connection(result.0.clone());
         self.connection = Some(result.0);
         self.access_point = connection.1;
@@ -829,17 +1255,19 @@ impl Device {
 
     pub fn add_and_connect_to_access_point(
         &mut self,
+        conn: &Arc<SyncConnection>,
         access_point: AccessPoint,
         password: String,
-    ) -> Result<(), ConnectionError> {
+    ) -> Result<(), StatusError> {
         if self.dbus_path.is_empty() {
             ERROR!(
                 "Tried to connect to access point with invalid device.",
                 ErrorLevel::PartialBreakage
             );
-            return Err(ConnectionError {
-                method: "WifiDevice is not valid",
-            });
+            return Err(StatusError::new(
+                StatusCode::DeviceNotFound,
+                "The WiFi device is not valid",
+            ));
         }
         let mut properties = HashMap::new();
         properties.insert("802-11-wireless-security".to_string(), PropMap::new());
@@ -863,44 +1291,110 @@ impl Device {
         );
         if let Ok(res) = res {
             let (path, connection) = res;
-            let mut res = 1;
-            while res == 1 {
-                let result = get_dbus_property!(
-                    NM_INTERFACE_BASE!(),
-                    connection.clone(),
-                    NM_ACTIVE_CONNECTION_INTERFACE!(),
-                    "State",
-                    u32,
-                );
-                if result.is_err() {
-                    LOG!(format!("Wrong password entered for connection: {}.", path));
-                    return Err(ConnectionError {
-                        method: "Password was wrong",
-                    });
-                }
-                res = result.unwrap();
-            }
-            if res != 2 {
-                LOG!(format!("Wrong password entered for connection: {}.", path));
-                return Err(ConnectionError {
-                    method: "Password was wrong",
-                });
-            }
+            wait_for_activation(conn, &self.dbus_path, &connection)?;
             (self.connection, self.access_point) =
                 (Some(connection), Some(get_access_point_properties(path)));
             return Ok(());
         }
         LOG!(format!("Failed to connect to {}.", access_point.dbus_path));
-        Err(ConnectionError {
-            method: "connect to",
-        })
+        Err(StatusError::new(
+            StatusCode::ConnectionFailed,
+            "Failed to connect to the access point",
+        ))
+    }
+
+    pub fn add_connection_from_qr_payload(
+        &mut self,
+        conn: &Arc<SyncConnection>,
+        payload: WifiQrPayload,
+    ) -> Result<(), StatusError> {
+        if self.dbus_path.is_empty() {
+            ERROR!(
+                "Tried to connect to access point with invalid device.",
+                ErrorLevel::PartialBreakage
+            );
+            return Err(StatusError::new(
+                StatusCode::DeviceNotFound,
+                "The WiFi device is not valid",
+            ));
+        }
+        let mut properties: HashMap<String, PropMap> = HashMap::new();
+        let mut connection = PropMap::new();
+        connection.insert(
+            "id".to_string(),
+            Variant(Box::new(payload.ssid.clone()) as Box<dyn RefArg>),
+        );
+        connection.insert(
+            "type".to_string(),
+            Variant(Box::new(String::from("802-11-wireless")) as Box<dyn RefArg>),
+        );
+        properties.insert("connection".to_string(), connection);
+        let mut wireless = PropMap::new();
+        wireless.insert(
+            "ssid".to_string(),
+            Variant(Box::new(payload.ssid.clone().into_bytes()) as Box<dyn RefArg>),
+        );
+        wireless.insert(
+            "hidden".to_string(),
+            Variant(Box::new(payload.hidden) as Box<dyn RefArg>),
+        );
+        properties.insert("802-11-wireless".to_string(), wireless);
+        if let Some(key_management) = payload.security.key_management() {
+            let password_key = if payload.security == WifiQrSecurity::Wep {
+                "wep-key0"
+            } else {
+                "psk"
+            };
+            let mut security = PropMap::new();
+            security.insert(
+                "key-mgmt".to_string(),
+                Variant(Box::new(key_management.to_string()) as Box<dyn RefArg>),
+            );
+            security.insert(
+                password_key.to_string(),
+                Variant(Box::new(payload.password) as Box<dyn RefArg>),
+            );
+            properties.insert("802-11-wireless-security".to_string(), security);
+        }
+        let res = dbus_method!(
+            NM_INTERFACE_BASE!(),
+            Path::from(NM_PATH!()),
+            "AddAndActivateConnection",
+            NM_INTERFACE!(),
+            (properties, self.dbus_path.clone(), Path::from("/")),
+            1000,
+            (Path<'static>, Path<'static>),
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to add connection from QR payload: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(StatusError::new(
+                StatusCode::ConnectionFailed,
+                "Failed to connect to the access point",
+            ));
+        }
+        let (_, active_connection) = res.unwrap();
+        if let Err(error) = wait_for_activation(conn, &self.dbus_path, &active_connection) {
+            LOG!(format!(
+                "Failed to connect to {} from QR payload.",
+                payload.ssid
+            ));
+            return Err(error);
+        }
+        self.connection = Some(active_connection);
+        self.set_active_access_point();
+        self.connected = true;
+        Ok(())
     }
 
-    pub fn disconnect_from_current(&mut self) -> Result<(), ConnectionError> {
+    pub fn disconnect_from_current(&mut self) -> Result<(), StatusError> {
         if self.dbus_path.is_empty() {
-            return Err(ConnectionError {
-                method: "WifiDevice is not valid",
-            });
+            return Err(StatusError::new(
+                StatusCode::DeviceNotFound,
+                "The WiFi device is not valid",
+            ));
         }
         let res = get_dbus_property!(
             NM_INTERFACE_BASE!(),
@@ -914,9 +1408,10 @@ impl Device {
                 format!("Tried to disconnect from access point: {:?}", _error),
                 ErrorLevel::Recoverable
             );
-            return Err(ConnectionError {
-                method: "disconnect from",
-            });
+            return Err(StatusError::new(
+                StatusCode::Failed,
+                "Failed to disconnect from the access point",
+            ));
         }
         for connection in res.unwrap() {
             let (devices, _) = get_associations_of_active_connection(connection.clone());
@@ -928,9 +1423,10 @@ impl Device {
                             format!("Tried to disconnect from access point: {:?}", _error),
                             ErrorLevel::Recoverable
                         );
-                        return Err(ConnectionError {
-                            method: "disconnect from",
-                        });
+                        return Err(StatusError::new(
+                            StatusCode::Failed,
+                            "Failed to disconnect from the access point",
+                        ));
                     }
                     self.connected = false;
                     self.access_point = None;
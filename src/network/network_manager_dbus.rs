@@ -1,22 +1,118 @@
-use std::{collections::HashMap, sync::atomic::Ordering, thread, time::Duration};
+use std::{collections::HashMap, sync::atomic::Ordering, time::Duration};
 
 use dbus::{arg::PropMap, blocking::Connection, Path};
 use dbus_crossroads::Crossroads;
 use re_set_lib::network::network_structures::{AccessPoint, WifiDevice};
 
-use crate::{utils::get_wifi_status, DaemonData};
+use crate::{
+    authorization::authorize,
+    introspection::{annotate_struct_fields, STRUCT_OUT0},
+    utils::{get_wifi_status, MaskedPropMap},
+    DaemonData,
+};
 
+use super::access_point_cache::get_cached_access_points;
+use super::address_family::get_connection_address_families;
+use super::bssid_blacklist::{blacklist_access_point, list_blacklisted_bssids};
+use super::captive_portal::mark_portal_resolved;
+use super::channel_advisor::get_channel_utilization;
+use super::connection_sharing::{disable_connection_sharing, enable_connection_sharing};
+use super::credentials::validate_wifi_credentials;
+use super::dns::get_dns_state;
+use super::hotspot_clients::{list_hotspot_clients, watch_hotspot_clients};
+use super::keyfile::{export_connection, import_connection};
+use super::link_info::{get_wifi_link_info, WifiLinkInfo};
+use super::mac_randomization::{
+    get_wifi_mac_settings, set_cloned_mac_address, set_scan_mac_randomization,
+};
 use super::network_manager::{
-    get_connection_settings, get_stored_connections, get_wifi_devices, set_connection_settings,
-    set_wifi_enabled, start_listener, stop_listener,
+    emit_wifi_connect_result, get_access_point_qr_payload, get_autoconnect_order,
+    get_connection_settings, get_stored_connections, get_wifi_devices, prefer_strongest_bssid,
+    set_connection_autoconnect_priority, set_connection_dns, set_connection_settings,
+    set_prefer_strongest_bssid, set_wifi_enabled, set_wifi_listener_enabled, stop_listener,
+    supervise_listener,
+};
+use super::operation_queue::{
+    get_operation_queue, queue_operation, DeviceOperation, QueuedOperation,
 };
+use super::powersave::{get_wifi_powersave, set_wifi_powersave};
+use super::priority_learning::{
+    clear_learned_locations, list_learned_locations, record_manual_connection,
+};
+use super::radio::get_radio_states;
+use super::retry_policy::set_connection_retry_policy;
+use super::roaming::reassociate_to_strongest_bssid;
+use super::secret_agent::{get_connection_secrets, provide_secrets, SecretsRequested};
+use super::wake_on_lan::{get_wake_on_lan, send_magic_packet, set_wake_on_lan};
+use super::wifi_qr::WifiQrPayload;
+use crate::status::{status_error, StatusCode};
 
 pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
     let token = cross.register(NETWORK_INTERFACE!(), |c| {
-        c.signal::<(AccessPoint,), _>("AccessPointChanged", ("access_point",));
-        c.signal::<(AccessPoint,), _>("AccessPointAdded", ("access_point",));
-        c.signal::<(Path<'static>,), _>("AccessPointRemoved", ("path",));
-        c.signal::<(WifiDevice,), _>("WifiDeviceChanged", ("device",));
+        annotate_struct_fields(c, &["AccessPoint", "WifiDevice"]);
+        c.signal::<(AccessPoint, Path<'static>), _>(
+            "AccessPointChanged",
+            ("access_point", "device"),
+        )
+        .annotate(STRUCT_OUT0, "AccessPoint");
+        c.signal::<(AccessPoint, Path<'static>), _>("AccessPointAdded", ("access_point", "device"))
+            .annotate(STRUCT_OUT0, "AccessPoint");
+        c.signal::<(Path<'static>, Path<'static>), _>("AccessPointRemoved", ("path", "device"));
+        c.signal::<(Path<'static>,), _>("AutoconnectSuspended", ("path",));
+        c.signal::<(Path<'static>, WifiLinkInfo), _>("WifiLinkInfoChanged", ("device", "info"));
+        c.signal::<(Path<'static>, String, String), _>(
+            "RoamingProgress",
+            ("device", "stage", "bssid"),
+        );
+        c.signal::<(String,), _>("CaptivePortalDetected", ("url",));
+        c.signal::<(Path<'static>, String, String, String, u64), _>(
+            "HotspotClientConnected",
+            (
+                "device",
+                "mac_address",
+                "ip_address",
+                "hostname",
+                "connected_since",
+            ),
+        );
+        c.signal::<(Path<'static>, String), _>(
+            "HotspotClientDisconnected",
+            ("device", "mac_address"),
+        );
+        c.signal::<(Path<'static>, bool, String, String), _>(
+            "WifiConnectResult",
+            ("access_point", "success", "code", "detail"),
+        );
+        c.signal::<(Path<'static>, String, String), _>(
+            "ConnectionStateChanged",
+            ("device", "state", "reason"),
+        );
+        c.signal::<SecretsRequested, _>(
+            "SecretsRequested",
+            (
+                "id",
+                "connection_path",
+                "connection_id",
+                "setting",
+                "hints",
+                "flags",
+            ),
+        );
+        c.signal::<(u32,), _>("SecretsRequestCanceled", ("id",));
+        c.signal::<(bool, bool, bool), _>(
+            "RadioStateChanged",
+            (
+                "wireless_enabled",
+                "wireless_hardware_enabled",
+                "wwan_enabled",
+            ),
+        );
+        c.signal::<(Path<'static>, Vec<QueuedOperation>), _>(
+            "OperationQueueChanged",
+            ("device", "operations"),
+        );
+        c.signal::<(WifiDevice,), _>("WifiDeviceChanged", ("device",))
+            .annotate(STRUCT_OUT0, "WifiDevice");
         c.method_with_cr_async(
             "ListAccessPoints",
             (),
@@ -29,36 +125,188 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                     ctx.reply(Ok((access_points,)))
                 }
             }
+        )
+        .annotate(STRUCT_OUT0, "AccessPoint");
+        c.method(
+            "GetCachedAccessPoints",
+            (),
+            ("access_points",),
+            move |_, _, ()| Ok((get_cached_access_points(),)),
+        );
+        c.method_with_cr_async(
+            "ListAccessPointGroups",
+            (),
+            ("groups",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let device = data.current_n_device.clone();
+                async move {
+                    let groups = device.read().unwrap().get_access_point_groups();
+                    ctx.reply(Ok((groups,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ListAccessPointRadios",
+            (),
+            ("radios",),
+            move |mut ctx, cross, ()| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let device = data.current_n_device.clone();
+                async move {
+                    let radios = device.read().unwrap().get_access_point_radios();
+                    ctx.reply(Ok((radios,)))
+                }
+            },
+        );
+        c.method(
+            "SetPreferStrongestBSSID",
+            ("enabled",),
+            (),
+            move |_, _, (enabled,): (bool,)| {
+                set_prefer_strongest_bssid(enabled);
+                Ok(())
+            },
+        );
+        c.method(
+            "GetPreferStrongestBSSID",
+            (),
+            ("enabled",),
+            move |_, _, ()| Ok((prefer_strongest_bssid(),)),
         );
         c.method_with_cr_async("GetWifiStatus", (), ("status",), move |mut ctx, _, ()| async move {
             ctx.reply(Ok((get_wifi_status(),)))
         });
+        c.method_with_cr_async(
+            "GetWifiLinkInfo",
+            ("device",),
+            ("info",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_wifi_link_info(device).map(|info| (info,)))
+            },
+        );
+        c.method_with_cr_async(
+            "GetConnectionAddressFamilies",
+            ("device",),
+            ("families",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_connection_address_families(device).map(|families| (families,)))
+            },
+        );
+        c.method_with_cr_async(
+            "GetChannelUtilization",
+            ("device",),
+            ("channels",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_channel_utilization(device).map(|channels| (channels,)))
+            },
+        );
+        c.method_with_cr_async(
+            "GetWakeOnLan",
+            ("device",),
+            ("mode",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_wake_on_lan(device).map(|mode| (mode,)))
+            },
+        );
+        c.method_with_cr_async(
+            "SetWakeOnLan",
+            ("device", "mode"),
+            ("result",),
+            move |mut ctx, _, (device, mode): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_wake_on_lan(device, &mode).map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetWifiPowersave",
+            ("device",),
+            ("mode",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(get_wifi_powersave(device).map(|mode| (mode,)))
+            },
+        );
+        c.method_with_cr_async(
+            "SetWifiPowersave",
+            ("device", "mode"),
+            ("result",),
+            move |mut ctx, _, (device, mode): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_wifi_powersave(device, &mode).map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetWifiMacSettings",
+            ("connection",),
+            ("cloned_mac_address", "scan_randomization"),
+            move |mut ctx, _, (connection,): (Path<'static>,)| async move {
+                ctx.reply(get_wifi_mac_settings(connection))
+            },
+        );
+        c.method_with_cr_async(
+            "SetWifiMacRandomization",
+            ("connection", "mode"),
+            ("result",),
+            move |mut ctx, _, (connection, mode): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(set_cloned_mac_address(connection, &mode).map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetWifiScanMacRandomization",
+            ("connection", "enabled"),
+            ("result",),
+            move |mut ctx, _, (connection, enabled): (Path<'static>, bool)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = set_scan_mac_randomization(connection, enabled);
+                    ctx.reply(res.map(|result| (result,)))
+                }
+            },
+        );
+        c.method(
+            "SendMagicPacket",
+            ("mac", "broadcast"),
+            ("result",),
+            move |_, _, (mac, broadcast): (String, String)| {
+                Ok((send_magic_packet(&mac, &broadcast)?,))
+            },
+        );
+        c.method_with_cr_async(
+            "GetRadioStates",
+            (),
+            (
+                "wireless_enabled",
+                "wireless_hardware_enabled",
+                "wwan_enabled",
+            ),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok(get_radio_states())) },
+        );
         // needs blocking
         c.method(
             "SetWifiEnabled",
             ("enabled",),
             ("result",),
             move |_, data: &mut DaemonData, (enabled,): (bool,)| {
-                let active_listener = data.network_listener_active.clone();
-                let stop_requested = data.network_stop_requested.clone();
-                if enabled {
-                    if !active_listener.load(Ordering::SeqCst) {
-                        let path = data.current_n_device.read().unwrap().dbus_path.clone();
-                        let device = data.current_n_device.clone();
-                        let connection = data.connection.clone();
-                        thread::spawn(move || {
-                            start_listener(
-                                connection,
-                                device,
-                                path,
-                                active_listener,
-                                stop_requested,
-                            )
-                        });
-                    }
-                } else {
-                    stop_listener(stop_requested);
-                }
+                set_wifi_listener_enabled(enabled, data);
                 Ok((set_wifi_enabled(enabled, data),))
             },
         );
@@ -88,7 +336,8 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                 },)))
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "WifiDevice");
         c.method_with_cr_async(
             "GetAllWifiDevices",
             (),
@@ -118,7 +367,8 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                 ctx.reply(Ok((devices,)))
                 }
             },
-        );
+        )
+        .annotate(STRUCT_OUT0, "WifiDevice");
         // needs blocking
         c.method(
             "SetWifiDevice",
@@ -136,6 +386,7 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                 if res {
                     d.n_devices.push(d.current_n_device.clone());
                     d.current_n_device = d.n_devices.remove(iter);
+                    d.current_n_device_path = d.current_n_device.read().unwrap().dbus_path.clone();
                 }
                 Ok((res,))
             },
@@ -147,11 +398,27 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
             move |mut ctx, cross, (access_point,): (AccessPoint,)| {
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let device = data.current_n_device.clone();
+                let device_path = data.current_n_device_path.clone();
+                let connection = data.connection.clone();
                 async move {
+                    let path = access_point.dbus_path.clone();
+                    let ssid = access_point.ssid.clone();
+                    let operation =
+                        queue_operation(&connection, &device_path, DeviceOperation::Connect, &path)
+                            .await;
+                    if let Err(error) = operation {
+                        return ctx.reply(Err(error));
+                    }
+                    let _operation = operation.unwrap();
                     let res = device
                         .write()
                         .unwrap()
-                        .connect_to_access_point(access_point);
+                        .connect_to_access_point(&connection, access_point);
+                    emit_wifi_connect_result(&connection, path, &res);
+                    if res.is_ok() {
+                        let device_path = device.read().unwrap().dbus_path.clone();
+                        record_manual_connection(&device_path, &ssid);
+                    }
                     ctx.reply(Ok((res.is_ok(),)))
                 }
             },
@@ -163,15 +430,88 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
             move |mut ctx, cross, (access_point, password): (AccessPoint, String)| {
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let device = data.current_n_device.clone();
+                let device_path = data.current_n_device_path.clone();
+                let connection = data.connection.clone();
                 async move {
-                    let res = device
-                        .write()
-                        .unwrap()
-                        .add_and_connect_to_access_point(access_point, password);
+                    let path = access_point.dbus_path.clone();
+                    let ssid = access_point.ssid.clone();
+                    let operation =
+                        queue_operation(&connection, &device_path, DeviceOperation::Connect, &path)
+                            .await;
+                    if let Err(error) = operation {
+                        return ctx.reply(Err(error));
+                    }
+                    let _operation = operation.unwrap();
+                    let res = device.write().unwrap().add_and_connect_to_access_point(
+                        &connection,
+                        access_point,
+                        password,
+                    );
+                    emit_wifi_connect_result(&connection, path, &res);
+                    if res.is_ok() {
+                        let device_path = device.read().unwrap().dbus_path.clone();
+                        record_manual_connection(&device_path, &ssid);
+                    }
                     ctx.reply(Ok((res.is_ok(),)))
                 }
             }
         );
+        c.method_with_cr_async(
+            "GetAccessPointQRPayload",
+            ("path",),
+            ("payload",),
+            move |mut ctx, _, (path,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    // the payload contains the password of the connection
+                    let res = get_access_point_qr_payload(path);
+                    ctx.reply(res.map(|payload| (payload,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "AddConnectionFromQRPayload",
+            ("payload",),
+            ("result",),
+            move |mut ctx, cross, (payload,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let device = data.current_n_device.clone();
+                let device_path = data.current_n_device_path.clone();
+                let connection = data.connection.clone();
+                async move {
+                    let payload = WifiQrPayload::parse(&payload);
+                    if let Err(error) = payload {
+                        return ctx.reply(Err(status_error(StatusCode::InvalidArgument, &error)));
+                    }
+                    let operation = queue_operation(
+                        &connection,
+                        &device_path,
+                        DeviceOperation::Connect,
+                        &payload.as_ref().unwrap().ssid,
+                    )
+                    .await;
+                    if let Err(error) = operation {
+                        return ctx.reply(Err(error));
+                    }
+                    let _operation = operation.unwrap();
+                    let mut device = device.write().unwrap();
+                    let res = device.add_connection_from_qr_payload(&connection, payload.unwrap());
+                    // the access point is only known once the connection is active
+                    let path = device
+                        .access_point
+                        .as_ref()
+                        .filter(|_| res.is_ok())
+                        .map(|access_point| access_point.dbus_path.clone())
+                        .unwrap_or_else(|| Path::from("/"));
+                    drop(device);
+                    emit_wifi_connect_result(&connection, path, &res);
+                    ctx.reply(Ok((res.is_ok(),)))
+                }
+            },
+        );
         c.method_with_cr_async(
             "DisconnectFromCurrentAccessPoint",
             (),
@@ -179,7 +519,16 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
             move |mut ctx, cross, ()| {
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
                 let device = data.current_n_device.clone();
+                let device_path = data.current_n_device_path.clone();
+                let connection = data.connection.clone();
                 async move {
+                    let operation =
+                        queue_operation(&connection, &device_path, DeviceOperation::Disconnect, "")
+                            .await;
+                    if let Err(error) = operation {
+                        return ctx.reply(Err(error));
+                    }
+                    let _operation = operation.unwrap();
                 let res = device
                     .write()
                     .unwrap()
@@ -188,6 +537,12 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                 }
             },
         );
+        c.method(
+            "GetOperationQueue",
+            ("device",),
+            ("operations",),
+            move |_, _, (device,): (Path<'static>,)| Ok((get_operation_queue(&device),)),
+        );
         c.method_with_cr_async("ListStoredConnections", (), ("result",), move |mut ctx, _, ()| async move {
             let res = get_stored_connections();
             ctx.reply(Ok((res,)))
@@ -197,11 +552,13 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
             ("path",),
             ("result",),
             move |mut ctx, _, (path,): (Path<'static>,)| async move {
+                // secrets are only handed out by the authorized GetConnectionSecrets
                 let res = get_connection_settings(path);
                 if res.is_err() {
-                    return ctx.reply(Err(dbus::MethodErr::invalid_arg(
+                    return ctx.reply(Err(status_error(
+                        StatusCode::ConnectionNotFound,
                         "Could not get settings for this connection.",
-                    ),));
+                    )));
                 }
                 ctx.reply(Ok((res.unwrap(),)))
             },
@@ -210,35 +567,288 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
             "SetConnectionSettings",
             ("path", "settings"),
             ("result",),
-            move |mut ctx, _, (path, settings): (Path<'static>, HashMap<String, PropMap>)| async move {
-                ctx.reply(Ok((set_connection_settings(path, settings),)))
+            move |mut ctx, _, (path, settings): (Path<'static>, HashMap<String, PropMap>)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((set_connection_settings(path, settings),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ExportConnection",
+            ("path", "include_secrets"),
+            ("keyfile",),
+            move |mut ctx, _, (path, include_secrets): (Path<'static>, bool)| {
+                let authorized = include_secrets.then(|| authorize(ctx.message()));
+                async move {
+                    if let Some(authorized) = authorized {
+                        if let Err(error) = authorized.await {
+                            return ctx.reply(Err(error));
+                        }
+                    }
+                    let keyfile = export_connection(path, include_secrets);
+                    ctx.reply(keyfile.map(|keyfile| (keyfile,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ImportConnection",
+            ("keyfile",),
+            ("path",),
+            move |mut ctx, _, (keyfile,): (String,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(import_connection(&keyfile).map(|path| (path,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetConnectionSecrets",
+            ("path", "setting"),
+            ("secrets",),
+            move |mut ctx, _, (path, setting): (Path<'static>, String)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let secrets = get_connection_secrets(path, &setting);
+                    ctx.reply(secrets.map(|secrets| (secrets,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ProvideSecrets",
+            ("id", "secrets"),
+            ("result",),
+            move |mut ctx, _, (id, secrets): (u32, MaskedPropMap)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((provide_secrets(id, secrets),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetConnectionDNS",
+            ("path", "servers", "search_domains", "ignore_auto_dns"),
+            ("result",),
+            move |mut ctx,
+                  _,
+                  (path, servers, search_domains, ignore_auto_dns): (
+                Path<'static>,
+                Vec<String>,
+                Vec<String>,
+                bool,
+            )| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let result = set_connection_dns(path, servers, search_domains, ignore_auto_dns);
+                    ctx.reply(result.map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetDNSState",
+            (),
+            ("mode", "rc_manager", "servers", "configurations"),
+            move |mut ctx, _, ()| async move { ctx.reply(get_dns_state()) },
+        );
+        c.method_with_cr_async(
+            "SetConnectionAutoconnectPriority",
+            ("path", "priority"),
+            ("result",),
+            move |mut ctx, _, (path, priority): (Path<'static>, i32)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let result = set_connection_autoconnect_priority(path, priority);
+                    ctx.reply(result.map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetAutoconnectOrder",
+            (),
+            ("connections",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((get_autoconnect_order(),))) },
+        );
+        c.method(
+            "GetLearnedAutoconnectPriorities",
+            (),
+            ("locations",),
+            move |_, _, ()| Ok((list_learned_locations(),)),
+        );
+        c.method_with_cr_async(
+            "ClearLearnedAutoconnectPriorities",
+            (),
+            ("result",),
+            move |mut ctx, _, ()| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((clear_learned_locations(),)))
+                }
             },
         );
         c.method_with_cr_async(
             "DeleteConnection",
             ("path",),
             ("result",),
-            move |mut ctx, _, (path,): (Path<'static>,)| async move {
-                let res = dbus_method!(
-                    NM_INTERFACE_BASE!(),
-                    path,
-                    "Delete",
-                    NM_SETTINGS_INTERFACE!(),
-                    (),
-                    1000,
-                    (),
-            );
-                let result = res.is_ok();
-                ctx.reply(Ok((result,)))
+            move |mut ctx, _, (path,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = dbus_method!(
+                        NM_INTERFACE_BASE!(),
+                        path,
+                        "Delete",
+                        NM_SETTINGS_INTERFACE!(),
+                        (),
+                        1000,
+                        (),
+                    );
+                    let result = res.is_ok();
+                    ctx.reply(Ok((result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetConnectionRetryPolicy",
+            ("path", "max_retries", "backoff"),
+            ("result",),
+            move |mut ctx, _, (path, max_retries, backoff): (Path<'static>, u32, u32)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    if max_retries > 0 && backoff == 0 {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::InvalidArgument,
+                            "Backoff has to be at least one second",
+                        )));
+                    }
+                    let result = set_connection_retry_policy(path, max_retries, backoff as u64);
+                    ctx.reply(Ok((result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "BlacklistAccessPoint",
+            ("bssid", "duration"),
+            ("result",),
+            move |mut ctx, _, (bssid, duration): (String, u32)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let result = blacklist_access_point(bssid, duration as u64);
+                    ctx.reply(result.map(|result| (result,)))
+                }
+            },
+        );
+        c.method("ListBlacklistedBSSIDs", (), ("bssids",), move |_, _, ()| {
+            Ok((list_blacklisted_bssids(),))
+        });
+        c.method(
+            "ValidateWifiCredentials",
+            ("security_flags", "password", "eap"),
+            ("valid", "reason"),
+            move |_, _, (security_flags, password, eap): (u32, String, PropMap)| {
+                let result = validate_wifi_credentials(security_flags, &password, &eap);
+                Ok((result.is_ok(), result.err().unwrap_or_default()))
+            },
+        );
+        c.method_with_cr_async(
+            "ReassociateToStrongestBSSID",
+            ("device",),
+            ("result",),
+            move |mut ctx, cross, (device,): (Path<'static>,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let result = reassociate_to_strongest_bssid(connection, device);
+                    ctx.reply(result.map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "EnableConnectionSharing",
+            ("device",),
+            ("result",),
+            move |mut ctx, cross, (device,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let result = enable_connection_sharing(device.clone());
+                    if let Ok(true) = result {
+                        watch_hotspot_clients(connection, device);
+                    }
+                    ctx.reply(result.map(|result| (result,)))
+                }
             },
         );
+        c.method_with_cr_async(
+            "DisableConnectionSharing",
+            ("device",),
+            ("result",),
+            move |mut ctx, _, (device,): (Path<'static>,)| {
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    ctx.reply(Ok((disable_connection_sharing(device),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "ListHotspotClients",
+            ("device",),
+            ("clients",),
+            move |mut ctx, _, (device,): (Path<'static>,)| async move {
+                ctx.reply(list_hotspot_clients(device).map(|clients| (clients,)))
+            },
+        );
+        c.method_with_cr_async(
+            "MarkPortalResolved",
+            (),
+            ("result",),
+            move |mut ctx, _, ()| async move { ctx.reply(Ok((mark_portal_resolved(),))) },
+        );
         c.method_with_cr_async(
             "StartNetworkListener",
             (),
             ("result",),
             move |mut ctx, cross, ()| {
                 let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
-                let path = data.current_n_device.read().unwrap().dbus_path.clone();
                 let active_listener = data.network_listener_active.clone();
                 let stop_requested = data.network_stop_requested.clone();
                 let device = data.current_n_device.clone();
@@ -250,18 +860,7 @@ pub fn setup_wireless_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceT
                     {
                         result = false;
                     } else {
-                        thread::spawn(move || {
-                            let res = start_listener(
-                                connection,
-                                device,
-                                path,
-                                active_listener,
-                                stop_requested,
-                            );
-                            if res.is_err() {
-                                println!("{}", res.err().unwrap());
-                            }
-                        });
+                        supervise_listener(connection, device, active_listener, stop_requested);
                     }
                 }
                 async move { ctx.reply(Ok((result,))) }
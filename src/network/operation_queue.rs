@@ -0,0 +1,209 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use dbus::{nonblock::SyncConnection, Message, MethodErr, Path};
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+
+use crate::signals::emit_signal;
+use crate::status::{status_error, StatusCode};
+
+/// Operation of a device as (operation, target), e.g. (connect, path of the access point).\
+/// The first operation of a queue is running, the others wait in order.
+pub type QueuedOperation = (String, String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceOperation {
+    Connect,
+    Disconnect,
+    Scan,
+}
+
+impl DeviceOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeviceOperation::Connect => "connect",
+            DeviceOperation::Disconnect => "disconnect",
+            DeviceOperation::Scan => "scan",
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_id: u64,
+    queues: HashMap<Path<'static>, VecDeque<(u64, DeviceOperation, String)>>,
+}
+
+/// Serializes the operations of each device, as e.g. two interleaved ActivateConnection calls
+/// of different clients leave the device in an undefined state.
+#[derive(Default)]
+pub struct OperationQueue {
+    state: Mutex<QueueState>,
+    finished: Notify,
+}
+
+impl OperationQueue {
+    /// Queues the operation and returns (id, position), position 0 runs immediately.\
+    /// Fails with Busy if an operation of the same kind is already queued for the device, e.g.
+    /// a second connect.
+    pub fn push(
+        &self,
+        device: &Path<'static>,
+        operation: DeviceOperation,
+        target: &str,
+    ) -> Result<(u64, usize), MethodErr> {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        let queue = state.queues.entry(device.clone()).or_default();
+        if queue.iter().any(|(_, queued, _)| *queued == operation) {
+            return Err(status_error(
+                StatusCode::Busy,
+                &format!("A {} is already queued for the device", operation.name()),
+            ));
+        }
+        queue.push_back((id, operation, target.to_string()));
+        Ok((id, queue.len() - 1))
+    }
+
+    /// Queues the operation only if nothing else is queued for the device.
+    pub fn try_push(
+        &self,
+        device: &Path<'static>,
+        operation: DeviceOperation,
+        target: &str,
+    ) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.queues.contains_key(device) {
+            return None;
+        }
+        state.next_id += 1;
+        let id = state.next_id;
+        state.queues.entry(device.clone()).or_default().push_back((
+            id,
+            operation,
+            target.to_string(),
+        ));
+        Some(id)
+    }
+
+    /// Waits until all operations queued before this one finished.
+    pub async fn wait(&self, device: &Path<'static>, id: u64) {
+        loop {
+            let finished = self.finished.notified();
+            tokio::pin!(finished);
+            // registers the waiter before checking, a finish in between would be missed otherwise
+            finished.as_mut().enable();
+            if !self.is_queued_behind(device, id) {
+                return;
+            }
+            finished.await;
+        }
+    }
+
+    fn is_queued_behind(&self, device: &Path<'static>, id: u64) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .queues
+            .get(device)
+            .and_then(|queue| queue.front())
+            .is_some_and(|(running, _, _)| *running != id)
+    }
+
+    pub fn finish(&self, device: &Path<'static>, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(queue) = state.queues.get_mut(device) {
+            queue.retain(|(queued, _, _)| *queued != id);
+            if queue.is_empty() {
+                state.queues.remove(device);
+            }
+        }
+        drop(state);
+        self.finished.notify_waiters();
+    }
+
+    pub fn list(&self, device: &Path<'static>) -> Vec<QueuedOperation> {
+        self.state
+            .lock()
+            .unwrap()
+            .queues
+            .get(device)
+            .map(|queue| {
+                queue
+                    .iter()
+                    .map(|(_, operation, target)| (operation.name().to_string(), target.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub static OPERATION_QUEUE: Lazy<OperationQueue> = Lazy::new(OperationQueue::default);
+
+/// Running operation, the next one of the device starts once this is dropped.
+pub struct OperationTicket {
+    conn: Option<Arc<SyncConnection>>,
+    device: Path<'static>,
+    id: u64,
+}
+
+impl Drop for OperationTicket {
+    fn drop(&mut self) {
+        OPERATION_QUEUE.finish(&self.device, self.id);
+        if let Some(conn) = &self.conn {
+            emit_operation_queue_changed(conn, &self.device);
+        }
+    }
+}
+
+/// Queues the operation and waits until it is the first of the device.\
+/// Clients are informed about their position with OperationQueueChanged.
+pub async fn queue_operation(
+    conn: &Arc<SyncConnection>,
+    device: &Path<'static>,
+    operation: DeviceOperation,
+    target: &str,
+) -> Result<OperationTicket, MethodErr> {
+    let (id, _) = OPERATION_QUEUE.push(device, operation, target)?;
+    emit_operation_queue_changed(conn, device);
+    // the ticket removes the operation again if the handler is dropped while waiting
+    let ticket = OperationTicket {
+        conn: Some(conn.clone()),
+        device: device.clone(),
+        id,
+    };
+    OPERATION_QUEUE.wait(device, id).await;
+    Ok(ticket)
+}
+
+/// Used for background operations like the periodic scans, which are skipped while clients
+/// operate on the device.
+pub fn try_operation(
+    device: &Path<'static>,
+    operation: DeviceOperation,
+) -> Option<OperationTicket> {
+    let id = OPERATION_QUEUE.try_push(device, operation, "")?;
+    Some(OperationTicket {
+        conn: None,
+        device: device.clone(),
+        id,
+    })
+}
+
+pub fn get_operation_queue(device: &Path<'static>) -> Vec<QueuedOperation> {
+    OPERATION_QUEUE.list(device)
+}
+
+fn emit_operation_queue_changed(conn: &Arc<SyncConnection>, device: &Path<'static>) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"OperationQueueChanged".into(),
+    )
+    .append2(device.clone(), OPERATION_QUEUE.list(device));
+    emit_signal(conn, msg);
+}
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, time::Duration};
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::Connection,
+    MethodErr, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::{
+    connection_sharing::{active_connection, get_device_property, settings_of, DEVICE_TYPE_WIFI},
+    network_manager::{get_connection_settings_with_secrets, set_connection_settings},
+};
+use crate::status::{status_error, StatusCode};
+
+const WIRELESS_SETTING: &str = "802-11-wireless";
+const POWERSAVE_SETTING: &str = "powersave";
+
+/// Values of NMSettingWirelessPowersave as (name, value).\
+/// default uses the global NetworkManager setting, ignore leaves the setting of the driver
+/// untouched.
+pub const POWERSAVE_MODES: &[(&str, u32)] =
+    &[("default", 0), ("ignore", 1), ("disable", 2), ("enable", 3)];
+
+pub fn parse_powersave_mode(mode: &str) -> Option<u32> {
+    POWERSAVE_MODES
+        .iter()
+        .find(|(name, _)| *name == mode)
+        .map(|(_, value)| *value)
+}
+
+pub fn powersave_mode_name(value: u32) -> &'static str {
+    POWERSAVE_MODES
+        .iter()
+        .find(|(_, mode)| *mode == value)
+        .map(|(name, _)| *name)
+        .unwrap_or("default")
+}
+
+/// Stored connection of a WiFi device, the active connection is preferred over the connections
+/// available to the device.\
+/// Returns whether the connection is active as well.
+fn wireless_connection(device: &Path<'static>) -> Result<(Path<'static>, bool), MethodErr> {
+    if get_device_property::<u32>(device, "DeviceType") != Some(DEVICE_TYPE_WIFI) {
+        return Err(status_error(
+            StatusCode::WrongDeviceType,
+            "The device is not a WiFi device",
+        ));
+    }
+    if let Some(connection) = active_connection(device).and_then(|active| settings_of(&active)) {
+        return Ok((connection, true));
+    }
+    get_device_property::<Vec<Path<'static>>>(device, "AvailableConnections")
+        .and_then(|connections| connections.into_iter().next())
+        .map(|connection| (connection, false))
+        .ok_or_else(|| {
+            status_error(
+                StatusCode::ConnectionNotFound,
+                "The device has no connection",
+            )
+        })
+}
+
+/// Returns the powersave mode of the connection of a WiFi device.
+pub fn get_wifi_powersave(device: Path<'static>) -> Result<String, MethodErr> {
+    let (connection, _) = wireless_connection(&device)?;
+    let settings = get_connection_settings_with_secrets(connection)?;
+    // NetworkManager omits the setting while it is set to default
+    let value = settings
+        .get(WIRELESS_SETTING)
+        .and_then(|wireless| wireless.get(POWERSAVE_SETTING))
+        .and_then(|value| value.0.as_u64())
+        .unwrap_or(0);
+    Ok(powersave_mode_name(value as u32).to_string())
+}
+
+/// Changes the powersave mode of the connection of a WiFi device, active connections are
+/// reapplied right away.
+pub fn set_wifi_powersave(device: Path<'static>, mode: &str) -> Result<bool, MethodErr> {
+    let Some(value) = parse_powersave_mode(mode) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown powersave mode",
+        ));
+    };
+    let (connection, active) = wireless_connection(&device)?;
+    let mut settings = get_connection_settings_with_secrets(connection.clone())?;
+    settings
+        .entry(String::from(WIRELESS_SETTING))
+        .or_default()
+        .insert(
+            String::from(POWERSAVE_SETTING),
+            Variant(Box::new(value) as Box<dyn RefArg>),
+        );
+    if !set_connection_settings(connection, settings) {
+        return Ok(false);
+    }
+    if active {
+        reapply(device);
+    }
+    Ok(true)
+}
+
+/// Reapplies the stored connection to the device without reconnecting, drivers that can't
+/// change the mode at runtime receive it with the next activation.
+fn reapply(device: Path<'static>) {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        "Reapply",
+        NM_DEVICE_INTERFACE!(),
+        (HashMap::<String, PropMap>::new(), 0u64, 0u32),
+        1000,
+        (),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not reapply connection of {}: {}", device, _error),
+            ErrorLevel::Recoverable
+        );
+        return;
+    }
+    LOG!(format!("Reapplied connection of {}", device));
+}
@@ -0,0 +1,286 @@
+use std::{sync::Mutex, thread, time::Duration};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::network_manager::{get_stored_connections, set_connection_autoconnect_priority};
+use crate::utils::{get_daemon_config_value, load_state_file, save_state_file};
+
+const LEARNING_FILE: &str = "autoconnect_learning.toml";
+
+/// Share of visible SSIDs two scans need to have in common to count as the same location.
+const LOCATION_THRESHOLD: f64 = 0.5;
+/// Learned priorities stay below this, so priorities set by hand above it still take precedence.
+const MAX_LEARNED_PRIORITY: i32 = 50;
+
+/// Location learned from manual connections as (visible SSIDs, connections as (SSID, count)).
+pub type LearnedLocation = (Vec<String>, Vec<(String, u32)>);
+
+/// Locations recognized by their visible SSIDs, connections are keyed by SSID.
+/// ```toml
+/// [[locations]]
+/// ssids = ["Home", "Neighbour", "Neighbour-5G"]
+///
+/// [locations.connections]
+/// Home = 12
+/// Hotspot = 2
+/// ```
+static LOCATIONS: Lazy<Mutex<Vec<LearnedLocation>>> = Lazy::new(|| Mutex::new(load_locations()));
+
+/// Location the priorities were last applied for, avoids rewriting connections after every scan.
+static APPLIED_LOCATION: Lazy<Mutex<Option<usize>>> = Lazy::new(|| Mutex::new(None));
+
+/// Learning is opt-in with `learn_autoconnect_priority = true` within the `[Daemon]` section of
+/// the config, as it changes the priority of stored connections.
+pub fn learning_enabled() -> bool {
+    get_daemon_config_value("learn_autoconnect_priority")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+fn load_locations() -> Vec<LearnedLocation> {
+    let table = load_state_file(LEARNING_FILE);
+    let Some(locations) = table.get("locations").and_then(|value| value.as_array()) else {
+        return Vec::new();
+    };
+    locations
+        .iter()
+        .filter_map(|location| {
+            let ssids = location
+                .get("ssids")?
+                .as_array()?
+                .iter()
+                .filter_map(|ssid| ssid.as_str().map(String::from))
+                .collect();
+            let connections = location
+                .get("connections")?
+                .as_table()?
+                .iter()
+                .filter_map(|(ssid, count)| Some((ssid.clone(), count.as_integer()? as u32)))
+                .collect();
+            Some((ssids, connections))
+        })
+        .collect()
+}
+
+fn save_locations(locations: &[LearnedLocation]) -> bool {
+    let locations = locations
+        .iter()
+        .map(|(ssids, connections)| {
+            let mut location = toml::Table::new();
+            location.insert(
+                String::from("ssids"),
+                toml::Value::Array(ssids.iter().cloned().map(toml::Value::String).collect()),
+            );
+            location.insert(
+                String::from("connections"),
+                toml::Value::Table(
+                    connections
+                        .iter()
+                        .map(|(ssid, count)| (ssid.clone(), toml::Value::Integer(*count as i64)))
+                        .collect(),
+                ),
+            );
+            toml::Value::Table(location)
+        })
+        .collect();
+    let mut table = toml::Table::new();
+    table.insert(String::from("locations"), toml::Value::Array(locations));
+    save_state_file(LEARNING_FILE, &table)
+}
+
+/// Share of SSIDs both scans have in common, 0 if both are empty.
+pub fn location_similarity(first: &[String], second: &[String]) -> f64 {
+    let common = first.iter().filter(|ssid| second.contains(ssid)).count();
+    let total = first.len() + second.len() - common;
+    if total == 0 {
+        return 0.0;
+    }
+    common as f64 / total as f64
+}
+
+/// Returns the index of the learned location most similar to the visible SSIDs.
+pub fn find_location(locations: &[LearnedLocation], visible: &[String]) -> Option<usize> {
+    locations
+        .iter()
+        .enumerate()
+        .map(|(index, (ssids, _))| (index, location_similarity(ssids, visible)))
+        .filter(|(_, similarity)| *similarity >= LOCATION_THRESHOLD)
+        .max_by(|(_, first), (_, second)| first.total_cmp(second))
+        .map(|(index, _)| index)
+}
+
+/// Priorities of the connections of a location, the most used connection gets the highest.
+pub fn learned_priorities(connections: &[(String, u32)]) -> Vec<(String, i32)> {
+    let mut sorted: Vec<&(String, u32)> = connections.iter().collect();
+    sorted.sort_by(|first, second| second.1.cmp(&first.1).then(first.0.cmp(&second.0)));
+    let highest = (sorted.len() as i32).min(MAX_LEARNED_PRIORITY);
+    sorted
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (ssid, _))| (ssid.clone(), (highest - rank as i32).max(0)))
+        .collect()
+}
+
+fn visible_ssids(device: &Path<'static>) -> Vec<String> {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        "GetAllAccessPoints",
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        (),
+        1000,
+        (Vec<Path<'static>>,),
+    );
+    if res.is_err() {
+        return Vec::new();
+    }
+    let mut ssids: Vec<String> = res
+        .unwrap()
+        .0
+        .into_iter()
+        .filter_map(|access_point| {
+            get_dbus_property!(
+                NM_INTERFACE_BASE!(),
+                access_point,
+                NM_ACCESS_POINT_INTERFACE!(),
+                "Ssid",
+                Vec<u8>,
+            )
+            .ok()
+        })
+        .filter(|ssid| !ssid.is_empty())
+        .map(|ssid| String::from_utf8_lossy(&ssid).to_string())
+        .collect();
+    ssids.sort();
+    ssids.dedup();
+    ssids
+}
+
+/// Records a connection made by the user and raises the priority of the connections used most
+/// often at the current location.
+pub fn record_manual_connection(device: &Path<'static>, ssid: &[u8]) {
+    if !learning_enabled() || ssid.is_empty() {
+        return;
+    }
+    let ssid = String::from_utf8_lossy(ssid).to_string();
+    let visible = visible_ssids(device);
+    let mut locations = LOCATIONS.lock().unwrap();
+    let index = match find_location(&locations, &visible) {
+        Some(index) => index,
+        None => {
+            locations.push((Vec::new(), Vec::new()));
+            locations.len() - 1
+        }
+    };
+    let (ssids, connections) = &mut locations[index];
+    // the latest scan describes the location best, networks come and go over time
+    *ssids = visible;
+    match connections.iter_mut().find(|(name, _)| *name == ssid) {
+        Some((_, count)) => *count += 1,
+        None => connections.push((ssid, 1)),
+    }
+    save_locations(&locations);
+    let priorities = learned_priorities(&locations[index].1);
+    drop(locations);
+    APPLIED_LOCATION.lock().unwrap().replace(index);
+    apply_priorities(priorities);
+}
+
+fn apply_priorities(priorities: Vec<(String, i32)>) {
+    let stored = get_stored_connections();
+    for (ssid, priority) in priorities {
+        let connection = stored
+            .iter()
+            .find(|(_, stored_ssid)| String::from_utf8_lossy(stored_ssid) == ssid);
+        if let Some((path, _)) = connection {
+            let _ = set_connection_autoconnect_priority(path.clone(), priority);
+        }
+    }
+}
+
+/// Applies the learned priorities once a scan shows that the device is at a known location.
+fn apply_location(device: &Path<'static>) {
+    let visible = visible_ssids(device);
+    let locations = LOCATIONS.lock().unwrap();
+    let index = find_location(&locations, &visible);
+    if index.is_none() {
+        return;
+    }
+    let index = index.unwrap();
+    let mut applied = APPLIED_LOCATION.lock().unwrap();
+    if *applied == Some(index) {
+        return;
+    }
+    applied.replace(index);
+    let priorities = learned_priorities(&locations[index].1);
+    drop(applied);
+    drop(locations);
+    LOG!(format!(
+        "Applying learned autoconnect priorities of location {}",
+        index
+    ));
+    apply_priorities(priorities);
+}
+
+/// Returns all learned locations.
+pub fn list_learned_locations() -> Vec<LearnedLocation> {
+    LOCATIONS.lock().unwrap().clone()
+}
+
+/// Forgets all learned locations, priorities that were already applied are kept.
+pub fn clear_learned_locations() -> bool {
+    let mut locations = LOCATIONS.lock().unwrap();
+    locations.clear();
+    APPLIED_LOCATION.lock().unwrap().take();
+    save_locations(&locations)
+}
+
+/// Applies the learned priorities whenever a WiFi device finished a scan.
+pub fn start_priority_learning_listener() {
+    if !learning_enabled() {
+        return;
+    }
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let mut device_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&NM_INTERFACE_BASE!().into()),
+            Some(&Path::from(NM_DEVICES_PATH!())),
+        )
+        .static_clone();
+        device_changed.path_is_namespace = true;
+        let res = conn.add_match(
+            device_changed,
+            move |ir: PropertiesPropertiesChanged, _, msg| {
+                let last_scan: Option<&i64> = prop_cast(&ir.changed_properties, "LastScan");
+                if last_scan.is_some() {
+                    if let Some(path) = msg.path() {
+                        apply_location(&path.into_static());
+                    }
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on NetworkManager {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on NetworkManager.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
@@ -0,0 +1,98 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    message::SignalArgs,
+    nonblock::SyncConnection,
+    Message, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::signals::emit_signal;
+
+const RADIO_PROPERTIES: [&str; 3] = ["WirelessEnabled", "WirelessHardwareEnabled", "WwanEnabled"];
+
+/// Radio states as (wireless enabled, wireless hardware enabled, wwan enabled).\
+/// The hardware state reflects the rfkill switch, which can't be changed by software.
+pub type RadioStates = (bool, bool, bool);
+
+pub fn get_radio_states() -> RadioStates {
+    (
+        get_radio_state("WirelessEnabled"),
+        get_radio_state("WirelessHardwareEnabled"),
+        get_radio_state("WwanEnabled"),
+    )
+}
+
+fn get_radio_state(property: &str) -> bool {
+    let res = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        NM_INTERFACE!(),
+        property,
+        bool,
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not get radio state {}: {}", property, _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    res.unwrap()
+}
+
+/// Emits RadioStateChanged with all states whenever one of them changes.
+pub fn start_radio_state_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let radio_changed = PropertiesPropertiesChanged::match_rule(
+            Some(&NM_INTERFACE_BASE!().into()),
+            Some(&Path::from(NM_PATH!())),
+        )
+        .static_clone();
+        let res = conn.add_match(
+            radio_changed,
+            move |ir: PropertiesPropertiesChanged, _, _| {
+                let changed = RADIO_PROPERTIES
+                    .iter()
+                    .any(|property| prop_cast::<bool>(&ir.changed_properties, property).is_some());
+                if changed {
+                    handle_radio_change(&connection);
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on NetworkManager {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on NetworkManager.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn handle_radio_change(conn: &Arc<SyncConnection>) {
+    let (wireless, wireless_hardware, wwan) = get_radio_states();
+    LOG!(format!(
+        "Radio states changed: wireless {}, wireless hardware {}, wwan {}",
+        wireless, wireless_hardware, wwan
+    ));
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"RadioStateChanged".into(),
+    )
+    .append3(wireless, wireless_hardware, wwan);
+    emit_signal(conn, msg);
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::{prop_cast, RefArg, Variant},
+    blocking::Connection,
+    message::MatchRule,
+    nonblock::SyncConnection,
+    Message, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::network_manager::{get_connection_settings_with_secrets, ConnectionStatusChanged};
+use crate::{
+    signals::emit_signal,
+    utils::{load_state_file, save_state_file},
+};
+
+const RETRY_POLICY_FILE: &str = "connection_retry_policies.toml";
+
+// NMActiveConnectionState and NMActiveConnectionStateReason
+const STATE_ACTIVATING: u32 = 1;
+const STATE_ACTIVATED: u32 = 2;
+const STATE_DEACTIVATED: u32 = 4;
+const REASON_USER_DISCONNECTED: u32 = 2;
+
+/// Retry policies of stored connections, keyed by the path of the connection.
+/// ```toml
+/// ["/org/freedesktop/NetworkManager/Settings/3"]
+/// max_retries = 3
+/// backoff = 300
+/// ```
+static RETRY_POLICIES: Lazy<Mutex<HashMap<Path<'static>, RetryPolicy>>> =
+    Lazy::new(|| Mutex::new(load_policies()));
+
+/// Activations which did not succeed yet, active connection -> stored connection.
+static PENDING_ACTIVATIONS: Lazy<Mutex<HashMap<Path<'static>, Path<'static>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    max_retries: u32,
+    backoff: u64,
+    failures: u32,
+    suspended: bool,
+}
+
+fn load_policies() -> HashMap<Path<'static>, RetryPolicy> {
+    let mut policies = HashMap::new();
+    for (path, entry) in load_state_file(RETRY_POLICY_FILE) {
+        let max_retries = entry
+            .get("max_retries")
+            .and_then(|value| value.as_integer());
+        let backoff = entry.get("backoff").and_then(|value| value.as_integer());
+        let path = Path::new(path);
+        if max_retries.is_none() || backoff.is_none() || path.is_err() {
+            continue;
+        }
+        policies.insert(
+            path.unwrap(),
+            RetryPolicy {
+                max_retries: max_retries.unwrap() as u32,
+                backoff: backoff.unwrap() as u64,
+                failures: 0,
+                suspended: false,
+            },
+        );
+    }
+    policies
+}
+
+fn save_policies(policies: &HashMap<Path<'static>, RetryPolicy>) -> bool {
+    let mut table = toml::Table::new();
+    for (path, policy) in policies.iter() {
+        let mut entry = toml::Table::new();
+        entry.insert(
+            String::from("max_retries"),
+            toml::Value::Integer(policy.max_retries as i64),
+        );
+        entry.insert(
+            String::from("backoff"),
+            toml::Value::Integer(policy.backoff as i64),
+        );
+        table.insert(path.to_string(), toml::Value::Table(entry));
+    }
+    save_state_file(RETRY_POLICY_FILE, &table)
+}
+
+/// Sets the retry policy of a stored connection, a max_retries of 0 removes the policy.\
+/// The backoff is provided in seconds.
+pub fn set_connection_retry_policy(path: Path<'static>, max_retries: u32, backoff: u64) -> bool {
+    let mut policies = RETRY_POLICIES.lock().unwrap();
+    if max_retries == 0 {
+        policies.remove(&path);
+    } else {
+        let policy = policies.entry(path).or_insert(RetryPolicy {
+            max_retries,
+            backoff,
+            failures: 0,
+            suspended: false,
+        });
+        policy.max_retries = max_retries;
+        policy.backoff = backoff;
+    }
+    save_policies(&policies)
+}
+
+/// Counts failed activations of connections with a retry policy.\
+/// Once the maximum is reached, autoconnect is disabled until the backoff has passed.
+pub fn start_retry_policy_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let state_changed =
+            MatchRule::new_signal(NM_ACTIVE_CONNECTION_INTERFACE!(), "StateChanged")
+                .with_sender(NM_INTERFACE_BASE!());
+        let res = conn.add_match(
+            state_changed,
+            move |signal: ConnectionStatusChanged, _, msg: &Message| {
+                if let Some(active_connection) = msg.path() {
+                    handle_state_change(
+                        &connection,
+                        active_connection.into_static(),
+                        signal.state,
+                        signal.reason,
+                    );
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on NetworkManager {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on NetworkManager.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+fn handle_state_change(
+    conn: &Arc<SyncConnection>,
+    active_connection: Path<'static>,
+    state: u32,
+    reason: u32,
+) {
+    match state {
+        STATE_ACTIVATING => {
+            // the stored connection is no longer available once the activation failed
+            let res = get_dbus_property!(
+                NM_INTERFACE_BASE!(),
+                active_connection.clone(),
+                NM_ACTIVE_CONNECTION_INTERFACE!(),
+                "Connection",
+                Path<'static>,
+            );
+            if let Ok(path) = res {
+                if RETRY_POLICIES.lock().unwrap().contains_key(&path) {
+                    PENDING_ACTIVATIONS
+                        .lock()
+                        .unwrap()
+                        .insert(active_connection, path);
+                }
+            }
+        }
+        STATE_ACTIVATED => {
+            let path = PENDING_ACTIVATIONS
+                .lock()
+                .unwrap()
+                .remove(&active_connection);
+            if path.is_none() {
+                return;
+            }
+            if let Some(policy) = RETRY_POLICIES.lock().unwrap().get_mut(&path.unwrap()) {
+                policy.failures = 0;
+            }
+        }
+        STATE_DEACTIVATED => {
+            let path = PENDING_ACTIVATIONS
+                .lock()
+                .unwrap()
+                .remove(&active_connection);
+            if path.is_none() || reason == REASON_USER_DISCONNECTED {
+                return;
+            }
+            handle_failure(conn, path.unwrap());
+        }
+        _ => (),
+    }
+}
+
+fn handle_failure(conn: &Arc<SyncConnection>, path: Path<'static>) {
+    let mut policies = RETRY_POLICIES.lock().unwrap();
+    let policy = policies.get_mut(&path);
+    if policy.is_none() {
+        return;
+    }
+    let policy = policy.unwrap();
+    policy.failures += 1;
+    if policy.suspended || policy.failures < policy.max_retries {
+        return;
+    }
+    if !set_autoconnect(&path, false) {
+        return;
+    }
+    policy.suspended = true;
+    let backoff = policy.backoff;
+    drop(policies);
+    LOG!(format!(
+        "Suspended autoconnect of {} for {} seconds",
+        path, backoff
+    ));
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"AutoconnectSuspended".into(),
+    )
+    .append1(path.clone());
+    emit_signal(conn, msg);
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(backoff));
+        set_autoconnect(&path, true);
+        if let Some(policy) = RETRY_POLICIES.lock().unwrap().get_mut(&path) {
+            policy.failures = 0;
+            policy.suspended = false;
+        }
+    });
+}
+
+/// Changes autoconnect without saving it to disk, a restart of NetworkManager or the daemon
+/// therefore never leaves a connection suspended.\
+/// Connections with autoconnect disabled by the user are left untouched.
+fn set_autoconnect(path: &Path<'static>, enabled: bool) -> bool {
+    let settings = get_connection_settings_with_secrets(path.clone());
+    if settings.is_err() {
+        return false;
+    }
+    let mut settings = settings.unwrap();
+    let connection = settings.get_mut("connection");
+    if connection.is_none() {
+        return false;
+    }
+    let connection = connection.unwrap();
+    let autoconnect = prop_cast::<bool>(connection, "autoconnect")
+        .copied()
+        .unwrap_or(true);
+    if autoconnect == enabled {
+        return false;
+    }
+    connection.insert(
+        String::from("autoconnect"),
+        Variant(Box::new(enabled) as Box<dyn RefArg>),
+    );
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        path.clone(),
+        "UpdateUnsaved",
+        NM_CONNECTION_INTERFACE!(),
+        (settings,),
+        1000,
+        (),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to change autoconnect of {}: {}", path, _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    true
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::{
+    bssid_blacklist::is_blacklisted,
+    network_manager::{
+        get_access_point_properties, get_bssid_properties, get_connection_settings_with_secrets,
+        set_connection_settings,
+    },
+};
+use crate::{
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::MaskedPropMap,
+};
+
+/// Candidates have to be at least this many percent stronger than the current BSSID, roaming
+/// between access points of similar strength would only interrupt the connection.
+const ROAMING_MARGIN: u8 = 10;
+const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(20);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// NM_DEVICE_STATE_ACTIVATED
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+
+/// Devices which are currently reassociating, a second request for the same device is rejected.
+static ROAMING_DEVICES: Lazy<Mutex<HashSet<Path<'static>>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Stages reported by RoamingProgress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoamingStage {
+    Scanning,
+    Reassociating,
+    Done,
+    AlreadyStrongest,
+    Failed,
+}
+
+impl RoamingStage {
+    fn name(self) -> &'static str {
+        match self {
+            RoamingStage::Scanning => "scanning",
+            RoamingStage::Reassociating => "reassociating",
+            RoamingStage::Done => "done",
+            RoamingStage::AlreadyStrongest => "already_strongest",
+            RoamingStage::Failed => "failed",
+        }
+    }
+}
+
+/// State of a WiFi device required for roaming.
+struct RoamingTarget {
+    device: Path<'static>,
+    connection: Path<'static>,
+    access_point: Path<'static>,
+    ssid: Vec<u8>,
+}
+
+/// Moves a connected WiFi device to the strongest BSSID of its current SSID.\
+/// The device scans, locks the connection to the strongest BSSID, reactivates the connection and
+/// removes the lock again once the device is activated. The lock is only kept in memory, a
+/// restart of NetworkManager therefore never leaves the connection locked.\
+/// Returns once the checks passed, the progress is emitted as RoamingProgress.
+pub fn reassociate_to_strongest_bssid(
+    conn: Arc<SyncConnection>,
+    device: Path<'static>,
+) -> Result<bool, MethodErr> {
+    let target = get_roaming_target(device.clone())?;
+    let settings = get_connection_settings_with_secrets(target.connection.clone())?;
+    let locked = settings
+        .get("802-11-wireless")
+        .and_then(|wireless| wireless.get("bssid"))
+        .and_then(|bssid| bssid.0.as_iter())
+        .is_some_and(|mut bytes| bytes.next().is_some());
+    if locked {
+        return Err(status_error(
+            StatusCode::Busy,
+            "The connection is locked to a BSSID, remove the lock before roaming",
+        ));
+    }
+    if !ROAMING_DEVICES.lock().unwrap().insert(device.clone()) {
+        return Err(status_error(
+            StatusCode::Busy,
+            "The device is already reassociating",
+        ));
+    }
+    thread::spawn(move || {
+        let stage = roam(&conn, &target, settings);
+        emit_roaming_progress(&conn, &target.device, stage, None);
+        ROAMING_DEVICES.lock().unwrap().remove(&target.device);
+    });
+    Ok(true)
+}
+
+fn get_roaming_target(device: Path<'static>) -> Result<RoamingTarget, MethodErr> {
+    let conn = dbus_connection!();
+    let proxy = conn.with_proxy(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        Duration::from_millis(1000),
+    );
+    let access_point: Path<'static> = proxy
+        .get(NM_DEVICE_WIRELESS_INTERFACE!(), "ActiveAccessPoint")
+        .map_err(|_| {
+            status_error(
+                StatusCode::WrongDeviceType,
+                "The device is not a WiFi device",
+            )
+        })?;
+    if &*access_point == "/" {
+        return Err(status_error(
+            StatusCode::NotConnected,
+            "The device is not connected",
+        ));
+    }
+    let active_connection: Path<'static> = proxy
+        .get(NM_DEVICE_INTERFACE!(), "ActiveConnection")
+        .map_err(|_| {
+            status_error(
+                StatusCode::NotConnected,
+                "The device has no active connection",
+            )
+        })?;
+    let connection = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        active_connection,
+        NM_ACTIVE_CONNECTION_INTERFACE!(),
+        "Connection",
+        Path<'static>,
+    )
+    .map_err(|_| {
+        status_error(
+            StatusCode::NotConnected,
+            "The device has no active connection",
+        )
+    })?;
+    let ssid = get_access_point_properties(access_point.clone()).ssid;
+    if ssid.is_empty() {
+        return Err(status_error(
+            StatusCode::Unsupported,
+            "Hidden networks can not be roamed",
+        ));
+    }
+    Ok(RoamingTarget {
+        device,
+        connection,
+        access_point,
+        ssid,
+    })
+}
+
+fn roam(
+    conn: &Arc<SyncConnection>,
+    target: &RoamingTarget,
+    mut settings: MaskedPropMap,
+) -> RoamingStage {
+    emit_roaming_progress(conn, &target.device, RoamingStage::Scanning, None);
+    scan(&target.device);
+    let Some((candidate, bssid)) = find_stronger_bssid(target) else {
+        return RoamingStage::AlreadyStrongest;
+    };
+    emit_roaming_progress(
+        conn,
+        &target.device,
+        RoamingStage::Reassociating,
+        Some(&bssid),
+    );
+    let Some(bssid_bytes) = bssid_to_bytes(&bssid) else {
+        return RoamingStage::Failed;
+    };
+    settings
+        .entry(String::from("802-11-wireless"))
+        .or_default()
+        .insert(
+            String::from("bssid"),
+            Variant(Box::new(bssid_bytes) as Box<dyn RefArg>),
+        );
+    if !update_unsaved(target.connection.clone(), &settings) {
+        return RoamingStage::Failed;
+    }
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        Path::from(NM_PATH!()),
+        "ActivateConnection",
+        NM_INTERFACE!(),
+        (target.connection.clone(), target.device.clone(), candidate),
+        1000,
+        (Path<'static>,),
+    );
+    let activated = res.is_ok() && wait_for_activation(&target.device);
+    // the lock is always removed, the connection would otherwise never roam again
+    if let Some(wireless) = settings.get_mut("802-11-wireless") {
+        wireless.remove("bssid");
+    }
+    if !set_connection_settings(target.connection.clone(), settings) {
+        ERROR!(
+            format!(
+                "Failed to remove the BSSID lock of {}, reload the connection to remove it",
+                target.connection
+            ),
+            ErrorLevel::Recoverable
+        );
+    }
+    if !activated {
+        return RoamingStage::Failed;
+    }
+    LOG!(format!("Reassociated {} to {}", target.device, bssid));
+    RoamingStage::Done
+}
+
+/// Requests a scan and waits until it finished or timed out.
+pub fn scan(device: &Path<'static>) {
+    let last_scan = || {
+        get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            device.clone(),
+            NM_DEVICE_WIRELESS_INTERFACE!(),
+            "LastScan",
+            i64,
+        )
+        .unwrap_or(0)
+    };
+    let previous_scan = last_scan();
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        device.clone(),
+        "RequestScan",
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        (PropMap::new(),),
+        1000,
+        (),
+    );
+    // NetworkManager rejects scans shortly after another one, the existing results are used then
+    if res.is_err() {
+        return;
+    }
+    let start = Instant::now();
+    while last_scan() == previous_scan && start.elapsed() < SCAN_TIMEOUT {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns the strongest BSSID of the SSID as (access point, bssid) if it is stronger than the
+/// current one by at least the roaming margin, blacklisted BSSIDs are skipped.
+fn find_stronger_bssid(target: &RoamingTarget) -> Option<(Path<'static>, String)> {
+    let (_, _, _, current_strength) = get_bssid_properties(target.access_point.clone());
+    let access_points = get_dbus_property!(
+        NM_INTERFACE_BASE!(),
+        target.device.clone(),
+        NM_DEVICE_WIRELESS_INTERFACE!(),
+        "AccessPoints",
+        Vec<Path<'static>>,
+    )
+    .ok()?;
+    access_points
+        .into_iter()
+        .filter(|path| *path != target.access_point)
+        .filter(|path| get_access_point_properties(path.clone()).ssid == target.ssid)
+        .map(get_bssid_properties)
+        .filter(|(_, bssid, _, _)| !bssid.is_empty() && !is_blacklisted(bssid))
+        .filter(|(_, _, _, strength)| *strength >= current_strength.saturating_add(ROAMING_MARGIN))
+        .max_by_key(|(_, _, _, strength)| *strength)
+        .map(|(path, bssid, _, _)| (path, bssid))
+}
+
+fn wait_for_activation(device: &Path<'static>) -> bool {
+    let start = Instant::now();
+    // give NetworkManager time to leave the activated state of the previous association
+    thread::sleep(POLL_INTERVAL);
+    while start.elapsed() < ACTIVATION_TIMEOUT {
+        let state = get_dbus_property!(
+            NM_INTERFACE_BASE!(),
+            device.clone(),
+            NM_DEVICE_INTERFACE!(),
+            "State",
+            u32,
+        );
+        if state.is_ok_and(|state| state == DEVICE_STATE_ACTIVATED) {
+            return true;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    false
+}
+
+/// Updates the connection without writing it to disk.
+fn update_unsaved(path: Path<'static>, settings: &MaskedPropMap) -> bool {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        path,
+        "UpdateUnsaved",
+        NM_CONNECTION_INTERFACE!(),
+        (settings,),
+        1000,
+        (),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to lock the connection to a BSSID: {:?}", _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    true
+}
+
+/// Converts a BSSID as reported by NetworkManager, e.g. AA:BB:CC:DD:EE:FF, to the bytes used in
+/// connection settings.
+pub fn bssid_to_bytes(bssid: &str) -> Option<Vec<u8>> {
+    let bytes: Vec<u8> = bssid
+        .split(':')
+        .map(|octet| u8::from_str_radix(octet, 16).ok())
+        .collect::<Option<_>>()?;
+    if bytes.len() != 6 {
+        return None;
+    }
+    Some(bytes)
+}
+
+fn emit_roaming_progress(
+    conn: &Arc<SyncConnection>,
+    device: &Path<'static>,
+    stage: RoamingStage,
+    bssid: Option<&str>,
+) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"RoamingProgress".into(),
+    )
+    .append3(device.clone(), stage.name(), bssid.unwrap_or_default());
+    emit_signal(conn, msg);
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::prop_cast,
+    blocking::{Connection, SyncConnection},
+    channel::{MatchingReceiver, Sender},
+    message::MatchRule,
+    nonblock, Message, MethodErr, Path,
+};
+use once_cell::sync::{Lazy, OnceCell};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    clients::ClientRegistry,
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{get_daemon_config_value, is_sent_by_owner, MaskedPropMap, BASE},
+};
+
+// NMSecretAgentGetSecretsFlags
+const ALLOW_INTERACTION: u32 = 0x1;
+
+const NO_SECRETS: &str = "org.freedesktop.NetworkManager.SecretAgent.NoSecrets";
+const USER_CANCELED: &str = "org.freedesktop.NetworkManager.SecretAgent.UserCanceled";
+const AGENT_CANCELED: &str = "org.freedesktop.NetworkManager.SecretAgent.AgentCanceled";
+
+/// Clients have this long to provide the secrets before NetworkManager asks the next agent.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Arguments of the SecretsRequested signal as (id, connection path, connection name, setting,
+/// hints, flags).
+pub type SecretsRequested = (u32, Path<'static>, String, String, Vec<String>, u32);
+
+static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Connection the agent is registered with, kept in order to register again after
+/// NetworkManager restarted.
+static AGENT: OnceCell<Arc<SyncConnection>> = OnceCell::new();
+
+/// Requests forwarded to clients, keyed by the id of the SecretsRequested signal.
+static PENDING_REQUESTS: Lazy<Mutex<HashMap<u32, SecretsRequest>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct SecretsRequest {
+    message: Message,
+    connection_path: Path<'static>,
+    setting_name: String,
+    agent: Arc<SyncConnection>,
+}
+
+impl SecretsRequest {
+    fn reply_error(&self, name: &'static str, message: &str) {
+        let _ = self
+            .agent
+            .send(MethodErr::from((name, message)).to_message(&self.message));
+    }
+}
+
+/// Registers the daemon as secret agent of NetworkManager, requests for agent-owned secrets are
+/// forwarded to registered clients with SecretsRequested and answered with ProvideSecrets.\
+/// Enabled with `secret_agent = true` within the `[Daemon]` section of the config.
+pub fn start_secret_agent(connection: Arc<nonblock::SyncConnection>, clients: Arc<ClientRegistry>) {
+    let enabled = get_daemon_config_value("secret_agent")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        // NetworkManager identifies agents by their unique name on the system bus
+        let agent = Arc::new(dbus_sync_connection!()?);
+        let agent_ref = agent.clone();
+        agent.start_receive(
+            MatchRule::new_method_call()
+                .with_path(NM_SECRET_AGENT_PATH!())
+                .with_interface(NM_SECRET_AGENT_INTERFACE!()),
+            Box::new(move |msg, _| {
+                handle_agent_call(&connection, &agent_ref, &clients, msg);
+                true
+            }),
+        );
+        let _ = AGENT.set(agent.clone());
+        if !register_secret_agent() {
+            return Err(dbus::Error::new_custom(
+                "RegistrationFailed",
+                "Failed to register secret agent.",
+            ));
+        }
+        loop {
+            agent.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+/// Registers the running agent with the AgentManager of NetworkManager.\
+/// Returns false if the agent is disabled or NetworkManager rejected it.
+pub fn register_secret_agent() -> bool {
+    let agent = AGENT.get();
+    if agent.is_none() {
+        return false;
+    }
+    let res: Result<(), dbus::Error> = agent
+        .unwrap()
+        .with_proxy(
+            NM_INTERFACE_BASE!(),
+            NM_AGENT_MANAGER_PATH!(),
+            Duration::from_millis(1000),
+        )
+        .method_call(NM_AGENT_MANAGER_INTERFACE!(), "Register", (BASE,));
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to register secret agent: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    LOG!("Registered secret agent");
+    true
+}
+
+fn handle_agent_call(
+    conn: &Arc<nonblock::SyncConnection>,
+    agent: &Arc<SyncConnection>,
+    clients: &Arc<ClientRegistry>,
+    msg: Message,
+) {
+    let member = msg
+        .member()
+        .map(|member| member.to_string())
+        .unwrap_or_default();
+    // every other peer on the system bus could otherwise ask the user for secrets in its name
+    if !is_sent_by_owner(agent, &msg, NM_INTERFACE_BASE!()) {
+        LOG!(format!(
+            "Rejected secret agent call {} from {:?}",
+            member,
+            msg.sender()
+        ));
+        let _ = agent.send(
+            MethodErr::from((
+                "org.freedesktop.DBus.Error.AccessDenied",
+                "Only NetworkManager may call the secret agent",
+            ))
+            .to_message(&msg),
+        );
+        return;
+    }
+    let reply = match member.as_str() {
+        "GetSecrets" => {
+            if clients.list().is_empty() {
+                // nobody could answer, NetworkManager should ask the next agent right away
+                MethodErr::from((NO_SECRETS, "No client is listening")).to_message(&msg)
+            } else {
+                request_secrets(conn, agent, msg);
+                return;
+            }
+        }
+        "CancelGetSecrets" => {
+            let (connection_path, setting_name): (Path<'static>, String) = match msg.read2() {
+                Ok(args) => args,
+                Err(error) => {
+                    let _ = agent.send(MethodErr::from(error).to_message(&msg));
+                    return;
+                }
+            };
+            cancel_request(conn, &connection_path, &setting_name);
+            msg.method_return()
+        }
+        // secrets are stored by the clients, e.g. within a keyring
+        "SaveSecrets" | "DeleteSecrets" => msg.method_return(),
+        _ => MethodErr::no_method(&member).to_message(&msg),
+    };
+    let _ = agent.send(reply);
+}
+
+fn request_secrets(
+    conn: &Arc<nonblock::SyncConnection>,
+    agent: &Arc<SyncConnection>,
+    msg: Message,
+) {
+    let res: Result<(MaskedPropMap, Path<'static>, String, Vec<String>, u32), _> = msg.read_all();
+    if let Err(error) = res {
+        let _ = agent.send(MethodErr::from(error).to_message(&msg));
+        return;
+    }
+    let (settings, connection_path, setting_name, hints, flags) = res.unwrap();
+    if flags & ALLOW_INTERACTION == 0 {
+        let _ =
+            agent.send(MethodErr::from((NO_SECRETS, "No client can be asked")).to_message(&msg));
+        return;
+    }
+    let connection_id = settings
+        .get("connection")
+        .and_then(|connection| prop_cast::<String>(connection, "id"))
+        .cloned()
+        .unwrap_or_default();
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    LOG!(format!(
+        "Requesting {} secrets of {} from clients",
+        setting_name, connection_id
+    ));
+    PENDING_REQUESTS.lock().unwrap().insert(
+        id,
+        SecretsRequest {
+            message: msg,
+            connection_path: connection_path.clone(),
+            setting_name: setting_name.clone(),
+            agent: agent.clone(),
+        },
+    );
+    let signal = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NETWORK_INTERFACE!().into(),
+        &"SecretsRequested".into(),
+    )
+    .append3(id, connection_path, connection_id)
+    .append3(setting_name, hints, flags);
+    emit_signal(conn, signal);
+    thread::spawn(move || {
+        thread::sleep(REQUEST_TIMEOUT);
+        if let Some(request) = PENDING_REQUESTS.lock().unwrap().remove(&id) {
+            request.reply_error(NO_SECRETS, "No client provided the secrets");
+        }
+    });
+}
+
+/// Answers a request of SecretsRequested, empty secrets cancel the request.\
+/// Returns false if the request does not exist, e.g. as it already timed out.
+pub fn provide_secrets(id: u32, secrets: MaskedPropMap) -> bool {
+    let request = PENDING_REQUESTS.lock().unwrap().remove(&id);
+    if request.is_none() {
+        return false;
+    }
+    let request = request.unwrap();
+    if secrets.is_empty() {
+        request.reply_error(USER_CANCELED, "The request was canceled by the user");
+        return true;
+    }
+    let reply = request.message.method_return().append1(secrets);
+    request.agent.send(reply).is_ok()
+}
+
+fn cancel_request(conn: &Arc<nonblock::SyncConnection>, connection_path: &Path, setting: &str) {
+    let mut requests = PENDING_REQUESTS.lock().unwrap();
+    let canceled: Vec<u32> = requests
+        .iter()
+        .filter(|(_, request)| {
+            request.connection_path == *connection_path && request.setting_name == setting
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    for id in canceled {
+        let request = requests.remove(&id).unwrap();
+        request.reply_error(AGENT_CANCELED, "The request was canceled by NetworkManager");
+        let signal = Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &NETWORK_INTERFACE!().into(),
+            &"SecretsRequestCanceled".into(),
+        )
+        .append1(id);
+        emit_signal(conn, signal);
+    }
+}
+
+/// Secrets of a single setting, e.g. 802-11-wireless-security, see GetConnectionSecrets.
+pub fn get_connection_secrets(
+    path: Path<'static>,
+    setting_name: &str,
+) -> Result<MaskedPropMap, MethodErr> {
+    let res = dbus_method!(
+        NM_INTERFACE_BASE!(),
+        path,
+        "GetSecrets",
+        NM_CONNECTION_INTERFACE!(),
+        (setting_name,),
+        1000,
+        (MaskedPropMap,),
+    );
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Failed to get secrets of connection: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Err(status_error(
+            StatusCode::SecretsUnavailable,
+            "Could not get secrets of this connection",
+        ));
+    }
+    Ok(res.unwrap().0)
+}
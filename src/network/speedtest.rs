@@ -0,0 +1,395 @@
+use std::{
+    io::{Read, Write},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{nonblock::SyncConnection, Message, MethodErr, Path};
+use dbus_crossroads::Crossroads;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    signals::emit_signal,
+    status::{status_error, StatusCode, StatusError},
+    utils::{get_daemon_config_value, SPEEDTEST},
+    DaemonData,
+};
+
+/// Each transfer stops after this time, slow connections would otherwise take minutes.
+const TRANSFER_DURATION: Duration = Duration::from_secs(10);
+/// Upper bound of the uploaded data, fast connections finish before the transfer duration.
+const UPLOAD_BYTES: usize = 100_000_000;
+const LATENCY_SAMPLES: usize = 5;
+const CHUNK_SIZE: usize = 64 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Result as (server, latency in milliseconds, download and upload in Mbit/s).
+pub type SpeedTestResult = (String, f64, f64, f64);
+
+/// Measurement endpoints of a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeedTestServer {
+    pub name: String,
+    pub download: String,
+    pub upload: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Latency,
+    Download,
+    Upload,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Latency => "latency",
+            Stage::Download => "download",
+            Stage::Upload => "upload",
+        }
+    }
+}
+
+/// Whether curl, which performs the transfers, is installed.
+pub fn speedtest_available() -> bool {
+    Command::new("curl")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+pub fn setup_speedtest_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(SPEEDTEST, |c| {
+        c.signal::<(String, f64), _>("SpeedTestProgress", ("stage", "value"));
+        c.signal::<(SpeedTestResult, String, String), _>(
+            "SpeedTestFinished",
+            ("result", "error", "code"),
+        );
+        c.method_with_cr_async(
+            "StartSpeedTest",
+            ("server",),
+            ("result",),
+            move |mut ctx, cross, (server,): (String,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                async move {
+                    ctx.reply(start_speed_test(connection, &server).map(|result| (result,)))
+                }
+            },
+        );
+        c.method("CancelSpeedTest", (), ("result",), move |_, _, ()| {
+            let running = RUNNING.load(Ordering::SeqCst);
+            if running {
+                CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+            }
+            Ok((running,))
+        });
+        c.method("ListSpeedTestServers", (), ("servers",), move |_, _, ()| {
+            Ok((configured_servers()
+                .into_iter()
+                .map(|server| server.name)
+                .collect::<Vec<String>>(),))
+        });
+    });
+    token
+}
+
+/// Servers of the config, no server is contacted unless configured explicitly.
+/// ```toml
+/// [Daemon.speedtest_servers.home]
+/// download = "http://192.168.1.2/100MB.bin"
+/// upload = "http://192.168.1.2/upload"
+/// ```
+/// The download endpoint has to serve a large file, the upload endpoint has to accept POST
+/// requests of arbitrary size.
+pub fn configured_servers() -> Vec<SpeedTestServer> {
+    get_daemon_config_value("speedtest_servers")
+        .and_then(|servers| servers.as_table())
+        .map(parse_servers)
+        .unwrap_or_default()
+}
+
+/// Servers without a download or upload endpoint are skipped.
+pub fn parse_servers(servers: &toml::Table) -> Vec<SpeedTestServer> {
+    let mut servers: Vec<SpeedTestServer> = servers
+        .iter()
+        .filter_map(|(name, endpoints)| {
+            let endpoint = |key: &str| {
+                endpoints
+                    .get(key)
+                    .and_then(|url| url.as_str())
+                    .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+                    .map(String::from)
+            };
+            Some(SpeedTestServer {
+                name: name.clone(),
+                download: endpoint("download")?,
+                upload: endpoint("upload")?,
+            })
+        })
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    servers
+}
+
+/// Starts a speed test against the server, the first configured server is used if the name is
+/// empty.\
+/// Returns false if another test is running, progress and result are emitted as signals.
+fn start_speed_test(connection: Arc<SyncConnection>, server: &str) -> Result<bool, MethodErr> {
+    let servers = configured_servers();
+    if servers.is_empty() {
+        return Err(status_error(
+            StatusCode::Unsupported,
+            "No speed test server is configured",
+        ));
+    }
+    let server = if server.is_empty() {
+        servers.into_iter().next()
+    } else {
+        servers
+            .into_iter()
+            .find(|configured| configured.name == server)
+    };
+    let Some(server) = server else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown speed test server",
+        ));
+    };
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+    thread::spawn(move || {
+        LOG!(format!("Starting speed test against {}", server.name));
+        let res = run_speed_test(&connection, &server);
+        let (result, error, code) = match res {
+            Ok(result) => (result, String::new(), StatusCode::Ok),
+            Err(error) => (
+                (server.name.clone(), 0.0, 0.0, 0.0),
+                error.detail,
+                error.code,
+            ),
+        };
+        let msg = Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &SPEEDTEST.into(),
+            &"SpeedTestFinished".into(),
+        )
+        .append3(result, error, code.name());
+        emit_signal(&connection, msg);
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+    Ok(true)
+}
+
+fn run_speed_test(
+    conn: &Arc<SyncConnection>,
+    server: &SpeedTestServer,
+) -> Result<SpeedTestResult, StatusError> {
+    let latency = measure_latency(&server.download)?;
+    emit_progress(conn, Stage::Latency, latency);
+    let download = measure_download(conn, &server.download)?;
+    let upload = measure_upload(conn, &server.upload)?;
+    Ok((server.name.clone(), latency, download, upload))
+}
+
+fn cancelled() -> Result<(), StatusError> {
+    if CANCEL_REQUESTED.load(Ordering::SeqCst) {
+        return Err(StatusError::new(
+            StatusCode::Cancelled,
+            "The speed test was cancelled",
+        ));
+    }
+    Ok(())
+}
+
+/// Fastest TCP handshake of several requests in milliseconds, which is one round trip.
+fn measure_latency(url: &str) -> Result<f64, StatusError> {
+    let mut latency = f64::MAX;
+    for _ in 0..LATENCY_SAMPLES {
+        cancelled()?;
+        let output = Command::new("curl")
+            .args(["--silent", "--head", "--output", "/dev/null"])
+            .args(["--max-time", "5", "--write-out", "%{time_connect}", url])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|_| StatusError::new(StatusCode::Unsupported, "curl could not be started"))?;
+        let connect = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        if connect > 0.0 {
+            latency = latency.min(connect * 1000.0);
+        }
+    }
+    if latency == f64::MAX {
+        return Err(StatusError::new(
+            StatusCode::ServerUnreachable,
+            "The server is not reachable",
+        ));
+    }
+    Ok(latency)
+}
+
+/// curl transfer which stops after the transfer duration.
+fn transfer(url: &str) -> Command {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--location", "--fail", "--max-time"])
+        .arg(TRANSFER_DURATION.as_secs().to_string())
+        .arg(url)
+        .stderr(Stdio::null());
+    command
+}
+
+fn stop_transfer(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Counts the received bytes, curl only writes the body to stdout.
+fn measure_download(conn: &Arc<SyncConnection>, url: &str) -> Result<f64, StatusError> {
+    let mut child = transfer(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|_| StatusError::new(StatusCode::Unsupported, "curl could not be started"))?;
+    let mut stdout = child.stdout.take().unwrap();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut meter = Meter::new();
+    loop {
+        if let Err(error) = cancelled() {
+            stop_transfer(child);
+            return Err(error);
+        }
+        match stdout.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                if let Some(speed) = meter.add(read) {
+                    emit_progress(conn, Stage::Download, speed);
+                }
+            }
+        }
+    }
+    let _ = child.wait();
+    meter.finish(Stage::Download)
+}
+
+/// Streams the data to curl, the result is the upload speed measured by curl itself.\
+/// Progress is based on the bytes handed to curl, which runs ahead of the network by the
+/// buffers of the pipe and curl.
+fn measure_upload(conn: &Arc<SyncConnection>, url: &str) -> Result<f64, StatusError> {
+    let mut child = transfer(url)
+        .args([
+            "--upload-file",
+            "-",
+            "--request",
+            "POST",
+            "--output",
+            "/dev/null",
+        ])
+        .args(["--write-out", "%{speed_upload}"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|_| StatusError::new(StatusCode::Unsupported, "curl could not be started"))?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let buffer = vec![0; CHUNK_SIZE];
+    let mut meter = Meter::new();
+    while meter.bytes < UPLOAD_BYTES {
+        if let Err(error) = cancelled() {
+            stop_transfer(child);
+            return Err(error);
+        }
+        // curl closes the pipe once the transfer duration is reached
+        if stdin.write_all(&buffer).is_err() {
+            break;
+        }
+        if let Some(speed) = meter.add(buffer.len()) {
+            emit_progress(conn, Stage::Upload, speed);
+        }
+    }
+    drop(stdin);
+    let mut output = String::new();
+    let _ = stdout.read_to_string(&mut output);
+    let _ = child.wait();
+    // curl reports bytes per second
+    let speed = output.trim().parse::<f64>().unwrap_or(0.0);
+    if speed <= 0.0 {
+        return Err(transfer_failed(Stage::Upload));
+    }
+    Ok(speed * 8.0 / 1_000_000.0)
+}
+
+/// Converts transferred bytes to Mbit/s and rate limits the progress.
+struct Meter {
+    start: Instant,
+    last_progress: Instant,
+    bytes: usize,
+}
+
+impl Meter {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            last_progress: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Returns the current speed once per progress interval.
+    fn add(&mut self, bytes: usize) -> Option<f64> {
+        self.bytes += bytes;
+        if self.last_progress.elapsed() < PROGRESS_INTERVAL {
+            return None;
+        }
+        self.last_progress = Instant::now();
+        Some(self.speed())
+    }
+
+    fn speed(&self) -> f64 {
+        let seconds = self.start.elapsed().as_secs_f64().max(0.001);
+        self.bytes as f64 * 8.0 / seconds / 1_000_000.0
+    }
+
+    fn finish(&self, stage: Stage) -> Result<f64, StatusError> {
+        if self.bytes == 0 {
+            return Err(transfer_failed(stage));
+        }
+        Ok(self.speed())
+    }
+}
+
+fn transfer_failed(stage: Stage) -> StatusError {
+    ERROR!(
+        format!("The {} of the speed test transferred no data", stage.name()),
+        ErrorLevel::Recoverable
+    );
+    StatusError::new(StatusCode::Failed, format!("The {} failed", stage.name()))
+}
+
+fn emit_progress(conn: &Arc<SyncConnection>, stage: Stage, value: f64) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &SPEEDTEST.into(),
+        &"SpeedTestProgress".into(),
+    )
+    .append2(stage.name(), value);
+    emit_signal(conn, msg);
+}
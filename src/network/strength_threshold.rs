@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use dbus::{Message, Path};
+use re_set_lib::network::network_structures::AccessPoint;
+
+use crate::utils::get_daemon_config_value;
+
+/// Used when no threshold is configured.
+pub const DEFAULT_STRENGTH_THRESHOLD: u8 = 5;
+
+/// State of an access point as (strength, ssid, stored).
+pub type AccessPointState = (u8, Vec<u8>, bool);
+
+/// Minimum change of the strength in percent before AccessPointChanged is emitted, changes of
+/// the SSID or the stored state are always emitted.
+/// ```toml
+/// [Daemon]
+/// access_point_strength_threshold = 10
+/// ```
+/// A threshold of 0 emits every change.
+pub fn strength_threshold() -> u8 {
+    get_daemon_config_value("access_point_strength_threshold")
+        .and_then(|value| value.as_integer())
+        .map(|threshold| threshold.clamp(0, 100) as u8)
+        .unwrap_or(DEFAULT_STRENGTH_THRESHOLD)
+}
+
+pub fn access_point_state(access_point: &AccessPoint) -> AccessPointState {
+    (
+        access_point.strength,
+        access_point.ssid.clone(),
+        access_point.stored,
+    )
+}
+
+/// Returns true if the change from the last delivered state has to be delivered, access points
+/// without a delivered state always are.
+pub fn significant_change(
+    last: Option<&AccessPointState>,
+    current: &AccessPointState,
+    threshold: u8,
+) -> bool {
+    let Some(last) = last else {
+        return true;
+    };
+    last.1 != current.1 || last.2 != current.2 || last.0.abs_diff(current.0) >= threshold
+}
+
+/// Tracks the access point states delivered to a receiver, either the broadcast of the listener
+/// or a client with its own threshold.
+#[derive(Debug, Default)]
+pub struct StrengthFilter {
+    threshold: u8,
+    delivered: HashMap<Path<'static>, AccessPointState>,
+}
+
+impl StrengthFilter {
+    pub fn new(threshold: u8) -> Self {
+        Self {
+            threshold,
+            delivered: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the state should be delivered, the state is then recorded as delivered.
+    pub fn accept(&mut self, path: &Path<'static>, state: AccessPointState) -> bool {
+        if !significant_change(self.delivered.get(path), &state, self.threshold) {
+            return false;
+        }
+        self.delivered.insert(path.clone(), state);
+        true
+    }
+
+    /// Records the full state, which AccessPointAdded delivers to every receiver.
+    pub fn record(&mut self, path: Path<'static>, state: AccessPointState) {
+        self.delivered.insert(path, state);
+    }
+
+    pub fn forget(&mut self, path: &Path<'static>) {
+        self.delivered.remove(path);
+    }
+}
+
+/// Keeps the filter of a client in sync with added and removed access points.
+pub fn track_access_point_signal(msg: &Message, filter: &mut StrengthFilter) {
+    match msg.member().as_deref() {
+        Some("AccessPointAdded") => {
+            if let Ok(access_point) = msg.read1::<AccessPoint>() {
+                let state = access_point_state(&access_point);
+                filter.record(access_point.dbus_path, state);
+            }
+        }
+        Some("AccessPointRemoved") => {
+            if let Ok(path) = msg.read1::<Path<'static>>() {
+                filter.forget(&path);
+            }
+        }
+        _ => (),
+    }
+}
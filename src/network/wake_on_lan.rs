@@ -0,0 +1,168 @@
+use std::net::{Ipv4Addr, UdpSocket};
+
+use dbus::{
+    arg::{RefArg, Variant},
+    MethodErr, Path,
+};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::{
+    connection_sharing::{
+        active_connection, get_device_property, settings_of, DEVICE_TYPE_ETHERNET,
+    },
+    network_manager::{get_connection_settings_with_secrets, set_connection_settings},
+    roaming::bssid_to_bytes,
+};
+use crate::status::{status_error, StatusCode};
+
+const WIRED_SETTING: &str = "802-3-ethernet";
+const WAKE_ON_LAN_SETTING: &str = "wake-on-lan";
+/// Port of the discard service, which is the usual destination of magic packets.
+const MAGIC_PACKET_PORT: u16 = 9;
+
+/// Flags of NMSettingWiredWakeOnLan as (name, flag).\
+/// default uses the global NetworkManager setting, ignore leaves the setting of the driver
+/// untouched and disabled turns wake-on-lan off.
+pub const WAKE_ON_LAN_FLAGS: &[(&str, u32)] = &[
+    ("default", 0x1),
+    ("phy", 0x2),
+    ("unicast", 0x4),
+    ("multicast", 0x8),
+    ("broadcast", 0x10),
+    ("arp", 0x20),
+    ("magic", 0x40),
+    ("ignore", 0x8000),
+];
+const WAKE_ON_LAN_DISABLED: &str = "disabled";
+
+/// Converts a comma separated list of WAKE_ON_LAN_FLAGS, e.g. "magic,unicast", to the flags of
+/// NetworkManager.\
+/// default and ignore can not be combined with other flags.
+pub fn parse_wake_on_lan_mode(mode: &str) -> Option<u32> {
+    if mode == WAKE_ON_LAN_DISABLED {
+        return Some(0);
+    }
+    let mut flags = 0;
+    for name in mode.split(',').map(str::trim) {
+        let (_, flag) = WAKE_ON_LAN_FLAGS.iter().find(|(flag, _)| *flag == name)?;
+        flags |= flag;
+    }
+    let exclusive = flags & (0x1 | 0x8000);
+    if flags == 0 || (exclusive != 0 && flags != exclusive) {
+        return None;
+    }
+    Some(flags)
+}
+
+pub fn wake_on_lan_mode_name(flags: u32) -> String {
+    if flags == 0 {
+        return String::from(WAKE_ON_LAN_DISABLED);
+    }
+    WAKE_ON_LAN_FLAGS
+        .iter()
+        .filter(|(_, flag)| flags & flag != 0)
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+/// Stored connection of an ethernet device, the active connection is preferred over the
+/// connections available to the device.
+fn wired_connection(device: &Path<'static>) -> Result<Path<'static>, MethodErr> {
+    if get_device_property::<u32>(device, "DeviceType") != Some(DEVICE_TYPE_ETHERNET) {
+        return Err(status_error(
+            StatusCode::WrongDeviceType,
+            "The device is not an ethernet device",
+        ));
+    }
+    active_connection(device)
+        .and_then(|active| settings_of(&active))
+        .or_else(|| {
+            get_device_property::<Vec<Path<'static>>>(device, "AvailableConnections")
+                .and_then(|connections| connections.into_iter().next())
+        })
+        .ok_or_else(|| {
+            status_error(
+                StatusCode::ConnectionNotFound,
+                "The device has no connection",
+            )
+        })
+}
+
+/// Returns the wake-on-lan mode of the connection of an ethernet device.
+pub fn get_wake_on_lan(device: Path<'static>) -> Result<String, MethodErr> {
+    let connection = wired_connection(&device)?;
+    let settings = get_connection_settings_with_secrets(connection)?;
+    // NetworkManager omits the setting while it is set to default
+    let flags = settings
+        .get(WIRED_SETTING)
+        .and_then(|wired| wired.get(WAKE_ON_LAN_SETTING))
+        .and_then(|flags| flags.0.as_u64())
+        .unwrap_or(0x1);
+    Ok(wake_on_lan_mode_name(flags as u32))
+}
+
+/// Changes the wake-on-lan mode of the connection of an ethernet device, the mode is applied
+/// once the connection is activated again.
+pub fn set_wake_on_lan(device: Path<'static>, mode: &str) -> Result<bool, MethodErr> {
+    let Some(flags) = parse_wake_on_lan_mode(mode) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown wake-on-lan mode",
+        ));
+    };
+    let connection = wired_connection(&device)?;
+    let mut settings = get_connection_settings_with_secrets(connection.clone())?;
+    settings
+        .entry(String::from(WIRED_SETTING))
+        .or_default()
+        .insert(
+            String::from(WAKE_ON_LAN_SETTING),
+            Variant(Box::new(flags) as Box<dyn RefArg>),
+        );
+    Ok(set_connection_settings(connection, settings))
+}
+
+/// Magic packet as 6 times 0xff followed by 16 repetitions of the mac address.
+pub fn magic_packet(mac: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(mac);
+    }
+    packet
+}
+
+/// Wakes a machine by sending a magic packet to the broadcast address, the limited broadcast
+/// address is used if it is empty.\
+/// Directed broadcasts, e.g. 192.168.1.255, reach machines of other subnets if the router
+/// forwards them.
+pub fn send_magic_packet(mac: &str, broadcast: &str) -> Result<bool, MethodErr> {
+    let Some(mac) = bssid_to_bytes(mac) else {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Invalid mac address",
+        ));
+    };
+    let broadcast = if broadcast.is_empty() {
+        Ipv4Addr::BROADCAST
+    } else {
+        broadcast
+            .parse::<Ipv4Addr>()
+            .map_err(|_| status_error(StatusCode::InvalidArgument, "Invalid broadcast address"))?
+    };
+    let res = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).and_then(|socket| {
+        socket.set_broadcast(true)?;
+        socket.send_to(&magic_packet(&mac), (broadcast, MAGIC_PACKET_PORT))
+    });
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not send magic packet: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return Ok(false);
+    }
+    LOG!(format!("Sent magic packet to {}", broadcast));
+    Ok(true)
+}
@@ -0,0 +1,166 @@
+/// Contents of a WiFi QR code as defined by the ZXing barcode contents specification:\
+/// `WIFI:S:<ssid>;T:<WPA|SAE|WEP|nopass>;P:<password>;H:<true|false>;;`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WifiQrPayload {
+    pub ssid: String,
+    pub security: WifiQrSecurity,
+    pub password: String,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WifiQrSecurity {
+    #[default]
+    None,
+    Wep,
+    Wpa,
+    Sae,
+}
+
+impl WifiQrSecurity {
+    /// Converts the NetworkManager key-mgmt setting, None for security types which can't be
+    /// shared with a QR code, e.g. wpa-eap, owe or dynamic WEP with ieee8021x.
+    pub fn from_key_management(key_management: &str) -> Option<Self> {
+        match key_management {
+            "none" => Some(WifiQrSecurity::Wep),
+            "wpa-psk" => Some(WifiQrSecurity::Wpa),
+            "sae" => Some(WifiQrSecurity::Sae),
+            _ => None,
+        }
+    }
+
+    /// Returns the NetworkManager key-mgmt setting, None for open networks.
+    pub fn key_management(&self) -> Option<&'static str> {
+        match self {
+            WifiQrSecurity::None => None,
+            WifiQrSecurity::Wep => Some("none"),
+            WifiQrSecurity::Wpa => Some("wpa-psk"),
+            WifiQrSecurity::Sae => Some("sae"),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            WifiQrSecurity::None => "nopass",
+            WifiQrSecurity::Wep => "WEP",
+            WifiQrSecurity::Wpa => "WPA",
+            WifiQrSecurity::Sae => "SAE",
+        }
+    }
+
+    fn from_type(security: &str) -> Option<Self> {
+        match security.to_uppercase().as_str() {
+            "" | "NOPASS" => Some(WifiQrSecurity::None),
+            "WEP" => Some(WifiQrSecurity::Wep),
+            "WPA" | "WPA2" => Some(WifiQrSecurity::Wpa),
+            "SAE" | "WPA3" => Some(WifiQrSecurity::Sae),
+            _ => None,
+        }
+    }
+}
+
+impl WifiQrPayload {
+    pub fn to_payload(&self) -> String {
+        let mut payload = format!(
+            "WIFI:S:{};T:{};",
+            escape(&self.ssid),
+            self.security.as_str()
+        );
+        if self.security != WifiQrSecurity::None {
+            payload.push_str(&format!("P:{};", escape(&self.password)));
+        }
+        if self.hidden {
+            payload.push_str("H:true;");
+        }
+        payload.push(';');
+        payload
+    }
+
+    pub fn parse(payload: &str) -> Result<Self, String> {
+        let fields = payload
+            .trim()
+            .strip_prefix("WIFI:")
+            .ok_or_else(|| String::from("Payload does not start with WIFI:"))?;
+        let mut result = WifiQrPayload::default();
+        let mut security = None;
+        for field in split_fields(fields) {
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+            let value = unescape(value);
+            match key {
+                "S" => result.ssid = value,
+                "T" => {
+                    security = Some(
+                        WifiQrSecurity::from_type(&value)
+                            .ok_or_else(|| format!("Unsupported security type {}", value))?,
+                    )
+                }
+                "P" => result.password = value,
+                "H" => result.hidden = value.eq_ignore_ascii_case("true"),
+                _ => (),
+            }
+        }
+        if result.ssid.is_empty() {
+            return Err(String::from("Payload does not contain an SSID"));
+        }
+        result.security = match security {
+            Some(security) => security,
+            None if result.password.is_empty() => WifiQrSecurity::None,
+            None => WifiQrSecurity::Wpa,
+        };
+        Ok(result)
+    }
+}
+
+/// Splits on unescaped semicolons, escape sequences are kept for unescaping the values.
+fn split_fields(fields: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for character in fields.chars() {
+        if escaped {
+            current.push(character);
+            escaped = false;
+        } else if character == '\\' {
+            current.push(character);
+            escaped = true;
+        } else if character == ';' {
+            if !current.is_empty() {
+                result.push(current.clone());
+            }
+            current.clear();
+        } else {
+            current.push(character);
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    result
+}
+
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for character in value.chars() {
+        if matches!(character, '\\' | ';' | ',' | ':' | '"') {
+            result.push('\\');
+        }
+        result.push(character);
+    }
+    result
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut escaped = false;
+    for character in value.chars() {
+        if character == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        result.push(character);
+    }
+    result
+}
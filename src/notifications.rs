@@ -0,0 +1,299 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    blocking::{
+        stdintf::org_freedesktop_dbus::{Properties, PropertiesPropertiesChanged},
+        Connection,
+    },
+    message::{MatchRule, MessageType, SignalArgs},
+    nonblock::SyncConnection,
+    Message, MethodErr, Path,
+};
+use dbus_crossroads::Crossroads;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use crate::{
+    context::set_do_not_disturb_context,
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{get_daemon_config_value, session_connection, NOTIFICATIONS},
+    DaemonData,
+};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const DUNST_INTERFACE: &str = "org.dunstproject.cmd0";
+const MAKO_PATH: &str = "/fr/emersion/Mako";
+const MAKO_INTERFACE: &str = "fr.emersion.Mako";
+const SWAYNC_NAME: &str = "org.erikreider.swaync.cc";
+const SWAYNC_PATH: &str = "/org/erikreider/swaync/cc";
+/// Mode of the mako config used for do not disturb, override with dnd_mode within the daemon
+/// section of the config.
+const MAKO_DEFAULT_MODE: &str = "do-not-disturb";
+
+static DO_NOT_DISTURB: AtomicBool = AtomicBool::new(false);
+
+/// Notification daemons supporting do not disturb.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationBackend {
+    Mako,
+    Dunst,
+    Swaync,
+}
+
+impl NotificationBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            NotificationBackend::Mako => "mako",
+            NotificationBackend::Dunst => "dunst",
+            NotificationBackend::Swaync => "swaync",
+        }
+    }
+
+    /// Matches the server name reported by GetServerInformation of the notification spec.
+    pub fn from_server_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "mako" => Some(NotificationBackend::Mako),
+            "dunst" => Some(NotificationBackend::Dunst),
+            "swaync" | "swaynotificationcenter" => Some(NotificationBackend::Swaync),
+            _ => None,
+        }
+    }
+}
+
+pub fn setup_notification_manager(
+    cross: &mut Crossroads,
+) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(NOTIFICATIONS, |c| {
+        c.signal::<(bool,), _>("DoNotDisturbChanged", ("enabled",));
+        c.method_with_cr_async(
+            "GetDoNotDisturb",
+            (),
+            ("enabled",),
+            move |mut ctx, _, ()| async move {
+                ctx.reply(get_do_not_disturb().map(|enabled| (enabled,)))
+            },
+        );
+        c.method_with_cr_async(
+            "SetDoNotDisturb",
+            ("enabled",),
+            ("result",),
+            move |mut ctx, cross, (enabled,): (bool,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                async move {
+                    let res = set_do_not_disturb(enabled);
+                    if res.as_ref().is_ok_and(|result| *result) {
+                        update_do_not_disturb(&connection);
+                    }
+                    ctx.reply(res.map(|result| (result,)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "GetNotificationDaemon",
+            (),
+            ("daemon",),
+            move |mut ctx, _, ()| async move {
+                let name = detect_backend()
+                    .map(NotificationBackend::name)
+                    .unwrap_or_default();
+                ctx.reply(Ok((name.to_string(),)))
+            },
+        );
+    });
+    token
+}
+
+/// Returns the running notification daemon if it supports do not disturb.\
+/// swaync is checked first, as it only exposes do not disturb on its own bus name.
+pub fn detect_backend() -> Option<NotificationBackend> {
+    let conn = session_connection().ok()?;
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_millis(1000),
+    );
+    let res: Result<(bool,), dbus::Error> =
+        proxy.method_call("org.freedesktop.DBus", "NameHasOwner", (SWAYNC_NAME,));
+    if res.is_ok_and(|(owned,)| owned) {
+        return Some(NotificationBackend::Swaync);
+    }
+    let proxy = conn.with_proxy(
+        NOTIFICATIONS_NAME,
+        NOTIFICATIONS_PATH,
+        Duration::from_millis(1000),
+    );
+    let res: Result<(String, String, String, String), dbus::Error> =
+        proxy.method_call(NOTIFICATIONS_NAME, "GetServerInformation", ());
+    let (name, _, _, _) = res.ok()?;
+    NotificationBackend::from_server_name(&name)
+}
+
+fn no_backend() -> MethodErr {
+    status_error(
+        StatusCode::ServiceUnavailable,
+        "No supported notification daemon is running",
+    )
+}
+
+fn mako_mode() -> String {
+    get_daemon_config_value("dnd_mode")
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_else(|| String::from(MAKO_DEFAULT_MODE))
+}
+
+pub fn get_do_not_disturb() -> Result<bool, MethodErr> {
+    let backend = detect_backend().ok_or_else(no_backend)?;
+    let conn = session_connection().map_err(|_| no_backend())?;
+    let res = match backend {
+        NotificationBackend::Dunst => conn
+            .with_proxy(
+                NOTIFICATIONS_NAME,
+                NOTIFICATIONS_PATH,
+                Duration::from_millis(1000),
+            )
+            .get(DUNST_INTERFACE, "paused"),
+        NotificationBackend::Swaync => conn
+            .with_proxy(SWAYNC_NAME, SWAYNC_PATH, Duration::from_millis(1000))
+            .method_call(SWAYNC_NAME, "GetDnd", ())
+            .map(|(enabled,): (bool,)| enabled),
+        NotificationBackend::Mako => {
+            get_mako_modes(&conn).map(|modes| modes.contains(&mako_mode()))
+        }
+    };
+    res.map_err(|_error| {
+        ERROR!(
+            format!(
+                "Could not read do not disturb of {}: {}",
+                backend.name(),
+                _error
+            ),
+            ErrorLevel::Recoverable
+        );
+        status_error(StatusCode::Failed, "Could not read do not disturb")
+    })
+}
+
+/// Returns false if the notification daemon rejected the change.
+pub fn set_do_not_disturb(enabled: bool) -> Result<bool, MethodErr> {
+    let backend = detect_backend().ok_or_else(no_backend)?;
+    let conn = session_connection().map_err(|_| no_backend())?;
+    let res = match backend {
+        NotificationBackend::Dunst => conn
+            .with_proxy(
+                NOTIFICATIONS_NAME,
+                NOTIFICATIONS_PATH,
+                Duration::from_millis(1000),
+            )
+            .set(DUNST_INTERFACE, "paused", enabled),
+        NotificationBackend::Swaync => conn
+            .with_proxy(SWAYNC_NAME, SWAYNC_PATH, Duration::from_millis(1000))
+            .method_call(SWAYNC_NAME, "SetDnd", (enabled,)),
+        NotificationBackend::Mako => get_mako_modes(&conn).and_then(|modes| {
+            conn.with_proxy(NOTIFICATIONS_NAME, MAKO_PATH, Duration::from_millis(1000))
+                .method_call(
+                    MAKO_INTERFACE,
+                    "SetModes",
+                    (with_mode(modes, &mako_mode(), enabled),),
+                )
+        }),
+    };
+    if let Err(_error) = res {
+        ERROR!(
+            format!(
+                "Could not set do not disturb of {}: {}",
+                backend.name(),
+                _error
+            ),
+            ErrorLevel::Recoverable
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn get_mako_modes(conn: &Connection) -> Result<Vec<String>, dbus::Error> {
+    conn.with_proxy(NOTIFICATIONS_NAME, MAKO_PATH, Duration::from_millis(1000))
+        .method_call(MAKO_INTERFACE, "ListModes", ())
+        .map(|(modes,): (Vec<String>,)| modes)
+}
+
+/// Adds or removes a mako mode while keeping the other active modes.
+pub fn with_mode(mut modes: Vec<String>, mode: &str, enabled: bool) -> Vec<String> {
+    modes.retain(|active| active != mode);
+    if enabled {
+        modes.push(mode.to_string());
+    }
+    modes
+}
+
+/// Reads the current state and emits DoNotDisturbChanged if it changed.
+fn update_do_not_disturb(connection: &Arc<SyncConnection>) {
+    let Ok(enabled) = get_do_not_disturb() else {
+        return;
+    };
+    set_do_not_disturb_context(connection, enabled);
+    if DO_NOT_DISTURB.swap(enabled, Ordering::SeqCst) == enabled {
+        return;
+    }
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &NOTIFICATIONS.into(),
+        &"DoNotDisturbChanged".into(),
+    )
+    .append1(enabled);
+    emit_signal(connection, msg);
+}
+
+/// Follows do not disturb toggled outside of the daemon, e.g. with dunstctl or the panel of
+/// swaync.\
+/// dunst and mako report changes as PropertiesChanged, swaync emits its state to subscribers.
+pub fn start_do_not_disturb_listener(connection: Arc<SyncConnection>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = session_connection()?;
+        if let Ok(enabled) = get_do_not_disturb() {
+            DO_NOT_DISTURB.store(enabled, Ordering::SeqCst);
+            set_do_not_disturb_context(&connection, enabled);
+        }
+        let mut rules = vec![
+            PropertiesPropertiesChanged::match_rule(None, Some(&Path::from(NOTIFICATIONS_PATH)))
+                .static_clone(),
+            PropertiesPropertiesChanged::match_rule(None, Some(&Path::from(MAKO_PATH)))
+                .static_clone(),
+        ];
+        let mut swaync_state = MatchRule::new();
+        swaync_state.msg_type = Some(MessageType::Signal);
+        swaync_state.interface = Some(SWAYNC_NAME.into());
+        rules.push(swaync_state);
+        for rule in rules {
+            let changed_ref = connection.clone();
+            let res = conn.add_match(rule, move |_: (), _, _| {
+                update_do_not_disturb(&changed_ref);
+                true
+            });
+            if let Err(_error) = res {
+                ERROR!(
+                    format!("Failed to match signal on the session bus: {:?}", _error),
+                    ErrorLevel::Recoverable
+                );
+                return Err(dbus::Error::new_custom(
+                    "SignalMatchFailed",
+                    "Failed to match signal on the session bus.",
+                ));
+            }
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
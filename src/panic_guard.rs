@@ -0,0 +1,106 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::CString,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Arc,
+};
+
+use dbus::{channel::Sender, Message};
+use dbus_crossroads::Crossroads;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use crate::status::StatusCode;
+
+thread_local! {
+    /// Method call currently handled by crossroads on this thread, async handlers are spawned
+    /// while it is handled and keep it to answer a panic.
+    static CURRENT_CALL: RefCell<Option<Message>> = const { RefCell::new(None) };
+}
+
+/// Handles a method call with crossroads, a panicking handler is answered with the
+/// HandlerPanicked error instead of unwinding through the receive loop.\
+/// The remaining calls are served as usual, the state of the panicking subsystem may however be
+/// incomplete.
+pub fn handle_message_guarded<S: Sender>(cross: &mut Crossroads, msg: Message, conn: &S) {
+    CURRENT_CALL.with(|call| *call.borrow_mut() = msg.duplicate().ok());
+    let res = panic::catch_unwind(AssertUnwindSafe(|| cross.handle_message(msg, conn)));
+    let original = CURRENT_CALL.with(|call| call.borrow_mut().take());
+    let Err(payload) = res else {
+        return;
+    };
+    if let Some(original) = original {
+        reply_panicked(conn, original, &panic_message(&*payload));
+    }
+}
+
+/// Returns the spawner for the futures of async handlers.\
+/// A panicking handler is answered with the HandlerPanicked error like in
+/// handle_message_guarded, the reply owned by the handler is dropped with the task.
+pub fn spawn_handler<S: Sender + Send + Sync + 'static>(
+    conn: Arc<S>,
+) -> impl Fn(Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + 'static {
+    move |handler| {
+        let original = CURRENT_CALL.with(|call| {
+            call.borrow()
+                .as_ref()
+                .and_then(|call| call.duplicate().ok())
+        });
+        let conn = conn.clone();
+        let handle = tokio::spawn(handler);
+        tokio::spawn(async move {
+            let Err(error) = handle.await else {
+                return;
+            };
+            if !error.is_panic() {
+                return;
+            }
+            let reason = panic_message(&*error.into_panic());
+            if let Some(original) = original {
+                reply_panicked(&*conn, original, &reason);
+            } else {
+                ERROR!(
+                    format!("Async handler panicked: {}", reason),
+                    ErrorLevel::PartialBreakage
+                );
+            }
+        });
+    }
+}
+
+fn reply_panicked<S: Sender + ?Sized>(conn: &S, original: Message, reason: &str) {
+    let context = format!(
+        "{}.{} on {}",
+        original
+            .interface()
+            .map(|i| i.to_string())
+            .unwrap_or_default(),
+        original.member().map(|m| m.to_string()).unwrap_or_default(),
+        original.path().map(|p| p.to_string()).unwrap_or_default(),
+    );
+    ERROR!(
+        format!("Handler {} panicked: {}", context, reason),
+        ErrorLevel::PartialBreakage
+    );
+    if original.get_no_reply() {
+        return;
+    }
+    let text =
+        CString::new(format!("{} failed unexpectedly: {}", context, reason)).unwrap_or_default();
+    let _ = conn.send(original.error(&StatusCode::HandlerPanicked.error_name().into(), &text));
+}
+
+/// Returns the message given to panic!, panics with other payloads are reported as unknown.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    String::from("unknown panic")
+}
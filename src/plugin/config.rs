@@ -0,0 +1,212 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use dbus::{
+    arg::{ArgType, PropMap, RefArg, Variant},
+    MethodErr,
+};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{utils::config::CONFIG, ERROR, LOG};
+use toml::{Table, Value};
+
+use super::lifecycle::{call_plugin, current_plugin};
+use crate::status::{status_error, StatusCode};
+use crate::utils::edit_config_file;
+
+/// Section of the config file containing one table per plugin.
+/// ```toml
+/// [Plugin.yourplugin]
+/// refresh_interval = 5
+/// ```
+pub const PLUGIN_CONFIG: &str = "Plugin";
+
+/// Called with the parsed section of the plugin, once on registration and again whenever the
+/// section is changed with SetPluginConfig.
+#[allow(improper_ctypes_definitions)]
+pub type PluginConfigCallback = extern "C" fn(Table);
+
+/// Sections of all plugins, changes made with SetPluginConfig replace the parsed config file.
+static PLUGIN_CONFIGS: Lazy<RwLock<HashMap<String, Table>>> = Lazy::new(|| {
+    let configs = CONFIG
+        .get(PLUGIN_CONFIG)
+        .and_then(|section| section.as_table())
+        .map(|section| {
+            section
+                .iter()
+                .filter_map(|(name, config)| Some((name.clone(), config.as_table()?.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    RwLock::new(configs)
+});
+
+static CONFIG_CALLBACKS: Lazy<RwLock<HashMap<String, PluginConfigCallback>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Returns the config section of the calling plugin, empty if the section does not exist.\
+/// Call this within a function the daemon calls on the plugin, e.g. dbus_interface, the section
+/// is passed to the registered callback after every change as well.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn get_plugin_config() -> Table {
+    current_plugin("get_plugin_config")
+        .map(|plugin| plugin_config(&plugin))
+        .unwrap_or_default()
+}
+
+/// Registers the backend_config function of the calling plugin, which is called with the config
+/// section right away and after every change.\
+/// Call this within dbus_interface, the callback is removed once the plugin is unloaded.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn register_plugin_config(callback: PluginConfigCallback) -> bool {
+    let Some(plugin) = current_plugin("register_plugin_config") else {
+        return false;
+    };
+    CONFIG_CALLBACKS
+        .write()
+        .unwrap()
+        .insert(plugin.clone(), callback);
+    callback(plugin_config(&plugin));
+    true
+}
+
+fn plugin_config(plugin: &str) -> Table {
+    PLUGIN_CONFIGS
+        .read()
+        .unwrap()
+        .get(plugin)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub(crate) fn remove_plugin_config_callback(plugin: &str) {
+    CONFIG_CALLBACKS.write().unwrap().remove(plugin);
+}
+
+/// Returns the config section of the plugin as dbus values, tables are converted to nested maps.
+pub fn get_plugin_config_map(plugin: &str) -> PropMap {
+    table_to_map(&plugin_config(plugin))
+}
+
+/// Replaces the config section of the plugin, writes it to the config file and notifies the
+/// plugin.\
+/// Returns false if the config file could not be written, the plugin still receives the new
+/// section in this case.
+pub fn set_plugin_config(plugin: &str, config: &PropMap) -> Result<bool, MethodErr> {
+    if plugin.is_empty() {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "The plugin name can not be empty",
+        ));
+    }
+    let mut table = Table::new();
+    for (key, value) in config {
+        let Some(value) = value_from_arg(&*value.0) else {
+            return Err(status_error(
+                StatusCode::InvalidArgument,
+                &format!(
+                    "Unsupported value for {} with signature {}",
+                    key,
+                    value.0.signature()
+                ),
+            ));
+        };
+        table.insert(key.clone(), value);
+    }
+    let persisted = write_config_section(PLUGIN_CONFIG, plugin, &table);
+    PLUGIN_CONFIGS
+        .write()
+        .unwrap()
+        .insert(plugin.to_string(), table.clone());
+    let callback = CONFIG_CALLBACKS.read().unwrap().get(plugin).copied();
+    if let Some(callback) = callback {
+        call_plugin(plugin, || callback(table));
+    }
+    LOG!(format!("Changed config of plugin {}", plugin));
+    Ok(persisted)
+}
+
+/// Replaces the table name within the section of the config file, e.g. `[Plugin.yourplugin]`,
+/// the rest of the file including comments is left untouched.
+pub(crate) fn write_config_section(parent: &str, name: &str, table: &Table) -> bool {
+    let Ok(section) = table.to_string().parse::<toml_edit::DocumentMut>() else {
+        ERROR!(
+            format!("Could not convert the config of {}", name),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    };
+    edit_config_file(|document| {
+        let Some(sections) = document
+            .entry(parent)
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+        else {
+            return Err(format!("{} within the config file is not a table", parent));
+        };
+        sections.set_implicit(true);
+        sections.insert(name, toml_edit::Item::Table(section.as_table().clone()));
+        Ok(())
+    })
+}
+
+pub fn table_to_map(table: &Table) -> PropMap {
+    table
+        .iter()
+        .map(|(key, value)| (key.clone(), value_to_variant(value)))
+        .collect()
+}
+
+fn value_to_variant(value: &Value) -> Variant<Box<dyn RefArg>> {
+    match value {
+        Value::String(value) => Variant(Box::new(value.clone())),
+        Value::Integer(value) => Variant(Box::new(*value)),
+        Value::Float(value) => Variant(Box::new(*value)),
+        Value::Boolean(value) => Variant(Box::new(*value)),
+        Value::Datetime(value) => Variant(Box::new(value.to_string())),
+        Value::Array(values) => Variant(Box::new(
+            values
+                .iter()
+                .map(value_to_variant)
+                .collect::<Vec<Variant<Box<dyn RefArg>>>>(),
+        )),
+        Value::Table(table) => Variant(Box::new(table_to_map(table))),
+    }
+}
+
+/// Converts a dbus value to toml, dicts need string keys and unsigned 64 bit integers have to fit
+/// into the signed integers of toml.
+pub fn value_from_arg(value: &dyn RefArg) -> Option<Value> {
+    match value.arg_type() {
+        ArgType::Variant => value_from_arg(value.as_iter()?.next()?),
+        ArgType::String | ArgType::ObjectPath | ArgType::Signature => {
+            Some(Value::String(value.as_str()?.to_string()))
+        }
+        ArgType::Boolean => Some(Value::Boolean(value.as_u64()? != 0)),
+        ArgType::Double => Some(Value::Float(value.as_f64()?)),
+        ArgType::UInt64 => Some(Value::Integer(i64::try_from(value.as_u64()?).ok()?)),
+        ArgType::Byte
+        | ArgType::Int16
+        | ArgType::UInt16
+        | ArgType::Int32
+        | ArgType::UInt32
+        | ArgType::Int64 => Some(Value::Integer(value.as_i64()?)),
+        ArgType::Array if value.signature().starts_with("a{") => {
+            let mut table = Table::new();
+            let mut iter = value.as_iter()?;
+            while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+                table.insert(key.as_str()?.to_string(), value_from_arg(value)?);
+            }
+            Some(Value::Table(table))
+        }
+        ArgType::Array => Some(Value::Array(
+            value
+                .as_iter()?
+                .map(value_from_arg)
+                .collect::<Option<Vec<Value>>>()?,
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use once_cell::sync::Lazy;
+
+use super::lifecycle::{call_plugin, current_plugin};
+use crate::context::{current_system_context, SystemContext};
+
+/// Called with (on battery, metered, do not disturb), once on registration and again whenever
+/// the context changed.
+#[allow(improper_ctypes_definitions)]
+pub type SystemContextCallback = extern "C" fn(SystemContext);
+
+static CONTEXT_CALLBACKS: Lazy<RwLock<HashMap<String, SystemContextCallback>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Returns the current system context as (on battery, metered, do not disturb).
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn get_system_context() -> SystemContext {
+    current_system_context()
+}
+
+/// Registers a callback of the calling plugin for changes of the system context, e.g. to poll less often
+/// on a metered connection.\
+/// Call this within dbus_interface, the callback is removed once the plugin is unloaded.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn register_system_context_callback(callback: SystemContextCallback) -> bool {
+    let Some(plugin) = current_plugin("register_system_context_callback") else {
+        return false;
+    };
+    CONTEXT_CALLBACKS.write().unwrap().insert(plugin, callback);
+    callback(current_system_context());
+    true
+}
+
+pub(crate) fn remove_system_context_callback(plugin: &str) {
+    CONTEXT_CALLBACKS.write().unwrap().remove(plugin);
+}
+
+pub(crate) fn notify_system_context(context: SystemContext) {
+    let callbacks: Vec<(String, SystemContextCallback)> = CONTEXT_CALLBACKS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(plugin, callback)| (plugin.clone(), *callback))
+        .collect();
+    for (plugin, callback) in callbacks {
+        call_plugin(&plugin, || callback(context));
+    }
+}
@@ -5,7 +5,8 @@ extern "C" {
     /// The startup function is intended to be used to allocate any required resources.
     pub fn backend_startup();
 
-    /// Cleanup any resources allocated for your plugin that aren't automatically removed.
+    /// Cleanup any resources allocated for your plugin that aren't automatically removed.\
+    /// Called when the plugin is unloaded with UnloadPlugin or the daemon shuts down.
     pub fn backend_shutdown();
 
     /// Reports the capabilities that your plugin will provide, simply return a vector of strings.
@@ -18,10 +19,28 @@ extern "C" {
 
     /// Inserts your plugin interface into the dbus server.
     /// Provided as a parameter is the crossroads context, which you can use in order to insert your
-    /// interfaces and objects
+    /// interfaces and objects.\
+    /// Signals of your interfaces can be emitted by the daemon, register them with
+    /// register_plugin_signal and emit them with emit_plugin_signal, see plugin::signals.\
+    /// Register inserted objects with register_plugin_object, they are removed once the plugin is
+    /// unloaded, see plugin::lifecycle.\
+    /// The daemon knows which plugin it is calling, the host API therefore takes no plugin name.
     #[allow(improper_ctypes)]
     pub fn dbus_interface(cross: &mut Crossroads);
 
+    /// Optional, receives the `[Plugin.yourplugin]` section of the config file.\
+    /// Register it with register_plugin_config within dbus_interface, it is called right away and
+    /// again whenever the section is changed with SetPluginConfig, see plugin::config.\
+    /// Use get_plugin_config to read the section within other functions the daemon calls.
+    #[allow(improper_ctypes)]
+    pub fn backend_config(config: toml::Table);
+
+    /// Optional, receives the system context as (on battery, metered, do not disturb).\
+    /// Register it with register_system_context_callback within dbus_interface, it is called right
+    /// away and again whenever the context changed, see plugin::context.
+    #[allow(improper_ctypes)]
+    pub fn backend_system_context(context: (bool, bool, bool));
+
     /// Use this function to return any tests you would like to have run.
     /// This might be a bit confusing as this will force you to define your functions for testing
     /// outside of your typical rust tests.
@@ -0,0 +1,223 @@
+use std::{cell::RefCell, collections::HashMap, sync::RwLock};
+
+use dbus::Path;
+use dbus_crossroads::Crossroads;
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use super::config::{get_plugin_config, register_plugin_config, remove_plugin_config_callback};
+use super::context::{
+    get_system_context, register_system_context_callback, remove_system_context_callback,
+};
+use super::signals::{
+    emit_plugin_signal, get_plugin_signals, register_plugin_signal, remove_plugin_signals,
+};
+
+/// Prefix of the object name given to CrossWrapper::insert.
+pub const PLUGIN_PATH: &str = "/org/Xetibo/ReSet/Plugins/";
+
+/// Loaded backend plugins keyed by their name.
+static LOADED_PLUGINS: Lazy<RwLock<HashMap<String, LoadedPlugin>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Objects inserted by a plugin, registered before the plugin finished loading as well.
+static PLUGIN_OBJECTS: Lazy<RwLock<HashMap<String, Vec<Path<'static>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+struct LoadedPlugin {
+    shutdown: unsafe extern "C" fn(),
+}
+
+thread_local! {
+    /// Plugin whose function the daemon is currently calling on this thread.\
+    /// The host API derives the calling plugin from this instead of trusting a name passed by
+    /// the plugin.
+    static CURRENT_PLUGIN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous plugin once the call into a plugin returned or panicked.
+struct PluginScope(Option<String>);
+
+impl Drop for PluginScope {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_PLUGIN.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Calls a function of the plugin with the given name, host API calls made within are
+/// attributed to this plugin.
+pub(crate) fn call_plugin<T>(name: &str, call: impl FnOnce() -> T) -> T {
+    let previous = CURRENT_PLUGIN.with(|current| current.replace(Some(name.to_string())));
+    let _scope = PluginScope(previous);
+    call()
+}
+
+/// Returns the plugin whose function is currently called on this thread, None if the host API
+/// was called outside of a call by the daemon.
+pub(crate) fn current_plugin(_function: &str) -> Option<String> {
+    let plugin = CURRENT_PLUGIN.with(|current| current.borrow().clone());
+    if plugin.is_none() {
+        ERROR!(
+            format!(
+                "{} has to be called within a function the daemon calls on the plugin",
+                _function
+            ),
+            ErrorLevel::Recoverable
+        );
+    }
+    plugin
+}
+
+/// Registers an object that the plugin inserted with CrossWrapper::insert, the object name is
+/// the same one given to insert.\
+/// Call this within dbus_interface, registered objects are removed once the plugin is unloaded
+/// or fails to load. Objects of registered signals are removed without registration.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn register_plugin_object(object_name: String) -> bool {
+    let Some(plugin) = current_plugin("register_plugin_object") else {
+        return false;
+    };
+    let path = Path::new(PLUGIN_PATH.to_string() + &object_name);
+    if path.is_err() {
+        ERROR!(
+            format!(
+                "Plugin {} used an invalid object name: {}",
+                plugin, object_name
+            ),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    let path = path.unwrap();
+    let mut objects = PLUGIN_OBJECTS.write().unwrap();
+    let objects = objects.entry(plugin).or_default();
+    if !objects.contains(&path) {
+        objects.push(path);
+    }
+    true
+}
+
+/// Returns the plugin which registered the object at the path.
+pub(crate) fn plugin_of_object(path: &str) -> Option<String> {
+    PLUGIN_OBJECTS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(_, objects)| objects.iter().any(|object| &**object == path))
+        .map(|(plugin, _)| plugin.clone())
+}
+
+/// References the host API so the linker keeps it within the executable, build.rs exports it to
+/// the dynamic symbol table for the plugins.
+pub(crate) fn export_host_api() {
+    let api = [
+        register_plugin_object as *const (),
+        register_plugin_signal as *const (),
+        emit_plugin_signal as *const (),
+        get_plugin_config as *const (),
+        register_plugin_config as *const (),
+        get_system_context as *const (),
+        register_system_context_callback as *const (),
+    ];
+    std::hint::black_box(api);
+}
+
+pub(crate) fn set_plugin_loaded(name: String, shutdown: unsafe extern "C" fn()) {
+    LOADED_PLUGINS
+        .write()
+        .unwrap()
+        .insert(name, LoadedPlugin { shutdown });
+}
+
+pub(crate) fn is_plugin_loaded(name: &str) -> bool {
+    LOADED_PLUGINS.read().unwrap().contains_key(name)
+}
+
+/// Removes the capabilities of plugins that were unloaded or failed to load.
+pub(crate) fn loaded_capabilities(
+    capabilities: &[&'static str],
+    plugins: &[(String, Vec<&'static str>)],
+) -> Vec<&'static str> {
+    let loaded = LOADED_PLUGINS.read().unwrap();
+    capabilities
+        .iter()
+        .filter(|capability| {
+            plugins.iter().all(|(name, plugin_capabilities)| {
+                loaded.contains_key(name) || !plugin_capabilities.contains(capability)
+            })
+        })
+        .copied()
+        .collect()
+}
+
+/// Returns the capabilities that were (added, removed) between two results of
+/// loaded_capabilities.
+pub(crate) fn capability_changes(
+    before: &[&'static str],
+    after: &[&'static str],
+) -> (Vec<String>, Vec<String>) {
+    let difference = |first: &[&'static str], second: &[&'static str]| {
+        first
+            .iter()
+            .filter(|capability| !second.contains(capability))
+            .map(|capability| capability.to_string())
+            .collect()
+    };
+    (difference(after, before), difference(before, after))
+}
+
+/// Shuts the plugin down and removes all of its objects and signals.\
+/// Returns false if no plugin with this name is loaded.
+pub(crate) fn unload_plugin(cross: &mut Crossroads, name: &str) -> bool {
+    let plugin = LOADED_PLUGINS.write().unwrap().remove(name);
+    if plugin.is_none() {
+        return false;
+    }
+    call_plugin(name, || unsafe {
+        (plugin.unwrap().shutdown)();
+    });
+    remove_plugin_objects(cross, name);
+    LOG!(format!("Unloaded plugin: {}", name));
+    true
+}
+
+/// Unloads all plugins, used when the daemon shuts down.
+pub(crate) fn unload_all_plugins(cross: &mut Crossroads) {
+    let names: Vec<String> = LOADED_PLUGINS.read().unwrap().keys().cloned().collect();
+    for name in names {
+        unload_plugin(cross, &name);
+    }
+}
+
+/// Removes the registered objects of a plugin and the objects of its signals, the plugin itself
+/// is not shut down. The config and system context callbacks of the plugin are removed as well.\
+/// Used for plugins that failed while loading, their handlers would otherwise stay reachable.
+pub(crate) fn remove_plugin_objects(cross: &mut Crossroads, name: &str) {
+    let mut paths = PLUGIN_OBJECTS
+        .write()
+        .unwrap()
+        .remove(name)
+        .unwrap_or_default();
+    for (path, _, _, _) in get_plugin_signals(name) {
+        if !path.starts_with(PLUGIN_PATH) {
+            continue;
+        }
+        if let Ok(path) = Path::new(path) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    remove_plugin_signals(name);
+    remove_plugin_config_callback(name);
+    remove_system_context_callback(name);
+    for path in paths {
+        // the data of plugin objects is unknown, the object is removed regardless of the type
+        cross.remove::<()>(&path);
+        LOG!(format!("Removed plugin object {}", path));
+    }
+}
@@ -1 +1,5 @@
+pub mod config;
+pub mod context;
 pub mod functions;
+pub mod lifecycle;
+pub mod signals;
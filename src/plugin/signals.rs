@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use dbus::{arg::RefArg, nonblock::SyncConnection, Message, Path};
+use once_cell::sync::{Lazy, OnceCell};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use super::lifecycle::current_plugin;
+use crate::signals::emit_signal;
+
+/// Registered signals of each plugin together with the id handed to the plugin.
+type PluginSignals = HashMap<String, Vec<(u32, PluginSignal)>>;
+
+static RELAY_CONNECTION: OnceCell<Arc<SyncConnection>> = OnceCell::new();
+static PLUGIN_SIGNALS: Lazy<RwLock<PluginSignals>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static NEXT_SIGNAL_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Signal of a plugin as (path, interface, name, signature).
+pub type PluginSignalInfo = (String, String, String, String);
+/// Plugin as (name, capabilities, signals).
+pub type PluginInfo = (String, Vec<String>, Vec<PluginSignalInfo>);
+
+/// Definition of a signal that a plugin emits through the daemon.\
+/// The signature is the dbus signature of the payload, e.g. "su" for a string and an u32.\
+/// Plugins declare the same struct with `#[repr(C)]` to pass it to register_plugin_signal.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginSignal {
+    pub path: String,
+    pub interface: String,
+    pub name: String,
+    pub signature: String,
+}
+
+pub(crate) fn set_relay_connection(conn: Arc<SyncConnection>) {
+    let _ = RELAY_CONNECTION.set(conn);
+}
+
+/// Returns the signals registered by a plugin.
+pub(crate) fn get_plugin_signals(plugin: &str) -> Vec<PluginSignalInfo> {
+    PLUGIN_SIGNALS
+        .read()
+        .unwrap()
+        .get(plugin)
+        .map(|signals| {
+            signals
+                .iter()
+                .map(|(_, signal)| {
+                    (
+                        signal.path.clone(),
+                        signal.interface.clone(),
+                        signal.name.clone(),
+                        signal.signature.clone(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the signal with the id if the plugin registered it.
+pub(crate) fn get_plugin_signal(plugin: &str, id: u32) -> Option<PluginSignal> {
+    PLUGIN_SIGNALS
+        .read()
+        .unwrap()
+        .get(plugin)?
+        .iter()
+        .find(|(registered, _)| *registered == id)
+        .map(|(_, signal)| signal.clone())
+}
+
+pub(crate) fn remove_plugin_signals(plugin: &str) {
+    PLUGIN_SIGNALS.write().unwrap().remove(plugin);
+}
+
+/// Registers a signal definition for the calling plugin and returns the id used to emit it, 0 if
+/// the signal could not be registered.\
+/// Call this within dbus_interface, only registered signals can be emitted with
+/// emit_plugin_signal.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn register_plugin_signal(signal: PluginSignal) -> u32 {
+    let Some(plugin) = current_plugin("register_plugin_signal") else {
+        return 0;
+    };
+    if Path::new(signal.path.clone()).is_err() {
+        ERROR!(
+            format!(
+                "Plugin {} used an invalid signal path: {}",
+                plugin, signal.path
+            ),
+            ErrorLevel::Recoverable
+        );
+        return 0;
+    }
+    let mut signals = PLUGIN_SIGNALS.write().unwrap();
+    let signals = signals.entry(plugin).or_default();
+    if let Some((id, _)) = signals.iter().find(|(_, registered)| *registered == signal) {
+        return *id;
+    }
+    let id = NEXT_SIGNAL_ID.fetch_add(1, Ordering::SeqCst);
+    signals.push((id, signal));
+    id
+}
+
+/// Emits a previously registered signal with the given payload, the signal is identified by the
+/// id returned from register_plugin_signal.\
+/// Like the rest of the host API this has to be called within a function the daemon calls on the
+/// plugin, which includes the handlers of the objects registered with register_plugin_object.\
+/// Returns false if the calling plugin did not register the signal or the payload does not match
+/// the registered signature.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn emit_plugin_signal(signal: u32, payload: Vec<Box<dyn RefArg>>) -> bool {
+    let Some(conn) = RELAY_CONNECTION.get() else {
+        return false;
+    };
+    let Some(plugin) = current_plugin("emit_plugin_signal") else {
+        return false;
+    };
+    let Some(registered) = get_plugin_signal(&plugin, signal) else {
+        ERROR!(
+            format!(
+                "Plugin {} emitted signal {} which it did not register",
+                plugin, signal
+            ),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    };
+    let signature: String = payload
+        .iter()
+        .map(|argument| argument.signature().to_string())
+        .collect();
+    if signature != registered.signature {
+        ERROR!(
+            format!(
+                "Plugin {} emitted {} with signature {}, expected {}",
+                plugin, registered.name, signature, registered.signature
+            ),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    let msg = Message::new_signal(registered.path, registered.interface, registered.name);
+    if msg.is_err() {
+        return false;
+    }
+    let msg = msg.unwrap().append_ref(&payload);
+    emit_signal(conn, msg);
+    true
+}
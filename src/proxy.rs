@@ -0,0 +1,258 @@
+use std::{fs, sync::Mutex};
+
+use dbus::{Message, MethodErr, Path};
+use dbus_crossroads::Crossroads;
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{create_config_directory, ERROR, LOG};
+
+use crate::{
+    authorization::authorize,
+    plugin::config::write_config_section,
+    signals::emit_signal,
+    status::{status_error, StatusCode},
+    utils::{get_daemon_config_value, DAEMON_CONFIG, PROXY},
+    DaemonData,
+};
+
+/// Table within the `[Daemon]` section of the config.
+const PROXY_CONFIG: &str = "proxy";
+/// Schemes accepted in front of the host of manual proxies.
+const PROXY_SCHEMES: &[&str] = &["http://", "https://", "socks://", "socks4://", "socks5://"];
+/// Written to the environment.d directory of systemd, which is read by the user session.
+const ENVIRONMENT_FILE: &str = "90-reset-proxy.conf";
+pub const PROXY_MODES: &[&str] = &["none", "manual", "auto"];
+
+/// Proxy as (mode, host, port, PAC url, ignored hosts).\
+/// The mode is one of PROXY_MODES, host and port are used by manual, the PAC url by auto.
+pub type ProxySettings = (String, String, u16, String, Vec<String>);
+
+/// Settings of the `[Daemon.proxy]` section of the config, SetProxySettings writes the section
+/// back to the config file.
+/// ```toml
+/// mode = "manual"
+/// host = "proxy.example.com"
+/// port = 3128
+/// pac_url = ""
+/// ignore_hosts = ["localhost", "127.0.0.1"]
+/// ```
+static PROXY_SETTINGS: Lazy<Mutex<ProxySettings>> = Lazy::new(|| {
+    let settings = get_daemon_config_value(PROXY_CONFIG)
+        .and_then(|proxy| proxy.as_table())
+        .map(proxy_from_table)
+        .unwrap_or_else(|| proxy_from_table(&toml::Table::new()));
+    Mutex::new(settings)
+});
+
+/// Single source of truth for the proxy of the session, GUIs read and change the proxy here
+/// instead of editing environment files themselves.
+pub fn setup_proxy_manager(cross: &mut Crossroads) -> dbus_crossroads::IfaceToken<DaemonData> {
+    let token = cross.register(PROXY, |c| {
+        c.signal::<(ProxySettings,), _>("ProxySettingsChanged", ("settings",));
+        c.method("GetProxySettings", (), ("settings",), move |_, _, ()| {
+            Ok((PROXY_SETTINGS.lock().unwrap().clone(),))
+        });
+        c.method_with_cr_async(
+            "SetProxySettings",
+            ("settings",),
+            ("result",),
+            move |mut ctx, cross, (settings,): (ProxySettings,)| {
+                let data: &mut DaemonData = cross.data_mut(ctx.path()).unwrap();
+                let connection = data.connection.clone();
+                let authorized = authorize(ctx.message());
+                async move {
+                    if let Err(error) = authorized.await {
+                        return ctx.reply(Err(error));
+                    }
+                    let res = set_proxy_settings(settings.clone());
+                    if res.is_ok() {
+                        let msg = Message::signal(
+                            &Path::from(DBUS_PATH!()),
+                            &PROXY.into(),
+                            &"ProxySettingsChanged".into(),
+                        )
+                        .append1(settings);
+                        emit_signal(&connection, msg);
+                    }
+                    ctx.reply(res.map(|result| (result,)))
+                }
+            },
+        );
+    });
+    token
+}
+
+/// Missing entries fall back to no proxy.
+pub fn proxy_from_table(table: &toml::Table) -> ProxySettings {
+    let text = |key: &str| {
+        table
+            .get(key)
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mode = Some(text("mode"))
+        .filter(|mode| PROXY_MODES.contains(&mode.as_str()))
+        .unwrap_or_else(|| String::from("none"));
+    let port = table
+        .get("port")
+        .and_then(|port| port.as_integer())
+        .and_then(|port| u16::try_from(port).ok())
+        .unwrap_or(0);
+    let ignore_hosts = table
+        .get("ignore_hosts")
+        .and_then(|hosts| hosts.as_array())
+        .map(|hosts| {
+            hosts
+                .iter()
+                .filter_map(|host| host.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    (mode, text("host"), port, text("pac_url"), ignore_hosts)
+}
+
+fn proxy_to_table(settings: &ProxySettings) -> toml::Table {
+    let (mode, host, port, pac_url, ignore_hosts) = settings.clone();
+    let mut table = toml::Table::new();
+    table.insert(String::from("mode"), mode.into());
+    table.insert(String::from("host"), host.into());
+    table.insert(String::from("port"), i64::from(port).into());
+    table.insert(String::from("pac_url"), pac_url.into());
+    table.insert(String::from("ignore_hosts"), ignore_hosts.into());
+    table
+}
+
+/// Host names, IPv4 and bracketed IPv6 addresses, optionally prefixed with one of PROXY_SCHEMES.
+pub fn is_valid_proxy_host(host: &str) -> bool {
+    let host = PROXY_SCHEMES
+        .iter()
+        .find_map(|scheme| host.strip_prefix(scheme))
+        .unwrap_or(host);
+    if let Some(address) = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+    {
+        return !address.is_empty()
+            && address
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.');
+    }
+    !host.is_empty()
+        && !host.starts_with('-')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+/// Entries of no_proxy, e.g. localhost, .example.com, *.example.com or 10.0.0.0/8.
+pub fn is_valid_ignore_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '*' | ':' | '/' | '[' | ']')
+        })
+}
+
+/// Settings end up in the environment of the whole session, values are therefore restricted to
+/// characters which can not break out of their variable.
+pub fn validate_proxy_settings(settings: &ProxySettings) -> Result<(), MethodErr> {
+    let (mode, host, port, pac_url, ignore_hosts) = settings;
+    if let Some(ignored) = ignore_hosts
+        .iter()
+        .find(|ignored| !is_valid_ignore_host(ignored))
+    {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            &format!("Invalid host to ignore: {:?}", ignored),
+        ));
+    }
+    if !host.is_empty() && !is_valid_proxy_host(host) {
+        return Err(status_error(
+            StatusCode::InvalidArgument,
+            "Invalid proxy host",
+        ));
+    }
+    if pac_url
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '\\')
+    {
+        return Err(status_error(StatusCode::InvalidArgument, "Invalid PAC url"));
+    }
+    match mode.as_str() {
+        "none" => Ok(()),
+        "manual" if host.is_empty() || *port == 0 => Err(status_error(
+            StatusCode::InvalidArgument,
+            "A manual proxy requires a host and a port",
+        )),
+        "manual" => Ok(()),
+        "auto" if pac_url.is_empty() => Err(status_error(
+            StatusCode::InvalidArgument,
+            "An automatic proxy requires a PAC url",
+        )),
+        "auto" => Ok(()),
+        _ => Err(status_error(
+            StatusCode::InvalidArgument,
+            "Unknown proxy mode",
+        )),
+    }
+}
+
+/// Writes the settings to the config and the environment file.\
+/// Returns false if either could not be written, the settings are still used by the daemon.
+fn set_proxy_settings(settings: ProxySettings) -> Result<bool, MethodErr> {
+    validate_proxy_settings(&settings)?;
+    let mut current = PROXY_SETTINGS.lock().unwrap();
+    *current = settings;
+    LOG!(format!("Proxy set to {}", current.0));
+    let saved = write_config_section(DAEMON_CONFIG, PROXY_CONFIG, &proxy_to_table(&current));
+    Ok(saved && write_environment_file(&current))
+}
+
+fn write_environment_file(settings: &ProxySettings) -> bool {
+    let path = create_config_directory("environment.d").map(|dir| dir.join(ENVIRONMENT_FILE));
+    if path.is_none() {
+        return false;
+    }
+    let res = fs::write(path.unwrap(), environment_file(settings));
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not write proxy environment file: {}", _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    true
+}
+
+/// Creates the environment file with the proxy variables, in lower and upper case as
+/// applications disagree on the name.\
+/// Values are quoted, the settings have to be validated with validate_proxy_settings.\
+/// PAC files can not be expressed as environment variables, the file is therefore empty for
+/// automatic proxies and applications have to query GetProxySettings.
+pub fn environment_file(settings: &ProxySettings) -> String {
+    let mut contents = String::from("# generated by ReSet, changes will be overwritten\n");
+    let (mode, host, port, _, ignore_hosts) = settings;
+    if mode != "manual" {
+        return contents;
+    }
+    let url = if host.contains("://") {
+        format!("{}:{}", host, port)
+    } else {
+        format!("http://{}:{}", host, port)
+    };
+    let mut variables = vec![
+        ("http_proxy", url.clone()),
+        ("https_proxy", url.clone()),
+        ("ftp_proxy", url.clone()),
+        ("all_proxy", url),
+    ];
+    if !ignore_hosts.is_empty() {
+        variables.push(("no_proxy", ignore_hosts.join(",")));
+    }
+    for (name, value) in variables {
+        contents.push_str(&format!("{}=\"{}\"\n", name, value));
+        contents.push_str(&format!("{}=\"{}\"\n", name.to_uppercase(), value));
+    }
+    contents
+}
@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::Sender;
+use dbus::{blocking::Connection, message::MatchRule, nonblock::SyncConnection, Message, Path};
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    bluetooth::{
+        bluetooth_manager::{get_all_bluetooth_devices, BluetoothInterface},
+        scan::SharedScanState,
+    },
+    network::network_manager::{supervise_listener, Device},
+    signals::emit_signal,
+    utils::{session_connection, AudioRequest, BASE},
+    DaemonData,
+};
+
+/// Time given to NetworkManager and BlueZ to come back up after resuming.
+const RESUME_DELAY: Duration = Duration::from_secs(2);
+const LISTENER_STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Handles of the daemon data used by the resync, which blocks while waiting for the listeners
+/// and therefore runs outside of crossroads.
+pub struct ResyncTargets {
+    connection: Arc<SyncConnection>,
+    current_n_device: Arc<RwLock<Device>>,
+    network_listener_active: Arc<AtomicBool>,
+    network_stop_requested: Arc<AtomicBool>,
+    b_interface: BluetoothInterface,
+    bluetooth_listener_active: Arc<AtomicBool>,
+    bluetooth_stop_requested: Arc<AtomicBool>,
+    bluetooth_scan: SharedScanState,
+    audio_sender: Arc<Sender<AudioRequest>>,
+}
+
+impl ResyncTargets {
+    pub fn new(data: &DaemonData) -> Self {
+        Self {
+            connection: data.connection.clone(),
+            current_n_device: data.current_n_device.clone(),
+            network_listener_active: data.network_listener_active.clone(),
+            network_stop_requested: data.network_stop_requested.clone(),
+            b_interface: data.b_interface.clone(),
+            bluetooth_listener_active: data.bluetooth_listener_active.clone(),
+            bluetooth_stop_requested: data.bluetooth_stop_requested.clone(),
+            bluetooth_scan: data.bluetooth_scan.clone(),
+            audio_sender: data.audio_sender.clone(),
+        }
+    }
+}
+
+/// Re-probes NetworkManager, BlueZ and pulse, restarts active listeners and emits the added
+/// signals for all current objects, as clients will hold stale lists otherwise.\
+/// Blocks until the listeners are restarted.
+pub fn resync_all(data: ResyncTargets) {
+    LOG!("Resynchronizing daemon state");
+    resync_network(&data);
+    resync_bluetooth(&data);
+    let _ = data.audio_sender.send(AudioRequest::Resync);
+}
+
+fn resync_network(data: &ResyncTargets) {
+    if data.current_n_device.read().unwrap().dbus_path.is_empty() {
+        return;
+    }
+    data.current_n_device.write().unwrap().initialize();
+    if data.network_listener_active.load(Ordering::SeqCst) {
+        data.network_stop_requested.store(true, Ordering::SeqCst);
+        if wait_for_listener_stop(&data.network_listener_active) {
+            let connection = data.connection.clone();
+            let device = data.current_n_device.clone();
+            let active_listener = data.network_listener_active.clone();
+            let stop_requested = data.network_stop_requested.clone();
+            supervise_listener(connection, device, active_listener, stop_requested);
+        }
+    }
+    let device = data.current_n_device.read().unwrap().dbus_path.clone();
+    let access_points = data.current_n_device.read().unwrap().get_access_points();
+    for access_point in access_points {
+        let msg = Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &NETWORK_INTERFACE!().into(),
+            &"AccessPointAdded".into(),
+        )
+        .append2(access_point, device.clone());
+        emit_signal(&data.connection, msg);
+    }
+}
+
+fn resync_bluetooth(data: &ResyncTargets) {
+    if &*data.b_interface.current_adapter == "/" {
+        return;
+    }
+    if data.bluetooth_listener_active.load(Ordering::SeqCst) {
+        data.bluetooth_stop_requested.store(true, Ordering::SeqCst);
+        if wait_for_listener_stop(&data.bluetooth_listener_active) {
+            data.b_interface.start_bluetooth_listener(
+                data.bluetooth_listener_active.clone(),
+                data.bluetooth_scan.clone(),
+                data.bluetooth_stop_requested.clone(),
+            );
+        }
+    }
+    for device in get_all_bluetooth_devices() {
+        let msg = Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &BLUETOOTH_INTERFACE!().into(),
+            &"BluetoothDeviceAdded".into(),
+        )
+        .append1(device);
+        emit_signal(&data.connection, msg);
+    }
+}
+
+fn wait_for_listener_stop(active_listener: &AtomicBool) -> bool {
+    let start = Instant::now();
+    while active_listener.load(Ordering::SeqCst) {
+        if start.elapsed() > LISTENER_STOP_TIMEOUT {
+            ERROR!(
+                "Listener did not stop in time, skipping restart",
+                ErrorLevel::Recoverable
+            );
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
+
+/// Listens to PrepareForSleep of logind and triggers ResyncAll after resuming.
+pub fn start_sleep_listener() {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let rule = MatchRule::new_signal(LOGIND_MANAGER_INTERFACE!(), "PrepareForSleep");
+        let resume_requested = Arc::new(AtomicBool::new(false));
+        let resume_ref = resume_requested.clone();
+        let res = conn.add_match(rule, move |(sleeping,): (bool,), _, _| {
+            if !sleeping {
+                resume_ref.store(true, Ordering::SeqCst);
+            }
+            true
+        });
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Signal Match on logind failed: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on logind.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+            if resume_requested.swap(false, Ordering::SeqCst) {
+                LOG!("Resumed from sleep");
+                thread::sleep(RESUME_DELAY);
+                request_resync();
+            }
+        }
+    });
+}
+
+/// The daemon data is owned by crossroads, hence the resync is requested via the daemon's own
+/// dbus interface.
+pub fn request_resync() {
+    let conn = session_connection();
+    if conn.is_err() {
+        return;
+    }
+    let conn = conn.unwrap();
+    let proxy = conn.with_proxy(BASE, DBUS_PATH!(), Duration::from_millis(10000));
+    let res: Result<(), dbus::Error> = proxy.method_call(BASE, "ResyncAll", ());
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not resync after resume: {}", _error),
+            ErrorLevel::Recoverable
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path as FsPath,
+};
+
+use dbus::{MethodErr, Path};
+use re_set_lib::LOG;
+
+use crate::status::{status_error, StatusCode};
+
+const RFKILL_CLASS: &str = "/sys/class/rfkill";
+const BLUETOOTH_CLASS: &str = "/sys/class/bluetooth";
+const RFKILL_DEVICE: &str = "/dev/rfkill";
+/// Applies the state to every rfkill switch of a type, see linux/rfkill.h.
+const RFKILL_OP_CHANGE_ALL: u8 = 3;
+
+/// Rfkill state as (soft blocked, hard blocked).\
+/// Soft blocks can be lifted with SetRfkillBlocked, hard blocks require a hardware switch.
+pub type RfkillState = (bool, bool);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfkillKind {
+    Wlan,
+    Bluetooth,
+    Wwan,
+}
+
+impl RfkillKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wlan" => Some(RfkillKind::Wlan),
+            "bluetooth" => Some(RfkillKind::Bluetooth),
+            "wwan" => Some(RfkillKind::Wwan),
+            _ => None,
+        }
+    }
+
+    /// Name used by the type attribute in sysfs.
+    fn name(self) -> &'static str {
+        match self {
+            RfkillKind::Wlan => "wlan",
+            RfkillKind::Bluetooth => "bluetooth",
+            RfkillKind::Wwan => "wwan",
+        }
+    }
+
+    /// Type used by rfkill events, see linux/rfkill.h.
+    fn event_type(self) -> u8 {
+        match self {
+            RfkillKind::Wlan => 1,
+            RfkillKind::Bluetooth => 2,
+            RfkillKind::Wwan => 5,
+        }
+    }
+}
+
+fn read_flag(switch: &FsPath, attribute: &str) -> bool {
+    fs::read_to_string(switch.join(attribute)).is_ok_and(|value| value.trim() == "1")
+}
+
+fn read_state(switch: &FsPath) -> RfkillState {
+    (read_flag(switch, "soft"), read_flag(switch, "hard"))
+}
+
+/// Returns whether any switch of the kind is blocked, systems without a switch of the kind are
+/// reported as unblocked.
+pub fn get_rfkill_state(kind: RfkillKind) -> RfkillState {
+    let Ok(switches) = fs::read_dir(RFKILL_CLASS) else {
+        return (false, false);
+    };
+    switches
+        .flatten()
+        .map(|switch| switch.path())
+        .filter(|switch| {
+            fs::read_to_string(switch.join("type")).is_ok_and(|name| name.trim() == kind.name())
+        })
+        .map(|switch| read_state(&switch))
+        .fold(
+            (false, false),
+            |(soft, hard), (switch_soft, switch_hard)| (soft || switch_soft, hard || switch_hard),
+        )
+}
+
+/// Returns the state of the switch belonging to a bluez adapter, e.g. /org/bluez/hci0.
+pub fn get_adapter_rfkill_state(adapter: &Path<'static>) -> Option<RfkillState> {
+    let name = adapter.rsplit('/').next()?;
+    let switches = fs::read_dir(FsPath::new(BLUETOOTH_CLASS).join(name)).ok()?;
+    switches
+        .flatten()
+        .find(|entry| entry.file_name().to_string_lossy().starts_with("rfkill"))
+        .map(|switch| read_state(&switch.path()))
+}
+
+/// Soft blocks or unblocks every switch of the kind.\
+/// Writing to /dev/rfkill is usually granted to the user of the active session, other users
+/// receive an error.
+pub fn set_rfkill_blocked(kind: RfkillKind, blocked: bool) -> Result<bool, MethodErr> {
+    let device = OpenOptions::new().write(true).open(RFKILL_DEVICE);
+    let mut device = device.map_err(|error| {
+        status_error(
+            StatusCode::Failed,
+            &format!("Failed to open {}: {}", RFKILL_DEVICE, error),
+        )
+    })?;
+    // struct rfkill_event: idx, type, op, soft, hard
+    let mut event = [0_u8; 8];
+    event[4] = kind.event_type();
+    event[5] = RFKILL_OP_CHANGE_ALL;
+    event[6] = blocked as u8;
+    if let Err(error) = device.write_all(&event) {
+        return Err(status_error(
+            StatusCode::Failed,
+            &format!("Failed to change the rfkill state: {}", error),
+        ));
+    }
+    LOG!(format!(
+        "Set rfkill soft block of {} to {}",
+        kind.name(),
+        blocked
+    ));
+    // unblocking has no effect while a hard block is in place
+    Ok(blocked || !get_rfkill_state(kind).1)
+}
+
+/// Error returned when a radio can not be enabled because of rfkill.
+pub fn blocked_error(name: &str, (_, hard): RfkillState) -> MethodErr {
+    let reason = if hard {
+        "hard blocked by rfkill, use the hardware switch to unblock it"
+    } else {
+        "soft blocked by rfkill, unblock it with SetRfkillBlocked"
+    };
+    status_error(
+        StatusCode::RfkillBlocked,
+        &format!("{} is {}.", name, reason),
+    )
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{channel::Sender, nonblock::SyncConnection, strings::BusName, Message, Path};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::ERROR;
+
+use crate::{
+    audio::delta::{delta_delivery, forget_removed, AudioObject, Delivery},
+    network::strength_threshold::{track_access_point_signal, AccessPointState, StrengthFilter},
+    utils::AUDIO,
+};
+
+/// Clients that requested throttled signals, keyed by their unique bus name.
+static THROTTLED_CLIENTS: Lazy<Mutex<HashMap<String, ClientThrottle>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static FLUSHER_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default)]
+struct ClientThrottle {
+    intervals: HashMap<SignalClass, Duration>,
+    last_sent: HashMap<String, Instant>,
+    pending: HashMap<String, (Message, Instant)>,
+    /// Whether changed audio signals are replaced by their delta variant.
+    delta: bool,
+    /// Audio objects delivered to a delta client, keyed like the pending signals.
+    delivered: HashMap<String, AudioObject>,
+    /// Strength threshold of the client, replaces the configured threshold of AccessPointChanged.
+    strength_filter: Option<StrengthFilter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignalClass {
+    Audio,
+    Network,
+    Bluetooth,
+}
+
+impl SignalClass {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Audio" => Some(SignalClass::Audio),
+            "Network" => Some(SignalClass::Network),
+            "Bluetooth" => Some(SignalClass::Bluetooth),
+            _ => None,
+        }
+    }
+
+    fn from_interface(interface: &str) -> Option<Self> {
+        if interface == AUDIO {
+            Some(SignalClass::Audio)
+        } else if interface == NETWORK_INTERFACE!() {
+            Some(SignalClass::Network)
+        } else if interface == BLUETOOTH_INTERFACE!() {
+            Some(SignalClass::Bluetooth)
+        } else {
+            None
+        }
+    }
+}
+
+/// Registers the minimum interval between changed signals of each class for a client.\
+/// Throttled clients receive every signal addressed to their unique bus name instead of the
+/// broadcast.
+pub fn register_throttled_client(
+    conn: Arc<SyncConnection>,
+    sender: String,
+    intervals: HashMap<SignalClass, Duration>,
+) {
+    THROTTLED_CLIENTS
+        .lock()
+        .unwrap()
+        .entry(sender)
+        .or_default()
+        .intervals = intervals;
+    start_flusher(conn);
+}
+
+/// Registers a client for delta signals, changed audio signals are replaced by
+/// (index, changed fields mask, values) once the client received the full object.\
+/// Like throttled clients, delta clients receive every signal addressed to their unique bus name.
+pub fn register_delta_client(conn: Arc<SyncConnection>, sender: String) {
+    THROTTLED_CLIENTS
+        .lock()
+        .unwrap()
+        .entry(sender)
+        .or_default()
+        .delta = true;
+    start_flusher(conn);
+}
+
+/// Registers a strength threshold for AccessPointChanged, the client receives a change once the
+/// strength differs by at least the threshold from the last strength delivered to it.\
+/// Like throttled clients, these clients receive every signal addressed to their unique bus name.
+pub fn register_strength_threshold_client(
+    conn: Arc<SyncConnection>,
+    sender: String,
+    threshold: u8,
+) {
+    THROTTLED_CLIENTS
+        .lock()
+        .unwrap()
+        .entry(sender)
+        .or_default()
+        .strength_filter = Some(StrengthFilter::new(threshold));
+    start_flusher(conn);
+}
+
+fn start_flusher(conn: Arc<SyncConnection>) {
+    if !FLUSHER_ACTIVE.swap(true, Ordering::SeqCst) {
+        thread::spawn(move || flush_pending(conn));
+    }
+}
+
+pub fn unregister_throttled_client(sender: &str) {
+    THROTTLED_CLIENTS.lock().unwrap().remove(sender);
+}
+
+/// Sends a signal immediately to every listener.
+pub fn emit_signal(conn: &Arc<SyncConnection>, msg: Message) {
+    emit(conn, msg, None, None);
+}
+
+/// Sends a signal that reports a change of the object identified by key.\
+/// Throttled clients receive at most one of these per object and interval, the latest state is
+/// delivered once the interval has passed.
+pub fn emit_changed_signal(conn: &Arc<SyncConnection>, msg: Message, key: impl ToString) {
+    emit(conn, msg, Some(key.to_string()), None);
+}
+
+/// Sends AccessPointChanged, which the listener only broadcasts once the configured strength
+/// threshold was reached.\
+/// Clients with their own threshold receive the change whenever their threshold is reached.
+pub fn emit_access_point_changed(
+    conn: &Arc<SyncConnection>,
+    msg: Message,
+    path: Path<'static>,
+    state: AccessPointState,
+    broadcast: bool,
+) {
+    let key = path.to_string();
+    emit(conn, msg, Some(key), Some((path, state, broadcast)));
+}
+
+fn emit(
+    conn: &Arc<SyncConnection>,
+    msg: Message,
+    key: Option<String>,
+    access_point: Option<(Path<'static>, AccessPointState, bool)>,
+) {
+    {
+        let mut clients = THROTTLED_CLIENTS.lock().unwrap();
+        let class = msg
+            .interface()
+            .and_then(|interface| SignalClass::from_interface(&interface));
+        let member = msg.member().map(|member| member.to_string());
+        for (client, throttle) in clients.iter_mut() {
+            if let Some(filter) = &mut throttle.strength_filter {
+                track_access_point_signal(&msg, filter);
+            }
+            if let Some((path, state, broadcast)) = &access_point {
+                let deliver = match &mut throttle.strength_filter {
+                    Some(filter) => filter.accept(path, state.clone()),
+                    None => *broadcast,
+                };
+                if !deliver {
+                    continue;
+                }
+            }
+            let (Some(key), Some(member)) = (&key, &member) else {
+                if throttle.delta {
+                    forget_removed(&msg, &mut throttle.delivered);
+                }
+                send_to(conn, &msg, Some(client));
+                continue;
+            };
+            let key = format!("{}{}", member, key);
+            let interval = class.and_then(|class| throttle.intervals.get(&class));
+            let Some(interval) = interval.copied() else {
+                send_changed_to(conn, &msg, client, throttle, &key);
+                continue;
+            };
+            let now = Instant::now();
+            let due = throttle
+                .last_sent
+                .get(&key)
+                .map(|last| *last + interval)
+                .unwrap_or(now);
+            if due <= now {
+                throttle.pending.remove(&key);
+                throttle.last_sent.insert(key.clone(), now);
+                send_changed_to(conn, &msg, client, throttle, &key);
+            } else if let Ok(duplicate) = msg.duplicate() {
+                throttle.pending.insert(key, (duplicate, due));
+            }
+        }
+    }
+    // clients with per-client delivery negotiated it in RegisterClient and only match signals
+    // addressed to them, the broadcast is left to all other listeners
+    if access_point.is_none_or(|(_, _, broadcast)| broadcast) {
+        send_to(conn, &msg, None);
+    }
+}
+
+/// Sends a changed signal to a single client, as delta if the client requested it.
+fn send_changed_to(
+    conn: &Arc<SyncConnection>,
+    msg: &Message,
+    client: &String,
+    throttle: &mut ClientThrottle,
+    key: &str,
+) {
+    if !throttle.delta {
+        send_to(conn, msg, Some(client));
+        return;
+    }
+    match delta_delivery(msg, &mut throttle.delivered, key) {
+        Delivery::Full => send_to(conn, msg, Some(client)),
+        Delivery::Delta(delta) => send_to(conn, &delta, Some(client)),
+        Delivery::Unchanged => (),
+    }
+}
+
+fn send_to(conn: &Arc<SyncConnection>, msg: &Message, destination: Option<&String>) {
+    let msg = msg.duplicate();
+    if let Err(_error) = msg {
+        ERROR!(
+            format!("Could not copy signal: {}", _error),
+            ErrorLevel::PartialBreakage
+        );
+        return;
+    }
+    let mut msg = msg.unwrap();
+    if let Some(destination) = destination {
+        msg.set_destination(Some(BusName::from(destination.clone())));
+    }
+    if conn.send(msg).is_err() {
+        ERROR!("Could not send signal", ErrorLevel::PartialBreakage);
+    }
+}
+
+fn flush_pending(conn: Arc<SyncConnection>) {
+    loop {
+        thread::sleep(Duration::from_millis(100));
+        let mut clients = THROTTLED_CLIENTS.lock().unwrap();
+        if clients.is_empty() {
+            FLUSHER_ACTIVE.store(false, Ordering::SeqCst);
+            return;
+        }
+        let now = Instant::now();
+        for (client, throttle) in clients.iter_mut() {
+            let due: Vec<String> = throttle
+                .pending
+                .iter()
+                .filter(|(_, (_, due))| *due <= now)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in due {
+                if let Some((msg, _)) = throttle.pending.remove(&key) {
+                    send_changed_to(&conn, &msg, client, throttle, &key);
+                    throttle.last_sent.insert(key, now);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use dbus::{blocking::Connection, Path};
+
+use crate::{
+    battery::battery_manager::{UPOWER_NAME, UPOWER_PATH},
+    network::speedtest::speedtest_available,
+    notifications::{detect_backend, NotificationBackend},
+    utils::get_daemon_config_value,
+};
+
+pub const STARTUP_AUDIO: &str = "Audio";
+pub const STARTUP_WIFI: &str = "WiFi";
+pub const STARTUP_BLUETOOTH: &str = "Bluetooth";
+/// Subsystems initialized concurrently on startup.
+pub const STARTUP_TASKS: &[&str] = &[STARTUP_AUDIO, STARTUP_WIFI, STARTUP_BLUETOOTH];
+
+/// Features which can be disabled within the `[Daemon]` section of the config as (capability,
+/// config key).
+/// ```toml
+/// [Daemon]
+/// audio = false
+/// ```
+/// Disabled features are neither set up nor probed and are omitted from the capabilities.
+pub const FEATURE_FLAGS: &[(&str, &str)] = &[
+    ("WiFi", "wifi"),
+    ("Bluetooth", "bluetooth"),
+    ("Modem", "modem"),
+    ("Battery", "battery"),
+    ("Audio", "audio"),
+    ("Media", "media"),
+    ("Proxy", "proxy"),
+    ("DoNotDisturb", "do_not_disturb"),
+    ("SpeedTest", "speedtest"),
+];
+
+/// Returns false if the capability was disabled in the config, capabilities of plugins are
+/// always enabled.
+pub fn feature_enabled(capability: &str) -> bool {
+    FEATURE_FLAGS
+        .iter()
+        .find(|(name, _)| *name == capability)
+        .and_then(|(_, key)| get_daemon_config_value(key))
+        .and_then(|enabled| enabled.as_bool())
+        .unwrap_or(true)
+}
+
+/// Services found on startup, probed concurrently as each probe waits for a timeout when the
+/// service is missing.
+pub struct FeatureProbe {
+    pub wifi: bool,
+    pub bluetooth: bool,
+    pub modem: bool,
+    pub battery: bool,
+    pub do_not_disturb: Option<NotificationBackend>,
+    pub speedtest: bool,
+}
+
+pub async fn probe_features() -> FeatureProbe {
+    let introspect = |capability: &'static str, service: &'static str, path: &'static str| {
+        tokio::task::spawn_blocking(move || {
+            feature_enabled(capability)
+                && dbus_method!(
+                    service,
+                    Path::from(path),
+                    "Introspect",
+                    "org.freedesktop.DBus.Introspectable",
+                    (),
+                    100,
+                    (),
+                )
+                .is_ok()
+        })
+    };
+    let (wifi, bluetooth, modem, battery, do_not_disturb, speedtest) = tokio::join!(
+        introspect("WiFi", NM_INTERFACE_BASE!(), NM_PATH!()),
+        introspect("Bluetooth", BLUEZ_INTERFACE!(), "/"),
+        introspect("Modem", MM_INTERFACE_BASE!(), MM_PATH!()),
+        introspect("Battery", UPOWER_NAME, UPOWER_PATH),
+        tokio::task::spawn_blocking(|| feature_enabled("DoNotDisturb")
+            .then(detect_backend)
+            .flatten()),
+        tokio::task::spawn_blocking(|| feature_enabled("SpeedTest") && speedtest_available()),
+    );
+    let wifi = wifi.unwrap_or(false);
+    FeatureProbe {
+        wifi,
+        bluetooth: bluetooth.unwrap_or(false),
+        // connections of modems are activated through NetworkManager
+        modem: wifi && modem.unwrap_or(false),
+        battery: battery.unwrap_or(false),
+        do_not_disturb: do_not_disturb.unwrap_or(None),
+        speedtest: speedtest.unwrap_or(false),
+    }
+}
+
+/// Number of STARTUP_TASKS which have to finish before the daemon is ready, configured with
+/// startup_quorum within the `[Daemon]` section of the config.\
+/// Defaults to all tasks.
+pub fn startup_quorum() -> usize {
+    get_daemon_config_value("startup_quorum")
+        .and_then(|quorum| quorum.as_integer())
+        .map(|quorum| quorum.clamp(1, STARTUP_TASKS.len() as i64) as usize)
+        .unwrap_or(STARTUP_TASKS.len())
+}
+
+#[derive(Default)]
+struct StartupState {
+    finished: Vec<(&'static str, bool)>,
+    ready: bool,
+}
+
+/// Tracks the subsystems started concurrently by DaemonData::create.\
+/// WiFi and Bluetooth devices are always awaited as the interfaces are built from them, with a
+/// lower quorum the audio server finishes connecting in the background instead.
+pub struct StartupTasks {
+    state: Mutex<StartupState>,
+    changed: Condvar,
+    quorum: usize,
+}
+
+impl StartupTasks {
+    pub fn new(quorum: usize) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(StartupState::default()),
+            changed: Condvar::new(),
+            quorum,
+        })
+    }
+
+    /// Marks the task as finished, returns true if the daemon was already ready.
+    pub fn finish(&self, task: &'static str, success: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.finished.push((task, success));
+        self.changed.notify_all();
+        state.ready
+    }
+
+    pub fn wait_for_quorum(&self) {
+        let mut state = self
+            .changed
+            .wait_while(self.state.lock().unwrap(), |state| {
+                state.finished.len() < self.quorum
+            })
+            .unwrap();
+        state.ready = true;
+    }
+
+    /// Returns true if the task finished unsuccessfully, pending tasks have not failed yet.
+    pub fn failed(&self, task: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .finished
+            .iter()
+            .any(|(name, success)| *name == task && !success)
+    }
+}
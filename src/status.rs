@@ -0,0 +1,152 @@
+use std::fmt;
+
+use dbus::MethodErr;
+
+const ERROR_PREFIX: &str = "org.Xetibo.ReSet.Error.";
+
+/// Stable codes of user visible failures, GUIs translate the code and show the text of the
+/// error only as detail.\
+/// Errors use the dbus error name of the code, signals reporting failures carry the name of the
+/// code, e.g. WrongPassword. Codes are never renamed or removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCode {
+    Ok,
+    /// Failures without a more specific code.
+    Failed,
+    InvalidArgument,
+    AuthorizationDenied,
+    RfkillBlocked,
+    HandlerPanicked,
+    /// A service like NetworkManager, BlueZ or the notification daemon is not running.
+    ServiceUnavailable,
+    PulseUnavailable,
+    DeviceNotFound,
+    AdapterNotFound,
+    /// The device exists but is of the wrong kind, e.g. ethernet instead of WiFi.
+    WrongDeviceType,
+    NotConnected,
+    ConnectionNotFound,
+    ApNotFound,
+    WrongPassword,
+    SecretsUnavailable,
+    ConnectionFailed,
+    PairingFailed,
+    Timeout,
+    Cancelled,
+    ServerUnreachable,
+    Unsupported,
+    /// The operation is already running or blocked by another one.
+    Busy,
+}
+
+impl StatusCode {
+    pub const ALL: &'static [StatusCode] = &[
+        StatusCode::Ok,
+        StatusCode::Failed,
+        StatusCode::InvalidArgument,
+        StatusCode::AuthorizationDenied,
+        StatusCode::RfkillBlocked,
+        StatusCode::HandlerPanicked,
+        StatusCode::ServiceUnavailable,
+        StatusCode::PulseUnavailable,
+        StatusCode::DeviceNotFound,
+        StatusCode::AdapterNotFound,
+        StatusCode::WrongDeviceType,
+        StatusCode::NotConnected,
+        StatusCode::ConnectionNotFound,
+        StatusCode::ApNotFound,
+        StatusCode::WrongPassword,
+        StatusCode::SecretsUnavailable,
+        StatusCode::ConnectionFailed,
+        StatusCode::PairingFailed,
+        StatusCode::Timeout,
+        StatusCode::Cancelled,
+        StatusCode::ServerUnreachable,
+        StatusCode::Unsupported,
+        StatusCode::Busy,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusCode::Ok => "Ok",
+            StatusCode::Failed => "Failed",
+            StatusCode::InvalidArgument => "InvalidArgument",
+            StatusCode::AuthorizationDenied => "AuthorizationDenied",
+            StatusCode::RfkillBlocked => "RfkillBlocked",
+            StatusCode::HandlerPanicked => "HandlerPanicked",
+            StatusCode::ServiceUnavailable => "ServiceUnavailable",
+            StatusCode::PulseUnavailable => "PulseUnavailable",
+            StatusCode::DeviceNotFound => "DeviceNotFound",
+            StatusCode::AdapterNotFound => "AdapterNotFound",
+            StatusCode::WrongDeviceType => "WrongDeviceType",
+            StatusCode::NotConnected => "NotConnected",
+            StatusCode::ConnectionNotFound => "ConnectionNotFound",
+            StatusCode::ApNotFound => "ApNotFound",
+            StatusCode::WrongPassword => "WrongPassword",
+            StatusCode::SecretsUnavailable => "SecretsUnavailable",
+            StatusCode::ConnectionFailed => "ConnectionFailed",
+            StatusCode::PairingFailed => "PairingFailed",
+            StatusCode::Timeout => "Timeout",
+            StatusCode::Cancelled => "Cancelled",
+            StatusCode::ServerUnreachable => "ServerUnreachable",
+            StatusCode::Unsupported => "Unsupported",
+            StatusCode::Busy => "Busy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|code| code.name() == name).copied()
+    }
+
+    /// Invalid arguments and generic failures keep the error names of dbus, which clients
+    /// already handle.
+    pub fn error_name(&self) -> String {
+        match self {
+            StatusCode::InvalidArgument => String::from("org.freedesktop.DBus.Error.InvalidArgs"),
+            StatusCode::Failed => String::from("org.freedesktop.DBus.Error.Failed"),
+            code => format!("{}{}", ERROR_PREFIX, code.name()),
+        }
+    }
+
+    /// Returns the code of a dbus error name, unknown errors are Failed.
+    pub fn from_error_name(error_name: &str) -> Self {
+        Self::ALL
+            .iter()
+            .find(|code| code.error_name() == error_name)
+            .copied()
+            .unwrap_or(StatusCode::Failed)
+    }
+}
+
+/// Failure with its code and a free-form detail for logs and as secondary text within GUIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusError {
+    pub code: StatusCode,
+    pub detail: String,
+}
+
+impl StatusError {
+    pub fn new(code: StatusCode, detail: impl Into<String>) -> Self {
+        Self {
+            code,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code.name(), self.detail)
+    }
+}
+
+impl From<StatusError> for MethodErr {
+    fn from(error: StatusError) -> Self {
+        status_error(error.code, &error.detail)
+    }
+}
+
+/// Creates the dbus error of the code with the detail as message.
+pub fn status_error(code: StatusCode, detail: &str) -> MethodErr {
+    MethodErr::from((code.error_name(), detail.to_string()))
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Mutex, RwLock},
+};
+
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{create_config_directory, ERROR, LOG};
+
+use crate::utils::get_daemon_config_value;
+
+/// Persistence of the state files of the daemon, like device aliases, routing rules and
+/// histories.\
+/// Each state file is a toml table identified by its file name, backends decide how the tables
+/// are stored.
+pub trait StateStorage: Send + Sync {
+    /// Returns an empty table if nothing was stored under the name yet.
+    fn load(&self, name: &str) -> toml::Table;
+    /// Returns false if the table could not be stored.
+    fn save(&self, name: &str, table: &toml::Table) -> bool;
+    /// Name of the backend, e.g. for the logs.
+    fn backend(&self) -> &'static str;
+}
+
+/// Backend chosen with `storage_backend` within the `[Daemon]` section of the config.
+/// ```toml
+/// [Daemon]
+/// storage_backend = "sqlite"
+/// ```
+/// file is the default, sqlite is only available when the daemon was built with the sqlite
+/// feature and otherwise falls back to file.
+static STORAGE: Lazy<RwLock<Box<dyn StateStorage>>> =
+    Lazy::new(|| RwLock::new(configured_storage()));
+
+fn configured_storage() -> Box<dyn StateStorage> {
+    let backend = get_daemon_config_value("storage_backend")
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default();
+    let storage: Box<dyn StateStorage> = match backend.as_str() {
+        #[cfg(feature = "sqlite")]
+        "sqlite" => match SqliteStorage::open_default() {
+            Some(storage) => Box::new(storage),
+            None => Box::new(FileStorage::default()),
+        },
+        "" | "file" => Box::new(FileStorage::default()),
+        _backend => {
+            ERROR!(
+                format!(
+                    "Storage backend {} is not available, using file instead",
+                    _backend
+                ),
+                ErrorLevel::Recoverable
+            );
+            Box::new(FileStorage::default())
+        }
+    };
+    LOG!(format!(
+        "Using {} storage for state files",
+        storage.backend()
+    ));
+    storage
+}
+
+/// Replaces the storage of all state files, e.g. when the daemon is embedded into an application
+/// with its own persistence.\
+/// Features that already loaded their state keep it until they save again.
+pub fn set_state_storage(storage: Box<dyn StateStorage>) {
+    *STORAGE.write().unwrap() = storage;
+}
+
+pub fn load_state(name: &str) -> toml::Table {
+    STORAGE.read().unwrap().load(name)
+}
+
+pub fn save_state(name: &str, table: &toml::Table) -> bool {
+    STORAGE.read().unwrap().save(name, table)
+}
+
+/// Stores each state file as a toml file within a directory, by default the ReSet config
+/// directory.
+pub struct FileStorage {
+    directory: Option<PathBuf>,
+}
+
+impl FileStorage {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory: Some(directory),
+        }
+    }
+}
+
+impl Default for FileStorage {
+    fn default() -> Self {
+        Self {
+            directory: create_config_directory("reset"),
+        }
+    }
+}
+
+impl StateStorage for FileStorage {
+    fn load(&self, name: &str) -> toml::Table {
+        let Some(directory) = &self.directory else {
+            return toml::Table::new();
+        };
+        let contents = fs::read_to_string(directory.join(name));
+        if contents.is_err() {
+            return toml::Table::new();
+        }
+        parse_state(name, &contents.unwrap())
+    }
+
+    fn save(&self, name: &str, table: &toml::Table) -> bool {
+        let Some(directory) = &self.directory else {
+            return false;
+        };
+        let res = fs::write(directory.join(name), table.to_string());
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Could not write state file {}: {}", name, _error),
+                ErrorLevel::Recoverable
+            );
+            return false;
+        }
+        true
+    }
+
+    fn backend(&self) -> &'static str {
+        "file"
+    }
+}
+
+/// Keeps the state files in memory only, used for tests and sessions that must not leave traces.
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<HashMap<String, toml::Table>>,
+}
+
+impl StateStorage for MemoryStorage {
+    fn load(&self, name: &str) -> toml::Table {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn save(&self, name: &str, table: &toml::Table) -> bool {
+        self.tables
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), table.clone());
+        true
+    }
+
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+}
+
+/// Stores all state files as rows of a single SQLite database, which avoids partially written
+/// files when the daemon is stopped during a write.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    const DATABASE_FILE: &'static str = "state.sqlite";
+
+    pub fn open(path: &std::path::Path) -> rusqlite::Result<Self> {
+        let connection = rusqlite::Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS state (name TEXT PRIMARY KEY, contents TEXT NOT NULL)",
+            (),
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Opens state.sqlite within the ReSet config directory.
+    fn open_default() -> Option<Self> {
+        let path = create_config_directory("reset")?.join(Self::DATABASE_FILE);
+        match Self::open(&path) {
+            Ok(storage) => Some(storage),
+            Err(_error) => {
+                ERROR!(
+                    format!("Could not open state database: {}", _error),
+                    ErrorLevel::Recoverable
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StateStorage for SqliteStorage {
+    fn load(&self, name: &str) -> toml::Table {
+        let contents: rusqlite::Result<String> = self.connection.lock().unwrap().query_row(
+            "SELECT contents FROM state WHERE name = ?1",
+            (name,),
+            |row| row.get(0),
+        );
+        match contents {
+            Ok(contents) => parse_state(name, &contents),
+            Err(_) => toml::Table::new(),
+        }
+    }
+
+    fn save(&self, name: &str, table: &toml::Table) -> bool {
+        let res = self.connection.lock().unwrap().execute(
+            "INSERT INTO state (name, contents) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET contents = excluded.contents",
+            (name, table.to_string()),
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Could not store state {}: {}", name, _error),
+                ErrorLevel::Recoverable
+            );
+            return false;
+        }
+        true
+    }
+
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+}
+
+/// Parses a stored table, broken tables are replaced by an empty one.
+fn parse_state(_name: &str, contents: &str) -> toml::Table {
+    let table = contents.parse::<toml::Table>();
+    if let Err(_error) = table {
+        ERROR!(
+            format!("Could not parse state file {}: {}", _name, _error),
+            ErrorLevel::Recoverable
+        );
+        return toml::Table::new();
+    }
+    table.unwrap()
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use dbus::{blocking::Connection, message::MatchRule, nonblock::SyncConnection, Message, Path};
+use once_cell::sync::Lazy;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    bluetooth::bluetooth_agent_dbus::{bluetooth_agent_registered, register_bluetooth_agent},
+    capabilities::{CapabilityDetails, STATUS_ACTIVE, STATUS_DEGRADED},
+    network::secret_agent::register_secret_agent,
+    panic_guard::panic_message,
+    resync::request_resync,
+    signals::emit_signal,
+    status::StatusCode,
+    utils::BASE,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_ATTACH_ATTEMPTS: u32 = 10;
+/// Workers that ran this long before exiting start over with the initial backoff.
+const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+
+pub const WORKER_AUDIO: &str = "audio";
+pub const WORKER_NETWORK: &str = "network";
+pub const WORKER_BLUETOOTH: &str = "bluetooth";
+
+/// Worker thread as (name, running, restarts, last error).\
+/// Workers are still running while they wait for their restart.
+pub type WorkerMetrics = (String, bool, u32, String);
+
+/// Backing service of a feature, watched by its bus name.
+#[derive(Clone, Copy)]
+struct SupervisedService {
+    capability: &'static str,
+    bus_name: &'static str,
+    /// Object that answers once the service is ready again.
+    probe_path: &'static str,
+    backend: &'static str,
+}
+
+/// The bus names depend on the routing to the mock implementations, hence no constant.
+fn supervised_services() -> [SupervisedService; 3] {
+    [
+        SupervisedService {
+            capability: "WiFi",
+            bus_name: NM_INTERFACE_BASE!(),
+            probe_path: NM_PATH!(),
+            backend: "NetworkManager",
+        },
+        SupervisedService {
+            capability: "Bluetooth",
+            bus_name: BLUEZ_INTERFACE!(),
+            probe_path: "/",
+            backend: "BlueZ",
+        },
+        SupervisedService {
+            capability: "Modem",
+            bus_name: MM_INTERFACE_BASE!(),
+            probe_path: MM_PATH!(),
+            backend: "ModemManager",
+        },
+    ]
+}
+
+/// Features whose service dropped off the bus, keyed by the capability.\
+/// Features without an entry are in the state reported by their probe.
+static DEGRADED: Lazy<Mutex<HashMap<&'static str, DegradedState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct DegradedState {
+    reason: String,
+    attaching: bool,
+}
+
+#[derive(Default)]
+struct WorkerState {
+    /// A listener can be started again while a previous one is stopping.
+    instances: u32,
+    restarts: u32,
+    last_error: String,
+}
+
+static WORKERS: Lazy<Mutex<HashMap<&'static str, WorkerState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Runs the task on its own thread and restarts it with backoff whenever it panics or returns an
+/// error.\
+/// Returning Ok ends the worker, e.g. after a stop was requested.
+pub fn supervise_worker<F>(name: &'static str, mut task: F) -> JoinHandle<()>
+where
+    F: FnMut() -> Result<(), String> + Send + 'static,
+{
+    WORKERS.lock().unwrap().entry(name).or_default().instances += 1;
+    thread::spawn(move || {
+        let mut backoff = None;
+        loop {
+            let started = Instant::now();
+            let error = match catch_unwind(AssertUnwindSafe(&mut task)) {
+                Ok(Ok(())) => break,
+                Ok(Err(error)) => error,
+                Err(panic) => format!("panicked: {}", panic_message(&*panic)),
+            };
+            ERROR!(
+                format!("Worker {} exited, restarting: {}", name, error),
+                ErrorLevel::PartialBreakage
+            );
+            {
+                let mut workers = WORKERS.lock().unwrap();
+                let state = workers.entry(name).or_default();
+                state.restarts += 1;
+                state.last_error = error;
+            }
+            let delay = restart_backoff(backoff, started.elapsed());
+            backoff = Some(delay);
+            thread::sleep(delay);
+        }
+        if let Some(state) = WORKERS.lock().unwrap().get_mut(name) {
+            state.instances = state.instances.saturating_sub(1);
+        }
+    })
+}
+
+/// Doubles the previous backoff, workers that ran stable for a while start over.
+pub fn restart_backoff(previous: Option<Duration>, runtime: Duration) -> Duration {
+    match previous {
+        Some(previous) if runtime < STABLE_RUNTIME => (previous * 2).min(MAX_BACKOFF),
+        _ => INITIAL_BACKOFF,
+    }
+}
+
+/// Returns the workers that were started at least once, ordered by their name.
+pub fn worker_metrics() -> Vec<WorkerMetrics> {
+    let mut metrics: Vec<WorkerMetrics> = WORKERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, state)| {
+            (
+                name.to_string(),
+                state.instances > 0,
+                state.restarts,
+                state.last_error.clone(),
+            )
+        })
+        .collect();
+    metrics.sort();
+    metrics
+}
+
+/// Watches the bus names of the services backing the given capabilities.\
+/// A service dropping off the bus marks its feature as degraded, once the name is owned again
+/// the daemon reattaches with backoff and resyncs all listeners.
+/// Every change is emitted with CapabilityStateChanged.
+pub fn start_service_supervisor(connection: Arc<SyncConnection>, capabilities: Vec<&'static str>) {
+    thread::spawn(move || -> Result<(), dbus::Error> {
+        let conn = dbus_connection!();
+        let rule = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged");
+        let res = conn.add_match(
+            rule,
+            move |(name, old_owner, new_owner): (String, String, String), _, _| {
+                let service = supervised_services().into_iter().find(|service| {
+                    service.bus_name == name && capabilities.contains(&service.capability)
+                });
+                if let Some(service) = service {
+                    if new_owner.is_empty() {
+                        handle_service_lost(&connection, service);
+                    } else if old_owner.is_empty() {
+                        handle_service_returned(connection.clone(), service);
+                    }
+                }
+                true
+            },
+        );
+        if let Err(_error) = res {
+            ERROR!(
+                format!("Failed to match signal on the bus: {:?}", _error),
+                ErrorLevel::Recoverable
+            );
+            return Err(dbus::Error::new_custom(
+                "SignalMatchFailed",
+                "Failed to match signal on the bus.",
+            ));
+        }
+        loop {
+            conn.process(Duration::from_millis(1000))?;
+        }
+    });
+}
+
+/// Replaces the probed state of features that are currently reattaching.
+pub fn apply_supervised_state(details: &mut [CapabilityDetails]) {
+    let degraded = DEGRADED.lock().unwrap();
+    for detail in details.iter_mut() {
+        if let Some(state) = degraded.get(detail.0.as_str()) {
+            detail.1 = STATUS_DEGRADED.to_string();
+            detail.3 = state.reason.clone();
+        }
+    }
+}
+
+fn handle_service_lost(conn: &Arc<SyncConnection>, service: SupervisedService) {
+    let reason = format!(
+        "{} stopped unexpectedly, waiting for it to restart.",
+        service.backend
+    );
+    ERROR!(
+        format!("{} dropped off the bus", service.backend),
+        ErrorLevel::PartialBreakage
+    );
+    DEGRADED
+        .lock()
+        .unwrap()
+        .entry(service.capability)
+        .and_modify(|state| state.reason = reason.clone())
+        .or_insert(DegradedState {
+            reason: reason.clone(),
+            attaching: false,
+        });
+    emit_capability_state(
+        conn,
+        service.capability,
+        STATUS_DEGRADED,
+        StatusCode::ServiceUnavailable,
+        &reason,
+    );
+}
+
+fn handle_service_returned(conn: Arc<SyncConnection>, service: SupervisedService) {
+    {
+        let mut degraded = DEGRADED.lock().unwrap();
+        // services that were never lost are attached by the startup of the daemon
+        let Some(state) = degraded.get_mut(service.capability) else {
+            return;
+        };
+        if state.attaching {
+            return;
+        }
+        state.attaching = true;
+        state.reason = format!("{} restarted, reattaching.", service.backend);
+    }
+    thread::spawn(move || {
+        let mut backoff = INITIAL_BACKOFF;
+        for _ in 0..MAX_ATTACH_ATTEMPTS {
+            thread::sleep(backoff);
+            if probe_service(&service) {
+                reattach(&conn, &service);
+                return;
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        let reason = format!("{} did not respond after restarting.", service.backend);
+        ERROR!(reason.clone(), ErrorLevel::PartialBreakage);
+        if let Some(state) = DEGRADED.lock().unwrap().get_mut(service.capability) {
+            state.attaching = false;
+            state.reason = reason.clone();
+        }
+        emit_capability_state(
+            &conn,
+            service.capability,
+            STATUS_DEGRADED,
+            StatusCode::ServiceUnavailable,
+            &reason,
+        );
+    });
+}
+
+fn probe_service(service: &SupervisedService) -> bool {
+    dbus_method!(
+        service.bus_name,
+        Path::from(service.probe_path),
+        "Introspect",
+        "org.freedesktop.DBus.Introspectable",
+        (),
+        1000,
+        (),
+    )
+    .is_ok()
+}
+
+fn reattach(conn: &Arc<SyncConnection>, service: &SupervisedService) {
+    LOG!(format!("Reattached to {}", service.backend));
+    if service.capability == "WiFi" {
+        // NetworkManager forgets all agents when it stops
+        register_secret_agent();
+    }
+    if service.capability == "Bluetooth" && bluetooth_agent_registered() {
+        // bluez forgets all agents when it stops
+        register_bluetooth_agent();
+    }
+    DEGRADED.lock().unwrap().remove(service.capability);
+    // listeners and clients still hold the objects of the previous instance
+    request_resync();
+    emit_capability_state(conn, service.capability, STATUS_ACTIVE, StatusCode::Ok, "");
+}
+
+pub fn emit_capability_state(
+    conn: &Arc<SyncConnection>,
+    capability: &str,
+    status: &str,
+    code: StatusCode,
+    reason: &str,
+) {
+    let msg = Message::signal(
+        &Path::from(DBUS_PATH!()),
+        &BASE.into(),
+        &"CapabilityStateChanged".into(),
+    )
+    .append3(capability, status, code.name())
+    .append1(reason);
+    emit_signal(conn, msg);
+}
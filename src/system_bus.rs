@@ -0,0 +1,257 @@
+use std::{collections::HashMap, future, time::Duration};
+
+use dbus::{
+    arg::{PropMap, Variant},
+    blocking::Connection,
+    channel::MatchingReceiver,
+    message::MatchRule,
+    MethodErr, Path,
+};
+use dbus_crossroads::Crossroads;
+use dbus_tokio::connection;
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
+use re_set_lib::{ERROR, LOG};
+
+use crate::{
+    bluetooth::{
+        bluetooth_manager::{get_all_bluetooth_adapters, set_adapter_enabled},
+        device_services::list_devices_with_mode,
+    },
+    network::network_manager::{get_access_point_properties, get_wifi_devices},
+    panic_guard::{handle_message_guarded, spawn_handler},
+    status::{status_error, StatusCode},
+    utils::{get_daemon_config_value, get_wifi_status, BASE},
+};
+
+pub const SYSTEM: &str = "org.Xetibo.ReSet.System";
+/// Polkit action guarding the radio toggles, see dbus/org.xetibo.reset.policy.
+const TOGGLE_RADIO_ACTION: &str = "org.xetibo.reset.toggle-radio";
+/// Lets polkit ask the user for a password.
+const POLKIT_ALLOW_USER_INTERACTION: u32 = 1;
+/// Polkit may wait for the user to authenticate.
+const POLKIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Status as (wifi enabled, ssid of the connected access point, bluetooth enabled, connected
+/// bluetooth devices).\
+/// The ssid is empty if no WiFi device is connected.
+pub type StatusSummary = (bool, Vec<u8>, bool, u32);
+/// Polkit result as (is authorized, is challenge, details).
+type AuthorizationResult = (bool, bool, HashMap<String, String>);
+
+/// Whether the daemon should also serve the restricted interface on the system bus.\
+/// Enabled with the --system-bus flag or system_bus = true within the daemon section of the
+/// config, requires the policy file dbus/org.Xetibo.ReSet.Daemon.conf to be installed.
+pub fn system_bus_requested(flag: bool) -> bool {
+    flag || get_daemon_config_value("system_bus")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// Claims the daemon name on the system bus and serves org.Xetibo.ReSet.System, intended for
+/// greeters and other users which can not reach the session bus of the daemon.\
+/// Only read-only methods and radio toggles guarded by polkit are offered.
+pub async fn serve_system_bus(wifi_enabled: bool, bluetooth_enabled: bool) {
+    let res = connection::new_system_sync();
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not connect to the system bus: {}", _error),
+            ErrorLevel::PartialBreakage
+        );
+        return;
+    }
+    let (resource, conn) = res.unwrap();
+    tokio::spawn(async {
+        let _err = resource.await;
+        ERROR!(
+            format!("Lost connection to the system bus: {}", _err),
+            ErrorLevel::PartialBreakage
+        );
+    });
+    if let Err(_error) = conn.request_name(BASE, false, true, true).await {
+        ERROR!(
+            format!(
+                "Could not claim {} on the system bus, is the policy file installed and the user in the reset group? {}",
+                BASE, _error
+            ),
+            ErrorLevel::PartialBreakage
+        );
+        return;
+    }
+
+    let mut cross = Crossroads::new();
+    cross.set_async_support(Some((conn.clone(), Box::new(spawn_handler(conn.clone())))));
+    let token = cross.register(SYSTEM, |c| {
+        c.method_with_cr_async(
+            "GetStatusSummary",
+            (),
+            ("status",),
+            move |mut ctx, _, ()| {
+                let summary = get_status_summary(wifi_enabled, bluetooth_enabled);
+                async move { ctx.reply(Ok((summary,))) }
+            },
+        );
+        c.method_with_cr_async(
+            "SetWifiEnabled",
+            ("enabled",),
+            ("result",),
+            move |mut ctx, _, (enabled,): (bool,)| {
+                let sender = ctx.message().sender().map(|sender| sender.to_string());
+                async move {
+                    // polkit might wait for the user to authenticate
+                    let authorized =
+                        tokio::task::spawn_blocking(move || authorize_radio_toggle(sender))
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(status_error(
+                                    StatusCode::AuthorizationDenied,
+                                    "Authorization failed",
+                                ))
+                            });
+                    if let Err(error) = authorized {
+                        return ctx.reply(Err(error));
+                    }
+                    if !wifi_enabled {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::ServiceUnavailable,
+                            "NetworkManager is not running",
+                        )));
+                    }
+                    let res = set_dbus_property!(
+                        NM_INTERFACE_BASE!(),
+                        Path::from(NM_PATH!()),
+                        NM_INTERFACE!(),
+                        "WirelessEnabled",
+                        enabled,
+                    );
+                    ctx.reply(Ok((res.is_ok(),)))
+                }
+            },
+        );
+        c.method_with_cr_async(
+            "SetBluetoothEnabled",
+            ("enabled",),
+            ("result",),
+            move |mut ctx, _, (enabled,): (bool,)| {
+                let sender = ctx.message().sender().map(|sender| sender.to_string());
+                async move {
+                    // polkit might wait for the user to authenticate
+                    let authorized =
+                        tokio::task::spawn_blocking(move || authorize_radio_toggle(sender))
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(status_error(
+                                    StatusCode::AuthorizationDenied,
+                                    "Authorization failed",
+                                ))
+                            });
+                    if let Err(error) = authorized {
+                        return ctx.reply(Err(error));
+                    }
+                    if !bluetooth_enabled {
+                        return ctx.reply(Err(status_error(
+                            StatusCode::ServiceUnavailable,
+                            "bluez is not running",
+                        )));
+                    }
+                    // every adapter is toggled even if one fails
+                    let failed = get_all_bluetooth_adapters()
+                        .into_iter()
+                        .filter(|adapter| !set_adapter_enabled(adapter.path.clone(), enabled))
+                        .count();
+                    ctx.reply(Ok((failed == 0,)))
+                }
+            },
+        );
+    });
+    cross.insert(DBUS_PATH!(), &[token], ());
+    conn.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            handle_message_guarded(&mut cross, msg, conn);
+            true
+        }),
+    );
+    LOG!(format!("Serving {} on the system bus", SYSTEM));
+    future::pending::<()>().await;
+}
+
+fn get_status_summary(wifi_enabled: bool, bluetooth_enabled: bool) -> StatusSummary {
+    let mut summary: StatusSummary = (false, Vec::new(), false, 0);
+    if wifi_enabled {
+        summary.0 = get_wifi_status();
+        for device in get_wifi_devices() {
+            let path = device.read().unwrap().dbus_path.clone();
+            let active_access_point = get_dbus_property!(
+                NM_INTERFACE_BASE!(),
+                path,
+                NM_DEVICE_WIRELESS_INTERFACE!(),
+                "ActiveAccessPoint",
+                Path<'static>,
+            );
+            if let Ok(active_access_point) = active_access_point {
+                if &*active_access_point != "/" {
+                    summary.1 = get_access_point_properties(active_access_point).ssid;
+                    break;
+                }
+            }
+        }
+    }
+    if bluetooth_enabled {
+        summary.2 = get_all_bluetooth_adapters()
+            .iter()
+            .any(|adapter| adapter.powered);
+        summary.3 = list_devices_with_mode(true, true).len() as u32;
+    }
+    summary
+}
+
+/// Asks polkit whether the sender may toggle radios, the user might be asked to authenticate.
+fn authorize_radio_toggle(sender: Option<String>) -> Result<(), MethodErr> {
+    let denied = |reason: &str| {
+        status_error(
+            StatusCode::AuthorizationDenied,
+            &format!("Toggling radios requires authorization: {}.", reason),
+        )
+    };
+    let Some(sender) = sender else {
+        return Err(denied("the caller could not be identified"));
+    };
+    let conn = Connection::new_system().map_err(|_| denied("polkit is not reachable"))?;
+    let proxy = conn.with_proxy(
+        "org.freedesktop.PolicyKit1",
+        "/org/freedesktop/PolicyKit1/Authority",
+        POLKIT_TIMEOUT,
+    );
+    let mut subject_details = PropMap::new();
+    subject_details.insert(String::from("name"), Variant(Box::new(sender.clone())));
+    let details: HashMap<String, String> = HashMap::new();
+    let res: Result<(AuthorizationResult,), dbus::Error> = proxy.method_call(
+        "org.freedesktop.PolicyKit1.Authority",
+        "CheckAuthorization",
+        (
+            (String::from("system-bus-name"), subject_details),
+            TOGGLE_RADIO_ACTION,
+            details,
+            POLKIT_ALLOW_USER_INTERACTION,
+            String::new(),
+        ),
+    );
+    match res {
+        Ok(((true, _, _),)) => Ok(()),
+        Ok(_) => {
+            LOG!(format!(
+                "Polkit denied {} for {}",
+                TOGGLE_RADIO_ACTION, sender
+            ));
+            Err(denied("polkit denied the request"))
+        }
+        Err(_error) => {
+            ERROR!(
+                format!("Could not check authorization with polkit: {}", _error),
+                ErrorLevel::Recoverable
+            );
+            Err(denied("polkit is not reachable"))
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! # Conformance tests for the DBus API
+//! Available with the `testing` feature. While a [TestBus] is running, the daemon uses a private
+//! dbus-daemon and the mock implementations of NetworkManager, bluez and the audio server instead
+//! of the system services, the environment of the process is left untouched.\
+//! Plugin and GUI repositories can register their own tests and run them together with the
+//! tests of the daemon:
+//! ```no_run
+//! use reset_daemon::testing::{register_conformance_test, run_conformance, ConformanceTest};
+//!
+//! register_conformance_test(ConformanceTest {
+//!     name: "myplugin/ping",
+//!     run: || reset_daemon::testing::call_daemon::<(), ()>("org.Xetibo.ReSet.MyPlugin", "Ping", ()),
+//! });
+//! let report = run_conformance().expect("Could not start the test bus");
+//! assert!(report.passed(), "{:?}", report.failures());
+//! ```
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use dbus::arg::{AppendAll, ReadAll};
+use once_cell::sync::Lazy;
+use re_set_lib::{
+    audio::audio_structures::Sink, bluetooth::bluetooth_structures::BluetoothAdapter,
+    bluetooth::bluetooth_structures::BluetoothDevice, network::network_structures::AccessPoint,
+    network::network_structures::WifiDevice,
+};
+use tokio::runtime;
+
+use crate::{
+    mock::mock_dbus::start_mock_implementation_server,
+    run_daemon,
+    utils::{session_connection, AUDIO, BASE},
+    VERSION,
+};
+
+pub type ConformanceResult = Result<(), String>;
+
+/// A single test, run against the daemon on the test bus.
+#[derive(Debug, Clone, Copy)]
+pub struct ConformanceTest {
+    pub name: &'static str,
+    pub run: fn() -> ConformanceResult,
+}
+
+/// Results of all tests in registration order.
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    pub results: Vec<(&'static str, ConformanceResult)>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    pub fn failures(&self) -> Vec<(&'static str, String)> {
+        self.results
+            .iter()
+            .filter_map(|(name, result)| result.clone().err().map(|error| (*name, error)))
+            .collect()
+    }
+}
+
+static CONFORMANCE_TESTS: Lazy<Mutex<Vec<ConformanceTest>>> =
+    Lazy::new(|| Mutex::new(builtin_tests()));
+
+static MOCK_READY: AtomicBool = AtomicBool::new(false);
+
+/// Address of the private bus of the running TestBus.
+static TEST_BUS_ADDRESS: Mutex<Option<String>> = Mutex::new(None);
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Private dbus-daemon serving the mock implementations and the daemon itself.\
+/// Connections of the daemon are routed to the private bus and the mock implementations as long
+/// as the handle is alive, only one test bus can run at a time.
+pub struct TestBus {
+    bus: Child,
+    stop: mpsc::Sender<()>,
+    servers: Option<JoinHandle<()>>,
+}
+
+impl TestBus {
+    pub fn start() -> Result<Self, String> {
+        let mut address = TEST_BUS_ADDRESS.lock().unwrap();
+        if address.is_some() {
+            return Err(String::from("A test bus is already running"));
+        }
+        let (bus, bus_address) = start_private_bus()?;
+        *address = Some(bus_address);
+        drop(address);
+        MOCK_READY.store(false, Ordering::SeqCst);
+        let (stop, stop_receiver) = mpsc::channel::<()>();
+        let daemon_ready = Arc::new(AtomicBool::new(false));
+        let daemon_ready_ref = daemon_ready.clone();
+        let servers = thread::spawn(move || {
+            let mock_runtime = runtime::Runtime::new().expect("Failed to create runtime");
+            mock_runtime.spawn(start_mock_implementation_server(&MOCK_READY));
+            while !MOCK_READY.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            let daemon_runtime = runtime::Runtime::new().expect("Failed to create runtime");
+            daemon_runtime.spawn(run_daemon(Some(daemon_ready_ref)));
+            let _ = stop_receiver.recv();
+            daemon_runtime.shutdown_background();
+            mock_runtime.shutdown_background();
+        });
+        let test_bus = Self {
+            bus,
+            stop,
+            servers: Some(servers),
+        };
+        let started = Instant::now();
+        while !daemon_ready.load(Ordering::SeqCst) {
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(String::from("Daemon did not start on the test bus"));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(test_bus)
+    }
+
+    pub fn address(&self) -> String {
+        test_bus_address().unwrap_or_default()
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(servers) = self.servers.take() {
+            let _ = servers.join();
+        }
+        let _ = self.bus.kill();
+        let _ = self.bus.wait();
+        MOCK_READY.store(false, Ordering::SeqCst);
+        *TEST_BUS_ADDRESS.lock().unwrap() = None;
+    }
+}
+
+pub(crate) fn test_bus_address() -> Option<String> {
+    TEST_BUS_ADDRESS.lock().unwrap().clone()
+}
+
+pub fn test_bus_running() -> bool {
+    TEST_BUS_ADDRESS.lock().unwrap().is_some()
+}
+
+/// Whether connections are routed to the mock implementations, see MOCK_ROUTE.
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) fn mock_routing() -> bool {
+    test_bus_running()
+}
+
+/// Adds a test which is run by every following run_conformance call.
+pub fn register_conformance_test(test: ConformanceTest) {
+    CONFORMANCE_TESTS.lock().unwrap().push(test);
+}
+
+/// Calls a method of the daemon on the test bus.
+pub fn call_daemon<I: AppendAll, O: ReadAll + 'static>(
+    interface: &str,
+    method: &str,
+    args: I,
+) -> Result<O, String> {
+    let conn = session_connection().map_err(|error| error.to_string())?;
+    let proxy = conn.with_proxy(BASE, DBUS_PATH!(), Duration::from_millis(4000));
+    proxy
+        .method_call(interface, method, args)
+        .map_err(|error| format!("{} failed: {}", method, error))
+}
+
+/// Starts a TestBus and runs all registered tests against it.\
+/// Every session connection of the process uses the private bus while the tests run, hence this
+/// should be called from a dedicated test binary.
+pub fn run_conformance() -> Result<ConformanceReport, String> {
+    let _test_bus = TestBus::start()?;
+    Ok(run_conformance_tests())
+}
+
+/// Runs all registered tests against an already running daemon.
+pub(crate) fn run_conformance_tests() -> ConformanceReport {
+    let tests = CONFORMANCE_TESTS.lock().unwrap().clone();
+    ConformanceReport {
+        results: tests.iter().map(|test| (test.name, (test.run)())).collect(),
+    }
+}
+
+fn start_private_bus() -> Result<(Child, String), String> {
+    let bus = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn();
+    if let Err(error) = bus {
+        return Err(format!("Could not start dbus-daemon: {}", error));
+    }
+    let mut bus = bus.unwrap();
+    let mut address = String::new();
+    let stdout = bus.stdout.take().unwrap();
+    if BufReader::new(stdout).read_line(&mut address).is_err() || address.trim().is_empty() {
+        stop_private_bus(bus);
+        return Err(String::from("dbus-daemon did not provide an address"));
+    }
+    Ok((bus, address.trim().to_string()))
+}
+
+fn stop_private_bus(mut bus: Child) {
+    let _ = bus.kill();
+    let _ = bus.wait();
+}
+
+fn expect_not_empty<T>(name: &str, values: Vec<T>) -> ConformanceResult {
+    if values.is_empty() {
+        return Err(format!("{} returned no entries", name));
+    }
+    Ok(())
+}
+
+fn builtin_tests() -> Vec<ConformanceTest> {
+    vec![
+        ConformanceTest {
+            name: "base/api_version",
+            run: || {
+                let (version,): (String,) = call_daemon(BASE, "APIVersion", ())?;
+                if version != VERSION {
+                    return Err(format!("Expected version {}, got {}", VERSION, version));
+                }
+                Ok(())
+            },
+        },
+        ConformanceTest {
+            name: "base/capabilities",
+            run: || {
+                let (capabilities,): (Vec<String>,) = call_daemon(BASE, "GetCapabilities", ())?;
+                for capability in ["WiFi", "Bluetooth", "Audio"] {
+                    if !capabilities.iter().any(|entry| entry == capability) {
+                        return Err(format!("Capability {} is missing", capability));
+                    }
+                }
+                Ok(())
+            },
+        },
+        ConformanceTest {
+            name: "base/interface_xml",
+            run: || {
+                let (xml,): (String,) = call_daemon(BASE, "GetInterfaceXML", (AUDIO,))?;
+                if !xml.contains("ListSinks") {
+                    return Err(String::from("Audio interface does not contain ListSinks"));
+                }
+                Ok(())
+            },
+        },
+        ConformanceTest {
+            name: "network/access_points",
+            run: || {
+                let (access_points,): (Vec<AccessPoint>,) =
+                    call_daemon(NETWORK_INTERFACE!(), "ListAccessPoints", ())?;
+                expect_not_empty("ListAccessPoints", access_points)
+            },
+        },
+        ConformanceTest {
+            name: "network/wifi_devices",
+            run: || {
+                let (devices,): (Vec<WifiDevice>,) =
+                    call_daemon(NETWORK_INTERFACE!(), "GetAllWifiDevices", ())?;
+                expect_not_empty("GetAllWifiDevices", devices)
+            },
+        },
+        ConformanceTest {
+            name: "bluetooth/adapters",
+            run: || {
+                let (adapters,): (Vec<BluetoothAdapter>,) =
+                    call_daemon(BLUETOOTH_INTERFACE!(), "GetBluetoothAdapters", ())?;
+                expect_not_empty("GetBluetoothAdapters", adapters)
+            },
+        },
+        ConformanceTest {
+            name: "bluetooth/devices",
+            run: || {
+                call_daemon::<(), ()>(BLUETOOTH_INTERFACE!(), "StartBluetoothScan", ())?;
+                let (devices,): (Vec<BluetoothDevice>,) =
+                    call_daemon(BLUETOOTH_INTERFACE!(), "GetBluetoothDevices", ())?;
+                expect_not_empty("GetBluetoothDevices", devices)
+            },
+        },
+        ConformanceTest {
+            name: "audio/sinks",
+            run: || {
+                let (sinks,): (Vec<Sink>,) = call_daemon(AUDIO, "ListSinks", ())?;
+                expect_not_empty("ListSinks", sinks)
+            },
+        },
+        ConformanceTest {
+            name: "audio/volume_limit",
+            run: || {
+                call_daemon::<(), (u32, u32)>(AUDIO, "GetVolumeLimit", ())?;
+                if call_daemon::<(u32,), (bool,)>(AUDIO, "SetVolumeLimit", (0,)).is_ok() {
+                    return Err(String::from("SetVolumeLimit accepted a limit of 0"));
+                }
+                Ok(())
+            },
+        },
+    ]
+}
@@ -1,20 +1,99 @@
+use crate::audio::availability::{is_dummy_device, no_sink};
+use crate::audio::channel_test::{parse_channel_position, test_tone};
+use crate::audio::delta::{delta_delivery, Delivery};
+use crate::audio::device_hints::{device_hints, GENERIC_DEVICE_ICON};
+use crate::audio::network_sinks::{network_sink_kind, tunnel_sink_arguments};
+use crate::audio::noise_suppression::{filtered_source_name, noise_suppression_arguments};
+use crate::audio::server_info::{is_supported_sample_rate, sample_rate_arguments};
+use crate::audio::stream_routing::routing_target;
+use crate::audio::stream_state::cork_state;
+use crate::audio::volume_info::volume_to_db;
+use crate::authorization::set_sender_uid;
+use crate::battery::battery_manager::{battery_kind, battery_state, convert_battery};
+use crate::bluetooth::anonymous::{is_anonymous_device, AnonymousDevices, DeviceVisibility};
+use crate::bluetooth::bluetooth_manager::connect_error_code;
+use crate::bluetooth::cleanup::{select_not_seen_since, select_unpaired};
+use crate::bluetooth::device_services::{normalize_uuid, profiles};
+use crate::bluetooth::media::track_from_arg;
+use crate::bluetooth::obex::{download_path, effective_policy, is_allowed_download_dir};
+use crate::bluetooth::pairing::{
+    finish_pairing, format_passkey, generate_pin, get_pairing_state, pin_display_signal,
+    set_pairing_stage, PairingStage,
+};
+use crate::bluetooth::privacy::{
+    adapter_index, main_conf_value, mgmt_error_code, mgmt_settings, set_just_works_repairing,
+};
+use crate::bluetooth::rssi::rssi_changed;
+use crate::clients::ClientRegistry;
+use crate::context::is_metered;
+use crate::inhibitor::InhibitorInfo;
+use crate::media::track_from_metadata;
+use crate::modem::modem_manager::Modem;
+use crate::network::access_point_cache::security_name;
+use crate::network::activation::{
+    activation_failure_code, active_connection_reason_name, active_connection_state_name,
+};
+use crate::network::address_family::{address_family, is_global_ipv6, is_usable_ipv4};
+use crate::network::channel_advisor::{
+    band_name, channel_utilization, frequency_to_channel, least_crowded_channel, WifiBand,
+};
+use crate::network::credentials::validate_wifi_credentials;
+use crate::network::dns::{dns_configuration_from_map, merge_dns_servers};
+use crate::network::hotspot_clients::{
+    hotspot_client_changes, hotspot_clients, parse_leases, parse_station_dump,
+};
+use crate::network::keyfile::{keyfile_to_settings, settings_to_keyfile};
+use crate::network::link_info::wifi_generation;
+use crate::network::mac_randomization::{is_mac_address, is_randomization_enabled};
+use crate::network::operation_queue::{DeviceOperation, OperationQueue};
+use crate::network::powersave::{parse_powersave_mode, powersave_mode_name};
+use crate::network::priority_learning::{find_location, learned_priorities, location_similarity};
+use crate::network::roaming::bssid_to_bytes;
+use crate::network::speedtest::parse_servers;
+use crate::network::strength_threshold::{significant_change, StrengthFilter};
+use crate::network::wake_on_lan::{magic_packet, parse_wake_on_lan_mode, wake_on_lan_mode_name};
+use crate::network::wifi_qr::{WifiQrPayload, WifiQrSecurity};
+use crate::notifications::{with_mode, NotificationBackend};
+use crate::panic_guard::{handle_message_guarded, spawn_handler};
+use crate::plugin::config::{table_to_map, value_from_arg};
+use crate::plugin::lifecycle::{call_plugin, capability_changes, register_plugin_object};
+use crate::plugin::signals::{
+    get_plugin_signal, get_plugin_signals, register_plugin_signal, remove_plugin_signals,
+    PluginInfo, PluginSignal,
+};
+use crate::proxy::{
+    environment_file, is_valid_proxy_host, proxy_from_table, validate_proxy_settings,
+};
+use crate::startup::{feature_enabled, FEATURE_FLAGS, STARTUP_TASKS};
+use crate::status::StatusCode;
+use crate::storage::{FileStorage, MemoryStorage, StateStorage};
+use crate::supervisor::{restart_backoff, supervise_worker, worker_metrics};
+use crate::system_bus::system_bus_requested;
+use crate::testing::run_conformance_tests;
+use crate::utils::{AudioRequest, AudioResponse, ACTIONS, AUDIO, BASE, IDLE, MEDIA, MODEM};
+use crate::virtual_devices::{
+    virtual_sink_arguments, virtual_source_arguments, VIRTUAL_SINK_NAME, VIRTUAL_SOURCE_NAME,
+};
 use crate::{mock::mock_dbus::start_mock_implementation_server, BACKEND_PLUGINS};
-use crate::{run_daemon, utils::AUDIO};
+use crate::{mock::sound::MockAudio, run_daemon};
 use dbus::{
-    arg::{AppendAll, ReadAll},
+    arg::{AppendAll, PropMap, ReadAll, RefArg, Variant},
     blocking::Connection,
-    Path,
+    Message, MethodErr, Path,
 };
+use dbus_crossroads::Crossroads;
 
 use once_cell::sync::Lazy;
+use pulse::volume::Volume;
 use serial_test::serial;
 
 use re_set_lib::audio::audio_structures::Sink;
 use re_set_lib::audio::audio_structures::{InputStream, OutputStream, Source};
 use re_set_lib::bluetooth::bluetooth_structures::BluetoothDevice;
-use re_set_lib::network::network_structures::AccessPoint;
+use re_set_lib::network::network_structures::{AccessPoint, WifiDevice};
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::{
     hint,
     sync::atomic::{AtomicBool, AtomicU16, Ordering},
@@ -67,6 +146,15 @@ fn setup() {
     }
 }
 
+#[tokio::test]
+#[serial]
+// runs the conformance tests exposed to other repositories against the test daemon
+async fn test_conformance() {
+    setup();
+    let report = run_conformance_tests();
+    assert!(report.passed(), "{:?}", report.failures());
+}
+
 #[tokio::test]
 // tests fetching bluetooth devices
 async fn test_bluetooth_get_devices() {
@@ -98,6 +186,197 @@ async fn test_bluetooth_get_devices() {
     assert!(!res.unwrap().0.is_empty());
 }
 
+#[tokio::test]
+// tests rejecting a scan without duration
+async fn test_bluetooth_scan_for_zero_seconds() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "StartBluetoothScanFor",
+        BLUETOOTH_INTERFACE!(),
+        (0_u32,),
+        4000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// tests rejecting discoverability without duration
+async fn test_bluetooth_discoverable_for_zero_seconds() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "MakeDiscoverableFor",
+        BLUETOOTH_INTERFACE!(),
+        (0_u32,),
+        4000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// tests the services of a device unknown to bluez
+async fn test_bluetooth_device_services_unknown() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetBluetoothDeviceServices",
+        BLUETOOTH_INTERFACE!(),
+        (Path::from("/org/bluez/hci0/dev_FF_FF_FF_FF_FF_FF"),),
+        4000,
+        (String, Vec<(String, String)>),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// tests rejecting a malformed profile UUID
+async fn test_bluetooth_connect_profile_invalid_uuid() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "ConnectBluetoothDeviceProfile",
+        BLUETOOTH_INTERFACE!(),
+        (
+            Path::from("/org/bluez/hci0/dev_FF_FF_FF_FF_FF_FF"),
+            String::from("audio")
+        ),
+        4000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_normalize_profile_uuid() {
+    assert_eq!(
+        normalize_uuid("110b").as_deref(),
+        Some(profiles::AUDIO_SINK)
+    );
+    assert_eq!(
+        normalize_uuid("0x1124").as_deref(),
+        Some(profiles::HUMAN_INTERFACE_DEVICE)
+    );
+    assert_eq!(
+        normalize_uuid("0000110A-0000-1000-8000-00805F9B34FB").as_deref(),
+        Some(profiles::AUDIO_SOURCE)
+    );
+    assert_eq!(normalize_uuid("0000110a-0000-1000-8000"), None);
+    assert_eq!(normalize_uuid("audio"), None);
+}
+
+#[tokio::test]
+// tests the mode of a device unknown to bluez
+async fn test_bluetooth_device_mode_unknown() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetBluetoothDeviceMode",
+        BLUETOOTH_INTERFACE!(),
+        (Path::from("/org/bluez/hci0/dev_FF_FF_FF_FF_FF_FF"),),
+        4000,
+        (String,),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// tests media control of an unknown device
+async fn test_bluetooth_media_unknown_device() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "Play",
+        BLUETOOTH_MEDIA_INTERFACE!(),
+        (Path::from("/org/bluez/hci0/dev_FF_FF_FF_FF_FF_FF"),),
+        4000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+// tests reading the track metadata of bluez
+fn test_bluetooth_media_track() {
+    let mut track = PropMap::new();
+    track.insert(
+        String::from("Title"),
+        Variant(Box::new(String::from("Song"))),
+    );
+    track.insert(
+        String::from("Artist"),
+        Variant(Box::new(String::from("Band"))),
+    );
+    track.insert(String::from("Duration"), Variant(Box::new(215000u32)));
+    track.insert(String::from("TrackNumber"), Variant(Box::new(3u32)));
+    assert_eq!(
+        track_from_arg(&track),
+        (
+            String::from("Song"),
+            String::from("Band"),
+            String::new(),
+            215000
+        )
+    );
+    assert_eq!(track_from_arg(&String::from("invalid")), Default::default());
+}
+
+#[test]
+// tests the PINs shown while pairing keyboards
+fn test_pairing_pin() {
+    let pin = generate_pin();
+    assert_eq!(pin.len(), 6);
+    assert!(pin.chars().all(|c| c.is_ascii_digit()));
+    assert_eq!(format_passkey(42), "000042");
+    assert_eq!(format_passkey(123456), "123456");
+}
+
+#[test]
+// tests the pairing state reported by GetBluetoothState
+fn test_pairing_state() {
+    let keyboard = Path::from("/org/bluez/hci0/dev_00_1A_7D_DA_71_13");
+    let headset = Path::from("/org/bluez/hci0/dev_00_1A_7D_DA_71_14");
+    set_pairing_stage(keyboard.clone(), PairingStage::Started);
+    let _ = pin_display_signal(keyboard.clone(), String::from("123456"));
+    assert_eq!(
+        get_pairing_state(),
+        Some((keyboard.clone(), PairingStage::PinDisplayed))
+    );
+    assert_eq!(PairingStage::PinDisplayed.name(), "pin_displayed");
+    // another device finishing does not end the pairing
+    finish_pairing(Some(&headset));
+    assert!(get_pairing_state().is_some());
+    finish_pairing(Some(&keyboard));
+    assert_eq!(get_pairing_state(), None);
+}
+
+#[tokio::test]
+// tests the history of an unknown device
+async fn test_bluetooth_device_history_unknown() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetBluetoothDeviceHistory",
+        BLUETOOTH_INTERFACE!(),
+        ("FF:FF:FF:FF:FF:FF",),
+        4000,
+        (u64, u64),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert_eq!(res.unwrap(), (0, 0));
+}
+
 #[tokio::test]
 // tests the existance of the mock implementation
 async fn test_mock_connection() {
@@ -134,6 +413,34 @@ async fn test_list_connections() {
     assert!(!res.unwrap().0.is_empty());
 }
 
+#[tokio::test]
+// tests grouping the access points of the mock implementation by SSID
+async fn test_list_access_point_groups() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "ListAccessPointGroups",
+        NM_INTERFACE_TEST!(),
+        (),
+        4000,
+        (
+            Vec<(
+                Vec<u8>,
+                bool,
+                Path<'static>,
+                Vec<(Path<'static>, String, u32, u8)>
+            )>,
+        ),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let groups = res.unwrap().0;
+    assert!(!groups.is_empty());
+    assert!(groups.iter().all(|group| !group.3.is_empty()));
+}
+
 #[tokio::test]
 #[serial]
 // tests adding and removing an access point
@@ -266,6 +573,98 @@ fn connect_to_known_access_point() {
     assert!(res.unwrap().0);
 }
 
+#[tokio::test]
+#[serial]
+// tests that the powersave mode is stored in the active connection of the current device
+async fn test_set_wifi_powersave() {
+    setup();
+    connect_to_new_access_point();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetCurrentWifiDevice",
+        NM_INTERFACE_TEST!(),
+        (),
+        1000,
+        (WifiDevice,),
+    );
+    let device = res.expect("Failed to get the current device").0.path;
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetWifiPowersave",
+        NM_INTERFACE_TEST!(),
+        (device.clone(), "disable"),
+        1000,
+        (bool,),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0);
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetWifiPowersave",
+        NM_INTERFACE_TEST!(),
+        (device,),
+        1000,
+        (String,),
+    );
+    assert_eq!(res.unwrap().0, "disable");
+}
+
+#[tokio::test]
+#[serial]
+// tests that updating a connection succeeds, NetworkManager replies to Update without a body
+async fn test_set_connection_settings() {
+    setup();
+    connect_to_new_access_point();
+    let mut wireless = PropMap::new();
+    wireless.insert("ssid".into(), Variant(Box::new(b"ReSet".to_vec())));
+    let settings = HashMap::from([(String::from("802-11-wireless"), wireless)]);
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetConnectionSettings",
+        NM_INTERFACE_TEST!(),
+        (
+            Path::from(NM_PATH!().to_string() + "/Connection/100"),
+            settings
+        ),
+        1000,
+        (bool,),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0);
+}
+
+#[tokio::test]
+#[serial]
+// tests that the randomization is stored within the connection settings
+async fn test_set_wifi_scan_mac_randomization() {
+    setup();
+    connect_to_new_access_point();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetWifiScanMacRandomization",
+        NM_INTERFACE_TEST!(),
+        (
+            Path::from(NM_PATH!().to_string() + "/Connection/100"),
+            false
+        ),
+        1000,
+        (bool,),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0);
+}
+
 #[tokio::test]
 // tests connecting to a new access point with a *wrong* password
 async fn test_connect_to_new_access_point_wrong_password() {
@@ -303,74 +702,363 @@ async fn test_connect_to_new_access_point_wrong_password() {
     assert!(!res.unwrap().0);
 }
 
-// #[tokio::test]
-// async fn test_wireless_listener() {
-//     setup();
-//     thread::sleep(Duration::from_millis(4000));
-//     let res = call_session_dbus_method::<(), ()>("StartNetworkListener", NETWORK_INTERFACE!(), ());
-//     if let Err(_error) = res { panic!("connection failed: {}", (_error)); }
-// }
-//
-// #[tokio::test]
-// async fn test_bluetooth_listener() {
-//     setup();
-//     thread::sleep(Duration::from_millis(4000));
-//     let res = call_session_dbus_method::<(u32,), ()>(
-//         "StartBluetoothListener",
-//         BLUETOOTH_INTERFACE!(),
-//         (5,),
-//     );
-//     if let Err(_error) = res { panic!("connection failed: {}", (_error)); }
-// }
-//
-
 #[tokio::test]
-#[serial]
-async fn test_get_sinks() {
+// tests rejecting a retry policy without backoff
+async fn test_set_connection_retry_policy_without_backoff() {
     setup();
-    let res = call_session_dbus_method::<(), (Vec<Sink>,)>("ListSinks", AUDIO, ());
-    if let Err(_error) = res {
-        panic!("connection failed: {}", (_error));
-    }
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetConnectionRetryPolicy",
+        NM_INTERFACE_TEST!(),
+        (
+            Path::from(NM_PATH!().to_string() + "/Connection/100"),
+            3_u32,
+            0_u32
+        ),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
 }
 
 #[tokio::test]
-#[serial]
-async fn test_get_default_sink() {
+// tests rejecting a priority outside of the range of NetworkManager
+async fn test_autoconnect_priority_out_of_range() {
     setup();
-    let res = call_session_dbus_method::<(), (Sink,)>("GetDefaultSink", AUDIO, ());
-    if let Err(_error) = res {
-        panic!("connection failed: {}", (_error));
-    }
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetConnectionAutoconnectPriority",
+        NM_INTERFACE_TEST!(),
+        (
+            Path::from(NM_PATH!().to_string() + "/Connection/100"),
+            1000_i32
+        ),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
 }
 
 #[tokio::test]
-#[serial]
-async fn test_get_default_source() {
+// tests rejecting a malformed BSSID
+async fn test_blacklist_invalid_bssid() {
     setup();
-    let res = call_session_dbus_method::<(), (Source,)>("GetDefaultSource", AUDIO, ());
-    if let Err(_error) = res {
-        panic!("connection failed: {}", (_error));
-    }
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "BlacklistAccessPoint",
+        NM_INTERFACE_TEST!(),
+        ("AA:BB:CC:DD:EE".to_string(), 60_u32),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
 }
 
 #[tokio::test]
-#[serial]
-async fn test_get_sources() {
+// the mock does not provide link properties
+async fn test_wifi_link_info_unknown_device() {
     setup();
-    let res = call_session_dbus_method::<(), (Vec<Source>,)>("ListSources", AUDIO, ());
-    if let Err(_error) = res {
-        panic!("connection failed: {}", (_error));
-    }
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetWifiLinkInfo",
+        NM_INTERFACE_TEST!(),
+        (Path::from("/"),),
+        1000,
+        ((u32, u32, u8, u8),),
+    );
+    assert!(res.is_err());
 }
 
 #[tokio::test]
-async fn test_get_input_streams() {
+async fn test_reassociate_unknown_device() {
     setup();
-    let res = call_session_dbus_method::<(), (Vec<InputStream>,)>("ListInputStreams", AUDIO, ());
-    if let Err(_error) = res {
-        panic!("connection failed: {}", (_error));
-    }
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "ReassociateToStrongestBSSID",
+        NM_INTERFACE_TEST!(),
+        (Path::from("/"),),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_bssid_to_bytes() {
+    assert_eq!(
+        bssid_to_bytes("AA:bb:0C:dd:EE:01"),
+        Some(vec![0xaa, 0xbb, 0x0c, 0xdd, 0xee, 0x01])
+    );
+    assert_eq!(bssid_to_bytes("AA:BB:CC:DD:EE"), None);
+    assert_eq!(bssid_to_bytes("AA:BB:CC:DD:EE:GG"), None);
+}
+
+#[test]
+fn test_validate_wifi_credentials() {
+    let eap = PropMap::new();
+    // wpa-psk
+    assert!(validate_wifi_credentials(0x100, "password", &eap).is_ok());
+    assert!(validate_wifi_credentials(0x100, "short", &eap).is_err());
+    assert!(validate_wifi_credentials(0x100, &"a".repeat(64), &eap).is_ok());
+    assert!(validate_wifi_credentials(0x100, &"g".repeat(64), &eap).is_err());
+    assert!(validate_wifi_credentials(0x100, "pässword", &eap).is_err());
+    // wep
+    assert!(validate_wifi_credentials(0x1, "abcde", &eap).is_ok());
+    assert!(validate_wifi_credentials(0x1, "abcdef", &eap).is_err());
+    // open
+    assert!(validate_wifi_credentials(0, "", &eap).is_ok());
+    // enterprise
+    assert!(validate_wifi_credentials(0x200, "password", &eap).is_err());
+    let mut eap = PropMap::new();
+    eap.insert(
+        String::from("eap"),
+        Variant(Box::new(vec![String::from("peap")])),
+    );
+    eap.insert(
+        String::from("identity"),
+        Variant(Box::new(String::from("user"))),
+    );
+    assert!(validate_wifi_credentials(0x200, "password", &eap).is_err());
+    eap.insert(
+        String::from("phase2-auth"),
+        Variant(Box::new(String::from("mschapv2"))),
+    );
+    assert!(validate_wifi_credentials(0x200, "password", &eap).is_ok());
+    assert!(validate_wifi_credentials(0x200, "", &eap).is_err());
+}
+
+#[test]
+fn test_access_point_security() {
+    assert_eq!(security_name(0, 0, 0), "none");
+    assert_eq!(security_name(0x1, 0, 0), "wep");
+    assert_eq!(security_name(0x1, 0, 0x188), "wpa");
+    assert_eq!(security_name(0x1, 0, 0x588), "wpa3");
+    assert_eq!(security_name(0x1, 0, 0x288), "enterprise");
+    assert_eq!(security_name(0, 0, 0x800), "owe");
+}
+
+#[test]
+fn test_wifi_generation() {
+    assert_eq!(wifi_generation(2412, 54_000), 0);
+    assert_eq!(wifi_generation(2437, 300_000), 4);
+    assert_eq!(wifi_generation(5180, 300_000), 4);
+    assert_eq!(wifi_generation(5180, 866_700), 5);
+    assert_eq!(wifi_generation(5955, 1_200_000), 6);
+}
+
+#[test]
+fn test_connection_address_families() {
+    assert!(is_global_ipv6("2001:db8::1"));
+    assert!(!is_global_ipv6("fe80::1"));
+    assert!(!is_global_ipv6("fd12:3456::1"));
+    assert!(!is_global_ipv6("::1"));
+    assert!(!is_global_ipv6("invalid"));
+    assert!(is_usable_ipv4("192.168.1.10"));
+    assert!(!is_usable_ipv4("169.254.3.4"));
+    assert_eq!(address_family(true, true), "dual-stack");
+    assert_eq!(address_family(true, false), "ipv4-only");
+    assert_eq!(address_family(false, true), "ipv6-only");
+    assert_eq!(address_family(false, false), "none");
+}
+
+#[tokio::test]
+async fn test_set_connection_dns_invalid_server() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "SetConnectionDNS",
+        NM_INTERFACE_TEST!(),
+        (
+            Path::from(NM_PATH!().to_string() + "/Connection/100"),
+            vec![String::from("not.an.address")],
+            Vec::<String>::new(),
+            true
+        ),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_provide_secrets_unknown_request() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "ProvideSecrets",
+        NM_INTERFACE_TEST!(),
+        (0_u32, HashMap::<String, PropMap>::new()),
+        1000,
+        (bool,),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(!res.unwrap().0);
+}
+
+#[tokio::test]
+// tests that unauthorized callers can't read the password within the QR payload
+async fn test_qr_payload_unauthorized() {
+    setup();
+    let conn = Connection::new_session().unwrap();
+    set_sender_uid(&conn.unique_name(), 65534);
+    let proxy = conn.with_proxy(BASE_INTERFACE!(), DBUS_PATH!(), Duration::from_millis(1000));
+    let res: Result<(String,), dbus::Error> = proxy.method_call(
+        NM_INTERFACE_TEST!(),
+        "GetAccessPointQRPayload",
+        (Path::from("/"),),
+    );
+    assert_eq!(
+        res.unwrap_err().name(),
+        Some(StatusCode::AuthorizationDenied.error_name().as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_enable_connection_sharing_without_internet() {
+    setup();
+    let device = Path::from(NM_DEVICES_PATH!().to_string() + "/1");
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "EnableConnectionSharing",
+        NM_INTERFACE_TEST!(),
+        (device,),
+        1000,
+        (bool,),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// tests resolving a portal while the mock implementation does not report full connectivity
+async fn test_mark_portal_resolved() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "MarkPortalResolved",
+        NM_INTERFACE_TEST!(),
+        (),
+        6000,
+        (bool,),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(!res.unwrap().0);
+}
+
+#[tokio::test]
+// tests the radio states reported by the mock implementation
+async fn test_get_radio_states() {
+    setup();
+    let res = dbus_method!(
+        BASE_INTERFACE!(),
+        DBUS_PATH!(),
+        "GetRadioStates",
+        NM_INTERFACE_TEST!(),
+        (),
+        1000,
+        (bool, bool, bool),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let (_, wireless_hardware, wwan) = res.unwrap();
+    assert!(wireless_hardware);
+    assert!(!wwan);
+}
+
+// #[tokio::test]
+// async fn test_wireless_listener() {
+//     setup();
+//     thread::sleep(Duration::from_millis(4000));
+//     let res = call_session_dbus_method::<(), ()>("StartNetworkListener", NETWORK_INTERFACE!(), ());
+//     if let Err(_error) = res { panic!("connection failed: {}", (_error)); }
+// }
+//
+// #[tokio::test]
+// async fn test_bluetooth_listener() {
+//     setup();
+//     thread::sleep(Duration::from_millis(4000));
+//     let res = call_session_dbus_method::<(u32,), ()>(
+//         "StartBluetoothListener",
+//         BLUETOOTH_INTERFACE!(),
+//         (5,),
+//     );
+//     if let Err(_error) = res { panic!("connection failed: {}", (_error)); }
+// }
+//
+
+#[tokio::test]
+#[serial]
+async fn test_get_sinks() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<Sink>,)>("ListSinks", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+#[serial]
+// tests the typed client against the same call as test_get_sinks
+async fn test_client_list_sinks() {
+    setup();
+    let client = crate::client::ReSetClient::new().await.unwrap();
+    let res = client.list_sinks().await;
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_default_sink() {
+    setup();
+    let res = call_session_dbus_method::<(), (Sink,)>("GetDefaultSink", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_default_source() {
+    setup();
+    let res = call_session_dbus_method::<(), (Source,)>("GetDefaultSource", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_sources() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<Source>,)>("ListSources", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+async fn test_get_input_streams() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<InputStream>,)>("ListInputStreams", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
 }
 
 #[tokio::test]
@@ -382,6 +1070,681 @@ async fn test_get_output_streams() {
     }
 }
 
+#[tokio::test]
+async fn test_refresh_audio_cache() {
+    setup();
+    let res = call_session_dbus_method::<(), (bool,)>("RefreshAudioCache", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let res = call_session_dbus_method::<(), (Vec<Sink>,)>("ListSinks", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+async fn test_volume_up_action() {
+    setup();
+    let limit = call_session_dbus_method::<(), (u32, u32)>("GetVolumeLimit", AUDIO, ());
+    let res = call_session_dbus_method::<(u32,), (u32,)>("VolumeUp", ACTIONS, (0,));
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0 <= limit.unwrap().0);
+}
+
+#[tokio::test]
+async fn test_list_source_monitors() {
+    setup();
+    let res =
+        call_session_dbus_method::<(), (Vec<(u32, bool, u32)>,)>("ListSourceMonitors", AUDIO, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+async fn test_set_device_alias_invalid_kind() {
+    setup();
+    let res = call_session_dbus_method::<(String, String, String), (bool,)>(
+        "SetDeviceAlias",
+        AUDIO,
+        (
+            String::from("card"),
+            String::from("pci-0000:00:1f.3"),
+            String::from("Desk Speakers"),
+        ),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_set_volume_limit_out_of_range() {
+    setup();
+    let res = call_session_dbus_method::<(u32,), (bool,)>("SetVolumeLimit", AUDIO, (0,));
+    assert!(res.is_err());
+    let res = call_session_dbus_method::<(u32,), (bool,)>("SetVolumeLimit", AUDIO, (1000,));
+    assert!(res.is_err());
+    let res = call_session_dbus_method::<(), (u32, u32)>("GetVolumeLimit", AUDIO, ());
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_set_volume_percent_invalid() {
+    setup();
+    let res =
+        call_session_dbus_method::<(u32, f64), (u32,)>("SetSinkVolumePercent", AUDIO, (0, -5.0));
+    assert!(res.is_err());
+    let res = call_session_dbus_method::<(String,), (Vec<(u32, Vec<u32>, Vec<f64>)>,)>(
+        "ListVolumeInfo",
+        AUDIO,
+        (String::from("card"),),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_stream_peak_monitor_unknown_stream() {
+    setup();
+    let res =
+        call_session_dbus_method::<(u32,), (bool,)>("StartStreamPeakMonitor", AUDIO, (u32::MAX,));
+    assert!(!res.unwrap().0);
+    let res =
+        call_session_dbus_method::<(u32,), (bool,)>("StopStreamPeakMonitor", AUDIO, (u32::MAX,));
+    assert!(!res.unwrap().0);
+}
+
+#[tokio::test]
+async fn test_set_sink_description_invalid() {
+    setup();
+    let res = call_session_dbus_method::<(u32, String), (bool,)>(
+        "SetSinkDescription",
+        AUDIO,
+        (0, String::from("  ")),
+    );
+    assert!(res.is_err());
+    let res = call_session_dbus_method::<(u32, String), (bool,)>(
+        "SetSourceDescription",
+        AUDIO,
+        (u32::MAX, String::from("Desk Microphone")),
+    );
+    assert!(!res.unwrap().0);
+}
+
+#[test]
+fn test_dummy_audio_device() {
+    assert!(is_dummy_device("auto_null"));
+    assert!(is_dummy_device("auto_null.monitor"));
+    assert!(!is_dummy_device(
+        "alsa_output.pci-0000_00_1f.3.analog-stereo"
+    ));
+    assert_eq!(no_sink().index, u32::MAX);
+}
+
+#[test]
+// tests that connecting Bluetooth devices become the default sink and the previous default is
+// restored on disconnect
+fn test_bluetooth_auto_route() {
+    let (request_sender, request_receiver) = crossbeam::channel::unbounded();
+    let (response_sender, response_receiver) = crossbeam::channel::unbounded();
+    let audio = thread::spawn(move || {
+        MockAudio::default().listen_to_messages(response_sender, request_receiver)
+    });
+    let default_sink = || {
+        request_sender
+            .send(AudioRequest::GetDefaultSinkName)
+            .unwrap();
+        match response_receiver.recv().unwrap() {
+            AudioResponse::DefaultSinkName(name) => name,
+            _ => panic!("unexpected response"),
+        }
+    };
+    let address = String::from("AA:BB:CC:DD:EE:FF");
+    // the routing is disabled by default
+    request_sender
+        .send(AudioRequest::BluetoothDeviceConnected(address.clone()))
+        .unwrap();
+    assert_eq!(default_sink(), "reset_mock_sink");
+    request_sender
+        .send(AudioRequest::BluetoothDeviceDisconnected(address.clone()))
+        .unwrap();
+    request_sender
+        .send(AudioRequest::SetBluetoothAutoRoute(true))
+        .unwrap();
+    request_sender
+        .send(AudioRequest::BluetoothDeviceConnected(address.clone()))
+        .unwrap();
+    assert_eq!(default_sink(), "bluez_output.AA_BB_CC_DD_EE_FF.1");
+    request_sender
+        .send(AudioRequest::BluetoothDeviceDisconnected(address))
+        .unwrap();
+    assert_eq!(default_sink(), "reset_mock_sink");
+    request_sender.send(AudioRequest::StopListener).unwrap();
+    audio.join().unwrap();
+}
+
+#[test]
+fn test_volume_to_db() {
+    assert_eq!(volume_to_db(Volume::NORMAL.0), 0.0);
+    assert!(volume_to_db(Volume::NORMAL.0 / 2) < 0.0);
+    assert_eq!(volume_to_db(0), f64::NEG_INFINITY);
+}
+
+#[tokio::test]
+// only disables the switch, enabling it would change the profiles of real Bluetooth cards
+async fn test_disable_auto_headset_profile() {
+    setup();
+    let res =
+        call_session_dbus_method::<(bool,), (bool,)>("SetAutoHeadsetProfile", AUDIO, (false,));
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0);
+}
+
+#[tokio::test]
+async fn test_register_throttled_client() {
+    setup();
+    // tokens belong to the connection that registered the client
+    let conn = Connection::new_session().unwrap();
+    let proxy = conn.with_proxy(BASE_INTERFACE!(), DBUS_PATH!(), Duration::from_millis(4000));
+    let (token,): (String,) = proxy
+        .method_call(
+            BASE,
+            "RegisterClient",
+            ("throttled_test_client", PropMap::new()),
+        )
+        .unwrap();
+    let mut intervals = HashMap::new();
+    intervals.insert(String::from("Audio"), 1000_u32);
+    let res: Result<(bool,), dbus::Error> =
+        proxy.method_call(BASE, "RegisterThrottledClient", (token.clone(), intervals));
+    assert!(res.is_ok() && res.unwrap().0);
+    let mut intervals = HashMap::new();
+    intervals.insert(String::from("Unknown"), 1000_u32);
+    let res: Result<(bool,), dbus::Error> =
+        proxy.method_call(BASE, "RegisterThrottledClient", (token.clone(), intervals));
+    assert!(res.is_err());
+    let mut intervals = HashMap::new();
+    intervals.insert(String::from("Audio"), 1000_u32);
+    let res: Result<(bool,), dbus::Error> = proxy.method_call(
+        BASE,
+        "RegisterThrottledClient",
+        (String::from("unknown"), intervals),
+    );
+    assert!(res.is_err());
+    let res: Result<(bool,), dbus::Error> = proxy.method_call(BASE, "UnregisterClient", (token,));
+    assert!(res.is_ok() && res.unwrap().0);
+}
+
+#[tokio::test]
+// tests negotiating per-client signals while registering a client
+async fn test_register_client_signals() {
+    setup();
+    let conn = Connection::new_session().unwrap();
+    let proxy = conn.with_proxy(BASE_INTERFACE!(), DBUS_PATH!(), Duration::from_millis(4000));
+    let mut signals = PropMap::new();
+    let intervals = HashMap::from([(String::from("Audio"), 1000_u32)]);
+    signals.insert("intervals".into(), Variant(Box::new(intervals)));
+    signals.insert("delta".into(), Variant(Box::new(true)));
+    let res: Result<(String,), dbus::Error> =
+        proxy.method_call(BASE, "RegisterClient", ("signals_test_client", signals));
+    assert!(res.is_ok());
+    let token = res.unwrap().0;
+    let mut signals = PropMap::new();
+    signals.insert("strength_threshold".into(), Variant(Box::new(101_u8)));
+    let res: Result<(String,), dbus::Error> =
+        proxy.method_call(BASE, "RegisterClient", ("signals_test_client", signals));
+    assert!(res.is_err());
+    let mut signals = PropMap::new();
+    signals.insert("unknown".into(), Variant(Box::new(true)));
+    let res: Result<(String,), dbus::Error> =
+        proxy.method_call(BASE, "RegisterClient", ("signals_test_client", signals));
+    assert!(res.is_err());
+    let res: Result<(bool,), dbus::Error> = proxy.method_call(BASE, "UnregisterClient", (token,));
+    assert!(res.is_ok() && res.unwrap().0);
+}
+
+#[test]
+// tests that streams of suspended sinks are reported as suspended
+fn test_stream_cork_state() {
+    let input_stream = InputStream {
+        index: 4,
+        sink_index: 1,
+        corked: true,
+        ..Default::default()
+    };
+    assert_eq!(cork_state(&input_stream, None), (4, true, false));
+    let mut sink = Sink {
+        index: 1,
+        active: 2,
+        ..Default::default()
+    };
+    assert_eq!(cork_state(&input_stream, Some(&sink)), (4, true, true));
+    sink.active = 0;
+    assert_eq!(cork_state(&input_stream, Some(&sink)), (4, true, false));
+}
+
+#[test]
+fn test_noise_suppression_arguments() {
+    assert_eq!(filtered_source_name("mic"), "mic.noise-suppressed");
+    let pulse = noise_suppression_arguments("mic", "Built-in \"Mic\"", false);
+    assert!(pulse.contains("source_master=mic source_name=mic.noise-suppressed"));
+    assert!(pulse.contains("device.description=\"Built-in Mic (Noise Suppression)\""));
+    assert!(pulse.contains("noise_suppression=1"));
+    let pipewire = noise_suppression_arguments("mic", "Mic", true);
+    assert!(pipewire.contains("webrtc.noise_suppression=true"));
+}
+
+#[test]
+fn test_audio_delta() {
+    let mut delivered = HashMap::new();
+    let mut sink = Sink {
+        index: 3,
+        volume: vec![100, 100],
+        ..Default::default()
+    };
+    let signal = |sink: &Sink| {
+        Message::signal(
+            &Path::from(DBUS_PATH!()),
+            &AUDIO.into(),
+            &"SinkChanged".into(),
+        )
+        .append1(sink.clone())
+    };
+    let key = "SinkChanged3";
+    assert!(matches!(
+        delta_delivery(&signal(&sink), &mut delivered, key),
+        Delivery::Full
+    ));
+    assert!(matches!(
+        delta_delivery(&signal(&sink), &mut delivered, key),
+        Delivery::Unchanged
+    ));
+    sink.volume = vec![50, 50];
+    let Delivery::Delta(delta) = delta_delivery(&signal(&sink), &mut delivered, key) else {
+        panic!("expected a delta");
+    };
+    assert_eq!(delta.member().unwrap().to_string(), "SinkChangedDelta");
+    let (index, mask, values): (u32, u32, Vec<Variant<Box<dyn RefArg>>>) = delta.read3().unwrap();
+    assert_eq!(index, 3);
+    assert_eq!(mask, 0b10000);
+    assert_eq!(values.len(), 1);
+}
+
+#[test]
+fn test_wifi_qr_payload() {
+    let payload = WifiQrPayload::parse("WIFI:T:WPA;S:my\\;network;P:pass\\:word;H:true;;").unwrap();
+    assert_eq!(payload.ssid, "my;network");
+    assert_eq!(payload.password, "pass:word");
+    assert_eq!(payload.security, WifiQrSecurity::Wpa);
+    assert!(payload.hidden);
+    assert_eq!(WifiQrPayload::parse(&payload.to_payload()), Ok(payload));
+    assert!(WifiQrPayload::parse("S:network;;").is_err());
+    assert_eq!(
+        WifiQrSecurity::from_key_management("none"),
+        Some(WifiQrSecurity::Wep)
+    );
+    for key_management in ["wpa-eap", "owe", "ieee8021x"] {
+        assert_eq!(WifiQrSecurity::from_key_management(key_management), None);
+    }
+}
+
+#[test]
+fn test_connection_keyfile() {
+    let keyfile = "[connection]\nautoconnect-priority=5\nid=Home Network\ntype=wifi\n\n\
+        [wifi]\nhidden=true\nmac-address=AA:BB:CC:DD:EE:FF\nssid=Home Network\n\n\
+        [wifi-security]\nkey-mgmt=wpa-psk\npsk=12345678\n\n\
+        [ipv4]\naddress1=192.168.1.5/24\ndns=1.1.1.1;9.9.9.9;\ndns-search=home.lan;\n\
+        gateway=192.168.1.1\nmethod=manual\n\n";
+    let settings = keyfile_to_settings(keyfile).unwrap();
+    let connection = settings.get("connection").unwrap();
+    assert_eq!(
+        connection.get("type").unwrap().0.as_str(),
+        Some("802-11-wireless")
+    );
+    assert_eq!(
+        &*connection
+            .get("autoconnect-priority")
+            .unwrap()
+            .0
+            .signature(),
+        "i"
+    );
+    let security = settings.get("802-11-wireless-security").unwrap();
+    assert_eq!(security.get("psk").unwrap().0.as_str(), Some("12345678"));
+    let dns = &settings.get("ipv4").unwrap().get("dns").unwrap().0;
+    assert_eq!(&*dns.signature(), "au");
+    assert_eq!(settings_to_keyfile(&settings), keyfile);
+    assert!(keyfile_to_settings("[wifi]\nssid=network\n").is_err());
+    assert!(keyfile_to_settings("[connection]\ntype=wifi\nautoconnect=maybe\n").is_err());
+}
+
+struct RecordingSender(Mutex<Vec<Message>>);
+
+impl dbus::channel::Sender for RecordingSender {
+    fn send(&self, msg: Message) -> Result<u32, ()> {
+        self.0.lock().unwrap().push(msg);
+        Ok(0)
+    }
+}
+
+#[test]
+fn test_handler_panic_guard() {
+    let mut cross = Crossroads::new();
+    let token = cross.register("org.Xetibo.ReSet.Test.Panic", |c| {
+        c.method(
+            "Panic",
+            (),
+            ("result",),
+            |_, _, ()| -> Result<(bool,), MethodErr> { panic!("broken handler") },
+        );
+        c.method("Ping", (), ("result",), |_, _, ()| Ok((true,)));
+    });
+    cross.insert("/org/Xetibo/ReSet/Test", &[token], ());
+    let call = |method: &str| {
+        let mut msg = Message::new_method_call(
+            "org.Xetibo.ReSet.Test",
+            "/org/Xetibo/ReSet/Test",
+            "org.Xetibo.ReSet.Test.Panic",
+            method,
+        )
+        .unwrap();
+        msg.set_serial(1);
+        msg
+    };
+    let sender = RecordingSender(Mutex::new(Vec::new()));
+    handle_message_guarded(&mut cross, call("Panic"), &sender);
+    handle_message_guarded(&mut cross, call("Ping"), &sender);
+    let mut replies = sender.0.into_inner().unwrap();
+    assert_eq!(replies.len(), 2);
+    let error = replies[0].as_result().unwrap_err();
+    assert_eq!(
+        error.name(),
+        Some(StatusCode::HandlerPanicked.error_name().as_str())
+    );
+    assert!(error.message().unwrap().contains("broken handler"));
+    assert_eq!(replies[1].read1::<bool>(), Ok(true));
+}
+
+#[tokio::test]
+// tests that a panicking async handler is answered with the HandlerPanicked error as well
+#[allow(unreachable_code)]
+async fn test_async_handler_panic_guard() {
+    let sender = Arc::new(RecordingSender(Mutex::new(Vec::new())));
+    let mut cross = Crossroads::new();
+    cross.set_async_support(Some((
+        sender.clone(),
+        Box::new(spawn_handler(sender.clone())),
+    )));
+    let token = cross.register("org.Xetibo.ReSet.Test.Panic", |c| {
+        c.method_with_cr_async("Panic", (), ("result",), |mut ctx, _, ()| async move {
+            panic!("broken async handler");
+            // only determines the reply type of the handler
+            ctx.reply(Ok((true,)))
+        });
+    });
+    cross.insert("/org/Xetibo/ReSet/Test", &[token], ());
+    let mut msg = Message::new_method_call(
+        "org.Xetibo.ReSet.Test",
+        "/org/Xetibo/ReSet/Test",
+        "org.Xetibo.ReSet.Test.Panic",
+        "Panic",
+    )
+    .unwrap();
+    msg.set_serial(1);
+    handle_message_guarded(&mut cross, msg, &*sender);
+    // the reply is sent by the task awaiting the handler
+    for _ in 0..100 {
+        if !sender.0.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let mut replies = sender.0.lock().unwrap();
+    assert_eq!(replies.len(), 1);
+    let error = replies[0].as_result().unwrap_err();
+    assert_eq!(
+        error.name(),
+        Some(StatusCode::HandlerPanicked.error_name().as_str())
+    );
+    assert!(error.message().unwrap().contains("broken async handler"));
+}
+
+#[tokio::test]
+async fn test_get_plugin_info() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<PluginInfo>,)>("GetPluginInfo", BASE, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+async fn test_get_interface_xml() {
+    setup();
+    let res = call_session_dbus_method::<(String,), (String,)>(
+        "GetInterfaceXML",
+        BASE,
+        (String::from(AUDIO),),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let xml = res.unwrap().0;
+    assert!(xml.contains("org.Xetibo.ReSet.Struct.Out0"));
+    let res = call_session_dbus_method::<(String,), (String,)>(
+        "GetInterfaceXML",
+        BASE,
+        (String::from("org.Xetibo.ReSet.Unknown"),),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+// clients rely on the signal being declared in order to generate bindings
+async fn test_capability_state_signal() {
+    setup();
+    let res = call_session_dbus_method::<(String,), (String,)>(
+        "GetInterfaceXML",
+        BASE,
+        (String::from(BASE),),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(res.unwrap().0.contains("CapabilityStateChanged"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_resync_all() {
+    setup();
+    let res = call_session_dbus_method::<(), ()>("ResyncAll", BASE, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_capability_details() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<(String, String, String, String)>,)>(
+        "GetCapabilityDetails",
+        BASE,
+        (),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let details = res.unwrap().0;
+    assert!(details.iter().any(|detail| detail.0 == "WiFi"));
+    assert!(details.iter().any(|detail| detail.0 == "Bluetooth"));
+}
+
+#[tokio::test]
+async fn test_rfkill_state_unknown_kind() {
+    setup();
+    let res = call_session_dbus_method::<(String,), (bool, bool)>(
+        "GetRfkillState",
+        BASE,
+        (String::from("gps"),),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_hooks() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<(String, String)>,)>("ListHooks", BASE, ());
+    assert!(res.is_ok());
+    let res = call_session_dbus_method::<(String,), (Vec<(String, i32)>,)>(
+        "TestHook",
+        BASE,
+        (String::from("wifi_disconnected"),),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_system_bus_requested() {
+    // the flag takes precedence over the config
+    assert!(system_bus_requested(true));
+}
+
+#[tokio::test]
+// tests the modem provided by the mock implementation
+async fn test_list_modems() {
+    setup();
+    let res = call_session_dbus_method::<(), (Vec<Modem>,)>("ListModems", MODEM, ());
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let modems = res.unwrap().0;
+    assert_eq!(modems.len(), 1);
+    assert_eq!(modems[0].2, "test_modem");
+    assert_eq!(modems[0].4, "test_operator");
+}
+
+#[tokio::test]
+// tests rejecting actions of unknown players and unknown actions
+async fn test_player_action_unknown() {
+    setup();
+    let res = call_session_dbus_method::<(String, String), (bool,)>(
+        "PlayerAction",
+        MEDIA,
+        (
+            String::from("org.mpris.MediaPlayer2.not_a_player"),
+            String::from("play"),
+        ),
+    );
+    assert!(res.is_err());
+    let res = call_session_dbus_method::<(String, String), (bool,)>(
+        "PlayerAction",
+        MEDIA,
+        (
+            String::from("org.mpris.MediaPlayer2.not_a_player"),
+            String::from("rewind"),
+        ),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+// tests reading the metadata of MPRIS players
+fn test_track_from_metadata() {
+    let mut metadata = PropMap::new();
+    metadata.insert(
+        String::from("xesam:title"),
+        Variant(Box::new(String::from("Song"))),
+    );
+    metadata.insert(
+        String::from("xesam:artist"),
+        Variant(Box::new(vec![String::from("Band"), String::from("Guest")])),
+    );
+    metadata.insert(
+        String::from("mpris:length"),
+        Variant(Box::new(215_000_000i64)),
+    );
+    metadata.insert(
+        String::from("mpris:trackid"),
+        Variant(Box::new(Path::from("/track/1"))),
+    );
+    let track = track_from_metadata(&metadata);
+    assert_eq!(track.0, "Song");
+    assert_eq!(track.1, vec![String::from("Band"), String::from("Guest")]);
+    assert_eq!(track.2, "");
+    assert_eq!(track.3, 215000);
+}
+
+#[test]
+// tests detecting the notification daemons supporting do not disturb
+fn test_notification_backend() {
+    assert_eq!(
+        NotificationBackend::from_server_name("dunst"),
+        Some(NotificationBackend::Dunst)
+    );
+    assert_eq!(
+        NotificationBackend::from_server_name("SwayNotificationCenter"),
+        Some(NotificationBackend::Swaync)
+    );
+    assert_eq!(NotificationBackend::from_server_name("notify-osd"), None);
+}
+
+#[test]
+// tests toggling the do not disturb mode of mako without touching other modes
+fn test_mako_modes() {
+    let modes = vec![String::from("default"), String::from("work")];
+    let enabled = with_mode(modes, "do-not-disturb", true);
+    assert_eq!(enabled, ["default", "work", "do-not-disturb"]);
+    let enabled = with_mode(enabled, "do-not-disturb", true);
+    assert_eq!(enabled.len(), 3);
+    assert_eq!(
+        with_mode(enabled, "do-not-disturb", false),
+        ["default", "work"]
+    );
+}
+
+#[tokio::test]
+// tests that inhibitors belong to the client which created them
+async fn test_idle_inhibitor() {
+    setup();
+    let conn = Connection::new_session().unwrap();
+    let proxy = conn.with_proxy(BASE_INTERFACE!(), DBUS_PATH!(), Duration::from_millis(4000));
+    let res: Result<(u32,), dbus::Error> = proxy.method_call(IDLE, "Inhibit", ("presentation",));
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    let cookie = res.unwrap().0;
+    let res: Result<(Vec<InhibitorInfo>,), dbus::Error> =
+        proxy.method_call(IDLE, "ListInhibitors", ());
+    let inhibitors = res.unwrap().0;
+    assert!(inhibitors
+        .iter()
+        .any(|inhibitor| inhibitor.0 == cookie && inhibitor.2 == "presentation"));
+    // other clients can not release the inhibitor
+    let res = call_session_dbus_method::<(u32,), (bool,)>("Uninhibit", IDLE, (cookie,));
+    assert!(!res.unwrap().0);
+    let res: Result<(bool,), dbus::Error> = proxy.method_call(IDLE, "Uninhibit", (cookie,));
+    assert!(res.unwrap().0);
+}
+
+#[tokio::test]
+async fn test_unload_unknown_plugin() {
+    setup();
+    let res = call_session_dbus_method::<(String,), (bool,)>(
+        "UnloadPlugin",
+        BASE,
+        (String::from("not_a_plugin"),),
+    );
+    if let Err(_error) = res {
+        panic!("connection failed: {}", (_error));
+    }
+    assert!(!res.unwrap().0);
+}
+
 #[tokio::test]
 async fn test_plugins() {
     use re_set_lib::utils::plugin::plugin_tests;
@@ -401,3 +1764,910 @@ async fn test_plugins() {
 //     setup();
 //     thread::sleep(Duration::from_millis(60 * 60 * 4000));
 // }
+
+#[test]
+// tests converting plugin config sections to dbus and back
+fn test_plugin_config_values() {
+    let table = r#"
+        name = "plugin"
+        interval = 5
+        scale = 1.5
+        enabled = true
+        devices = ["a", "b"]
+        [nested]
+        depth = 2
+    "#
+    .parse::<toml::Table>()
+    .unwrap();
+    let map = table_to_map(&table);
+    let converted: toml::Table = map
+        .iter()
+        .map(|(key, value)| (key.clone(), value_from_arg(&value.0).unwrap()))
+        .collect();
+    assert_eq!(converted, table);
+    assert_eq!(value_from_arg(&42u32), Some(toml::Value::Integer(42)));
+    assert_eq!(value_from_arg(&u64::MAX), None);
+}
+
+#[test]
+// tests that the host API attributes calls to the plugin the daemon is calling
+fn test_plugin_identity() {
+    let signal = PluginSignal {
+        path: String::from("/org/Xetibo/ReSet/Plugins/identity"),
+        interface: String::from("org.Xetibo.ReSet.Identity"),
+        name: String::from("Changed"),
+        signature: String::from("s"),
+    };
+    assert!(!register_plugin_object(String::from("identity")));
+    assert_eq!(register_plugin_signal(signal.clone()), 0);
+    let id = call_plugin("identity", || register_plugin_signal(signal.clone()));
+    assert_ne!(id, 0);
+    assert_eq!(
+        call_plugin("identity", || register_plugin_signal(signal)),
+        id
+    );
+    assert_eq!(get_plugin_signals("identity").len(), 1);
+    // only the registering plugin may emit the signal
+    assert!(get_plugin_signal("identity", id).is_some());
+    assert!(get_plugin_signal("other", id).is_none());
+    remove_plugin_signals("identity");
+}
+
+#[test]
+// the daemon state is shared with the dbus handlers and the listener threads
+fn test_daemon_data_is_thread_safe() {
+    fn assert_thread_safe<T: Send + Sync>() {}
+    assert_thread_safe::<crate::DaemonData>();
+}
+
+#[test]
+// tests that servers need both endpoints as http(s) urls
+fn test_parse_speedtest_servers() {
+    let servers: toml::Table = toml::from_str(
+        r#"
+        [work]
+        download = "https://speed.example.com/100MB.bin"
+        upload = "https://speed.example.com/upload"
+        [home]
+        download = "http://192.168.1.2/100MB.bin"
+        upload = "http://192.168.1.2/upload"
+        [incomplete]
+        download = "http://192.168.1.3/100MB.bin"
+        [ftp]
+        download = "ftp://192.168.1.4/100MB.bin"
+        upload = "ftp://192.168.1.4/upload"
+        "#,
+    )
+    .unwrap();
+    let servers = parse_servers(&servers);
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[0].name, "home");
+    assert_eq!(servers[0].upload, "http://192.168.1.2/upload");
+    assert_eq!(servers[1].name, "work");
+}
+
+#[test]
+// tests the conversion between wake-on-lan modes and the flags of NetworkManager
+fn test_wake_on_lan_mode() {
+    assert_eq!(parse_wake_on_lan_mode("magic,unicast"), Some(0x44));
+    assert_eq!(parse_wake_on_lan_mode("disabled"), Some(0));
+    assert_eq!(parse_wake_on_lan_mode("default"), Some(0x1));
+    assert_eq!(parse_wake_on_lan_mode("default,magic"), None);
+    assert_eq!(parse_wake_on_lan_mode("magic,sleep"), None);
+    assert_eq!(wake_on_lan_mode_name(0x44), "unicast,magic");
+    assert_eq!(wake_on_lan_mode_name(0), "disabled");
+    let packet = magic_packet(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc]);
+    assert_eq!(packet.len(), 102);
+    assert_eq!(packet[..6], [0xff; 6]);
+    assert_eq!(packet[96..], [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc]);
+}
+
+#[test]
+// tests that manual proxies are exported in lower and upper case
+fn test_proxy_environment_file() {
+    let table: toml::Table = toml::from_str(
+        r#"
+        mode = "manual"
+        host = "proxy.example.com"
+        port = 3128
+        ignore_hosts = ["localhost", "127.0.0.1"]
+        "#,
+    )
+    .unwrap();
+    let settings = proxy_from_table(&table);
+    assert!(validate_proxy_settings(&settings).is_ok());
+    let contents = environment_file(&settings);
+    assert!(contents.contains("http_proxy=\"http://proxy.example.com:3128\"\n"));
+    assert!(contents.contains("HTTPS_PROXY=\"http://proxy.example.com:3128\"\n"));
+    assert!(contents.contains("no_proxy=\"localhost,127.0.0.1\"\n"));
+    let settings = proxy_from_table(&toml::Table::new());
+    assert_eq!(settings.0, "none");
+    assert!(!environment_file(&settings).contains("http_proxy"));
+    let auto = (
+        String::from("auto"),
+        String::new(),
+        0,
+        String::new(),
+        Vec::new(),
+    );
+    assert!(validate_proxy_settings(&auto).is_err());
+}
+
+#[test]
+// tests that proxy values can not inject other variables into the session environment
+fn test_proxy_validation() {
+    assert!(is_valid_proxy_host("proxy.example.com"));
+    assert!(is_valid_proxy_host("socks5://10.0.0.1"));
+    assert!(is_valid_proxy_host("[::1]"));
+    assert!(!is_valid_proxy_host("proxy\nLD_PRELOAD=/tmp/evil.so"));
+    assert!(!is_valid_proxy_host("proxy example"));
+    assert!(!is_valid_proxy_host("a=b"));
+    let manual = |host: &str, ignore: &str| {
+        (
+            String::from("manual"),
+            host.to_string(),
+            3128,
+            String::new(),
+            vec![ignore.to_string()],
+        )
+    };
+    assert!(validate_proxy_settings(&manual("proxy", "*.example.com")).is_ok());
+    assert!(validate_proxy_settings(&manual("proxy", "10.0.0.0/8")).is_ok());
+    assert!(validate_proxy_settings(&manual("proxy", "a\nLD_PRELOAD=x")).is_err());
+    assert!(validate_proxy_settings(&manual("proxy", "a b")).is_err());
+}
+
+#[test]
+// tests that the channel advisor avoids channels overlapping with crowded ones
+fn test_channel_utilization() {
+    assert_eq!(frequency_to_channel(2437), Some(("2.4GHz", 6)));
+    assert_eq!(frequency_to_channel(5180), Some(("5GHz", 36)));
+    assert_eq!(frequency_to_channel(5975), Some(("6GHz", 5)));
+    assert_eq!(frequency_to_channel(900), None);
+    let utilization = channel_utilization(&[(2412, 40), (2417, 70), (2437, 50), (5180, 80)]);
+    assert_eq!(utilization.len(), 4);
+    assert_eq!(utilization[0], (String::from("2.4GHz"), 1, 1, 40));
+    // channel 2 overlaps with 1 and 6, which leaves 11 as the only free channel
+    assert_eq!(least_crowded_channel(&utilization, "bg"), Some(11));
+    assert_eq!(least_crowded_channel(&utilization, "a"), Some(40));
+    assert_eq!(least_crowded_channel(&utilization, "n"), None);
+}
+
+#[test]
+// tests that access point frequencies are mapped to their band
+fn test_wifi_band() {
+    assert_eq!(WifiBand::from_frequency(2412), Some(WifiBand::TwoPointFour));
+    assert_eq!(WifiBand::from_frequency(2484), Some(WifiBand::TwoPointFour));
+    assert_eq!(WifiBand::from_frequency(5745), Some(WifiBand::Five));
+    assert_eq!(WifiBand::from_frequency(6115), Some(WifiBand::Six));
+    assert_eq!(WifiBand::from_frequency(0), None);
+    assert_eq!(band_name(5180), "5GHz");
+    assert_eq!(band_name(900), "");
+    assert_eq!(frequency_to_channel(2484), Some(("2.4GHz", 14)));
+    assert_eq!(frequency_to_channel(2477), None);
+}
+
+#[test]
+// tests that status codes keep stable and unique names
+fn test_status_codes() {
+    let mut names: Vec<&str> = StatusCode::ALL.iter().map(|code| code.name()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), StatusCode::ALL.len());
+    for code in StatusCode::ALL {
+        assert_eq!(StatusCode::from_name(code.name()), Some(*code));
+        assert_eq!(StatusCode::from_error_name(&code.error_name()), *code);
+    }
+    assert_eq!(
+        StatusCode::WrongPassword.error_name(),
+        "org.Xetibo.ReSet.Error.WrongPassword"
+    );
+    assert_eq!(
+        StatusCode::InvalidArgument.error_name(),
+        "org.freedesktop.DBus.Error.InvalidArgs"
+    );
+    assert_eq!(
+        StatusCode::from_error_name("org.freedesktop.DBus.Error.NoReply"),
+        StatusCode::Failed
+    );
+    assert_eq!(StatusCode::from_name("Unknown"), None);
+    assert_eq!(
+        connect_error_code("org.bluez.Error.AuthenticationFailed"),
+        StatusCode::PairingFailed
+    );
+    assert_eq!(
+        connect_error_code("org.bluez.Error.AuthenticationTimeout"),
+        StatusCode::Timeout
+    );
+    assert_eq!(
+        connect_error_code("org.bluez.Error.Failed"),
+        StatusCode::ConnectionFailed
+    );
+}
+
+#[test]
+// tests that access point changes below the strength threshold are not delivered
+fn test_strength_threshold() {
+    let state = |strength: u8, stored: bool| (strength, b"ReSet".to_vec(), stored);
+    assert!(significant_change(None, &state(50, false), 5));
+    assert!(!significant_change(
+        Some(&state(50, false)),
+        &state(54, false),
+        5
+    ));
+    assert!(significant_change(
+        Some(&state(50, false)),
+        &state(45, false),
+        5
+    ));
+    assert!(significant_change(
+        Some(&state(50, false)),
+        &state(51, true),
+        5
+    ));
+    assert!(significant_change(
+        Some(&state(50, false)),
+        &state(50, false),
+        0
+    ));
+    let path = Path::from("/org/freedesktop/NetworkManager/AccessPoint/1");
+    let mut filter = StrengthFilter::new(10);
+    assert!(filter.accept(&path, state(50, false)));
+    assert!(!filter.accept(&path, state(55, false)));
+    assert!(!filter.accept(&path, state(59, false)));
+    // the difference is measured against the last delivered strength
+    assert!(filter.accept(&path, state(60, false)));
+    filter.forget(&path);
+    assert!(filter.accept(&path, state(61, false)));
+}
+
+#[test]
+// tests the conversion between powersave modes and NetworkManager values
+fn test_wifi_powersave_mode() {
+    assert_eq!(parse_powersave_mode("disable"), Some(2));
+    assert_eq!(parse_powersave_mode("enable"), Some(3));
+    assert_eq!(parse_powersave_mode("off"), None);
+    assert_eq!(powersave_mode_name(1), "ignore");
+    // unknown values are treated like the global setting
+    assert_eq!(powersave_mode_name(7), "default");
+}
+
+#[test]
+// tests that NetworkManager activation states and reasons are translated for clients
+fn test_activation_progress() {
+    assert_eq!(active_connection_state_name(1), "activating");
+    assert_eq!(active_connection_state_name(2), "activated");
+    assert_eq!(active_connection_state_name(42), "unknown");
+    assert_eq!(active_connection_reason_name(9), "no-secrets");
+    assert_eq!(activation_failure_code(9), StatusCode::WrongPassword);
+    assert_eq!(activation_failure_code(6), StatusCode::Timeout);
+    assert_eq!(activation_failure_code(3), StatusCode::ConnectionFailed);
+}
+
+#[test]
+// tests that failing workers are restarted with backoff until they exit cleanly
+fn test_worker_supervision() {
+    assert_eq!(
+        restart_backoff(None, Duration::from_secs(1)),
+        Duration::from_millis(500)
+    );
+    assert_eq!(
+        restart_backoff(Some(Duration::from_secs(1)), Duration::from_secs(1)),
+        Duration::from_secs(2)
+    );
+    assert_eq!(
+        restart_backoff(Some(Duration::from_secs(20)), Duration::from_secs(1)),
+        Duration::from_secs(30)
+    );
+    // workers that ran stable start over
+    assert_eq!(
+        restart_backoff(Some(Duration::from_secs(20)), Duration::from_secs(120)),
+        Duration::from_millis(500)
+    );
+    let mut runs = 0;
+    supervise_worker("test_worker", move || {
+        runs += 1;
+        match runs {
+            1 => panic!("listener died"),
+            2 => Err(String::from("bus error")),
+            _ => Ok(()),
+        }
+    })
+    .join()
+    .unwrap();
+    let metrics = worker_metrics();
+    let worker = metrics
+        .iter()
+        .find(|worker| worker.0 == "test_worker")
+        .unwrap();
+    assert!(!worker.1);
+    assert_eq!(worker.2, 2);
+    assert_eq!(worker.3, "bus error");
+}
+
+#[test]
+// tests that every startup task can be disabled while plugin capabilities are always enabled
+fn test_feature_flags() {
+    for task in STARTUP_TASKS {
+        assert!(FEATURE_FLAGS
+            .iter()
+            .any(|(capability, _)| capability == task));
+    }
+    assert!(feature_enabled("ExamplePluginCapability"));
+}
+
+#[test]
+// tests the parsing of btmgmt and the main.conf of bluez
+fn test_bluetooth_privacy() {
+    assert_eq!(adapter_index(&Path::from("/org/bluez/hci1")), Some(1));
+    assert_eq!(adapter_index(&Path::from("/org/bluez")), None);
+    let info = "hci0:\tPrimary controller\n\
+        \taddr 00:1A:7D:DA:71:13 version 8 manufacturer 10 class 0x000000\n\
+        \tsupported settings: powered le advertising privacy static-addr\n\
+        \tcurrent settings: powered le\n";
+    assert!(mgmt_settings(info, "supported settings").contains(&String::from("privacy")));
+    assert!(!mgmt_settings(info, "current settings").contains(&String::from("privacy")));
+    assert_eq!(
+        mgmt_error_code("Set Privacy for hci0 failed with status 0x14 (Permission Denied)"),
+        StatusCode::AuthorizationDenied
+    );
+    let config = "[General]\n#JustWorksRepairing = never\nName = ReSet\n\n[Policy]\nJustWorksRepairing = always\n";
+    assert_eq!(main_conf_value(config, "JustWorksRepairing"), None);
+    assert_eq!(
+        main_conf_value(
+            "[General]\nJustWorksRepairing = confirm\n",
+            "JustWorksRepairing"
+        )
+        .as_deref(),
+        Some("confirm")
+    );
+    // the daemon does not write the configuration of bluez
+    assert!(set_just_works_repairing().is_err());
+}
+
+#[test]
+// tests the validation of cloned addresses and the randomization values of NetworkManager
+fn test_wifi_mac_randomization() {
+    assert!(is_mac_address("02:1A:7D:DA:71:13"));
+    assert!(!is_mac_address("02:1A:7D:DA:71"));
+    assert!(!is_mac_address("random"));
+    assert!(!is_randomization_enabled(Some(1)));
+    assert!(is_randomization_enabled(Some(2)));
+    // NetworkManager randomizes without configuration
+    assert!(is_randomization_enabled(Some(0)));
+    assert!(is_randomization_enabled(None));
+}
+
+#[test]
+// tests the policies of OBEX pushes and the file names of received files
+fn test_obex_policy() {
+    assert_eq!(effective_policy(None, true), "accept");
+    assert_eq!(effective_policy(None, false), "prompt");
+    assert_eq!(effective_policy(Some("reject"), true), "reject");
+    assert_eq!(effective_policy(Some("accept"), false), "accept");
+    let dir = std::path::Path::new("/home/user/Downloads");
+    let existing = [
+        dir.join("photo.jpg"),
+        dir.join("photo (1).jpg"),
+        dir.join("notes"),
+    ];
+    let exists = |path: &std::path::Path| existing.iter().any(|existing| existing == path);
+    assert_eq!(
+        download_path(dir, "photo.jpg", exists),
+        dir.join("photo (2).jpg")
+    );
+    assert_eq!(download_path(dir, "notes", exists), dir.join("notes (1)"));
+    assert_eq!(download_path(dir, ".bashrc", exists), dir.join(".bashrc"));
+    // remote devices can not write outside of the download directory
+    assert_eq!(
+        download_path(dir, "../../.bashrc", exists),
+        dir.join(".bashrc")
+    );
+    assert_eq!(download_path(dir, "..", exists), dir.join("file"));
+    // remote devices choose the file names, e.g. autostart entries
+    let home = std::path::Path::new("/home/user");
+    assert!(is_allowed_download_dir(dir, home));
+    assert!(!is_allowed_download_dir(home, home));
+    assert!(!is_allowed_download_dir(
+        std::path::Path::new("/home/user/.config/autostart"),
+        home
+    ));
+    assert!(!is_allowed_download_dir(std::path::Path::new("/tmp"), home));
+}
+
+#[test]
+// tests the conversion of the DNS configuration of NetworkManager
+fn test_dns_state() {
+    let mut map = PropMap::new();
+    map.insert(
+        String::from("nameservers"),
+        Variant(Box::new(vec![String::from("192.168.1.1")])),
+    );
+    map.insert(
+        String::from("domains"),
+        Variant(Box::new(vec![String::from("lan")])),
+    );
+    map.insert(
+        String::from("interface"),
+        Variant(Box::new(String::from("wlan0"))),
+    );
+    map.insert(String::from("priority"), Variant(Box::new(100)));
+    let wifi = dns_configuration_from_map(&map);
+    assert_eq!(
+        wifi,
+        (
+            String::new(),
+            String::from("wlan0"),
+            vec![String::from("192.168.1.1")],
+            vec![String::from("lan")],
+            100,
+            false
+        )
+    );
+    let vpn = (
+        String::from("Work"),
+        String::from("tun0"),
+        vec![String::from("10.0.0.1"), String::from("192.168.1.1")],
+        Vec::new(),
+        50,
+        true,
+    );
+    let global = (
+        String::new(),
+        String::new(),
+        vec![String::from("9.9.9.9")],
+        Vec::new(),
+        50,
+        false,
+    );
+    // lower priorities first, the global configuration wins ties, duplicates are dropped
+    assert_eq!(
+        merge_dns_servers(&[wifi, vpn, global]),
+        vec![
+            String::from("9.9.9.9"),
+            String::from("10.0.0.1"),
+            String::from("192.168.1.1")
+        ]
+    );
+}
+
+#[test]
+// tests which devices are removed by the Bluetooth cleanup
+fn test_bluetooth_cleanup() {
+    let adapter = Path::from("/org/bluez/hci0");
+    let device = |address: &str, paired: bool, connected: bool| {
+        (
+            Path::from(format!("/org/bluez/hci0/dev_{}", address.replace(':', "_"))),
+            adapter.clone(),
+            address.to_string(),
+            paired,
+            connected,
+        )
+    };
+    let devices = vec![
+        device("00:00:00:00:00:01", false, false),
+        device("00:00:00:00:00:02", true, false),
+        device("00:00:00:00:00:03", false, true),
+        device("00:00:00:00:00:04", true, true),
+    ];
+    let unpaired = select_unpaired(&devices);
+    assert_eq!(unpaired.len(), 1);
+    assert_eq!(unpaired[0].2, "00:00:00:00:00:01");
+    let last_seen = |address: &str| match address {
+        "00:00:00:00:00:01" => 2000,
+        "00:00:00:00:00:02" => 500,
+        "00:00:00:00:00:03" => 100,
+        _ => 0,
+    };
+    // connected devices are kept even if they were not recorded
+    let stale: Vec<&str> = select_not_seen_since(&devices, 1000, last_seen)
+        .iter()
+        .map(|device| device.2.as_str())
+        .collect();
+    assert_eq!(stale, vec!["00:00:00:00:00:02"]);
+}
+
+#[test]
+// tests when new streams are moved to the sink learned for their application
+fn test_stream_routing() {
+    let sinks = vec![
+        (
+            1,
+            String::from("alsa_output.pci-0000_00_1f.3.analog-stereo"),
+        ),
+        (7, String::from("alsa_output.usb-headset.analog-stereo")),
+    ];
+    assert_eq!(
+        routing_target("alsa_output.usb-headset.analog-stereo", 1, &sinks),
+        Some(7)
+    );
+    // already playing on the learned sink
+    assert_eq!(
+        routing_target("alsa_output.usb-headset.analog-stereo", 7, &sinks),
+        None
+    );
+    // the learned sink is unplugged
+    assert_eq!(
+        routing_target("bluez_output.00_1A_7D_DA_71_13.1", 1, &sinks),
+        None
+    );
+}
+
+#[test]
+// tests recognizing locations by their visible SSIDs and ranking their connections
+fn test_autoconnect_priority_learning() {
+    let ssids =
+        |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+    let home = ssids(&["Home", "Neighbour", "Neighbour-5G", "Printer"]);
+    let office = ssids(&["Office", "Office-Guest", "Cafe"]);
+    assert_eq!(location_similarity(&home, &home), 1.0);
+    assert_eq!(location_similarity(&home, &office), 0.0);
+    assert_eq!(location_similarity(&[], &[]), 0.0);
+    let locations = vec![
+        (
+            home,
+            vec![(String::from("Home"), 12), (String::from("Hotspot"), 2)],
+        ),
+        (office, vec![(String::from("Office"), 3)]),
+    ];
+    // a neighbour is out of range, still the same location
+    assert_eq!(
+        find_location(&locations, &ssids(&["Home", "Neighbour", "Printer"])),
+        Some(0)
+    );
+    assert_eq!(
+        find_location(&locations, &ssids(&["Office", "Office-Guest"])),
+        Some(1)
+    );
+    assert_eq!(find_location(&locations, &ssids(&["Airport"])), None);
+    assert_eq!(
+        learned_priorities(&locations[0].1),
+        vec![(String::from("Home"), 2), (String::from("Hotspot"), 1)]
+    );
+}
+
+#[test]
+// tests that network sinks are recognized and hosts can not inject module arguments
+fn test_network_sinks() {
+    assert_eq!(
+        network_sink_kind("raop_output.livingroom.local", "module-raop-sink.c"),
+        Some("raop")
+    );
+    assert_eq!(
+        network_sink_kind("tunnel.desktop.local.alsa_output", "PipeWire"),
+        Some("tunnel")
+    );
+    assert_eq!(
+        network_sink_kind("alsa_output.pci-0000_00_1f.3.analog-stereo", "PipeWire"),
+        None
+    );
+    assert_eq!(
+        tunnel_sink_arguments("desktop.local:4713"),
+        Some(String::from("server=tcp:desktop.local:4713"))
+    );
+    assert_eq!(
+        tunnel_sink_arguments("[fe80::1]"),
+        Some(String::from("server=tcp:[fe80::1]"))
+    );
+    assert_eq!(tunnel_sink_arguments("host sink_name=evil"), None);
+    assert_eq!(tunnel_sink_arguments(""), None);
+}
+
+#[test]
+// tests that the storage backends return the stored tables and empty tables for unknown names
+fn test_state_storage() {
+    let mut table = toml::Table::new();
+    table.insert(
+        String::from("Firefox"),
+        toml::Value::String(String::from("hdmi")),
+    );
+    let directory = std::env::temp_dir().join(format!("reset_storage_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let backends: Vec<Box<dyn StateStorage>> = vec![
+        Box::new(FileStorage::new(directory.clone())),
+        Box::new(MemoryStorage::default()),
+    ];
+    for storage in backends {
+        assert!(storage.load("routing.toml").is_empty());
+        assert!(storage.save("routing.toml", &table));
+        assert_eq!(storage.load("routing.toml"), table);
+    }
+    std::fs::write(directory.join("broken.toml"), "not = [toml").unwrap();
+    assert!(FileStorage::new(directory.clone())
+        .load("broken.toml")
+        .is_empty());
+    let _ = std::fs::remove_dir_all(directory);
+}
+
+#[test]
+// tests that only capabilities that appeared or disappeared are reported as changed
+fn test_capability_changes() {
+    let (added, removed) = capability_changes(&["WiFi", "Audio", "Weather"], &["WiFi", "Audio"]);
+    assert!(added.is_empty());
+    assert_eq!(removed, vec![String::from("Weather")]);
+    let (added, removed) = capability_changes(&["WiFi"], &["WiFi", "Weather"]);
+    assert_eq!(added, vec![String::from("Weather")]);
+    assert!(removed.is_empty());
+    assert_eq!(
+        capability_changes(&["WiFi"], &["WiFi"]),
+        (Vec::new(), Vec::new())
+    );
+}
+
+#[test]
+// tests that hotspot stations are combined with their DHCP leases
+fn test_hotspot_clients() {
+    let dump = "Station AA:BB:CC:DD:EE:01 (on wlan0)\n\tinactive time:\t120 ms\n\tconnected time:\t300 seconds\nStation aa:bb:cc:dd:ee:02 (on wlan0)\n\tconnected time:\t5 seconds\n";
+    let stations = parse_station_dump(dump);
+    assert_eq!(
+        stations,
+        vec![
+            (String::from("aa:bb:cc:dd:ee:01"), 300),
+            (String::from("aa:bb:cc:dd:ee:02"), 5)
+        ]
+    );
+    let leases = parse_leases("1700000000 aa:bb:cc:dd:ee:01 10.42.0.23 phone 01:aa:bb\n1700000000 aa:bb:cc:dd:ee:02 10.42.0.24 * *\n");
+    let clients = hotspot_clients(&stations, &leases, 1000);
+    assert_eq!(
+        clients[0],
+        (
+            String::from("aa:bb:cc:dd:ee:01"),
+            String::from("10.42.0.23"),
+            String::from("phone"),
+            700
+        )
+    );
+    assert_eq!(clients[1].2, "");
+    let (connected, disconnected) = hotspot_client_changes(&clients[..1], &clients[1..]);
+    assert_eq!(connected, vec![clients[1].clone()]);
+    assert_eq!(disconnected, vec![String::from("aa:bb:cc:dd:ee:01")]);
+}
+
+#[test]
+// tests that guessed metered connections count as metered
+fn test_metered_context() {
+    assert!(!is_metered(0));
+    assert!(is_metered(1));
+    assert!(!is_metered(2));
+    assert!(is_metered(3));
+    assert!(!is_metered(4));
+}
+
+#[test]
+// tests that power supplies and missing batteries are not listed
+fn test_battery_conversion() {
+    assert_eq!(battery_kind(2), "battery");
+    assert_eq!(battery_kind(5), "mouse");
+    assert_eq!(battery_kind(99), "unknown");
+    assert_eq!(battery_state(1), "charging");
+    assert_eq!(battery_state(0), "unknown");
+    let path = Path::from("/org/freedesktop/UPower/devices/mouse_dev_1");
+    let mut properties = PropMap::new();
+    properties.insert(String::from("Type"), Variant(Box::new(5_u32)));
+    properties.insert(String::from("IsPresent"), Variant(Box::new(true)));
+    properties.insert(String::from("Model"), Variant(Box::new(String::from("MX"))));
+    properties.insert(String::from("Percentage"), Variant(Box::new(42.0_f64)));
+    properties.insert(String::from("State"), Variant(Box::new(2_u32)));
+    let battery = convert_battery(path.clone(), &properties).unwrap();
+    assert_eq!(battery.1, "mouse");
+    assert_eq!(battery.2, "MX");
+    assert_eq!(battery.3, 42.0);
+    assert_eq!(battery.4, "discharging");
+    assert!(!battery.5);
+    properties.insert(String::from("IsPresent"), Variant(Box::new(false)));
+    assert!(convert_battery(path.clone(), &properties).is_none());
+    properties.insert(String::from("IsPresent"), Variant(Box::new(true)));
+    properties.insert(String::from("Type"), Variant(Box::new(1_u32)));
+    assert!(convert_battery(path, &properties).is_none());
+}
+
+#[test]
+// tests that only common rates are forced and 0 resets the rate
+fn test_sample_rate() {
+    assert!(is_supported_sample_rate(0));
+    assert!(is_supported_sample_rate(48000));
+    assert!(is_supported_sample_rate(96000));
+    assert!(!is_supported_sample_rate(12345));
+    assert_eq!(
+        sample_rate_arguments(96000),
+        vec!["-n", "settings", "0", "clock.force-rate", "96000"]
+    );
+}
+
+#[test]
+// tests that the virtual devices are named and described for frontends
+fn test_virtual_device_arguments() {
+    let sink = virtual_sink_arguments();
+    assert!(sink.starts_with(&format!("sink_name={} ", VIRTUAL_SINK_NAME)));
+    assert!(sink.contains("device.description=\"ReSet Virtual Sink\""));
+    let source = virtual_source_arguments();
+    assert!(source.starts_with(&format!("source_name={} ", VIRTUAL_SOURCE_NAME)));
+    assert!(source.contains("device.description=\"ReSet Virtual Source\""));
+}
+
+#[tokio::test]
+// tests that operations of a device run in order and conflicting ones are rejected
+async fn test_operation_queue() {
+    let queue = OperationQueue::default();
+    let device = Path::from("/org/freedesktop/NetworkManager/Devices/3");
+    let other = Path::from("/org/freedesktop/NetworkManager/Devices/4");
+    let (connect, position) = queue
+        .push(&device, DeviceOperation::Connect, "/ap/1")
+        .unwrap();
+    assert_eq!(position, 0);
+    let err = queue.push(&device, DeviceOperation::Connect, "/ap/2");
+    assert!(err.is_err());
+    let (disconnect, position) = queue
+        .push(&device, DeviceOperation::Disconnect, "")
+        .unwrap();
+    assert_eq!(position, 1);
+    // background scans are skipped while clients operate on the device
+    assert!(queue.try_push(&device, DeviceOperation::Scan, "").is_none());
+    assert!(queue.try_push(&other, DeviceOperation::Scan, "").is_some());
+    assert_eq!(
+        queue.list(&device),
+        vec![
+            (String::from("connect"), String::from("/ap/1")),
+            (String::from("disconnect"), String::new())
+        ]
+    );
+    queue.finish(&device, connect);
+    // returns immediately as the disconnect is the first operation now
+    queue.wait(&device, disconnect).await;
+    queue.finish(&device, disconnect);
+    assert!(queue.list(&device).is_empty());
+}
+
+#[test]
+// tests that RSSI jitter below the threshold is not reported
+fn test_rssi_threshold() {
+    assert!(rssi_changed(None, -70, 5));
+    assert!(!rssi_changed(Some(-70), -73, 5));
+    assert!(rssi_changed(Some(-70), -75, 5));
+    assert!(rssi_changed(Some(-70), -65, 5));
+    // a threshold of 0 reports every change, but not unchanged values
+    assert!(rssi_changed(Some(-70), -71, 0));
+    assert!(!rssi_changed(Some(-70), -70, 0));
+}
+
+#[test]
+fn test_device_hints() {
+    assert_eq!(
+        device_hints(Some("headphone"), Some("audio-headphones-bluetooth"), None),
+        (
+            String::from("headphone"),
+            String::from("audio-headphones-bluetooth")
+        )
+    );
+    assert_eq!(device_hints(Some("headset"), None, None).1, "audio-headset");
+    assert_eq!(
+        device_hints(None, Some("audio-card-pci"), Some("output:hdmi-stereo")),
+        (String::new(), String::from("video-display"))
+    );
+    assert_eq!(
+        device_hints(None, Some(""), Some("output:analog-stereo")).1,
+        GENERIC_DEVICE_ICON
+    );
+}
+
+#[test]
+fn test_anonymous_devices() {
+    let device = |address_type: &str| {
+        let mut map = PropMap::new();
+        map.insert(
+            String::from("AddressType"),
+            Variant(Box::new(address_type.to_string()) as Box<dyn RefArg>),
+        );
+        map.insert(String::from("RSSI"), Variant(Box::new(-70_i16)));
+        map
+    };
+    assert!(is_anonymous_device(&device("random")));
+    assert!(!is_anonymous_device(&device("public")));
+    let mut named = device("random");
+    named.insert(
+        String::from("Name"),
+        Variant(Box::new(String::from("Mouse"))),
+    );
+    assert!(!is_anonymous_device(&named));
+    let mut paired = device("random");
+    paired.insert(String::from("Paired"), Variant(Box::new(true)));
+    assert!(!is_anonymous_device(&paired));
+
+    let mut devices = AnonymousDevices::default();
+    let beacon = Path::from("/org/bluez/hci0/dev_AA");
+    let other = Path::from("/org/bluez/hci0/dev_BB");
+    assert_eq!(devices.update(&beacon, true), DeviceVisibility::Hidden);
+    assert_eq!(devices.update(&beacon, true), DeviceVisibility::Hidden);
+    assert_eq!(devices.update(&other, true), DeviceVisibility::Hidden);
+    assert_eq!(devices.count(), 2);
+    assert_eq!(devices.update(&beacon, false), DeviceVisibility::Revealed);
+    assert_eq!(devices.update(&beacon, false), DeviceVisibility::Visible);
+    assert!(devices.remove(&other));
+    assert!(!devices.remove(&beacon));
+    assert_eq!(devices.count(), 0);
+}
+
+#[test]
+// tests that disconnected clients are removed along with the peak monitors only they used
+fn test_client_registry() {
+    let clients = ClientRegistry::default();
+    clients.register(String::from("ReSet"), ":1.10");
+    clients.register(String::from("ReSet-Tray"), ":1.10");
+    let widget = clients.register(String::from("Widget"), ":1.11");
+    assert_eq!(clients.list().len(), 3);
+    clients.add_peak_monitor(4, ":1.10");
+    clients.add_peak_monitor(5, ":1.10");
+    clients.add_peak_monitor(5, ":1.11");
+    let (names, peak_monitors) = clients.remove_connection(":1.10");
+    assert_eq!(
+        names,
+        vec![String::from("ReSet"), String::from("ReSet-Tray")]
+    );
+    // the monitor of input stream 5 is still used by the widget
+    assert_eq!(peak_monitors, vec![4]);
+    let remaining = clients.list();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].0, "Widget");
+    assert_eq!(remaining[0].1, ":1.11");
+    assert_eq!(clients.unregister(&widget), Some(String::from(":1.11")));
+    assert!(clients.unregister(&widget).is_none());
+    assert_eq!(clients.remove_connection(":1.11"), (Vec::new(), vec![5]));
+}
+
+#[test]
+// tests that a crashed client resumes its registration with its preferences
+fn test_client_resume() {
+    let clients = ClientRegistry::default();
+    let token = clients.register(String::from("ReSet"), ":1.10");
+    let other = clients.register(String::from("ReSet"), ":1.11");
+    assert_ne!(token, other);
+    assert_eq!(token.len(), 32);
+    // only the connection of the client may change its preferences
+    assert!(!clients.update_preferences(&token, ":1.11", |preferences| preferences.delta = true));
+    assert!(clients.update_preferences(&token, ":1.10", |preferences| {
+        preferences.delta = true;
+        preferences.strength_threshold = Some(10);
+    }));
+    // the registration is still used by its connection
+    assert!(clients.resume(&token, ":1.12").is_none());
+    clients.remove_connection(":1.10");
+    assert_eq!(clients.list().len(), 1);
+    let preferences = clients.resume(&token, ":1.12").unwrap();
+    assert!(preferences.delta);
+    assert_eq!(preferences.strength_threshold, Some(10));
+    assert_eq!(preferences.intervals, None);
+    assert_eq!(clients.list().len(), 2);
+    assert!(clients.resume("unknown", ":1.12").is_none());
+    assert_eq!(clients.unregister(&token), Some(String::from(":1.12")));
+    assert!(clients.resume(&token, ":1.13").is_none());
+}
+
+#[test]
+// tests that the test tone is only played on the requested channel
+fn test_channel_test_tone() {
+    assert!(parse_channel_position("front-left").is_some());
+    assert!(parse_channel_position("lfe").is_some());
+    assert!(parse_channel_position("left-ish").is_none());
+    assert!(parse_channel_position("").is_none());
+    let rate = 8000;
+    let tone = test_tone(3, 1, false, rate);
+    let samples: Vec<f32> = tone
+        .chunks_exact(4)
+        .map(|sample| f32::from_ne_bytes(sample.try_into().unwrap()))
+        .collect();
+    assert_eq!(samples.len(), rate as usize * 3);
+    assert!(samples
+        .iter()
+        .skip(1)
+        .step_by(3)
+        .any(|sample| *sample != 0.0));
+    assert!(samples.iter().step_by(3).all(|sample| *sample == 0.0));
+    assert!(samples
+        .iter()
+        .skip(2)
+        .step_by(3)
+        .all(|sample| *sample == 0.0));
+    // faded in and out
+    assert_eq!(samples[1], 0.0);
+    assert_eq!(samples[samples.len() - 2], 0.0);
+}
@@ -1,35 +1,57 @@
 use std::{
     collections::HashMap,
-    hint,
+    fs,
+    ptr::addr_of,
     sync::{
-        atomic::{AtomicBool, AtomicI8, AtomicU8, Ordering},
-        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
     },
     thread,
+    time::Duration,
 };
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use dbus::{
     arg::{self, PropMap, RefArg, Variant},
+    blocking::Connection,
+    channel::{BusType, Channel},
     nonblock::SyncConnection,
-    Path,
+    Message, Path,
 };
+use dbus_tokio::connection::{self, IOResource};
+use pulse::channelmap::Position;
 
+#[cfg(debug_assertions)]
+use re_set_lib::utils::macros::ErrorLevel;
 use re_set_lib::{
     audio::audio_structures::{Card, InputStream, OutputStream, Sink, Source},
     network::network_structures::Error,
-    utils::dbus_utils::get_system_dbus_property,
-    ERROR,
+    utils::{
+        config::{CONFIG, CONFIG_STRING},
+        dbus_utils::get_system_dbus_property,
+    },
+    ERROR, LOG,
 };
-#[cfg(debug_assertions)]
-use re_set_lib::{utils::macros::ErrorLevel, write_log_to_file};
 
 use tokio::task::JoinHandle;
 
 use crate::{
-    audio::audio_manager::PulseServer,
-    bluetooth::bluetooth_manager::{BluetoothAgent, BluetoothInterface},
+    audio::{
+        audio_manager::PulseServer, channel_test::ChannelPosition, device_hints::DeviceHints,
+        network_sinks::NetworkSink, server_info::AudioServerInfo,
+    },
+    bluetooth::{
+        bluetooth_manager::{BluetoothAgent, BluetoothInterface},
+        scan::{ScanState, SharedScanState},
+    },
+    capabilities::{STATUS_ACTIVE, STATUS_UNAVAILABLE},
+    clients::ClientRegistry,
+    mock::sound::MockAudio,
     network::network_manager::{get_wifi_devices, Device},
+    startup::{feature_enabled, StartupTasks, STARTUP_AUDIO, STARTUP_BLUETOOTH, STARTUP_WIFI},
+    status::StatusCode,
+    storage::{load_state, save_state},
+    supervisor::{emit_capability_state, supervise_worker, WORKER_AUDIO},
 };
 
 pub enum Mode {
@@ -57,7 +79,16 @@ pub struct ConstPaths {
 }
 
 pub const AUDIO: &str = "org.Xetibo.ReSet.Audio";
+pub const ACTIONS: &str = "org.Xetibo.ReSet.Actions";
 pub const BASE: &str = "org.Xetibo.ReSet.Daemon";
+pub const BATTERY: &str = "org.Xetibo.ReSet.Battery";
+pub const MODEM: &str = "org.Xetibo.ReSet.Modem";
+pub const MEDIA: &str = "org.Xetibo.ReSet.Media";
+pub const NOTIFICATIONS: &str = "org.Xetibo.ReSet.Notifications";
+pub const IDLE: &str = "org.Xetibo.ReSet.Idle";
+pub const SPEEDTEST: &str = "org.Xetibo.ReSet.SpeedTest";
+pub const PROXY: &str = "org.Xetibo.ReSet.Proxy";
+pub const DAEMON_CONFIG: &str = "Daemon";
 
 pub type MaskedPropMap = HashMap<String, PropMap>;
 
@@ -78,7 +109,10 @@ pub enum AudioRequest {
     GetDefaultSinkName,
     SetSinkVolume(u32, u16, u32),
     SetSinkMute(u32, bool),
+    SuspendSink(u32, bool),
     SetDefaultSink(String),
+    ChangeDefaultSinkVolume(i32),
+    ToggleDefaultSinkMute,
     ListInputStreams,
     SetSinkOfInputStream(u32, u32),
     SetInputStreamVolume(u32, u16, u32),
@@ -89,6 +123,38 @@ pub enum AudioRequest {
     SetOutputStreamMute(u32, bool),
     ListCards,
     SetCardProfileOfDevice(u32, String),
+    ListSourceMonitors,
+    CreateLoopback(u32, u32, u32),
+    RemoveLoopback(u32),
+    SetSinkDescription(u32, String),
+    SetSourceDescription(u32, String),
+    SetNoiseSuppression(u32, bool),
+    ListNoiseSuppressedSources,
+    DiscoverNetworkSinks,
+    ConnectNetworkSink(String),
+    DisconnectNetworkSink(String),
+    StartStreamPeakMonitor(u32),
+    StopStreamPeakMonitor(u32),
+    /// Stops the peak monitors of a disconnected client, no response is sent.
+    ReleasePeakMonitors(Vec<u32>),
+    GetSinkChannelMap(u32),
+    GetSinkHints(u32),
+    GetSourceHints(u32),
+    PlayChannelTest(u32, Position),
+    SetBluetoothAutoRoute(bool),
+    BluetoothDeviceConnected(String),
+    BluetoothDeviceDisconnected(String),
+    BluetoothSinkAdded(String),
+    InputStreamAdded(u32, String, u32),
+    SetAutoHeadsetProfile(bool),
+    SourceOutputsChanged,
+    AudioDevicesChanged,
+    GetServerInfo,
+    GetAudioServerInfo,
+    CreateVirtualDevices,
+    SetDefaultSampleRate(u32),
+    RefreshCache,
+    Resync,
     StopListener,
 }
 
@@ -102,12 +168,32 @@ pub enum AudioResponse {
     InputStreams(Vec<InputStream>),
     OutputStreams(Vec<OutputStream>),
     Cards(Vec<Card>),
+    SourceMonitors(Vec<(u32, bool, u32)>),
+    Loopback(u32),
+    LoopbackRemoved(bool),
+    DescriptionSet(bool),
+    NoiseSuppression(bool),
+    NoiseSuppressedSources(Vec<(u32, u32)>),
+    NetworkSinks(Vec<NetworkSink>),
+    NetworkSink(bool),
+    PeakMonitor(bool),
+    ChannelMap(Vec<ChannelPosition>),
+    DeviceHints(DeviceHints),
+    ChannelTest(bool),
+    SinkSuspended(bool),
+    ServerInfo(String),
+    AudioServerInfo(AudioServerInfo),
+    /// None if the server can not change the rate at runtime.
+    DefaultSampleRate(Option<bool>),
+    CacheRefreshed,
     Error,
 }
 
 pub struct DaemonData {
     pub n_devices: Vec<Arc<RwLock<Device>>>,
     pub current_n_device: Arc<RwLock<Device>>,
+    /// Path of current_n_device, which can be read while an operation holds the device.
+    pub current_n_device_path: Path<'static>,
     pub b_interface: BluetoothInterface,
     pub bluetooth_agent: BluetoothAgent,
     pub audio_sender: Arc<Sender<AudioRequest>>,
@@ -117,69 +203,128 @@ pub struct DaemonData {
     pub network_stop_requested: Arc<AtomicBool>,
     pub bluetooth_listener_active: Arc<AtomicBool>,
     pub bluetooth_stop_requested: Arc<AtomicBool>,
-    pub bluetooth_scan_request: Arc<AtomicI8>,
-    pub bluetooth_scan_active: Arc<AtomicBool>,
-    pub clients: HashMap<String, usize>,
+    pub audio_routing_stop_requested: Arc<AtomicBool>,
+    pub bluetooth_scan: SharedScanState,
+    pub clients: Arc<ClientRegistry>,
     pub connection: Arc<SyncConnection>,
     pub handle: JoinHandle<()>,
 }
 
-unsafe impl Send for DaemonData {}
-unsafe impl Sync for DaemonData {}
-
 impl DaemonData {
-    pub fn create(handle: JoinHandle<()>, conn: Arc<SyncConnection>) -> Result<Self, Error> {
+    /// Connects to the audio server and enumerates the WiFi and Bluetooth devices concurrently,
+    /// returns once the quorum of the startup tasks finished.
+    pub fn create(
+        handle: JoinHandle<()>,
+        conn: Arc<SyncConnection>,
+        startup: Arc<StartupTasks>,
+    ) -> Result<Self, Error> {
+        let (dbus_pulse_sender, pulse_receiver): (Sender<AudioRequest>, Receiver<AudioRequest>) =
+            unbounded();
+        let (pulse_sender, dbus_pulse_receiver): (Sender<AudioResponse>, Receiver<AudioResponse>) =
+            unbounded();
+        let audio_listener_active = Arc::new(AtomicBool::new(false));
+        let audio_listener_ref = audio_listener_active.clone();
+        let connection_ref = conn.clone();
+        let request_sender_ref = dbus_pulse_sender.clone();
+        let audio_startup = startup.clone();
+        if test_bus_address().is_some() {
+            audio_listener_active.store(true, Ordering::SeqCst);
+            startup.finish(STARTUP_AUDIO, true);
+            thread::spawn(move || {
+                MockAudio::default().listen_to_messages(pulse_sender, pulse_receiver)
+            });
+        } else if feature_enabled(STARTUP_AUDIO) {
+            let mut restarted = false;
+            supervise_worker(WORKER_AUDIO, move || {
+                let res = PulseServer::create(
+                    pulse_sender.clone(),
+                    pulse_receiver.clone(),
+                    request_sender_ref.clone(),
+                    connection_ref.clone(),
+                );
+                match res {
+                    Ok(mut res) => {
+                        audio_listener_ref.store(true, Ordering::SeqCst);
+                        if restarted {
+                            LOG!("Audio server reconnected");
+                        } else if audio_startup.finish(STARTUP_AUDIO, true) {
+                            LOG!("Audio server connected after startup");
+                            emit_capability_state(
+                                &connection_ref,
+                                STARTUP_AUDIO,
+                                STATUS_ACTIVE,
+                                StatusCode::Ok,
+                                "",
+                            );
+                        }
+                        restarted = true;
+                        res.listen_to_messages()
+                            .map_err(|error| error.0.to_string())
+                    }
+                    // keep retrying, the audio server was reachable before
+                    Err(error) if restarted => Err(error.0.to_string()),
+                    Err(_error) => {
+                        ERROR!(format!("{}", _error.0), ErrorLevel::PartialBreakage);
+                        ERROR!(
+                            "Could not create audio sender, aborting",
+                            ErrorLevel::PartialBreakage
+                        );
+                        if audio_startup.finish(STARTUP_AUDIO, false) {
+                            emit_capability_state(
+                                &connection_ref,
+                                STARTUP_AUDIO,
+                                STATUS_UNAVAILABLE,
+                                StatusCode::PulseUnavailable,
+                                "Could not connect to the audio server.",
+                            );
+                        }
+                        Ok(())
+                    }
+                }
+            });
+        } else {
+            LOG!("Audio is disabled in the config");
+            startup.finish(STARTUP_AUDIO, false);
+        }
+
         // TODO create check for pcs that don't offer wifi
-        let mut n_devices = get_wifi_devices();
+        let (mut n_devices, b_interface_opt) = thread::scope(|scope| {
+            let wifi = scope.spawn(|| {
+                if !feature_enabled(STARTUP_WIFI) {
+                    return Vec::new();
+                }
+                get_wifi_devices()
+            });
+            let bluetooth = scope.spawn(|| {
+                feature_enabled(STARTUP_BLUETOOTH)
+                    .then(|| BluetoothInterface::create(conn.clone()))
+                    .flatten()
+            });
+            let n_devices = wifi.join();
+            startup.finish(STARTUP_WIFI, n_devices.is_ok());
+            let b_interface_opt = bluetooth.join();
+            startup.finish(STARTUP_BLUETOOTH, b_interface_opt.is_ok());
+            (
+                n_devices.unwrap_or_default(),
+                b_interface_opt.unwrap_or_default(),
+            )
+        });
         let current_n_device = n_devices.pop().unwrap_or(Arc::new(RwLock::new(Device::new(
             Path::from("/"),
             String::from("empty"),
         ))));
-        let b_interface_opt = BluetoothInterface::create(conn.clone());
         let b_interface: BluetoothInterface = if let Some(b_interface_opt) = b_interface_opt {
             b_interface_opt
         } else {
             BluetoothInterface::empty()
         };
+        startup.wait_for_quorum();
 
-        let (dbus_pulse_sender, pulse_receiver): (Sender<AudioRequest>, Receiver<AudioRequest>) =
-            unbounded();
-        let (pulse_sender, dbus_pulse_receiver): (Sender<AudioResponse>, Receiver<AudioResponse>) =
-            unbounded();
-        let audio_listener_active = Arc::new(AtomicBool::new(false));
-        let audio_listener_ref = audio_listener_active.clone();
-        let connection_ref = conn.clone();
-        let running = Arc::new(AtomicU8::new(0));
-        let running_ref = running.clone();
-        thread::spawn(move || {
-            let res = PulseServer::create(pulse_sender, pulse_receiver, connection_ref);
-            if let Ok(mut res) = res {
-                audio_listener_ref.store(true, Ordering::SeqCst);
-                running_ref.store(1, Ordering::SeqCst);
-                res.listen_to_messages();
-            } else if let Err(_error) = res {
-                running_ref.store(2, Ordering::SeqCst);
-                ERROR!(format!("{}", _error.0), ErrorLevel::PartialBreakage);
-            }
-        });
-        while running.load(Ordering::SeqCst) == 0 {
-            hint::spin_loop();
-        }
-        match running.load(Ordering::SeqCst) {
-            1 => (),
-            2 => {
-                ERROR!(
-                    "Could not create audio sender, aborting",
-                    ErrorLevel::PartialBreakage
-                );
-            }
-            // impossible condition
-            _ => (),
-        }
-
+        let current_n_device_path = current_n_device.read().unwrap().dbus_path.clone();
         Ok(DaemonData {
             n_devices,
             current_n_device,
+            current_n_device_path,
             b_interface,
             bluetooth_agent: BluetoothAgent::new(),
             audio_sender: Arc::new(dbus_pulse_sender),
@@ -189,15 +334,61 @@ impl DaemonData {
             audio_listener_active,
             bluetooth_listener_active: Arc::new(AtomicBool::new(false)),
             bluetooth_stop_requested: Arc::new(AtomicBool::new(false)),
-            bluetooth_scan_request: Arc::new(AtomicI8::new(0)),
-            bluetooth_scan_active: Arc::new(AtomicBool::new(false)),
+            audio_routing_stop_requested: Arc::new(AtomicBool::new(false)),
+            bluetooth_scan: Arc::new(Mutex::new(ScanState::Idle)),
             connection: conn,
             handle,
-            clients: HashMap::new(),
+            clients: Arc::new(ClientRegistry::default()),
         })
     }
 }
 
+/// Opens a channel to the session bus, which is the private bus while a testing::TestBus is
+/// running.
+pub fn session_channel() -> Result<Channel, dbus::Error> {
+    if let Some(address) = test_bus_address() {
+        let mut channel = Channel::open_private(&address)?;
+        channel.register()?;
+        return Ok(channel);
+    }
+    Channel::get_private(BusType::Session)
+}
+
+pub fn session_connection() -> Result<Connection, dbus::Error> {
+    session_channel().map(Connection::from)
+}
+
+pub fn session_connection_sync(
+) -> Result<(IOResource<SyncConnection>, Arc<SyncConnection>), dbus::Error> {
+    connection::from_channel(session_channel()?)
+}
+
+/// Returns true if the message was sent by the current owner of the well-known name, agents use
+/// this to only answer the service they registered with.
+pub fn is_sent_by_owner(conn: &dbus::blocking::SyncConnection, msg: &Message, name: &str) -> bool {
+    let Some(sender) = msg.sender() else {
+        return false;
+    };
+    let res: Result<(String,), dbus::Error> = conn
+        .with_proxy(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            Duration::from_millis(1000),
+        )
+        .method_call("org.freedesktop.DBus", "GetNameOwner", (name,));
+    res.is_ok_and(|(owner,)| owner == *sender)
+}
+
+#[cfg(any(test, feature = "testing"))]
+fn test_bus_address() -> Option<String> {
+    crate::testing::test_bus_address()
+}
+
+#[cfg(not(any(test, feature = "testing")))]
+fn test_bus_address() -> Option<String> {
+    None
+}
+
 pub fn get_wifi_status() -> bool {
     let res = get_system_dbus_property::<(), bool>(
         "org.freedesktop.NetworkManager",
@@ -217,6 +408,78 @@ pub fn get_wifi_status() -> bool {
     }
 }
 
+/// Returns an entry of the daemon section within the ReSet config file.
+/// ```toml
+/// [Daemon]
+/// bluetooth_auto_route = true
+/// ```
+pub fn get_daemon_config_value(entry: &str) -> Option<&'static toml::Value> {
+    CONFIG.get(DAEMON_CONFIG)?.get(entry)
+}
+
+/// Sets an entry of the daemon section within the ReSet config file.\
+/// The loaded config is not changed, the value applies from the next start of the daemon on.
+pub fn set_daemon_config_value(entry: &str, value: toml::Value) -> bool {
+    let Ok(value) = value.to_string().parse::<toml_edit::Value>() else {
+        return false;
+    };
+    edit_config_file(|document| {
+        let Some(section) = document
+            .entry(DAEMON_CONFIG)
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+        else {
+            return Err(format!(
+                "{} within the config file is not a table",
+                DAEMON_CONFIG
+            ));
+        };
+        section.insert(entry, toml_edit::value(value));
+        Ok(())
+    })
+}
+
+/// Applies the edit to the ReSet config file, the rest of the file including comments is left
+/// untouched.
+pub(crate) fn edit_config_file(
+    edit: impl FnOnce(&mut toml_edit::DocumentMut) -> Result<(), String>,
+) -> bool {
+    let path = unsafe { (*addr_of!(CONFIG_STRING)).to_string() };
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    let Ok(mut document) = contents.parse::<toml_edit::DocumentMut>() else {
+        ERROR!(
+            format!("Could not parse config file {}", path),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    };
+    if let Err(_error) = edit(&mut document) {
+        ERROR!(_error, ErrorLevel::Recoverable);
+        return false;
+    }
+    let res = fs::write(&path, document.to_string());
+    if let Err(_error) = res {
+        ERROR!(
+            format!("Could not write config file {}: {}", path, _error),
+            ErrorLevel::Recoverable
+        );
+        return false;
+    }
+    true
+}
+
+/// Loads a state file of the daemon with the configured storage, see storage.rs.\
+/// State files contain data set at runtime, like device aliases, and are not meant to be edited by
+/// hand. Returns an empty table if the file does not exist yet.
+pub fn load_state_file(name: &str) -> toml::Table {
+    load_state(name)
+}
+
+/// Writes a state file of the daemon with the configured storage.
+pub fn save_state_file(name: &str, table: &toml::Table) -> bool {
+    save_state(name, table)
+}
+
 pub fn convert_bluetooth_map_bool(map_key: Option<&Variant<Box<dyn RefArg>>>) -> bool {
     if let Some(bonded_opt) = map_key {
         if let Some(bonded) = arg::cast::<bool>(&bonded_opt.0) {
@@ -0,0 +1,32 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set with `--virtual-devices`, for building frontends without audio hardware.\
+/// Only a null sink and a null source are created, WiFi and bluetooth stay the real devices.
+static VIRTUAL_DEVICES: AtomicBool = AtomicBool::new(false);
+
+pub const NULL_SINK_MODULE: &str = "module-null-sink";
+pub const NULL_SOURCE_MODULE: &str = "module-null-source";
+pub const VIRTUAL_SINK_NAME: &str = "reset_virtual_sink";
+pub const VIRTUAL_SOURCE_NAME: &str = "reset_virtual_source";
+
+pub fn enable_virtual_devices() {
+    VIRTUAL_DEVICES.store(true, Ordering::SeqCst);
+}
+
+pub fn virtual_devices_enabled() -> bool {
+    VIRTUAL_DEVICES.load(Ordering::SeqCst)
+}
+
+pub fn virtual_sink_arguments() -> String {
+    format!(
+        "sink_name={} sink_properties='device.description=\"ReSet Virtual Sink\"'",
+        VIRTUAL_SINK_NAME
+    )
+}
+
+pub fn virtual_source_arguments() -> String {
+    format!(
+        "source_name={} source_properties='device.description=\"ReSet Virtual Source\"'",
+        VIRTUAL_SOURCE_NAME
+    )
+}
@@ -1,9 +1,12 @@
 use std::{
-    sync::{Arc, RwLock, RwLockWriteGuard},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, RwLock, RwLockWriteGuard,
+    },
     time::Duration,
 };
 
-use dbus::blocking::Connection;
+use dbus::{arg::RefArg, blocking::Connection};
 use dbus_crossroads::IfaceBuilder;
 use re_set_lib::{
     plug_assert, plug_assert_eq,
@@ -14,6 +17,31 @@ use re_set_lib::{
     },
 };
 
+/// Same layout as PluginSignal of the daemon.
+#[repr(C)]
+pub struct PluginSignal {
+    pub path: String,
+    pub interface: String,
+    pub name: String,
+    pub signature: String,
+}
+
+// host API, resolved from the daemon executable once the plugin is loaded
+#[allow(improper_ctypes)]
+extern "C" {
+    fn register_plugin_object(object_name: String) -> bool;
+    fn register_plugin_signal(signal: PluginSignal) -> u32;
+    fn emit_plugin_signal(signal: u32, payload: Vec<Box<dyn RefArg>>) -> bool;
+    fn get_system_context() -> (bool, bool, bool);
+    fn register_system_context_callback(callback: extern "C" fn((bool, bool, bool))) -> bool;
+}
+
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+static TEST_SIGNAL: AtomicU32 = AtomicU32::new(0);
+static CONTEXT_RECEIVED: AtomicBool = AtomicBool::new(false);
+static EMITTED: AtomicBool = AtomicBool::new(false);
+static MISMATCH_REJECTED: AtomicBool = AtomicBool::new(false);
+
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub extern "C" fn capabilities() -> PluginCapabilities {
@@ -42,6 +70,34 @@ pub extern "C" fn dbus_interface(cross: Arc<RwLock<CrossWrapper>>) {
             age: 10,
         },
     );
+    let signal = unsafe {
+        let object = register_plugin_object(String::from("test"));
+        let context = register_system_context_callback(system_context);
+        REGISTERED.store(object && context, Ordering::SeqCst);
+        register_plugin_signal(PluginSignal {
+            path: String::from("/org/Xetibo/ReSet/Plugins/test"),
+            interface: String::from("org.Xetibo.ReSet.TestPlugin"),
+            name: String::from("TestSignal"),
+            signature: String::from("s"),
+        })
+    };
+    TEST_SIGNAL.store(signal, Ordering::SeqCst);
+    // signals are emitted within calls of the daemon, which attributes them to this plugin
+    let emitted = unsafe {
+        emit_plugin_signal(
+            signal,
+            vec![Box::new(String::from("pingpang")) as Box<dyn RefArg>],
+        )
+    };
+    EMITTED.store(emitted, Ordering::SeqCst);
+    // the payload has to match the registered signature
+    let mismatched =
+        unsafe { emit_plugin_signal(signal, vec![Box::new(10_u32) as Box<dyn RefArg>]) };
+    MISMATCH_REJECTED.store(!mismatched, Ordering::SeqCst);
+}
+
+extern "C" fn system_context(_context: (bool, bool, bool)) {
+    CONTEXT_RECEIVED.store(true, Ordering::SeqCst);
 }
 
 #[no_mangle]
@@ -58,7 +114,29 @@ pub extern "C" fn backend_shutdown() {
 #[allow(improper_ctypes_definitions)]
 pub extern "C" fn backend_tests() -> Vec<PluginTestFunc> {
     println!("tests called");
-    vec![PluginTestFunc::new(test1, "testconnection")]
+    vec![
+        PluginTestFunc::new(test1, "testconnection"),
+        PluginTestFunc::new(test_host_api, "testhostapi"),
+    ]
+}
+
+fn test_host_api() -> Result<(), PluginTestError> {
+    plug_assert!(REGISTERED.load(Ordering::SeqCst))?;
+    plug_assert!(CONTEXT_RECEIVED.load(Ordering::SeqCst))?;
+    let signal = TEST_SIGNAL.load(Ordering::SeqCst);
+    plug_assert!(signal != 0)?;
+    let _context = unsafe { get_system_context() };
+    plug_assert!(EMITTED.load(Ordering::SeqCst))?;
+    plug_assert!(MISMATCH_REJECTED.load(Ordering::SeqCst))?;
+    // tests run on their own thread, the daemon can't tell which plugin emits here
+    let unattributed = unsafe {
+        emit_plugin_signal(
+            signal,
+            vec![Box::new(String::from("pingpang")) as Box<dyn RefArg>],
+        )
+    };
+    plug_assert!(!unattributed)?;
+    Ok(())
 }
 
 fn test1() -> Result<(), PluginTestError> {
@@ -0,0 +1,11 @@
+use reset_daemon::testing::{run_conformance, TestBus};
+
+#[test]
+// starts the daemon against the mock implementations twice, the test bus has to be restartable
+fn test_run_conformance() {
+    let test_bus = TestBus::start().expect("Could not start the test bus");
+    assert!(TestBus::start().is_err());
+    drop(test_bus);
+    let report = run_conformance().expect("Could not start the test bus");
+    assert!(report.passed(), "{:?}", report.failures());
+}